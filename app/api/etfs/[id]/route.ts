@@ -85,7 +85,7 @@ export async function DELETE(
 
     // Check if ETF exists and user is the creator
     const etfResult = await pool.query(
-      'SELECT creator FROM etf_listings WHERE id = $1',
+      'SELECT creator, etf_index FROM etf_listings WHERE id = $1',
       [id]
     );
 
@@ -162,7 +162,7 @@ export async function DELETE(
         const connection = getConnection(network);
 
         console.log(`[Delete ETF] Closing ETF PDA on-chain (${network})...`);
-        const closeTxSignature = await closeEtf(connection, listerKeypair);
+        const closeTxSignature = await closeEtf(connection, listerKeypair, etfResult.rows[0].etf_index ?? 0);
         console.log('[Delete ETF] ✅ ETF PDA closed successfully:', closeTxSignature);
       } else {
         console.warn('[Delete ETF] Could not find lister wallet to close PDA');
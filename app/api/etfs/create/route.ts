@@ -4,7 +4,7 @@ import { generateTokenHash } from '@/lib/utils/tokenHash';
 import { TokenInfo } from '@/types';
 import { decryptPrivateKey, getKeypairFromPrivateKey } from '@/lib/solana/wallet';
 import { PublicKey, LAMPORTS_PER_SOL } from '@solana/web3.js';
-import { initializeEtf, getEtfPda, getAllEtfPdas, getConnection, PROGRAM_ID } from '@/lib/solana/program';
+import { initializeEtf, getEtfPda, getAllEtfPdas, getConnection, metadataFromName, PROGRAM_ID } from '@/lib/solana/program';
 
 // Maximum tokens per ETF (smart contract space limitation)
 const MAX_TOKENS_PER_ETF = 10;
@@ -174,7 +174,7 @@ export async function POST(request: NextRequest) {
 
     let signature: string;
     try {
-      signature = await initializeEtf(connection, keypair, tokenPubkeys, nextAvailableIndex);
+      signature = await initializeEtf(connection, keypair, tokenPubkeys, nextAvailableIndex, metadataFromName(name));
       console.log('[ETF Create] ✅ Success! TX:', signature);
       console.log(`[ETF Create] ETF index: ${nextAvailableIndex}, PDA: ${etfPda.toBase58()}`);
     } catch (err: any) {
@@ -1,6 +1,6 @@
 import { NextRequest, NextResponse } from 'next/server';
 import { PublicKey, Connection, clusterApiUrl } from '@solana/web3.js';
-import { buildUnsignedInitializeEtf, getEtfPda, getAllEtfPdas } from '@/lib/solana/program';
+import { buildUnsignedInitializeEtf, getEtfPda, getAllEtfPdas, metadataFromName } from '@/lib/solana/program';
 import { generateTokenHash } from '@/lib/utils/tokenHash';
 import { getDatabasePool } from '@/lib/database/connection';
 
@@ -118,7 +118,8 @@ export async function POST(request: NextRequest) {
       connection,
       userPubkey,
       tokenPubkeys,
-      nextAvailableIndex
+      nextAvailableIndex,
+      metadataFromName(name)
     );
 
     console.log('[ETF Prepare] Transaction built successfully');
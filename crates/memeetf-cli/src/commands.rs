@@ -0,0 +1,1539 @@
+// Subcommand implementations
+use std::thread::sleep;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anchor_lang::prelude::{Pubkey, Rent};
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_spl::associated_token::{
+    get_associated_token_address, get_associated_token_address_with_program_id, spl_associated_token_account,
+};
+use anchor_spl::token_2022::spl_token_2022::extension::StateWithExtensions;
+use anchor_spl::token_2022::{self, spl_token_2022};
+use anyhow::{anyhow, bail, Result};
+use mtf_etf::{
+    AllowlistPass, BuyLimits, DcaPlan, EtfCategory, EtfGate, ForkChanges, EtfStatus, ExitTrigger, LimitOrder, OrderSide, PendingPurchase, ETF, MAX_EARLY_EXIT_FEE_BPS, MAX_HOLD_SECONDS,
+    MAX_REFUND_TIMEOUT_SECONDS, DCA_CRANK_TIP_LAMPORTS,
+    MIN_DCA_INTERVAL_SECONDS, MIN_REFUND_TIMEOUT_SECONDS, NAV_SNAPSHOT_INTERVAL_SECONDS, LEADERBOARD_EPOCH_SECONDS,
+    LEADERBOARD_MIN_NET_ASSETS, LEADERBOARD_SIZE, LEADERBOARD_SUBMISSION_SECONDS, MAX_REWARDS_SHARE_BPS,
+    MAX_PARENT_FEE_BPS, LEGACY_ETF_SPACE, PENDING_PURCHASE_SPACE,
+};
+use mtf_etf_client::instructions::{self, EtfMetadata};
+use mtf_etf_client::allowlist::Allowlist;
+use mtf_etf_client::{accounts, math, pda, views, RpcClient};
+use solana_keypair::Keypair;
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+
+use crate::config::{format_sol, load_allowlist, load_keypair, parse_sol, resolve_url};
+use crate::{Cli, Command, DcaAction, ExitAction, LeaderboardAction, OrderAction, QuoteSide, RewardsAction};
+
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(60);
+
+struct Context {
+    rpc: RpcClient,
+    keypair_path: String,
+}
+
+impl Context {
+    fn payer(&self) -> Result<Keypair> {
+        load_keypair(&self.keypair_path)
+    }
+}
+
+pub fn run(cli: Cli) -> Result<()> {
+    let ctx = Context {
+        rpc: RpcClient::new(resolve_url(&cli.url)),
+        keypair_path: cli.keypair,
+    };
+
+    match cli.command {
+        Command::Create {
+            index,
+            tokens,
+            name,
+            symbol,
+            uri,
+            category,
+        } => {
+            let metadata = EtfMetadata {
+                name,
+                symbol,
+                uri,
+                category: category.into(),
+            };
+            create(&ctx, index, tokens, metadata)
+        }
+        Command::Fork {
+            parent,
+            index,
+            remove,
+            add,
+            parent_fee_bps,
+            name,
+            symbol,
+            uri,
+            category,
+        } => {
+            let changes = ForkChanges {
+                remove_tokens: remove,
+                add_tokens: add,
+                parent_fee_bps,
+            };
+            // Unless given, the category is the parent's
+            let metadata = move |parent_category| EtfMetadata {
+                name,
+                symbol,
+                uri,
+                category: category.map_or(parent_category, Into::into),
+            };
+            fork(&ctx, parent, index, changes, metadata)
+        }
+        Command::ClaimParentFees { etf } => claim_parent_fees(&ctx, etf),
+        Command::Migrate { etf } => migrate(&ctx, etf),
+        Command::Buy {
+            etf,
+            sol,
+            percentages,
+            allowlist,
+        } => buy(&ctx, etf, parse_sol(&sol)?, percentages, allowlist),
+        Command::Sell { etf, shares, all } => sell(&ctx, etf, shares, all),
+        Command::Redeem { etf, shares, all } => redeem(&ctx, etf, shares, all),
+        Command::Wrap { etf, shares } => wrap(&ctx, etf, shares),
+        Command::Unwrap { etf, shares } => unwrap(&ctx, etf, shares),
+        Command::SetKeeper {
+            etf,
+            keeper,
+            refund_timeout,
+            ..
+        } => set_keeper(&ctx, etf, keeper, refund_timeout),
+        Command::SetGate {
+            etf,
+            allowlist,
+            mint,
+            min_amount,
+            ..
+        } => {
+            let gate = match (allowlist, mint.zip(min_amount)) {
+                (Some(path), _) => EtfGate::MerkleAllowlist {
+                    root: Allowlist::new(&load_allowlist(&path)?).root(),
+                },
+                (None, Some((mint, amount))) => EtfGate::MinimumBalance { mint, amount },
+                (None, None) => EtfGate::Open,
+            };
+            set_gate(&ctx, etf, gate)
+        }
+        Command::SetLimits {
+            etf,
+            min_buy,
+            max_buy,
+            max_supply,
+        } => {
+            let sol_or_unlimited = |amount: Option<String>| amount.map_or(Ok(0), |amount| parse_sol(&amount));
+            let limits = BuyLimits {
+                min_buy_lamports: sol_or_unlimited(min_buy)?,
+                max_buy_lamports: sol_or_unlimited(max_buy)?,
+                max_total_supply: sol_or_unlimited(max_supply)?,
+            };
+            set_limits(&ctx, etf, limits)
+        }
+        Command::SetHold {
+            etf,
+            seconds,
+            early_exit_fee_bps,
+        } => set_hold(&ctx, etf, seconds, early_exit_fee_bps),
+        Command::Dca { action } => dca(&ctx, action),
+        Command::Order { action } => order(&ctx, action),
+        Command::Exit { action } => exit(&ctx, action),
+        Command::Refund { etf } => refund(&ctx, etf),
+        Command::Close { etf } => close(&ctx, etf),
+        Command::Show { etf } => show(&ctx, etf),
+        Command::Nav { etf, twap } => nav(&ctx, etf, twap),
+        Command::Snapshot { etf } => snapshot(&ctx, etf),
+        Command::Leaderboard { action } => leaderboard(&ctx, action),
+        Command::Rewards { action } => rewards(&ctx, action),
+        Command::List { lister } => list(&ctx, lister),
+        Command::Quote { side } => quote(&ctx, side),
+    }
+}
+
+fn create(ctx: &Context, etf_index: u8, tokens: Vec<Pubkey>, metadata: EtfMetadata) -> Result<()> {
+    let payer = ctx.payer()?;
+    let registry_index = accounts::fetch_registry(&ctx.rpc)?
+        .map(|registry| registry.etf_count)
+        .unwrap_or_default();
+    let (etf, _) = pda::etf_address(&payer.pubkey(), etf_index);
+
+    let ix =
+        instructions::initialize_etf(payer.pubkey(), etf_index, registry_index, tokens, metadata);
+    let signature = send_and_confirm(ctx, &payer, &[ix])?;
+
+    println!("Created ETF {etf}");
+    println!("Signature: {signature}");
+    Ok(())
+}
+
+// `metadata` is built from the parent's category
+fn fork(
+    ctx: &Context,
+    parent_address: Pubkey,
+    etf_index: u8,
+    changes: ForkChanges,
+    metadata: impl FnOnce(EtfCategory) -> EtfMetadata,
+) -> Result<()> {
+    if changes.parent_fee_bps > MAX_PARENT_FEE_BPS {
+        bail!("parent fee share is at most {MAX_PARENT_FEE_BPS} bps");
+    }
+    let payer = ctx.payer()?;
+    let parent = accounts::fetch_etf(&ctx.rpc, &parent_address)?;
+    let registry_index = accounts::fetch_registry(&ctx.rpc)?
+        .map(|registry| registry.etf_count)
+        .unwrap_or_default();
+    let (etf, _) = pda::etf_address(&payer.pubkey(), etf_index);
+    let metadata = metadata(parent.category);
+
+    let ix = instructions::fork_etf(payer.pubkey(), etf_index, parent_address, registry_index, changes, metadata);
+    let signature = send_and_confirm(ctx, &payer, &[ix])?;
+
+    println!("Forked {parent_address} into {etf}");
+    println!("Signature: {signature}");
+    Ok(())
+}
+
+fn claim_parent_fees(ctx: &Context, etf_address: Pubkey) -> Result<()> {
+    let payer = ctx.payer()?;
+    let etf = accounts::fetch_etf(&ctx.rpc, &etf_address)?;
+    if etf.parent_etf.is_none() {
+        bail!("{etf_address} is not a fork");
+    }
+    if etf.parent_fees_owed == 0 {
+        bail!("no parent fees owed by {etf_address}");
+    }
+
+    let ix = instructions::claim_parent_fees(etf_address, etf.parent_lister);
+    let signature = send_and_confirm(ctx, &payer, &[ix])?;
+
+    println!(
+        "Paid {} SOL to {} ({signature})",
+        format_sol(etf.parent_fees_owed),
+        etf.parent_lister
+    );
+    Ok(())
+}
+
+fn migrate(ctx: &Context, etf_address: Pubkey) -> Result<()> {
+    let payer = ctx.payer()?;
+    let data = ctx
+        .rpc
+        .get_account_data(&etf_address)?
+        .ok_or_else(|| anyhow!("ETF {etf_address} not found"))?;
+    if data.len() != LEGACY_ETF_SPACE {
+        bail!("{etf_address} already has the current layout");
+    }
+    let registry_index = accounts::fetch_registry(&ctx.rpc)?
+        .map(|registry| registry.etf_count)
+        .unwrap_or_default();
+
+    let ix = instructions::migrate_etf(etf_address, payer.pubkey(), registry_index);
+    let signature = send_and_confirm(ctx, &payer, &[ix])?;
+
+    println!("Migrated {etf_address}");
+    println!("Signature: {signature}");
+    Ok(())
+}
+
+fn buy(
+    ctx: &Context,
+    etf_address: Pubkey,
+    sol_amount: u64,
+    percentages: Option<Vec<u8>>,
+    allowlist: Option<String>,
+) -> Result<()> {
+    let payer = ctx.payer()?;
+    let etf = accounts::fetch_etf(&ctx.rpc, &etf_address)?;
+    let percentages =
+        percentages.unwrap_or_else(|| math::even_percentages(etf.token_addresses.len()));
+    if percentages.len() != etf.token_addresses.len() {
+        bail!(
+            "expected {} percentages, got {}",
+            etf.token_addresses.len(),
+            percentages.len()
+        );
+    }
+
+    let quote = math::quote_buy(sol_amount, &percentages, etf.total_supply, etf.sol_assets);
+    let mut ixs = Vec::new();
+    let gate_account = match etf.gate {
+        EtfGate::Open => None,
+        EtfGate::MerkleAllowlist { root } => {
+            let path = allowlist
+                .ok_or_else(|| anyhow!("{etf_address} is allowlisted - pass --allowlist <FILE>"))?;
+            let allowlist = Allowlist::new(&load_allowlist(&path)?);
+            if allowlist.root() != root {
+                bail!("{path} is not the allowlist of {etf_address}");
+            }
+            // Prove membership unless a pass for the current root exists
+            let (pass, _) = pda::allowlist_pass_address(&etf_address, &payer.pubkey());
+            let current: Option<AllowlistPass> = accounts::fetch_optional(&ctx.rpc, &pass)?;
+            if current.map(|pass| pass.root) != Some(root) {
+                let proof = allowlist
+                    .proof(&payer.pubkey())
+                    .ok_or_else(|| anyhow!("{} is not on the allowlist", payer.pubkey()))?;
+                ixs.push(instructions::verify_allowlist(etf_address, payer.pubkey(), proof));
+            }
+            Some(pass)
+        }
+        EtfGate::MinimumBalance { mint, .. } => Some(get_associated_token_address(&payer.pubkey(), &mint)),
+    };
+
+    let ix = match (gate_account, etf.keeper) {
+        (Some(gate_account), keeper) => instructions::buy_etf_gated(
+            etf_address,
+            etf.lister,
+            payer.pubkey(),
+            keeper.map(|_| etf.purchase_count),
+            gate_account,
+            sol_amount,
+            percentages,
+        ),
+        (None, Some(_)) => instructions::buy_etf_pending(
+            etf_address,
+            etf.lister,
+            payer.pubkey(),
+            etf.purchase_count,
+            sol_amount,
+            percentages,
+        ),
+        (None, None) => instructions::buy_etf(
+            etf_address,
+            etf.lister,
+            payer.pubkey(),
+            sol_amount,
+            percentages,
+        ),
+    };
+    ixs.push(ix);
+    let signature = send_and_confirm(ctx, &payer, &ixs)?;
+
+    // Keeper purchases are minted at settlement, on the value delivered
+    match etf.keeper {
+        Some(keeper) => println!(
+            "Purchase #{} of {} SOL of {etf_address} pending settlement by keeper {keeper}",
+            etf.purchase_count,
+            format_sol(quote.sol_after_fees)
+        ),
+        None => println!("Bought {} shares of {etf_address}", quote.shares_out),
+    }
+    println!(
+        "Fees: creator {} SOL, dev {} SOL",
+        format_sol(quote.creator_fee),
+        format_sol(quote.dev_fee)
+    );
+    println!("Signature: {signature}");
+    Ok(())
+}
+
+fn sell(ctx: &Context, etf_address: Pubkey, shares: Option<u64>, all: bool) -> Result<()> {
+    let payer = ctx.payer()?;
+    let etf = accounts::fetch_etf(&ctx.rpc, &etf_address)?;
+    let position = accounts::fetch_position(&ctx.rpc, &etf_address, &payer.pubkey())?
+        .ok_or_else(|| anyhow!("{} has no position in {etf_address}", payer.pubkey()))?;
+
+    let shares = if all {
+        position.shares
+    } else {
+        shares.unwrap_or_default()
+    };
+    if shares == 0 || shares > position.shares {
+        bail!(
+            "cannot sell {shares} shares - position holds {}",
+            position.shares
+        );
+    }
+
+    if etf.holdings.iter().any(|held| *held > 0) {
+        bail!("{etf_address} holds basket tokens - use `redeem` to exit in kind");
+    }
+    let quote = math::quote_sell(shares, etf.total_supply, etf.sol_assets);
+    let mut sol_out = quote.sol_out;
+    let hold_ends = position.last_buy_ts.saturating_add(etf.min_hold_seconds);
+    if unix_now()? < hold_ends {
+        if etf.early_exit_fee_bps == 0 {
+            bail!("shares of {etf_address} are locked until {hold_ends} (unix time)");
+        }
+        if shares < etf.total_supply {
+            let fee = mtf_etf::liquidation_payout(
+                mtf_etf::early_exit_fee(shares, etf.early_exit_fee_bps),
+                etf.total_supply,
+                etf.sol_assets,
+            );
+            println!("Selling before {hold_ends} costs an early-exit fee of {} SOL", format_sol(fee));
+            sol_out -= fee;
+        }
+    }
+    let ix = instructions::sell_etf(etf_address, etf.lister, payer.pubkey(), shares);
+    let signature = send_and_confirm(ctx, &payer, &[ix])?;
+
+    println!(
+        "Sold {shares} shares of {etf_address} for {} SOL",
+        format_sol(sol_out)
+    );
+    println!("Signature: {signature}");
+    Ok(())
+}
+
+// Basket tokens land in the wallet's associated accounts; vaults that do not
+// exist hold nothing and are left out
+fn redeem(ctx: &Context, etf_address: Pubkey, shares: Option<u64>, all: bool) -> Result<()> {
+    let payer = ctx.payer()?;
+    let etf = accounts::fetch_etf(&ctx.rpc, &etf_address)?;
+    let position = accounts::fetch_position(&ctx.rpc, &etf_address, &payer.pubkey())?
+        .ok_or_else(|| anyhow!("{} has no position in {etf_address}", payer.pubkey()))?;
+
+    let shares = if all {
+        position.shares
+    } else {
+        shares.unwrap_or_default()
+    };
+    if shares == 0 || shares > position.shares {
+        bail!(
+            "cannot redeem {shares} shares - position holds {}",
+            position.shares
+        );
+    }
+    let hold_ends = position.last_buy_ts.saturating_add(etf.min_hold_seconds);
+    if unix_now()? < hold_ends {
+        if etf.early_exit_fee_bps == 0 {
+            bail!("shares of {etf_address} are locked until {hold_ends} (unix time)");
+        }
+        println!(
+            "Redeeming before {hold_ends} leaves {} bps of your share in the ETF",
+            etf.early_exit_fee_bps
+        );
+    }
+
+    // Every basket vault is passed; token accounts are only needed for the
+    // vaults that exist
+    let mut ixs = Vec::new();
+    let mut vaults = Vec::new();
+    let mut paying = 0;
+    for mint in &etf.token_addresses {
+        let vault = pda::vault_address(&etf_address, mint);
+        if ctx.rpc.get_account_data(&vault)?.is_some() {
+            ixs.push(
+                spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+                    &payer.pubkey(),
+                    &payer.pubkey(),
+                    mint,
+                    &anchor_spl::token::ID,
+                ),
+            );
+            paying += 1;
+        }
+        vaults.push((vault, get_associated_token_address(&payer.pubkey(), mint)));
+    }
+    ixs.push(instructions::redeem_to_tokens(
+        etf_address,
+        etf.lister,
+        payer.pubkey(),
+        shares,
+        &vaults,
+        &etf.token_addresses,
+    ));
+
+    let signature = send_and_confirm(ctx, &payer, &ixs)?;
+    println!("Redeemed {shares} shares of {etf_address} into {paying} basket token account(s)");
+    println!("Signature: {signature}");
+    Ok(())
+}
+
+// Wrapped shares land in the wallet's associated account for the share mint
+fn wrap(ctx: &Context, etf_address: Pubkey, shares: u64) -> Result<()> {
+    let payer = ctx.payer()?;
+    let position = accounts::fetch_position(&ctx.rpc, &etf_address, &payer.pubkey())?
+        .ok_or_else(|| anyhow!("{} has no position in {etf_address}", payer.pubkey()))?;
+    if shares == 0 || shares > position.shares {
+        bail!("cannot wrap {shares} shares - position holds {}", position.shares);
+    }
+
+    let (share_mint, _) = pda::share_mint_address(&etf_address);
+    let mut ixs = Vec::new();
+    if ctx.rpc.get_account_data(&share_mint)?.is_none() {
+        ixs.push(instructions::create_share_mint(etf_address, payer.pubkey()));
+    }
+    ixs.push(
+        spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+            &payer.pubkey(),
+            &payer.pubkey(),
+            &share_mint,
+            &token_2022::ID,
+        ),
+    );
+    let destination = get_associated_token_address_with_program_id(&payer.pubkey(), &share_mint, &token_2022::ID);
+    ixs.push(instructions::wrap_shares(etf_address, payer.pubkey(), destination, shares));
+
+    let signature = send_and_confirm(ctx, &payer, &ixs)?;
+    println!("Wrapped {shares} shares of {etf_address} into {destination}");
+    println!("Share mint: {share_mint}");
+    println!("Signature: {signature}");
+    Ok(())
+}
+
+fn unwrap(ctx: &Context, etf_address: Pubkey, shares: u64) -> Result<()> {
+    let payer = ctx.payer()?;
+    let (share_mint, _) = pda::share_mint_address(&etf_address);
+    let source = get_associated_token_address_with_program_id(&payer.pubkey(), &share_mint, &token_2022::ID);
+    let balance = match ctx.rpc.get_account_data(&source)? {
+        Some(data) => StateWithExtensions::<spl_token_2022::state::Account>::unpack(&data)
+            .map_err(|error| anyhow!("failed to decode share account {source}: {error}"))?
+            .base
+            .amount,
+        None => 0,
+    };
+    if shares == 0 || shares > balance {
+        bail!("cannot unwrap {shares} shares - {source} holds {balance}");
+    }
+
+    let ix = instructions::unwrap_shares(etf_address, payer.pubkey(), source, payer.pubkey(), shares);
+    let signature = send_and_confirm(ctx, &payer, &[ix])?;
+    println!("Unwrapped {shares} shares of {etf_address} into your position");
+    println!("Signature: {signature}");
+    Ok(())
+}
+
+fn close(ctx: &Context, etf_address: Pubkey) -> Result<()> {
+    let payer = ctx.payer()?;
+    let etf = accounts::fetch_etf(&ctx.rpc, &etf_address)?;
+    if etf.lister != payer.pubkey() {
+        bail!("only the lister {} can close this ETF", etf.lister);
+    }
+    if etf.total_supply > 0 {
+        bail!("ETF still has {} shares outstanding", etf.total_supply);
+    }
+
+    // Every basket vault is passed; existing ones are drained into the
+    // lister's token accounts
+    let mut ixs = Vec::new();
+    let mut vaults = Vec::new();
+    let mut swept = 0;
+    for mint in &etf.token_addresses {
+        let vault = pda::vault_address(&etf_address, mint);
+        if ctx.rpc.get_account_data(&vault)?.is_some() {
+            ixs.push(
+                spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+                    &payer.pubkey(),
+                    &payer.pubkey(),
+                    mint,
+                    &anchor_spl::token::ID,
+                ),
+            );
+            swept += 1;
+        }
+        vaults.push((vault, get_associated_token_address(&payer.pubkey(), mint)));
+    }
+    ixs.push(instructions::close_etf(
+        etf_address,
+        payer.pubkey(),
+        etf.registry_index,
+        &vaults,
+    ));
+
+    let signature = send_and_confirm(ctx, &payer, &ixs)?;
+    println!("Closed ETF {etf_address} ({swept} vaults swept)");
+    println!("Signature: {signature}");
+    Ok(())
+}
+
+fn set_keeper(
+    ctx: &Context,
+    etf_address: Pubkey,
+    keeper: Option<Pubkey>,
+    refund_timeout: i64,
+) -> Result<()> {
+    let payer = ctx.payer()?;
+    let etf = accounts::fetch_etf(&ctx.rpc, &etf_address)?;
+    if etf.lister != payer.pubkey() {
+        bail!("only the lister {} can set the keeper", etf.lister);
+    }
+    if etf.keeper.is_some() != keeper.is_some() && (etf.total_supply > 0 || etf.pending_sol > 0) {
+        bail!("settlement mode can only change while the ETF has no shares or pending purchases");
+    }
+    if !(MIN_REFUND_TIMEOUT_SECONDS..=MAX_REFUND_TIMEOUT_SECONDS).contains(&refund_timeout) {
+        bail!(
+            "refund timeout must be between {MIN_REFUND_TIMEOUT_SECONDS} and {MAX_REFUND_TIMEOUT_SECONDS} seconds"
+        );
+    }
+
+    let ix = instructions::set_keeper(etf_address, payer.pubkey(), keeper, refund_timeout);
+    let signature = send_and_confirm(ctx, &payer, &[ix])?;
+    match keeper {
+        Some(keeper) => println!("Buys of {etf_address} are now settled by {keeper}"),
+        None => println!("Buys of {etf_address} now settle instantly"),
+    }
+    println!("Signature: {signature}");
+    Ok(())
+}
+
+fn set_gate(ctx: &Context, etf_address: Pubkey, gate: EtfGate) -> Result<()> {
+    let payer = ctx.payer()?;
+    let etf = accounts::fetch_etf(&ctx.rpc, &etf_address)?;
+    if etf.lister != payer.pubkey() {
+        bail!("only the lister {} can gate this ETF", etf.lister);
+    }
+
+    let ix = instructions::set_gate(etf_address, payer.pubkey(), gate);
+    let signature = send_and_confirm(ctx, &payer, &[ix])?;
+    println!("Buys of {etf_address}: {}", describe_gate(&gate));
+    println!("Signature: {signature}");
+    Ok(())
+}
+
+fn set_limits(ctx: &Context, etf_address: Pubkey, limits: BuyLimits) -> Result<()> {
+    let payer = ctx.payer()?;
+    let etf = accounts::fetch_etf(&ctx.rpc, &etf_address)?;
+    if etf.lister != payer.pubkey() {
+        bail!("only the lister {} can set buy limits", etf.lister);
+    }
+    mtf_etf::validate_buy_limits(&limits)
+        .map_err(|_| anyhow!("minimum buy is above the maximum, or a limit exceeds the protocol's"))?;
+
+    let ix = instructions::set_buy_limits(etf_address, payer.pubkey(), limits);
+    let signature = send_and_confirm(ctx, &payer, &[ix])?;
+    println!("Buy limits of {etf_address}: {}", describe_limits(&limits));
+    println!("Signature: {signature}");
+    Ok(())
+}
+
+fn set_hold(ctx: &Context, etf_address: Pubkey, seconds: i64, early_exit_fee_bps: u16) -> Result<()> {
+    let payer = ctx.payer()?;
+    let etf = accounts::fetch_etf(&ctx.rpc, &etf_address)?;
+    if etf.lister != payer.pubkey() {
+        bail!("only the lister {} can set the hold period", etf.lister);
+    }
+    if !(0..=MAX_HOLD_SECONDS).contains(&seconds) {
+        bail!("hold period must be between 0 and {MAX_HOLD_SECONDS} seconds");
+    }
+    if early_exit_fee_bps > MAX_EARLY_EXIT_FEE_BPS {
+        bail!("early-exit fee must be at most {MAX_EARLY_EXIT_FEE_BPS} bps");
+    }
+
+    let ix = instructions::set_hold_period(etf_address, payer.pubkey(), seconds, early_exit_fee_bps);
+    let signature = send_and_confirm(ctx, &payer, &[ix])?;
+    println!("Shares of {etf_address}: {}", describe_hold(seconds, early_exit_fee_bps));
+    println!("Signature: {signature}");
+    Ok(())
+}
+
+fn dca(ctx: &Context, action: DcaAction) -> Result<()> {
+    match action {
+        DcaAction::Create {
+            etf,
+            sol,
+            interval,
+            deposit,
+            percentages,
+        } => dca_create(ctx, etf, parse_sol(&sol)?, interval, parse_sol(&deposit)?, percentages),
+        DcaAction::Fund { etf, sol } => {
+            let payer = ctx.payer()?;
+            let ix = instructions::fund_dca_plan(etf, payer.pubkey(), parse_sol(&sol)?);
+            let signature = send_and_confirm(ctx, &payer, &[ix])?;
+            println!("Added {sol} SOL to your plan for {etf}");
+            println!("Signature: {signature}");
+            Ok(())
+        }
+        DcaAction::Cancel { etf } => {
+            let payer = ctx.payer()?;
+            let (plan, _) = pda::dca_plan_address(&etf, &payer.pubkey());
+            let balance = ctx.rpc.get_balance(&plan)?;
+            if balance == 0 {
+                bail!("{} has no DCA plan for {etf}", payer.pubkey());
+            }
+            let ix = instructions::cancel_dca_plan(etf, payer.pubkey());
+            let signature = send_and_confirm(ctx, &payer, &[ix])?;
+            println!("Cancelled your plan for {etf}; refunded {} SOL", format_sol(balance));
+            println!("Signature: {signature}");
+            Ok(())
+        }
+        DcaAction::Crank { etf } => dca_crank(ctx, etf),
+    }
+}
+
+fn dca_create(
+    ctx: &Context,
+    etf_address: Pubkey,
+    sol_amount: u64,
+    interval: i64,
+    deposit: u64,
+    percentages: Option<Vec<u8>>,
+) -> Result<()> {
+    let payer = ctx.payer()?;
+    let etf = accounts::fetch_etf(&ctx.rpc, &etf_address)?;
+    if etf.keeper.is_some() {
+        bail!("{etf_address} settles buys through a keeper; DCA plans need instant buys");
+    }
+    if interval < MIN_DCA_INTERVAL_SECONDS {
+        bail!("interval must be at least {MIN_DCA_INTERVAL_SECONDS} seconds");
+    }
+    let percentages =
+        percentages.unwrap_or_else(|| math::even_percentages(etf.token_addresses.len()));
+
+    let ix = instructions::create_dca_plan(etf_address, payer.pubkey(), sol_amount, interval, percentages, deposit);
+    let signature = send_and_confirm(ctx, &payer, &[ix])?;
+    let periods = deposit / (sol_amount + DCA_CRANK_TIP_LAMPORTS);
+    println!(
+        "Buying {} SOL of {etf_address} every {interval}s; the deposit covers {periods} buy(s)",
+        format_sol(sol_amount)
+    );
+    println!("Signature: {signature}");
+    Ok(())
+}
+
+// One pass over the due plans; failures are reported and left for the next crank
+fn dca_crank(ctx: &Context, etf_filter: Option<Pubkey>) -> Result<()> {
+    let payer = ctx.payer()?;
+    let now = unix_now()?;
+    let due: Vec<DcaPlan> = accounts::fetch_dca_plans(&ctx.rpc, etf_filter.as_ref())?
+        .into_iter()
+        .map(|(_, plan)| plan)
+        .filter(|plan| plan.next_execution_ts <= now)
+        .collect();
+    if due.is_empty() {
+        println!("No DCA plans are due");
+        return Ok(());
+    }
+
+    for plan in due {
+        let label = format!("{} for {}", plan.etf, plan.investor);
+        let result = accounts::fetch_etf(&ctx.rpc, &plan.etf)
+            .map_err(anyhow::Error::from)
+            .and_then(|etf| {
+                let gate_account = crank_gate_account(&plan.etf, &etf, &plan.investor);
+                let ix = instructions::execute_dca(plan.etf, etf.lister, plan.investor, payer.pubkey(), gate_account);
+                send_and_confirm(ctx, &payer, &[ix])
+            });
+        match result {
+            Ok(signature) => println!("Executed plan {label} ({signature})"),
+            Err(e) => eprintln!("Could not execute plan {label}: {e}"),
+        }
+    }
+    Ok(())
+}
+
+// Gate account of an investor, for instructions cranked on their behalf.
+// Allowlist passes must already exist; nobody else can prove membership.
+fn crank_gate_account(etf_address: &Pubkey, etf: &ETF, investor: &Pubkey) -> Option<Pubkey> {
+    match etf.gate {
+        EtfGate::Open => None,
+        EtfGate::MerkleAllowlist { .. } => Some(pda::allowlist_pass_address(etf_address, investor).0),
+        EtfGate::MinimumBalance { mint, .. } => Some(get_associated_token_address(investor, &mint)),
+    }
+}
+
+fn order(ctx: &Context, action: OrderAction) -> Result<()> {
+    match action {
+        OrderAction::Place {
+            etf,
+            buy,
+            sell,
+            nav,
+            percentages,
+            id,
+            deposit,
+        } => {
+            let (side, amount) = match (buy, sell) {
+                (Some(sol), _) => (OrderSide::Buy, parse_sol(&sol)?),
+                (None, Some(shares)) => (OrderSide::Sell, shares),
+                (None, None) => bail!("pass --buy <SOL> or --sell <SHARES>"),
+            };
+            let id = match id {
+                Some(id) => id,
+                None => unix_now()? as u64,
+            };
+            let deposit = deposit.as_deref().map(parse_sol).transpose()?;
+            order_place(ctx, etf, side, amount, parse_sol(&nav)?, percentages, id, deposit)
+        }
+        OrderAction::Cancel { etf, id } => {
+            let payer = ctx.payer()?;
+            let (address, _) = pda::limit_order_address(&etf, &payer.pubkey(), id);
+            let order: LimitOrder = accounts::fetch_optional(&ctx.rpc, &address)?
+                .ok_or_else(|| anyhow!("{} has no order #{id} for {etf}", payer.pubkey()))?;
+            let ix = instructions::cancel_limit_order(etf, payer.pubkey(), id, order.side);
+            let signature = send_and_confirm(ctx, &payer, &[ix])?;
+            println!("Cancelled order #{id}; returned {}", describe_order_amount(order.side, order.amount));
+            println!("Signature: {signature}");
+            Ok(())
+        }
+        OrderAction::List { etf } => {
+            let owner = ctx.payer()?.pubkey();
+            let orders: Vec<LimitOrder> = accounts::fetch_limit_orders(&ctx.rpc, etf.as_ref())?
+                .into_iter()
+                .map(|(_, order)| order)
+                .filter(|order| order.owner == owner)
+                .collect();
+            if orders.is_empty() {
+                println!("No open orders for {owner}");
+            }
+            for order in orders {
+                println!(
+                    "#{}  {}  {:?} {} at NAV {}",
+                    order.order_id,
+                    order.etf,
+                    order.side,
+                    describe_order_amount(order.side, order.amount),
+                    format_sol(order.target_nav)
+                );
+            }
+            Ok(())
+        }
+        OrderAction::Crank { etf } => order_crank(ctx, etf),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn order_place(
+    ctx: &Context,
+    etf_address: Pubkey,
+    side: OrderSide,
+    amount: u64,
+    target_nav: u64,
+    percentages: Option<Vec<u8>>,
+    id: u64,
+    deposit: Option<u64>,
+) -> Result<()> {
+    let payer = ctx.payer()?;
+    let etf = accounts::fetch_etf(&ctx.rpc, &etf_address)?;
+    let mut ixs = Vec::new();
+    let percentages = match side {
+        OrderSide::Buy => {
+            percentages.unwrap_or_else(|| math::even_percentages(etf.token_addresses.len()))
+        }
+        OrderSide::Sell => {
+            let position = accounts::fetch_position(&ctx.rpc, &etf_address, &payer.pubkey())?
+                .ok_or_else(|| anyhow!("{} has no position in {etf_address}", payer.pubkey()))?;
+            if amount > position.shares {
+                bail!("cannot sell {amount} shares - position holds {}", position.shares);
+            }
+            let hold_ends = position.last_buy_ts.saturating_add(etf.min_hold_seconds);
+            if unix_now()? < hold_ends {
+                bail!("shares of {etf_address} are locked until {hold_ends} (unix time)");
+            }
+            // A keeper ETF pays a sell in kind into these accounts
+            if etf.keeper.is_some() {
+                for mint in &etf.token_addresses {
+                    ixs.push(
+                        spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+                            &payer.pubkey(),
+                            &payer.pubkey(),
+                            mint,
+                            &anchor_spl::token::ID,
+                        ),
+                    );
+                }
+            }
+            Vec::new()
+        }
+    };
+
+    // Fills on a keeper ETF are paid for out of the deposit; whatever is
+    // left comes back with the order
+    let deposit = match (deposit, side) {
+        (Some(deposit), _) => deposit,
+        _ if etf.keeper.is_none() => 0,
+        (None, OrderSide::Buy) => Rent::default().minimum_balance(PENDING_PURCHASE_SPACE),
+        (None, OrderSide::Sell) => math::in_kind_exit_fees(amount, target_nav.saturating_mul(2)),
+    };
+    ixs.push(instructions::place_limit_order(
+        etf_address,
+        payer.pubkey(),
+        id,
+        side,
+        amount,
+        target_nav,
+        percentages,
+        deposit,
+    ));
+    let signature = send_and_confirm(ctx, &payer, &ixs)?;
+    println!(
+        "Order #{id}: {:?} {} of {etf_address} at NAV {}",
+        side,
+        describe_order_amount(side, amount),
+        format_sol(target_nav)
+    );
+    println!("Signature: {signature}");
+    Ok(())
+}
+
+// One pass over the open orders, reading each ETF's NAV once; failures are
+// reported and left for the next crank
+fn order_crank(ctx: &Context, etf_filter: Option<Pubkey>) -> Result<()> {
+    let payer = ctx.payer()?;
+    let orders = accounts::fetch_limit_orders(&ctx.rpc, etf_filter.as_ref())?;
+    let mut etfs: Vec<Pubkey> = orders.iter().map(|(_, order)| order.etf).collect();
+    etfs.sort();
+    etfs.dedup();
+
+    let mut filled = 0;
+    for etf_address in etfs {
+        let state = accounts::fetch_etf(&ctx.rpc, &etf_address).map_err(anyhow::Error::from).and_then(|etf| {
+                Ok((
+                    views::get_nav(&ctx.rpc, &payer.pubkey(), etf_address, &etf.token_addresses)?.nav_per_share,
+                    etf,
+                ))
+        });
+        let (nav, etf) = match state {
+            Ok(state) => state,
+            Err(e) => {
+                eprintln!("Could not read the NAV of {etf_address}: {e}");
+                continue;
+            }
+        };
+
+        let triggered = orders
+            .iter()
+            .map(|(_, order)| order)
+            .filter(|order| order.etf == etf_address)
+            .filter(|order| mtf_etf::order_triggered(order.side, nav, order.target_nav));
+        for order in triggered {
+            let label = format!("#{} of {} in {etf_address}", order.order_id, order.owner);
+            let gate_account = match order.side {
+                OrderSide::Buy => crank_gate_account(&etf_address, &etf, &order.owner),
+                OrderSide::Sell => None,
+            };
+            // A sell is paid in kind while the ETF holds basket tokens
+            let vaults: Vec<(Pubkey, Pubkey)> = match order.side {
+                OrderSide::Sell if etf.holdings.iter().any(|held| *held > 0) => etf
+                    .token_addresses
+                    .iter()
+                    .map(|mint| {
+                        (
+                            pda::vault_address(&etf_address, mint),
+                            get_associated_token_address(&order.owner, mint),
+                        )
+                    })
+                    .collect(),
+                _ => Vec::new(),
+            };
+            let ix = instructions::fill_limit_order(etf_address, &etf, order, payer.pubkey(), gate_account, &vaults);
+            match send_and_confirm(ctx, &payer, &[ix]) {
+                Ok(signature) => {
+                    filled += 1;
+                    println!("Filled order {label} at NAV {} ({signature})", format_sol(nav));
+                }
+                Err(e) => eprintln!("Could not fill order {label}: {e}"),
+            }
+        }
+    }
+    if filled == 0 {
+        println!("No orders were filled");
+    }
+    Ok(())
+}
+
+fn describe_order_amount(side: OrderSide, amount: u64) -> String {
+    match side {
+        OrderSide::Buy => format!("{} SOL", format_sol(amount)),
+        OrderSide::Sell => format!("{amount} shares"),
+    }
+}
+
+fn exit(ctx: &Context, action: ExitAction) -> Result<()> {
+    match action {
+        ExitAction::Set {
+            etf,
+            stop_loss,
+            take_profit,
+            shares,
+            deposit,
+        } => {
+            let nav_or_none = |nav: Option<String>| nav.map_or(Ok(0), |nav| parse_sol(&nav));
+            let (stop_loss, take_profit) = (nav_or_none(stop_loss)?, nav_or_none(take_profit)?);
+            if stop_loss > 0 && take_profit > 0 && stop_loss >= take_profit {
+                bail!("the stop-loss must be below the take-profit");
+            }
+            let payer = ctx.payer()?;
+            let state = accounts::fetch_etf(&ctx.rpc, &etf)?;
+            let position = accounts::fetch_position(&ctx.rpc, &etf, &payer.pubkey())?
+                .ok_or_else(|| anyhow!("{} has no position in {etf}", payer.pubkey()))?;
+            let shares = shares.unwrap_or_default();
+            if shares > position.shares {
+                bail!("cannot sell {shares} shares - position holds {}", position.shares);
+            }
+
+            // A keeper ETF pays the exit in kind into these accounts, and its
+            // fees out of the deposit
+            let mut ixs = Vec::new();
+            if state.keeper.is_some() {
+                for mint in &state.token_addresses {
+                    ixs.push(
+                        spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+                            &payer.pubkey(),
+                            &payer.pubkey(),
+                            mint,
+                            &anchor_spl::token::ID,
+                        ),
+                    );
+                }
+            }
+            let deposit = match deposit.as_deref().map(parse_sol).transpose()? {
+                Some(deposit) => deposit,
+                None if state.keeper.is_none() => 0,
+                None => {
+                    let selling = if shares == 0 { position.shares } else { shares };
+                    math::in_kind_exit_fees(selling, stop_loss.max(take_profit).saturating_mul(2))
+                }
+            };
+            ixs.push(instructions::set_exit_trigger(etf, payer.pubkey(), stop_loss, take_profit, shares, deposit));
+            let signature = send_and_confirm(ctx, &payer, &ixs)?;
+            let amount = if shares == 0 {
+                "the whole position".to_string()
+            } else {
+                format!("{shares} shares")
+            };
+            println!("Selling {amount} of {etf} when {}", describe_exit(stop_loss, take_profit));
+            println!("Signature: {signature}");
+            Ok(())
+        }
+        ExitAction::Cancel { etf } => {
+            let payer = ctx.payer()?;
+            let (address, _) = pda::exit_trigger_address(&etf, &payer.pubkey());
+            if accounts::fetch_optional::<ExitTrigger>(&ctx.rpc, &address)?.is_none() {
+                bail!("{} has no exit trigger for {etf}", payer.pubkey());
+            }
+            let ix = instructions::cancel_exit_trigger(etf, payer.pubkey());
+            let signature = send_and_confirm(ctx, &payer, &[ix])?;
+            println!("Removed your exit trigger for {etf}");
+            println!("Signature: {signature}");
+            Ok(())
+        }
+        ExitAction::Crank { etf } => exit_crank(ctx, etf),
+    }
+}
+
+// One pass over the triggers, reading each ETF's NAV once; failures are
+// reported and left for the next crank
+fn exit_crank(ctx: &Context, etf_filter: Option<Pubkey>) -> Result<()> {
+    let payer = ctx.payer()?;
+    let triggers = accounts::fetch_exit_triggers(&ctx.rpc, etf_filter.as_ref())?;
+    let mut etfs: Vec<Pubkey> = triggers.iter().map(|(_, trigger)| trigger.etf).collect();
+    etfs.sort();
+    etfs.dedup();
+
+    let mut fired = 0;
+    for etf_address in etfs {
+        let state = accounts::fetch_etf(&ctx.rpc, &etf_address).map_err(anyhow::Error::from).and_then(|etf| {
+                Ok((
+                    views::get_nav(&ctx.rpc, &payer.pubkey(), etf_address, &etf.token_addresses)?.nav_per_share,
+                    etf,
+                ))
+        });
+        let (nav, etf) = match state {
+            Ok(state) => state,
+            Err(e) => {
+                eprintln!("Could not read the NAV of {etf_address}: {e}");
+                continue;
+            }
+        };
+
+        for (_, trigger) in triggers.iter().filter(|(_, trigger)| trigger.etf == etf_address) {
+            let Some(kind) = mtf_etf::exit_triggered(nav, trigger.stop_loss_nav, trigger.take_profit_nav) else {
+                continue;
+            };
+            let label = format!("{kind:?} of {} in {etf_address}", trigger.investor);
+            // The exit is paid in kind while the ETF holds basket tokens
+            let vaults: Vec<(Pubkey, Pubkey)> = if etf.holdings.iter().any(|held| *held > 0) {
+                etf.token_addresses
+                    .iter()
+                    .map(|mint| {
+                        (
+                            pda::vault_address(&etf_address, mint),
+                            get_associated_token_address(&trigger.investor, mint),
+                        )
+                    })
+                    .collect()
+            } else {
+                Vec::new()
+            };
+            let ix = instructions::trigger_exit(etf_address, &etf, trigger.investor, payer.pubkey(), &vaults);
+            match send_and_confirm(ctx, &payer, &[ix]) {
+                Ok(signature) => {
+                    fired += 1;
+                    println!("Fired {label} at NAV {} ({signature})", format_sol(nav));
+                }
+                Err(e) => eprintln!("Could not fire {label}: {e}"),
+            }
+        }
+    }
+    if fired == 0 {
+        println!("No exit triggers fired");
+    }
+    Ok(())
+}
+
+fn describe_exit(stop_loss_nav: u64, take_profit_nav: u64) -> String {
+    match (stop_loss_nav, take_profit_nav) {
+        (0, take) => format!("NAV reaches {}", format_sol(take)),
+        (stop, 0) => format!("NAV falls to {}", format_sol(stop)),
+        (stop, take) => format!("NAV falls to {} or reaches {}", format_sol(stop), format_sol(take)),
+    }
+}
+
+fn refund(ctx: &Context, etf_address: Pubkey) -> Result<()> {
+    let payer = ctx.payer()?;
+    let etf = accounts::fetch_etf(&ctx.rpc, &etf_address)?;
+    let purchases: Vec<PendingPurchase> =
+        accounts::fetch_pending_purchases(&ctx.rpc, Some(&etf_address))?
+            .into_iter()
+            .map(|(_, purchase)| purchase)
+            .filter(|purchase| purchase.investor == payer.pubkey())
+            .collect();
+    if purchases.is_empty() {
+        bail!("{} has no pending purchases in {etf_address}", payer.pubkey());
+    }
+
+    // Liquidating ETFs can no longer settle, so everything is refundable
+    let now = unix_now()?;
+    let (ready, waiting): (Vec<_>, Vec<_>) = purchases
+        .into_iter()
+        .partition(|purchase| etf.status != EtfStatus::Active || purchase.refundable_ts <= now);
+    for purchase in &waiting {
+        println!(
+            "Purchase #{} becomes refundable in {}s",
+            purchase.purchase_id,
+            purchase.refundable_ts - now
+        );
+    }
+    if ready.is_empty() {
+        return Ok(());
+    }
+
+    let ixs: Vec<Instruction> = ready
+        .iter()
+        .map(|purchase| {
+            instructions::refund_pending_purchase(etf_address, payer.pubkey(), purchase.purchase_id)
+        })
+        .collect();
+    let signature = send_and_confirm(ctx, &payer, &ixs)?;
+    let refunded: u64 = ready.iter().map(|purchase| purchase.sol_to_swap).sum();
+    println!(
+        "Refunded {} purchase(s) of {etf_address}: {} SOL",
+        ready.len(),
+        format_sol(refunded)
+    );
+    println!("Signature: {signature}");
+    Ok(())
+}
+
+fn show(ctx: &Context, etf_address: Pubkey) -> Result<()> {
+    let etf = accounts::fetch_etf(&ctx.rpc, &etf_address)?;
+    print_etf(&etf_address, &etf);
+    Ok(())
+}
+
+fn nav(ctx: &Context, etf_address: Pubkey, twap_window: Option<i64>) -> Result<()> {
+    let payer = ctx.payer()?;
+    let etf = accounts::fetch_etf(&ctx.rpc, &etf_address)?;
+    let nav = views::get_nav(&ctx.rpc, &payer.pubkey(), etf_address, &etf.token_addresses)?;
+    println!("Status:        {:?}", nav.status);
+    println!("Total supply:  {}", nav.total_supply);
+    println!("Net assets:    {} SOL", format_sol(nav.net_assets));
+    println!("Pending SOL:   {}", format_sol(nav.pending_sol));
+    // An empty ETF mints shares 1:1 with lamports, so NAV_SCALE is par
+    println!("NAV/share:     {} (1.0 = par)", format_sol(nav.nav_per_share));
+    if let Some(window) = twap_window {
+        let twap = views::get_nav_twap(&ctx.rpc, &payer.pubkey(), etf_address, window)?;
+        println!(
+            "TWAP:          {} over {}s of {window}s ({} snapshots)",
+            format_sol(twap.nav_per_share),
+            twap.covered_seconds,
+            twap.snapshots
+        );
+    }
+    Ok(())
+}
+
+fn snapshot(ctx: &Context, etf_filter: Option<Pubkey>) -> Result<()> {
+    let payer = ctx.payer()?;
+    if let Some(etf_address) = etf_filter {
+        let mut ixs = Vec::new();
+        if accounts::fetch_nav_history(&ctx.rpc, &etf_address)?.is_none() {
+            ixs.push(instructions::create_nav_history(etf_address, payer.pubkey()));
+        }
+        let etf = accounts::fetch_etf(&ctx.rpc, &etf_address)?;
+        ixs.push(instructions::snapshot_nav(etf_address, &etf.token_addresses));
+        let signature = send_and_confirm(ctx, &payer, &ixs)?;
+        println!("Snapshotted the NAV of {etf_address} ({signature})");
+        return Ok(());
+    }
+
+    // Crank: every history whose interval has passed; failures are reported
+    // and left for the next run
+    let now = unix_now()?;
+    let mut taken = 0;
+    for (_, history) in accounts::fetch_nav_histories(&ctx.rpc)? {
+        let last = history.snapshots.iter().map(|snapshot| snapshot.timestamp).max();
+        if last.is_some_and(|last| now < last + NAV_SNAPSHOT_INTERVAL_SECONDS) {
+            continue;
+        }
+        let snapshot = accounts::fetch_etf(&ctx.rpc, &history.etf)
+            .map_err(anyhow::Error::from)
+            .and_then(|etf| {
+                send_and_confirm(
+                    ctx,
+                    &payer,
+                    &[instructions::snapshot_nav(history.etf, &etf.token_addresses)],
+                )
+            });
+        match snapshot {
+            Ok(signature) => {
+                taken += 1;
+                println!("Snapshotted the NAV of {} ({signature})", history.etf);
+            }
+            Err(e) => eprintln!("Could not snapshot {}: {e}", history.etf),
+        }
+    }
+    if taken == 0 {
+        println!("No NAV histories due");
+    }
+    Ok(())
+}
+
+// `epoch`, or the last epoch that ended at least `delay` seconds ago
+fn epoch_or_last_ended(epoch: Option<u64>, delay: i64) -> Result<u64> {
+    match epoch {
+        Some(epoch) => Ok(epoch),
+        None => Ok(((unix_now()? - delay) / LEADERBOARD_EPOCH_SECONDS - 1) as u64),
+    }
+}
+
+fn leaderboard(ctx: &Context, action: LeaderboardAction) -> Result<()> {
+    let last_ended = |epoch| epoch_or_last_ended(epoch, 0);
+    match action {
+        LeaderboardAction::Show { epoch } => {
+            let epoch_id = last_ended(epoch)?;
+            let Some(board) = accounts::fetch_leaderboard_epoch(&ctx.rpc, epoch_id)? else {
+                bail!("Epoch {epoch_id} has no leaderboard");
+            };
+            println!("Epoch {epoch_id} ({} to {})", board.start_ts, board.end_ts);
+            if board.entries.is_empty() {
+                println!("No returns submitted");
+            }
+            for (rank, entry) in board.entries.iter().enumerate() {
+                println!(
+                    "{:>2}. {:+.2}%  {}  by {}  (NAV {} -> {})",
+                    rank + 1,
+                    entry.return_bps as f64 / 100.0,
+                    entry.etf,
+                    entry.lister,
+                    format_sol(entry.start_nav),
+                    format_sol(entry.end_nav)
+                );
+            }
+            Ok(())
+        }
+        LeaderboardAction::Submit { epoch, etf } => leaderboard_submit(ctx, last_ended(epoch)?, etf),
+    }
+}
+
+// Candidates are checked against their NAV histories first, best return
+// first, so only submissions that would rank are sent
+fn leaderboard_submit(ctx: &Context, epoch_id: u64, etf_filter: Option<Pubkey>) -> Result<()> {
+    let payer = ctx.payer()?;
+    let mut board = accounts::fetch_leaderboard_epoch(&ctx.rpc, epoch_id)?;
+    if board.is_none() {
+        let ix = instructions::create_leaderboard_epoch(epoch_id, payer.pubkey());
+        send_and_confirm(ctx, &payer, &[ix])?;
+        board = accounts::fetch_leaderboard_epoch(&ctx.rpc, epoch_id)?;
+    }
+    let board = board.ok_or_else(|| anyhow!("Could not create the leaderboard of epoch {epoch_id}"))?;
+    if unix_now()? < board.end_ts {
+        bail!("Epoch {epoch_id} ends at {}", board.end_ts);
+    }
+
+    let histories = match etf_filter {
+        Some(etf) => {
+            let history = accounts::fetch_nav_history(&ctx.rpc, &etf)?
+                .ok_or_else(|| anyhow!("{etf} has no NAV history"))?;
+            vec![history]
+        }
+        None => accounts::fetch_nav_histories(&ctx.rpc)?
+            .into_iter()
+            .map(|(_, history)| history)
+            .collect(),
+    };
+    let mut candidates: Vec<(Pubkey, i64)> = histories
+        .iter()
+        .filter(|history| board.entries.iter().all(|entry| entry.etf != history.etf))
+        .filter_map(|history| {
+            let (start_nav, start) = mtf_etf::boundary_nav(&history.snapshots, board.start_ts)?;
+            let (end_nav, _) = mtf_etf::boundary_nav(&history.snapshots, board.end_ts)?;
+            if start.total_value < LEADERBOARD_MIN_NET_ASSETS {
+                return None;
+            }
+            Some((history.etf, mtf_etf::nav_return_bps(start_nav, end_nav)?))
+        })
+        .collect();
+    candidates.sort_by_key(|(_, return_bps)| std::cmp::Reverse(*return_bps));
+
+    let mut entries = board.entries;
+    let mut submitted = 0;
+    for (etf, return_bps) in candidates {
+        if mtf_etf::leaderboard_rank(&entries, return_bps) >= LEADERBOARD_SIZE {
+            break;
+        }
+        match send_and_confirm(ctx, &payer, &[instructions::submit_epoch_return(epoch_id, etf)]) {
+            Ok(signature) => {
+                submitted += 1;
+                println!("Submitted {etf} at {return_bps} bps ({signature})");
+                if let Some(updated) = accounts::fetch_leaderboard_epoch(&ctx.rpc, epoch_id)? {
+                    entries = updated.entries;
+                }
+            }
+            Err(e) => eprintln!("Could not submit {etf}: {e}"),
+        }
+    }
+    if submitted == 0 {
+        println!("No returns to submit for epoch {epoch_id}");
+    }
+    Ok(())
+}
+
+fn rewards(ctx: &Context, action: RewardsAction) -> Result<()> {
+    let payer = ctx.payer()?;
+    match action {
+        RewardsAction::Show { epoch } => {
+            let Some(treasury) = accounts::fetch_treasury(&ctx.rpc)? else {
+                bail!("The treasury has not been configured");
+            };
+            println!("Rewards share: {} bps", treasury.rewards_share_bps);
+            println!("Rewards pool:  {} SOL", format_sol(treasury.rewards_pool));
+            println!("Rewards owed:  {} SOL", format_sol(treasury.rewards_owed));
+            println!("Dev balance:   {} SOL", format_sol(treasury.dev_balance));
+            if let Some(epoch_id) = epoch {
+                let Some(rewards) = accounts::fetch_epoch_rewards(&ctx.rpc, epoch_id)? else {
+                    bail!("Epoch {epoch_id} has not been finalized");
+                };
+                for (rank, reward) in rewards.rewards.iter().enumerate() {
+                    println!(
+                        "{:>2}. {} SOL  {}  by {}{}",
+                        rank + 1,
+                        format_sol(reward.amount),
+                        reward.etf,
+                        reward.lister,
+                        if reward.claimed { "  (claimed)" } else { "" }
+                    );
+                }
+            }
+            Ok(())
+        }
+        RewardsAction::Fund { sol } => {
+            let amount = parse_sol(&sol)?;
+            let signature = send_and_confirm(ctx, &payer, &[instructions::fund_rewards(payer.pubkey(), amount)])?;
+            println!("Funded the rewards pool with {} SOL ({signature})", format_sol(amount));
+            Ok(())
+        }
+        RewardsAction::Finalize { epoch } => {
+            let epoch_id = epoch_or_last_ended(epoch, LEADERBOARD_SUBMISSION_SECONDS)?;
+            let signature = send_and_confirm(ctx, &payer, &[instructions::finalize_epoch(epoch_id, payer.pubkey())])?;
+            println!("Finalized epoch {epoch_id} ({signature})");
+            Ok(())
+        }
+        RewardsAction::Claim { epoch } => {
+            let Some(rewards) = accounts::fetch_epoch_rewards(&ctx.rpc, epoch)? else {
+                bail!("Epoch {epoch} has not been finalized");
+            };
+            let mine: Vec<(u8, u64)> = rewards
+                .rewards
+                .iter()
+                .enumerate()
+                .filter(|(_, reward)| reward.lister == payer.pubkey() && !reward.claimed)
+                .map(|(rank, reward)| (rank as u8, reward.amount))
+                .collect();
+            if mine.is_empty() {
+                bail!("{} has no unclaimed rewards in epoch {epoch}", payer.pubkey());
+            }
+            let ixs: Vec<Instruction> = mine
+                .iter()
+                .map(|(rank, _)| instructions::claim_creator_reward(epoch, payer.pubkey(), *rank))
+                .collect();
+            let signature = send_and_confirm(ctx, &payer, &ixs)?;
+            let total: u64 = mine.iter().map(|(_, amount)| amount).sum();
+            println!("Claimed {} SOL from epoch {epoch} ({signature})", format_sol(total));
+            Ok(())
+        }
+        RewardsAction::WithdrawDev => {
+            let signature = send_and_confirm(ctx, &payer, &[instructions::withdraw_dev_fees()])?;
+            println!("Withdrew dev fees ({signature})");
+            Ok(())
+        }
+        RewardsAction::Configure { rewards_share_bps } => {
+            if rewards_share_bps > MAX_REWARDS_SHARE_BPS {
+                bail!("Rewards share cannot exceed {MAX_REWARDS_SHARE_BPS} bps");
+            }
+            let ix = instructions::configure_treasury(payer.pubkey(), rewards_share_bps);
+            let signature = send_and_confirm(ctx, &payer, &[ix])?;
+            println!("Rewards share set to {rewards_share_bps} bps ({signature})");
+            Ok(())
+        }
+    }
+}
+
+fn list(ctx: &Context, lister: Option<Pubkey>) -> Result<()> {
+    let lister = match lister {
+        Some(lister) => lister,
+        None => ctx.payer()?.pubkey(),
+    };
+    let mut etfs = accounts::fetch_etfs_by_lister(&ctx.rpc, &lister)?;
+    etfs.sort_by_key(|(_, etf)| etf.etf_index);
+
+    if etfs.is_empty() {
+        println!("No ETFs found for {lister}");
+    }
+    for (address, etf) in etfs {
+        println!(
+            "[{}] {address}  {} ({})  supply {}  {:?}",
+            etf.etf_index, etf.name, etf.symbol, etf.total_supply, etf.status
+        );
+    }
+    Ok(())
+}
+
+fn quote(ctx: &Context, side: QuoteSide) -> Result<()> {
+    match side {
+        QuoteSide::Buy {
+            sol,
+            etf,
+            percentages,
+        } => {
+            let sol_amount = parse_sol(&sol)?;
+            let etf = etf.map(|etf| accounts::fetch_etf(&ctx.rpc, &etf)).transpose()?;
+            let percentages = match (percentages, &etf) {
+                (Some(percentages), _) => percentages,
+                (None, Some(etf)) => math::even_percentages(etf.token_addresses.len()),
+                (None, None) => vec![100],
+            };
+            // Without an ETF, quote into an empty one
+            let (total_supply, sol_assets) = etf.map_or((0, 0), |etf| (etf.total_supply, etf.sol_assets));
+            let quote = math::quote_buy(sol_amount, &percentages, total_supply, sol_assets);
+            println!("SOL in:        {}", format_sol(quote.sol_amount));
+            println!("Creator fee:   {}", format_sol(quote.creator_fee));
+            println!("Dev fee:       {}", format_sol(quote.dev_fee));
+            println!("Shares out:    {}", quote.shares_out);
+            for (percentage, allocation) in percentages.iter().zip(&quote.allocations) {
+                println!("  {percentage:>3}% -> {} SOL", format_sol(*allocation));
+            }
+        }
+        QuoteSide::Sell { shares, etf } => {
+            // Without an ETF, quote at one lamport per share
+            let (total_supply, sol_assets) = match etf {
+                Some(etf) => {
+                    let etf = accounts::fetch_etf(&ctx.rpc, &etf)?;
+                    (etf.total_supply, etf.sol_assets)
+                }
+                None => (shares, shares),
+            };
+            let quote = math::quote_sell(shares, total_supply, sol_assets);
+            println!("Shares in:     {}", quote.shares_in);
+            println!("Creator fee:   {}", format_sol(quote.creator_fee));
+            println!("Dev fee:       {}", format_sol(quote.dev_fee));
+            println!("SOL out:       {}", format_sol(quote.sol_out));
+        }
+    }
+    Ok(())
+}
+
+fn print_etf(address: &Pubkey, etf: &ETF) {
+    println!("ETF:           {address}");
+    println!("Name:          {} ({})", etf.name, etf.symbol);
+    println!("Category:      {:?}", etf.category);
+    println!("URI:           {}", etf.uri);
+    println!("Lister:        {}", etf.lister);
+    println!("Index:         {}", etf.etf_index);
+    println!("Registry:      #{}", etf.registry_index);
+    println!("Total supply:  {}", etf.total_supply);
+    println!("Trades:        {}", etf.trade_sequence);
+    println!("Buyers:        {}", describe_gate(&etf.gate));
+    println!("Buy limits:    {}", describe_limits(&etf.limits));
+    println!("Hold period:   {}", describe_hold(etf.min_hold_seconds, etf.early_exit_fee_bps));
+    if let Some(parent) = etf.parent_etf {
+        println!("Forked from:   {parent} (lister {})", etf.parent_lister);
+        println!(
+            "Parent fees:   {} bps of creator fees, {} SOL unclaimed",
+            etf.parent_fee_bps,
+            format_sol(etf.parent_fees_owed)
+        );
+    }
+    if let Some(keeper) = etf.keeper {
+        println!("Keeper:        {keeper}");
+        println!("Pending SOL:   {}", format_sol(etf.pending_sol));
+        println!("Refund after:  {}s", etf.refund_timeout);
+    }
+    println!(
+        "Status:        {:?}{}",
+        etf.status,
+        if etf.status == EtfStatus::Liquidating {
+            format!(" (since {})", etf.liquidation_started_ts)
+        } else {
+            String::new()
+        }
+    );
+    println!("Tokens:");
+    for mint in &etf.token_addresses {
+        println!("  {mint}");
+    }
+}
+
+fn describe_gate(gate: &EtfGate) -> String {
+    match gate {
+        EtfGate::Open => "open to anyone".to_string(),
+        EtfGate::MerkleAllowlist { root } => format!(
+            "allowlist, root {}",
+            root.iter().map(|byte| format!("{byte:02x}")).collect::<String>()
+        ),
+        EtfGate::MinimumBalance { mint, amount } => {
+            format!("holders of at least {amount} of {mint}")
+        }
+    }
+}
+
+fn describe_limits(limits: &BuyLimits) -> String {
+    let limit = |lamports: u64| {
+        if lamports == 0 {
+            "protocol default".to_string()
+        } else {
+            format_sol(lamports)
+        }
+    };
+    format!(
+        "min buy {}, max buy {}, max supply {}",
+        limit(limits.min_buy_lamports),
+        limit(limits.max_buy_lamports),
+        limit(limits.max_total_supply)
+    )
+}
+
+fn describe_hold(seconds: i64, early_exit_fee_bps: u16) -> String {
+    match (seconds, early_exit_fee_bps) {
+        (0, _) => "none".to_string(),
+        (seconds, 0) => format!("{seconds}s, no early exits"),
+        (seconds, bps) => format!("{seconds}s, early exits pay {bps} bps"),
+    }
+}
+
+// Local clock; close enough to the cluster's for deciding what to submit
+fn unix_now() -> Result<i64> {
+    Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64)
+}
+
+fn send_and_confirm(ctx: &Context, payer: &Keypair, ixs: &[Instruction]) -> Result<String> {
+    let blockhash = ctx.rpc.get_latest_blockhash()?;
+    let tx = Transaction::new_signed_with_payer(ixs, Some(&payer.pubkey()), &[payer], blockhash);
+    let signature = ctx.rpc.send_transaction(&bincode::serialize(&tx)?)?;
+
+    let started = Instant::now();
+    while !ctx.rpc.is_confirmed(&signature)? {
+        if started.elapsed() > CONFIRM_TIMEOUT {
+            bail!("transaction {signature} was not confirmed within {CONFIRM_TIMEOUT:?}");
+        }
+        sleep(Duration::from_millis(500));
+    }
+    Ok(signature)
+}
@@ -0,0 +1,113 @@
+// RPC URL and keypair resolution, following the Solana CLI conventions
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use anchor_lang::prelude::Pubkey;
+use anyhow::{anyhow, Result};
+use mtf_etf_client::rpc::{DEVNET_RPC_URL, LOCALNET_RPC_URL, MAINNET_RPC_URL};
+use solana_keypair::{read_keypair_file, Keypair};
+
+// Same default wallet as Anchor.toml's [provider]
+pub const DEFAULT_KEYPAIR_PATH: &str = "~/.config/solana/id.json";
+
+// Accepts a full URL or a moniker: localnet / devnet / mainnet (or l / d / m)
+pub fn resolve_url(url_or_moniker: &str) -> String {
+    match url_or_moniker {
+        "l" | "localnet" | "localhost" => LOCALNET_RPC_URL.to_string(),
+        "d" | "devnet" => DEVNET_RPC_URL.to_string(),
+        "m" | "mainnet" | "mainnet-beta" => MAINNET_RPC_URL.to_string(),
+        url => url.to_string(),
+    }
+}
+
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+pub fn load_keypair(path: &str) -> Result<Keypair> {
+    let path = expand_home(path);
+    read_keypair_file(&path).map_err(|e| anyhow!("failed to read keypair {}: {e}", path.display()))
+}
+
+// Parse a decimal SOL amount ("0.25") into lamports without float rounding
+pub fn parse_sol(amount: &str) -> Result<u64> {
+    let (whole, fraction) = amount.split_once('.').unwrap_or((amount, ""));
+    if fraction.len() > 9 || (whole.is_empty() && fraction.is_empty()) {
+        return Err(anyhow!("invalid SOL amount: {amount}"));
+    }
+    let whole: u64 = if whole.is_empty() { 0 } else { whole.parse()? };
+    let fraction: u64 = if fraction.is_empty() {
+        0
+    } else {
+        format!("{fraction:0<9}").parse()?
+    };
+    whole
+        .checked_mul(1_000_000_000)
+        .and_then(|lamports| lamports.checked_add(fraction))
+        .ok_or_else(|| anyhow!("SOL amount too large: {amount}"))
+}
+
+// Allowlist files hold one wallet per line; blank lines and # comments are skipped
+pub fn parse_allowlist(text: &str) -> Result<Vec<Pubkey>> {
+    text.lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| Pubkey::from_str(line).map_err(|_| anyhow!("invalid wallet in allowlist: {line}")))
+        .collect()
+}
+
+pub fn load_allowlist(path: &str) -> Result<Vec<Pubkey>> {
+    let path = expand_home(path);
+    let text = std::fs::read_to_string(&path)
+        .map_err(|e| anyhow!("failed to read allowlist {}: {e}", path.display()))?;
+    parse_allowlist(&text)
+}
+
+pub fn format_sol(lamports: u64) -> String {
+    format!(
+        "{}.{:09}",
+        lamports / 1_000_000_000,
+        lamports % 1_000_000_000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_url_monikers() {
+        assert_eq!(resolve_url("localnet"), LOCALNET_RPC_URL);
+        assert_eq!(resolve_url("d"), DEVNET_RPC_URL);
+        assert_eq!(resolve_url("http://10.0.0.1:8899"), "http://10.0.0.1:8899");
+    }
+
+    #[test]
+    fn test_parse_sol() {
+        assert_eq!(parse_sol("1").unwrap(), 1_000_000_000);
+        assert_eq!(parse_sol("0.1").unwrap(), 100_000_000);
+        assert_eq!(parse_sol(".5").unwrap(), 500_000_000);
+        assert_eq!(parse_sol("2.000000001").unwrap(), 2_000_000_001);
+        assert!(parse_sol("0.0000000001").is_err());
+        assert!(parse_sol("abc").is_err());
+        assert!(parse_sol(".").is_err());
+    }
+
+    #[test]
+    fn test_parse_allowlist() {
+        let a = Pubkey::new_unique();
+        let b = Pubkey::new_unique();
+        let text = format!("# community wallets\n{a}\n\n  {b}  # team\n");
+        assert_eq!(parse_allowlist(&text).unwrap(), vec![a, b]);
+        assert!(parse_allowlist("not-a-wallet").is_err());
+    }
+
+    #[test]
+    fn test_format_sol() {
+        assert_eq!(format_sol(1_500_000_000), "1.500000000");
+        assert_eq!(format_sol(5_000), "0.000005000");
+    }
+}
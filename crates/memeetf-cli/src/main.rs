@@ -0,0 +1,688 @@
+// memeetf - command-line tool for operating MemeETF ETFs
+//
+// Uses the same instruction builders as the Rust SDK (mtf-etf-client), which
+// take their layouts from the mtf_etf program crate.
+
+mod commands;
+mod config;
+
+use anchor_lang::prelude::Pubkey;
+use anyhow::Result;
+use clap::{Parser, Subcommand, ValueEnum};
+use mtf_etf::EtfCategory;
+
+#[derive(Parser)]
+#[command(name = "memeetf", version, about = "Create and operate MemeETF ETFs")]
+pub struct Cli {
+    /// RPC URL or moniker (localnet, devnet, mainnet)
+    #[arg(
+        short = 'u',
+        long,
+        global = true,
+        env = "MEMEETF_RPC_URL",
+        default_value = "devnet"
+    )]
+    pub url: String,
+
+    /// Keypair used to sign and pay for transactions
+    #[arg(short = 'k', long, global = true, env = "MEMEETF_KEYPAIR", default_value = config::DEFAULT_KEYPAIR_PATH)]
+    pub keypair: String,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Create a new ETF owned by the keypair
+    Create {
+        /// ETF slot for this wallet (0-4)
+        #[arg(long, default_value_t = 0)]
+        index: u8,
+        /// Basket token mints, comma separated (1-10)
+        #[arg(long, value_delimiter = ',', required = true)]
+        tokens: Vec<Pubkey>,
+        #[arg(long)]
+        name: String,
+        #[arg(long)]
+        symbol: String,
+        #[arg(long, default_value = "")]
+        uri: String,
+        #[arg(long, value_enum, default_value_t = Category::Meme)]
+        category: Category,
+    },
+    /// Create an ETF owned by the keypair from another ETF's basket
+    Fork {
+        /// ETF to fork
+        parent: Pubkey,
+        /// ETF slot for this wallet (0-4)
+        #[arg(long, default_value_t = 0)]
+        index: u8,
+        /// Parent mints to leave out, comma separated
+        #[arg(long, value_delimiter = ',')]
+        remove: Vec<Pubkey>,
+        /// Mints to add, comma separated
+        #[arg(long, value_delimiter = ',')]
+        add: Vec<Pubkey>,
+        /// Share of this ETF's creator fees, in basis points, paid to the
+        /// parent's lister
+        #[arg(long, default_value_t = 0)]
+        parent_fee_bps: u16,
+        #[arg(long)]
+        name: String,
+        #[arg(long)]
+        symbol: String,
+        #[arg(long, default_value = "")]
+        uri: String,
+        /// Defaults to the parent's category
+        #[arg(long, value_enum)]
+        category: Option<Category>,
+    },
+    /// Pay a fork's accrued parent fees to the parent's lister
+    ClaimParentFees { etf: Pubkey },
+    /// Upgrade an ETF listed before the registry to the current account layout
+    Migrate { etf: Pubkey },
+    /// Buy into an ETF with SOL
+    Buy {
+        etf: Pubkey,
+        /// Amount of SOL to invest (e.g. 0.5)
+        #[arg(long)]
+        sol: String,
+        /// Per-token percentages, comma separated (defaults to equal weights)
+        #[arg(long, value_delimiter = ',')]
+        percentages: Option<Vec<u8>>,
+        /// Wallet list of an allowlisted ETF, to prove membership from
+        #[arg(long)]
+        allowlist: Option<String>,
+    },
+    /// Sell ETF shares back for SOL
+    Sell {
+        etf: Pubkey,
+        /// Number of shares to sell
+        #[arg(long, conflicts_with = "all", required_unless_present = "all")]
+        shares: Option<u64>,
+        /// Sell the whole position
+        #[arg(long)]
+        all: bool,
+    },
+    /// Burn shares for your share of each basket token and the ETF's SOL
+    Redeem {
+        etf: Pubkey,
+        /// Number of shares to redeem
+        #[arg(long, conflicts_with = "all", required_unless_present = "all")]
+        shares: Option<u64>,
+        /// Redeem the whole position
+        #[arg(long)]
+        all: bool,
+    },
+    /// Move shares from your position into SPL share tokens
+    Wrap {
+        etf: Pubkey,
+        #[arg(long)]
+        shares: u64,
+    },
+    /// Burn SPL share tokens back into your position
+    Unwrap {
+        etf: Pubkey,
+        #[arg(long)]
+        shares: u64,
+    },
+    /// Close an ETF with no outstanding supply (lister only)
+    Close { etf: Pubkey },
+    /// Set or remove the keeper that settles buys (lister only)
+    SetKeeper {
+        etf: Pubkey,
+        /// Keeper wallet; buys become two-phase once set
+        #[arg(long, conflicts_with = "none", required_unless_present = "none")]
+        keeper: Option<Pubkey>,
+        /// Remove the keeper and return to instant settlement
+        #[arg(long)]
+        none: bool,
+        /// Seconds before investors may refund an unsettled purchase
+        #[arg(long, default_value_t = mtf_etf::DEFAULT_REFUND_TIMEOUT_SECONDS)]
+        refund_timeout: i64,
+    },
+    /// Restrict who may buy an ETF (lister only)
+    SetGate {
+        etf: Pubkey,
+        /// Let anyone buy
+        #[arg(long, conflicts_with_all = ["allowlist", "mint"], required_unless_present_any = ["allowlist", "mint"])]
+        open: bool,
+        /// File of allowed wallets, one per line; its merkle root is stored
+        #[arg(long, conflicts_with = "mint")]
+        allowlist: Option<String>,
+        /// Only holders of this mint may buy
+        #[arg(long, requires = "min_amount")]
+        mint: Option<Pubkey>,
+        /// Balance of --mint required, in base units
+        #[arg(long, requires = "mint")]
+        min_amount: Option<u64>,
+    },
+    /// Limit buy sizes and total supply (lister only); omitted limits are removed
+    SetLimits {
+        etf: Pubkey,
+        /// Smallest buy, in SOL
+        #[arg(long)]
+        min_buy: Option<String>,
+        /// Largest buy, in SOL
+        #[arg(long)]
+        max_buy: Option<String>,
+        /// Most shares that may exist, in SOL at 1:1
+        #[arg(long)]
+        max_supply: Option<String>,
+    },
+    /// Lock shares for a while after each buy (lister only)
+    SetHold {
+        etf: Pubkey,
+        /// Seconds shares must be held after a buy; 0 removes the lock-up
+        #[arg(long)]
+        seconds: i64,
+        /// Let holders sell early for this fee, in basis points, paid to the
+        /// remaining holders; without it early sells fail
+        #[arg(long, default_value_t = 0)]
+        early_exit_fee_bps: u16,
+    },
+    /// Recurring buys from SOL deposited in advance
+    Dca {
+        #[command(subcommand)]
+        action: DcaAction,
+    },
+    /// Buy or sell automatically once the NAV reaches a target
+    Order {
+        #[command(subcommand)]
+        action: OrderAction,
+    },
+    /// Sell automatically when the NAV falls to a stop-loss or rises to a take-profit
+    Exit {
+        #[command(subcommand)]
+        action: ExitAction,
+    },
+    /// Reclaim the SOL of your purchases the keeper has not settled in time
+    Refund { etf: Pubkey },
+    /// Decode and print an ETF account
+    Show { etf: Pubkey },
+    /// Net asset value per share, as computed by the program (simulated)
+    Nav {
+        etf: Pubkey,
+        /// Also print the time-weighted NAV over this many seconds of the
+        /// ETF's NAV history
+        #[arg(long)]
+        twap: Option<i64>,
+    },
+    /// Record the NAV into an ETF's history, creating the history if needed;
+    /// without --etf, snapshot every history that is due
+    Snapshot {
+        #[arg(long)]
+        etf: Option<Pubkey>,
+    },
+    /// On-chain ranking of ETFs by NAV return per epoch
+    Leaderboard {
+        #[command(subcommand)]
+        action: LeaderboardAction,
+    },
+    /// Protocol treasury and the creator rewards paid from it
+    Rewards {
+        #[command(subcommand)]
+        action: RewardsAction,
+    },
+    /// List ETFs created by a lister (defaults to the keypair)
+    List {
+        #[arg(long)]
+        lister: Option<Pubkey>,
+    },
+    /// Quote a buy or sell without sending a transaction
+    Quote {
+        #[command(subcommand)]
+        side: QuoteSide,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum DcaAction {
+    /// Open a plan buying a fixed amount of SOL every interval
+    Create {
+        etf: Pubkey,
+        /// SOL per buy (e.g. 0.1)
+        #[arg(long)]
+        sol: String,
+        /// Seconds between buys
+        #[arg(long, default_value_t = 24 * 60 * 60)]
+        interval: i64,
+        /// SOL deposited now to pay for the buys
+        #[arg(long)]
+        deposit: String,
+        /// Per-token percentages, comma separated (defaults to equal weights)
+        #[arg(long, value_delimiter = ',')]
+        percentages: Option<Vec<u8>>,
+    },
+    /// Add SOL to your plan
+    Fund {
+        etf: Pubkey,
+        #[arg(long)]
+        sol: String,
+    },
+    /// Close your plan and get its remaining SOL back
+    Cancel { etf: Pubkey },
+    /// Execute every plan that is due, earning the crank tip for each
+    Crank {
+        #[arg(long)]
+        etf: Option<Pubkey>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum OrderAction {
+    /// Escrow SOL or shares until the NAV crosses --nav
+    Place {
+        etf: Pubkey,
+        /// Buy with this much SOL once the NAV is at or below --nav
+        #[arg(long, conflicts_with = "sell", required_unless_present = "sell")]
+        buy: Option<String>,
+        /// Sell this many shares once the NAV is at or above --nav
+        #[arg(long)]
+        sell: Option<u64>,
+        /// Target NAV per share, in SOL (1.0 = par)
+        #[arg(long)]
+        nav: String,
+        /// Per-token percentages of a buy, comma separated (defaults to equal weights)
+        #[arg(long, value_delimiter = ',', conflicts_with = "sell")]
+        percentages: Option<Vec<u8>>,
+        /// Order id, unique per ETF and wallet (defaults to the current unix time)
+        #[arg(long)]
+        id: Option<u64>,
+        /// SOL held to pay for a fill on a keeper ETF, refunded with the order
+        /// (defaults to a pending purchase's rent for buys, or the fees at
+        /// twice --nav for sells)
+        #[arg(long)]
+        deposit: Option<String>,
+    },
+    /// Close an order and get its SOL or shares back
+    Cancel {
+        etf: Pubkey,
+        #[arg(long)]
+        id: u64,
+    },
+    /// List your open orders
+    List {
+        #[arg(long)]
+        etf: Option<Pubkey>,
+    },
+    /// Fill every order whose target the NAV has crossed
+    Crank {
+        #[arg(long)]
+        etf: Option<Pubkey>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ExitAction {
+    /// Attach (or replace) the thresholds of your position
+    Set {
+        etf: Pubkey,
+        /// Sell once the NAV per share is at or below this, in SOL
+        #[arg(long, required_unless_present = "take_profit")]
+        stop_loss: Option<String>,
+        /// Sell once the NAV per share is at or above this, in SOL
+        #[arg(long)]
+        take_profit: Option<String>,
+        /// Shares to sell (defaults to the whole position)
+        #[arg(long)]
+        shares: Option<u64>,
+        /// SOL held to pay the fees of an in-kind exit on a keeper ETF,
+        /// refunded with the trigger (defaults to the fees at twice the
+        /// highest threshold)
+        #[arg(long)]
+        deposit: Option<String>,
+    },
+    /// Remove your trigger and get its tip and deposit back
+    Cancel { etf: Pubkey },
+    /// Fire every trigger the NAV has reached, earning the crank tip for each
+    Crank {
+        #[arg(long)]
+        etf: Option<Pubkey>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum LeaderboardAction {
+    /// Print an epoch's ranking (defaults to the last ended epoch)
+    Show {
+        #[arg(long)]
+        epoch: Option<u64>,
+    },
+    /// Submit returns of an ended epoch, creating its leaderboard if needed;
+    /// without --etf, every ETF with a NAV history that would rank
+    Submit {
+        #[arg(long)]
+        epoch: Option<u64>,
+        #[arg(long)]
+        etf: Option<Pubkey>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum RewardsAction {
+    /// Print the treasury's books, and an epoch's rewards with --epoch
+    Show {
+        #[arg(long)]
+        epoch: Option<u64>,
+    },
+    /// Add SOL to the rewards pool (also books collected dev fees)
+    Fund { sol: String },
+    /// Allocate the pool to an epoch's leaderboard (defaults to the last
+    /// epoch whose submissions have closed)
+    Finalize {
+        #[arg(long)]
+        epoch: Option<u64>,
+    },
+    /// Claim your rewards of an epoch
+    Claim {
+        #[arg(long)]
+        epoch: u64,
+    },
+    /// Pay the dev share of the treasury to the dev wallet
+    WithdrawDev,
+    /// Create the treasury or set its rewards share (governance only)
+    Configure {
+        /// Share of dev fees for the rewards pool, in basis points
+        #[arg(long)]
+        rewards_share_bps: u16,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum QuoteSide {
+    /// Quote buying with an amount of SOL
+    Buy {
+        /// Amount of SOL to invest (e.g. 0.5)
+        sol: String,
+        /// ETF to quote against (uses its basket size for allocations)
+        #[arg(long)]
+        etf: Option<Pubkey>,
+        #[arg(long, value_delimiter = ',')]
+        percentages: Option<Vec<u8>>,
+    },
+    /// Quote selling a number of shares
+    Sell {
+        shares: u64,
+        /// ETF to quote against (uses its NAV)
+        #[arg(long)]
+        etf: Option<Pubkey>,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Category {
+    Meme,
+    Ai,
+    Gaming,
+    Defi,
+    Community,
+    Other,
+}
+
+impl From<Category> for EtfCategory {
+    fn from(category: Category) -> Self {
+        match category {
+            Category::Meme => EtfCategory::Meme,
+            Category::Ai => EtfCategory::AI,
+            Category::Gaming => EtfCategory::Gaming,
+            Category::Defi => EtfCategory::DeFi,
+            Category::Community => EtfCategory::Community,
+            Category::Other => EtfCategory::Other,
+        }
+    }
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    commands::run(cli)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_cli_definition() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_parse_buy_with_percentages() {
+        let etf = Pubkey::new_unique();
+        let cli = Cli::try_parse_from([
+            "memeetf",
+            "-u",
+            "localnet",
+            "buy",
+            &etf.to_string(),
+            "--sol",
+            "0.5",
+            "--percentages",
+            "60,40",
+        ])
+        .unwrap();
+        match cli.command {
+            Command::Buy {
+                etf: parsed,
+                sol,
+                percentages,
+                ..
+            } => {
+                assert_eq!(parsed, etf);
+                assert_eq!(sol, "0.5");
+                assert_eq!(percentages, Some(vec![60, 40]));
+            }
+            _ => panic!("expected buy"),
+        }
+    }
+
+    #[test]
+    fn test_sell_requires_shares_or_all() {
+        let etf = Pubkey::new_unique().to_string();
+        assert!(Cli::try_parse_from(["memeetf", "sell", &etf]).is_err());
+        assert!(Cli::try_parse_from(["memeetf", "sell", &etf, "--all"]).is_ok());
+        assert!(Cli::try_parse_from(["memeetf", "sell", &etf, "--all", "--shares", "1"]).is_err());
+    }
+
+    #[test]
+    fn test_wrap_requires_shares() {
+        let etf = Pubkey::new_unique().to_string();
+        assert!(Cli::try_parse_from(["memeetf", "wrap", &etf]).is_err());
+        match Cli::try_parse_from(["memeetf", "unwrap", &etf, "--shares", "5"]).unwrap().command {
+            Command::Unwrap { shares, .. } => assert_eq!(shares, 5),
+            _ => panic!("expected unwrap"),
+        }
+    }
+
+    #[test]
+    fn test_set_gate_takes_one_gate() {
+        let etf = Pubkey::new_unique().to_string();
+        let mint = Pubkey::new_unique().to_string();
+        let parse = |args: &[&str]| Cli::try_parse_from([&["memeetf", "set-gate", &etf], args].concat());
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["--open"]).is_ok());
+        assert!(parse(&["--allowlist", "wallets.txt"]).is_ok());
+        assert!(parse(&["--mint", &mint, "--min-amount", "1"]).is_ok());
+        assert!(parse(&["--mint", &mint]).is_err());
+        assert!(parse(&["--open", "--allowlist", "wallets.txt"]).is_err());
+        assert!(parse(&["--allowlist", "wallets.txt", "--mint", &mint, "--min-amount", "1"]).is_err());
+    }
+
+    #[test]
+    fn test_set_limits_are_optional() {
+        let etf = Pubkey::new_unique().to_string();
+        match Cli::try_parse_from(["memeetf", "set-limits", &etf, "--max-buy", "5"]).unwrap().command {
+            Command::SetLimits { min_buy, max_buy, max_supply, .. } => {
+                assert_eq!(min_buy, None);
+                assert_eq!(max_buy.as_deref(), Some("5"));
+                assert_eq!(max_supply, None);
+            }
+            _ => panic!("expected set-limits"),
+        }
+    }
+
+    #[test]
+    fn test_set_hold_fee_defaults_to_zero() {
+        let etf = Pubkey::new_unique().to_string();
+        assert!(Cli::try_parse_from(["memeetf", "set-hold", &etf]).is_err());
+        match Cli::try_parse_from(["memeetf", "set-hold", &etf, "--seconds", "86400"]).unwrap().command {
+            Command::SetHold { seconds, early_exit_fee_bps, .. } => assert_eq!((seconds, early_exit_fee_bps), (86400, 0)),
+            _ => panic!("expected set-hold"),
+        }
+    }
+
+    #[test]
+    fn test_dca_create_defaults_to_daily() {
+        let etf = Pubkey::new_unique().to_string();
+        let args = ["memeetf", "dca", "create", &etf, "--sol", "0.1", "--deposit", "3"];
+        match Cli::try_parse_from(args).unwrap().command {
+            Command::Dca {
+                action: DcaAction::Create { interval, percentages, .. },
+            } => {
+                assert_eq!(interval, 86_400);
+                assert_eq!(percentages, None);
+            }
+            _ => panic!("expected dca create"),
+        }
+        assert!(Cli::try_parse_from(["memeetf", "dca", "create", &etf, "--sol", "0.1"]).is_err());
+        assert!(Cli::try_parse_from(["memeetf", "dca", "crank"]).is_ok());
+    }
+
+    #[test]
+    fn test_order_place_takes_one_side() {
+        let etf = Pubkey::new_unique().to_string();
+        let args = ["memeetf", "order", "place", &etf, "--sell", "500", "--nav", "1.2"];
+        match Cli::try_parse_from(args).unwrap().command {
+            Command::Order {
+                action: OrderAction::Place { buy, sell, nav, id, .. },
+            } => {
+                assert_eq!(buy, None);
+                assert_eq!(sell, Some(500));
+                assert_eq!(nav, "1.2");
+                assert_eq!(id, None);
+            }
+            _ => panic!("expected order place"),
+        }
+        assert!(Cli::try_parse_from(["memeetf", "order", "place", &etf, "--nav", "1"]).is_err());
+        let both = ["memeetf", "order", "place", &etf, "--buy", "1", "--sell", "5", "--nav", "1"];
+        assert!(Cli::try_parse_from(both).is_err());
+    }
+
+    #[test]
+    fn test_exit_set_needs_a_threshold() {
+        let etf = Pubkey::new_unique().to_string();
+        let args = ["memeetf", "exit", "set", &etf, "--take-profit", "2"];
+        match Cli::try_parse_from(args).unwrap().command {
+            Command::Exit {
+                action: ExitAction::Set { stop_loss, take_profit, shares, .. },
+            } => {
+                assert_eq!(stop_loss, None);
+                assert_eq!(take_profit.as_deref(), Some("2"));
+                assert_eq!(shares, None);
+            }
+            _ => panic!("expected exit set"),
+        }
+        assert!(Cli::try_parse_from(["memeetf", "exit", "set", &etf]).is_err());
+    }
+
+    #[test]
+    fn test_parse_nav_twap() {
+        let etf = Pubkey::new_unique().to_string();
+        let cli = Cli::try_parse_from(["memeetf", "nav", &etf, "--twap", "86400"]).unwrap();
+        match cli.command {
+            Command::Nav { twap, .. } => assert_eq!(twap, Some(86_400)),
+            _ => panic!("expected nav"),
+        }
+        assert!(Cli::try_parse_from(["memeetf", "snapshot"]).is_ok());
+    }
+
+    #[test]
+    fn test_parse_leaderboard_submit() {
+        let etf = Pubkey::new_unique();
+        let cli = Cli::try_parse_from(["memeetf", "leaderboard", "submit", "--epoch", "7", "--etf", &etf.to_string()])
+            .unwrap();
+        match cli.command {
+            Command::Leaderboard {
+                action: LeaderboardAction::Submit { epoch, etf: target },
+            } => {
+                assert_eq!(epoch, Some(7));
+                assert_eq!(target, Some(etf));
+            }
+            _ => panic!("expected leaderboard submit"),
+        }
+    }
+
+    #[test]
+    fn test_parse_rewards() {
+        let cli = Cli::try_parse_from(["memeetf", "rewards", "configure", "--rewards-share-bps", "2000"]).unwrap();
+        match cli.command {
+            Command::Rewards {
+                action: RewardsAction::Configure { rewards_share_bps },
+            } => assert_eq!(rewards_share_bps, 2_000),
+            _ => panic!("expected rewards configure"),
+        }
+        assert!(Cli::try_parse_from(["memeetf", "rewards", "claim"]).is_err());
+        assert!(Cli::try_parse_from(["memeetf", "rewards", "withdraw-dev"]).is_ok());
+    }
+
+    #[test]
+    fn test_parse_fork() {
+        let parent = Pubkey::new_unique();
+        let removed = Pubkey::new_unique();
+        let cli = Cli::try_parse_from([
+            "memeetf",
+            "fork",
+            &parent.to_string(),
+            "--remove",
+            &removed.to_string(),
+            "--parent-fee-bps",
+            "500",
+            "--name",
+            "Cat Coins",
+            "--symbol",
+            "CATS",
+        ])
+        .unwrap();
+        match cli.command {
+            Command::Fork {
+                parent: forked,
+                remove,
+                add,
+                parent_fee_bps,
+                category,
+                ..
+            } => {
+                assert_eq!(forked, parent);
+                assert_eq!(remove, vec![removed]);
+                assert!(add.is_empty());
+                assert_eq!(parent_fee_bps, 500);
+                assert!(category.is_none());
+            }
+            _ => panic!("expected fork"),
+        }
+    }
+
+    #[test]
+    fn test_set_keeper_requires_keeper_or_none() {
+        let etf = Pubkey::new_unique().to_string();
+        let keeper = Pubkey::new_unique().to_string();
+        assert!(Cli::try_parse_from(["memeetf", "set-keeper", &etf]).is_err());
+        assert!(Cli::try_parse_from(["memeetf", "set-keeper", &etf, "--none"]).is_ok());
+        assert!(Cli::try_parse_from(["memeetf", "set-keeper", &etf, "--keeper", &keeper]).is_ok());
+        assert!(Cli::try_parse_from(["memeetf", "set-keeper", &etf, "--keeper", &keeper, "--none"]).is_err());
+    }
+
+    #[test]
+    fn test_set_keeper_refund_timeout() {
+        let etf = Pubkey::new_unique().to_string();
+        let parse = |args: &[&str]| match Cli::try_parse_from(args).unwrap().command {
+            Command::SetKeeper { refund_timeout, .. } => refund_timeout,
+            _ => panic!("expected set-keeper"),
+        };
+        assert_eq!(parse(&["memeetf", "set-keeper", &etf, "--none"]), mtf_etf::DEFAULT_REFUND_TIMEOUT_SECONDS);
+        assert_eq!(parse(&["memeetf", "set-keeper", &etf, "--none", "--refund-timeout", "600"]), 600);
+    }
+}
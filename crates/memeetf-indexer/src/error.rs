@@ -0,0 +1,17 @@
+use mtf_etf_client::ClientError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum IndexerError {
+    #[error("Database error: {0}")]
+    Database(#[from] rusqlite::Error),
+    #[error(transparent)]
+    Client(#[from] ClientError),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid transaction: {0}")]
+    InvalidTransaction(String),
+    #[error("Amount {0} does not fit in the store")]
+    AmountOverflow(u64),
+}
+
+pub type Result<T> = std::result::Result<T, IndexerError>;
@@ -0,0 +1,415 @@
+// Anchor event decoding. `emit!` writes `Program data: <base64>` log lines
+// holding the event discriminator followed by the borsh-encoded event.
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AnchorDeserialize, Discriminator};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use mtf_etf::{
+    BuyLimitsUpdatedEvent, CreatorRewardClaimedEvent, DcaExecutedEvent, DcaPlanCancelledEvent, DcaPlanCreatedEvent,
+    DcaPlanFundedEvent, DevFeesWithdrawnEvent, ETFBoughtEvent, ETFClosedEvent, ETFCreatedEvent, ETFForkedEvent,
+    ETFMigratedEvent, ETFSoldEvent, EpochFinalizedEvent, EpochReturnSubmittedEvent, ExitTriggerCancelledEvent,
+    ExitTriggerSetEvent, ExitTriggeredEvent, FeeTransferEvent, GateUpdatedEvent, HoldPeriodUpdatedEvent,
+    KeeperUpdatedEvent, LegacyPositionRestoredEvent, LimitOrderCancelledEvent, LimitOrderFilledEvent,
+    LimitOrderPlacedEvent, LiquidationClaimEvent, LiquidationStartedEvent, MetadataUpdatedEvent, NavSnapshotEvent,
+    ParentFeesClaimedEvent, PriceUpdatedEvent, PurchasePendingEvent, PurchaseRefundedEvent, PurchaseSettledEvent,
+    RewardsFundedEvent, ShareMintCreatedEvent, SharesUnwrappedEvent, SharesWrappedEvent, TokenPurchaseEvent,
+    TokenRedeemedEvent, TokenSaleEvent, TreasuryConfiguredEvent, VaultLiquidatedEvent,
+};
+
+pub enum EtfEvent {
+    Created(ETFCreatedEvent),
+    MetadataUpdated(MetadataUpdatedEvent),
+    TokenPurchase(TokenPurchaseEvent),
+    TokenSale(TokenSaleEvent),
+    Bought(ETFBoughtEvent),
+    Sold(ETFSoldEvent),
+    PurchasePending(PurchasePendingEvent),
+    PurchaseSettled(PurchaseSettledEvent),
+    PurchaseRefunded(PurchaseRefundedEvent),
+    KeeperUpdated(KeeperUpdatedEvent),
+    SharesWrapped(SharesWrappedEvent),
+    SharesUnwrapped(SharesUnwrappedEvent),
+    FeeTransfer(FeeTransferEvent),
+    LiquidationStarted(LiquidationStartedEvent),
+    VaultLiquidated(VaultLiquidatedEvent),
+    LiquidationClaim(LiquidationClaimEvent),
+    Closed(ETFClosedEvent),
+    TokenRedeemed(TokenRedeemedEvent),
+    GateUpdated(GateUpdatedEvent),
+    BuyLimitsUpdated(BuyLimitsUpdatedEvent),
+    HoldPeriodUpdated(HoldPeriodUpdatedEvent),
+    DcaPlanCreated(DcaPlanCreatedEvent),
+    DcaPlanFunded(DcaPlanFundedEvent),
+    DcaExecuted(DcaExecutedEvent),
+    DcaPlanCancelled(DcaPlanCancelledEvent),
+    LimitOrderPlaced(LimitOrderPlacedEvent),
+    LimitOrderFilled(LimitOrderFilledEvent),
+    LimitOrderCancelled(LimitOrderCancelledEvent),
+    ExitTriggerSet(ExitTriggerSetEvent),
+    ExitTriggered(ExitTriggeredEvent),
+    ExitTriggerCancelled(ExitTriggerCancelledEvent),
+    NavSnapshot(NavSnapshotEvent),
+    EpochReturnSubmitted(EpochReturnSubmittedEvent),
+    PriceUpdated(PriceUpdatedEvent),
+    TreasuryConfigured(TreasuryConfiguredEvent),
+    RewardsFunded(RewardsFundedEvent),
+    DevFeesWithdrawn(DevFeesWithdrawnEvent),
+    EpochFinalized(EpochFinalizedEvent),
+    CreatorRewardClaimed(CreatorRewardClaimedEvent),
+    Forked(ETFForkedEvent),
+    Migrated(ETFMigratedEvent),
+    LegacyPositionRestored(LegacyPositionRestoredEvent),
+    ParentFeesClaimed(ParentFeesClaimedEvent),
+    ShareMintCreated(ShareMintCreatedEvent),
+}
+
+fn decode_as<T: AnchorDeserialize + Discriminator>(data: &[u8]) -> Option<T> {
+    let mut payload = data.strip_prefix(T::DISCRIMINATOR)?;
+    T::deserialize(&mut payload).ok()
+}
+
+impl EtfEvent {
+    // None for payloads that are not mtf_etf events
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        decode_as(data)
+            .map(Self::Created)
+            .or_else(|| decode_as(data).map(Self::MetadataUpdated))
+            .or_else(|| decode_as(data).map(Self::TokenPurchase))
+            .or_else(|| decode_as(data).map(Self::TokenSale))
+            .or_else(|| decode_as(data).map(Self::Bought))
+            .or_else(|| decode_as(data).map(Self::Sold))
+            .or_else(|| decode_as(data).map(Self::PurchasePending))
+            .or_else(|| decode_as(data).map(Self::PurchaseSettled))
+            .or_else(|| decode_as(data).map(Self::PurchaseRefunded))
+            .or_else(|| decode_as(data).map(Self::KeeperUpdated))
+            .or_else(|| decode_as(data).map(Self::SharesWrapped))
+            .or_else(|| decode_as(data).map(Self::SharesUnwrapped))
+            .or_else(|| decode_as(data).map(Self::FeeTransfer))
+            .or_else(|| decode_as(data).map(Self::LiquidationStarted))
+            .or_else(|| decode_as(data).map(Self::VaultLiquidated))
+            .or_else(|| decode_as(data).map(Self::LiquidationClaim))
+            .or_else(|| decode_as(data).map(Self::Closed))
+            .or_else(|| decode_as(data).map(Self::TokenRedeemed))
+            .or_else(|| decode_as(data).map(Self::GateUpdated))
+            .or_else(|| decode_as(data).map(Self::BuyLimitsUpdated))
+            .or_else(|| decode_as(data).map(Self::HoldPeriodUpdated))
+            .or_else(|| decode_as(data).map(Self::DcaPlanCreated))
+            .or_else(|| decode_as(data).map(Self::DcaPlanFunded))
+            .or_else(|| decode_as(data).map(Self::DcaExecuted))
+            .or_else(|| decode_as(data).map(Self::DcaPlanCancelled))
+            .or_else(|| decode_as(data).map(Self::LimitOrderPlaced))
+            .or_else(|| decode_as(data).map(Self::LimitOrderFilled))
+            .or_else(|| decode_as(data).map(Self::LimitOrderCancelled))
+            .or_else(|| decode_as(data).map(Self::ExitTriggerSet))
+            .or_else(|| decode_as(data).map(Self::ExitTriggered))
+            .or_else(|| decode_as(data).map(Self::ExitTriggerCancelled))
+            .or_else(|| decode_as(data).map(Self::NavSnapshot))
+            .or_else(|| decode_as(data).map(Self::EpochReturnSubmitted))
+            .or_else(|| decode_as(data).map(Self::PriceUpdated))
+            .or_else(|| decode_as(data).map(Self::TreasuryConfigured))
+            .or_else(|| decode_as(data).map(Self::RewardsFunded))
+            .or_else(|| decode_as(data).map(Self::DevFeesWithdrawn))
+            .or_else(|| decode_as(data).map(Self::EpochFinalized))
+            .or_else(|| decode_as(data).map(Self::CreatorRewardClaimed))
+            .or_else(|| decode_as(data).map(Self::Forked))
+            .or_else(|| decode_as(data).map(Self::Migrated))
+            .or_else(|| decode_as(data).map(Self::LegacyPositionRestored))
+            .or_else(|| decode_as(data).map(Self::ParentFeesClaimed))
+            .or_else(|| decode_as(data).map(Self::ShareMintCreated))
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Created(_) => "ETFCreatedEvent",
+            Self::MetadataUpdated(_) => "MetadataUpdatedEvent",
+            Self::TokenPurchase(_) => "TokenPurchaseEvent",
+            Self::TokenSale(_) => "TokenSaleEvent",
+            Self::Bought(_) => "ETFBoughtEvent",
+            Self::Sold(_) => "ETFSoldEvent",
+            Self::PurchasePending(_) => "PurchasePendingEvent",
+            Self::PurchaseSettled(_) => "PurchaseSettledEvent",
+            Self::PurchaseRefunded(_) => "PurchaseRefundedEvent",
+            Self::KeeperUpdated(_) => "KeeperUpdatedEvent",
+            Self::SharesWrapped(_) => "SharesWrappedEvent",
+            Self::SharesUnwrapped(_) => "SharesUnwrappedEvent",
+            Self::FeeTransfer(_) => "FeeTransferEvent",
+            Self::LiquidationStarted(_) => "LiquidationStartedEvent",
+            Self::VaultLiquidated(_) => "VaultLiquidatedEvent",
+            Self::LiquidationClaim(_) => "LiquidationClaimEvent",
+            Self::Closed(_) => "ETFClosedEvent",
+            Self::TokenRedeemed(_) => "TokenRedeemedEvent",
+            Self::GateUpdated(_) => "GateUpdatedEvent",
+            Self::BuyLimitsUpdated(_) => "BuyLimitsUpdatedEvent",
+            Self::HoldPeriodUpdated(_) => "HoldPeriodUpdatedEvent",
+            Self::DcaPlanCreated(_) => "DcaPlanCreatedEvent",
+            Self::DcaPlanFunded(_) => "DcaPlanFundedEvent",
+            Self::DcaExecuted(_) => "DcaExecutedEvent",
+            Self::DcaPlanCancelled(_) => "DcaPlanCancelledEvent",
+            Self::LimitOrderPlaced(_) => "LimitOrderPlacedEvent",
+            Self::LimitOrderFilled(_) => "LimitOrderFilledEvent",
+            Self::LimitOrderCancelled(_) => "LimitOrderCancelledEvent",
+            Self::ExitTriggerSet(_) => "ExitTriggerSetEvent",
+            Self::ExitTriggered(_) => "ExitTriggeredEvent",
+            Self::ExitTriggerCancelled(_) => "ExitTriggerCancelledEvent",
+            Self::NavSnapshot(_) => "NavSnapshotEvent",
+            Self::EpochReturnSubmitted(_) => "EpochReturnSubmittedEvent",
+            Self::PriceUpdated(_) => "PriceUpdatedEvent",
+            Self::TreasuryConfigured(_) => "TreasuryConfiguredEvent",
+            Self::RewardsFunded(_) => "RewardsFundedEvent",
+            Self::DevFeesWithdrawn(_) => "DevFeesWithdrawnEvent",
+            Self::EpochFinalized(_) => "EpochFinalizedEvent",
+            Self::CreatorRewardClaimed(_) => "CreatorRewardClaimedEvent",
+            Self::Forked(_) => "ETFForkedEvent",
+            Self::Migrated(_) => "ETFMigratedEvent",
+            Self::LegacyPositionRestored(_) => "LegacyPositionRestoredEvent",
+            Self::ParentFeesClaimed(_) => "ParentFeesClaimedEvent",
+            Self::ShareMintCreated(_) => "ShareMintCreatedEvent",
+        }
+    }
+
+    // None for protocol-wide events (prices, treasury, epoch finalization)
+    pub fn etf(&self) -> Option<Pubkey> {
+        match self {
+            Self::Created(e) => Some(e.etf_address),
+            Self::MetadataUpdated(e) => Some(e.etf_address),
+            Self::TokenPurchase(e) => Some(e.etf_address),
+            Self::TokenSale(e) => Some(e.etf_address),
+            Self::Bought(e) => Some(e.etf_address),
+            Self::Sold(e) => Some(e.etf_address),
+            Self::PurchasePending(e) => Some(e.etf_address),
+            Self::PurchaseSettled(e) => Some(e.etf_address),
+            Self::PurchaseRefunded(e) => Some(e.etf_address),
+            Self::KeeperUpdated(e) => Some(e.etf_address),
+            Self::SharesWrapped(e) => Some(e.etf_address),
+            Self::SharesUnwrapped(e) => Some(e.etf_address),
+            Self::FeeTransfer(e) => Some(e.etf_address),
+            Self::LiquidationStarted(e) => Some(e.etf_address),
+            Self::VaultLiquidated(e) => Some(e.etf_address),
+            Self::LiquidationClaim(e) => Some(e.etf_address),
+            Self::Closed(e) => Some(e.etf_address),
+            Self::TokenRedeemed(e) => Some(e.etf_address),
+            Self::GateUpdated(e) => Some(e.etf_address),
+            Self::BuyLimitsUpdated(e) => Some(e.etf_address),
+            Self::HoldPeriodUpdated(e) => Some(e.etf_address),
+            Self::DcaPlanCreated(e) => Some(e.etf_address),
+            Self::DcaPlanFunded(e) => Some(e.etf_address),
+            Self::DcaExecuted(e) => Some(e.etf_address),
+            Self::DcaPlanCancelled(e) => Some(e.etf_address),
+            Self::LimitOrderPlaced(e) => Some(e.etf_address),
+            Self::LimitOrderFilled(e) => Some(e.etf_address),
+            Self::LimitOrderCancelled(e) => Some(e.etf_address),
+            Self::ExitTriggerSet(e) => Some(e.etf_address),
+            Self::ExitTriggered(e) => Some(e.etf_address),
+            Self::ExitTriggerCancelled(e) => Some(e.etf_address),
+            Self::NavSnapshot(e) => Some(e.etf_address),
+            Self::EpochReturnSubmitted(e) => Some(e.etf_address),
+            Self::CreatorRewardClaimed(e) => Some(e.etf_address),
+            Self::Forked(e) => Some(e.etf_address),
+            Self::Migrated(e) => Some(e.etf_address),
+            Self::LegacyPositionRestored(e) => Some(e.etf_address),
+            Self::ParentFeesClaimed(e) => Some(e.etf_address),
+            Self::ShareMintCreated(e) => Some(e.etf_address),
+            Self::PriceUpdated(_)
+            | Self::TreasuryConfigured(_)
+            | Self::RewardsFunded(_)
+            | Self::DevFeesWithdrawn(_)
+            | Self::EpochFinalized(_) => None,
+        }
+    }
+
+    // (ETF, trade sequence number), for buy/sell summaries
+    pub fn trade_sequence(&self) -> Option<(Pubkey, u64)> {
+        match self {
+            Self::Bought(e) => Some((e.etf_address, e.sequence)),
+            Self::Sold(e) => Some((e.etf_address, e.sequence)),
+            _ => None,
+        }
+    }
+
+    pub fn timestamp(&self) -> i64 {
+        match self {
+            Self::Created(e) => e.timestamp,
+            Self::MetadataUpdated(e) => e.timestamp,
+            Self::TokenPurchase(e) => e.timestamp,
+            Self::TokenSale(e) => e.timestamp,
+            Self::Bought(e) => e.timestamp,
+            Self::Sold(e) => e.timestamp,
+            Self::PurchasePending(e) => e.timestamp,
+            Self::PurchaseSettled(e) => e.timestamp,
+            Self::PurchaseRefunded(e) => e.timestamp,
+            Self::KeeperUpdated(e) => e.timestamp,
+            Self::SharesWrapped(e) => e.timestamp,
+            Self::SharesUnwrapped(e) => e.timestamp,
+            Self::FeeTransfer(e) => e.timestamp,
+            Self::LiquidationStarted(e) => e.timestamp,
+            Self::VaultLiquidated(e) => e.timestamp,
+            Self::LiquidationClaim(e) => e.timestamp,
+            Self::Closed(e) => e.timestamp,
+            Self::TokenRedeemed(e) => e.timestamp,
+            Self::GateUpdated(e) => e.timestamp,
+            Self::BuyLimitsUpdated(e) => e.timestamp,
+            Self::HoldPeriodUpdated(e) => e.timestamp,
+            Self::DcaPlanCreated(e) => e.timestamp,
+            Self::DcaPlanFunded(e) => e.timestamp,
+            Self::DcaExecuted(e) => e.timestamp,
+            Self::DcaPlanCancelled(e) => e.timestamp,
+            Self::LimitOrderPlaced(e) => e.timestamp,
+            Self::LimitOrderFilled(e) => e.timestamp,
+            Self::LimitOrderCancelled(e) => e.timestamp,
+            Self::ExitTriggerSet(e) => e.timestamp,
+            Self::ExitTriggered(e) => e.timestamp,
+            Self::ExitTriggerCancelled(e) => e.timestamp,
+            Self::NavSnapshot(e) => e.timestamp,
+            Self::EpochReturnSubmitted(e) => e.timestamp,
+            Self::PriceUpdated(e) => e.timestamp,
+            Self::TreasuryConfigured(e) => e.timestamp,
+            Self::RewardsFunded(e) => e.timestamp,
+            Self::DevFeesWithdrawn(e) => e.timestamp,
+            Self::EpochFinalized(e) => e.timestamp,
+            Self::CreatorRewardClaimed(e) => e.timestamp,
+            Self::Forked(e) => e.timestamp,
+            Self::Migrated(e) => e.timestamp,
+            Self::LegacyPositionRestored(e) => e.timestamp,
+            Self::ParentFeesClaimed(e) => e.timestamp,
+            Self::ShareMintCreated(e) => e.timestamp,
+        }
+    }
+}
+
+// Raw event payloads logged by `program_id` itself, in order. Data logged by
+// programs it invokes (or that invoke it) is attributed to them instead.
+pub fn program_data(logs: &[String], program_id: &Pubkey) -> Vec<Vec<u8>> {
+    let program_id = program_id.to_string();
+    let mut stack: Vec<&str> = Vec::new();
+    let mut payloads = Vec::new();
+
+    for line in logs {
+        let Some(rest) = line.strip_prefix("Program ") else {
+            continue;
+        };
+        if let Some(fields) = rest.strip_prefix("data: ") {
+            if stack.last() == Some(&program_id.as_str()) {
+                let decoded: Option<Vec<Vec<u8>>> =
+                    fields.split_whitespace().map(|field| BASE64.decode(field).ok()).collect();
+                if let Some(decoded) = decoded {
+                    payloads.push(decoded.concat());
+                }
+            }
+            continue;
+        }
+
+        let mut words = rest.split_whitespace();
+        let (Some(program), Some(verb)) = (words.next(), words.next()) else {
+            continue;
+        };
+        // Skip "Program log:", "Program return:" and friends
+        if program.ends_with(':') {
+            continue;
+        }
+        match verb {
+            "invoke" => stack.push(program),
+            "success" | "failed:" => {
+                stack.pop();
+            }
+            _ => {}
+        }
+    }
+
+    payloads
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::AnchorSerialize;
+
+    fn encode<T: AnchorSerialize + Discriminator>(event: &T) -> Vec<u8> {
+        let mut data = T::DISCRIMINATOR.to_vec();
+        event.serialize(&mut data).unwrap();
+        data
+    }
+
+    fn created(etf: Pubkey) -> ETFCreatedEvent {
+        ETFCreatedEvent {
+            etf_address: etf,
+            lister: Pubkey::new_unique(),
+            token_count: 3,
+            name: "Dog Coins".to_string(),
+            symbol: "DOGS".to_string(),
+            uri: "https://example.com/dogs.json".to_string(),
+            category: mtf_etf::EtfCategory::Meme,
+            timestamp: 42,
+        }
+    }
+
+    #[test]
+    fn test_decode_event() {
+        let etf = Pubkey::new_unique();
+        let event = EtfEvent::decode(&encode(&created(etf))).unwrap();
+        assert_eq!(event.name(), "ETFCreatedEvent");
+        assert_eq!(event.etf(), Some(etf));
+        assert_eq!(event.timestamp(), 42);
+    }
+
+    // Every #[event] in the program decodes to the variant of its name.
+    // Borsh reads any all-zero payload long enough as a valid event.
+    #[test]
+    fn test_every_program_event_is_decoded() {
+        let source = include_str!("../../../programs/mtf-etf/src/lib.rs");
+        let names: Vec<&str> = source
+            .split("#[event]\npub struct ")
+            .skip(1)
+            .map(|rest| rest.split_whitespace().next().unwrap())
+            .collect();
+        assert!(names.len() > 40);
+
+        for name in names {
+            let discriminator = anchor_lang::solana_program::hash::hash(format!("event:{name}").as_bytes());
+            let mut data = discriminator.to_bytes()[..8].to_vec();
+            data.resize(8 + 1_024, 0);
+            let event = EtfEvent::decode(&data).unwrap_or_else(|| panic!("{name} is not decoded"));
+            assert_eq!(event.name(), name);
+        }
+    }
+
+    #[test]
+    fn test_decode_rejects_unknown_payload() {
+        assert!(EtfEvent::decode(&[1, 2, 3, 4, 5, 6, 7, 8, 9]).is_none());
+        assert!(EtfEvent::decode(&[]).is_none());
+    }
+
+    #[test]
+    fn test_program_data_follows_invoke_stack() {
+        let program = mtf_etf::ID;
+        let other = Pubkey::new_unique();
+        let logs: Vec<String> = [
+            format!("Program {other} invoke [1]"),
+            "Program data: AQ==".to_string(),
+            format!("Program {program} invoke [2]"),
+            "Program log: Instruction: BuyEtf".to_string(),
+            "Program 11111111111111111111111111111111 invoke [3]".to_string(),
+            "Program 11111111111111111111111111111111 success".to_string(),
+            "Program data: Ag== Aw==".to_string(),
+            format!("Program {program} consumed 1000 of 200000 compute units"),
+            format!("Program {program} success"),
+            "Program data: BA==".to_string(),
+            format!("Program {other} success"),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(program_data(&logs, &program), vec![vec![2, 3]]);
+        assert_eq!(program_data(&logs, &other), vec![vec![1], vec![4]]);
+    }
+
+    #[test]
+    fn test_program_data_skips_invalid_base64() {
+        let program = mtf_etf::ID;
+        let logs = vec![
+            format!("Program {program} invoke [1]"),
+            "Program data: not-base64!".to_string(),
+            format!("Program {program} failed: custom program error: 0x1770"),
+        ];
+        assert!(program_data(&logs, &program).is_empty());
+    }
+}
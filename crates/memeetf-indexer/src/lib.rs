@@ -0,0 +1,72 @@
+// Event indexer for the mtf_etf program.
+//
+// Decodes the Anchor events the program emits from transaction logs and folds
+// them into a SQLite materialized view of ETFs, positions and fee totals. The
+// raw events are stored alongside the views, so everything from a given slot
+// onwards can be rolled back and replayed, and views can be rebuilt at any
+// time without touching the chain.
+
+pub mod error;
+pub mod events;
+pub mod source;
+pub mod store;
+
+use anchor_lang::prelude::Pubkey;
+
+pub use error::{IndexerError, Result};
+pub use events::EtfEvent;
+pub use source::TransactionRecord;
+pub use store::Store;
+
+pub struct Indexer {
+    store: Store,
+    program_id: Pubkey,
+}
+
+impl Indexer {
+    pub fn new(store: Store) -> Self {
+        Self::with_program_id(store, mtf_etf::ID)
+    }
+
+    pub fn with_program_id(store: Store, program_id: Pubkey) -> Self {
+        Self { store, program_id }
+    }
+
+    pub fn store(&self) -> &Store {
+        &self.store
+    }
+
+    pub fn store_mut(&mut self) -> &mut Store {
+        &mut self.store
+    }
+
+    // Index one transaction; returns the number of events recorded. Failed
+    // and already-indexed transactions record nothing.
+    pub fn ingest(&mut self, record: &TransactionRecord) -> Result<usize> {
+        if record.failed || self.store.contains(&record.signature)? {
+            return Ok(0);
+        }
+
+        let events: Vec<(Vec<u8>, EtfEvent)> = events::program_data(&record.logs, &self.program_id)
+            .into_iter()
+            .filter_map(|data| EtfEvent::decode(&data).map(|event| (data, event)))
+            .collect();
+        self.store.record_transaction(record, &events)?;
+        Ok(events.len())
+    }
+
+    pub fn ingest_all<'a>(&mut self, records: impl IntoIterator<Item = &'a TransactionRecord>) -> Result<usize> {
+        let mut indexed = 0;
+        for record in records {
+            indexed += self.ingest(record)?;
+        }
+        Ok(indexed)
+    }
+
+    // Drop everything indexed at or after `slot`, then re-index the records
+    // from that slot on. Returns the number of events recorded.
+    pub fn replay_from(&mut self, slot: u64, records: &[TransactionRecord]) -> Result<usize> {
+        self.store.rollback_from(slot)?;
+        self.ingest_all(records.iter().filter(|record| record.slot >= slot))
+    }
+}
@@ -0,0 +1,154 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use anchor_lang::prelude::Pubkey;
+use anyhow::{anyhow, Result};
+use clap::{Parser, Subcommand};
+use memeetf_indexer::{source, Indexer, Store};
+use mtf_etf_client::rpc::LOCALNET_RPC_URL;
+use mtf_etf_client::RpcClient;
+
+#[derive(Parser)]
+#[command(name = "memeetf-indexer", version, about = "Rebuild MemeETF state from program events")]
+struct Cli {
+    /// SQLite database file
+    #[arg(long, global = true, env = "MEMEETF_INDEX_DB", default_value = "memeetf-index.sqlite")]
+    db: PathBuf,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Index getTransaction JSON fixture files or directories
+    Ingest {
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+    },
+    /// Index new transactions from an RPC node
+    Sync {
+        #[arg(short, long, default_value = LOCALNET_RPC_URL)]
+        url: String,
+        /// Start slot (default: after the last indexed slot)
+        #[arg(long)]
+        from_slot: Option<u64>,
+    },
+    /// Roll back to a slot and re-index from there (from fixtures, or RPC if none given)
+    Replay {
+        #[arg(long)]
+        from_slot: u64,
+        #[arg(short, long, default_value = LOCALNET_RPC_URL)]
+        url: String,
+        paths: Vec<PathBuf>,
+    },
+    /// List indexed ETFs
+    Etfs,
+    /// List positions in an ETF
+    Positions { etf: String },
+    /// Fee totals, optionally for one ETF
+    Fees {
+        #[arg(long)]
+        etf: Option<String>,
+    },
+    /// Missing or repeated trade sequence numbers, optionally for one ETF
+    Gaps {
+        #[arg(long)]
+        etf: Option<String>,
+    },
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let mut indexer = Indexer::new(Store::open(&cli.db)?);
+
+    match cli.command {
+        Command::Ingest { paths } => {
+            let records = source::load_fixtures(&paths)?;
+            let indexed = indexer.ingest_all(&records)?;
+            report(&indexer, records.len(), indexed)?;
+        }
+        Command::Sync { url, from_slot } => {
+            let from_slot = match from_slot {
+                Some(slot) => slot,
+                None => indexer.store().last_slot()?.map_or(0, |slot| slot + 1),
+            };
+            let records = source::fetch_since(&RpcClient::new(url), &mtf_etf::ID, from_slot)?;
+            let indexed = indexer.ingest_all(&records)?;
+            report(&indexer, records.len(), indexed)?;
+        }
+        Command::Replay { from_slot, url, paths } => {
+            let records = if paths.is_empty() {
+                source::fetch_since(&RpcClient::new(url), &mtf_etf::ID, from_slot)?
+            } else {
+                source::load_fixtures(&paths)?
+            };
+            let indexed = indexer.replay_from(from_slot, &records)?;
+            report(&indexer, records.len(), indexed)?;
+        }
+        Command::Etfs => {
+            for etf in indexer.store().etfs()? {
+                println!(
+                    "{}  {:<11} {:<10} tokens {:>2}  supply {:>14}  trades {:>6}  invested {:>14}  creator fees {:>12}  dev fees {:>12}",
+                    etf.address,
+                    etf.status,
+                    etf.symbol.as_deref().unwrap_or("-"),
+                    etf.token_count,
+                    etf.total_supply,
+                    etf.trade_sequence,
+                    etf.sol_invested,
+                    etf.creator_fees,
+                    etf.dev_fees,
+                );
+                if let Some(keeper) = etf.keeper {
+                    println!("    keeper {keeper}  pending {}", etf.pending_sol);
+                }
+            }
+        }
+        Command::Positions { etf } => {
+            for position in indexer.store().positions(&parse_pubkey(&etf)?)? {
+                println!(
+                    "{}  shares {:>14}  invested {:>14}  redeemed {:>14}  claimed {:>14}",
+                    position.investor,
+                    position.shares,
+                    position.sol_invested,
+                    position.sol_redeemed,
+                    position.liquidation_claimed
+                );
+            }
+        }
+        Command::Fees { etf } => {
+            let etf = etf.as_deref().map(parse_pubkey).transpose()?;
+            for total in indexer.store().fee_totals(etf.as_ref())? {
+                println!(
+                    "{}  {:<7} {}  {:>14} lamports in {} transfers",
+                    total.etf, total.fee_type, total.recipient, total.amount, total.transfers
+                );
+            }
+        }
+        Command::Gaps { etf } => {
+            let etf = etf.as_deref().map(parse_pubkey).transpose()?;
+            for gap in indexer.store().trade_gaps(etf.as_ref())? {
+                println!(
+                    "{}  expected trade {} got {} at slot {} ({})",
+                    gap.etf, gap.expected, gap.received, gap.slot, gap.signature
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn report(indexer: &Indexer, transactions: usize, events: usize) -> Result<()> {
+    let last_slot = indexer.store().last_slot()?;
+    println!(
+        "Indexed {events} events from {transactions} transactions (last slot: {})",
+        last_slot.map_or("-".to_string(), |slot| slot.to_string())
+    );
+    Ok(())
+}
+
+fn parse_pubkey(value: &str) -> Result<Pubkey> {
+    Pubkey::from_str(value).map_err(|e| anyhow!("invalid address {value}: {e}"))
+}
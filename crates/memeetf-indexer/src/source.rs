@@ -0,0 +1,177 @@
+// Where transactions come from: `getTransaction` JSON saved to fixture files,
+// or fetched live from an RPC node (e.g. a local validator).
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anchor_lang::prelude::Pubkey;
+use mtf_etf_client::RpcClient;
+use serde_json::Value;
+
+use crate::error::{IndexerError, Result};
+
+const SIGNATURE_PAGE_SIZE: usize = 1000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionRecord {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub failed: bool,
+    pub logs: Vec<String>,
+}
+
+impl TransactionRecord {
+    // Parse a `getTransaction` result (json encoding)
+    pub fn from_rpc_json(value: &Value) -> Result<Self> {
+        let invalid = |what: &str| IndexerError::InvalidTransaction(format!("missing {what}"));
+
+        let signature = value["transaction"]["signatures"][0]
+            .as_str()
+            .ok_or_else(|| invalid("transaction.signatures"))?;
+        let slot = value["slot"].as_u64().ok_or_else(|| invalid("slot"))?;
+        let meta = value.get("meta").filter(|meta| !meta.is_null()).ok_or_else(|| invalid("meta"))?;
+        let logs = meta["logMessages"]
+            .as_array()
+            .ok_or_else(|| invalid("meta.logMessages"))?
+            .iter()
+            .filter_map(|line| line.as_str().map(str::to_string))
+            .collect();
+
+        Ok(Self {
+            signature: signature.to_string(),
+            slot,
+            block_time: value["blockTime"].as_i64(),
+            failed: !meta["err"].is_null(),
+            logs,
+        })
+    }
+}
+
+// Load `getTransaction` results from files or directories of `.json` files.
+// A file may hold one result or an array of them. Records are returned in
+// slot order; same-slot records keep their file order.
+pub fn load_fixtures(paths: &[PathBuf]) -> Result<Vec<TransactionRecord>> {
+    let mut files = Vec::new();
+    for path in paths {
+        collect_files(path, &mut files)?;
+    }
+
+    let mut records = Vec::new();
+    for file in files {
+        let value: Value = serde_json::from_str(&fs::read_to_string(&file)?).map_err(|e| {
+            IndexerError::InvalidTransaction(format!("{}: {e}", file.display()))
+        })?;
+        match value {
+            Value::Array(values) => {
+                for value in &values {
+                    records.push(TransactionRecord::from_rpc_json(value)?);
+                }
+            }
+            value => records.push(TransactionRecord::from_rpc_json(&value)?),
+        }
+    }
+
+    records.sort_by_key(|record| record.slot);
+    Ok(records)
+}
+
+fn collect_files(path: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    if !path.is_dir() {
+        files.push(path.to_path_buf());
+        return Ok(());
+    }
+    let mut entries: Vec<PathBuf> = fs::read_dir(path)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<_>>()?;
+    entries.sort();
+    for entry in entries {
+        if entry.is_dir() || entry.extension().is_some_and(|ext| ext == "json") {
+            collect_files(&entry, files)?;
+        }
+    }
+    Ok(())
+}
+
+// Fetch every transaction for `program_id` at or after `from_slot`, oldest first
+pub fn fetch_since(rpc: &RpcClient, program_id: &Pubkey, from_slot: u64) -> Result<Vec<TransactionRecord>> {
+    let mut signatures = Vec::new();
+    let mut before: Option<String> = None;
+
+    'pages: loop {
+        let page = rpc.get_signatures_for_address(program_id, before.as_deref(), SIGNATURE_PAGE_SIZE)?;
+        let full_page = page.len() == SIGNATURE_PAGE_SIZE;
+        for info in page {
+            if info.slot < from_slot {
+                break 'pages;
+            }
+            before = Some(info.signature.clone());
+            if !info.failed {
+                signatures.push(info.signature);
+            }
+        }
+        if !full_page {
+            break;
+        }
+    }
+
+    let mut records = Vec::with_capacity(signatures.len());
+    for signature in signatures.iter().rev() {
+        if let Some(value) = rpc.get_transaction(signature)? {
+            records.push(TransactionRecord::from_rpc_json(&value)?);
+        }
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rpc_transaction(signature: &str, slot: u64, err: Value) -> Value {
+        json!({
+            "slot": slot,
+            "blockTime": 1_700_000_000,
+            "transaction": { "signatures": [signature], "message": {} },
+            "meta": { "err": err, "logMessages": ["Program log: hi"] },
+        })
+    }
+
+    #[test]
+    fn test_from_rpc_json() {
+        let record = TransactionRecord::from_rpc_json(&rpc_transaction("sig", 7, Value::Null)).unwrap();
+        assert_eq!(record.signature, "sig");
+        assert_eq!(record.slot, 7);
+        assert_eq!(record.block_time, Some(1_700_000_000));
+        assert!(!record.failed);
+        assert_eq!(record.logs, vec!["Program log: hi".to_string()]);
+    }
+
+    #[test]
+    fn test_from_rpc_json_failed_transaction() {
+        let value = rpc_transaction("sig", 7, json!({ "InstructionError": [0, { "Custom": 6000 }] }));
+        assert!(TransactionRecord::from_rpc_json(&value).unwrap().failed);
+    }
+
+    #[test]
+    fn test_from_rpc_json_rejects_missing_meta() {
+        let value = json!({ "slot": 1, "transaction": { "signatures": ["sig"] }, "meta": null });
+        assert!(TransactionRecord::from_rpc_json(&value).is_err());
+    }
+
+    #[test]
+    fn test_load_fixtures_sorts_by_slot() {
+        let dir = std::env::temp_dir().join(format!("memeetf-indexer-fixtures-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let batch = json!([rpc_transaction("b", 9, Value::Null), rpc_transaction("c", 9, Value::Null)]);
+        fs::write(dir.join("a.json"), batch.to_string()).unwrap();
+        fs::write(dir.join("b.json"), rpc_transaction("a", 3, Value::Null).to_string()).unwrap();
+        fs::write(dir.join("notes.txt"), "ignored").unwrap();
+
+        let records = load_fixtures(std::slice::from_ref(&dir)).unwrap();
+        let signatures: Vec<&str> = records.iter().map(|r| r.signature.as_str()).collect();
+        assert_eq!(signatures, vec!["a", "b", "c"]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,929 @@
+// SQLite store: the raw event log plus materialized views folded from it.
+// Views can always be rebuilt from `events`, which is what replay relies on.
+use std::path::Path;
+use std::str::FromStr;
+
+use anchor_lang::prelude::Pubkey;
+use mtf_etf::FeeType;
+use rusqlite::types::Type;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+use crate::error::{IndexerError, Result};
+use crate::events::EtfEvent;
+use crate::source::TransactionRecord;
+
+// Bump when a view table changes; views are then dropped and rebuilt from
+// the event log on open
+const VIEW_VERSION: i64 = 4;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS transactions (
+    signature    TEXT PRIMARY KEY,
+    slot         INTEGER NOT NULL,
+    block_time   INTEGER
+);
+CREATE INDEX IF NOT EXISTS transactions_slot ON transactions (slot);
+
+CREATE TABLE IF NOT EXISTS events (
+    seq          INTEGER PRIMARY KEY AUTOINCREMENT,
+    signature    TEXT NOT NULL,
+    slot         INTEGER NOT NULL,
+    event_index  INTEGER NOT NULL,
+    name         TEXT NOT NULL,
+    etf          TEXT NOT NULL,  -- '' for protocol-wide events
+    data         BLOB NOT NULL,
+    UNIQUE (signature, event_index)
+);
+CREATE INDEX IF NOT EXISTS events_slot ON events (slot, seq);
+";
+
+const VIEWS: &str = "
+CREATE TABLE IF NOT EXISTS etfs (
+    address               TEXT PRIMARY KEY,
+    lister                TEXT NOT NULL,
+    token_count           INTEGER NOT NULL,
+    name                  TEXT,
+    symbol                TEXT,
+    uri                   TEXT,
+    category              TEXT,
+    status                TEXT NOT NULL,
+    created_slot          INTEGER NOT NULL,
+    created_at            INTEGER NOT NULL,
+    closed_slot           INTEGER,
+    sol_invested          INTEGER NOT NULL DEFAULT 0,
+    total_supply          INTEGER NOT NULL DEFAULT 0,
+    trade_sequence        INTEGER NOT NULL DEFAULT 0,
+    nav_per_share         INTEGER,
+    keeper                TEXT,
+    pending_sol           INTEGER NOT NULL DEFAULT 0,
+    liquidation_proceeds  INTEGER NOT NULL DEFAULT 0,
+    rent_recovered        INTEGER,
+    dust_recovered        INTEGER,
+    registry_index        INTEGER,
+    sol_assets            INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE TABLE IF NOT EXISTS positions (
+    etf                  TEXT NOT NULL,
+    investor             TEXT NOT NULL,
+    shares               INTEGER NOT NULL DEFAULT 0,
+    sol_invested         INTEGER NOT NULL DEFAULT 0,
+    sol_redeemed         INTEGER NOT NULL DEFAULT 0,
+    liquidation_claimed  INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (etf, investor)
+);
+
+-- Trade summaries whose sequence was not last_sequence + 1
+CREATE TABLE IF NOT EXISTS trade_gaps (
+    etf        TEXT NOT NULL,
+    expected   INTEGER NOT NULL,
+    received   INTEGER NOT NULL,
+    slot       INTEGER NOT NULL,
+    signature  TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS fee_totals (
+    etf        TEXT NOT NULL,
+    recipient  TEXT NOT NULL,
+    fee_type   TEXT NOT NULL,
+    amount     INTEGER NOT NULL DEFAULT 0,
+    transfers  INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (etf, recipient, fee_type)
+);
+";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EtfView {
+    pub address: Pubkey,
+    pub lister: Pubkey,
+    pub token_count: u8,
+    // From ETFCreatedEvent, then the latest MetadataUpdatedEvent
+    pub name: Option<String>,
+    pub symbol: Option<String>,
+    pub status: String,
+    pub created_slot: u64,
+    pub closed_slot: Option<u64>,
+    // SOL routed to basket tokens, after fees
+    pub sol_invested: u64,
+    pub total_supply: u64,
+    pub trade_sequence: u64,
+    // Lamports per mtf_etf::NAV_SCALE shares as of the last trade
+    pub nav_per_share: Option<u64>,
+    // Set for ETFs whose buys are settled by a keeper
+    pub keeper: Option<Pubkey>,
+    // SOL held for purchases the keeper has not settled yet
+    pub pending_sol: u64,
+    // Set for legacy ETFs from ETFMigratedEvent
+    pub registry_index: Option<u64>,
+    // Lamports booked as NAV when a legacy ETF was migrated
+    pub sol_assets: u64,
+    pub creator_fees: u64,
+    pub dev_fees: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PositionView {
+    pub etf: Pubkey,
+    pub investor: Pubkey,
+    pub shares: u64,
+    pub sol_invested: u64,
+    // SOL paid out on sells, after fees
+    pub sol_redeemed: u64,
+    pub liquidation_claimed: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TradeGap {
+    pub etf: Pubkey,
+    pub expected: u64,
+    pub received: u64,
+    pub slot: u64,
+    pub signature: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeeTotal {
+    pub etf: Pubkey,
+    pub recipient: Pubkey,
+    pub fee_type: String,
+    pub amount: u64,
+    pub transfers: u64,
+}
+
+pub struct Store {
+    conn: Connection,
+}
+
+impl Store {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+        let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version != VIEW_VERSION {
+            conn.execute_batch(
+                "DROP TABLE IF EXISTS etfs; DROP TABLE IF EXISTS positions;
+                 DROP TABLE IF EXISTS fee_totals; DROP TABLE IF EXISTS trade_gaps;",
+            )?;
+            conn.execute_batch(VIEWS)?;
+            rebuild(&conn)?;
+            conn.pragma_update(None, "user_version", VIEW_VERSION)?;
+        }
+        Ok(Self { conn })
+    }
+
+    pub fn contains(&self, signature: &str) -> Result<bool> {
+        let found = self
+            .conn
+            .query_row("SELECT 1 FROM transactions WHERE signature = ?1", [signature], |_| Ok(()))
+            .optional()?;
+        Ok(found.is_some())
+    }
+
+    // Highest slot indexed so far
+    pub fn last_slot(&self) -> Result<Option<u64>> {
+        let slot: Option<i64> = self.conn.query_row("SELECT MAX(slot) FROM transactions", [], |row| row.get(0))?;
+        Ok(slot.map(|slot| slot as u64))
+    }
+
+    pub fn event_count(&self) -> Result<u64> {
+        let count: i64 = self.conn.query_row("SELECT COUNT(*) FROM events", [], |row| row.get(0))?;
+        Ok(count as u64)
+    }
+
+    // Store a transaction's events and fold them into the views atomically.
+    // Returns false if the transaction was already indexed.
+    pub fn record_transaction(&mut self, record: &TransactionRecord, events: &[(Vec<u8>, EtfEvent)]) -> Result<bool> {
+        let tx = self.conn.transaction()?;
+        let inserted = tx.execute(
+            "INSERT OR IGNORE INTO transactions (signature, slot, block_time) VALUES (?1, ?2, ?3)",
+            params![record.signature, amount(record.slot)?, record.block_time],
+        )?;
+        if inserted == 0 {
+            return Ok(false);
+        }
+
+        for (index, (data, event)) in events.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO events (signature, slot, event_index, name, etf, data)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    record.signature,
+                    amount(record.slot)?,
+                    index as i64,
+                    event.name(),
+                    event.etf().map(|etf| etf.to_string()).unwrap_or_default(),
+                    data,
+                ],
+            )?;
+            apply(&tx, record.slot, &record.signature, event)?;
+        }
+
+        tx.commit()?;
+        Ok(true)
+    }
+
+    // Forget every transaction at or after `slot` and rebuild the views from
+    // the events that remain. Returns the number of events removed.
+    pub fn rollback_from(&mut self, slot: u64) -> Result<u64> {
+        let tx = self.conn.transaction()?;
+        let removed = tx.execute("DELETE FROM events WHERE slot >= ?1", [amount(slot)?])?;
+        tx.execute("DELETE FROM transactions WHERE slot >= ?1", [amount(slot)?])?;
+        rebuild(&tx)?;
+        tx.commit()?;
+        Ok(removed as u64)
+    }
+
+    // Recompute every view from the raw event log
+    pub fn rebuild(&mut self) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        rebuild(&tx)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn etfs(&self) -> Result<Vec<EtfView>> {
+        self.query_etfs("", [])
+    }
+
+    pub fn etf(&self, address: &Pubkey) -> Result<Option<EtfView>> {
+        Ok(self.query_etfs("WHERE e.address = ?1", [address.to_string()])?.pop())
+    }
+
+    fn query_etfs<P: rusqlite::Params>(&self, filter: &str, params: P) -> Result<Vec<EtfView>> {
+        let sql = format!(
+            "SELECT e.address, e.lister, e.token_count, e.name, e.symbol, e.status,
+                    e.created_slot, e.closed_slot, e.sol_invested,
+                    e.total_supply, e.trade_sequence, e.nav_per_share, e.keeper, e.pending_sol,
+                    e.registry_index, e.sol_assets,
+                    COALESCE((SELECT SUM(amount) FROM fee_totals f
+                              WHERE f.etf = e.address AND f.fee_type = 'creator'), 0),
+                    COALESCE((SELECT SUM(amount) FROM fee_totals f
+                              WHERE f.etf = e.address AND f.fee_type = 'dev'), 0)
+             FROM etfs e {filter}
+             ORDER BY e.created_slot, e.address"
+        );
+        let mut statement = self.conn.prepare(&sql)?;
+        let rows = statement.query_map(params, |row| {
+            Ok(EtfView {
+                address: pubkey(row, 0)?,
+                lister: pubkey(row, 1)?,
+                token_count: row.get(2)?,
+                name: row.get(3)?,
+                symbol: row.get(4)?,
+                status: row.get(5)?,
+                created_slot: row.get::<_, i64>(6)? as u64,
+                closed_slot: row.get::<_, Option<i64>>(7)?.map(|slot| slot as u64),
+                sol_invested: row.get::<_, i64>(8)? as u64,
+                total_supply: row.get::<_, i64>(9)? as u64,
+                trade_sequence: row.get::<_, i64>(10)? as u64,
+                nav_per_share: row.get::<_, Option<i64>>(11)?.map(|nav| nav as u64),
+                keeper: optional_pubkey(row, 12)?,
+                pending_sol: row.get::<_, i64>(13)? as u64,
+                registry_index: row.get::<_, Option<i64>>(14)?.map(|index| index as u64),
+                sol_assets: row.get::<_, i64>(15)? as u64,
+                creator_fees: row.get::<_, i64>(16)? as u64,
+                dev_fees: row.get::<_, i64>(17)? as u64,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    pub fn positions(&self, etf: &Pubkey) -> Result<Vec<PositionView>> {
+        let mut statement = self.conn.prepare(
+            "SELECT etf, investor, shares, sol_invested, sol_redeemed, liquidation_claimed FROM positions
+             WHERE etf = ?1 ORDER BY investor",
+        )?;
+        let rows = statement.query_map([etf.to_string()], |row| {
+            Ok(PositionView {
+                etf: pubkey(row, 0)?,
+                investor: pubkey(row, 1)?,
+                shares: row.get::<_, i64>(2)? as u64,
+                sol_invested: row.get::<_, i64>(3)? as u64,
+                sol_redeemed: row.get::<_, i64>(4)? as u64,
+                liquidation_claimed: row.get::<_, i64>(5)? as u64,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    // Missing or repeated trade sequence numbers; all ETFs if `etf` is None
+    pub fn trade_gaps(&self, etf: Option<&Pubkey>) -> Result<Vec<TradeGap>> {
+        let mut statement = self.conn.prepare(
+            "SELECT etf, expected, received, slot, signature FROM trade_gaps
+             WHERE ?1 IS NULL OR etf = ?1 ORDER BY slot, rowid",
+        )?;
+        let rows = statement.query_map([etf.map(|etf| etf.to_string())], |row| {
+            Ok(TradeGap {
+                etf: pubkey(row, 0)?,
+                expected: row.get::<_, i64>(1)? as u64,
+                received: row.get::<_, i64>(2)? as u64,
+                slot: row.get::<_, i64>(3)? as u64,
+                signature: row.get(4)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    // Fee totals per (ETF, recipient, fee type); all ETFs if `etf` is None
+    pub fn fee_totals(&self, etf: Option<&Pubkey>) -> Result<Vec<FeeTotal>> {
+        let mut statement = self.conn.prepare(
+            "SELECT etf, recipient, fee_type, amount, transfers FROM fee_totals
+             WHERE ?1 IS NULL OR etf = ?1 ORDER BY etf, fee_type, recipient",
+        )?;
+        let rows = statement.query_map([etf.map(|etf| etf.to_string())], |row| {
+            Ok(FeeTotal {
+                etf: pubkey(row, 0)?,
+                recipient: pubkey(row, 1)?,
+                fee_type: row.get(2)?,
+                amount: row.get::<_, i64>(3)? as u64,
+                transfers: row.get::<_, i64>(4)? as u64,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+}
+
+fn rebuild(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "DELETE FROM etfs; DELETE FROM positions; DELETE FROM fee_totals; DELETE FROM trade_gaps;",
+    )?;
+
+    let mut statement = conn.prepare("SELECT slot, signature, data FROM events ORDER BY slot, seq")?;
+    let mut rows = statement.query([])?;
+    while let Some(row) = rows.next()? {
+        let slot = row.get::<_, i64>(0)? as u64;
+        let signature: String = row.get(1)?;
+        let data: Vec<u8> = row.get(2)?;
+        if let Some(event) = EtfEvent::decode(&data) {
+            apply(conn, slot, &signature, &event)?;
+        }
+    }
+    Ok(())
+}
+
+// Fold one event into the views
+fn apply(conn: &Connection, slot: u64, signature: &str, event: &EtfEvent) -> Result<()> {
+    if let Some((etf, sequence)) = event.trade_sequence() {
+        check_sequence(conn, slot, signature, &etf, sequence)?;
+    }
+
+    match event {
+        EtfEvent::Created(e) => {
+            conn.execute(
+                "INSERT INTO etfs (address, lister, token_count, name, symbol, uri, category, status, created_slot,
+                                   created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 'active', ?8, ?9)
+                 ON CONFLICT (address) DO NOTHING",
+                params![
+                    e.etf_address.to_string(),
+                    e.lister.to_string(),
+                    e.token_count,
+                    e.name,
+                    e.symbol,
+                    e.uri,
+                    format!("{:?}", e.category),
+                    amount(slot)?,
+                    e.timestamp,
+                ],
+            )?;
+        }
+        EtfEvent::Migrated(e) => {
+            conn.execute(
+                "INSERT INTO etfs (address, lister, token_count, status, created_slot, created_at, registry_index,
+                                   sol_assets)
+                 VALUES (?1, ?2, ?3, 'active', ?4, ?5, ?6, ?7)
+                 ON CONFLICT (address) DO UPDATE SET registry_index = excluded.registry_index,
+                                                     sol_assets = excluded.sol_assets",
+                params![
+                    e.etf_address.to_string(),
+                    e.lister.to_string(),
+                    e.token_count,
+                    amount(slot)?,
+                    e.timestamp,
+                    amount(e.registry_index)?,
+                    amount(e.sol_assets)?,
+                ],
+            )?;
+        }
+        EtfEvent::MetadataUpdated(e) => {
+            conn.execute(
+                "UPDATE etfs SET name = ?2, symbol = ?3, uri = ?4, category = ?5 WHERE address = ?1",
+                params![
+                    e.etf_address.to_string(),
+                    e.name,
+                    e.symbol,
+                    e.uri,
+                    format!("{:?}", e.category),
+                ],
+            )?;
+        }
+        EtfEvent::TokenPurchase(e) => {
+            let sol_amount = amount(e.sol_amount)?;
+            conn.execute(
+                "INSERT INTO positions (etf, investor, sol_invested) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (etf, investor) DO UPDATE SET sol_invested = sol_invested + excluded.sol_invested",
+                params![e.etf_address.to_string(), e.investor.to_string(), sol_amount],
+            )?;
+            conn.execute(
+                "UPDATE etfs SET sol_invested = sol_invested + ?2 WHERE address = ?1",
+                params![e.etf_address.to_string(), sol_amount],
+            )?;
+        }
+        // Per-token sell instructions for the backend; the summary carries the totals
+        EtfEvent::TokenSale(_) => {}
+        EtfEvent::Bought(e) => {
+            let shares = amount(e.shares_minted)?;
+            conn.execute(
+                "INSERT INTO positions (etf, investor, shares) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (etf, investor) DO UPDATE SET shares = shares + excluded.shares",
+                params![e.etf_address.to_string(), e.investor.to_string(), shares],
+            )?;
+            update_supply(conn, &e.etf_address, e.total_supply, e.nav_per_share)?;
+        }
+        EtfEvent::Sold(e) => {
+            conn.execute(
+                "UPDATE positions SET shares = MAX(shares - ?3, 0), sol_redeemed = sol_redeemed + ?4
+                 WHERE etf = ?1 AND investor = ?2",
+                params![
+                    e.etf_address.to_string(),
+                    e.investor.to_string(),
+                    amount(e.shares_burned)?,
+                    amount(e.sol_amount)?,
+                ],
+            )?;
+            update_supply(conn, &e.etf_address, e.total_supply, e.nav_per_share)?;
+        }
+        EtfEvent::PurchasePending(e) => {
+            conn.execute(
+                "UPDATE etfs SET pending_sol = pending_sol + ?2 WHERE address = ?1",
+                params![e.etf_address.to_string(), amount(e.sol_amount)?],
+            )?;
+        }
+        // Shares arrive with the ETFBoughtEvent emitted alongside
+        EtfEvent::PurchaseSettled(e) => {
+            conn.execute(
+                "UPDATE etfs SET pending_sol = MAX(pending_sol - ?2, 0) WHERE address = ?1",
+                params![e.etf_address.to_string(), amount(e.sol_amount)?],
+            )?;
+        }
+        // The refunded SOL never reached the basket
+        EtfEvent::PurchaseRefunded(e) => {
+            let sol_amount = amount(e.sol_amount)?;
+            conn.execute(
+                "UPDATE etfs SET pending_sol = MAX(pending_sol - ?2, 0), sol_invested = MAX(sol_invested - ?2, 0)
+                 WHERE address = ?1",
+                params![e.etf_address.to_string(), sol_amount],
+            )?;
+            conn.execute(
+                "UPDATE positions SET sol_invested = MAX(sol_invested - ?3, 0) WHERE etf = ?1 AND investor = ?2",
+                params![e.etf_address.to_string(), e.investor.to_string(), sol_amount],
+            )?;
+        }
+        EtfEvent::KeeperUpdated(e) => {
+            conn.execute(
+                "UPDATE etfs SET keeper = ?2 WHERE address = ?1",
+                params![e.etf_address.to_string(), e.keeper.map(|keeper| keeper.to_string())],
+            )?;
+        }
+        // Positions only; wrapped share tokens are held outside the program
+        EtfEvent::SharesWrapped(e) => {
+            conn.execute(
+                "UPDATE positions SET shares = MAX(shares - ?3, 0) WHERE etf = ?1 AND investor = ?2",
+                params![e.etf_address.to_string(), e.owner.to_string(), amount(e.amount)?],
+            )?;
+        }
+        EtfEvent::SharesUnwrapped(e) => {
+            conn.execute(
+                "INSERT INTO positions (etf, investor, shares) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (etf, investor) DO UPDATE SET shares = shares + excluded.shares",
+                params![e.etf_address.to_string(), e.recipient.to_string(), amount(e.amount)?],
+            )?;
+        }
+        EtfEvent::FeeTransfer(e) => {
+            let fee_type = match e.fee_type {
+                FeeType::Creator => "creator",
+                FeeType::Dev => "dev",
+                FeeType::Parent => "parent",
+            };
+            conn.execute(
+                "INSERT INTO fee_totals (etf, recipient, fee_type, amount, transfers) VALUES (?1, ?2, ?3, ?4, 1)
+                 ON CONFLICT (etf, recipient, fee_type)
+                 DO UPDATE SET amount = amount + excluded.amount, transfers = transfers + 1",
+                params![
+                    e.etf_address.to_string(),
+                    e.recipient.to_string(),
+                    fee_type,
+                    amount(e.amount)?,
+                ],
+            )?;
+        }
+        EtfEvent::LiquidationStarted(e) => {
+            conn.execute(
+                "UPDATE etfs SET status = 'liquidating' WHERE address = ?1",
+                [e.etf_address.to_string()],
+            )?;
+        }
+        EtfEvent::VaultLiquidated(e) => {
+            conn.execute(
+                "UPDATE etfs SET liquidation_proceeds = liquidation_proceeds + ?2 WHERE address = ?1",
+                params![e.etf_address.to_string(), amount(e.sol_amount)?],
+            )?;
+        }
+        EtfEvent::LiquidationClaim(e) => {
+            conn.execute(
+                "INSERT INTO positions (etf, investor, liquidation_claimed) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (etf, investor)
+                 DO UPDATE SET liquidation_claimed = liquidation_claimed + excluded.liquidation_claimed,
+                               shares = MAX(shares - ?4, 0)",
+                params![
+                    e.etf_address.to_string(),
+                    e.investor.to_string(),
+                    amount(e.sol_amount)?,
+                    amount(e.shares_burned)?,
+                ],
+            )?;
+            conn.execute(
+                "UPDATE etfs SET total_supply = MAX(total_supply - ?2, 0) WHERE address = ?1",
+                params![e.etf_address.to_string(), amount(e.shares_burned)?],
+            )?;
+        }
+        EtfEvent::Closed(e) => {
+            conn.execute(
+                "UPDATE etfs SET status = 'closed', closed_slot = ?2, rent_recovered = ?3, dust_recovered = ?4
+                 WHERE address = ?1",
+                params![
+                    e.etf_address.to_string(),
+                    amount(slot)?,
+                    amount(e.rent_recovered)?,
+                    amount(e.dust_recovered)?,
+                ],
+            )?;
+        }
+        // Shares of a migrated ETF credited to a holder from before positions
+        EtfEvent::LegacyPositionRestored(e) => {
+            conn.execute(
+                "INSERT INTO positions (etf, investor, shares) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (etf, investor) DO UPDATE SET shares = shares + excluded.shares",
+                params![e.etf_address.to_string(), e.investor.to_string(), amount(e.shares)?],
+            )?;
+        }
+        // Kept in the raw log only. Share and supply changes of DCA buys,
+        // order fills, exit triggers and redemptions arrive with the
+        // ETFBoughtEvent / ETFSoldEvent emitted alongside; shares escrowed by
+        // an open sell order stay in the owner's position until it fills.
+        EtfEvent::TokenRedeemed(_)
+        | EtfEvent::GateUpdated(_)
+        | EtfEvent::BuyLimitsUpdated(_)
+        | EtfEvent::HoldPeriodUpdated(_)
+        | EtfEvent::DcaPlanCreated(_)
+        | EtfEvent::DcaPlanFunded(_)
+        | EtfEvent::DcaExecuted(_)
+        | EtfEvent::DcaPlanCancelled(_)
+        | EtfEvent::LimitOrderPlaced(_)
+        | EtfEvent::LimitOrderFilled(_)
+        | EtfEvent::LimitOrderCancelled(_)
+        | EtfEvent::ExitTriggerSet(_)
+        | EtfEvent::ExitTriggered(_)
+        | EtfEvent::ExitTriggerCancelled(_)
+        | EtfEvent::NavSnapshot(_)
+        | EtfEvent::EpochReturnSubmitted(_)
+        | EtfEvent::PriceUpdated(_)
+        | EtfEvent::TreasuryConfigured(_)
+        | EtfEvent::RewardsFunded(_)
+        | EtfEvent::DevFeesWithdrawn(_)
+        | EtfEvent::EpochFinalized(_)
+        | EtfEvent::CreatorRewardClaimed(_)
+        | EtfEvent::Forked(_)
+        | EtfEvent::ParentFeesClaimed(_)
+        | EtfEvent::ShareMintCreated(_) => {}
+    }
+    Ok(())
+}
+
+fn update_supply(conn: &Connection, etf: &Pubkey, total_supply: u64, nav_per_share: u64) -> Result<()> {
+    conn.execute(
+        "UPDATE etfs SET total_supply = ?2, nav_per_share = ?3 WHERE address = ?1",
+        params![etf.to_string(), amount(total_supply)?, amount(nav_per_share)?],
+    )?;
+    Ok(())
+}
+
+// Record a gap if `sequence` does not directly follow the last trade seen
+// for the ETF. The view always advances to the highest sequence seen.
+fn check_sequence(conn: &Connection, slot: u64, signature: &str, etf: &Pubkey, sequence: u64) -> Result<()> {
+    let last: Option<i64> = conn
+        .query_row("SELECT trade_sequence FROM etfs WHERE address = ?1", [etf.to_string()], |row| row.get(0))
+        .optional()?;
+    let expected = last.unwrap_or(0) as u64 + 1;
+    if sequence != expected {
+        conn.execute(
+            "INSERT INTO trade_gaps (etf, expected, received, slot, signature) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![etf.to_string(), amount(expected)?, amount(sequence)?, amount(slot)?, signature],
+        )?;
+    }
+    conn.execute(
+        "UPDATE etfs SET trade_sequence = MAX(trade_sequence, ?2) WHERE address = ?1",
+        params![etf.to_string(), amount(sequence)?],
+    )?;
+    Ok(())
+}
+
+// SQLite integers are signed
+fn amount(value: u64) -> Result<i64> {
+    i64::try_from(value).map_err(|_| IndexerError::AmountOverflow(value))
+}
+
+fn pubkey(row: &Row, index: usize) -> rusqlite::Result<Pubkey> {
+    let text: String = row.get(index)?;
+    Pubkey::from_str(&text).map_err(|e| rusqlite::Error::FromSqlConversionFailure(index, Type::Text, Box::new(e)))
+}
+
+fn optional_pubkey(row: &Row, index: usize) -> rusqlite::Result<Option<Pubkey>> {
+    match row.get::<_, Option<String>>(index)? {
+        Some(_) => pubkey(row, index).map(Some),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::{AnchorSerialize, Discriminator};
+    use mtf_etf::{
+        ETFBoughtEvent, ETFCreatedEvent, ETFMigratedEvent, ETFSoldEvent, EtfCategory, FeeTransferEvent, LegacyPositionRestoredEvent,
+        PriceUpdatedEvent, TokenPurchaseEvent,
+    };
+
+    fn encode<T: AnchorSerialize + Discriminator>(event: &T) -> Vec<u8> {
+        let mut data = T::DISCRIMINATOR.to_vec();
+        event.serialize(&mut data).unwrap();
+        data
+    }
+
+    fn record(signature: &str, slot: u64) -> TransactionRecord {
+        TransactionRecord {
+            signature: signature.to_string(),
+            slot,
+            block_time: None,
+            failed: false,
+            logs: Vec::new(),
+        }
+    }
+
+    fn events(payloads: Vec<Vec<u8>>) -> Vec<(Vec<u8>, EtfEvent)> {
+        payloads
+            .into_iter()
+            .map(|data| {
+                let event = EtfEvent::decode(&data).unwrap();
+                (data, event)
+            })
+            .collect()
+    }
+
+    fn created(etf: Pubkey, lister: Pubkey) -> Vec<u8> {
+        encode(&ETFCreatedEvent {
+            etf_address: etf,
+            lister,
+            token_count: 2,
+            name: "Dog Coins".to_string(),
+            symbol: "DOGS".to_string(),
+            uri: String::new(),
+            category: EtfCategory::Meme,
+            timestamp: 1,
+        })
+    }
+
+    fn migrated(etf: Pubkey, lister: Pubkey) -> Vec<u8> {
+        encode(&ETFMigratedEvent {
+            etf_address: etf,
+            lister,
+            token_count: 1,
+            registry_index: 7,
+            sol_assets: 900,
+            timestamp: 1,
+        })
+    }
+
+    fn purchase(etf: Pubkey, investor: Pubkey, sol_amount: u64) -> Vec<u8> {
+        encode(&TokenPurchaseEvent {
+            etf_address: etf,
+            investor,
+            token_address: Pubkey::new_unique(),
+            sol_amount,
+            percentage: 50,
+            timestamp: 2,
+        })
+    }
+
+    fn fee(etf: Pubkey, recipient: Pubkey, amount: u64) -> Vec<u8> {
+        encode(&FeeTransferEvent {
+            etf_address: etf,
+            recipient,
+            amount,
+            fee_type: FeeType::Creator,
+            timestamp: 2,
+        })
+    }
+
+    fn bought(etf: Pubkey, investor: Pubkey, sequence: u64, shares: u64, total_supply: u64) -> Vec<u8> {
+        encode(&ETFBoughtEvent {
+            etf_address: etf,
+            investor,
+            sequence,
+            sol_amount: shares,
+            shares_minted: shares,
+            creator_fee: 0,
+            dev_fee: 0,
+            total_supply,
+            nav_per_share: mtf_etf::NAV_SCALE,
+            timestamp: 2,
+        })
+    }
+
+    fn sold(etf: Pubkey, investor: Pubkey, sequence: u64, shares: u64, total_supply: u64) -> Vec<u8> {
+        encode(&ETFSoldEvent {
+            etf_address: etf,
+            investor,
+            sequence,
+            shares_burned: shares,
+            sol_amount: shares - 1,
+            creator_fee: 0,
+            dev_fee: 0,
+            early_exit_fee: 0,
+            total_supply,
+            nav_per_share: mtf_etf::NAV_SCALE,
+            timestamp: 3,
+        })
+    }
+
+    #[test]
+    fn test_record_transaction_folds_events() {
+        let mut store = Store::open_in_memory().unwrap();
+        let (etf, lister, investor) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+
+        store.record_transaction(&record("a", 1), &events(vec![created(etf, lister)])).unwrap();
+        store
+            .record_transaction(
+                &record("b", 2),
+                &events(vec![fee(etf, lister, 5), purchase(etf, investor, 300), purchase(etf, investor, 200)]),
+            )
+            .unwrap();
+
+        let view = store.etf(&etf).unwrap().unwrap();
+        assert_eq!(view.lister, lister);
+        assert_eq!(view.name.as_deref(), Some("Dog Coins"));
+        assert_eq!(view.symbol.as_deref(), Some("DOGS"));
+        assert_eq!(view.status, "active");
+        assert_eq!(view.sol_invested, 500);
+        assert_eq!(view.creator_fees, 5);
+        assert_eq!(view.dev_fees, 0);
+        assert_eq!(store.positions(&etf).unwrap()[0].sol_invested, 500);
+        assert_eq!(store.last_slot().unwrap(), Some(2));
+        assert_eq!(store.event_count().unwrap(), 4);
+    }
+
+    #[test]
+    fn test_record_transaction_keeps_protocol_events() {
+        let mut store = Store::open_in_memory().unwrap();
+        let (etf, investor) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let price = encode(&PriceUpdatedEvent {
+            token_address: Pubkey::new_unique(),
+            price: 100,
+            timestamp: 1,
+        });
+        let restored = encode(&LegacyPositionRestoredEvent {
+            etf_address: etf,
+            investor,
+            shares: 700,
+            legacy_shares: 300,
+            timestamp: 1,
+        });
+
+        store.record_transaction(&record("a", 1), &events(vec![price, restored])).unwrap();
+
+        assert_eq!(store.event_count().unwrap(), 2);
+        let position = &store.positions(&etf).unwrap()[0];
+        assert_eq!(position.investor, investor);
+        assert_eq!(position.shares, 700);
+    }
+
+    #[test]
+    fn test_record_transaction_is_idempotent() {
+        let mut store = Store::open_in_memory().unwrap();
+        let etf = Pubkey::new_unique();
+        let fee_event = events(vec![fee(etf, Pubkey::new_unique(), 5)]);
+
+        assert!(store.record_transaction(&record("a", 1), &fee_event).unwrap());
+        assert!(!store.record_transaction(&record("a", 1), &fee_event).unwrap());
+        assert_eq!(store.fee_totals(Some(&etf)).unwrap()[0].amount, 5);
+    }
+
+    #[test]
+    fn test_rollback_from_rebuilds_views() {
+        let mut store = Store::open_in_memory().unwrap();
+        let (etf, lister, investor) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        store.record_transaction(&record("a", 1), &events(vec![created(etf, lister)])).unwrap();
+        store.record_transaction(&record("b", 5), &events(vec![purchase(etf, investor, 100)])).unwrap();
+        store.record_transaction(&record("c", 9), &events(vec![purchase(etf, investor, 50)])).unwrap();
+
+        assert_eq!(store.rollback_from(5).unwrap(), 2);
+
+        assert_eq!(store.etf(&etf).unwrap().unwrap().sol_invested, 0);
+        assert!(store.positions(&etf).unwrap().is_empty());
+        assert_eq!(store.last_slot().unwrap(), Some(1));
+        assert!(!store.contains("b").unwrap());
+        assert!(store.contains("a").unwrap());
+    }
+
+    #[test]
+    fn test_trades_track_shares_and_supply() {
+        let mut store = Store::open_in_memory().unwrap();
+        let (etf, lister, investor) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        store.record_transaction(&record("a", 1), &events(vec![created(etf, lister)])).unwrap();
+        store.record_transaction(&record("b", 2), &events(vec![bought(etf, investor, 1, 500, 500)])).unwrap();
+        store.record_transaction(&record("c", 3), &events(vec![sold(etf, investor, 2, 200, 300)])).unwrap();
+
+        let view = store.etf(&etf).unwrap().unwrap();
+        assert_eq!(view.total_supply, 300);
+        assert_eq!(view.trade_sequence, 2);
+        assert_eq!(view.nav_per_share, Some(mtf_etf::NAV_SCALE));
+        let position = &store.positions(&etf).unwrap()[0];
+        assert_eq!(position.shares, 300);
+        assert_eq!(position.sol_redeemed, 199);
+        assert!(store.trade_gaps(None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_migrated_etf_survives_replay() {
+        let mut store = Store::open_in_memory().unwrap();
+        let (etf, lister, investor) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        store.record_transaction(&record("a", 3), &events(vec![migrated(etf, lister)])).unwrap();
+        store.record_transaction(&record("b", 4), &events(vec![bought(etf, investor, 1, 100, 1_100)])).unwrap();
+
+        store.rebuild().unwrap();
+
+        let view = store.etf(&etf).unwrap().unwrap();
+        assert_eq!(view.lister, lister);
+        assert_eq!(view.token_count, 1);
+        assert_eq!(view.name, None);
+        assert_eq!(view.status, "active");
+        assert_eq!(view.created_slot, 3);
+        assert_eq!(view.registry_index, Some(7));
+        assert_eq!(view.sol_assets, 900);
+        assert_eq!(view.total_supply, 1_100);
+        assert_eq!(view.trade_sequence, 1);
+        assert!(store.trade_gaps(None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_trade_gaps_detected() {
+        let mut store = Store::open_in_memory().unwrap();
+        let (etf, lister, investor) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        store.record_transaction(&record("a", 1), &events(vec![created(etf, lister)])).unwrap();
+        store.record_transaction(&record("b", 2), &events(vec![bought(etf, investor, 1, 100, 100)])).unwrap();
+        // Sequences 2 and 3 were never seen
+        store.record_transaction(&record("c", 3), &events(vec![bought(etf, investor, 4, 100, 400)])).unwrap();
+
+        let gaps = store.trade_gaps(Some(&etf)).unwrap();
+        assert_eq!(gaps.len(), 1);
+        assert_eq!((gaps[0].expected, gaps[0].received), (2, 4));
+        assert_eq!(gaps[0].signature, "c");
+        assert_eq!(store.etf(&etf).unwrap().unwrap().trade_sequence, 4);
+
+        // Gaps are part of the views, so they survive a rebuild and vanish on rollback
+        store.rebuild().unwrap();
+        assert_eq!(store.trade_gaps(None).unwrap().len(), 1);
+        store.rollback_from(3).unwrap();
+        assert!(store.trade_gaps(None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_open_rebuilds_outdated_views() {
+        let path = std::env::temp_dir().join(format!("memeetf-indexer-views-{}.sqlite", std::process::id()));
+        let (etf, lister) = (Pubkey::new_unique(), Pubkey::new_unique());
+        {
+            let mut store = Store::open(&path).unwrap();
+            store.record_transaction(&record("a", 1), &events(vec![created(etf, lister)])).unwrap();
+            // Simulate a database written by an older indexer
+            store.conn.execute_batch("DROP TABLE etfs; PRAGMA user_version = 1;").unwrap();
+        }
+
+        let store = Store::open(&path).unwrap();
+        assert_eq!(store.etf(&etf).unwrap().unwrap().lister, lister);
+        drop(store);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_amount_overflow() {
+        assert!(matches!(amount(u64::MAX), Err(IndexerError::AmountOverflow(u64::MAX))));
+        assert_eq!(amount(i64::MAX as u64).unwrap(), i64::MAX);
+    }
+}
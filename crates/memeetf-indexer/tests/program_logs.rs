@@ -0,0 +1,313 @@
+// Index logs produced by actually running mtf_etf in the program harness
+use anchor_lang::prelude::Pubkey;
+use memeetf_indexer::{source, Indexer, Store, TransactionRecord};
+use mtf_etf::{
+    calculate_fees, EtfCategory, DEFAULT_REFUND_TIMEOUT_SECONDS, GOVERNANCE_AUTHORITY, PRICE_SCALE, TREASURY,
+};
+use mtf_etf_client::instructions::{self, EtfMetadata};
+use mtf_etf_client::pda;
+use program_harness::{Harness, TransactionOutcome};
+
+const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+
+struct Chain {
+    harness: Harness,
+    records: Vec<TransactionRecord>,
+    treasury_start: u64,
+}
+
+impl Chain {
+    fn new() -> Self {
+        let mut harness = Harness::new();
+        harness.add_program(mtf_etf::ID, mtf_etf::entry);
+        harness.airdrop(&GOVERNANCE_AUTHORITY, LAMPORTS_PER_SOL);
+        harness
+            .process_instruction(
+                instructions::configure_treasury(GOVERNANCE_AUTHORITY, 0),
+                &[GOVERNANCE_AUTHORITY],
+            )
+            .unwrap();
+        let treasury_start = harness.lamports(&TREASURY);
+        Self { harness, records: Vec::new(), treasury_start }
+    }
+
+    // Run one transaction and keep it as an RPC-style record; a new slot per tx
+    fn send(&mut self, instruction: anchor_lang::solana_program::instruction::Instruction, signer: Pubkey) {
+        self.harness.advance_time(1);
+        let outcome: TransactionOutcome = self.harness.process_instruction(instruction, &[signer]).unwrap();
+        self.records.push(TransactionRecord {
+            signature: format!("tx{}", self.records.len()),
+            slot: self.harness.clock().slot,
+            block_time: Some(self.harness.clock().unix_timestamp),
+            failed: false,
+            logs: outcome.logs,
+        });
+    }
+}
+
+fn metadata() -> EtfMetadata {
+    EtfMetadata {
+        name: "Dog Coins".to_string(),
+        symbol: "DOGS".to_string(),
+        uri: String::new(),
+        category: EtfCategory::Meme,
+    }
+}
+
+// Create an ETF, two investors buy, one sells out, the lister renames it
+fn scenario() -> (Chain, Pubkey, Pubkey, [Pubkey; 2]) {
+    let mut chain = Chain::new();
+    let lister = Pubkey::new_unique();
+    let investors = [Pubkey::new_unique(), Pubkey::new_unique()];
+    chain.harness.airdrop(&lister, 10 * LAMPORTS_PER_SOL);
+    for investor in &investors {
+        chain.harness.airdrop(investor, 10 * LAMPORTS_PER_SOL);
+    }
+    let mints = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+    let (etf, _) = pda::etf_address(&lister, 0);
+
+    chain.send(instructions::initialize_etf(lister, 0, 0, mints, metadata()), lister);
+    chain.send(instructions::buy_etf(etf, lister, investors[0], LAMPORTS_PER_SOL, vec![50, 50]), investors[0]);
+    chain.send(instructions::buy_etf(etf, lister, investors[1], 2 * LAMPORTS_PER_SOL, vec![70, 30]), investors[1]);
+    chain.send(instructions::sell_etf(etf, lister, investors[0], 990_000_000), investors[0]);
+    let renamed = EtfMetadata { symbol: "WOOF".to_string(), ..metadata() };
+    chain.send(instructions::update_metadata(etf, lister, renamed), lister);
+
+    (chain, etf, lister, investors)
+}
+
+#[test]
+fn test_created_event_lists_metadata() {
+    let (chain, etf, _, _) = scenario();
+    let mut indexer = Indexer::new(Store::open_in_memory().unwrap());
+
+    indexer.ingest_all(&chain.records[..1]).unwrap();
+
+    let view = indexer.store().etf(&etf).unwrap().unwrap();
+    assert_eq!(view.name.as_deref(), Some("Dog Coins"));
+    assert_eq!(view.symbol.as_deref(), Some("DOGS"));
+}
+
+#[test]
+fn test_views_match_chain() {
+    let (chain, etf, lister, investors) = scenario();
+    let mut indexer = Indexer::new(Store::open_in_memory().unwrap());
+
+    let indexed = indexer.ingest_all(&chain.records).unwrap();
+    // created + 2 x (2 fees + 2 purchases + summary) + (2 fees + 2 sales + summary) + metadata
+    assert_eq!(indexed, 1 + 10 + 5 + 1);
+
+    let view = indexer.store().etf(&etf).unwrap().unwrap();
+    assert_eq!(view.lister, lister);
+    assert_eq!(view.token_count, 2);
+    assert_eq!(view.symbol.as_deref(), Some("WOOF"));
+    assert_eq!(view.status, "active");
+    let state: mtf_etf::ETF = chain.harness.anchor_account(&etf).unwrap();
+    assert_eq!(view.total_supply, state.total_supply);
+    assert_eq!(view.trade_sequence, state.trade_sequence);
+    assert_eq!(view.nav_per_share, Some(mtf_etf::NAV_SCALE));
+    assert!(indexer.store().trade_gaps(None).unwrap().is_empty());
+
+    // Fee totals agree with what the fee recipients actually received
+    let expected_fees: u64 = [LAMPORTS_PER_SOL, 2 * LAMPORTS_PER_SOL, 990_000_000]
+        .iter()
+        .map(|amount| calculate_fees(*amount).0)
+        .sum();
+    assert_eq!(view.creator_fees, expected_fees);
+    assert_eq!(view.dev_fees, expected_fees);
+    assert_eq!(chain.harness.lamports(&TREASURY) - chain.treasury_start, expected_fees);
+
+    let positions = indexer.store().positions(&etf).unwrap();
+    assert_eq!(positions.len(), 2);
+    for position in &positions {
+        let state: mtf_etf::Position =
+            chain.harness.anchor_account(&pda::position_address(&etf, &position.investor).0).unwrap();
+        assert_eq!(position.shares, state.shares);
+    }
+    let position = |investor: &Pubkey| positions.iter().find(|p| p.investor == *investor).unwrap();
+    assert_eq!(position(&investors[0]).sol_invested, 990_000_000);
+    assert_eq!(position(&investors[0]).sol_redeemed, 990_000_000 - 2 * calculate_fees(990_000_000).0);
+    assert_eq!(position(&investors[1]).sol_invested, 1_980_000_000);
+    assert_eq!(position(&investors[1]).sol_redeemed, 0);
+}
+
+#[test]
+fn test_reingest_is_idempotent() {
+    let (chain, etf, _, _) = scenario();
+    let mut indexer = Indexer::new(Store::open_in_memory().unwrap());
+    indexer.ingest_all(&chain.records).unwrap();
+    let before = indexer.store().etf(&etf).unwrap();
+
+    assert_eq!(indexer.ingest_all(&chain.records).unwrap(), 0);
+    assert_eq!(indexer.store().etf(&etf).unwrap(), before);
+}
+
+#[test]
+fn test_replay_from_slot_matches_full_index() {
+    let (chain, etf, _, _) = scenario();
+    let mut full = Indexer::new(Store::open_in_memory().unwrap());
+    full.ingest_all(&chain.records).unwrap();
+
+    // Index only part of history, with a stale view of the rest
+    let mut partial = Indexer::new(Store::open_in_memory().unwrap());
+    partial.ingest_all(&chain.records).unwrap();
+    let from_slot = chain.records[2].slot;
+    partial.store_mut().rollback_from(from_slot).unwrap();
+    assert_eq!(partial.store().last_slot().unwrap(), Some(chain.records[1].slot));
+
+    partial.replay_from(from_slot, &chain.records).unwrap();
+
+    assert_eq!(partial.store().etf(&etf).unwrap(), full.store().etf(&etf).unwrap());
+    assert_eq!(partial.store().positions(&etf).unwrap(), full.store().positions(&etf).unwrap());
+    assert_eq!(partial.store().fee_totals(None).unwrap(), full.store().fee_totals(None).unwrap());
+}
+
+#[test]
+fn test_failed_transactions_are_skipped() {
+    let (mut chain, etf, _, _) = scenario();
+    let mut failed = chain.records[1].clone();
+    failed.signature = "failed".to_string();
+    failed.failed = true;
+    chain.records.push(failed);
+
+    let mut indexer = Indexer::new(Store::open_in_memory().unwrap());
+    indexer.ingest_all(&chain.records).unwrap();
+    assert!(!indexer.store().contains("failed").unwrap());
+    assert_eq!(indexer.store().positions(&etf).unwrap().len(), 2);
+}
+
+#[test]
+fn test_missing_transaction_shows_as_gap() {
+    let (mut chain, etf, _, _) = scenario();
+    let dropped = chain.records.remove(2);
+
+    let mut indexer = Indexer::new(Store::open_in_memory().unwrap());
+    indexer.ingest_all(&chain.records).unwrap();
+    let gaps = indexer.store().trade_gaps(Some(&etf)).unwrap();
+    assert_eq!(gaps.len(), 1);
+    assert_eq!((gaps[0].expected, gaps[0].received), (2, 3));
+
+    // Backfilling the missing transaction and replaying clears it
+    chain.records.insert(2, dropped);
+    indexer.replay_from(chain.records[2].slot, &chain.records).unwrap();
+    assert!(indexer.store().trade_gaps(None).unwrap().is_empty());
+}
+
+#[test]
+fn test_keeper_purchases_track_pending_sol() {
+    let mut chain = Chain::new();
+    let lister = Pubkey::new_unique();
+    let keeper = Pubkey::new_unique();
+    let investor = Pubkey::new_unique();
+    for wallet in [lister, keeper, investor] {
+        chain.harness.airdrop(&wallet, 10 * LAMPORTS_PER_SOL);
+    }
+    let mint = Pubkey::new_unique();
+    chain.harness.create_mint(mint, &lister, 6);
+    let (etf, _) = pda::etf_address(&lister, 0);
+    let source = Pubkey::new_unique();
+    chain.harness.create_token_account(source, &mint, &keeper, 1_000);
+    chain
+        .harness
+        .create_token_account(pda::vault_address(&etf, &mint), &mint, &etf, 0);
+
+    // 500 tokens are worth the 0.99 SOL swapped
+    chain.send(
+        instructions::set_price(GOVERNANCE_AUTHORITY, mint, 1_980_000 * PRICE_SCALE),
+        GOVERNANCE_AUTHORITY,
+    );
+    chain.send(
+        instructions::initialize_etf(lister, 0, 0, vec![mint], metadata()),
+        lister,
+    );
+    chain.send(instructions::set_keeper(etf, lister, Some(keeper), DEFAULT_REFUND_TIMEOUT_SECONDS), lister);
+    chain.send(instructions::buy_etf_pending(etf, lister, investor, 0, LAMPORTS_PER_SOL, vec![100]), investor);
+    chain.send(instructions::buy_etf_pending(etf, lister, investor, 1, LAMPORTS_PER_SOL, vec![100]), investor);
+    chain.send(
+        instructions::settle_purchase(etf, investor, keeper, 0, &[(mint, source)], vec![500]),
+        keeper,
+    );
+
+    let mut indexer = Indexer::new(Store::open_in_memory().unwrap());
+    indexer.ingest_all(&chain.records).unwrap();
+    let view = indexer.store().etf(&etf).unwrap().unwrap();
+    let state: mtf_etf::ETF = chain.harness.anchor_account(&etf).unwrap();
+    assert_eq!(view.keeper, Some(keeper));
+    assert_eq!(view.pending_sol, state.pending_sol);
+    assert_eq!(view.pending_sol, 990_000_000);
+    assert_eq!(view.total_supply, state.total_supply);
+    assert_eq!(view.trade_sequence, 1);
+    assert_eq!(indexer.store().positions(&etf).unwrap()[0].shares, 990_000_000);
+
+    // The second purchase times out and is refunded
+    chain.harness.advance_time(DEFAULT_REFUND_TIMEOUT_SECONDS);
+    chain.send(instructions::refund_pending_purchase(etf, investor, 1), investor);
+    indexer.ingest_all(&chain.records).unwrap();
+    let view = indexer.store().etf(&etf).unwrap().unwrap();
+    assert_eq!(view.pending_sol, 0);
+    assert_eq!(view.sol_invested, 990_000_000);
+    assert_eq!(indexer.store().positions(&etf).unwrap()[0].sol_invested, 990_000_000);
+}
+
+#[test]
+fn test_wrapped_shares_move_between_positions() {
+    let (mut chain, etf, _, investors) = scenario();
+    let tokens = Pubkey::new_unique();
+    chain.send(instructions::create_share_mint(etf, investors[1]), investors[1]);
+    chain
+        .harness
+        .create_token_account(tokens, &pda::share_mint_address(&etf).0, &investors[1], 0);
+    chain.send(instructions::wrap_shares(etf, investors[1], tokens, 500_000_000), investors[1]);
+    chain.send(
+        instructions::unwrap_shares(etf, investors[1], tokens, investors[0], 200_000_000),
+        investors[1],
+    );
+
+    let mut indexer = Indexer::new(Store::open_in_memory().unwrap());
+    indexer.ingest_all(&chain.records).unwrap();
+    for position in indexer.store().positions(&etf).unwrap() {
+        let on_chain: mtf_etf::Position = chain
+            .harness
+            .anchor_account(&pda::position_address(&etf, &position.investor).0)
+            .unwrap();
+        assert_eq!(position.shares, on_chain.shares);
+    }
+    let view = indexer.store().etf(&etf).unwrap().unwrap();
+    assert_eq!(view.total_supply, 1_980_000_000);
+}
+
+#[test]
+fn test_fixture_file() {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+    let records = source::load_fixtures(&[path]).unwrap();
+    let mut indexer = Indexer::new(Store::open_in_memory().unwrap());
+
+    assert!(indexer.ingest_all(&records).unwrap() > 0);
+    let etfs = indexer.store().etfs().unwrap();
+    assert_eq!(etfs.len(), 1);
+    assert_eq!(etfs[0].symbol.as_deref(), Some("WOOF"));
+    assert!(etfs[0].creator_fees > 0);
+    assert_eq!(etfs[0].trade_sequence, 3);
+}
+
+// Regenerate fixtures/scenario.json with
+// `cargo test -p memeetf-indexer --test program_logs -- --ignored`
+#[test]
+#[ignore]
+fn write_fixture() {
+    let (chain, _, _, _) = scenario();
+    let transactions: Vec<serde_json::Value> = chain
+        .records
+        .iter()
+        .map(|record| {
+            serde_json::json!({
+                "slot": record.slot,
+                "blockTime": record.block_time,
+                "transaction": { "signatures": [record.signature] },
+                "meta": { "err": null, "logMessages": record.logs },
+            })
+        })
+        .collect();
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/scenario.json");
+    std::fs::write(path, serde_json::to_string_pretty(&transactions).unwrap() + "\n").unwrap();
+}
@@ -0,0 +1,16 @@
+use anchor_lang::prelude::Pubkey;
+use mtf_etf_client::ClientError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum KeeperError {
+    #[error(transparent)]
+    Client(#[from] ClientError),
+    #[error("No swap route for mint {0}")]
+    NoRoute(Pubkey),
+    #[error("Swapping {lamports} lamports into {mint} returns no tokens")]
+    UnfillableAllocation { mint: Pubkey, lamports: u64 },
+    #[error("Pending purchase has {allocations} allocations for {tokens} basket tokens")]
+    AllocationMismatch { allocations: usize, tokens: usize },
+}
+
+pub type Result<T> = std::result::Result<T, KeeperError>;
@@ -0,0 +1,53 @@
+// Reference keeper for keeper-settled mtf_etf ETFs.
+//
+// buy_etf on an ETF with a keeper only records a PendingPurchase holding the
+// investor's SOL. The keeper buys each token's allocation with its own SOL on
+// a swap venue, then calls settle_purchase in the same transaction: the
+// tokens move into the ETF vaults, the keeper is reimbursed from the pending
+// SOL and the investor's shares are minted. The keeper only ever fronts its
+// own SOL; investor funds stay in the ETF account until settlement.
+
+pub mod error;
+pub mod settlement;
+pub mod venue;
+
+use anchor_lang::prelude::Pubkey;
+use mtf_etf::{EtfStatus, PendingPurchase, ETF};
+use mtf_etf_client::{accounts, RpcClient};
+
+pub use error::{KeeperError, Result};
+pub use settlement::{plan_settlement, Settlement};
+pub use venue::{MockSwapVenue, Swap, SwapVenue};
+
+// A pending purchase this keeper can settle now
+pub struct Work {
+    pub etf_address: Pubkey,
+    pub etf: ETF,
+    pub purchase: PendingPurchase,
+}
+
+// Pending purchases, oldest first, on active ETFs whose keeper is `keeper`
+pub fn fetch_work(rpc: &RpcClient, keeper: &Pubkey, etf: Option<&Pubkey>) -> Result<Vec<Work>> {
+    let mut work = Vec::new();
+    let mut current: Option<(Pubkey, ETF)> = None;
+    for (_, purchase) in accounts::fetch_pending_purchases(rpc, etf)? {
+        if current.as_ref().map(|(address, _)| *address) != Some(purchase.etf) {
+            current = Some((purchase.etf, accounts::fetch_etf(rpc, &purchase.etf)?));
+        }
+        let Some((etf_address, etf)) = &current else {
+            continue;
+        };
+        if is_settleable(etf, keeper) {
+            work.push(Work {
+                etf_address: *etf_address,
+                etf: etf.clone(),
+                purchase,
+            });
+        }
+    }
+    Ok(work)
+}
+
+pub fn is_settleable(etf: &ETF, keeper: &Pubkey) -> bool {
+    etf.status == EtfStatus::Active && etf.keeper == Some(*keeper)
+}
@@ -0,0 +1,90 @@
+use std::path::PathBuf;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anyhow::{anyhow, bail, Result};
+use clap::Parser;
+use memeetf_keeper::{fetch_work, plan_settlement, MockSwapVenue};
+use mtf_etf_client::rpc::LOCALNET_RPC_URL;
+use mtf_etf_client::RpcClient;
+use solana_keypair::{read_keypair_file, Keypair};
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Parser)]
+#[command(name = "memeetf-keeper", version, about = "Swap and settle pending MemeETF purchases")]
+struct Cli {
+    #[arg(short, long, env = "MEMEETF_RPC_URL", default_value = LOCALNET_RPC_URL)]
+    url: String,
+    /// Keeper keypair; pays for swaps and is reimbursed on settlement
+    #[arg(short, long, env = "MEMEETF_KEEPER_KEYPAIR")]
+    keypair: PathBuf,
+    /// Only settle purchases of this ETF
+    #[arg(long)]
+    etf: Option<Pubkey>,
+    /// Seconds between polls
+    #[arg(long, default_value_t = 5)]
+    interval: u64,
+    /// Settle what is pending now and exit
+    #[arg(long)]
+    once: bool,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let rpc = RpcClient::new(cli.url);
+    let keeper = read_keypair_file(&cli.keypair)
+        .map_err(|e| anyhow!("failed to read keypair {}: {e}", cli.keypair.display()))?;
+    println!("Keeper {} watching {}", keeper.pubkey(), rpc.url());
+
+    loop {
+        if let Err(e) = settle_pending(&rpc, &keeper, cli.etf.as_ref()) {
+            eprintln!("poll failed: {e}");
+        }
+        if cli.once {
+            return Ok(());
+        }
+        sleep(Duration::from_secs(cli.interval));
+    }
+}
+
+// One pass over the pending purchases; a purchase that fails to settle is
+// reported and retried on the next pass
+fn settle_pending(rpc: &RpcClient, keeper: &Keypair, etf: Option<&Pubkey>) -> Result<()> {
+    for work in fetch_work(rpc, &keeper.pubkey(), etf)? {
+        let venue = MockSwapVenue::fetch(rpc, &work.etf.token_addresses)?;
+        let label = format!("{} #{}", work.etf_address, work.purchase.purchase_id);
+        let result = plan_settlement(&work.etf_address, &work.etf, &work.purchase, &keeper.pubkey(), &venue)
+            .map_err(anyhow::Error::from)
+            .and_then(|settlement| {
+                let signature = send_and_confirm(rpc, keeper, &settlement.instructions)?;
+                Ok((settlement.token_amounts, signature))
+            });
+        match result {
+            Ok((token_amounts, signature)) => {
+                println!("Settled {label}: {token_amounts:?} ({signature})")
+            }
+            Err(e) => eprintln!("Could not settle {label}: {e}"),
+        }
+    }
+    Ok(())
+}
+
+fn send_and_confirm(rpc: &RpcClient, payer: &Keypair, ixs: &[Instruction]) -> Result<String> {
+    let blockhash = rpc.get_latest_blockhash()?;
+    let tx = Transaction::new_signed_with_payer(ixs, Some(&payer.pubkey()), &[payer], blockhash);
+    let signature = rpc.send_transaction(&bincode::serialize(&tx)?)?;
+
+    let started = Instant::now();
+    while !rpc.is_confirmed(&signature)? {
+        if started.elapsed() > CONFIRM_TIMEOUT {
+            bail!("transaction {signature} was not confirmed within {CONFIRM_TIMEOUT:?}");
+        }
+        sleep(Duration::from_millis(500));
+    }
+    Ok(signature)
+}
@@ -0,0 +1,174 @@
+// Turn one pending purchase into a settlement transaction: create the token
+// accounts it needs, swap each allocation on the venue, then settle_purchase.
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_spl::associated_token::{get_associated_token_address, spl_associated_token_account};
+use mtf_etf::{PendingPurchase, ETF};
+use mtf_etf_client::instructions;
+
+use crate::error::{KeeperError, Result};
+use crate::venue::SwapVenue;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Settlement {
+    pub instructions: Vec<Instruction>,
+    pub token_amounts: Vec<u64>,
+}
+
+pub fn plan_settlement(
+    etf_address: &Pubkey,
+    etf: &ETF,
+    purchase: &PendingPurchase,
+    keeper: &Pubkey,
+    venue: &impl SwapVenue,
+) -> Result<Settlement> {
+    if purchase.allocations.len() != etf.token_addresses.len() {
+        return Err(KeeperError::AllocationMismatch {
+            allocations: purchase.allocations.len(),
+            tokens: etf.token_addresses.len(),
+        });
+    }
+
+    let mut ixs = Vec::new();
+    let mut swaps = Vec::new();
+    let mut sources = Vec::new();
+    let mut token_amounts = Vec::new();
+    for (mint, lamports) in etf.token_addresses.iter().zip(&purchase.allocations) {
+        let source = get_associated_token_address(keeper, mint);
+        for owner in [keeper, etf_address] {
+            ixs.push(
+                spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+                    keeper,
+                    owner,
+                    mint,
+                    &anchor_spl::token::ID,
+                ),
+            );
+        }
+
+        let amount_out = if *lamports > 0 {
+            let swap = venue.swap(keeper, mint, *lamports, &source)?;
+            if swap.amount_out == 0 {
+                return Err(KeeperError::UnfillableAllocation {
+                    mint: *mint,
+                    lamports: *lamports,
+                });
+            }
+            swaps.extend(swap.instructions);
+            swap.amount_out
+        } else {
+            0
+        };
+        sources.push((*mint, source));
+        token_amounts.push(amount_out);
+    }
+
+    ixs.extend(swaps);
+    ixs.push(instructions::settle_purchase(
+        *etf_address,
+        purchase.investor,
+        *keeper,
+        purchase.purchase_id,
+        &sources,
+        token_amounts.clone(),
+    ));
+
+    Ok(Settlement {
+        instructions: ixs,
+        token_amounts,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::venue::MockSwapVenue;
+    use mock_swap::Pool;
+    use mtf_etf_client::pda;
+
+    fn etf(mints: Vec<Pubkey>, keeper: Pubkey) -> ETF {
+        ETF {
+            lister: Pubkey::new_unique(),
+            token_addresses: mints,
+            bump: 255,
+            name: "Dog Coins".to_string(),
+            symbol: "DOGS".to_string(),
+            keeper: Some(keeper),
+            purchase_count: 5,
+            ..Default::default()
+        }
+    }
+
+    fn purchase(etf: Pubkey, allocations: Vec<u64>) -> PendingPurchase {
+        PendingPurchase {
+            etf,
+            investor: Pubkey::new_unique(),
+            purchase_id: 4,
+            sol_amount: 0,
+            sol_to_swap: allocations.iter().sum(),
+            allocations,
+            created_ts: 0,
+            refundable_ts: 3_600,
+            bump: 255,
+        }
+    }
+
+    fn venue(mints: &[Pubkey]) -> MockSwapVenue {
+        let mut venue = MockSwapVenue::new();
+        for mint in mints {
+            venue.add_pool(Pool {
+                mint: *mint,
+                authority: Pubkey::new_unique(),
+                tokens_per_sol: 1_000,
+                bump: 255,
+            });
+        }
+        venue
+    }
+
+    #[test]
+    fn test_plan_settlement_swaps_each_allocation() {
+        let mints = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+        let keeper = Pubkey::new_unique();
+        let etf_address = Pubkey::new_unique();
+        let etf = etf(mints.clone(), keeper);
+        let purchase = purchase(etf_address, vec![600_000_000, 400_000_000]);
+
+        let plan = plan_settlement(&etf_address, &etf, &purchase, &keeper, &venue(&mints)).unwrap();
+        assert_eq!(plan.token_amounts, vec![600, 400]);
+        // 2 ATAs per token, 1 swap per token, then settle
+        assert_eq!(plan.instructions.len(), 4 + 2 + 1);
+        let settle = plan.instructions.last().unwrap();
+        assert_eq!(settle.program_id, mtf_etf::ID);
+        assert_eq!(settle.accounts[1].pubkey, pda::pending_purchase_address(&etf_address, 4).0);
+        let last = &settle.accounts[settle.accounts.len() - 3..];
+        assert_eq!(last[1].pubkey, pda::vault_address(&etf_address, &mints[1]));
+        assert_eq!(last[2].pubkey, pda::price_feed_address(&mints[1]).0);
+    }
+
+    #[test]
+    fn test_plan_settlement_skips_empty_allocation() {
+        let mints = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+        let keeper = Pubkey::new_unique();
+        let etf_address = Pubkey::new_unique();
+        let etf = etf(mints.clone(), keeper);
+        let purchase = purchase(etf_address, vec![1_000_000_000, 0]);
+
+        // No pool for the second mint is needed when nothing is bought
+        let plan = plan_settlement(&etf_address, &etf, &purchase, &keeper, &venue(&mints[..1])).unwrap();
+        assert_eq!(plan.token_amounts, vec![1_000, 0]);
+        assert_eq!(plan.instructions.len(), 4 + 1 + 1);
+    }
+
+    #[test]
+    fn test_plan_settlement_rejects_dust() {
+        let mints = vec![Pubkey::new_unique()];
+        let keeper = Pubkey::new_unique();
+        let etf_address = Pubkey::new_unique();
+        let etf = etf(mints.clone(), keeper);
+        let purchase = purchase(etf_address, vec![1_000]);
+
+        let result = plan_settlement(&etf_address, &etf, &purchase, &keeper, &venue(&mints));
+        assert!(matches!(result, Err(KeeperError::UnfillableAllocation { lamports: 1_000, .. })));
+    }
+}
@@ -0,0 +1,130 @@
+// Where the keeper buys basket tokens. The mock venue trades against
+// mock_swap pools; a Jupiter-backed venue would implement the same trait.
+use std::collections::HashMap;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::get_associated_token_address;
+use mock_swap::Pool;
+use mtf_etf_client::{accounts, RpcClient};
+
+use crate::error::{KeeperError, Result};
+
+// Instructions that turn the keeper's SOL into tokens, and what they yield
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Swap {
+    pub instructions: Vec<Instruction>,
+    pub amount_out: u64,
+}
+
+pub trait SwapVenue {
+    // Swap `lamports_in` of the keeper's SOL into `mint`, delivered to the
+    // keeper's `destination` token account
+    fn swap(&self, keeper: &Pubkey, mint: &Pubkey, lamports_in: u64, destination: &Pubkey) -> Result<Swap>;
+}
+
+// ["pool", mint] under the mock_swap program
+pub fn pool_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"pool", mint.as_ref()], &mock_swap::ID).0
+}
+
+// Pool reserve - the pool PDA's associated token account
+pub fn reserve_address(mint: &Pubkey) -> Pubkey {
+    get_associated_token_address(&pool_address(mint), mint)
+}
+
+#[derive(Clone, Default)]
+pub struct MockSwapVenue {
+    pools: HashMap<Pubkey, Pool>,
+}
+
+impl MockSwapVenue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_pool(&mut self, pool: Pool) {
+        self.pools.insert(pool.mint, pool);
+    }
+
+    // Load the pools for `mints`; mints without a pool are left unroutable
+    pub fn fetch(rpc: &RpcClient, mints: &[Pubkey]) -> Result<Self> {
+        let mut venue = Self::new();
+        for mint in mints {
+            if let Some(pool) = accounts::fetch_optional::<Pool>(rpc, &pool_address(mint))? {
+                venue.add_pool(pool);
+            }
+        }
+        Ok(venue)
+    }
+}
+
+impl SwapVenue for MockSwapVenue {
+    fn swap(&self, keeper: &Pubkey, mint: &Pubkey, lamports_in: u64, destination: &Pubkey) -> Result<Swap> {
+        let pool = self.pools.get(mint).ok_or(KeeperError::NoRoute(*mint))?;
+        let amount_out = mock_swap::quote(lamports_in, pool.tokens_per_sol);
+
+        let instruction = Instruction {
+            program_id: mock_swap::ID,
+            accounts: mock_swap::accounts::SwapSolForTokens {
+                pool: pool_address(mint),
+                reserve: reserve_address(mint),
+                destination: *destination,
+                user: *keeper,
+                token_program: anchor_spl::token::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: mock_swap::instruction::SwapSolForTokens {
+                lamports_in,
+                min_tokens_out: amount_out,
+            }
+            .data(),
+        };
+
+        Ok(Swap {
+            instructions: vec![instruction],
+            amount_out,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(mint: Pubkey, tokens_per_sol: u64) -> Pool {
+        Pool {
+            mint,
+            authority: Pubkey::new_unique(),
+            tokens_per_sol,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn test_mock_swap_quotes_pool_rate() {
+        let mint = Pubkey::new_unique();
+        let mut venue = MockSwapVenue::new();
+        venue.add_pool(pool(mint, 2_000_000));
+        let keeper = Pubkey::new_unique();
+        let destination = Pubkey::new_unique();
+
+        let swap = venue.swap(&keeper, &mint, 500_000_000, &destination).unwrap();
+        assert_eq!(swap.amount_out, 1_000_000);
+        assert_eq!(swap.instructions.len(), 1);
+        let ix = &swap.instructions[0];
+        assert_eq!(ix.program_id, mock_swap::ID);
+        assert_eq!(ix.accounts[0].pubkey, pool_address(&mint));
+        assert_eq!(ix.accounts[2].pubkey, destination);
+        assert!(ix.accounts[3].is_signer);
+    }
+
+    #[test]
+    fn test_mock_swap_without_pool() {
+        let mint = Pubkey::new_unique();
+        let result = MockSwapVenue::new().swap(&Pubkey::new_unique(), &mint, 1, &Pubkey::new_unique());
+        assert!(matches!(result, Err(KeeperError::NoRoute(m)) if m == mint));
+    }
+}
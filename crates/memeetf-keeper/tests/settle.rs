@@ -0,0 +1,186 @@
+// Run the keeper's settlement plan against mtf_etf and mock_swap in the
+// program harness
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::{get_associated_token_address, spl_associated_token_account};
+use anchor_spl::token::spl_token;
+use memeetf_keeper::venue::{pool_address, reserve_address};
+use memeetf_keeper::{plan_settlement, MockSwapVenue};
+use mock_swap::Pool;
+use mtf_etf::{
+    EtfCategory, PendingPurchase, PurchaseSettledEvent, DEFAULT_REFUND_TIMEOUT_SECONDS, ETF, GOVERNANCE_AUTHORITY,
+    PRICE_SCALE,
+};
+use mtf_etf_client::instructions::{self, EtfMetadata};
+use mtf_etf_client::pda;
+use program_harness::Harness;
+
+const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+
+struct Market {
+    harness: Harness,
+    etf: Pubkey,
+    lister: Pubkey,
+    keeper: Pubkey,
+    mints: Vec<Pubkey>,
+}
+
+// A keeper-settled ETF over two tokens, each with a funded mock_swap pool
+fn market(rates: [u64; 2]) -> Market {
+    let mut harness = Harness::new();
+    harness.add_program(mtf_etf::ID, mtf_etf::entry);
+    harness.add_program(mock_swap::ID, mock_swap::entry);
+    harness.add_program(
+        spl_associated_token_account::ID,
+        spl_associated_token_account::processor::process_instruction,
+    );
+    harness.airdrop(&GOVERNANCE_AUTHORITY, LAMPORTS_PER_SOL);
+    harness
+        .process_instruction(
+            instructions::configure_treasury(GOVERNANCE_AUTHORITY, 0),
+            &[GOVERNANCE_AUTHORITY],
+        )
+        .unwrap();
+
+    let lister = Pubkey::new_unique();
+    let keeper = Pubkey::new_unique();
+    harness.airdrop(&lister, 10 * LAMPORTS_PER_SOL);
+    harness.airdrop(&keeper, 10 * LAMPORTS_PER_SOL);
+
+    let mints = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+    for (mint, rate) in mints.iter().zip(rates) {
+        harness.create_mint(*mint, &lister, 6);
+        harness
+            .process_instruction(
+                Instruction {
+                    program_id: mock_swap::ID,
+                    accounts: mock_swap::accounts::InitializePool {
+                        pool: pool_address(mint),
+                        mint: *mint,
+                        authority: lister,
+                        system_program: system_program::ID,
+                    }
+                    .to_account_metas(None),
+                    data: mock_swap::instruction::InitializePool { tokens_per_sol: rate }.data(),
+                },
+                &[lister],
+            )
+            .unwrap();
+        harness.create_token_account(reserve_address(mint), mint, &pool_address(mint), 1_000_000_000_000);
+        // Price each token at its pool rate so settlements clear the floor
+        harness
+            .process_instruction(
+                instructions::set_price(GOVERNANCE_AUTHORITY, *mint, LAMPORTS_PER_SOL * PRICE_SCALE / rate),
+                &[GOVERNANCE_AUTHORITY],
+            )
+            .unwrap();
+    }
+
+    let metadata = EtfMetadata {
+        name: "Dog Coins".to_string(),
+        symbol: "DOGS".to_string(),
+        uri: String::new(),
+        category: EtfCategory::Meme,
+    };
+    let etf = pda::etf_address(&lister, 0).0;
+    harness
+        .process_instruction(instructions::initialize_etf(lister, 0, 0, mints.clone(), metadata), &[lister])
+        .unwrap();
+    harness
+        .process_instruction(instructions::set_keeper(etf, lister, Some(keeper), DEFAULT_REFUND_TIMEOUT_SECONDS), &[lister])
+        .unwrap();
+
+    Market { harness, etf, lister, keeper, mints }
+}
+
+impl Market {
+    fn pending_buy(&mut self, sol_amount: u64) -> (Pubkey, u64) {
+        let investor = Pubkey::new_unique();
+        self.harness.airdrop(&investor, 10 * LAMPORTS_PER_SOL);
+        let purchase_id = self.etf().purchase_count;
+        self.harness
+            .process_instruction(
+                instructions::buy_etf_pending(self.etf, self.lister, investor, purchase_id, sol_amount, vec![60, 40]),
+                &[investor],
+            )
+            .unwrap();
+        (investor, purchase_id)
+    }
+
+    fn etf(&self) -> ETF {
+        self.harness.anchor_account(&self.etf).unwrap()
+    }
+
+    fn purchase(&self, purchase_id: u64) -> Option<PendingPurchase> {
+        self.harness.anchor_account(&pda::pending_purchase_address(&self.etf, purchase_id).0)
+    }
+
+    fn venue(&self) -> MockSwapVenue {
+        let mut venue = MockSwapVenue::new();
+        for mint in &self.mints {
+            venue.add_pool(self.harness.anchor_account::<Pool>(&pool_address(mint)).unwrap());
+        }
+        venue
+    }
+}
+
+#[test]
+fn test_keeper_settles_pending_purchase() {
+    let mut market = market([1_000_000, 5_000_000]);
+    let (investor, purchase_id) = market.pending_buy(LAMPORTS_PER_SOL);
+    let purchase = market.purchase(purchase_id).unwrap();
+    let keeper_before = market.harness.lamports(&market.keeper);
+
+    let plan = plan_settlement(&market.etf, &market.etf(), &purchase, &market.keeper, &market.venue()).unwrap();
+    let outcome = market.harness.process_transaction(&plan.instructions, &[market.keeper]).unwrap();
+
+    // 0.99 SOL after fees, split 60/40 and swapped at each pool's rate
+    assert_eq!(plan.token_amounts, vec![594_000, 1_980_000]);
+    for (mint, amount) in market.mints.iter().zip(&plan.token_amounts) {
+        assert_eq!(market.harness.token_balance(&pda::vault_address(&market.etf, mint)), Some(*amount));
+        assert_eq!(
+            market.harness.token_balance(&get_associated_token_address(&market.keeper, mint)),
+            Some(0)
+        );
+    }
+
+    let etf = market.etf();
+    assert_eq!(etf.total_supply, 990_000_000);
+    assert_eq!(etf.pending_sol, 0);
+    let position: mtf_etf::Position =
+        market.harness.anchor_account(&pda::position_address(&market.etf, &investor).0).unwrap();
+    assert_eq!(position.shares, 990_000_000);
+    assert!(market.purchase(purchase_id).is_none());
+
+    // The keeper is made whole for the swaps, paying only token account rent
+    let rent = 4 * market.harness.rent().minimum_balance(spl_token::state::Account::LEN);
+    assert_eq!(market.harness.lamports(&market.keeper), keeper_before - rent);
+
+    let settled = outcome.events::<PurchaseSettledEvent>();
+    assert_eq!(settled.len(), 1);
+    assert_eq!(settled[0].token_amounts, plan.token_amounts);
+    assert_eq!(settled[0].keeper, market.keeper);
+}
+
+#[test]
+fn test_keeper_settles_purchases_in_order() {
+    let mut market = market([1_000_000, 1_000_000]);
+    let first = market.pending_buy(LAMPORTS_PER_SOL).1;
+    let second = market.pending_buy(2 * LAMPORTS_PER_SOL).1;
+    assert_eq!((first, second), (0, 1));
+
+    for purchase_id in [first, second] {
+        let purchase = market.purchase(purchase_id).unwrap();
+        let plan =
+            plan_settlement(&market.etf, &market.etf(), &purchase, &market.keeper, &market.venue()).unwrap();
+        // Token accounts already exist for the second purchase; creation is idempotent
+        market.harness.process_transaction(&plan.instructions, &[market.keeper]).unwrap();
+    }
+
+    let etf = market.etf();
+    assert_eq!(etf.total_supply, 990_000_000 + 1_980_000_000);
+    assert_eq!(etf.pending_sol, 0);
+    assert_eq!(etf.trade_sequence, 2);
+}
@@ -0,0 +1,278 @@
+// Account fetching and decoding for mtf_etf accounts
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AccountDeserialize, Discriminator};
+use mtf_etf::{DcaPlan, EpochRewards, ExitTrigger, LeaderboardEpoch, LimitOrder, NavHistory, PendingPurchase, Position, Registry, RegistryEntry, RegistryPage, Treasury, ETF, ID, TREASURY};
+
+use crate::error::{ClientError, Result};
+use crate::pda;
+use crate::rpc::{MemcmpFilter, RpcClient};
+
+// Byte offset of ETF.lister (right after the 8-byte discriminator)
+pub const ETF_LISTER_OFFSET: usize = 8;
+
+// Byte offset of PendingPurchase.etf
+pub const PENDING_PURCHASE_ETF_OFFSET: usize = 8;
+
+// Byte offset of DcaPlan.etf
+pub const DCA_PLAN_ETF_OFFSET: usize = 8;
+
+// Byte offset of LimitOrder.etf
+pub const LIMIT_ORDER_ETF_OFFSET: usize = 8;
+
+// Byte offset of ExitTrigger.etf
+pub const EXIT_TRIGGER_ETF_OFFSET: usize = 8;
+
+// Decode any Anchor account (discriminator checked)
+pub fn decode<T: AccountDeserialize>(data: &[u8]) -> Result<T> {
+    T::try_deserialize(&mut &data[..]).map_err(|e| ClientError::Decode(e.to_string()))
+}
+
+pub fn decode_etf(data: &[u8]) -> Result<ETF> {
+    decode(data)
+}
+
+pub fn decode_position(data: &[u8]) -> Result<Position> {
+    decode(data)
+}
+
+pub fn fetch<T: AccountDeserialize>(rpc: &RpcClient, address: &Pubkey) -> Result<T> {
+    let data = rpc
+        .get_account_data(address)?
+        .ok_or(ClientError::AccountNotFound(*address))?;
+    decode(&data)
+}
+
+pub fn fetch_optional<T: AccountDeserialize>(
+    rpc: &RpcClient,
+    address: &Pubkey,
+) -> Result<Option<T>> {
+    rpc.get_account_data(address)?
+        .map(|data| decode(&data))
+        .transpose()
+}
+
+pub fn fetch_etf(rpc: &RpcClient, address: &Pubkey) -> Result<ETF> {
+    fetch(rpc, address)
+}
+
+// None if the investor has never bought into this ETF
+pub fn fetch_position(
+    rpc: &RpcClient,
+    etf: &Pubkey,
+    investor: &Pubkey,
+) -> Result<Option<Position>> {
+    fetch_optional(rpc, &pda::position_address(etf, investor).0)
+}
+
+// None until someone creates the ETF's NAV history
+pub fn fetch_nav_history(rpc: &RpcClient, etf: &Pubkey) -> Result<Option<NavHistory>> {
+    fetch_optional(rpc, &pda::nav_history_address(etf).0)
+}
+
+// None until someone creates the epoch's leaderboard
+pub fn fetch_leaderboard_epoch(rpc: &RpcClient, epoch_id: u64) -> Result<Option<LeaderboardEpoch>> {
+    fetch_optional(rpc, &pda::leaderboard_address(epoch_id).0)
+}
+
+// None until the epoch is finalized
+pub fn fetch_epoch_rewards(rpc: &RpcClient, epoch_id: u64) -> Result<Option<EpochRewards>> {
+    fetch_optional(rpc, &pda::epoch_rewards_address(epoch_id).0)
+}
+
+// None until governance configures the treasury
+pub fn fetch_treasury(rpc: &RpcClient) -> Result<Option<Treasury>> {
+    fetch_optional(rpc, &TREASURY)
+}
+
+// Registry header; None until the first ETF is created
+pub fn fetch_registry(rpc: &RpcClient) -> Result<Option<Registry>> {
+    fetch_optional(rpc, &pda::registry_address().0)
+}
+
+// Every ETF created by `lister` (getProgramAccounts on the lister field)
+pub fn fetch_etfs_by_lister(rpc: &RpcClient, lister: &Pubkey) -> Result<Vec<(Pubkey, ETF)>> {
+    let filters = [
+        MemcmpFilter {
+            offset: 0,
+            bytes: ETF::DISCRIMINATOR.to_vec(),
+        },
+        MemcmpFilter {
+            offset: ETF_LISTER_OFFSET,
+            bytes: lister.to_bytes().to_vec(),
+        },
+    ];
+    rpc.get_program_accounts(&ID, &filters)?
+        .into_iter()
+        .map(|(address, data)| Ok((address, decode_etf(&data)?)))
+        .collect()
+}
+
+// Unsettled purchases, for one ETF or all of them, oldest first per ETF
+pub fn fetch_pending_purchases(
+    rpc: &RpcClient,
+    etf: Option<&Pubkey>,
+) -> Result<Vec<(Pubkey, PendingPurchase)>> {
+    let mut filters = vec![MemcmpFilter {
+        offset: 0,
+        bytes: PendingPurchase::DISCRIMINATOR.to_vec(),
+    }];
+    if let Some(etf) = etf {
+        filters.push(MemcmpFilter {
+            offset: PENDING_PURCHASE_ETF_OFFSET,
+            bytes: etf.to_bytes().to_vec(),
+        });
+    }
+    let mut pending: Vec<(Pubkey, PendingPurchase)> = rpc
+        .get_program_accounts(&ID, &filters)?
+        .into_iter()
+        .map(|(address, data)| Ok((address, decode(&data)?)))
+        .collect::<Result<_>>()?;
+    pending.sort_by_key(|(_, purchase)| (purchase.etf, purchase.purchase_id));
+    Ok(pending)
+}
+
+// DCA plans, for one ETF or all of them, soonest due first
+pub fn fetch_dca_plans(rpc: &RpcClient, etf: Option<&Pubkey>) -> Result<Vec<(Pubkey, DcaPlan)>> {
+    let mut filters = vec![MemcmpFilter {
+        offset: 0,
+        bytes: DcaPlan::DISCRIMINATOR.to_vec(),
+    }];
+    if let Some(etf) = etf {
+        filters.push(MemcmpFilter {
+            offset: DCA_PLAN_ETF_OFFSET,
+            bytes: etf.to_bytes().to_vec(),
+        });
+    }
+    let mut plans: Vec<(Pubkey, DcaPlan)> = rpc
+        .get_program_accounts(&ID, &filters)?
+        .into_iter()
+        .map(|(address, data)| Ok((address, decode(&data)?)))
+        .collect::<Result<_>>()?;
+    plans.sort_by_key(|(_, plan)| plan.next_execution_ts);
+    Ok(plans)
+}
+
+// Open limit orders, for one ETF or all of them, oldest first
+pub fn fetch_limit_orders(rpc: &RpcClient, etf: Option<&Pubkey>) -> Result<Vec<(Pubkey, LimitOrder)>> {
+    let mut filters = vec![MemcmpFilter {
+        offset: 0,
+        bytes: LimitOrder::DISCRIMINATOR.to_vec(),
+    }];
+    if let Some(etf) = etf {
+        filters.push(MemcmpFilter {
+            offset: LIMIT_ORDER_ETF_OFFSET,
+            bytes: etf.to_bytes().to_vec(),
+        });
+    }
+    let mut orders: Vec<(Pubkey, LimitOrder)> = rpc
+        .get_program_accounts(&ID, &filters)?
+        .into_iter()
+        .map(|(address, data)| Ok((address, decode(&data)?)))
+        .collect::<Result<_>>()?;
+    orders.sort_by_key(|(_, order)| (order.created_ts, order.order_id));
+    Ok(orders)
+}
+
+// Exit triggers, for one ETF or all of them
+pub fn fetch_exit_triggers(rpc: &RpcClient, etf: Option<&Pubkey>) -> Result<Vec<(Pubkey, ExitTrigger)>> {
+    let mut filters = vec![MemcmpFilter {
+        offset: 0,
+        bytes: ExitTrigger::DISCRIMINATOR.to_vec(),
+    }];
+    if let Some(etf) = etf {
+        filters.push(MemcmpFilter {
+            offset: EXIT_TRIGGER_ETF_OFFSET,
+            bytes: etf.to_bytes().to_vec(),
+        });
+    }
+    rpc.get_program_accounts(&ID, &filters)?
+        .into_iter()
+        .map(|(address, data)| Ok((address, decode(&data)?)))
+        .collect()
+}
+
+// Every ETF's NAV history, for the snapshot crank
+pub fn fetch_nav_histories(rpc: &RpcClient) -> Result<Vec<(Pubkey, NavHistory)>> {
+    let filters = [MemcmpFilter {
+        offset: 0,
+        bytes: NavHistory::DISCRIMINATOR.to_vec(),
+    }];
+    rpc.get_program_accounts(&ID, &filters)?
+        .into_iter()
+        .map(|(address, data)| Ok((address, decode(&data)?)))
+        .collect()
+}
+
+// All registry entries in creation order, including tombstoned (closed) ETFs
+pub fn fetch_registry_entries(rpc: &RpcClient) -> Result<Vec<RegistryEntry>> {
+    let Some(registry) = fetch_registry(rpc)? else {
+        return Ok(Vec::new());
+    };
+    if registry.etf_count == 0 {
+        return Ok(Vec::new());
+    }
+
+    let last_page = mtf_etf::registry_page_index(registry.etf_count - 1);
+    let mut entries = Vec::with_capacity(registry.etf_count as usize);
+    for page_index in 0..=last_page {
+        let page: RegistryPage = fetch(rpc, &pda::registry_page_address(page_index).0)?;
+        entries.extend(page.entries);
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::AccountSerialize;
+
+    fn sample_etf(lister: Pubkey) -> ETF {
+        ETF {
+            lister,
+            etf_index: 2,
+            token_addresses: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+            total_supply: 990_000_000,
+            bump: 254,
+            registry_index: 7,
+            name: "Dog Coins".to_string(),
+            symbol: "DOGS".to_string(),
+            lister_last_active_ts: 1_700_000_000,
+            trade_sequence: 3,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_decode_etf_round_trip() {
+        let lister = Pubkey::new_unique();
+        let mut data = Vec::new();
+        sample_etf(lister).try_serialize(&mut data).unwrap();
+
+        let etf = decode_etf(&data).unwrap();
+        assert_eq!(etf.lister, lister);
+        assert_eq!(etf.total_supply, 990_000_000);
+        assert_eq!(etf.symbol, "DOGS");
+        // Lister filter offset must point at the serialized lister key
+        assert_eq!(
+            &data[ETF_LISTER_OFFSET..ETF_LISTER_OFFSET + 32],
+            lister.as_ref()
+        );
+    }
+
+    #[test]
+    fn test_decode_rejects_wrong_account_type() {
+        let mut data = Vec::new();
+        Position {
+            etf: Pubkey::new_unique(),
+            investor: Pubkey::new_unique(),
+            shares: 1,
+            bump: 255,
+            last_buy_ts: 0,
+        }
+        .try_serialize(&mut data)
+        .unwrap();
+
+        assert!(decode_etf(&data).is_err());
+        assert_eq!(decode_position(&data).unwrap().shares, 1);
+    }
+}
@@ -0,0 +1,92 @@
+// Merkle allowlists for gated ETFs, hashed the way verify_allowlist checks
+// them. The lister publishes the wallet list and sets the root with
+// set_gate; each investor derives their own proof from the same list.
+use anchor_lang::prelude::Pubkey;
+use mtf_etf::{allowlist_leaf, merkle_parent};
+
+#[derive(Debug, Clone)]
+pub struct Allowlist {
+    // layers[0] are the sorted leaves, the last layer is the root
+    layers: Vec<Vec<[u8; 32]>>,
+}
+
+impl Allowlist {
+    // Duplicate wallets are ignored. A node without a sibling moves up a
+    // layer unchanged.
+    pub fn new(wallets: &[Pubkey]) -> Self {
+        let mut leaves: Vec<[u8; 32]> = wallets.iter().map(allowlist_leaf).collect();
+        leaves.sort_unstable();
+        leaves.dedup();
+
+        let mut layers = vec![leaves];
+        while layers.last().unwrap().len() > 1 {
+            let next = layers
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| match pair {
+                    [a, b] => merkle_parent(a, b),
+                    [a] => *a,
+                    _ => unreachable!(),
+                })
+                .collect();
+            layers.push(next);
+        }
+        Self { layers }
+    }
+
+    // All zeroes for an empty list, which no proof can match
+    pub fn root(&self) -> [u8; 32] {
+        self.layers.last().and_then(|layer| layer.first()).copied().unwrap_or_default()
+    }
+
+    pub fn proof(&self, wallet: &Pubkey) -> Option<Vec<[u8; 32]>> {
+        let mut index = self.layers[0].binary_search(&allowlist_leaf(wallet)).ok()?;
+        let mut proof = Vec::new();
+        for layer in &self.layers[..self.layers.len() - 1] {
+            if let Some(sibling) = layer.get(index ^ 1) {
+                proof.push(*sibling);
+            }
+            index /= 2;
+        }
+        Some(proof)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mtf_etf::verify_merkle_proof;
+
+    #[test]
+    fn test_every_member_proves_membership() {
+        for size in 1..=9 {
+            let wallets: Vec<Pubkey> = (0..size).map(|_| Pubkey::new_unique()).collect();
+            let allowlist = Allowlist::new(&wallets);
+            for wallet in &wallets {
+                let proof = allowlist.proof(wallet).unwrap();
+                assert!(verify_merkle_proof(allowlist_leaf(wallet), &proof, allowlist.root()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_outsider_has_no_proof() {
+        let wallets = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        let allowlist = Allowlist::new(&wallets);
+        let outsider = Pubkey::new_unique();
+        assert!(allowlist.proof(&outsider).is_none());
+
+        // Borrowing a member's proof does not help either
+        let proof = allowlist.proof(&wallets[0]).unwrap();
+        assert!(!verify_merkle_proof(allowlist_leaf(&outsider), &proof, allowlist.root()));
+    }
+
+    #[test]
+    fn test_root_ignores_order_and_duplicates() {
+        let a = Pubkey::new_unique();
+        let b = Pubkey::new_unique();
+        assert_eq!(Allowlist::new(&[a, b]).root(), Allowlist::new(&[b, a, b]).root());
+        assert_eq!(Allowlist::new(&[]).root(), [0; 32]);
+    }
+}
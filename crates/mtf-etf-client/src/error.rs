@@ -0,0 +1,19 @@
+use anchor_lang::prelude::Pubkey;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    #[error("RPC transport error: {0}")]
+    Transport(String),
+    #[error("RPC error {code}: {message}")]
+    Rpc { code: i64, message: String },
+    #[error("Unexpected RPC response: {0}")]
+    InvalidResponse(String),
+    #[error("Account {0} not found")]
+    AccountNotFound(Pubkey),
+    #[error("Failed to decode account: {0}")]
+    Decode(String),
+    #[error("Simulation failed: {0}")]
+    Simulation(String),
+}
+
+pub type Result<T> = std::result::Result<T, ClientError>;
@@ -0,0 +1,1148 @@
+// Instruction builders. Account metas and instruction data are produced by
+// the Anchor-generated `accounts` / `instruction` modules of the program.
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use mtf_etf::{BuyLimits, EtfCategory, EtfGate, ForkChanges, LimitOrder, OrderSide, DEV_WALLET, ETF, ID, TREASURY};
+
+use crate::pda;
+
+// Human-readable metadata passed to initialize_etf / update_metadata
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EtfMetadata {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub category: EtfCategory,
+}
+
+// `registry_index` is the registry's current `etf_count`; it selects the
+// registry page the new ETF is appended to.
+pub fn initialize_etf(
+    lister: Pubkey,
+    etf_index: u8,
+    registry_index: u64,
+    token_addresses: Vec<Pubkey>,
+    metadata: EtfMetadata,
+) -> Instruction {
+    let (etf, _) = pda::etf_address(&lister, etf_index);
+
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::InitializeETF {
+            etf,
+            registry: pda::registry_address().0,
+            registry_page: pda::registry_page_for_index(registry_index).0,
+            lister,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::InitializeEtf {
+            etf_index,
+            token_addresses,
+            name: metadata.name,
+            symbol: metadata.symbol,
+            uri: metadata.uri,
+            category: metadata.category,
+        }
+        .data(),
+    }
+}
+
+// Fork `parent` into the lister's slot `etf_index`; `registry_index` as for
+// initialize_etf.
+pub fn fork_etf(
+    lister: Pubkey,
+    etf_index: u8,
+    parent: Pubkey,
+    registry_index: u64,
+    changes: ForkChanges,
+    metadata: EtfMetadata,
+) -> Instruction {
+    let (etf, _) = pda::etf_address(&lister, etf_index);
+
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::ForkETF {
+            etf,
+            parent,
+            registry: pda::registry_address().0,
+            registry_page: pda::registry_page_for_index(registry_index).0,
+            lister,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::ForkEtf {
+            etf_index,
+            changes,
+            name: metadata.name,
+            symbol: metadata.symbol,
+            uri: metadata.uri,
+            category: metadata.category,
+        }
+        .data(),
+    }
+}
+
+// Permissionless: grows an ETF listed before the registry to the current
+// layout; `registry_index` as for initialize_etf.
+pub fn migrate_etf(etf: Pubkey, payer: Pubkey, registry_index: u64) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::MigrateETF {
+            etf,
+            registry: pda::registry_address().0,
+            registry_page: pda::registry_page_for_index(registry_index).0,
+            payer,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::MigrateEtf {}.data(),
+    }
+}
+
+// Governance only: credits `investor` with `shares` of a migrated ETF's
+// legacy_shares, creating their position if needed
+pub fn restore_legacy_position(etf: Pubkey, investor: Pubkey, authority: Pubkey, shares: u64) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::RestoreLegacyPosition {
+            etf,
+            position: pda::position_address(&etf, &investor).0,
+            investor,
+            authority,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::RestoreLegacyPosition { shares }.data(),
+    }
+}
+
+// Permissionless: pays a fork's accrued parent fees to `parent_lister`
+pub fn claim_parent_fees(etf: Pubkey, parent_lister: Pubkey) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::ClaimParentFees { etf, parent_lister }.to_account_metas(None),
+        data: mtf_etf::instruction::ClaimParentFees {}.data(),
+    }
+}
+
+pub fn update_metadata(etf: Pubkey, lister: Pubkey, metadata: EtfMetadata) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::UpdateMetadata {
+            etf,
+            share_mint: pda::share_mint_address(&etf).0,
+            lister,
+            token_program: anchor_spl::token_2022::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::UpdateMetadata {
+            name: metadata.name,
+            symbol: metadata.symbol,
+            uri: metadata.uri,
+            category: metadata.category,
+        }
+        .data(),
+    }
+}
+
+// Buy into an ETF without a keeper; shares are minted immediately
+pub fn buy_etf(
+    etf: Pubkey,
+    lister: Pubkey,
+    investor: Pubkey,
+    sol_amount: u64,
+    token_percentages: Vec<u8>,
+) -> Instruction {
+    buy_etf_ix(etf, lister, investor, None, None, sol_amount, token_percentages)
+}
+
+// Buy into a keeper-settled ETF. `purchase_id` is the ETF's current
+// `purchase_count`; shares are minted when the keeper settles.
+pub fn buy_etf_pending(
+    etf: Pubkey,
+    lister: Pubkey,
+    investor: Pubkey,
+    purchase_id: u64,
+    sol_amount: u64,
+    token_percentages: Vec<u8>,
+) -> Instruction {
+    let pending_purchase = pda::pending_purchase_address(&etf, purchase_id).0;
+    buy_etf_ix(etf, lister, investor, Some(pending_purchase), None, sol_amount, token_percentages)
+}
+
+// Buy into a gated ETF. `gate_account` is the investor's allowlist pass
+// (pda::allowlist_pass_address) or their token account of the gate mint;
+// `purchase_id` as for buy_etf_pending when the ETF has a keeper.
+pub fn buy_etf_gated(
+    etf: Pubkey,
+    lister: Pubkey,
+    investor: Pubkey,
+    purchase_id: Option<u64>,
+    gate_account: Pubkey,
+    sol_amount: u64,
+    token_percentages: Vec<u8>,
+) -> Instruction {
+    let pending_purchase = purchase_id.map(|id| pda::pending_purchase_address(&etf, id).0);
+    buy_etf_ix(etf, lister, investor, pending_purchase, Some(gate_account), sol_amount, token_percentages)
+}
+
+fn buy_etf_ix(
+    etf: Pubkey,
+    lister: Pubkey,
+    investor: Pubkey,
+    pending_purchase: Option<Pubkey>,
+    gate_account: Option<Pubkey>,
+    sol_amount: u64,
+    token_percentages: Vec<u8>,
+) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::BuyETF {
+            etf,
+            position: pda::position_address(&etf, &investor).0,
+            investor,
+            lister_account: lister,
+            dev_wallet: TREASURY,
+            system_program: system_program::ID,
+            pending_purchase,
+            gate_account,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::BuyEtf {
+            sol_amount,
+            token_percentages,
+        }
+        .data(),
+    }
+}
+
+// `sources` are (mint, keeper token account) for every basket token, in the
+// ETF's token order; `token_amounts` are what each moves into the ETF's
+// vault of that mint. The vaults and price feeds are derived.
+pub fn settle_purchase(
+    etf: Pubkey,
+    investor: Pubkey,
+    keeper: Pubkey,
+    purchase_id: u64,
+    sources: &[(Pubkey, Pubkey)],
+    token_amounts: Vec<u64>,
+) -> Instruction {
+    let mut accounts = mtf_etf::accounts::SettlePurchase {
+        etf,
+        pending_purchase: pda::pending_purchase_address(&etf, purchase_id).0,
+        position: pda::position_address(&etf, &investor).0,
+        investor,
+        keeper,
+        token_program: anchor_spl::token::ID,
+    }
+    .to_account_metas(None);
+    for (mint, source) in sources {
+        accounts.extend([
+            AccountMeta::new(*source, false),
+            AccountMeta::new(pda::vault_address(&etf, mint), false),
+            AccountMeta::new_readonly(pda::price_feed_address(mint).0, false),
+        ]);
+    }
+
+    Instruction {
+        program_id: ID,
+        accounts,
+        data: mtf_etf::instruction::SettlePurchase { token_amounts }.data(),
+    }
+}
+
+// Lister only; see mtf_etf::EtfGate
+pub fn set_gate(etf: Pubkey, lister: Pubkey, gate: EtfGate) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::SetGate { etf, lister }.to_account_metas(None),
+        data: mtf_etf::instruction::SetGate { gate }.data(),
+    }
+}
+
+// Zero fields fall back to the protocol limits
+pub fn set_buy_limits(etf: Pubkey, lister: Pubkey, limits: BuyLimits) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::SetBuyLimits { etf, lister }.to_account_metas(None),
+        data: mtf_etf::instruction::SetBuyLimits { limits }.data(),
+    }
+}
+
+pub fn set_hold_period(etf: Pubkey, lister: Pubkey, min_hold_seconds: i64, early_exit_fee_bps: u16) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::SetHoldPeriod { etf, lister }.to_account_metas(None),
+        data: mtf_etf::instruction::SetHoldPeriod {
+            min_hold_seconds,
+            early_exit_fee_bps,
+        }
+        .data(),
+    }
+}
+
+// `proof` from crate::allowlist::Allowlist::proof
+pub fn verify_allowlist(etf: Pubkey, investor: Pubkey, proof: Vec<[u8; 32]>) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::VerifyAllowlist {
+            etf,
+            allowlist_pass: pda::allowlist_pass_address(&etf, &investor).0,
+            investor,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::VerifyAllowlist { proof }.data(),
+    }
+}
+
+// Governance only; creates the feed on first use
+pub fn set_price(authority: Pubkey, mint: Pubkey, price: u64) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::SetPrice {
+            price_feed: pda::price_feed_address(&mint).0,
+            mint,
+            authority,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::SetPrice { price }.data(),
+    }
+}
+
+// Read-only views; see crate::views for running them. `basket` is the ETF's
+// token_addresses, whose price feeds value its holdings; an ETF that holds
+// no tokens may pass an empty slice.
+pub fn quote_buy(etf: Pubkey, basket: &[Pubkey], sol_amount: u64, token_percentages: Vec<u8>) -> Instruction {
+    let mut accounts = mtf_etf::accounts::QuoteETF { etf }.to_account_metas(None);
+    accounts.extend(price_feed_metas(basket));
+    Instruction {
+        program_id: ID,
+        accounts,
+        data: mtf_etf::instruction::QuoteBuy {
+            sol_amount,
+            token_percentages,
+        }
+        .data(),
+    }
+}
+
+pub fn quote_sell(etf: Pubkey, shares: u64) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::QuoteETF { etf }.to_account_metas(None),
+        data: mtf_etf::instruction::QuoteSell { shares }.data(),
+    }
+}
+
+pub fn get_nav(etf: Pubkey, basket: &[Pubkey]) -> Instruction {
+    let mut accounts = mtf_etf::accounts::QuoteETF { etf }.to_account_metas(None);
+    accounts.extend(price_feed_metas(basket));
+    Instruction {
+        program_id: ID,
+        accounts,
+        data: mtf_etf::instruction::GetNav {}.data(),
+    }
+}
+
+pub fn get_nav_twap(etf: Pubkey, window_seconds: i64) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::QuoteNavTwap {
+            nav_history: pda::nav_history_address(&etf).0,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::GetNavTwap { window_seconds }.data(),
+    }
+}
+
+// Investor reclaims an unsettled purchase once its refund timeout has passed
+pub fn refund_pending_purchase(etf: Pubkey, investor: Pubkey, purchase_id: u64) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::RefundPendingPurchase {
+            etf,
+            pending_purchase: pda::pending_purchase_address(&etf, purchase_id).0,
+            investor,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::RefundPendingPurchase {}.data(),
+    }
+}
+
+// `refund_timeout` is in seconds, see mtf_etf::MIN/MAX_REFUND_TIMEOUT_SECONDS
+pub fn set_keeper(etf: Pubkey, lister: Pubkey, keeper: Option<Pubkey>, refund_timeout: i64) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::SetKeeper { etf, lister }.to_account_metas(None),
+        data: mtf_etf::instruction::SetKeeper { keeper, refund_timeout }.data(),
+    }
+}
+
+pub fn sell_etf(etf: Pubkey, lister: Pubkey, investor: Pubkey, tokens_to_sell: u64) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::SellETF {
+            etf,
+            position: pda::position_address(&etf, &investor).0,
+            investor,
+            lister_account: lister,
+            dev_wallet: TREASURY,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::SellEtf { tokens_to_sell }.data(),
+    }
+}
+
+// Permissionless; `payer` funds the history's rent
+pub fn create_nav_history(etf: Pubkey, payer: Pubkey) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::CreateNavHistory {
+            etf,
+            nav_history: pda::nav_history_address(&etf).0,
+            payer,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::CreateNavHistory {}.data(),
+    }
+}
+
+// Permissionless crank; no signer beyond the fee payer. `basket` as for
+// get_nav.
+pub fn snapshot_nav(etf: Pubkey, basket: &[Pubkey]) -> Instruction {
+    let mut accounts = mtf_etf::accounts::SnapshotNav {
+        etf,
+        nav_history: pda::nav_history_address(&etf).0,
+    }
+    .to_account_metas(None);
+    accounts.extend(price_feed_metas(basket));
+    Instruction {
+        program_id: ID,
+        accounts,
+        data: mtf_etf::instruction::SnapshotNav {}.data(),
+    }
+}
+
+// Permissionless; `payer` funds the leaderboard's rent
+pub fn create_leaderboard_epoch(epoch_id: u64, payer: Pubkey) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::CreateLeaderboardEpoch {
+            leaderboard: pda::leaderboard_address(epoch_id).0,
+            payer,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::CreateLeaderboardEpoch { epoch_id }.data(),
+    }
+}
+
+// Permissionless once the epoch has ended
+pub fn submit_epoch_return(epoch_id: u64, etf: Pubkey) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::SubmitEpochReturn {
+            leaderboard: pda::leaderboard_address(epoch_id).0,
+            etf,
+            nav_history: pda::nav_history_address(&etf).0,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::SubmitEpochReturn {}.data(),
+    }
+}
+
+// Governance creates the treasury or changes its rewards share
+pub fn configure_treasury(authority: Pubkey, rewards_share_bps: u16) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::ConfigureTreasury {
+            treasury: TREASURY,
+            authority,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::ConfigureTreasury { rewards_share_bps }.data(),
+    }
+}
+
+// Sweep collected dev fees and add `amount` lamports to the rewards pool
+pub fn fund_rewards(funder: Pubkey, amount: u64) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::FundRewards {
+            treasury: TREASURY,
+            funder,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::FundRewards { amount }.data(),
+    }
+}
+
+pub fn withdraw_dev_fees() -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::WithdrawDevFees {
+            treasury: TREASURY,
+            dev_wallet: DEV_WALLET,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::WithdrawDevFees {}.data(),
+    }
+}
+
+// Permissionless once the epoch's submissions have closed
+pub fn finalize_epoch(epoch_id: u64, payer: Pubkey) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::FinalizeEpoch {
+            leaderboard: pda::leaderboard_address(epoch_id).0,
+            epoch_rewards: pda::epoch_rewards_address(epoch_id).0,
+            treasury: TREASURY,
+            payer,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::FinalizeEpoch {}.data(),
+    }
+}
+
+// `rank` is the lister's position on the epoch's leaderboard (0 = best)
+pub fn claim_creator_reward(epoch_id: u64, lister: Pubkey, rank: u8) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::ClaimCreatorReward {
+            epoch_rewards: pda::epoch_rewards_address(epoch_id).0,
+            treasury: TREASURY,
+            lister,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::ClaimCreatorReward { rank }.data(),
+    }
+}
+
+// A Token-2022 mint carrying the ETF's name, symbol and uri
+pub fn create_share_mint(etf: Pubkey, payer: Pubkey) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::CreateShareMint {
+            etf,
+            share_mint: pda::share_mint_address(&etf).0,
+            payer,
+            token_program: anchor_spl::token_2022::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::CreateShareMint {}.data(),
+    }
+}
+
+// `destination` is any token account of the ETF's share mint
+pub fn wrap_shares(etf: Pubkey, owner: Pubkey, destination: Pubkey, amount: u64) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::WrapShares {
+            etf,
+            position: pda::position_address(&etf, &owner).0,
+            share_mint: pda::share_mint_address(&etf).0,
+            destination,
+            owner,
+            token_program: anchor_spl::token_2022::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::WrapShares { amount }.data(),
+    }
+}
+
+// Burns from `source` (authority `owner`) into `recipient`'s position
+pub fn unwrap_shares(etf: Pubkey, owner: Pubkey, source: Pubkey, recipient: Pubkey, amount: u64) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::UnwrapShares {
+            etf,
+            share_mint: pda::share_mint_address(&etf).0,
+            source,
+            owner,
+            recipient,
+            recipient_position: pda::position_address(&etf, &recipient).0,
+            token_program: anchor_spl::token_2022::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::UnwrapShares { amount }.data(),
+    }
+}
+
+// Opens the investor's position too, if they have none yet
+pub fn create_dca_plan(
+    etf: Pubkey,
+    investor: Pubkey,
+    amount_per_period: u64,
+    interval_seconds: i64,
+    token_percentages: Vec<u8>,
+    deposit: u64,
+) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::CreateDcaPlan {
+            etf,
+            dca_plan: pda::dca_plan_address(&etf, &investor).0,
+            position: pda::position_address(&etf, &investor).0,
+            investor,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::CreateDcaPlan {
+            amount_per_period,
+            interval_seconds,
+            token_percentages,
+            deposit,
+        }
+        .data(),
+    }
+}
+
+pub fn fund_dca_plan(etf: Pubkey, investor: Pubkey, amount: u64) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::FundDcaPlan {
+            dca_plan: pda::dca_plan_address(&etf, &investor).0,
+            investor,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::FundDcaPlan { amount }.data(),
+    }
+}
+
+// Signed by any `caller`, who earns the crank tip. `gate_account` as for
+// buy_etf_gated, for the plan's investor.
+pub fn execute_dca(
+    etf: Pubkey,
+    lister: Pubkey,
+    investor: Pubkey,
+    caller: Pubkey,
+    gate_account: Option<Pubkey>,
+) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::ExecuteDca {
+            etf,
+            dca_plan: pda::dca_plan_address(&etf, &investor).0,
+            position: pda::position_address(&etf, &investor).0,
+            lister_account: lister,
+            dev_wallet: TREASURY,
+            caller,
+            gate_account,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::ExecuteDca {}.data(),
+    }
+}
+
+pub fn cancel_dca_plan(etf: Pubkey, investor: Pubkey) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::CancelDcaPlan {
+            etf,
+            dca_plan: pda::dca_plan_address(&etf, &investor).0,
+            investor,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::CancelDcaPlan {}.data(),
+    }
+}
+
+// `amount` is lamports (fees included) for buys and shares for sells;
+// `token_percentages` is empty for sells. `deposit` pays for a fill on a
+// keeper ETF and the rest comes back with the order. Opens the owner's
+// position too, if they have none yet.
+#[allow(clippy::too_many_arguments)]
+pub fn place_limit_order(
+    etf: Pubkey,
+    owner: Pubkey,
+    order_id: u64,
+    side: OrderSide,
+    amount: u64,
+    target_nav: u64,
+    token_percentages: Vec<u8>,
+    deposit: u64,
+) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::PlaceLimitOrder {
+            etf,
+            order: pda::limit_order_address(&etf, &owner, order_id).0,
+            position: pda::position_address(&etf, &owner).0,
+            owner,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::PlaceLimitOrder {
+            order_id,
+            side,
+            amount,
+            target_nav,
+            token_percentages,
+            deposit,
+        }
+        .data(),
+    }
+}
+
+// Signed by any `filler`, for `order` of the ETF at `etf` whose state is
+// `state`. `gate_account` as for buy_etf_gated, for the order's owner (buy
+// orders only). `vaults` are (vault, owner token account) pairs, one per
+// basket token in basket order, for a sell the ETF pays in kind because it
+// holds basket tokens; empty otherwise.
+pub fn fill_limit_order(
+    etf: Pubkey,
+    state: &ETF,
+    order: &LimitOrder,
+    filler: Pubkey,
+    gate_account: Option<Pubkey>,
+    vaults: &[(Pubkey, Pubkey)],
+) -> Instruction {
+    // A buy on a keeper ETF becomes the next pending purchase
+    let pending_purchase = (order.side == OrderSide::Buy && state.keeper.is_some())
+        .then(|| pda::pending_purchase_address(&etf, state.purchase_count).0);
+    let mut accounts = mtf_etf::accounts::FillLimitOrder {
+        etf,
+        order: pda::limit_order_address(&etf, &order.owner, order.order_id).0,
+        position: pda::position_address(&etf, &order.owner).0,
+        owner: order.owner,
+        lister_account: state.lister,
+        dev_wallet: TREASURY,
+        filler,
+        gate_account,
+        pending_purchase,
+        token_program: anchor_spl::token::ID,
+        system_program: system_program::ID,
+    }
+    .to_account_metas(None);
+    accounts.extend(price_feed_metas(&state.token_addresses));
+    accounts.extend(vault_metas(vaults));
+
+    Instruction {
+        program_id: ID,
+        accounts,
+        data: mtf_etf::instruction::FillLimitOrder {}.data(),
+    }
+}
+
+// Sell orders return their shares to the position, so it is passed for them
+pub fn cancel_limit_order(etf: Pubkey, owner: Pubkey, order_id: u64, side: OrderSide) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::CancelLimitOrder {
+            etf,
+            order: pda::limit_order_address(&etf, &owner, order_id).0,
+            position: (side == OrderSide::Sell).then(|| pda::position_address(&etf, &owner).0),
+            owner,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::CancelLimitOrder {}.data(),
+    }
+}
+
+// `vaults` are (vault, investor token account) pairs, one per basket token
+// in basket order; `basket` is the ETF's token_addresses, whose price feeds
+// follow them.
+pub fn redeem_to_tokens(
+    etf: Pubkey,
+    lister: Pubkey,
+    investor: Pubkey,
+    shares: u64,
+    vaults: &[(Pubkey, Pubkey)],
+    basket: &[Pubkey],
+) -> Instruction {
+    let mut accounts = mtf_etf::accounts::RedeemToTokens {
+        etf,
+        position: pda::position_address(&etf, &investor).0,
+        investor,
+        lister_account: lister,
+        dev_wallet: TREASURY,
+        token_program: anchor_spl::token::ID,
+        system_program: system_program::ID,
+    }
+    .to_account_metas(None);
+    accounts.extend(vault_metas(vaults));
+    accounts.extend(price_feed_metas(basket));
+
+    Instruction {
+        program_id: ID,
+        accounts,
+        data: mtf_etf::instruction::RedeemToTokens { shares }.data(),
+    }
+}
+
+// Zero disables a threshold; `shares` of 0 sells the whole position.
+// Funds the crank tip and `deposit`, which pays an in-kind exit's fees.
+pub fn set_exit_trigger(
+    etf: Pubkey,
+    investor: Pubkey,
+    stop_loss_nav: u64,
+    take_profit_nav: u64,
+    shares: u64,
+    deposit: u64,
+) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::SetExitTrigger {
+            etf,
+            exit_trigger: pda::exit_trigger_address(&etf, &investor).0,
+            position: pda::position_address(&etf, &investor).0,
+            investor,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::SetExitTrigger {
+            stop_loss_nav,
+            take_profit_nav,
+            shares,
+            deposit,
+        }
+        .data(),
+    }
+}
+
+// Signed by any `caller`, who earns the crank tip. `vaults` are (vault,
+// investor token account) pairs in basket order, needed while the ETF holds
+// tokens.
+pub fn trigger_exit(
+    etf: Pubkey,
+    state: &ETF,
+    investor: Pubkey,
+    caller: Pubkey,
+    vaults: &[(Pubkey, Pubkey)],
+) -> Instruction {
+    let mut accounts = mtf_etf::accounts::TriggerExit {
+        etf,
+        exit_trigger: pda::exit_trigger_address(&etf, &investor).0,
+        position: pda::position_address(&etf, &investor).0,
+        investor,
+        lister_account: state.lister,
+        dev_wallet: TREASURY,
+        caller,
+        token_program: anchor_spl::token::ID,
+    }
+    .to_account_metas(None);
+    accounts.extend(price_feed_metas(&state.token_addresses));
+    accounts.extend(vault_metas(vaults));
+
+    Instruction {
+        program_id: ID,
+        accounts,
+        data: mtf_etf::instruction::TriggerExit {}.data(),
+    }
+}
+
+pub fn cancel_exit_trigger(etf: Pubkey, investor: Pubkey) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::CancelExitTrigger {
+            etf,
+            exit_trigger: pda::exit_trigger_address(&etf, &investor).0,
+            investor,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::CancelExitTrigger {}.data(),
+    }
+}
+
+// `vaults` are (vault, lister token account) pairs, one per basket token in
+// basket order; each vault is drained to the lister and closed.
+pub fn close_etf(
+    etf: Pubkey,
+    lister: Pubkey,
+    registry_index: u64,
+    vaults: &[(Pubkey, Pubkey)],
+) -> Instruction {
+    let mut accounts = mtf_etf::accounts::CloseETF {
+        etf,
+        registry: pda::registry_address().0,
+        registry_page: pda::registry_page_for_index(registry_index).0,
+        share_mint: pda::share_mint_address(&etf).0,
+        lister,
+        token_program: anchor_spl::token::ID,
+        system_program: system_program::ID,
+    }
+    .to_account_metas(None);
+    accounts.extend(vault_metas(vaults));
+
+    Instruction {
+        program_id: ID,
+        accounts,
+        data: mtf_etf::instruction::CloseEtf {}.data(),
+    }
+}
+
+fn vault_metas(vaults: &[(Pubkey, Pubkey)]) -> impl Iterator<Item = AccountMeta> + '_ {
+    vaults.iter().flat_map(|(vault, destination)| {
+        [
+            AccountMeta::new(*vault, false),
+            AccountMeta::new(*destination, false),
+        ]
+    })
+}
+
+fn price_feed_metas(basket: &[Pubkey]) -> impl Iterator<Item = AccountMeta> + '_ {
+    basket
+        .iter()
+        .map(|mint| AccountMeta::new_readonly(pda::price_feed_address(mint).0, false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::Discriminator;
+
+    fn metadata() -> EtfMetadata {
+        EtfMetadata {
+            name: "Dog Coins".to_string(),
+            symbol: "DOGS".to_string(),
+            uri: String::new(),
+            category: EtfCategory::Meme,
+        }
+    }
+
+    #[test]
+    fn test_initialize_etf_accounts() {
+        let lister = Pubkey::new_unique();
+        let ix = initialize_etf(lister, 0, 0, vec![Pubkey::new_unique()], metadata());
+
+        assert_eq!(ix.program_id, ID);
+        assert_eq!(ix.accounts[0].pubkey, pda::etf_address(&lister, 0).0);
+        assert_eq!(ix.accounts[1].pubkey, pda::registry_address().0);
+        assert_eq!(ix.accounts[2].pubkey, pda::registry_page_address(0).0);
+        assert!(ix.accounts[3].is_signer);
+        assert!(ix
+            .data
+            .starts_with(mtf_etf::instruction::InitializeEtf::DISCRIMINATOR));
+    }
+
+    #[test]
+    fn test_fork_etf_accounts() {
+        let lister = Pubkey::new_unique();
+        let parent = Pubkey::new_unique();
+        let ix = fork_etf(lister, 1, parent, 0, ForkChanges::default(), metadata());
+
+        assert_eq!(ix.accounts[0].pubkey, pda::etf_address(&lister, 1).0);
+        assert_eq!(ix.accounts[1].pubkey, parent);
+        assert!(!ix.accounts[1].is_writable);
+        assert_eq!(ix.accounts[3].pubkey, pda::registry_page_address(0).0);
+        assert!(ix.accounts[4].is_signer);
+        assert!(ix
+            .data
+            .starts_with(mtf_etf::instruction::ForkEtf::DISCRIMINATOR));
+    }
+
+    #[test]
+    fn test_buy_etf_uses_dev_wallet_and_position() {
+        let etf = Pubkey::new_unique();
+        let investor = Pubkey::new_unique();
+        let ix = buy_etf(
+            etf,
+            Pubkey::new_unique(),
+            investor,
+            1_000_000_000,
+            vec![100],
+        );
+
+        assert!(ix
+            .accounts
+            .iter()
+            .any(|meta| meta.pubkey == TREASURY && meta.is_writable));
+        assert_eq!(
+            ix.accounts[1].pubkey,
+            pda::position_address(&etf, &investor).0
+        );
+    }
+
+    #[test]
+    fn test_buy_etf_pending_appends_pending_account() {
+        let etf = Pubkey::new_unique();
+        let instant = buy_etf(etf, Pubkey::new_unique(), Pubkey::new_unique(), 1, vec![100]);
+        let pending = buy_etf_pending(etf, Pubkey::new_unique(), Pubkey::new_unique(), 7, 1, vec![100]);
+
+        // Optional account slots hold the program id when absent; the gate
+        // account follows the pending purchase
+        let slot = instant.accounts.len() - 2;
+        assert_eq!(instant.accounts[slot].pubkey, ID);
+        assert_eq!(pending.accounts[slot].pubkey, pda::pending_purchase_address(&etf, 7).0);
+        assert!(pending.accounts[slot].is_writable);
+        assert_eq!(instant.accounts.len(), pending.accounts.len());
+    }
+
+    #[test]
+    fn test_buy_etf_gated_appends_gate_account() {
+        let etf = Pubkey::new_unique();
+        let gate_account = Pubkey::new_unique();
+        let instant = buy_etf(etf, Pubkey::new_unique(), Pubkey::new_unique(), 1, vec![100]);
+        let gated = buy_etf_gated(etf, Pubkey::new_unique(), Pubkey::new_unique(), None, gate_account, 1, vec![100]);
+
+        assert_eq!(instant.accounts.last().unwrap().pubkey, ID);
+        assert_eq!(gated.accounts.last().unwrap().pubkey, gate_account);
+        assert!(!gated.accounts.last().unwrap().is_writable);
+        assert_eq!(gated.accounts[gated.accounts.len() - 2].pubkey, ID);
+    }
+
+    #[test]
+    fn test_refund_pending_purchase_accounts() {
+        let etf = Pubkey::new_unique();
+        let investor = Pubkey::new_unique();
+        let ix = refund_pending_purchase(etf, investor, 3);
+
+        assert_eq!(ix.accounts[1].pubkey, pda::pending_purchase_address(&etf, 3).0);
+        assert_eq!(ix.accounts[2].pubkey, investor);
+        assert!(ix.accounts[2].is_signer && ix.accounts[2].is_writable);
+    }
+
+    #[test]
+    fn test_sell_etf_data_layout() {
+        let ix = sell_etf(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            42,
+        );
+        let discriminator = mtf_etf::instruction::SellEtf::DISCRIMINATOR;
+        assert_eq!(&ix.data[..discriminator.len()], discriminator);
+        assert_eq!(&ix.data[discriminator.len()..], &42u64.to_le_bytes());
+    }
+
+    #[test]
+    fn test_unwrap_shares_credits_recipient_position() {
+        let etf = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        let ix = unwrap_shares(etf, owner, Pubkey::new_unique(), recipient, 5);
+
+        assert_eq!(ix.accounts[1].pubkey, pda::share_mint_address(&etf).0);
+        assert!(ix.accounts[3].is_signer && ix.accounts[3].is_writable);
+        assert!(!ix.accounts[4].is_signer);
+        assert_eq!(ix.accounts[5].pubkey, pda::position_address(&etf, &recipient).0);
+    }
+
+    #[test]
+    fn test_close_etf_appends_vault_pairs() {
+        let vaults = [
+            (Pubkey::new_unique(), Pubkey::new_unique()),
+            (Pubkey::new_unique(), Pubkey::new_unique()),
+        ];
+        let ix = close_etf(Pubkey::new_unique(), Pubkey::new_unique(), 0, &vaults);
+        let base = mtf_etf::accounts::CloseETF {
+            etf: Pubkey::default(),
+            registry: Pubkey::default(),
+            registry_page: Pubkey::default(),
+            share_mint: Pubkey::default(),
+            lister: Pubkey::default(),
+            token_program: Pubkey::default(),
+            system_program: Pubkey::default(),
+        }
+        .to_account_metas(None)
+        .len();
+
+        assert_eq!(ix.accounts.len(), base + 4);
+        assert_eq!(ix.accounts[base].pubkey, vaults[0].0);
+        assert_eq!(ix.accounts[base + 3].pubkey, vaults[1].1);
+    }
+
+    #[test]
+    fn test_snapshot_nav_needs_no_signer() {
+        let etf = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let ix = snapshot_nav(etf, &[mint]);
+
+        assert_eq!(ix.accounts[1].pubkey, pda::nav_history_address(&etf).0);
+        assert!(ix.accounts[1].is_writable);
+        assert_eq!(ix.accounts[2].pubkey, pda::price_feed_address(&mint).0);
+        assert!(ix.accounts.iter().all(|meta| !meta.is_signer));
+    }
+
+    #[test]
+    fn test_settle_purchase_derives_vaults_and_feeds() {
+        let etf = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let source = Pubkey::new_unique();
+        let ix = settle_purchase(
+            etf,
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            0,
+            &[(mint, source)],
+            vec![5],
+        );
+        let triple = &ix.accounts[ix.accounts.len() - 3..];
+
+        assert_eq!(triple[0].pubkey, source);
+        assert_eq!(triple[1].pubkey, pda::vault_address(&etf, &mint));
+        assert!(triple[1].is_writable);
+        assert_eq!(triple[2].pubkey, pda::price_feed_address(&mint).0);
+        assert!(!triple[2].is_writable);
+    }
+
+    #[test]
+    fn test_execute_dca_targets_investor_accounts() {
+        let etf = Pubkey::new_unique();
+        let investor = Pubkey::new_unique();
+        let caller = Pubkey::new_unique();
+        let ix = execute_dca(etf, Pubkey::new_unique(), investor, caller, None);
+
+        assert_eq!(ix.accounts[1].pubkey, pda::dca_plan_address(&etf, &investor).0);
+        assert_eq!(ix.accounts[2].pubkey, pda::position_address(&etf, &investor).0);
+        let caller_meta = ix.accounts.iter().find(|meta| meta.pubkey == caller).unwrap();
+        assert!(caller_meta.is_signer && caller_meta.is_writable);
+        // Ungated: the optional gate slot holds the program id
+        assert_eq!(ix.accounts.last().unwrap().pubkey, ID);
+    }
+
+    #[test]
+    fn test_cancel_limit_order_passes_position_for_sells() {
+        let etf = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let position = pda::position_address(&etf, &owner).0;
+
+        let sell = cancel_limit_order(etf, owner, 3, OrderSide::Sell);
+        assert_eq!(sell.accounts[1].pubkey, pda::limit_order_address(&etf, &owner, 3).0);
+        assert_eq!(sell.accounts[2].pubkey, position);
+        assert!(sell.accounts[2].is_writable);
+
+        let buy = cancel_limit_order(etf, owner, 3, OrderSide::Buy);
+        assert_eq!(buy.accounts[2].pubkey, ID);
+    }
+
+    #[test]
+    fn test_trigger_exit_pays_investor_and_tips_caller() {
+        let etf = Pubkey::new_unique();
+        let investor = Pubkey::new_unique();
+        let caller = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let state = ETF {
+            lister: Pubkey::new_unique(),
+            token_addresses: vec![mint],
+            ..Default::default()
+        };
+        let pair = (pda::vault_address(&etf, &mint), Pubkey::new_unique());
+        let ix = trigger_exit(etf, &state, investor, caller, &[pair]);
+
+        assert_eq!(ix.accounts[1].pubkey, pda::exit_trigger_address(&etf, &investor).0);
+        let investor_meta = ix.accounts.iter().find(|meta| meta.pubkey == investor).unwrap();
+        assert!(investor_meta.is_writable && !investor_meta.is_signer);
+        let caller_meta = ix.accounts.iter().find(|meta| meta.pubkey == caller).unwrap();
+        assert!(caller_meta.is_signer && caller_meta.is_writable);
+        // The feeds, then the (vault, investor account) pairs of an in-kind exit
+        let tail = &ix.accounts[ix.accounts.len() - 3..];
+        assert_eq!(tail[0].pubkey, pda::price_feed_address(&mint).0);
+        assert_eq!((tail[1].pubkey, tail[2].pubkey), pair);
+        assert!(tail[1].is_writable && tail[2].is_writable);
+    }
+}
@@ -0,0 +1,20 @@
+// Rust client for the mtf_etf program.
+//
+// Mirrors lib/anchor/client.ts for Rust bots and tools: PDA derivation,
+// instruction builders, account fetching/decoding, fee/quote math,
+// simulated on-chain quotes and allowlist proofs.
+// Instruction and account layouts come from the program crate itself, so
+// they cannot drift from what is deployed.
+
+pub mod accounts;
+pub mod allowlist;
+pub mod error;
+pub mod instructions;
+pub mod math;
+pub mod pda;
+pub mod rpc;
+pub mod views;
+
+pub use error::{ClientError, Result};
+pub use mtf_etf::{self as program, ID as PROGRAM_ID};
+pub use rpc::RpcClient;
@@ -0,0 +1,124 @@
+// Fee and quote math - thin wrappers over the program's own helpers so
+// quotes always match what buy_etf / sell_etf will charge.
+use mtf_etf::{calculate_fees, liquidation_payout, shares_for_value, token_allocation, NAV_SCALE};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuyQuote {
+    pub sol_amount: u64,
+    pub creator_fee: u64,
+    pub dev_fee: u64,
+    pub sol_after_fees: u64,
+    pub shares_out: u64,       // Minted at NAV for the SOL after fees
+    pub allocations: Vec<u64>, // SOL routed to each basket token
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SellQuote {
+    pub shares_in: u64,
+    pub creator_fee: u64,
+    pub dev_fee: u64,
+    pub sol_out: u64, // SOL received by the investor after fees
+}
+
+// `total_supply` and `net_assets` describe the ETF bought into; an empty
+// ETF (both 0) mints 1:1
+pub fn quote_buy(sol_amount: u64, token_percentages: &[u8], total_supply: u64, net_assets: u64) -> BuyQuote {
+    let (creator_fee, dev_fee) = calculate_fees(sol_amount);
+    let sol_after_fees = sol_amount - creator_fee - dev_fee;
+    let allocations = token_percentages
+        .iter()
+        .map(|&percentage| token_allocation(sol_after_fees, percentage))
+        .collect();
+
+    BuyQuote {
+        sol_amount,
+        creator_fee,
+        dev_fee,
+        sol_after_fees,
+        shares_out: shares_for_value(sol_after_fees, total_supply, net_assets),
+        allocations,
+    }
+}
+
+// Shares redeem pro rata for the ETF's booked SOL (`sol_assets`) before fees
+pub fn quote_sell(shares_in: u64, total_supply: u64, sol_assets: u64) -> SellQuote {
+    let sol_value = liquidation_payout(shares_in, total_supply, sol_assets);
+    let (creator_fee, dev_fee) = calculate_fees(sol_value);
+
+    SellQuote {
+        shares_in,
+        creator_fee,
+        dev_fee,
+        sol_out: sol_value - creator_fee - dev_fee,
+    }
+}
+
+// Creator and dev fees of an in-kind exit of `shares` at `nav_per_share`,
+// which a sell order or exit trigger on a keeper ETF pays from its deposit
+pub fn in_kind_exit_fees(shares: u64, nav_per_share: u64) -> u64 {
+    let value = (shares as u128 * nav_per_share as u128 / NAV_SCALE as u128).min(u64::MAX as u128) as u64;
+    let (creator_fee, dev_fee) = calculate_fees(value);
+    creator_fee + dev_fee
+}
+
+// Equal-weight percentages summing to 100; the remainder goes to the first tokens
+pub fn even_percentages(token_count: usize) -> Vec<u8> {
+    if token_count == 0 {
+        return Vec::new();
+    }
+    let base = (100 / token_count) as u8;
+    let remainder = 100 % token_count;
+    (0..token_count)
+        .map(|i| if i < remainder { base + 1 } else { base })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote_buy_1_sol() {
+        let quote = quote_buy(1_000_000_000, &[50, 30, 20], 0, 0);
+        assert_eq!(quote.creator_fee, 5_000_000);
+        assert_eq!(quote.dev_fee, 5_000_000);
+        assert_eq!(quote.shares_out, 990_000_000);
+        assert_eq!(
+            quote.allocations,
+            vec![495_000_000, 297_000_000, 198_000_000]
+        );
+    }
+
+    #[test]
+    fn test_quote_buy_at_nav() {
+        // Two lamports of assets per share: half as many shares
+        let quote = quote_buy(1_000_000_000, &[100], 500, 1_000);
+        assert_eq!(quote.shares_out, 495_000_000);
+    }
+
+    #[test]
+    fn test_quote_sell_round_trip() {
+        let buy = quote_buy(1_000_000_000, &[100], 0, 0);
+        let sell = quote_sell(buy.shares_out, buy.shares_out, buy.sol_after_fees);
+        assert_eq!(sell.sol_out, 980_100_000);
+    }
+
+    #[test]
+    fn test_in_kind_exit_fees() {
+        assert_eq!(in_kind_exit_fees(1_000_000_000, NAV_SCALE), 10_000_000);
+        // Fees follow the value of the shares, not their count
+        assert_eq!(in_kind_exit_fees(1_000_000_000, 2 * NAV_SCALE), 20_000_000);
+        assert_eq!(in_kind_exit_fees(0, NAV_SCALE), 0);
+    }
+
+    #[test]
+    fn test_even_percentages() {
+        assert_eq!(even_percentages(1), vec![100]);
+        assert_eq!(even_percentages(3), vec![34, 33, 33]);
+        assert_eq!(
+            even_percentages(7).iter().map(|&p| p as u16).sum::<u16>(),
+            100
+        );
+        assert!(even_percentages(0).is_empty());
+    }
+}
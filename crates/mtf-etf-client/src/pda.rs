@@ -0,0 +1,133 @@
+// PDA derivation - seeds must match the #[account(seeds = ...)] constraints in the program
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::associated_token::get_associated_token_address;
+use mtf_etf::{registry_page_index, ID};
+
+// ["etf", lister, etf_index]
+pub fn etf_address(lister: &Pubkey, etf_index: u8) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"etf", lister.as_ref(), &[etf_index]], &ID)
+}
+
+// ["registry"]
+pub fn registry_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"registry"], &ID)
+}
+
+// ["registry_page", page_index (u32 LE)]
+pub fn registry_page_address(page_index: u32) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"registry_page", &page_index.to_le_bytes()], &ID)
+}
+
+// Registry page holding the ETF registered at `registry_index`
+pub fn registry_page_for_index(registry_index: u64) -> (Pubkey, u8) {
+    registry_page_address(registry_page_index(registry_index))
+}
+
+// ["position", etf, investor]
+pub fn position_address(etf: &Pubkey, investor: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"position", etf.as_ref(), investor.as_ref()], &ID)
+}
+
+// ["pending", etf, purchase_id (u64 LE)]
+pub fn pending_purchase_address(etf: &Pubkey, purchase_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"pending", etf.as_ref(), &purchase_id.to_le_bytes()], &ID)
+}
+
+// ["shares", etf] - SPL mint for wrapped shares, authority is the ETF PDA
+pub fn share_mint_address(etf: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"shares", etf.as_ref()], &ID)
+}
+
+// ["allowlist", etf, investor]
+pub fn allowlist_pass_address(etf: &Pubkey, investor: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"allowlist", etf.as_ref(), investor.as_ref()], &ID)
+}
+
+// ["dca", etf, investor]
+pub fn dca_plan_address(etf: &Pubkey, investor: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"dca", etf.as_ref(), investor.as_ref()], &ID)
+}
+
+// ["order", etf, owner, order_id as u64 LE]
+pub fn limit_order_address(etf: &Pubkey, owner: &Pubkey, order_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"order", etf.as_ref(), owner.as_ref(), &order_id.to_le_bytes()], &ID)
+}
+
+// ["exit", etf, investor]
+pub fn exit_trigger_address(etf: &Pubkey, investor: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"exit", etf.as_ref(), investor.as_ref()], &ID)
+}
+
+// ["nav_history", etf]
+pub fn nav_history_address(etf: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"nav_history", etf.as_ref()], &ID)
+}
+
+// ["leaderboard", epoch_id as u64 LE]
+pub fn leaderboard_address(epoch_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"leaderboard", &epoch_id.to_le_bytes()], &ID)
+}
+
+// ["epoch_rewards", epoch_id as u64 LE]
+pub fn epoch_rewards_address(epoch_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"epoch_rewards", &epoch_id.to_le_bytes()], &ID)
+}
+
+// ["price", mint]
+pub fn price_feed_address(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"price", mint.as_ref()], &ID)
+}
+
+// Basket vault - the ETF PDA's associated token account for `mint`
+pub fn vault_address(etf: &Pubkey, mint: &Pubkey) -> Pubkey {
+    get_associated_token_address(etf, mint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mtf_etf::REGISTRY_PAGE_SIZE;
+
+    #[test]
+    fn test_etf_address_is_deterministic() {
+        let lister = Pubkey::new_unique();
+        assert_eq!(etf_address(&lister, 0), etf_address(&lister, 0));
+        assert_ne!(etf_address(&lister, 0).0, etf_address(&lister, 1).0);
+    }
+
+    #[test]
+    fn test_etf_address_matches_seeds() {
+        let lister = Pubkey::new_unique();
+        let (address, bump) = etf_address(&lister, 3);
+        let expected =
+            Pubkey::create_program_address(&[b"etf", lister.as_ref(), &[3], &[bump]], &ID).unwrap();
+        assert_eq!(address, expected);
+    }
+
+    #[test]
+    fn test_registry_page_for_index() {
+        assert_eq!(registry_page_for_index(0), registry_page_address(0));
+        assert_eq!(
+            registry_page_for_index(REGISTRY_PAGE_SIZE as u64),
+            registry_page_address(1)
+        );
+    }
+
+    #[test]
+    fn test_pending_purchase_address_per_id() {
+        let etf = Pubkey::new_unique();
+        assert_ne!(pending_purchase_address(&etf, 0).0, pending_purchase_address(&etf, 1).0);
+        assert_ne!(
+            pending_purchase_address(&etf, 0).0,
+            pending_purchase_address(&Pubkey::new_unique(), 0).0
+        );
+    }
+
+    #[test]
+    fn test_position_address_per_investor() {
+        let etf = Pubkey::new_unique();
+        let a = Pubkey::new_unique();
+        let b = Pubkey::new_unique();
+        assert_ne!(position_address(&etf, &a).0, position_address(&etf, &b).0);
+    }
+}
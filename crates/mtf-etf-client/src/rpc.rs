@@ -0,0 +1,317 @@
+// Minimal blocking JSON-RPC client - just the calls the SDK and tools need.
+use std::str::FromStr;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::hash::Hash;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde_json::{json, Value};
+
+use crate::error::{ClientError, Result};
+
+pub const DEVNET_RPC_URL: &str = "https://api.devnet.solana.com";
+pub const MAINNET_RPC_URL: &str = "https://api.mainnet-beta.solana.com";
+pub const LOCALNET_RPC_URL: &str = "http://127.0.0.1:8899";
+
+// getProgramAccounts memcmp filter
+#[derive(Debug, Clone)]
+pub struct MemcmpFilter {
+    pub offset: usize,
+    pub bytes: Vec<u8>,
+}
+
+// Result of simulateTransaction
+#[derive(Debug, Clone)]
+pub struct Simulation {
+    pub err: Option<Value>,
+    pub logs: Vec<String>,
+    pub return_data: Option<Vec<u8>>,
+}
+
+// One entry of getSignaturesForAddress
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureInfo {
+    pub signature: String,
+    pub slot: u64,
+    pub failed: bool,
+}
+
+pub struct RpcClient {
+    url: String,
+    commitment: String,
+    agent: ureq::Agent,
+}
+
+impl RpcClient {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self::new_with_commitment(url, "confirmed")
+    }
+
+    pub fn new_with_commitment(url: impl Into<String>, commitment: &str) -> Self {
+        Self {
+            url: url.into(),
+            commitment: commitment.to_string(),
+            agent: ureq::Agent::new(),
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    fn request(&self, method: &str, params: Value) -> Result<Value> {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        });
+        let response: Value = self
+            .agent
+            .post(&self.url)
+            .send_json(body)
+            .map_err(|e| ClientError::Transport(e.to_string()))?
+            .into_json()
+            .map_err(|e| ClientError::Transport(e.to_string()))?;
+
+        if let Some(error) = response.get("error") {
+            return Err(ClientError::Rpc {
+                code: error["code"].as_i64().unwrap_or_default(),
+                message: error["message"].as_str().unwrap_or_default().to_string(),
+            });
+        }
+        response
+            .get("result")
+            .cloned()
+            .ok_or_else(|| ClientError::InvalidResponse(format!("{method}: missing result")))
+    }
+
+    pub fn get_account_data(&self, address: &Pubkey) -> Result<Option<Vec<u8>>> {
+        let result = self.request(
+            "getAccountInfo",
+            json!([address.to_string(), { "encoding": "base64", "commitment": self.commitment }]),
+        )?;
+        match &result["value"] {
+            Value::Null => Ok(None),
+            value => decode_base64_data(&value["data"]).map(Some),
+        }
+    }
+
+    pub fn get_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        filters: &[MemcmpFilter],
+    ) -> Result<Vec<(Pubkey, Vec<u8>)>> {
+        let filters: Vec<Value> = filters
+            .iter()
+            .map(|filter| {
+                json!({ "memcmp": {
+                    "offset": filter.offset,
+                    "bytes": BASE64.encode(&filter.bytes),
+                    "encoding": "base64",
+                }})
+            })
+            .collect();
+        let result = self.request(
+            "getProgramAccounts",
+            json!([program_id.to_string(), {
+                "encoding": "base64",
+                "commitment": self.commitment,
+                "filters": filters,
+            }]),
+        )?;
+
+        result
+            .as_array()
+            .ok_or_else(|| {
+                ClientError::InvalidResponse("getProgramAccounts: expected array".into())
+            })?
+            .iter()
+            .map(|entry| {
+                let pubkey = parse_pubkey(&entry["pubkey"])?;
+                let data = decode_base64_data(&entry["account"]["data"])?;
+                Ok((pubkey, data))
+            })
+            .collect()
+    }
+
+    pub fn get_balance(&self, address: &Pubkey) -> Result<u64> {
+        let result = self.request(
+            "getBalance",
+            json!([address.to_string(), { "commitment": self.commitment }]),
+        )?;
+        result["value"]
+            .as_u64()
+            .ok_or_else(|| ClientError::InvalidResponse("getBalance: missing value".into()))
+    }
+
+    pub fn get_slot(&self) -> Result<u64> {
+        let result = self.request("getSlot", json!([{ "commitment": self.commitment }]))?;
+        result
+            .as_u64()
+            .ok_or_else(|| ClientError::InvalidResponse("getSlot: expected number".into()))
+    }
+
+    pub fn get_latest_blockhash(&self) -> Result<Hash> {
+        let result = self.request(
+            "getLatestBlockhash",
+            json!([{ "commitment": self.commitment }]),
+        )?;
+        let blockhash = result["value"]["blockhash"].as_str().ok_or_else(|| {
+            ClientError::InvalidResponse("getLatestBlockhash: missing blockhash".into())
+        })?;
+        Hash::from_str(blockhash).map_err(|e| ClientError::InvalidResponse(e.to_string()))
+    }
+
+    // Send a serialized, signed transaction; returns the signature
+    pub fn send_transaction(&self, wire_transaction: &[u8]) -> Result<String> {
+        let result = self.request(
+            "sendTransaction",
+            json!([BASE64.encode(wire_transaction), {
+                "encoding": "base64",
+                "preflightCommitment": self.commitment,
+            }]),
+        )?;
+        result.as_str().map(str::to_string).ok_or_else(|| {
+            ClientError::InvalidResponse("sendTransaction: expected signature".into())
+        })
+    }
+
+    // Simulate a serialized transaction (signatures are not verified)
+    pub fn simulate_transaction(&self, wire_transaction: &[u8]) -> Result<Simulation> {
+        let result = self.request(
+            "simulateTransaction",
+            json!([BASE64.encode(wire_transaction), {
+                "encoding": "base64",
+                "commitment": self.commitment,
+                "sigVerify": false,
+                "replaceRecentBlockhash": true,
+            }]),
+        )?;
+        let value = &result["value"];
+        let logs = value["logs"]
+            .as_array()
+            .map(|logs| {
+                logs.iter()
+                    .filter_map(|l| l.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let return_data = match &value["returnData"] {
+            Value::Null => None,
+            data => Some(decode_base64_data(&data["data"])?),
+        };
+
+        Ok(Simulation {
+            err: value.get("err").filter(|err| !err.is_null()).cloned(),
+            logs,
+            return_data,
+        })
+    }
+
+    // Signatures involving `address`, newest first; page backwards with `before`
+    pub fn get_signatures_for_address(
+        &self,
+        address: &Pubkey,
+        before: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<SignatureInfo>> {
+        let mut config = json!({ "commitment": self.commitment, "limit": limit });
+        if let Some(before) = before {
+            config["before"] = json!(before);
+        }
+        let result = self.request(
+            "getSignaturesForAddress",
+            json!([address.to_string(), config]),
+        )?;
+
+        result
+            .as_array()
+            .ok_or_else(|| {
+                ClientError::InvalidResponse("getSignaturesForAddress: expected array".into())
+            })?
+            .iter()
+            .map(|entry| {
+                let signature = entry["signature"].as_str().ok_or_else(|| {
+                    ClientError::InvalidResponse("getSignaturesForAddress: missing signature".into())
+                })?;
+                let slot = entry["slot"].as_u64().ok_or_else(|| {
+                    ClientError::InvalidResponse("getSignaturesForAddress: missing slot".into())
+                })?;
+                Ok(SignatureInfo {
+                    signature: signature.to_string(),
+                    slot,
+                    failed: !entry["err"].is_null(),
+                })
+            })
+            .collect()
+    }
+
+    // Full transaction with meta (json encoding); None if the node does not have it
+    pub fn get_transaction(&self, signature: &str) -> Result<Option<Value>> {
+        let result = self.request(
+            "getTransaction",
+            json!([signature, {
+                "encoding": "json",
+                "commitment": self.commitment,
+                "maxSupportedTransactionVersion": 0,
+            }]),
+        )?;
+        Ok((!result.is_null()).then_some(result))
+    }
+
+    // Whether a signature has reached the client's commitment level
+    pub fn is_confirmed(&self, signature: &str) -> Result<bool> {
+        let result = self.request(
+            "getSignatureStatuses",
+            json!([[signature], { "searchTransactionHistory": false }]),
+        )?;
+        let status = &result["value"][0];
+        if status.is_null() {
+            return Ok(false);
+        }
+        if !status["err"].is_null() {
+            return Err(ClientError::Rpc {
+                code: 0,
+                message: format!("transaction {signature} failed: {}", status["err"]),
+            });
+        }
+        Ok(matches!(
+            status["confirmationStatus"].as_str(),
+            Some("confirmed") | Some("finalized")
+        ))
+    }
+}
+
+fn parse_pubkey(value: &Value) -> Result<Pubkey> {
+    value
+        .as_str()
+        .and_then(|s| Pubkey::from_str(s).ok())
+        .ok_or_else(|| ClientError::InvalidResponse(format!("invalid pubkey: {value}")))
+}
+
+// Account data is returned as ["<base64>", "base64"]
+fn decode_base64_data(value: &Value) -> Result<Vec<u8>> {
+    let encoded = value[0]
+        .as_str()
+        .ok_or_else(|| ClientError::InvalidResponse("expected base64 account data".into()))?;
+    BASE64
+        .decode(encoded)
+        .map_err(|e| ClientError::InvalidResponse(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_base64_data() {
+        let value = json!(["AQID", "base64"]);
+        assert_eq!(decode_base64_data(&value).unwrap(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_decode_base64_data_rejects_missing() {
+        assert!(decode_base64_data(&json!(null)).is_err());
+    }
+}
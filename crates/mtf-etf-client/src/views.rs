@@ -0,0 +1,80 @@
+// On-chain quotes. quote_buy / quote_sell / get_nav / get_nav_twap are read-only program
+// instructions that return a Borsh struct as return data; running them
+// through simulateTransaction gives the program's own numbers without
+// sending anything.
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::message::Message;
+use anchor_lang::AnchorDeserialize;
+use mtf_etf::{BuyQuote, NavQuote, SellQuote, TwapQuote};
+
+use crate::error::{ClientError, Result};
+use crate::{instructions, RpcClient};
+
+// `fee_payer` must be an existing account; nothing is signed or charged.
+// `basket` is the ETF's token_addresses, for pricing its holdings.
+pub fn quote_buy(
+    rpc: &RpcClient,
+    fee_payer: &Pubkey,
+    etf: Pubkey,
+    basket: &[Pubkey],
+    sol_amount: u64,
+    token_percentages: Vec<u8>,
+) -> Result<BuyQuote> {
+    simulate_view(
+        rpc,
+        fee_payer,
+        instructions::quote_buy(etf, basket, sol_amount, token_percentages),
+    )
+}
+
+pub fn quote_sell(rpc: &RpcClient, fee_payer: &Pubkey, etf: Pubkey, shares: u64) -> Result<SellQuote> {
+    simulate_view(rpc, fee_payer, instructions::quote_sell(etf, shares))
+}
+
+pub fn get_nav(rpc: &RpcClient, fee_payer: &Pubkey, etf: Pubkey, basket: &[Pubkey]) -> Result<NavQuote> {
+    simulate_view(rpc, fee_payer, instructions::get_nav(etf, basket))
+}
+
+// Fails if the ETF has no NAV history or it has no snapshots yet
+pub fn get_nav_twap(rpc: &RpcClient, fee_payer: &Pubkey, etf: Pubkey, window_seconds: i64) -> Result<TwapQuote> {
+    simulate_view(rpc, fee_payer, instructions::get_nav_twap(etf, window_seconds))
+}
+
+pub fn simulate_view<T: AnchorDeserialize>(rpc: &RpcClient, fee_payer: &Pubkey, ix: Instruction) -> Result<T> {
+    let simulation = rpc.simulate_transaction(&unsigned_transaction(&[ix], fee_payer))?;
+    if let Some(err) = simulation.err {
+        return Err(ClientError::Simulation(format!("{err} {}", simulation.logs.join("\n"))));
+    }
+    let data = simulation
+        .return_data
+        .ok_or_else(|| ClientError::Simulation("no return data".to_string()))?;
+    T::try_from_slice(&data).map_err(|e| ClientError::Decode(e.to_string()))
+}
+
+// Legacy wire transaction with blank signatures, for sigVerify=false simulation
+fn unsigned_transaction(ixs: &[Instruction], fee_payer: &Pubkey) -> Vec<u8> {
+    let message = Message::new(ixs, Some(fee_payer));
+    let signatures = message.header.num_required_signatures as usize;
+    // Signature count is a compact-u16; views never need more than 127
+    let mut wire = vec![signatures as u8];
+    wire.resize(1 + 64 * signatures, 0);
+    wire.extend(message.serialize());
+    wire
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unsigned_transaction_layout() {
+        let payer = Pubkey::new_unique();
+        let ix = instructions::get_nav(Pubkey::new_unique(), &[]);
+        let wire = unsigned_transaction(std::slice::from_ref(&ix), &payer);
+
+        assert_eq!(wire[0], 1);
+        assert!(wire[1..65].iter().all(|byte| *byte == 0));
+        assert_eq!(&wire[65..], Message::new(&[ix], Some(&payer)).serialize().as_slice());
+    }
+}
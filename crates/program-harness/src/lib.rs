@@ -0,0 +1,514 @@
+// In-process execution harness for Solana programs.
+//
+// Programs are run natively through their Rust entrypoint (e.g. the Anchor
+// generated `entry`), with the syscall stubs of `solana_program` routed into
+// a small emulated runtime:
+//
+// - accounts live in memory and use the same serialized layout as the real
+//   loader, so `AccountInfo::resize`, `assign` and Anchor's `close` work
+// - CPIs are dispatched to registered programs, the System program and the
+//   SPL Token and Token-2022 programs, with signer/writable privilege checks
+//   and PDA signing
+// - every instruction frame is checked for lamport conservation and for
+//   lamport/data/owner changes to accounts the program does not own
+// - Clock and Rent sysvars, emitted events and return data are captured;
+//   logs hold the runtime's invoke/success lines and `Program data:` entries
+//   (`msg!` output goes straight to stdout on native targets)
+//
+// Everything runs offline and in-process, in the spirit of solana-program-test's
+// native `processor!` mode. CPIs must target an executable account of the
+// top-level instruction, stay within the runtime's depth limit and not
+// re-enter a program already on the stack.
+//
+// What the emulation does not do, so tests cannot catch it:
+//
+// - programs run as native Rust, not SBF: no compute budget, stack or heap
+//   limits, and no loader (de)serialization or pointer-alignment checks
+// - the System program is a native stand-in covering create_account,
+//   transfer, allocate and assign only (no seeds, nonces or realloc variants)
+// - transactions have no signatures, fees, blockhashes, size or account-lock
+//   limits; a signer is any key passed in `signers`
+// - the instructions sysvar, slot hashes and other sysvars besides Clock and
+//   Rent are not available
+// - the SPL Token and Token-2022 programs run natively from the spl-token and
+//   spl-token-2022 crates; the associated token account program is not
+//   registered, so token accounts are created directly with
+//   `create_token_account`
+
+mod runtime;
+mod system;
+
+use std::collections::{HashMap, HashSet};
+
+use anchor_lang::prelude::{AccountInfo, Clock, ProgramError, Pubkey, Rent};
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::solana_program::{bpf_loader, pubkey};
+use anchor_lang::system_program;
+use anchor_lang::{AnchorDeserialize, Discriminator};
+use anchor_spl::token::spl_token;
+use anchor_spl::token_2022::spl_token_2022;
+use anchor_spl::token_2022::spl_token_2022::extension::StateWithExtensions;
+
+pub use runtime::RuntimeViolation;
+
+const NATIVE_LOADER_ID: Pubkey = pubkey!("NativeLoader1111111111111111111111111111111");
+
+// Native program entrypoint, e.g. `my_program::entry`
+pub type Processor = for<'a> fn(&Pubkey, &'a [AccountInfo<'a>], &[u8]) -> ProgramResult;
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct AccountState {
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub owner: Pubkey,
+    pub executable: bool,
+}
+
+impl AccountState {
+    pub fn system(lamports: u64) -> Self {
+        Self {
+            lamports,
+            data: Vec::new(),
+            owner: system_program::ID,
+            executable: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HarnessError {
+    // A meta is marked signer but its key was not passed as a signer
+    MissingSignature(Pubkey),
+    // Instruction targets a program that is not registered
+    UnknownProgram(Pubkey),
+    // The program (or a CPI callee) returned an error
+    Program(ProgramError),
+    // The program broke a runtime rule (see RuntimeViolation)
+    Runtime(RuntimeViolation),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransactionError {
+    pub error: HarnessError,
+    pub instruction_index: usize,
+    pub logs: Vec<String>,
+}
+
+impl TransactionError {
+    // Custom program error code (Anchor error codes start at 6000)
+    pub fn custom_code(&self) -> Option<u32> {
+        match self.error {
+            HarnessError::Program(ProgramError::Custom(code)) => Some(code),
+            _ => None,
+        }
+    }
+}
+
+// Logs, events and return data captured while processing a transaction
+#[derive(Debug, Clone, Default)]
+pub struct TransactionOutcome {
+    pub logs: Vec<String>,
+    // Raw `sol_log_data` payloads, in emission order
+    pub events: Vec<Vec<u8>>,
+    // Return data of the last instruction that set any
+    pub return_data: Option<(Pubkey, Vec<u8>)>,
+}
+
+impl TransactionOutcome {
+    // Decode every emitted Anchor event of type `T`
+    pub fn events<T: AnchorDeserialize + Discriminator>(&self) -> Vec<T> {
+        self.events
+            .iter()
+            .filter_map(|data| data.strip_prefix(T::DISCRIMINATOR))
+            .filter_map(|mut payload| T::deserialize(&mut payload).ok())
+            .collect()
+    }
+}
+
+pub struct Harness {
+    accounts: HashMap<Pubkey, AccountState>,
+    programs: HashMap<Pubkey, Processor>,
+    clock: Clock,
+    rent: Rent,
+}
+
+impl Default for Harness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Harness {
+    // Starts with the System, SPL Token and Token-2022 programs available
+    pub fn new() -> Self {
+        runtime::install_stubs();
+
+        let mut harness = Self {
+            accounts: HashMap::new(),
+            programs: HashMap::new(),
+            clock: Clock {
+                slot: 1,
+                epoch_start_timestamp: 1_700_000_000,
+                epoch: 0,
+                leader_schedule_epoch: 0,
+                unix_timestamp: 1_700_000_000,
+            },
+            rent: Rent::default(),
+        };
+        harness.add_executable(system_program::ID, NATIVE_LOADER_ID);
+        harness.add_executable(spl_token::ID, bpf_loader::ID);
+        harness.add_executable(spl_token_2022::ID, bpf_loader::ID);
+        harness
+    }
+
+    pub fn add_program(&mut self, program_id: Pubkey, processor: Processor) {
+        self.programs.insert(program_id, processor);
+        self.add_executable(program_id, bpf_loader::ID);
+    }
+
+    fn add_executable(&mut self, program_id: Pubkey, loader: Pubkey) {
+        self.accounts.insert(
+            program_id,
+            AccountState {
+                lamports: 1,
+                data: Vec::new(),
+                owner: loader,
+                executable: true,
+            },
+        );
+    }
+
+    pub fn rent(&self) -> &Rent {
+        &self.rent
+    }
+
+    pub fn clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    pub fn set_unix_timestamp(&mut self, unix_timestamp: i64) {
+        self.clock.unix_timestamp = unix_timestamp;
+    }
+
+    pub fn advance_time(&mut self, seconds: i64) {
+        self.clock.unix_timestamp += seconds;
+        self.clock.slot += (seconds.max(0) as u64).max(1) * 2;
+    }
+
+    pub fn set_account(&mut self, address: Pubkey, account: AccountState) {
+        self.accounts.insert(address, account);
+    }
+
+    pub fn account(&self, address: &Pubkey) -> Option<&AccountState> {
+        self.accounts.get(address)
+    }
+
+    // 0 for accounts that do not exist
+    pub fn lamports(&self, address: &Pubkey) -> u64 {
+        self.accounts.get(address).map(|a| a.lamports).unwrap_or_default()
+    }
+
+    pub fn airdrop(&mut self, address: &Pubkey, lamports: u64) {
+        self.accounts
+            .entry(*address)
+            .or_insert_with(|| AccountState::system(0))
+            .lamports += lamports;
+    }
+
+    // Sum of every account's lamports - constant across successful transactions
+    pub fn total_lamports(&self) -> u128 {
+        self.accounts.values().map(|a| a.lamports as u128).sum()
+    }
+
+    // Decode an Anchor account (discriminator checked)
+    pub fn anchor_account<T: anchor_lang::AccountDeserialize>(&self, address: &Pubkey) -> Option<T> {
+        let account = self.accounts.get(address)?;
+        T::try_deserialize(&mut account.data.as_slice()).ok()
+    }
+
+    pub fn create_mint(&mut self, mint: Pubkey, authority: &Pubkey, decimals: u8) {
+        let state = spl_token::state::Mint {
+            mint_authority: Some(*authority).into(),
+            supply: 0,
+            decimals,
+            is_initialized: true,
+            freeze_authority: None.into(),
+        };
+        self.set_packed(mint, state, spl_token::ID);
+    }
+
+    // Owned by the mint's token program: Token-2022 for a Token-2022 mint,
+    // SPL Token otherwise. Either way the account carries no extensions.
+    pub fn create_token_account(&mut self, address: Pubkey, mint: &Pubkey, owner: &Pubkey, amount: u64) {
+        let state = spl_token::state::Account {
+            mint: *mint,
+            owner: *owner,
+            amount,
+            state: spl_token::state::AccountState::Initialized,
+            ..Default::default()
+        };
+        let program = match self.accounts.get(mint) {
+            Some(account) if account.owner == spl_token_2022::ID => spl_token_2022::ID,
+            _ => spl_token::ID,
+        };
+        self.set_packed(address, state, program);
+    }
+
+    fn set_packed<T: Pack>(&mut self, address: Pubkey, state: T, program: Pubkey) {
+        let mut data = vec![0; T::LEN];
+        T::pack(state, &mut data).expect("pack token state");
+        self.accounts.insert(
+            address,
+            AccountState {
+                lamports: self.rent.minimum_balance(T::LEN),
+                data,
+                owner: program,
+                executable: false,
+            },
+        );
+    }
+
+    // Reads SPL Token and Token-2022 accounts, extensions included
+    pub fn token_balance(&self, address: &Pubkey) -> Option<u64> {
+        let account = self.accounts.get(address)?;
+        StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data)
+            .ok()
+            .map(|a| a.base.amount)
+    }
+
+    pub fn process_instruction(
+        &mut self,
+        instruction: Instruction,
+        signers: &[Pubkey],
+    ) -> Result<TransactionOutcome, TransactionError> {
+        self.process_transaction(&[instruction], signers)
+    }
+
+    // Instructions run in order; state is rolled back if any of them fails
+    pub fn process_transaction(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[Pubkey],
+    ) -> Result<TransactionOutcome, TransactionError> {
+        let checkpoint = self.accounts.clone();
+        let signers: HashSet<Pubkey> = signers.iter().copied().collect();
+        let mut outcome = TransactionOutcome::default();
+
+        for (index, instruction) in instructions.iter().enumerate() {
+            let result = runtime::execute(
+                instruction,
+                &signers,
+                &mut self.accounts,
+                &self.programs,
+                &self.clock,
+                &self.rent,
+                &mut outcome,
+            );
+            if let Err(error) = result {
+                self.accounts = checkpoint;
+                return Err(TransactionError {
+                    error,
+                    instruction_index: index,
+                    logs: std::mem::take(&mut outcome.logs),
+                });
+            }
+        }
+
+        Ok(outcome)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::solana_program::program::invoke;
+    #[allow(deprecated)]
+    use anchor_lang::solana_program::system_instruction;
+
+    // Test program: CPI into the program whose id starts the data, passing the
+    // rest of the data along; empty data returns straight away
+    fn relay<'a>(_program_id: &Pubkey, accounts: &'a [AccountInfo<'a>], data: &[u8]) -> ProgramResult {
+        let Some((target, rest)) = data.split_first_chunk::<32>() else {
+            return Ok(());
+        };
+        invoke(
+            &Instruction::new_with_bytes(Pubkey::new_from_array(*target), rest, Vec::new()),
+            accounts,
+        )
+    }
+
+    fn relay_harness() -> (Harness, Pubkey, Pubkey) {
+        let mut harness = Harness::new();
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        harness.add_program(a, relay);
+        harness.add_program(b, relay);
+        (harness, a, b)
+    }
+
+    fn relay_ix(program: Pubkey, hops: &[Pubkey], accounts: &[Pubkey]) -> Instruction {
+        let data: Vec<u8> = hops.iter().flat_map(|hop| hop.to_bytes()).collect();
+        let metas = accounts
+            .iter()
+            .map(|account| anchor_lang::solana_program::instruction::AccountMeta::new_readonly(*account, false))
+            .collect();
+        Instruction::new_with_bytes(program, &data, metas)
+    }
+
+    #[test]
+    fn test_system_transfer() {
+        let mut harness = Harness::new();
+        let from = Pubkey::new_unique();
+        let to = Pubkey::new_unique();
+        harness.airdrop(&from, 10_000_000);
+
+        harness
+            .process_instruction(system_instruction::transfer(&from, &to, 2_000_000), &[from])
+            .unwrap();
+
+        assert_eq!(harness.lamports(&from), 8_000_000);
+        assert_eq!(harness.lamports(&to), 2_000_000);
+    }
+
+    #[test]
+    fn test_missing_signature() {
+        let mut harness = Harness::new();
+        let from = Pubkey::new_unique();
+        harness.airdrop(&from, 10_000_000);
+
+        let err = harness
+            .process_instruction(system_instruction::transfer(&from, &Pubkey::new_unique(), 1), &[])
+            .unwrap_err();
+        assert_eq!(err.error, HarnessError::MissingSignature(from));
+        assert_eq!(harness.lamports(&from), 10_000_000);
+    }
+
+    #[test]
+    fn test_transfer_below_rent_is_rejected() {
+        let mut harness = Harness::new();
+        let from = Pubkey::new_unique();
+        let to = Pubkey::new_unique();
+        harness.airdrop(&from, 10_000_000);
+
+        let err = harness
+            .process_instruction(system_instruction::transfer(&from, &to, 1_000), &[from])
+            .unwrap_err();
+        assert_eq!(err.error, HarnessError::Runtime(RuntimeViolation::InsufficientFundsForRent(to)));
+    }
+
+    #[test]
+    fn test_failed_transaction_rolls_back() {
+        let mut harness = Harness::new();
+        let from = Pubkey::new_unique();
+        let to = Pubkey::new_unique();
+        harness.airdrop(&from, 10_000_000);
+
+        let result = harness.process_transaction(
+            &[
+                system_instruction::transfer(&from, &to, 2_000_000),
+                system_instruction::transfer(&from, &to, 20_000_000),
+            ],
+            &[from],
+        );
+        assert_eq!(result.unwrap_err().instruction_index, 1);
+        assert_eq!(harness.lamports(&from), 10_000_000);
+        assert_eq!(harness.lamports(&to), 0);
+    }
+
+    #[test]
+    fn test_create_account() {
+        let mut harness = Harness::new();
+        let payer = Pubkey::new_unique();
+        let new_account = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        harness.airdrop(&payer, 1_000_000_000);
+        let lamports = harness.rent().minimum_balance(100);
+
+        harness
+            .process_instruction(
+                system_instruction::create_account(&payer, &new_account, lamports, 100, &owner),
+                &[payer, new_account],
+            )
+            .unwrap();
+
+        let account = harness.account(&new_account).unwrap();
+        assert_eq!(account.owner, owner);
+        assert_eq!(account.data.len(), 100);
+        assert_eq!(account.lamports, lamports);
+    }
+
+    #[test]
+    fn test_cpi_needs_program_account() {
+        let (mut harness, a, b) = relay_harness();
+
+        let err = harness.process_instruction(relay_ix(a, &[b], &[]), &[]).unwrap_err();
+        assert_eq!(err.error, HarnessError::Runtime(RuntimeViolation::MissingProgramAccount(b)));
+        harness.process_instruction(relay_ix(a, &[b], &[b]), &[]).unwrap();
+    }
+
+    #[test]
+    fn test_cpi_reentrancy_is_rejected() {
+        let (mut harness, a, b) = relay_harness();
+
+        let err = harness.process_instruction(relay_ix(a, &[b, a], &[b]), &[]).unwrap_err();
+        assert_eq!(err.error, HarnessError::Runtime(RuntimeViolation::ReentrancyNotAllowed(a)));
+        // Direct self-recursion is allowed
+        harness.process_instruction(relay_ix(a, &[a, a], &[]), &[]).unwrap();
+    }
+
+    #[test]
+    fn test_cpi_depth_limit() {
+        let (mut harness, a, _) = relay_harness();
+
+        harness.process_instruction(relay_ix(a, &[a; 4], &[]), &[]).unwrap();
+        let err = harness.process_instruction(relay_ix(a, &[a; 5], &[]), &[]).unwrap_err();
+        assert_eq!(err.error, HarnessError::Runtime(RuntimeViolation::CallDepthExceeded));
+    }
+
+    #[test]
+    fn test_token_transfer() {
+        let mut harness = Harness::new();
+        let mint = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let source = Pubkey::new_unique();
+        let destination = Pubkey::new_unique();
+        harness.airdrop(&owner, 1_000_000_000);
+        harness.create_mint(mint, &owner, 6);
+        harness.create_token_account(source, &mint, &owner, 500);
+        harness.create_token_account(destination, &mint, &Pubkey::new_unique(), 0);
+
+        let ix = spl_token::instruction::transfer(&spl_token::ID, &source, &destination, &owner, &[], 200)
+            .unwrap();
+        harness.process_instruction(ix, &[owner]).unwrap();
+
+        assert_eq!(harness.token_balance(&source), Some(300));
+        assert_eq!(harness.token_balance(&destination), Some(200));
+    }
+
+    #[test]
+    fn test_token_2022_mint_to() {
+        let mut harness = Harness::new();
+        let mint = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let account = Pubkey::new_unique();
+        harness.airdrop(&authority, 1_000_000_000);
+
+        let space = spl_token_2022::state::Mint::LEN;
+        let lamports = harness.rent().minimum_balance(space);
+        let create = system_instruction::create_account(&authority, &mint, lamports, space as u64, &spl_token_2022::ID);
+        harness.process_instruction(create, &[authority, mint]).unwrap();
+        let init = spl_token_2022::instruction::initialize_mint2(&spl_token_2022::ID, &mint, &authority, None, 6)
+            .unwrap();
+        harness.process_instruction(init, &[]).unwrap();
+
+        // Token accounts follow their mint's program
+        harness.create_token_account(account, &mint, &Pubkey::new_unique(), 0);
+        assert_eq!(harness.account(&account).unwrap().owner, spl_token_2022::ID);
+        let ix = spl_token_2022::instruction::mint_to(&spl_token_2022::ID, &mint, &account, &authority, &[], 700)
+            .unwrap();
+        harness.process_instruction(ix, &[authority]).unwrap();
+
+        assert_eq!(harness.token_balance(&account), Some(700));
+    }
+}
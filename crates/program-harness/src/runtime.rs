@@ -0,0 +1,524 @@
+// Emulated runtime: account buffers, syscall stubs, CPI dispatch and the
+// per-frame account checks the real runtime performs. What it leaves out is
+// listed at the top of lib.rs.
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::sync::Once;
+
+use anchor_lang::prelude::{AccountInfo, Clock, ProgramError, Pubkey, Rent};
+use anchor_lang::solana_program::entrypoint::{ProgramResult, MAX_PERMITTED_DATA_INCREASE};
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program_stubs::{set_syscall_stubs, SyscallStubs};
+use anchor_lang::system_program;
+use anchor_spl::token::spl_token;
+use anchor_spl::token_2022::spl_token_2022;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+
+use crate::{AccountState, HarnessError, Processor, TransactionOutcome};
+
+const SUCCESS: u64 = 0;
+
+// The runtime's instruction stack limit: a top-level instruction plus four
+// levels of CPI
+const MAX_INSTRUCTION_STACK_DEPTH: usize = 5;
+
+// Placeholder error returned to the program when a runtime rule is broken;
+// the harness reports the recorded violation instead.
+const VIOLATION_ERROR: u32 = u32::MAX;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuntimeViolation {
+    // Lamports were created or destroyed within an instruction frame
+    UnbalancedInstruction,
+    // Lamports debited from an account the executing program does not own
+    ExternalAccountLamportSpend(Pubkey),
+    // Data changed on an account the executing program does not own
+    ExternalAccountDataModified(Pubkey),
+    // Owner changed by a program that did not own the account
+    ModifiedProgramId(Pubkey),
+    // A read-only account was modified
+    ReadonlyAccountModified(Pubkey),
+    // A CPI asked for signer/writable privileges the caller does not hold
+    PrivilegeEscalation(Pubkey),
+    // A writable account was left with lamports below rent exemption
+    InsufficientFundsForRent(Pubkey),
+    // A CPI targeted a program that is not an executable account of the
+    // top-level instruction
+    MissingProgramAccount(Pubkey),
+    // A CPI went deeper than MAX_INSTRUCTION_STACK_DEPTH
+    CallDepthExceeded,
+    // A CPI re-entered a program already on the stack, other than by
+    // direct self-recursion
+    ReentrancyNotAllowed(Pubkey),
+}
+
+struct Snapshot {
+    lamports: u64,
+    data: Vec<u8>,
+    owner: Pubkey,
+}
+
+struct Frame {
+    program_id: Pubkey,
+    writable: HashSet<Pubkey>,
+    pre: Vec<Snapshot>,
+}
+
+struct Context {
+    // One AccountInfo per unique account of the top-level instruction
+    accounts: Vec<AccountInfo<'static>>,
+    programs: HashMap<Pubkey, Processor>,
+    clock: Clock,
+    rent: Rent,
+    frames: Vec<Frame>,
+    logs: Vec<String>,
+    events: Vec<Vec<u8>>,
+    return_data: Option<(Pubkey, Vec<u8>)>,
+    violation: Option<RuntimeViolation>,
+}
+
+impl Context {
+    fn fail(&mut self, violation: RuntimeViolation) -> ProgramError {
+        self.violation.get_or_insert(violation);
+        ProgramError::Custom(VIOLATION_ERROR)
+    }
+
+    fn resolve(&self, program_id: &Pubkey) -> Option<Processor> {
+        resolve(&self.programs, program_id)
+    }
+}
+
+thread_local! {
+    static CONTEXT: RefCell<Option<Context>> = const { RefCell::new(None) };
+}
+
+fn with_context<R>(f: impl FnOnce(&mut Context) -> R) -> Option<R> {
+    CONTEXT.with(|context| context.borrow_mut().as_mut().map(f))
+}
+
+fn resolve(programs: &HashMap<Pubkey, Processor>, program_id: &Pubkey) -> Option<Processor> {
+    if *program_id == system_program::ID {
+        Some(crate::system::process)
+    } else if *program_id == spl_token::ID {
+        Some(spl_token::processor::Processor::process)
+    } else if *program_id == spl_token_2022::ID {
+        Some(spl_token_2022::processor::Processor::process)
+    } else {
+        programs.get(program_id).copied()
+    }
+}
+
+pub(crate) fn install_stubs() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        set_syscall_stubs(Box::new(HarnessStubs));
+    });
+}
+
+// Account memory in the loader's serialized layout: the original data length
+// sits 4 bytes before the key and the current length 8 bytes before the data,
+// followed by MAX_PERMITTED_DATA_INCREASE bytes of growth room.
+struct AccountBuffer {
+    key: *mut [u8; 36],
+    lamports: *mut u64,
+    data: *mut [u64],
+    owner: *mut Pubkey,
+    data_len: usize,
+}
+
+impl AccountBuffer {
+    fn new(address: &Pubkey, state: &AccountState) -> Self {
+        let data_len = state.data.len();
+        let mut key = [0u8; 36];
+        key[..4].copy_from_slice(&(data_len as u32).to_le_bytes());
+        key[4..].copy_from_slice(address.as_ref());
+
+        let words = (8 + data_len + MAX_PERMITTED_DATA_INCREASE).div_ceil(8);
+        let mut data = vec![0u64; words].into_boxed_slice();
+        data[0] = data_len as u64;
+        let bytes = data.as_mut_ptr() as *mut u8;
+        // SAFETY: the buffer holds 8 + data_len + MAX_PERMITTED_DATA_INCREASE bytes
+        unsafe { std::ptr::copy_nonoverlapping(state.data.as_ptr(), bytes.add(8), data_len) };
+
+        Self {
+            key: Box::into_raw(Box::new(key)),
+            lamports: Box::into_raw(Box::new(state.lamports)),
+            data: Box::into_raw(data),
+            owner: Box::into_raw(Box::new(state.owner)),
+            data_len,
+        }
+    }
+
+    // SAFETY: the returned AccountInfo (and its clones) must be dropped before
+    // this buffer is.
+    unsafe fn account_info(&self, is_signer: bool, is_writable: bool, executable: bool) -> AccountInfo<'static> {
+        let key = &*((self.key as *const u8).add(4) as *const Pubkey);
+        let data = std::slice::from_raw_parts_mut((self.data as *mut u8).add(8), self.data_len);
+        AccountInfo::new(
+            key,
+            is_signer,
+            is_writable,
+            &mut *self.lamports,
+            data,
+            &*self.owner,
+            executable,
+            0,
+        )
+    }
+}
+
+impl Drop for AccountBuffer {
+    fn drop(&mut self) {
+        // SAFETY: pointers come from Box::into_raw in AccountBuffer::new
+        unsafe {
+            drop(Box::from_raw(self.key));
+            drop(Box::from_raw(self.lamports));
+            drop(Box::from_raw(self.data));
+            drop(Box::from_raw(self.owner));
+        }
+    }
+}
+
+// AccountInfo is invariant over its lifetime, so narrow it explicitly to the
+// borrow of `infos` before handing it to the program
+fn call<'a>(processor: Processor, program_id: &Pubkey, infos: &'a [AccountInfo<'static>], data: &[u8]) -> ProgramResult {
+    // SAFETY: only shortens the lifetime
+    let infos: &'a [AccountInfo<'a>] = unsafe { std::mem::transmute(infos) };
+    processor(program_id, infos, data)
+}
+
+fn snapshot(accounts: &[AccountInfo]) -> Vec<Snapshot> {
+    accounts
+        .iter()
+        .map(|info| Snapshot {
+            lamports: info.lamports(),
+            data: info.data.borrow().to_vec(),
+            owner: *info.owner,
+        })
+        .collect()
+}
+
+// Attribute every change since `frame.pre` to `frame.program_id` and check it
+fn verify_frame(frame: &Frame, accounts: &[AccountInfo]) -> Result<(), RuntimeViolation> {
+    let mut pre_total: u128 = 0;
+    let mut post_total: u128 = 0;
+
+    for (info, pre) in accounts.iter().zip(&frame.pre) {
+        let key = *info.key;
+        let lamports = info.lamports();
+        pre_total += pre.lamports as u128;
+        post_total += lamports as u128;
+
+        let data_changed = **info.data.borrow() != pre.data[..];
+        let owner_changed = *info.owner != pre.owner;
+        if lamports == pre.lamports && !data_changed && !owner_changed {
+            continue;
+        }
+
+        let owned = pre.owner == frame.program_id;
+        if !frame.writable.contains(&key) {
+            return Err(RuntimeViolation::ReadonlyAccountModified(key));
+        }
+        if owner_changed && !owned {
+            return Err(RuntimeViolation::ModifiedProgramId(key));
+        }
+        if lamports < pre.lamports && !owned {
+            return Err(RuntimeViolation::ExternalAccountLamportSpend(key));
+        }
+        if data_changed && !owned {
+            return Err(RuntimeViolation::ExternalAccountDataModified(key));
+        }
+    }
+
+    if pre_total != post_total {
+        return Err(RuntimeViolation::UnbalancedInstruction);
+    }
+    Ok(())
+}
+
+// Run one top-level instruction against `accounts`, committing on success
+pub(crate) fn execute(
+    instruction: &Instruction,
+    signers: &HashSet<Pubkey>,
+    accounts: &mut HashMap<Pubkey, AccountState>,
+    programs: &HashMap<Pubkey, Processor>,
+    clock: &Clock,
+    rent: &Rent,
+    outcome: &mut TransactionOutcome,
+) -> Result<(), HarnessError> {
+    let processor = resolve(programs, &instruction.program_id)
+        .ok_or(HarnessError::UnknownProgram(instruction.program_id))?;
+
+    // Unique accounts with merged privileges, program account last
+    let mut keys: Vec<Pubkey> = Vec::new();
+    let mut privileges: HashMap<Pubkey, (bool, bool)> = HashMap::new();
+    for meta in &instruction.accounts {
+        if meta.is_signer && !signers.contains(&meta.pubkey) {
+            return Err(HarnessError::MissingSignature(meta.pubkey));
+        }
+        let entry = privileges.entry(meta.pubkey).or_insert_with(|| {
+            keys.push(meta.pubkey);
+            (false, false)
+        });
+        entry.0 |= meta.is_signer;
+        entry.1 |= meta.is_writable;
+    }
+    privileges.entry(instruction.program_id).or_insert_with(|| {
+        keys.push(instruction.program_id);
+        (false, false)
+    });
+
+    let pre_states: Vec<AccountState> = keys
+        .iter()
+        .map(|key| accounts.get(key).cloned().unwrap_or_else(|| AccountState::system(0)))
+        .collect();
+    let buffers: Vec<AccountBuffer> = keys
+        .iter()
+        .zip(&pre_states)
+        .map(|(key, state)| AccountBuffer::new(key, state))
+        .collect();
+    // SAFETY: every AccountInfo below is dropped before `buffers`
+    let infos: Vec<AccountInfo<'static>> = keys
+        .iter()
+        .zip(&buffers)
+        .zip(&pre_states)
+        .map(|((key, buffer), state)| {
+            let (is_signer, is_writable) = privileges[key];
+            unsafe { buffer.account_info(is_signer, is_writable, state.executable) }
+        })
+        .collect();
+    let instruction_infos: Vec<AccountInfo<'static>> = instruction
+        .accounts
+        .iter()
+        .map(|meta| infos[keys.iter().position(|k| *k == meta.pubkey).unwrap()].clone())
+        .collect();
+
+    let writable: HashSet<Pubkey> = keys.iter().filter(|key| privileges[*key].1).copied().collect();
+    CONTEXT.with(|context| {
+        *context.borrow_mut() = Some(Context {
+            accounts: infos.clone(),
+            programs: programs.clone(),
+            clock: clock.clone(),
+            rent: rent.clone(),
+            frames: vec![Frame {
+                program_id: instruction.program_id,
+                writable,
+                pre: snapshot(&infos),
+            }],
+            logs: vec![format!("Program {} invoke [1]", instruction.program_id)],
+            events: Vec::new(),
+            return_data: None,
+            violation: None,
+        });
+    });
+
+    let result = call(processor, &instruction.program_id, &instruction_infos, &instruction.data);
+    drop(instruction_infos);
+
+    let mut context = CONTEXT.with(|context| context.borrow_mut().take()).expect("harness context");
+    let verified = match (context.violation.take(), result) {
+        (Some(violation), _) => Err(HarnessError::Runtime(violation)),
+        (None, Err(error)) => Err(HarnessError::Program(error)),
+        (None, Ok(())) => verify_frame(&context.frames[0], &infos).map_err(HarnessError::Runtime),
+    };
+    match &verified {
+        Ok(()) => context.logs.push(format!("Program {} success", instruction.program_id)),
+        Err(error) => context.logs.push(format!("Program {} failed: {error:?}", instruction.program_id)),
+    }
+    outcome.logs.append(&mut context.logs);
+    outcome.events.append(&mut context.events);
+    if context.return_data.is_some() {
+        outcome.return_data = context.return_data.take();
+    }
+    drop(context);
+    verified?;
+
+    // Writable accounts may not end up rent-paying unless they already were
+    for ((key, info), pre) in keys.iter().zip(&infos).zip(&pre_states) {
+        let (_, is_writable) = privileges[key];
+        if !is_writable {
+            continue;
+        }
+        let lamports = info.lamports();
+        let data_len = info.data_len();
+        let rent_paying = |lamports: u64, len: usize| lamports > 0 && lamports < rent.minimum_balance(len);
+        if rent_paying(lamports, data_len)
+            && !(rent_paying(pre.lamports, pre.data.len()) && pre.data.len() == data_len)
+        {
+            return Err(HarnessError::Runtime(RuntimeViolation::InsufficientFundsForRent(*key)));
+        }
+    }
+
+    // Commit; accounts left with zero lamports are garbage collected
+    for ((key, info), pre) in keys.iter().zip(&infos).zip(&pre_states) {
+        if !privileges[key].1 {
+            continue;
+        }
+        if info.lamports() == 0 {
+            accounts.remove(key);
+        } else {
+            accounts.insert(
+                *key,
+                AccountState {
+                    lamports: info.lamports(),
+                    data: info.data.borrow().to_vec(),
+                    owner: *info.owner,
+                    executable: pre.executable,
+                },
+            );
+        }
+    }
+
+    drop(infos);
+    drop(buffers);
+    Ok(())
+}
+
+fn invoke(instruction: &Instruction, account_infos: &[AccountInfo], signers_seeds: &[&[&[u8]]]) -> ProgramResult {
+    let (processor, caller, depth) = with_context(|context| {
+        let frame = context.frames.last().expect("caller frame");
+        let caller = frame.program_id;
+        if let Err(violation) = verify_frame(frame, &context.accounts) {
+            return Err(context.fail(violation));
+        }
+        let callee = instruction.program_id;
+        if !context.accounts.iter().any(|info| *info.key == callee && info.executable) {
+            return Err(context.fail(RuntimeViolation::MissingProgramAccount(callee)));
+        }
+        if context.frames.len() >= MAX_INSTRUCTION_STACK_DEPTH {
+            return Err(context.fail(RuntimeViolation::CallDepthExceeded));
+        }
+        if caller != callee && context.frames.iter().any(|frame| frame.program_id == callee) {
+            return Err(context.fail(RuntimeViolation::ReentrancyNotAllowed(callee)));
+        }
+        let processor = context
+            .resolve(&instruction.program_id)
+            .ok_or(ProgramError::IncorrectProgramId)?;
+        Ok((processor, caller, context.frames.len() + 1))
+    })
+    .expect("CPI outside of a harness transaction")?;
+
+    let signer_pdas = signers_seeds
+        .iter()
+        .map(|seeds| Pubkey::create_program_address(seeds, &caller))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| ProgramError::InvalidSeeds)?;
+
+    let mut callee_infos = Vec::with_capacity(instruction.accounts.len());
+    for meta in &instruction.accounts {
+        let info = account_infos
+            .iter()
+            .find(|info| *info.key == meta.pubkey)
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+        let can_sign = info.is_signer || signer_pdas.contains(&meta.pubkey);
+        if (meta.is_signer && !can_sign) || (meta.is_writable && !info.is_writable) {
+            let violation = RuntimeViolation::PrivilegeEscalation(meta.pubkey);
+            return Err(with_context(|context| context.fail(violation)).unwrap());
+        }
+        let mut callee_info = info.clone();
+        callee_info.is_signer = meta.is_signer;
+        callee_info.is_writable = meta.is_writable;
+        callee_infos.push(callee_info);
+    }
+    // SAFETY: the infos borrow buffers owned by the top-level `execute` call,
+    // which outlives this CPI.
+    let callee_infos: Vec<AccountInfo<'static>> = unsafe { std::mem::transmute(callee_infos) };
+
+    with_context(|context| {
+        let pre = snapshot(&context.accounts);
+        context.frames.push(Frame {
+            program_id: instruction.program_id,
+            writable: instruction
+                .accounts
+                .iter()
+                .filter(|meta| meta.is_writable)
+                .map(|meta| meta.pubkey)
+                .collect(),
+            pre,
+        });
+        context
+            .logs
+            .push(format!("Program {} invoke [{depth}]", instruction.program_id));
+    });
+
+    let result = call(processor, &instruction.program_id, &callee_infos, &instruction.data);
+    drop(callee_infos);
+
+    with_context(|context| {
+        let frame = context.frames.pop().expect("callee frame");
+        let result = result.and_then(|()| match context.violation {
+            Some(_) => Err(ProgramError::Custom(VIOLATION_ERROR)),
+            None => verify_frame(&frame, &context.accounts).map_err(|v| context.fail(v)),
+        });
+        match &result {
+            Ok(()) => {
+                context.logs.push(format!("Program {} success", instruction.program_id));
+                // Callee changes become the caller's new baseline
+                let pre = snapshot(&context.accounts);
+                context.frames.last_mut().expect("caller frame").pre = pre;
+            }
+            Err(error) => context
+                .logs
+                .push(format!("Program {} failed: {error}", instruction.program_id)),
+        }
+        result
+    })
+    .expect("harness context")
+}
+
+struct HarnessStubs;
+
+impl SyscallStubs for HarnessStubs {
+    fn sol_log(&self, message: &str) {
+        if with_context(|context| context.logs.push(format!("Program log: {message}"))).is_none() {
+            println!("{message}");
+        }
+    }
+
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        let encoded: Vec<String> = fields.iter().map(|field| BASE64.encode(field)).collect();
+        with_context(|context| {
+            context.logs.push(format!("Program data: {}", encoded.join(" ")));
+            context.events.push(fields.concat());
+        });
+    }
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        invoke(instruction, account_infos, signers_seeds)
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = with_context(|context| context.clock.clone()).unwrap_or_default();
+        // SAFETY: called by Clock::get with a pointer to a Clock
+        unsafe { *(var_addr as *mut Clock) = clock };
+        SUCCESS
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let rent = with_context(|context| context.rent.clone()).unwrap_or_default();
+        // SAFETY: called by Rent::get with a pointer to a Rent
+        unsafe { *(var_addr as *mut Rent) = rent };
+        SUCCESS
+    }
+
+    fn sol_set_return_data(&self, data: &[u8]) {
+        with_context(|context| {
+            let program_id = context.frames.last().expect("frame").program_id;
+            context.return_data = (!data.is_empty()).then(|| (program_id, data.to_vec()));
+        });
+    }
+
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        with_context(|context| context.return_data.clone()).flatten()
+    }
+
+    fn sol_get_stack_height(&self) -> u64 {
+        with_context(|context| context.frames.len() as u64).unwrap_or_default()
+    }
+}
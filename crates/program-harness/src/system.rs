@@ -0,0 +1,77 @@
+// Native System program: the subset of instructions programs use via CPI
+use anchor_lang::prelude::{AccountInfo, ProgramError, Pubkey};
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::solana_program::system_instruction::{SystemError, SystemInstruction};
+use anchor_lang::system_program;
+
+pub(crate) fn process(_program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let instruction: SystemInstruction =
+        bincode::deserialize(data).map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    match instruction {
+        SystemInstruction::CreateAccount { lamports, space, owner } => {
+            let from = account(accounts, 0)?;
+            let to = account(accounts, 1)?;
+            require_signer(from)?;
+            require_signer(to)?;
+            if to.lamports() > 0 {
+                return Err(ProgramError::Custom(SystemError::AccountAlreadyInUse as u32));
+            }
+            allocate(to, space)?;
+            to.assign(&owner);
+            transfer(from, to, lamports)
+        }
+        SystemInstruction::Transfer { lamports } => {
+            let from = account(accounts, 0)?;
+            let to = account(accounts, 1)?;
+            require_signer(from)?;
+            transfer(from, to, lamports)
+        }
+        SystemInstruction::Allocate { space } => {
+            let target = account(accounts, 0)?;
+            require_signer(target)?;
+            allocate(target, space)
+        }
+        SystemInstruction::Assign { owner } => {
+            let target = account(accounts, 0)?;
+            require_signer(target)?;
+            if *target.owner != system_program::ID {
+                return Err(ProgramError::IllegalOwner);
+            }
+            target.assign(&owner);
+            Ok(())
+        }
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+fn account<'a, 'info>(accounts: &'a [AccountInfo<'info>], index: usize) -> Result<&'a AccountInfo<'info>, ProgramError> {
+    accounts.get(index).ok_or(ProgramError::NotEnoughAccountKeys)
+}
+
+fn require_signer(info: &AccountInfo) -> ProgramResult {
+    if info.is_signer {
+        Ok(())
+    } else {
+        Err(ProgramError::MissingRequiredSignature)
+    }
+}
+
+fn transfer(from: &AccountInfo, to: &AccountInfo, lamports: u64) -> ProgramResult {
+    if !from.data_is_empty() || *from.owner != system_program::ID {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if from.lamports() < lamports {
+        return Err(ProgramError::Custom(SystemError::ResultWithNegativeLamports as u32));
+    }
+    **from.try_borrow_mut_lamports()? -= lamports;
+    **to.try_borrow_mut_lamports()? += lamports;
+    Ok(())
+}
+
+fn allocate(target: &AccountInfo, space: u64) -> ProgramResult {
+    if !target.data_is_empty() || *target.owner != system_program::ID {
+        return Err(ProgramError::Custom(SystemError::AccountAlreadyInUse as u32));
+    }
+    target.resize(space as usize)
+}
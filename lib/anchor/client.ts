@@ -1,94 +1,15 @@
 import { AnchorProvider, Program, Wallet, Idl } from '@coral-xyz/anchor';
 import { Connection, Keypair, PublicKey, Transaction, VersionedTransaction } from '@solana/web3.js';
 import { getConnection, getKeypairFromPrivateKey } from '../solana/wallet';
+// IDL generated from programs/mtf-etf; it carries the program address
+import { IDL, PROGRAM_ID as PROGRAM_ID_STRING } from './idl';
 
 // Re-export for convenience
 export { getKeypairFromPrivateKey } from '../solana/wallet';
 
-// Hardcoded Program ID - deployed on Solana devnet
-const PROGRAM_ID_STRING = 'CwwrCDfrsuA2C4YPiobU82ZA9wSWrecyLbbvP35QXmyo';
-
 // Export for use elsewhere
 export const PROGRAM_ID = PROGRAM_ID_STRING;
 
-// Minimal IDL for MTF ETF Program
-// Using 'as any' to bypass strict IDL type checking
-const MTF_IDL: any = {
-  version: "0.1.0",
-  name: "mtf_etf",
-  instructions: [
-    {
-      name: "initializeEtf",
-      accounts: [
-        { name: "etf" },
-        { name: "lister" },
-        { name: "systemProgram" }
-      ],
-      args: [
-        { name: "tokenAddresses", type: { vec: "pubkey" } }
-      ]
-    },
-    {
-      name: "buyEtf",
-      accounts: [
-        { name: "etf" },
-        { name: "investor" },
-        { name: "investorAta" },
-        { name: "etfVault" },
-        { name: "listerAta" },
-        { name: "systemProgram" }
-      ],
-      args: [
-        { name: "solAmount", type: "u64" }
-      ]
-    },
-    {
-      name: "sellEtf",
-      accounts: [
-        { name: "etf" },
-        { name: "investor" },
-        { name: "investorAta" },
-        { name: "etfVault" },
-        { name: "listerAta" },
-        { name: "systemProgram" }
-      ],
-      args: [
-        { name: "tokensToSell", type: "u64" }
-      ]
-    },
-    {
-      name: "claimFees",
-      accounts: [
-        { name: "etf" },
-        { name: "lister" },
-        { name: "listerAta" },
-        { name: "etfVault" },
-        { name: "systemProgram" }
-      ],
-      args: []
-    }
-  ],
-  accounts: [
-    {
-      name: "ETF",
-      type: {
-        kind: "struct",
-        fields: [
-          { name: "lister", type: "pubkey" },
-          { name: "tokenAddresses", type: { vec: "pubkey" } },
-          { name: "totalSupply", type: "u64" },
-          { name: "bump", type: "u8" }
-        ]
-      }
-    }
-  ],
-  errors: [
-    { code: 6000, name: "InsufficientFunds", msg: "Insufficient funds for this operation" },
-    { code: 6001, name: "InvalidAmount", msg: "Invalid amount specified" },
-    { code: 6002, name: "Unauthorized", msg: "You are not authorized to perform this action" }
-  ]
-};
-
 /**
  * Get an Anchor provider configured with the given private key
  */
@@ -137,22 +58,19 @@ export function getAnchorProvider(privateKey?: string, network: 'mainnet' | 'dev
  */
 export function getProgram(privateKey?: string, network: 'mainnet' | 'devnet' = 'devnet'): any {
   const provider = getAnchorProvider(privateKey, network);
-  const programId = new PublicKey(PROGRAM_ID_STRING);
-  
-  // Create program with explicit programId
-  // Using type assertion to bypass IDL type checking issues
-  // @ts-ignore - IDL type mismatch
-  return new Program(MTF_IDL, programId, provider);
+
+  // Anchor 0.30+ reads the program ID from the IDL's address
+  return new Program(IDL as unknown as Idl, provider);
 }
 
 /**
- * Get the PDA for an ETF created by a lister
+ * Get the PDA for a lister's ETF at `etfIndex` (0-4)
  */
-export function getEtfPda(listerPubkey: PublicKey): [PublicKey, number] {
+export function getEtfPda(listerPubkey: PublicKey, etfIndex: number = 0): [PublicKey, number] {
   const programId = new PublicKey(PROGRAM_ID_STRING);
   
   return PublicKey.findProgramAddressSync(
-    [Buffer.from("etf"), listerPubkey.toBuffer()],
+    [Buffer.from("etf"), listerPubkey.toBuffer(), Buffer.from([etfIndex])],
     programId
   );
 }
@@ -167,9 +85,15 @@ export async function fetchEtfAccount(etfPubkey: PublicKey, network: 'mainnet' |
     const etfAccount = await program.account.etf.fetch(etfPubkey);
     return {
       lister: (etfAccount.lister as PublicKey).toString(),
+      etfIndex: etfAccount.etfIndex,
       tokenAddresses: (etfAccount.tokenAddresses as PublicKey[]).map((pk: PublicKey) => pk.toString()),
       totalSupply: (etfAccount.totalSupply as any).toNumber(),
       bump: etfAccount.bump,
+      name: etfAccount.name as string,
+      symbol: etfAccount.symbol as string,
+      uri: etfAccount.uri as string,
+      solAssets: (etfAccount.solAssets as any).toNumber(),
+      holdings: (etfAccount.holdings as any[]).map((amount) => amount.toString()),
     };
   } catch (error) {
     console.error('Failed to fetch ETF account:', error);
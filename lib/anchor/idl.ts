@@ -1,254 +1,5327 @@
 // MTF ETF Program IDL
-// This IDL should match the deployed Anchor program on devnet
+// Generated from programs/mtf-etf - whenever the program changes, run
+// `anchor idl build` in programs/mtf-etf and paste its JSON below
 
 // Program ID - Deployed on Solana devnet
 // To redeploy: cd programs/mtf-etf && anchor build && anchor deploy --provider.cluster devnet
 export const PROGRAM_ID = 'CwwrCDfrsuA2C4YPiobU82ZA9wSWrecyLbbvP35QXmyo';
 
 export const IDL = {
-  "version": "0.1.0",
-  "name": "mtf_etf",
+  "address": "CwwrCDfrsuA2C4YPiobU82ZA9wSWrecyLbbvP35QXmyo",
+  "metadata": {
+    "name": "mtf_etf",
+    "version": "0.1.0",
+    "spec": "0.1.0"
+  },
   "instructions": [
     {
-      "name": "initializeEtf",
-      "docs": [
-        "Initialize a new ETF with a list of token addresses.",
-        "Each wallet can only create one ETF (PDA is derived from lister pubkey)."
+      "name": "begin_liquidation",
+      "discriminator": [
+        198,
+        82,
+        224,
+        235,
+        190,
+        89,
+        172,
+        146
       ],
       "accounts": [
         {
           "name": "etf",
-          "isMut": true,
-          "isSigner": false,
-          "pda": {
-            "seeds": [
-              {
-                "kind": "const",
-                "type": "string",
-                "value": "etf"
-              },
-              {
-                "kind": "account",
-                "type": "publicKey",
-                "account": "lister",
-                "path": "lister"
-              }
-            ]
-          }
-        },
-        {
-          "name": "lister",
-          "isMut": true,
-          "isSigner": true
+          "writable": true
         },
         {
-          "name": "systemProgram",
-          "isMut": false,
-          "isSigner": false
+          "name": "authority",
+          "signer": true
         }
       ],
-      "args": [
-        {
-          "name": "tokenAddresses",
-          "type": {
-            "vec": "publicKey"
-          }
-        }
-      ]
+      "args": []
     },
     {
-      "name": "buyEtf",
-      "docs": [
-        "Buy into an ETF by depositing SOL.",
-        "Fees: 0.5% to ETF creator, 0.5% to dev wallet (automatic transfer).",
-        "Remaining SOL is used for token swaps via backend."
+      "name": "buy_etf",
+      "discriminator": [
+        243,
+        110,
+        32,
+        193,
+        56,
+        91,
+        105,
+        249
       ],
       "accounts": [
         {
           "name": "etf",
-          "isMut": true,
-          "isSigner": false
+          "writable": true
+        },
+        {
+          "name": "position",
+          "writable": true
         },
         {
           "name": "investor",
-          "isMut": true,
-          "isSigner": true
+          "writable": true,
+          "signer": true
+        },
+        {
+          "name": "lister_account",
+          "writable": true
+        },
+        {
+          "name": "dev_wallet",
+          "writable": true
         },
         {
-          "name": "listerAccount",
-          "isMut": true,
-          "isSigner": false,
-          "docs": ["ETF creator's account for receiving 0.5% fee"]
+          "name": "system_program"
         },
         {
-          "name": "devWallet",
-          "isMut": true,
-          "isSigner": false,
-          "docs": ["Dev wallet for receiving 0.5% fee (hardcoded in contract)"]
+          "name": "pending_purchase",
+          "writable": true,
+          "optional": true
         },
         {
-          "name": "systemProgram",
-          "isMut": false,
-          "isSigner": false
+          "name": "gate_account",
+          "optional": true
         }
       ],
       "args": [
         {
-          "name": "solAmount",
+          "name": "sol_amount",
           "type": "u64"
         },
         {
-          "name": "tokenPercentages",
-          "type": {
-            "vec": "u8"
-          }
+          "name": "token_percentages",
+          "type": "bytes"
         }
       ]
     },
     {
-      "name": "sellEtf",
-      "docs": [
-        "Sell ETF tokens back for SOL.",
-        "Fees: 0.5% to ETF creator, 0.5% to dev wallet (automatic transfer).",
-        "Remaining SOL is returned to the investor."
+      "name": "cancel_dca_plan",
+      "discriminator": [
+        238,
+        106,
+        180,
+        217,
+        78,
+        107,
+        103,
+        215
       ],
       "accounts": [
         {
-          "name": "etf",
-          "isMut": true,
-          "isSigner": false
+          "name": "etf"
+        },
+        {
+          "name": "dca_plan",
+          "writable": true
+        },
+        {
+          "name": "investor",
+          "writable": true,
+          "signer": true
+        }
+      ],
+      "args": []
+    },
+    {
+      "name": "cancel_exit_trigger",
+      "discriminator": [
+        24,
+        221,
+        24,
+        154,
+        180,
+        137,
+        79,
+        225
+      ],
+      "accounts": [
+        {
+          "name": "etf"
+        },
+        {
+          "name": "exit_trigger",
+          "writable": true
         },
         {
           "name": "investor",
-          "isMut": true,
-          "isSigner": true
+          "writable": true,
+          "signer": true
+        }
+      ],
+      "args": []
+    },
+    {
+      "name": "cancel_limit_order",
+      "discriminator": [
+        132,
+        156,
+        132,
+        31,
+        67,
+        40,
+        232,
+        97
+      ],
+      "accounts": [
+        {
+          "name": "etf"
+        },
+        {
+          "name": "order",
+          "writable": true
+        },
+        {
+          "name": "position",
+          "writable": true,
+          "optional": true
+        },
+        {
+          "name": "owner",
+          "writable": true,
+          "signer": true
         },
         {
-          "name": "listerAccount",
-          "isMut": true,
-          "isSigner": false,
-          "docs": ["ETF creator's account for receiving 0.5% fee"]
+          "name": "system_program"
+        }
+      ],
+      "args": []
+    },
+    {
+      "name": "claim_creator_reward",
+      "discriminator": [
+        174,
+        210,
+        14,
+        57,
+        187,
+        18,
+        230,
+        37
+      ],
+      "accounts": [
+        {
+          "name": "epoch_rewards",
+          "writable": true
         },
         {
-          "name": "devWallet",
-          "isMut": true,
-          "isSigner": false,
-          "docs": ["Dev wallet for receiving 0.5% fee (hardcoded in contract)"]
+          "name": "treasury",
+          "writable": true
         },
         {
-          "name": "systemProgram",
-          "isMut": false,
-          "isSigner": false
+          "name": "lister",
+          "writable": true,
+          "signer": true
         }
       ],
       "args": [
         {
-          "name": "tokensToSell",
-          "type": "u64"
+          "name": "rank",
+          "type": "u8"
         }
       ]
     },
     {
-      "name": "closeEtf",
-      "docs": [
-        "Close an ETF and reclaim rent.",
-        "Only the lister can close, and only if total_supply is 0."
+      "name": "claim_liquidation",
+      "discriminator": [
+        40,
+        164,
+        45,
+        19,
+        185,
+        47,
+        171,
+        117
       ],
       "accounts": [
         {
           "name": "etf",
-          "isMut": true,
-          "isSigner": false
+          "writable": true
         },
         {
-          "name": "lister",
-          "isMut": true,
-          "isSigner": true
+          "name": "position",
+          "writable": true
+        },
+        {
+          "name": "investor",
+          "writable": true,
+          "signer": true
         },
         {
-          "name": "systemProgram",
-          "isMut": false,
-          "isSigner": false
+          "name": "token_program",
+          "optional": true
         }
       ],
       "args": []
-    }
-  ],
-  "accounts": [
+    },
     {
-      "name": "ETF",
-      "type": {
-        "kind": "struct",
-        "fields": [
-          {
-            "name": "lister",
-            "type": "publicKey",
-            "docs": ["The wallet that created this ETF"]
-          },
-          {
-            "name": "tokenAddresses",
-            "type": {
-              "vec": "publicKey"
-            },
-            "docs": ["List of token contract addresses in this ETF"]
-          },
-          {
-            "name": "totalSupply",
-            "type": "u64",
-            "docs": ["Total supply of ETF tokens minted"]
-          },
-          {
-            "name": "bump",
-            "type": "u8",
-            "docs": ["PDA bump seed"]
-          }
-        ]
-      }
-    }
-  ],
-  "errors": [
+      "name": "claim_parent_fees",
+      "discriminator": [
+        121,
+        193,
+        225,
+        251,
+        59,
+        187,
+        73,
+        49
+      ],
+      "accounts": [
+        {
+          "name": "etf",
+          "writable": true
+        },
+        {
+          "name": "parent_lister",
+          "writable": true
+        }
+      ],
+      "args": []
+    },
     {
-      "code": 6000,
-      "name": "InsufficientFunds",
-      "msg": "Insufficient funds for this operation"
+      "name": "close_etf",
+      "discriminator": [
+        161,
+        124,
+        23,
+        253,
+        141,
+        103,
+        143,
+        240
+      ],
+      "accounts": [
+        {
+          "name": "etf",
+          "writable": true
+        },
+        {
+          "name": "registry",
+          "writable": true
+        },
+        {
+          "name": "registry_page",
+          "writable": true
+        },
+        {
+          "name": "share_mint"
+        },
+        {
+          "name": "lister",
+          "writable": true,
+          "signer": true
+        },
+        {
+          "name": "token_program"
+        },
+        {
+          "name": "system_program"
+        }
+      ],
+      "args": []
     },
     {
-      "code": 6001,
-      "name": "InvalidAmount",
-      "msg": "Invalid amount specified"
+      "name": "close_liquidated_etf",
+      "discriminator": [
+        136,
+        136,
+        253,
+        13,
+        184,
+        78,
+        207,
+        250
+      ],
+      "accounts": [
+        {
+          "name": "etf",
+          "writable": true
+        },
+        {
+          "name": "registry",
+          "writable": true
+        },
+        {
+          "name": "registry_page",
+          "writable": true
+        },
+        {
+          "name": "share_mint"
+        },
+        {
+          "name": "lister",
+          "writable": true
+        },
+        {
+          "name": "caller",
+          "signer": true
+        },
+        {
+          "name": "token_program"
+        }
+      ],
+      "args": []
     },
     {
-      "code": 6002,
-      "name": "Unauthorized",
-      "msg": "You are not authorized to perform this action"
+      "name": "configure_treasury",
+      "discriminator": [
+        61,
+        55,
+        46,
+        68,
+        135,
+        52,
+        168,
+        53
+      ],
+      "accounts": [
+        {
+          "name": "treasury",
+          "writable": true
+        },
+        {
+          "name": "authority",
+          "writable": true,
+          "signer": true
+        },
+        {
+          "name": "system_program"
+        }
+      ],
+      "args": [
+        {
+          "name": "rewards_share_bps",
+          "type": "u16"
+        }
+      ]
     },
     {
-      "code": 6003,
-      "name": "InvalidTokenPercentages",
-      "msg": "Invalid token percentages - must sum to 100"
+      "name": "create_dca_plan",
+      "discriminator": [
+        161,
+        215,
+        52,
+        81,
+        136,
+        239,
+        199,
+        249
+      ],
+      "accounts": [
+        {
+          "name": "etf"
+        },
+        {
+          "name": "dca_plan",
+          "writable": true
+        },
+        {
+          "name": "position",
+          "writable": true
+        },
+        {
+          "name": "investor",
+          "writable": true,
+          "signer": true
+        },
+        {
+          "name": "system_program"
+        }
+      ],
+      "args": [
+        {
+          "name": "amount_per_period",
+          "type": "u64"
+        },
+        {
+          "name": "interval_seconds",
+          "type": "i64"
+        },
+        {
+          "name": "token_percentages",
+          "type": "bytes"
+        },
+        {
+          "name": "deposit",
+          "type": "u64"
+        }
+      ]
     },
     {
-      "code": 6004,
-      "name": "CannotCloseWithSupply",
-      "msg": "Cannot close ETF with outstanding supply"
+      "name": "create_leaderboard_epoch",
+      "discriminator": [
+        241,
+        85,
+        162,
+        223,
+        108,
+        23,
+        237,
+        1
+      ],
+      "accounts": [
+        {
+          "name": "leaderboard",
+          "writable": true
+        },
+        {
+          "name": "payer",
+          "writable": true,
+          "signer": true
+        },
+        {
+          "name": "system_program"
+        }
+      ],
+      "args": [
+        {
+          "name": "epoch_id",
+          "type": "u64"
+        }
+      ]
     },
     {
-      "code": 6005,
-      "name": "InvalidTokenCount",
-      "msg": "Invalid token count - must be between 1 and 10"
+      "name": "create_nav_history",
+      "discriminator": [
+        223,
+        220,
+        238,
+        165,
+        52,
+        224,
+        169,
+        28
+      ],
+      "accounts": [
+        {
+          "name": "etf"
+        },
+        {
+          "name": "nav_history",
+          "writable": true
+        },
+        {
+          "name": "payer",
+          "writable": true,
+          "signer": true
+        },
+        {
+          "name": "system_program"
+        }
+      ],
+      "args": []
     },
     {
-      "code": 6006,
-      "name": "InvalidDevWallet",
-      "msg": "Invalid dev wallet address"
+      "name": "create_share_mint",
+      "discriminator": [
+        91,
+        27,
+        147,
+        39,
+        213,
+        104,
+        78,
+        140
+      ],
+      "accounts": [
+        {
+          "name": "etf"
+        },
+        {
+          "name": "share_mint",
+          "writable": true
+        },
+        {
+          "name": "payer",
+          "writable": true,
+          "signer": true
+        },
+        {
+          "name": "token_program"
+        },
+        {
+          "name": "system_program"
+        }
+      ],
+      "args": []
     },
     {
-      "code": 6007,
-      "name": "InvalidListerAccount",
-      "msg": "Invalid lister account - must match ETF creator"
-    }
-  ],
-  "metadata": {
-    "address": "CwwrCDfrsuA2C4YPiobU82ZA9wSWrecyLbbvP35QXmyo"
-  }
-};
+      "name": "execute_dca",
+      "discriminator": [
+        129,
+        25,
+        89,
+        105,
+        132,
+        188,
+        156,
+        3
+      ],
+      "accounts": [
+        {
+          "name": "etf",
+          "writable": true
+        },
+        {
+          "name": "dca_plan",
+          "writable": true
+        },
+        {
+          "name": "position",
+          "writable": true
+        },
+        {
+          "name": "lister_account",
+          "writable": true
+        },
+        {
+          "name": "dev_wallet",
+          "writable": true
+        },
+        {
+          "name": "caller",
+          "writable": true,
+          "signer": true
+        },
+        {
+          "name": "gate_account",
+          "optional": true
+        }
+      ],
+      "args": []
+    },
+    {
+      "name": "fill_limit_order",
+      "discriminator": [
+        83,
+        74,
+        211,
+        114,
+        227,
+        230,
+        105,
+        177
+      ],
+      "accounts": [
+        {
+          "name": "etf",
+          "writable": true
+        },
+        {
+          "name": "order",
+          "writable": true
+        },
+        {
+          "name": "position",
+          "writable": true
+        },
+        {
+          "name": "owner",
+          "writable": true
+        },
+        {
+          "name": "lister_account",
+          "writable": true
+        },
+        {
+          "name": "dev_wallet",
+          "writable": true
+        },
+        {
+          "name": "filler",
+          "writable": true,
+          "signer": true
+        },
+        {
+          "name": "gate_account",
+          "optional": true
+        },
+        {
+          "name": "pending_purchase",
+          "writable": true,
+          "optional": true
+        },
+        {
+          "name": "token_program"
+        },
+        {
+          "name": "system_program"
+        }
+      ],
+      "args": []
+    },
+    {
+      "name": "finalize_epoch",
+      "discriminator": [
+        159,
+        93,
+        117,
+        217,
+        63,
+        44,
+        249,
+        76
+      ],
+      "accounts": [
+        {
+          "name": "leaderboard"
+        },
+        {
+          "name": "epoch_rewards",
+          "writable": true
+        },
+        {
+          "name": "treasury",
+          "writable": true
+        },
+        {
+          "name": "payer",
+          "writable": true,
+          "signer": true
+        },
+        {
+          "name": "system_program"
+        }
+      ],
+      "args": []
+    },
+    {
+      "name": "fork_etf",
+      "discriminator": [
+        104,
+        86,
+        28,
+        148,
+        181,
+        102,
+        34,
+        104
+      ],
+      "accounts": [
+        {
+          "name": "etf",
+          "writable": true
+        },
+        {
+          "name": "parent"
+        },
+        {
+          "name": "registry",
+          "writable": true
+        },
+        {
+          "name": "registry_page",
+          "writable": true
+        },
+        {
+          "name": "lister",
+          "writable": true,
+          "signer": true
+        },
+        {
+          "name": "system_program"
+        }
+      ],
+      "args": [
+        {
+          "name": "etf_index",
+          "type": "u8"
+        },
+        {
+          "name": "changes",
+          "type": {
+            "defined": {
+              "name": "ForkChanges"
+            }
+          }
+        },
+        {
+          "name": "name",
+          "type": "string"
+        },
+        {
+          "name": "symbol",
+          "type": "string"
+        },
+        {
+          "name": "uri",
+          "type": "string"
+        },
+        {
+          "name": "category",
+          "type": {
+            "defined": {
+              "name": "EtfCategory"
+            }
+          }
+        }
+      ]
+    },
+    {
+      "name": "fund_dca_plan",
+      "discriminator": [
+        19,
+        139,
+        164,
+        58,
+        87,
+        207,
+        197,
+        234
+      ],
+      "accounts": [
+        {
+          "name": "dca_plan",
+          "writable": true
+        },
+        {
+          "name": "investor",
+          "writable": true,
+          "signer": true
+        },
+        {
+          "name": "system_program"
+        }
+      ],
+      "args": [
+        {
+          "name": "amount",
+          "type": "u64"
+        }
+      ]
+    },
+    {
+      "name": "fund_rewards",
+      "discriminator": [
+        114,
+        64,
+        163,
+        112,
+        175,
+        167,
+        19,
+        121
+      ],
+      "accounts": [
+        {
+          "name": "treasury",
+          "writable": true
+        },
+        {
+          "name": "funder",
+          "writable": true,
+          "signer": true
+        },
+        {
+          "name": "system_program"
+        }
+      ],
+      "args": [
+        {
+          "name": "amount",
+          "type": "u64"
+        }
+      ]
+    },
+    {
+      "name": "get_nav",
+      "discriminator": [
+        200,
+        89,
+        76,
+        53,
+        215,
+        218,
+        63,
+        21
+      ],
+      "accounts": [
+        {
+          "name": "etf"
+        }
+      ],
+      "args": [],
+      "returns": {
+        "defined": {
+          "name": "NavQuote"
+        }
+      }
+    },
+    {
+      "name": "get_nav_twap",
+      "discriminator": [
+        140,
+        2,
+        31,
+        130,
+        129,
+        100,
+        31,
+        52
+      ],
+      "accounts": [
+        {
+          "name": "nav_history"
+        }
+      ],
+      "args": [
+        {
+          "name": "window_seconds",
+          "type": "i64"
+        }
+      ],
+      "returns": {
+        "defined": {
+          "name": "TwapQuote"
+        }
+      }
+    },
+    {
+      "name": "initialize_etf",
+      "discriminator": [
+        123,
+        32,
+        97,
+        226,
+        112,
+        102,
+        12,
+        181
+      ],
+      "accounts": [
+        {
+          "name": "etf",
+          "writable": true
+        },
+        {
+          "name": "registry",
+          "writable": true
+        },
+        {
+          "name": "registry_page",
+          "writable": true
+        },
+        {
+          "name": "lister",
+          "writable": true,
+          "signer": true
+        },
+        {
+          "name": "system_program"
+        }
+      ],
+      "args": [
+        {
+          "name": "etf_index",
+          "type": "u8"
+        },
+        {
+          "name": "token_addresses",
+          "type": {
+            "vec": "pubkey"
+          }
+        },
+        {
+          "name": "name",
+          "type": "string"
+        },
+        {
+          "name": "symbol",
+          "type": "string"
+        },
+        {
+          "name": "uri",
+          "type": "string"
+        },
+        {
+          "name": "category",
+          "type": {
+            "defined": {
+              "name": "EtfCategory"
+            }
+          }
+        }
+      ]
+    },
+    {
+      "name": "liquidate_vault",
+      "discriminator": [
+        106,
+        212,
+        123,
+        68,
+        193,
+        252,
+        239,
+        189
+      ],
+      "accounts": [
+        {
+          "name": "etf",
+          "writable": true
+        },
+        {
+          "name": "authority",
+          "writable": true,
+          "signer": true
+        },
+        {
+          "name": "vault",
+          "writable": true
+        },
+        {
+          "name": "destination",
+          "writable": true
+        },
+        {
+          "name": "price_feed"
+        },
+        {
+          "name": "token_program"
+        },
+        {
+          "name": "system_program"
+        }
+      ],
+      "args": [
+        {
+          "name": "token_amount",
+          "type": "u64"
+        },
+        {
+          "name": "sol_amount",
+          "type": "u64"
+        }
+      ]
+    },
+    {
+      "name": "migrate_etf",
+      "discriminator": [
+        159,
+        118,
+        151,
+        124,
+        45,
+        193,
+        46,
+        91
+      ],
+      "accounts": [
+        {
+          "name": "etf",
+          "writable": true
+        },
+        {
+          "name": "registry",
+          "writable": true
+        },
+        {
+          "name": "registry_page",
+          "writable": true
+        },
+        {
+          "name": "payer",
+          "writable": true,
+          "signer": true
+        },
+        {
+          "name": "system_program"
+        }
+      ],
+      "args": []
+    },
+    {
+      "name": "place_limit_order",
+      "discriminator": [
+        108,
+        176,
+        33,
+        186,
+        146,
+        229,
+        1,
+        197
+      ],
+      "accounts": [
+        {
+          "name": "etf"
+        },
+        {
+          "name": "order",
+          "writable": true
+        },
+        {
+          "name": "position",
+          "writable": true
+        },
+        {
+          "name": "owner",
+          "writable": true,
+          "signer": true
+        },
+        {
+          "name": "system_program"
+        }
+      ],
+      "args": [
+        {
+          "name": "order_id",
+          "type": "u64"
+        },
+        {
+          "name": "side",
+          "type": {
+            "defined": {
+              "name": "OrderSide"
+            }
+          }
+        },
+        {
+          "name": "amount",
+          "type": "u64"
+        },
+        {
+          "name": "target_nav",
+          "type": "u64"
+        },
+        {
+          "name": "token_percentages",
+          "type": "bytes"
+        },
+        {
+          "name": "deposit",
+          "type": "u64"
+        }
+      ]
+    },
+    {
+      "name": "quote_buy",
+      "discriminator": [
+        83,
+        9,
+        231,
+        110,
+        146,
+        31,
+        40,
+        12
+      ],
+      "accounts": [
+        {
+          "name": "etf"
+        }
+      ],
+      "args": [
+        {
+          "name": "sol_amount",
+          "type": "u64"
+        },
+        {
+          "name": "token_percentages",
+          "type": "bytes"
+        }
+      ],
+      "returns": {
+        "defined": {
+          "name": "BuyQuote"
+        }
+      }
+    },
+    {
+      "name": "quote_sell",
+      "discriminator": [
+        5,
+        178,
+        49,
+        206,
+        140,
+        231,
+        131,
+        145
+      ],
+      "accounts": [
+        {
+          "name": "etf"
+        }
+      ],
+      "args": [
+        {
+          "name": "shares",
+          "type": "u64"
+        }
+      ],
+      "returns": {
+        "defined": {
+          "name": "SellQuote"
+        }
+      }
+    },
+    {
+      "name": "redeem_to_tokens",
+      "discriminator": [
+        137,
+        131,
+        52,
+        156,
+        143,
+        155,
+        38,
+        60
+      ],
+      "accounts": [
+        {
+          "name": "etf",
+          "writable": true
+        },
+        {
+          "name": "position",
+          "writable": true
+        },
+        {
+          "name": "investor",
+          "writable": true,
+          "signer": true
+        },
+        {
+          "name": "lister_account",
+          "writable": true
+        },
+        {
+          "name": "dev_wallet",
+          "writable": true
+        },
+        {
+          "name": "token_program"
+        },
+        {
+          "name": "system_program"
+        }
+      ],
+      "args": [
+        {
+          "name": "shares",
+          "type": "u64"
+        }
+      ]
+    },
+    {
+      "name": "refund_pending_purchase",
+      "discriminator": [
+        64,
+        161,
+        108,
+        61,
+        125,
+        80,
+        122,
+        149
+      ],
+      "accounts": [
+        {
+          "name": "etf",
+          "writable": true
+        },
+        {
+          "name": "pending_purchase",
+          "writable": true
+        },
+        {
+          "name": "investor",
+          "writable": true,
+          "signer": true
+        }
+      ],
+      "args": []
+    },
+    {
+      "name": "restore_legacy_position",
+      "discriminator": [
+        103,
+        54,
+        236,
+        121,
+        216,
+        19,
+        222,
+        184
+      ],
+      "accounts": [
+        {
+          "name": "etf",
+          "writable": true
+        },
+        {
+          "name": "position",
+          "writable": true
+        },
+        {
+          "name": "investor"
+        },
+        {
+          "name": "authority",
+          "writable": true,
+          "signer": true
+        },
+        {
+          "name": "system_program"
+        }
+      ],
+      "args": [
+        {
+          "name": "shares",
+          "type": "u64"
+        }
+      ]
+    },
+    {
+      "name": "sell_etf",
+      "discriminator": [
+        211,
+        13,
+        160,
+        84,
+        187,
+        109,
+        174,
+        30
+      ],
+      "accounts": [
+        {
+          "name": "etf",
+          "writable": true
+        },
+        {
+          "name": "position",
+          "writable": true
+        },
+        {
+          "name": "investor",
+          "writable": true,
+          "signer": true
+        },
+        {
+          "name": "lister_account",
+          "writable": true
+        },
+        {
+          "name": "dev_wallet",
+          "writable": true
+        },
+        {
+          "name": "system_program"
+        }
+      ],
+      "args": [
+        {
+          "name": "tokens_to_sell",
+          "type": "u64"
+        }
+      ]
+    },
+    {
+      "name": "set_buy_limits",
+      "discriminator": [
+        232,
+        195,
+        160,
+        255,
+        129,
+        88,
+        232,
+        38
+      ],
+      "accounts": [
+        {
+          "name": "etf",
+          "writable": true
+        },
+        {
+          "name": "lister",
+          "signer": true
+        }
+      ],
+      "args": [
+        {
+          "name": "limits",
+          "type": {
+            "defined": {
+              "name": "BuyLimits"
+            }
+          }
+        }
+      ]
+    },
+    {
+      "name": "set_exit_trigger",
+      "discriminator": [
+        243,
+        167,
+        167,
+        188,
+        252,
+        19,
+        193,
+        160
+      ],
+      "accounts": [
+        {
+          "name": "etf"
+        },
+        {
+          "name": "exit_trigger",
+          "writable": true
+        },
+        {
+          "name": "position"
+        },
+        {
+          "name": "investor",
+          "writable": true,
+          "signer": true
+        },
+        {
+          "name": "system_program"
+        }
+      ],
+      "args": [
+        {
+          "name": "stop_loss_nav",
+          "type": "u64"
+        },
+        {
+          "name": "take_profit_nav",
+          "type": "u64"
+        },
+        {
+          "name": "shares",
+          "type": "u64"
+        },
+        {
+          "name": "deposit",
+          "type": "u64"
+        }
+      ]
+    },
+    {
+      "name": "set_gate",
+      "discriminator": [
+        160,
+        18,
+        221,
+        241,
+        119,
+        196,
+        121,
+        6
+      ],
+      "accounts": [
+        {
+          "name": "etf",
+          "writable": true
+        },
+        {
+          "name": "lister",
+          "signer": true
+        }
+      ],
+      "args": [
+        {
+          "name": "gate",
+          "type": {
+            "defined": {
+              "name": "EtfGate"
+            }
+          }
+        }
+      ]
+    },
+    {
+      "name": "set_hold_period",
+      "discriminator": [
+        216,
+        158,
+        76,
+        224,
+        25,
+        221,
+        212,
+        35
+      ],
+      "accounts": [
+        {
+          "name": "etf",
+          "writable": true
+        },
+        {
+          "name": "lister",
+          "signer": true
+        }
+      ],
+      "args": [
+        {
+          "name": "min_hold_seconds",
+          "type": "i64"
+        },
+        {
+          "name": "early_exit_fee_bps",
+          "type": "u16"
+        }
+      ]
+    },
+    {
+      "name": "set_keeper",
+      "discriminator": [
+        102,
+        94,
+        23,
+        78,
+        157,
+        222,
+        243,
+        214
+      ],
+      "accounts": [
+        {
+          "name": "etf",
+          "writable": true
+        },
+        {
+          "name": "lister",
+          "signer": true
+        }
+      ],
+      "args": [
+        {
+          "name": "keeper",
+          "type": {
+            "option": "pubkey"
+          }
+        },
+        {
+          "name": "refund_timeout",
+          "type": "i64"
+        }
+      ]
+    },
+    {
+      "name": "set_price",
+      "discriminator": [
+        16,
+        19,
+        182,
+        8,
+        149,
+        83,
+        72,
+        181
+      ],
+      "accounts": [
+        {
+          "name": "price_feed",
+          "writable": true
+        },
+        {
+          "name": "mint"
+        },
+        {
+          "name": "authority",
+          "writable": true,
+          "signer": true
+        },
+        {
+          "name": "system_program"
+        }
+      ],
+      "args": [
+        {
+          "name": "price",
+          "type": "u64"
+        }
+      ]
+    },
+    {
+      "name": "settle_purchase",
+      "discriminator": [
+        96,
+        123,
+        151,
+        42,
+        186,
+        39,
+        84,
+        111
+      ],
+      "accounts": [
+        {
+          "name": "etf",
+          "writable": true
+        },
+        {
+          "name": "pending_purchase",
+          "writable": true
+        },
+        {
+          "name": "position",
+          "writable": true
+        },
+        {
+          "name": "investor",
+          "writable": true
+        },
+        {
+          "name": "keeper",
+          "writable": true,
+          "signer": true
+        },
+        {
+          "name": "token_program"
+        }
+      ],
+      "args": [
+        {
+          "name": "token_amounts",
+          "type": {
+            "vec": "u64"
+          }
+        }
+      ]
+    },
+    {
+      "name": "snapshot_nav",
+      "discriminator": [
+        163,
+        195,
+        165,
+        14,
+        98,
+        237,
+        94,
+        25
+      ],
+      "accounts": [
+        {
+          "name": "etf"
+        },
+        {
+          "name": "nav_history",
+          "writable": true
+        }
+      ],
+      "args": []
+    },
+    {
+      "name": "submit_epoch_return",
+      "discriminator": [
+        215,
+        249,
+        101,
+        32,
+        192,
+        1,
+        200,
+        247
+      ],
+      "accounts": [
+        {
+          "name": "leaderboard",
+          "writable": true
+        },
+        {
+          "name": "etf"
+        },
+        {
+          "name": "nav_history"
+        }
+      ],
+      "args": []
+    },
+    {
+      "name": "trigger_exit",
+      "discriminator": [
+        128,
+        24,
+        86,
+        81,
+        168,
+        238,
+        146,
+        146
+      ],
+      "accounts": [
+        {
+          "name": "etf",
+          "writable": true
+        },
+        {
+          "name": "exit_trigger",
+          "writable": true
+        },
+        {
+          "name": "position",
+          "writable": true
+        },
+        {
+          "name": "investor",
+          "writable": true
+        },
+        {
+          "name": "lister_account",
+          "writable": true
+        },
+        {
+          "name": "dev_wallet",
+          "writable": true
+        },
+        {
+          "name": "caller",
+          "writable": true,
+          "signer": true
+        },
+        {
+          "name": "token_program"
+        }
+      ],
+      "args": []
+    },
+    {
+      "name": "unwrap_shares",
+      "discriminator": [
+        14,
+        229,
+        244,
+        67,
+        136,
+        188,
+        183,
+        46
+      ],
+      "accounts": [
+        {
+          "name": "etf"
+        },
+        {
+          "name": "share_mint",
+          "writable": true
+        },
+        {
+          "name": "source",
+          "writable": true
+        },
+        {
+          "name": "owner",
+          "writable": true,
+          "signer": true
+        },
+        {
+          "name": "recipient"
+        },
+        {
+          "name": "recipient_position",
+          "writable": true
+        },
+        {
+          "name": "token_program"
+        },
+        {
+          "name": "system_program"
+        }
+      ],
+      "args": [
+        {
+          "name": "amount",
+          "type": "u64"
+        }
+      ]
+    },
+    {
+      "name": "update_metadata",
+      "discriminator": [
+        170,
+        182,
+        43,
+        239,
+        97,
+        78,
+        225,
+        186
+      ],
+      "accounts": [
+        {
+          "name": "etf",
+          "writable": true
+        },
+        {
+          "name": "share_mint",
+          "writable": true
+        },
+        {
+          "name": "lister",
+          "writable": true,
+          "signer": true
+        },
+        {
+          "name": "token_program"
+        },
+        {
+          "name": "system_program"
+        }
+      ],
+      "args": [
+        {
+          "name": "name",
+          "type": "string"
+        },
+        {
+          "name": "symbol",
+          "type": "string"
+        },
+        {
+          "name": "uri",
+          "type": "string"
+        },
+        {
+          "name": "category",
+          "type": {
+            "defined": {
+              "name": "EtfCategory"
+            }
+          }
+        }
+      ]
+    },
+    {
+      "name": "verify_allowlist",
+      "discriminator": [
+        32,
+        251,
+        20,
+        198,
+        19,
+        175,
+        95,
+        105
+      ],
+      "accounts": [
+        {
+          "name": "etf"
+        },
+        {
+          "name": "allowlist_pass",
+          "writable": true
+        },
+        {
+          "name": "investor",
+          "writable": true,
+          "signer": true
+        },
+        {
+          "name": "system_program"
+        }
+      ],
+      "args": [
+        {
+          "name": "proof",
+          "type": {
+            "vec": {
+              "array": [
+                "u8",
+                32
+              ]
+            }
+          }
+        }
+      ]
+    },
+    {
+      "name": "withdraw_dev_fees",
+      "discriminator": [
+        120,
+        194,
+        245,
+        142,
+        246,
+        177,
+        195,
+        9
+      ],
+      "accounts": [
+        {
+          "name": "treasury",
+          "writable": true
+        },
+        {
+          "name": "dev_wallet",
+          "writable": true
+        }
+      ],
+      "args": []
+    },
+    {
+      "name": "wrap_shares",
+      "discriminator": [
+        218,
+        167,
+        56,
+        170,
+        121,
+        220,
+        93,
+        217
+      ],
+      "accounts": [
+        {
+          "name": "etf"
+        },
+        {
+          "name": "position",
+          "writable": true
+        },
+        {
+          "name": "share_mint",
+          "writable": true
+        },
+        {
+          "name": "destination",
+          "writable": true
+        },
+        {
+          "name": "owner",
+          "signer": true
+        },
+        {
+          "name": "token_program"
+        }
+      ],
+      "args": [
+        {
+          "name": "amount",
+          "type": "u64"
+        }
+      ]
+    }
+  ],
+  "accounts": [
+    {
+      "name": "AllowlistPass",
+      "discriminator": [
+        84,
+        190,
+        211,
+        149,
+        46,
+        2,
+        85,
+        129
+      ]
+    },
+    {
+      "name": "DcaPlan",
+      "discriminator": [
+        231,
+        97,
+        112,
+        227,
+        171,
+        241,
+        52,
+        84
+      ]
+    },
+    {
+      "name": "ETF",
+      "discriminator": [
+        144,
+        207,
+        151,
+        113,
+        181,
+        176,
+        56,
+        54
+      ]
+    },
+    {
+      "name": "EpochRewards",
+      "discriminator": [
+        227,
+        115,
+        228,
+        203,
+        43,
+        151,
+        208,
+        253
+      ]
+    },
+    {
+      "name": "ExitTrigger",
+      "discriminator": [
+        141,
+        197,
+        206,
+        26,
+        33,
+        4,
+        179,
+        179
+      ]
+    },
+    {
+      "name": "LeaderboardEpoch",
+      "discriminator": [
+        145,
+        47,
+        193,
+        212,
+        177,
+        64,
+        8,
+        196
+      ]
+    },
+    {
+      "name": "LimitOrder",
+      "discriminator": [
+        137,
+        183,
+        212,
+        91,
+        115,
+        29,
+        141,
+        227
+      ]
+    },
+    {
+      "name": "NavHistory",
+      "discriminator": [
+        40,
+        139,
+        233,
+        237,
+        46,
+        197,
+        105,
+        74
+      ]
+    },
+    {
+      "name": "PendingPurchase",
+      "discriminator": [
+        9,
+        94,
+        204,
+        196,
+        18,
+        163,
+        135,
+        193
+      ]
+    },
+    {
+      "name": "Position",
+      "discriminator": [
+        170,
+        188,
+        143,
+        228,
+        122,
+        64,
+        247,
+        208
+      ]
+    },
+    {
+      "name": "PriceFeed",
+      "discriminator": [
+        189,
+        103,
+        252,
+        23,
+        152,
+        35,
+        243,
+        156
+      ]
+    },
+    {
+      "name": "Registry",
+      "discriminator": [
+        47,
+        174,
+        110,
+        246,
+        184,
+        182,
+        252,
+        218
+      ]
+    },
+    {
+      "name": "RegistryPage",
+      "discriminator": [
+        190,
+        151,
+        207,
+        163,
+        226,
+        253,
+        16,
+        250
+      ]
+    },
+    {
+      "name": "Treasury",
+      "discriminator": [
+        238,
+        239,
+        123,
+        238,
+        89,
+        1,
+        168,
+        253
+      ]
+    }
+  ],
+  "events": [
+    {
+      "discriminator": [
+        192,
+        181,
+        21,
+        64,
+        166,
+        160,
+        227,
+        217
+      ],
+      "name": "BuyLimitsUpdatedEvent"
+    },
+    {
+      "discriminator": [
+        14,
+        157,
+        76,
+        78,
+        158,
+        92,
+        109,
+        219
+      ],
+      "name": "CreatorRewardClaimedEvent"
+    },
+    {
+      "discriminator": [
+        100,
+        140,
+        163,
+        175,
+        60,
+        199,
+        85,
+        178
+      ],
+      "name": "DcaExecutedEvent"
+    },
+    {
+      "discriminator": [
+        254,
+        184,
+        250,
+        89,
+        160,
+        239,
+        34,
+        59
+      ],
+      "name": "DcaPlanCancelledEvent"
+    },
+    {
+      "discriminator": [
+        140,
+        172,
+        42,
+        169,
+        36,
+        30,
+        136,
+        212
+      ],
+      "name": "DcaPlanCreatedEvent"
+    },
+    {
+      "discriminator": [
+        254,
+        177,
+        43,
+        86,
+        42,
+        136,
+        59,
+        112
+      ],
+      "name": "DcaPlanFundedEvent"
+    },
+    {
+      "discriminator": [
+        109,
+        95,
+        21,
+        138,
+        191,
+        116,
+        147,
+        233
+      ],
+      "name": "DevFeesWithdrawnEvent"
+    },
+    {
+      "discriminator": [
+        220,
+        208,
+        197,
+        160,
+        189,
+        138,
+        77,
+        252
+      ],
+      "name": "ETFBoughtEvent"
+    },
+    {
+      "discriminator": [
+        56,
+        10,
+        59,
+        97,
+        36,
+        70,
+        254,
+        210
+      ],
+      "name": "ETFClosedEvent"
+    },
+    {
+      "discriminator": [
+        143,
+        21,
+        251,
+        24,
+        110,
+        99,
+        184,
+        192
+      ],
+      "name": "ETFCreatedEvent"
+    },
+    {
+      "discriminator": [
+        172,
+        232,
+        202,
+        42,
+        102,
+        213,
+        15,
+        147
+      ],
+      "name": "ETFForkedEvent"
+    },
+    {
+      "discriminator": [
+        115,
+        209,
+        13,
+        230,
+        17,
+        220,
+        255,
+        18
+      ],
+      "name": "ETFMigratedEvent"
+    },
+    {
+      "discriminator": [
+        214,
+        144,
+        125,
+        7,
+        199,
+        214,
+        70,
+        9
+      ],
+      "name": "ETFSoldEvent"
+    },
+    {
+      "discriminator": [
+        45,
+        121,
+        61,
+        7,
+        50,
+        60,
+        224,
+        72
+      ],
+      "name": "EpochFinalizedEvent"
+    },
+    {
+      "discriminator": [
+        228,
+        160,
+        125,
+        125,
+        206,
+        179,
+        218,
+        120
+      ],
+      "name": "EpochReturnSubmittedEvent"
+    },
+    {
+      "discriminator": [
+        43,
+        45,
+        81,
+        156,
+        220,
+        119,
+        191,
+        167
+      ],
+      "name": "ExitTriggerCancelledEvent"
+    },
+    {
+      "discriminator": [
+        102,
+        18,
+        137,
+        184,
+        49,
+        139,
+        89,
+        113
+      ],
+      "name": "ExitTriggerSetEvent"
+    },
+    {
+      "discriminator": [
+        225,
+        146,
+        67,
+        245,
+        201,
+        79,
+        170,
+        62
+      ],
+      "name": "ExitTriggeredEvent"
+    },
+    {
+      "discriminator": [
+        58,
+        59,
+        96,
+        89,
+        105,
+        94,
+        78,
+        229
+      ],
+      "name": "FeeTransferEvent"
+    },
+    {
+      "discriminator": [
+        249,
+        248,
+        195,
+        128,
+        69,
+        148,
+        76,
+        223
+      ],
+      "name": "GateUpdatedEvent"
+    },
+    {
+      "discriminator": [
+        227,
+        254,
+        46,
+        12,
+        91,
+        90,
+        92,
+        130
+      ],
+      "name": "HoldPeriodUpdatedEvent"
+    },
+    {
+      "discriminator": [
+        247,
+        152,
+        31,
+        15,
+        26,
+        115,
+        161,
+        136
+      ],
+      "name": "KeeperUpdatedEvent"
+    },
+    {
+      "discriminator": [
+        45,
+        97,
+        50,
+        146,
+        128,
+        128,
+        52,
+        12
+      ],
+      "name": "LegacyPositionRestoredEvent"
+    },
+    {
+      "discriminator": [
+        195,
+        70,
+        253,
+        130,
+        46,
+        197,
+        104,
+        110
+      ],
+      "name": "LimitOrderCancelledEvent"
+    },
+    {
+      "discriminator": [
+        130,
+        187,
+        109,
+        222,
+        241,
+        249,
+        61,
+        130
+      ],
+      "name": "LimitOrderFilledEvent"
+    },
+    {
+      "discriminator": [
+        102,
+        100,
+        70,
+        242,
+        177,
+        143,
+        91,
+        181
+      ],
+      "name": "LimitOrderPlacedEvent"
+    },
+    {
+      "discriminator": [
+        214,
+        0,
+        205,
+        25,
+        240,
+        136,
+        208,
+        134
+      ],
+      "name": "LiquidationClaimEvent"
+    },
+    {
+      "discriminator": [
+        194,
+        150,
+        97,
+        190,
+        95,
+        27,
+        187,
+        162
+      ],
+      "name": "LiquidationStartedEvent"
+    },
+    {
+      "discriminator": [
+        66,
+        19,
+        187,
+        5,
+        244,
+        90,
+        125,
+        113
+      ],
+      "name": "MetadataUpdatedEvent"
+    },
+    {
+      "discriminator": [
+        200,
+        120,
+        56,
+        38,
+        3,
+        136,
+        95,
+        29
+      ],
+      "name": "NavSnapshotEvent"
+    },
+    {
+      "discriminator": [
+        217,
+        41,
+        52,
+        212,
+        0,
+        102,
+        162,
+        90
+      ],
+      "name": "ParentFeesClaimedEvent"
+    },
+    {
+      "discriminator": [
+        217,
+        171,
+        222,
+        24,
+        64,
+        152,
+        217,
+        36
+      ],
+      "name": "PriceUpdatedEvent"
+    },
+    {
+      "discriminator": [
+        34,
+        51,
+        106,
+        108,
+        134,
+        137,
+        155,
+        64
+      ],
+      "name": "PurchasePendingEvent"
+    },
+    {
+      "discriminator": [
+        235,
+        222,
+        104,
+        198,
+        110,
+        175,
+        145,
+        41
+      ],
+      "name": "PurchaseRefundedEvent"
+    },
+    {
+      "discriminator": [
+        217,
+        173,
+        63,
+        23,
+        67,
+        187,
+        19,
+        237
+      ],
+      "name": "PurchaseSettledEvent"
+    },
+    {
+      "discriminator": [
+        19,
+        42,
+        141,
+        88,
+        172,
+        133,
+        247,
+        147
+      ],
+      "name": "RewardsFundedEvent"
+    },
+    {
+      "discriminator": [
+        125,
+        61,
+        68,
+        191,
+        204,
+        9,
+        78,
+        122
+      ],
+      "name": "ShareMintCreatedEvent"
+    },
+    {
+      "discriminator": [
+        86,
+        174,
+        60,
+        3,
+        222,
+        238,
+        119,
+        253
+      ],
+      "name": "SharesUnwrappedEvent"
+    },
+    {
+      "discriminator": [
+        25,
+        88,
+        245,
+        205,
+        15,
+        103,
+        82,
+        14
+      ],
+      "name": "SharesWrappedEvent"
+    },
+    {
+      "discriminator": [
+        15,
+        60,
+        99,
+        205,
+        190,
+        222,
+        240,
+        140
+      ],
+      "name": "TokenPurchaseEvent"
+    },
+    {
+      "discriminator": [
+        207,
+        83,
+        107,
+        1,
+        165,
+        45,
+        90,
+        183
+      ],
+      "name": "TokenRedeemedEvent"
+    },
+    {
+      "discriminator": [
+        149,
+        179,
+        200,
+        64,
+        35,
+        145,
+        56,
+        203
+      ],
+      "name": "TokenSaleEvent"
+    },
+    {
+      "discriminator": [
+        108,
+        35,
+        14,
+        144,
+        77,
+        135,
+        245,
+        29
+      ],
+      "name": "TreasuryConfiguredEvent"
+    },
+    {
+      "discriminator": [
+        87,
+        47,
+        75,
+        26,
+        204,
+        149,
+        94,
+        109
+      ],
+      "name": "VaultLiquidatedEvent"
+    }
+  ],
+  "errors": [
+    {
+      "code": 6000,
+      "name": "InsufficientFunds",
+      "msg": "Insufficient funds for this operation"
+    },
+    {
+      "code": 6001,
+      "name": "InvalidAmount",
+      "msg": "Invalid amount specified"
+    },
+    {
+      "code": 6002,
+      "name": "Unauthorized",
+      "msg": "You are not authorized to perform this action"
+    },
+    {
+      "code": 6003,
+      "name": "InvalidTokenPercentages",
+      "msg": "Invalid token percentages - must sum to 100"
+    },
+    {
+      "code": 6004,
+      "name": "CannotCloseWithSupply",
+      "msg": "Cannot close ETF with outstanding supply"
+    },
+    {
+      "code": 6005,
+      "name": "InvalidTokenCount",
+      "msg": "Invalid token count - must be between 1 and 10"
+    },
+    {
+      "code": 6006,
+      "name": "InvalidDevWallet",
+      "msg": "Dev fees must go to the treasury"
+    },
+    {
+      "code": 6007,
+      "name": "InvalidListerAccount",
+      "msg": "Invalid lister account - must match ETF creator"
+    },
+    {
+      "code": 6008,
+      "name": "MaxEtfsReached",
+      "msg": "Maximum 5 ETFs per wallet reached"
+    },
+    {
+      "code": 6009,
+      "name": "InvalidRegistryPage",
+      "msg": "Registry page does not contain this ETF"
+    },
+    {
+      "code": 6010,
+      "name": "InvalidName",
+      "msg": "Invalid name - must be between 1 and 32 bytes"
+    },
+    {
+      "code": 6011,
+      "name": "InvalidSymbol",
+      "msg": "Invalid symbol - must be between 1 and 10 bytes"
+    },
+    {
+      "code": 6012,
+      "name": "UriTooLong",
+      "msg": "Metadata URI must be at most 200 bytes"
+    },
+    {
+      "code": 6013,
+      "name": "InvalidVaultAccount",
+      "msg": "Invalid vault account - must be an ETF-owned basket token account"
+    },
+    {
+      "code": 6014,
+      "name": "InsufficientShares",
+      "msg": "Insufficient shares in position"
+    },
+    {
+      "code": 6015,
+      "name": "EtfLiquidating",
+      "msg": "ETF is in liquidation - buys and sells are disabled"
+    },
+    {
+      "code": 6016,
+      "name": "EtfNotLiquidating",
+      "msg": "ETF is not in liquidation"
+    },
+    {
+      "code": 6017,
+      "name": "ListerStillActive",
+      "msg": "Lister is still active - governance cannot force liquidation yet"
+    },
+    {
+      "code": 6018,
+      "name": "InvalidPendingPurchase",
+      "msg": "Pending purchase account must be passed if and only if the ETF has a keeper"
+    },
+    {
+      "code": 6019,
+      "name": "InvalidSettlement",
+      "msg": "Settlement must deliver every basket token the purchase paid for"
+    },
+    {
+      "code": 6020,
+      "name": "SettlementModeLocked",
+      "msg": "Settlement mode can only change while the ETF has no shares or pending purchases"
+    },
+    {
+      "code": 6021,
+      "name": "PendingPurchasesOutstanding",
+      "msg": "ETF has unsettled purchases"
+    },
+    {
+      "code": 6022,
+      "name": "InvalidRefundTimeout",
+      "msg": "Refund timeout out of range"
+    },
+    {
+      "code": 6023,
+      "name": "RefundNotYetAvailable",
+      "msg": "Purchase can still be settled by the keeper"
+    },
+    {
+      "code": 6024,
+      "name": "NotAllowlisted",
+      "msg": "Investor does not meet this ETF's gate"
+    },
+    {
+      "code": 6025,
+      "name": "BuyBelowMinimum",
+      "msg": "Buy is below the minimum amount"
+    },
+    {
+      "code": 6026,
+      "name": "BuyAboveMaximum",
+      "msg": "Buy is above the maximum amount"
+    },
+    {
+      "code": 6027,
+      "name": "MaxSupplyExceeded",
+      "msg": "Buy would take the ETF over its maximum supply"
+    },
+    {
+      "code": 6028,
+      "name": "InvalidBuyLimits",
+      "msg": "Invalid buy limits"
+    },
+    {
+      "code": 6029,
+      "name": "HoldPeriodActive",
+      "msg": "Shares are still in their hold period"
+    },
+    {
+      "code": 6030,
+      "name": "InvalidHoldPeriod",
+      "msg": "Hold period or early-exit fee out of range"
+    },
+    {
+      "code": 6031,
+      "name": "InvalidDcaInterval",
+      "msg": "DCA interval is too short"
+    },
+    {
+      "code": 6032,
+      "name": "DcaNotDue",
+      "msg": "DCA plan is not due yet"
+    },
+    {
+      "code": 6033,
+      "name": "DcaPlanUnderfunded",
+      "msg": "DCA plan cannot cover the next buy and crank tip"
+    },
+    {
+      "code": 6034,
+      "name": "RequiresInstantBuys",
+      "msg": "DCA plans need an ETF without a keeper"
+    },
+    {
+      "code": 6035,
+      "name": "OrderNotTriggered",
+      "msg": "NAV has not reached the order's target"
+    },
+    {
+      "code": 6036,
+      "name": "OrderPositionRequired",
+      "msg": "Sell orders return their shares to the owner's position, which must be passed"
+    },
+    {
+      "code": 6037,
+      "name": "InvalidExitTrigger",
+      "msg": "Exit trigger needs a threshold, and its stop-loss below its take-profit"
+    },
+    {
+      "code": 6038,
+      "name": "ExitNotTriggered",
+      "msg": "NAV has not reached a threshold of the exit trigger"
+    },
+    {
+      "code": 6039,
+      "name": "NavSnapshotTooSoon",
+      "msg": "NAV was snapshotted less than an interval ago"
+    },
+    {
+      "code": 6040,
+      "name": "NoNavHistory",
+      "msg": "NAV history has no snapshots yet"
+    },
+    {
+      "code": 6041,
+      "name": "EpochNotEnded",
+      "msg": "The leaderboard epoch has not ended"
+    },
+    {
+      "code": 6042,
+      "name": "MissingEpochSnapshot",
+      "msg": "NAV history does not cover the window before an epoch boundary"
+    },
+    {
+      "code": 6043,
+      "name": "EtfAlreadyRanked",
+      "msg": "ETF is already on this leaderboard"
+    },
+    {
+      "code": 6044,
+      "name": "ReturnNotRanked",
+      "msg": "Return does not make the leaderboard"
+    },
+    {
+      "code": 6045,
+      "name": "BelowLeaderboardMinimum",
+      "msg": "ETF net assets were below the leaderboard minimum"
+    },
+    {
+      "code": 6046,
+      "name": "InvalidRewardsShare",
+      "msg": "Rewards share cannot exceed 100%"
+    },
+    {
+      "code": 6047,
+      "name": "SubmissionsClosed",
+      "msg": "Submissions for this epoch have closed"
+    },
+    {
+      "code": 6048,
+      "name": "SubmissionsOpen",
+      "msg": "Submissions for this epoch are still open"
+    },
+    {
+      "code": 6049,
+      "name": "RewardAlreadyClaimed",
+      "msg": "Reward already claimed"
+    },
+    {
+      "code": 6050,
+      "name": "InvalidForkChanges",
+      "msg": "Fork removes a token not in the parent or adds one already in it"
+    },
+    {
+      "code": 6051,
+      "name": "InvalidParentFee",
+      "msg": "Parent fee share exceeds the maximum"
+    },
+    {
+      "code": 6052,
+      "name": "ParentFeesUnclaimed",
+      "msg": "Parent fees must be claimed first"
+    },
+    {
+      "code": 6053,
+      "name": "SharesStillWrapped",
+      "msg": "Wrapped shares are still outstanding"
+    },
+    {
+      "code": 6054,
+      "name": "TreasuryNotConfigured",
+      "msg": "The treasury has not been configured"
+    },
+    {
+      "code": 6055,
+      "name": "InvalidPriceFeed",
+      "msg": "Price feed account does not match the basket token"
+    },
+    {
+      "code": 6056,
+      "name": "MissingPriceFeed",
+      "msg": "A price feed is needed for every basket token the ETF holds"
+    },
+    {
+      "code": 6057,
+      "name": "StalePrice",
+      "msg": "Price feed is too old"
+    },
+    {
+      "code": 6058,
+      "name": "SettlementBelowPrice",
+      "msg": "Settlement delivered less than the allocation is worth at the feed price"
+    },
+    {
+      "code": 6059,
+      "name": "RedeemInKind",
+      "msg": "This ETF holds basket tokens; redeem shares in kind"
+    },
+    {
+      "code": 6060,
+      "name": "AlreadyMigrated",
+      "msg": "ETF account already has the current layout"
+    },
+    {
+      "code": 6061,
+      "name": "LegacySharesExceeded",
+      "msg": "More shares than the ETF has left to restore"
+    },
+    {
+      "code": 6062,
+      "name": "LiquidationNoticePending",
+      "msg": "Vaults cannot be converted until the liquidation notice has passed"
+    },
+    {
+      "code": 6063,
+      "name": "LiquidationBelowPrice",
+      "msg": "Liquidation proceeds are below the tokens' value at the feed price"
+    },
+    {
+      "code": 6064,
+      "name": "InsufficientDeposit",
+      "msg": "Order or trigger deposit does not cover the cost of an in-kind fill"
+    }
+  ],
+  "types": [
+    {
+      "name": "AllowlistPass",
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "etf",
+            "type": "pubkey"
+          },
+          {
+            "name": "investor",
+            "type": "pubkey"
+          },
+          {
+            "name": "root",
+            "type": {
+              "array": [
+                "u8",
+                32
+              ]
+            }
+          },
+          {
+            "name": "bump",
+            "type": "u8"
+          }
+        ]
+      }
+    },
+    {
+      "name": "BuyLimits",
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "min_buy_lamports",
+            "type": "u64"
+          },
+          {
+            "name": "max_buy_lamports",
+            "type": "u64"
+          },
+          {
+            "name": "max_total_supply",
+            "type": "u64"
+          }
+        ]
+      }
+    },
+    {
+      "name": "BuyLimitsUpdatedEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "etf_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "limits",
+            "type": {
+              "defined": {
+                "name": "BuyLimits"
+              }
+            }
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "BuyQuote",
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "sol_amount",
+            "type": "u64"
+          },
+          {
+            "name": "creator_fee",
+            "type": "u64"
+          },
+          {
+            "name": "dev_fee",
+            "type": "u64"
+          },
+          {
+            "name": "sol_after_fees",
+            "type": "u64"
+          },
+          {
+            "name": "shares_out",
+            "type": "u64"
+          },
+          {
+            "name": "allocations",
+            "type": {
+              "vec": "u64"
+            }
+          },
+          {
+            "name": "pending",
+            "type": "bool"
+          },
+          {
+            "name": "nav_per_share",
+            "type": "u64"
+          }
+        ]
+      }
+    },
+    {
+      "name": "CreatorReward",
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "etf",
+            "type": "pubkey"
+          },
+          {
+            "name": "lister",
+            "type": "pubkey"
+          },
+          {
+            "name": "amount",
+            "type": "u64"
+          },
+          {
+            "name": "claimed",
+            "type": "bool"
+          }
+        ]
+      }
+    },
+    {
+      "name": "CreatorRewardClaimedEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "epoch_id",
+            "type": "u64"
+          },
+          {
+            "name": "etf_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "lister",
+            "type": "pubkey"
+          },
+          {
+            "name": "amount",
+            "type": "u64"
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "DcaExecutedEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "etf_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "investor",
+            "type": "pubkey"
+          },
+          {
+            "name": "caller",
+            "type": "pubkey"
+          },
+          {
+            "name": "sol_amount",
+            "type": "u64"
+          },
+          {
+            "name": "shares_minted",
+            "type": "u64"
+          },
+          {
+            "name": "tip",
+            "type": "u64"
+          },
+          {
+            "name": "next_execution_ts",
+            "type": "i64"
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "DcaPlan",
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "etf",
+            "type": "pubkey"
+          },
+          {
+            "name": "investor",
+            "type": "pubkey"
+          },
+          {
+            "name": "amount_per_period",
+            "type": "u64"
+          },
+          {
+            "name": "interval_seconds",
+            "type": "i64"
+          },
+          {
+            "name": "next_execution_ts",
+            "type": "i64"
+          },
+          {
+            "name": "token_percentages",
+            "type": "bytes"
+          },
+          {
+            "name": "executions",
+            "type": "u64"
+          },
+          {
+            "name": "bump",
+            "type": "u8"
+          }
+        ]
+      }
+    },
+    {
+      "name": "DcaPlanCancelledEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "etf_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "investor",
+            "type": "pubkey"
+          },
+          {
+            "name": "refunded",
+            "type": "u64"
+          },
+          {
+            "name": "executions",
+            "type": "u64"
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "DcaPlanCreatedEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "etf_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "investor",
+            "type": "pubkey"
+          },
+          {
+            "name": "amount_per_period",
+            "type": "u64"
+          },
+          {
+            "name": "interval_seconds",
+            "type": "i64"
+          },
+          {
+            "name": "deposit",
+            "type": "u64"
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "DcaPlanFundedEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "etf_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "investor",
+            "type": "pubkey"
+          },
+          {
+            "name": "amount",
+            "type": "u64"
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "DevFeesWithdrawnEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "amount",
+            "type": "u64"
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "ETF",
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "lister",
+            "type": "pubkey"
+          },
+          {
+            "name": "etf_index",
+            "type": "u8"
+          },
+          {
+            "name": "token_addresses",
+            "type": {
+              "vec": "pubkey"
+            }
+          },
+          {
+            "name": "total_supply",
+            "type": "u64"
+          },
+          {
+            "name": "accumulated_fees",
+            "type": "u64"
+          },
+          {
+            "name": "bump",
+            "type": "u8"
+          },
+          {
+            "name": "registry_index",
+            "type": "u64"
+          },
+          {
+            "name": "name",
+            "type": "string"
+          },
+          {
+            "name": "symbol",
+            "type": "string"
+          },
+          {
+            "name": "uri",
+            "type": "string"
+          },
+          {
+            "name": "category",
+            "type": {
+              "defined": {
+                "name": "EtfCategory"
+              }
+            }
+          },
+          {
+            "name": "status",
+            "type": {
+              "defined": {
+                "name": "EtfStatus"
+              }
+            }
+          },
+          {
+            "name": "lister_last_active_ts",
+            "type": "i64"
+          },
+          {
+            "name": "liquidation_started_ts",
+            "type": "i64"
+          },
+          {
+            "name": "trade_sequence",
+            "type": "u64"
+          },
+          {
+            "name": "keeper",
+            "type": {
+              "option": "pubkey"
+            }
+          },
+          {
+            "name": "pending_sol",
+            "type": "u64"
+          },
+          {
+            "name": "purchase_count",
+            "type": "u64"
+          },
+          {
+            "name": "refund_timeout",
+            "type": "i64"
+          },
+          {
+            "name": "gate",
+            "type": {
+              "defined": {
+                "name": "EtfGate"
+              }
+            }
+          },
+          {
+            "name": "limits",
+            "type": {
+              "defined": {
+                "name": "BuyLimits"
+              }
+            }
+          },
+          {
+            "name": "min_hold_seconds",
+            "type": "i64"
+          },
+          {
+            "name": "early_exit_fee_bps",
+            "type": "u16"
+          },
+          {
+            "name": "parent_etf",
+            "type": {
+              "option": "pubkey"
+            }
+          },
+          {
+            "name": "parent_lister",
+            "type": "pubkey"
+          },
+          {
+            "name": "parent_fee_bps",
+            "type": "u16"
+          },
+          {
+            "name": "parent_fees_owed",
+            "type": "u64"
+          },
+          {
+            "name": "sol_assets",
+            "type": "u64"
+          },
+          {
+            "name": "holdings",
+            "type": {
+              "vec": "u64"
+            }
+          },
+          {
+            "name": "legacy_shares",
+            "type": "u64"
+          }
+        ]
+      }
+    },
+    {
+      "name": "ETFBoughtEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "etf_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "investor",
+            "type": "pubkey"
+          },
+          {
+            "name": "sequence",
+            "type": "u64"
+          },
+          {
+            "name": "sol_amount",
+            "type": "u64"
+          },
+          {
+            "name": "shares_minted",
+            "type": "u64"
+          },
+          {
+            "name": "creator_fee",
+            "type": "u64"
+          },
+          {
+            "name": "dev_fee",
+            "type": "u64"
+          },
+          {
+            "name": "total_supply",
+            "type": "u64"
+          },
+          {
+            "name": "nav_per_share",
+            "type": "u64"
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "ETFClosedEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "etf_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "lister",
+            "type": "pubkey"
+          },
+          {
+            "name": "rent_recovered",
+            "type": "u64"
+          },
+          {
+            "name": "dust_recovered",
+            "type": "u64"
+          },
+          {
+            "name": "vault_rent_recovered",
+            "type": "u64"
+          },
+          {
+            "name": "tokens_recovered",
+            "type": {
+              "vec": {
+                "defined": {
+                  "name": "TokenRecovered"
+                }
+              }
+            }
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "ETFCreatedEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "etf_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "lister",
+            "type": "pubkey"
+          },
+          {
+            "name": "token_count",
+            "type": "u8"
+          },
+          {
+            "name": "name",
+            "type": "string"
+          },
+          {
+            "name": "symbol",
+            "type": "string"
+          },
+          {
+            "name": "uri",
+            "type": "string"
+          },
+          {
+            "name": "category",
+            "type": {
+              "defined": {
+                "name": "EtfCategory"
+              }
+            }
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "ETFForkedEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "etf_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "parent_etf",
+            "type": "pubkey"
+          },
+          {
+            "name": "lister",
+            "type": "pubkey"
+          },
+          {
+            "name": "parent_lister",
+            "type": "pubkey"
+          },
+          {
+            "name": "removed_tokens",
+            "type": {
+              "vec": "pubkey"
+            }
+          },
+          {
+            "name": "added_tokens",
+            "type": {
+              "vec": "pubkey"
+            }
+          },
+          {
+            "name": "parent_fee_bps",
+            "type": "u16"
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "ETFMigratedEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "etf_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "lister",
+            "type": "pubkey"
+          },
+          {
+            "name": "token_count",
+            "type": "u8"
+          },
+          {
+            "name": "registry_index",
+            "type": "u64"
+          },
+          {
+            "name": "sol_assets",
+            "type": "u64"
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "ETFSoldEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "etf_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "investor",
+            "type": "pubkey"
+          },
+          {
+            "name": "sequence",
+            "type": "u64"
+          },
+          {
+            "name": "shares_burned",
+            "type": "u64"
+          },
+          {
+            "name": "sol_amount",
+            "type": "u64"
+          },
+          {
+            "name": "creator_fee",
+            "type": "u64"
+          },
+          {
+            "name": "dev_fee",
+            "type": "u64"
+          },
+          {
+            "name": "early_exit_fee",
+            "type": "u64"
+          },
+          {
+            "name": "total_supply",
+            "type": "u64"
+          },
+          {
+            "name": "nav_per_share",
+            "type": "u64"
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "EpochFinalizedEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "epoch_id",
+            "type": "u64"
+          },
+          {
+            "name": "total_rewards",
+            "type": "u64"
+          },
+          {
+            "name": "ranked",
+            "type": "u8"
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "EpochReturnSubmittedEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "epoch_id",
+            "type": "u64"
+          },
+          {
+            "name": "etf_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "lister",
+            "type": "pubkey"
+          },
+          {
+            "name": "return_bps",
+            "type": "i64"
+          },
+          {
+            "name": "rank",
+            "type": "u8"
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "EpochRewards",
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "epoch_id",
+            "type": "u64"
+          },
+          {
+            "name": "rewards",
+            "type": {
+              "vec": {
+                "defined": {
+                  "name": "CreatorReward"
+                }
+              }
+            }
+          },
+          {
+            "name": "bump",
+            "type": "u8"
+          }
+        ]
+      }
+    },
+    {
+      "name": "EtfCategory",
+      "type": {
+        "kind": "enum",
+        "variants": [
+          {
+            "name": "Meme"
+          },
+          {
+            "name": "AI"
+          },
+          {
+            "name": "Gaming"
+          },
+          {
+            "name": "DeFi"
+          },
+          {
+            "name": "Community"
+          },
+          {
+            "name": "Other"
+          }
+        ]
+      }
+    },
+    {
+      "name": "EtfGate",
+      "type": {
+        "kind": "enum",
+        "variants": [
+          {
+            "name": "Open"
+          },
+          {
+            "name": "MerkleAllowlist",
+            "fields": [
+              {
+                "name": "root",
+                "type": {
+                  "array": [
+                    "u8",
+                    32
+                  ]
+                }
+              }
+            ]
+          },
+          {
+            "name": "MinimumBalance",
+            "fields": [
+              {
+                "name": "mint",
+                "type": "pubkey"
+              },
+              {
+                "name": "amount",
+                "type": "u64"
+              }
+            ]
+          }
+        ]
+      }
+    },
+    {
+      "name": "EtfStatus",
+      "type": {
+        "kind": "enum",
+        "variants": [
+          {
+            "name": "Active"
+          },
+          {
+            "name": "Liquidating"
+          }
+        ]
+      }
+    },
+    {
+      "name": "ExitKind",
+      "type": {
+        "kind": "enum",
+        "variants": [
+          {
+            "name": "StopLoss"
+          },
+          {
+            "name": "TakeProfit"
+          }
+        ]
+      }
+    },
+    {
+      "name": "ExitTrigger",
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "etf",
+            "type": "pubkey"
+          },
+          {
+            "name": "investor",
+            "type": "pubkey"
+          },
+          {
+            "name": "stop_loss_nav",
+            "type": "u64"
+          },
+          {
+            "name": "take_profit_nav",
+            "type": "u64"
+          },
+          {
+            "name": "shares",
+            "type": "u64"
+          },
+          {
+            "name": "bump",
+            "type": "u8"
+          }
+        ]
+      }
+    },
+    {
+      "name": "ExitTriggerCancelledEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "etf_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "investor",
+            "type": "pubkey"
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "ExitTriggerSetEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "etf_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "investor",
+            "type": "pubkey"
+          },
+          {
+            "name": "stop_loss_nav",
+            "type": "u64"
+          },
+          {
+            "name": "take_profit_nav",
+            "type": "u64"
+          },
+          {
+            "name": "shares",
+            "type": "u64"
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "ExitTriggeredEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "etf_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "investor",
+            "type": "pubkey"
+          },
+          {
+            "name": "caller",
+            "type": "pubkey"
+          },
+          {
+            "name": "kind",
+            "type": {
+              "defined": {
+                "name": "ExitKind"
+              }
+            }
+          },
+          {
+            "name": "nav_per_share",
+            "type": "u64"
+          },
+          {
+            "name": "shares_sold",
+            "type": "u64"
+          },
+          {
+            "name": "sol_amount",
+            "type": "u64"
+          },
+          {
+            "name": "tip",
+            "type": "u64"
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "FeeTransferEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "etf_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "recipient",
+            "type": "pubkey"
+          },
+          {
+            "name": "amount",
+            "type": "u64"
+          },
+          {
+            "name": "fee_type",
+            "type": {
+              "defined": {
+                "name": "FeeType"
+              }
+            }
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "FeeType",
+      "type": {
+        "kind": "enum",
+        "variants": [
+          {
+            "name": "Creator"
+          },
+          {
+            "name": "Dev"
+          },
+          {
+            "name": "Parent"
+          }
+        ]
+      }
+    },
+    {
+      "name": "ForkChanges",
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "remove_tokens",
+            "type": {
+              "vec": "pubkey"
+            }
+          },
+          {
+            "name": "add_tokens",
+            "type": {
+              "vec": "pubkey"
+            }
+          },
+          {
+            "name": "parent_fee_bps",
+            "type": "u16"
+          }
+        ]
+      }
+    },
+    {
+      "name": "GateUpdatedEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "etf_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "gate",
+            "type": {
+              "defined": {
+                "name": "EtfGate"
+              }
+            }
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "HoldPeriodUpdatedEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "etf_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "min_hold_seconds",
+            "type": "i64"
+          },
+          {
+            "name": "early_exit_fee_bps",
+            "type": "u16"
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "KeeperUpdatedEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "etf_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "keeper",
+            "type": {
+              "option": "pubkey"
+            }
+          },
+          {
+            "name": "refund_timeout",
+            "type": "i64"
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "LeaderboardEntry",
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "etf",
+            "type": "pubkey"
+          },
+          {
+            "name": "lister",
+            "type": "pubkey"
+          },
+          {
+            "name": "return_bps",
+            "type": "i64"
+          },
+          {
+            "name": "start_nav",
+            "type": "u64"
+          },
+          {
+            "name": "end_nav",
+            "type": "u64"
+          }
+        ]
+      }
+    },
+    {
+      "name": "LeaderboardEpoch",
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "epoch_id",
+            "type": "u64"
+          },
+          {
+            "name": "start_ts",
+            "type": "i64"
+          },
+          {
+            "name": "end_ts",
+            "type": "i64"
+          },
+          {
+            "name": "entries",
+            "type": {
+              "vec": {
+                "defined": {
+                  "name": "LeaderboardEntry"
+                }
+              }
+            }
+          },
+          {
+            "name": "bump",
+            "type": "u8"
+          }
+        ]
+      }
+    },
+    {
+      "name": "LegacyPositionRestoredEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "etf_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "investor",
+            "type": "pubkey"
+          },
+          {
+            "name": "shares",
+            "type": "u64"
+          },
+          {
+            "name": "legacy_shares",
+            "type": "u64"
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "LimitOrder",
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "etf",
+            "type": "pubkey"
+          },
+          {
+            "name": "owner",
+            "type": "pubkey"
+          },
+          {
+            "name": "order_id",
+            "type": "u64"
+          },
+          {
+            "name": "side",
+            "type": {
+              "defined": {
+                "name": "OrderSide"
+              }
+            }
+          },
+          {
+            "name": "amount",
+            "type": "u64"
+          },
+          {
+            "name": "target_nav",
+            "type": "u64"
+          },
+          {
+            "name": "token_percentages",
+            "type": "bytes"
+          },
+          {
+            "name": "created_ts",
+            "type": "i64"
+          },
+          {
+            "name": "bump",
+            "type": "u8"
+          }
+        ]
+      }
+    },
+    {
+      "name": "LimitOrderCancelledEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "etf_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "owner",
+            "type": "pubkey"
+          },
+          {
+            "name": "order_id",
+            "type": "u64"
+          },
+          {
+            "name": "side",
+            "type": {
+              "defined": {
+                "name": "OrderSide"
+              }
+            }
+          },
+          {
+            "name": "amount",
+            "type": "u64"
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "LimitOrderFilledEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "etf_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "owner",
+            "type": "pubkey"
+          },
+          {
+            "name": "order_id",
+            "type": "u64"
+          },
+          {
+            "name": "side",
+            "type": {
+              "defined": {
+                "name": "OrderSide"
+              }
+            }
+          },
+          {
+            "name": "amount",
+            "type": "u64"
+          },
+          {
+            "name": "nav_per_share",
+            "type": "u64"
+          },
+          {
+            "name": "filler",
+            "type": "pubkey"
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "LimitOrderPlacedEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "etf_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "owner",
+            "type": "pubkey"
+          },
+          {
+            "name": "order_id",
+            "type": "u64"
+          },
+          {
+            "name": "side",
+            "type": {
+              "defined": {
+                "name": "OrderSide"
+              }
+            }
+          },
+          {
+            "name": "amount",
+            "type": "u64"
+          },
+          {
+            "name": "target_nav",
+            "type": "u64"
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "LiquidationClaimEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "etf_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "investor",
+            "type": "pubkey"
+          },
+          {
+            "name": "shares_burned",
+            "type": "u64"
+          },
+          {
+            "name": "sol_amount",
+            "type": "u64"
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "LiquidationStartedEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "etf_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "initiator",
+            "type": "pubkey"
+          },
+          {
+            "name": "total_supply",
+            "type": "u64"
+          },
+          {
+            "name": "notice_ends_ts",
+            "type": "i64"
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "MetadataUpdatedEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "etf_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "name",
+            "type": "string"
+          },
+          {
+            "name": "symbol",
+            "type": "string"
+          },
+          {
+            "name": "uri",
+            "type": "string"
+          },
+          {
+            "name": "category",
+            "type": {
+              "defined": {
+                "name": "EtfCategory"
+              }
+            }
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "NavHistory",
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "etf",
+            "type": "pubkey"
+          },
+          {
+            "name": "head",
+            "type": "u16"
+          },
+          {
+            "name": "snapshots",
+            "type": {
+              "vec": {
+                "defined": {
+                  "name": "NavSnapshot"
+                }
+              }
+            }
+          },
+          {
+            "name": "bump",
+            "type": "u8"
+          }
+        ]
+      }
+    },
+    {
+      "name": "NavQuote",
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "total_supply",
+            "type": "u64"
+          },
+          {
+            "name": "net_assets",
+            "type": "u64"
+          },
+          {
+            "name": "pending_sol",
+            "type": "u64"
+          },
+          {
+            "name": "nav_per_share",
+            "type": "u64"
+          },
+          {
+            "name": "status",
+            "type": {
+              "defined": {
+                "name": "EtfStatus"
+              }
+            }
+          }
+        ]
+      }
+    },
+    {
+      "name": "NavSnapshot",
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "timestamp",
+            "type": "i64"
+          },
+          {
+            "name": "nav_per_share",
+            "type": "u64"
+          },
+          {
+            "name": "total_value",
+            "type": "u64"
+          }
+        ]
+      }
+    },
+    {
+      "name": "NavSnapshotEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "etf_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "nav_per_share",
+            "type": "u64"
+          },
+          {
+            "name": "total_value",
+            "type": "u64"
+          },
+          {
+            "name": "total_supply",
+            "type": "u64"
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "OrderSide",
+      "type": {
+        "kind": "enum",
+        "variants": [
+          {
+            "name": "Buy"
+          },
+          {
+            "name": "Sell"
+          }
+        ]
+      }
+    },
+    {
+      "name": "ParentFeesClaimedEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "etf_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "parent_lister",
+            "type": "pubkey"
+          },
+          {
+            "name": "amount",
+            "type": "u64"
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "PendingPurchase",
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "etf",
+            "type": "pubkey"
+          },
+          {
+            "name": "investor",
+            "type": "pubkey"
+          },
+          {
+            "name": "purchase_id",
+            "type": "u64"
+          },
+          {
+            "name": "sol_amount",
+            "type": "u64"
+          },
+          {
+            "name": "sol_to_swap",
+            "type": "u64"
+          },
+          {
+            "name": "allocations",
+            "type": {
+              "vec": "u64"
+            }
+          },
+          {
+            "name": "created_ts",
+            "type": "i64"
+          },
+          {
+            "name": "refundable_ts",
+            "type": "i64"
+          },
+          {
+            "name": "bump",
+            "type": "u8"
+          }
+        ]
+      }
+    },
+    {
+      "name": "Position",
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "etf",
+            "type": "pubkey"
+          },
+          {
+            "name": "investor",
+            "type": "pubkey"
+          },
+          {
+            "name": "shares",
+            "type": "u64"
+          },
+          {
+            "name": "bump",
+            "type": "u8"
+          },
+          {
+            "name": "last_buy_ts",
+            "type": "i64"
+          }
+        ]
+      }
+    },
+    {
+      "name": "PriceFeed",
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "mint",
+            "type": "pubkey"
+          },
+          {
+            "name": "price",
+            "type": "u64"
+          },
+          {
+            "name": "updated_ts",
+            "type": "i64"
+          },
+          {
+            "name": "bump",
+            "type": "u8"
+          }
+        ]
+      }
+    },
+    {
+      "name": "PriceUpdatedEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "token_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "price",
+            "type": "u64"
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "PurchasePendingEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "etf_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "investor",
+            "type": "pubkey"
+          },
+          {
+            "name": "purchase_id",
+            "type": "u64"
+          },
+          {
+            "name": "sol_amount",
+            "type": "u64"
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "PurchaseRefundedEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "etf_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "investor",
+            "type": "pubkey"
+          },
+          {
+            "name": "purchase_id",
+            "type": "u64"
+          },
+          {
+            "name": "sol_amount",
+            "type": "u64"
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "PurchaseSettledEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "etf_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "investor",
+            "type": "pubkey"
+          },
+          {
+            "name": "keeper",
+            "type": "pubkey"
+          },
+          {
+            "name": "purchase_id",
+            "type": "u64"
+          },
+          {
+            "name": "sol_amount",
+            "type": "u64"
+          },
+          {
+            "name": "token_amounts",
+            "type": {
+              "vec": "u64"
+            }
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "Registry",
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "etf_count",
+            "type": "u64"
+          },
+          {
+            "name": "active_count",
+            "type": "u64"
+          },
+          {
+            "name": "bump",
+            "type": "u8"
+          }
+        ]
+      }
+    },
+    {
+      "name": "RegistryEntry",
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "etf",
+            "type": "pubkey"
+          },
+          {
+            "name": "closed",
+            "type": "bool"
+          }
+        ]
+      }
+    },
+    {
+      "name": "RegistryPage",
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "page_index",
+            "type": "u32"
+          },
+          {
+            "name": "entries",
+            "type": {
+              "vec": {
+                "defined": {
+                  "name": "RegistryEntry"
+                }
+              }
+            }
+          },
+          {
+            "name": "bump",
+            "type": "u8"
+          }
+        ]
+      }
+    },
+    {
+      "name": "RewardsFundedEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "funder",
+            "type": "pubkey"
+          },
+          {
+            "name": "amount",
+            "type": "u64"
+          },
+          {
+            "name": "fees_to_rewards",
+            "type": "u64"
+          },
+          {
+            "name": "fees_to_dev",
+            "type": "u64"
+          },
+          {
+            "name": "rewards_pool",
+            "type": "u64"
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "SellQuote",
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "shares_in",
+            "type": "u64"
+          },
+          {
+            "name": "sol_value",
+            "type": "u64"
+          },
+          {
+            "name": "creator_fee",
+            "type": "u64"
+          },
+          {
+            "name": "dev_fee",
+            "type": "u64"
+          },
+          {
+            "name": "sol_out",
+            "type": "u64"
+          },
+          {
+            "name": "liquidity",
+            "type": "u64"
+          },
+          {
+            "name": "nav_per_share",
+            "type": "u64"
+          }
+        ]
+      }
+    },
+    {
+      "name": "ShareMintCreatedEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "etf_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "share_mint",
+            "type": "pubkey"
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "SharesUnwrappedEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "etf_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "owner",
+            "type": "pubkey"
+          },
+          {
+            "name": "recipient",
+            "type": "pubkey"
+          },
+          {
+            "name": "amount",
+            "type": "u64"
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "SharesWrappedEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "etf_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "owner",
+            "type": "pubkey"
+          },
+          {
+            "name": "destination",
+            "type": "pubkey"
+          },
+          {
+            "name": "amount",
+            "type": "u64"
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "TokenPurchaseEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "etf_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "investor",
+            "type": "pubkey"
+          },
+          {
+            "name": "token_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "sol_amount",
+            "type": "u64"
+          },
+          {
+            "name": "percentage",
+            "type": "u8"
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "TokenRecovered",
+      "type": {
+        "fields": [
+          {
+            "name": "mint",
+            "type": "pubkey"
+          },
+          {
+            "name": "amount",
+            "type": "u64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "TokenRedeemedEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "etf_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "investor",
+            "type": "pubkey"
+          },
+          {
+            "name": "token_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "amount",
+            "type": "u64"
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "TokenSaleEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "etf_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "investor",
+            "type": "pubkey"
+          },
+          {
+            "name": "token_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "shares_burned",
+            "type": "u64"
+          },
+          {
+            "name": "supply_before",
+            "type": "u64"
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "Treasury",
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "rewards_share_bps",
+            "type": "u16"
+          },
+          {
+            "name": "dev_balance",
+            "type": "u64"
+          },
+          {
+            "name": "rewards_pool",
+            "type": "u64"
+          },
+          {
+            "name": "rewards_owed",
+            "type": "u64"
+          },
+          {
+            "name": "bump",
+            "type": "u8"
+          }
+        ]
+      }
+    },
+    {
+      "name": "TreasuryConfiguredEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "rewards_share_bps",
+            "type": "u16"
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    },
+    {
+      "name": "TwapQuote",
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "nav_per_share",
+            "type": "u64"
+          },
+          {
+            "name": "window_seconds",
+            "type": "i64"
+          },
+          {
+            "name": "covered_seconds",
+            "type": "i64"
+          },
+          {
+            "name": "snapshots",
+            "type": "u16"
+          }
+        ]
+      }
+    },
+    {
+      "name": "VaultLiquidatedEvent",
+      "type": {
+        "fields": [
+          {
+            "name": "etf_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "token_address",
+            "type": "pubkey"
+          },
+          {
+            "name": "token_amount",
+            "type": "u64"
+          },
+          {
+            "name": "sol_amount",
+            "type": "u64"
+          },
+          {
+            "name": "timestamp",
+            "type": "i64"
+          }
+        ],
+        "kind": "struct"
+      }
+    }
+  ]
+} as const;
+
+export type MtfEtf = typeof IDL;
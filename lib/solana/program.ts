@@ -12,11 +12,37 @@ import {
   Keypair,
   sendAndConfirmTransaction,
 } from '@solana/web3.js';
+import { getAssociatedTokenAddressSync, TOKEN_PROGRAM_ID } from '@solana/spl-token';
+import { BorshAccountsCoder, Idl } from '@coral-xyz/anchor';
 import { createHash } from 'crypto';
+import { IDL } from '../anchor/idl';
 import { swapForEtfPurchase, type SwapResult } from './jupiterSwap';
 
 // Program ID - deployed on devnet
-export const PROGRAM_ID = new PublicKey('CwwrCDfrsuA2C4YPiobU82ZA9wSWrecyLbbvP35QXmyo');
+export const PROGRAM_ID = new PublicKey(IDL.address);
+
+// Protocol treasury that receives the 0.5% dev fee (must match contract constant)
+const TREASURY = new PublicKey('65zUoyf4GfK6ai8dzYMoF4QhXgiFR753jDdhaQMG7wTs');
+
+// Optional accounts that are not passed are filled with the program ID
+const NONE = PROGRAM_ID;
+
+// Metadata limits (must match contract constants)
+const MAX_NAME_LEN = 32;
+const MAX_SYMBOL_LEN = 10;
+
+// Must match the EtfCategory enum order in the contract
+export const ETF_CATEGORIES = ['meme', 'ai', 'gaming', 'defi', 'community', 'other'] as const;
+export type EtfCategory = typeof ETF_CATEGORIES[number];
+
+export interface EtfMetadata {
+  name: string;
+  symbol: string;
+  uri: string;
+  category: EtfCategory;
+}
+
+const accountsCoder = new BorshAccountsCoder(IDL as unknown as Idl);
 
 // Anchor instruction discriminators
 function getDiscriminator(name: string): Buffer {
@@ -28,8 +54,8 @@ const DISC = {
   initializeEtf: getDiscriminator('initialize_etf'),
   buyEtf: getDiscriminator('buy_etf'),
   sellEtf: getDiscriminator('sell_etf'),
-  claimFees: getDiscriminator('claim_fees'),
   closeEtf: getDiscriminator('close_etf'),
+  migrateEtf: getDiscriminator('migrate_etf'),
 };
 
 export function getConnection(network: 'devnet' | 'mainnet' | 'mainnet-beta' = 'devnet'): Connection {
@@ -63,19 +89,88 @@ export function getAllEtfPdas(listerPubkey: PublicKey): Array<{ pda: PublicKey;
   return pdas;
 }
 
+// Number of ETF entries per registry page (must match REGISTRY_PAGE_SIZE)
+const REGISTRY_PAGE_SIZE = 64;
+
+export function getRegistryPda(): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync([Buffer.from('registry')], PROGRAM_ID);
+}
+
 /**
- * Initialize ETF - accounts: [etf, lister, system_program]
- * etfIndex: 0-4 (each wallet can have up to 5 ETFs)
+ * Registry page holding the ETF registered at `registryIndex`
  */
-export async function initializeEtf(
-  connection: Connection,
-  listerKeypair: Keypair,
+export function getRegistryPagePda(registryIndex: number): [PublicKey, number] {
+  const page = Buffer.alloc(4);
+  page.writeUInt32LE(Math.floor(registryIndex / REGISTRY_PAGE_SIZE), 0);
+  return PublicKey.findProgramAddressSync([Buffer.from('registry_page'), page], PROGRAM_ID);
+}
+
+export function getPositionPda(etfPda: PublicKey, investor: PublicKey): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from('position'), etfPda.toBuffer(), investor.toBuffer()],
+    PROGRAM_ID
+  );
+}
+
+export function getShareMintPda(etfPda: PublicKey): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync([Buffer.from('shares'), etfPda.toBuffer()], PROGRAM_ID);
+}
+
+/**
+ * Index the next listed ETF takes in the registry (its current etf_count)
+ */
+export async function fetchNextRegistryIndex(connection: Connection): Promise<number> {
+  const [registryPda] = getRegistryPda();
+  const info = await connection.getAccountInfo(registryPda);
+  if (!info) {
+    return 0;
+  }
+  return accountsCoder.decode('Registry', info.data).etf_count.toNumber();
+}
+
+/**
+ * Fetch and decode an ETF account
+ */
+export async function fetchEtf(connection: Connection, etfPda: PublicKey) {
+  const info = await connection.getAccountInfo(etfPda);
+  if (!info) {
+    throw new Error(`ETF ${etfPda.toBase58()} not found`);
+  }
+  return accountsCoder.decode('ETF', info.data);
+}
+
+/**
+ * Metadata for an ETF known only by name: the symbol is its leading letters and digits
+ */
+export function metadataFromName(name: string, category: EtfCategory = 'meme'): EtfMetadata {
+  let trimmed = name.trim();
+  while (Buffer.byteLength(trimmed) > MAX_NAME_LEN) {
+    trimmed = trimmed.slice(0, -1);
+  }
+  const symbol = trimmed.replace(/[^A-Za-z0-9]/g, '').toUpperCase().slice(0, MAX_SYMBOL_LEN) || 'ETF';
+  return { name: trimmed, symbol, uri: '', category };
+}
+
+function encodeString(value: string): Buffer {
+  const bytes = Buffer.from(value);
+  const len = Buffer.alloc(4);
+  len.writeUInt32LE(bytes.length, 0);
+  return Buffer.concat([len, bytes]);
+}
+
+/**
+ * initialize_etf instruction - accounts: [etf, registry, registry_page, lister, system_program]
+ */
+function initializeEtfInstruction(
+  listerPubkey: PublicKey,
   tokenAddresses: PublicKey[],
-  etfIndex: number = 0
-): Promise<string> {
-  const [etfPda] = getEtfPda(listerKeypair.publicKey, etfIndex);
+  etfIndex: number,
+  registryIndex: number,
+  metadata: EtfMetadata
+): TransactionInstruction {
+  const [etfPda] = getEtfPda(listerPubkey, etfIndex);
 
-  // Data: discriminator + etf_index (u8) + vec<pubkey>
+  // Data: discriminator + etf_index (u8) + vec<pubkey> + name + symbol + uri + category (u8)
   const indexBuf = Buffer.alloc(1);
   indexBuf.writeUInt8(etfIndex, 0);
 
@@ -86,26 +181,48 @@ export async function initializeEtf(
     DISC.initializeEtf,
     indexBuf,
     vecLen,
-    ...tokenAddresses.map(pk => pk.toBuffer())
+    ...tokenAddresses.map(pk => pk.toBuffer()),
+    encodeString(metadata.name),
+    encodeString(metadata.symbol),
+    encodeString(metadata.uri),
+    Buffer.from([ETF_CATEGORIES.indexOf(metadata.category)]),
   ]);
 
-  const ix = new TransactionInstruction({
+  return new TransactionInstruction({
     keys: [
       { pubkey: etfPda, isSigner: false, isWritable: true },
-      { pubkey: listerKeypair.publicKey, isSigner: true, isWritable: true },
+      { pubkey: getRegistryPda()[0], isSigner: false, isWritable: true },
+      { pubkey: getRegistryPagePda(registryIndex)[0], isSigner: false, isWritable: true },
+      { pubkey: listerPubkey, isSigner: true, isWritable: true },
       { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
     ],
     programId: PROGRAM_ID,
     data,
   });
+}
+
+/**
+ * Initialize ETF - accounts: [etf, registry, registry_page, lister, system_program]
+ * etfIndex: 0-4 (each wallet can have up to 5 ETFs)
+ */
+export async function initializeEtf(
+  connection: Connection,
+  listerKeypair: Keypair,
+  tokenAddresses: PublicKey[],
+  etfIndex: number,
+  metadata: EtfMetadata
+): Promise<string> {
+  const registryIndex = await fetchNextRegistryIndex(connection);
+  const ix = initializeEtfInstruction(listerKeypair.publicKey, tokenAddresses, etfIndex, registryIndex, metadata);
 
   const tx = new Transaction().add(ix);
   return sendAndConfirmTransaction(connection, tx, [listerKeypair], { commitment: 'confirmed' });
 }
 
 /**
- * Buy ETF - accounts: [etf, investor, lister_account, system_program, ...remaining_accounts]
- * New: includes token percentages and performs actual token swaps via Jupiter
+ * Buy ETF - accounts: [etf, position, investor, lister_account, dev_wallet, system_program,
+ * pending_purchase, gate_account, ...remaining_accounts]
+ * Instant ETFs only (no keeper, so no pending purchase); performs token swaps via Jupiter
  */
 export async function buyEtf(
   connection: Connection,
@@ -150,9 +267,13 @@ export async function buyEtf(
 
   const keys = [
     { pubkey: etfPda, isSigner: false, isWritable: true },
+    { pubkey: getPositionPda(etfPda, investorKeypair.publicKey)[0], isSigner: false, isWritable: true },
     { pubkey: investorKeypair.publicKey, isSigner: true, isWritable: true },
     { pubkey: listerPubkey, isSigner: false, isWritable: true },
+    { pubkey: TREASURY, isSigner: false, isWritable: true },
     { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+    { pubkey: NONE, isSigner: false, isWritable: false },
+    { pubkey: NONE, isSigner: false, isWritable: false },
   ];
 
   // Add token mint accounts as remaining accounts for validation
@@ -286,7 +407,7 @@ async function performJupiterSwap(
 }
 
 /**
- * Sell ETF - accounts: [etf, investor, lister_account, system_program]
+ * Sell ETF - accounts: [etf, position, investor, lister_account, dev_wallet, system_program]
  */
 export async function sellEtf(
   connection: Connection,
@@ -305,8 +426,10 @@ export async function sellEtf(
   const ix = new TransactionInstruction({
     keys: [
       { pubkey: etfPda, isSigner: false, isWritable: true },
+      { pubkey: getPositionPda(etfPda, investorKeypair.publicKey)[0], isSigner: false, isWritable: true },
       { pubkey: investorKeypair.publicKey, isSigner: true, isWritable: true },
       { pubkey: listerPubkey, isSigner: false, isWritable: true },
+      { pubkey: TREASURY, isSigner: false, isWritable: true },
       { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
     ],
     programId: PROGRAM_ID,
@@ -326,33 +449,12 @@ export async function buildUnsignedInitializeEtf(
   connection: Connection,
   listerPubkey: PublicKey,
   tokenAddresses: PublicKey[],
-  etfIndex: number = 0
+  etfIndex: number,
+  metadata: EtfMetadata
 ): Promise<{ transaction: string; etfPda: string; etfIndex: number }> {
   const [etfPda] = getEtfPda(listerPubkey, etfIndex);
-
-  // Data: discriminator + etf_index (u8) + vec<pubkey>
-  const indexBuf = Buffer.alloc(1);
-  indexBuf.writeUInt8(etfIndex, 0);
-
-  const vecLen = Buffer.alloc(4);
-  vecLen.writeUInt32LE(tokenAddresses.length, 0);
-
-  const data = Buffer.concat([
-    DISC.initializeEtf,
-    indexBuf,
-    vecLen,
-    ...tokenAddresses.map(pk => pk.toBuffer())
-  ]);
-
-  const ix = new TransactionInstruction({
-    keys: [
-      { pubkey: etfPda, isSigner: false, isWritable: true },
-      { pubkey: listerPubkey, isSigner: true, isWritable: true },
-      { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
-    ],
-    programId: PROGRAM_ID,
-    data,
-  });
+  const registryIndex = await fetchNextRegistryIndex(connection);
+  const ix = initializeEtfInstruction(listerPubkey, tokenAddresses, etfIndex, registryIndex, metadata);
 
   const tx = new Transaction().add(ix);
   tx.feePayer = listerPubkey;
@@ -372,9 +474,6 @@ export async function buildUnsignedInitializeEtf(
   };
 }
 
-// Dev wallet that receives 0.5% of all transactions (must match contract constant)
-const DEV_WALLET = new PublicKey('GdtZWBCTUrFneA7FdFaxyudhCLTKgBM4a9NVR3k4rPJx');
-
 /**
  * Build unsigned buy_etf transaction
  * NON-CUSTODIAL: User signs this with their wallet
@@ -409,16 +508,22 @@ export async function buildUnsignedBuyEtf(
 
   // Account order must match BuyETF struct in Rust:
   // 1. etf - the ETF PDA account
-  // 2. investor - signer (user's wallet)
-  // 3. lister_account - creator's wallet (receives 0.5% fee)
-  // 4. dev_wallet - dev wallet (receives 0.5% fee)
-  // 5. system_program - for SOL transfers
+  // 2. position - the investor's share balance (created on first buy)
+  // 3. investor - signer (user's wallet)
+  // 4. lister_account - creator's wallet (receives 0.5% fee)
+  // 5. dev_wallet - protocol treasury (receives 0.5% fee)
+  // 6. system_program - for SOL transfers
+  // 7. pending_purchase - none: instant ETFs only
+  // 8. gate_account - none: open ETFs only
   const keys = [
     { pubkey: etfPda, isSigner: false, isWritable: true },
+    { pubkey: getPositionPda(etfPda, investorPubkey)[0], isSigner: false, isWritable: true },
     { pubkey: investorPubkey, isSigner: true, isWritable: true },
     { pubkey: listerPubkey, isSigner: false, isWritable: true },
-    { pubkey: DEV_WALLET, isSigner: false, isWritable: true },
+    { pubkey: TREASURY, isSigner: false, isWritable: true },
     { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+    { pubkey: NONE, isSigner: false, isWritable: false },
+    { pubkey: NONE, isSigner: false, isWritable: false },
   ];
 
   const ix = new TransactionInstruction({
@@ -443,27 +548,67 @@ export async function buildUnsignedBuyEtf(
 }
 
 /**
- * Close ETF - accounts: [etf, lister, system_program]
- * Can only be called by the lister when total_supply is 0
+ * Close ETF - accounts: [etf, registry, registry_page, share_mint, lister, token_program,
+ * system_program, ...(vault, lister token account) per basket token]
+ * Can only be called by the lister when total_supply is 0; leftover vault tokens go to the lister
  */
 export async function closeEtf(
   connection: Connection,
-  listerKeypair: Keypair
+  listerKeypair: Keypair,
+  etfIndex: number = 0
 ): Promise<string> {
-  const [etfPda] = getEtfPda(listerKeypair.publicKey);
+  const [etfPda] = getEtfPda(listerKeypair.publicKey, etfIndex);
+  const etf = await fetchEtf(connection, etfPda);
 
   const data = DISC.closeEtf;
 
+  const keys = [
+    { pubkey: etfPda, isSigner: false, isWritable: true },
+    { pubkey: getRegistryPda()[0], isSigner: false, isWritable: true },
+    { pubkey: getRegistryPagePda(etf.registry_index.toNumber())[0], isSigner: false, isWritable: true },
+    { pubkey: getShareMintPda(etfPda)[0], isSigner: false, isWritable: false },
+    { pubkey: listerKeypair.publicKey, isSigner: true, isWritable: true },
+    { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+    { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+  ];
+  for (const mint of etf.token_addresses as PublicKey[]) {
+    keys.push({ pubkey: getAssociatedTokenAddressSync(mint, etfPda, true), isSigner: false, isWritable: true });
+    keys.push({ pubkey: getAssociatedTokenAddressSync(mint, listerKeypair.publicKey), isSigner: false, isWritable: true });
+  }
+
+  const ix = new TransactionInstruction({
+    keys,
+    programId: PROGRAM_ID,
+    data,
+  });
+
+  const tx = new Transaction().add(ix);
+  return sendAndConfirmTransaction(connection, tx, [listerKeypair], { commitment: 'confirmed' });
+}
+
+/**
+ * Migrate ETF - accounts: [etf, registry, registry_page, payer, system_program]
+ * Grows an ETF listed before the current account layout; anyone may pay for it
+ */
+export async function migrateEtf(
+  connection: Connection,
+  payerKeypair: Keypair,
+  etfPda: PublicKey
+): Promise<string> {
+  const registryIndex = await fetchNextRegistryIndex(connection);
+
   const ix = new TransactionInstruction({
     keys: [
       { pubkey: etfPda, isSigner: false, isWritable: true },
-      { pubkey: listerKeypair.publicKey, isSigner: true, isWritable: true },
+      { pubkey: getRegistryPda()[0], isSigner: false, isWritable: true },
+      { pubkey: getRegistryPagePda(registryIndex)[0], isSigner: false, isWritable: true },
+      { pubkey: payerKeypair.publicKey, isSigner: true, isWritable: true },
       { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
     ],
     programId: PROGRAM_ID,
-    data,
+    data: DISC.migrateEtf,
   });
 
   const tx = new Transaction().add(ix);
-  return sendAndConfirmTransaction(connection, tx, [listerKeypair], { commitment: 'confirmed' });
+  return sendAndConfirmTransaction(connection, tx, [payerKeypair], { commitment: 'confirmed' });
 }
@@ -0,0 +1,181 @@
+// Mock swap venue for localnet and tests.
+//
+// Each pool sells one mint for SOL at a fixed rate set by its authority, out
+// of a token reserve owned by the pool PDA. It stands in for Jupiter when
+// running the reference keeper against a local validator.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+
+declare_id!("n1SKASMDmWksLsMK3zVXv5hHByNxuRBbwGZXmhpZiKg");
+
+// Pool rates are quoted in token base units per SOL
+pub const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+
+// Tokens paid out for `lamports_in` at `tokens_per_sol`
+pub fn quote(lamports_in: u64, tokens_per_sol: u64) -> u64 {
+    (lamports_in as u128 * tokens_per_sol as u128 / LAMPORTS_PER_SOL as u128) as u64
+}
+
+// Anchor 0.31 emits its IDL instructions, which still call the deprecated
+// AccountInfo::realloc, next to the #[program] module rather than inside it,
+// so the allowance wraps both. The body keeps crate-level indentation
+#[allow(deprecated)]
+mod program_module {
+use super::*;
+
+#[program]
+pub mod mock_swap {
+    use super::*;
+
+    pub fn initialize_pool(ctx: Context<InitializePool>, tokens_per_sol: u64) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        pool.mint = ctx.accounts.mint.key();
+        pool.authority = ctx.accounts.authority.key();
+        pool.tokens_per_sol = tokens_per_sol;
+        pool.bump = ctx.bumps.pool;
+        Ok(())
+    }
+
+    pub fn set_rate(ctx: Context<SetRate>, tokens_per_sol: u64) -> Result<()> {
+        ctx.accounts.pool.tokens_per_sol = tokens_per_sol;
+        Ok(())
+    }
+
+    // Pay `lamports_in` into the pool and receive tokens from its reserve
+    pub fn swap_sol_for_tokens(
+        ctx: Context<SwapSolForTokens>,
+        lamports_in: u64,
+        min_tokens_out: u64,
+    ) -> Result<()> {
+        require!(lamports_in > 0, SwapError::InvalidAmount);
+
+        let pool = &ctx.accounts.pool;
+        let tokens_out = quote(lamports_in, pool.tokens_per_sol);
+        require!(tokens_out >= min_tokens_out, SwapError::SlippageExceeded);
+        require!(
+            ctx.accounts.reserve.amount >= tokens_out,
+            SwapError::InsufficientReserve
+        );
+
+        solana_program::program::invoke(
+            &solana_program::system_instruction::transfer(
+                ctx.accounts.user.key,
+                &pool.key(),
+                lamports_in,
+            ),
+            &[
+                ctx.accounts.user.to_account_info(),
+                pool.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        let signer_seeds: &[&[&[u8]]] = &[&[b"pool", pool.mint.as_ref(), &[pool.bump]]];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.reserve.to_account_info(),
+                    to: ctx.accounts.destination.to_account_info(),
+                    authority: pool.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            tokens_out,
+        )?;
+
+        emit!(SwapEvent {
+            pool: pool.key(),
+            user: ctx.accounts.user.key(),
+            lamports_in,
+            tokens_out,
+        });
+
+        Ok(())
+    }
+}
+}
+
+pub use program_module::*;
+
+#[derive(Accounts)]
+pub struct InitializePool<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 32 + 8 + 1,
+        seeds = [b"pool", mint.key().as_ref()],
+        bump
+    )]
+    pub pool: Account<'info, Pool>,
+    pub mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetRate<'info> {
+    #[account(mut, has_one = authority)]
+    pub pool: Account<'info, Pool>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SwapSolForTokens<'info> {
+    #[account(mut, seeds = [b"pool", pool.mint.as_ref()], bump = pool.bump)]
+    pub pool: Account<'info, Pool>,
+    #[account(mut, token::mint = pool.mint, token::authority = pool)]
+    pub reserve: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = pool.mint)]
+    pub destination: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[account]
+pub struct Pool {
+    pub mint: Pubkey,
+    pub authority: Pubkey,
+    pub tokens_per_sol: u64,
+    pub bump: u8,
+}
+
+#[event]
+pub struct SwapEvent {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub lamports_in: u64,
+    pub tokens_out: u64,
+}
+
+#[error_code]
+pub enum SwapError {
+    #[msg("Swap amount must be greater than zero")]
+    InvalidAmount,
+    #[msg("Pool would pay out less than the minimum requested")]
+    SlippageExceeded,
+    #[msg("Pool reserve cannot cover this swap")]
+    InsufficientReserve,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote(LAMPORTS_PER_SOL, 1_000_000), 1_000_000);
+        assert_eq!(quote(LAMPORTS_PER_SOL / 4, 1_000_000), 250_000);
+        assert_eq!(quote(1, 1_000_000), 0);
+    }
+
+    #[test]
+    fn test_quote_no_overflow() {
+        assert_eq!(quote(u64::MAX, LAMPORTS_PER_SOL), u64::MAX);
+    }
+}
@@ -1,379 +1,5071 @@
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program;
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::{self, CloseAccount, Mint, Token, TokenAccount, Transfer};
+use anchor_spl::token_2022::spl_token_2022::extension::{BaseStateWithExtensions, StateWithExtensions};
+use anchor_spl::token_2022::{self, Token2022};
+use anchor_spl::token_interface::spl_pod::optional_keys::OptionalNonZeroPubkey;
+use anchor_spl::token_interface::spl_token_metadata_interface::state::{Field, TokenMetadata};
+use anchor_spl::token_interface::{
+    self, Burn, MintTo, TokenInterface, TokenMetadataInitialize, TokenMetadataUpdateField,
+};
 
 declare_id!("CwwrCDfrsuA2C4YPiobU82ZA9wSWrecyLbbvP35QXmyo");
 
-// Hardcoded dev wallet - receives 0.5% fee on all buys/sells
+// Hardcoded dev wallet - withdraws the dev share of the treasury
 pub const DEV_WALLET: Pubkey = solana_program::pubkey!("GdtZWBCTUrFneA7FdFaxyudhCLTKgBM4a9NVR3k4rPJx");
 
+// Protocol treasury, the PDA ["treasury"] - receives 0.5% fee on all buys/sells
+pub const TREASURY: Pubkey = solana_program::pubkey!("65zUoyf4GfK6ai8dzYMoF4QhXgiFR753jDdhaQMG7wTs");
+pub const MAX_REWARDS_SHARE_BPS: u16 = 10_000;
+
+// Protocol governance - may force liquidation of ETFs whose lister has gone inactive
+pub const GOVERNANCE_AUTHORITY: Pubkey = DEV_WALLET;
+
+// ETF account size, fixed for the largest basket and metadata
+pub const ETF_SPACE: usize = 8 + 32 + 1 + (4 + 32 * 10) + 8 + 8 + 1 + 8 // etf_index + registry_index
+    + (4 + MAX_NAME_LEN) + (4 + MAX_SYMBOL_LEN) + (4 + MAX_URI_LEN) + 1 // metadata
+    + 1 + 8 + 8 // status + lister_last_active_ts + liquidation_started_ts
+    + 8 // trade_sequence
+    + (1 + 32) + 8 + 8 // keeper + pending_sol + purchase_count
+    + 8 // refund_timeout
+    + (1 + 32 + 8) // gate (largest variant)
+    + 8 + 8 + 8 // limits
+    + 8 + 2 // min_hold_seconds + early_exit_fee_bps
+    + (1 + 32) + 32 + 2 + 8 // parent_etf + parent_lister + parent_fee_bps + parent_fees_owed
+    + 8 + (4 + 8 * 10) // sol_assets + holdings
+    + 8; // legacy_shares
+
+// Size of ETF accounts listed before the registry, metadata and booked NAV;
+// migrate_etf grows them to ETF_SPACE
+pub const LEGACY_ETF_SPACE: usize = 8 + 32 + 1 + (4 + 32 * 10) + 8 + 8 + 1;
+
+// Lister inactivity after which governance may begin liquidation (180 days)
+pub const LISTER_INACTIVITY_SECONDS: i64 = 180 * 24 * 60 * 60;
+
+// Time between the start of a liquidation and the first vault conversion,
+// during which holders may claim their share in kind (7 days)
+pub const LIQUIDATION_NOTICE_SECONDS: i64 = 7 * 24 * 60 * 60;
+
+// Time a keeper has to settle a purchase before the investor may refund it.
+// The lister picks it with set_keeper, within these bounds.
+pub const DEFAULT_REFUND_TIMEOUT_SECONDS: i64 = 60 * 60;
+pub const MIN_REFUND_TIMEOUT_SECONDS: i64 = 60;
+pub const MAX_REFUND_TIMEOUT_SECONDS: i64 = 7 * 24 * 60 * 60;
+
+// Account size of a Position, for callers that pre-fund a PDA investor
+pub const POSITION_SPACE: usize = 8 + 32 + 32 + 8 + 1 + 8;
+
+// Account size of a PendingPurchase
+pub const PENDING_PURCHASE_SPACE: usize = 8 + 32 + 32 + 8 + 8 + 8 + (4 + 8 * 10) + 8 + 8 + 1;
+
+// Upper bounds on what a lister may pick with set_hold_period
+pub const MAX_HOLD_SECONDS: i64 = 365 * 24 * 60 * 60;
+pub const MAX_EARLY_EXIT_FEE_BPS: u16 = 1_000;
+
+// DCA plans buy at most this often, and pay the caller of each execution a tip
+pub const MIN_DCA_INTERVAL_SECONDS: i64 = 60;
+pub const DCA_CRANK_TIP_LAMPORTS: u64 = 10_000;
+
+// Exit triggers prepay the tip of the crank that fires them
+pub const EXIT_CRANK_TIP_LAMPORTS: u64 = 10_000;
+
+// NAV history keeps this many snapshots, taken at most once per interval
+// (a week of hourly snapshots)
+pub const NAV_HISTORY_LEN: usize = 168;
+pub const NAV_SNAPSHOT_INTERVAL_SECONDS: i64 = 60 * 60;
+
+// Leaderboard epochs are fixed windows of unix time starting at
+// epoch_id * LEADERBOARD_EPOCH_SECONDS. Three days fit in a NAV history, which
+// leaves the rest of the week to submit returns after an epoch ends.
+pub const LEADERBOARD_EPOCH_SECONDS: i64 = 3 * 24 * 60 * 60;
+pub const LEADERBOARD_SIZE: usize = 10;
+// A boundary NAV is the time-weighted NAV over the LEADERBOARD_TWAP_SECONDS
+// before the boundary. The snapshots must cover that window with no gap
+// longer than LEADERBOARD_SNAPSHOT_TOLERANCE_SECONDS, the last one included,
+// so whoever cranks the history cannot pick the moments that count.
+pub const LEADERBOARD_TWAP_SECONDS: i64 = 6 * NAV_SNAPSHOT_INTERVAL_SECONDS;
+pub const LEADERBOARD_SNAPSHOT_TOLERANCE_SECONDS: i64 = 2 * NAV_SNAPSHOT_INTERVAL_SECONDS;
+// ETFs need this much in net assets at the start of an epoch to be ranked,
+// so a tiny ETF cannot top the board on a few early-exit fees
+pub const LEADERBOARD_MIN_NET_ASSETS: u64 = 1_000_000_000;
+// Returns can be submitted this long after an epoch ends; then its rewards
+// can be finalized
+pub const LEADERBOARD_SUBMISSION_SECONDS: i64 = 24 * 60 * 60;
+
+// Forks may route up to this share of their creator fee to the parent ETF's
+// lister
+pub const MAX_PARENT_FEE_BPS: u16 = 5_000;
+
+// Wrapped shares are SPL tokens with the same base units as Position shares
+pub const SHARE_DECIMALS: u8 = 9;
+
+// Longest merkle proof accepted by verify_allowlist (trees of up to 2^32 wallets)
+pub const MAX_ALLOWLIST_PROOF_LEN: usize = 32;
+
 // Maximum ETFs per wallet (0-4 = 5 total)
 pub const MAX_ETFS_PER_WALLET: u8 = 5;
 
-#[program]
-pub mod mtf_etf {
-    use super::*;
+// Number of ETF entries stored in each registry page PDA
+pub const REGISTRY_PAGE_SIZE: u32 = 64;
 
-    pub fn initialize_etf(
-        ctx: Context<InitializeETF>,
-        etf_index: u8,
-        token_addresses: Vec<Pubkey>,
-    ) -> Result<()> {
-        require!(
-            etf_index < MAX_ETFS_PER_WALLET,
-            ErrorCode::MaxEtfsReached
-        );
-        require!(
-            token_addresses.len() > 0 && token_addresses.len() <= 10,
-            ErrorCode::InvalidTokenCount
-        );
+// Metadata length limits (bytes)
+pub const MAX_NAME_LEN: usize = 32;
+pub const MAX_SYMBOL_LEN: usize = 10;
+pub const MAX_URI_LEN: usize = 200;
 
-        let etf = &mut ctx.accounts.etf;
-        etf.lister = ctx.accounts.lister.key();
-        etf.etf_index = etf_index;
-        etf.token_addresses = token_addresses;
-        etf.total_supply = 0;
-        etf.accumulated_fees = 0;
-        etf.bump = ctx.bumps.etf;
+// Registry page that holds the ETF registered at `registry_index`
+pub fn registry_page_index(registry_index: u64) -> u32 {
+    (registry_index / REGISTRY_PAGE_SIZE as u64) as u32
+}
 
-        emit!(ETFCreatedEvent {
-            etf_address: etf.key(),
-            lister: ctx.accounts.lister.key(),
-            token_count: etf.token_addresses.len() as u8,
-            timestamp: Clock::get()?.unix_timestamp,
-        });
+// Creator and dev fees are each 1/200 (0.5%) of the traded SOL amount
+pub const FEE_DIVISOR: u64 = 200;
 
-        Ok(())
+// Fees charged on a buy or sell of `sol_amount`: (creator_fee, dev_fee)
+pub fn calculate_fees(sol_amount: u64) -> (u64, u64) {
+    (sol_amount / FEE_DIVISOR, sol_amount / FEE_DIVISOR)
+}
+
+// Protocol-wide buy limits. Below PROTOCOL_MIN_BUY_LAMPORTS the fees round to
+// zero; listers may tighten all three per ETF with set_buy_limits.
+pub const PROTOCOL_MIN_BUY_LAMPORTS: u64 = FEE_DIVISOR;
+pub const PROTOCOL_MAX_BUY_LAMPORTS: u64 = 1_000_000 * 1_000_000_000;   // 1M SOL
+pub const PROTOCOL_MAX_TOTAL_SUPPLY: u64 = 100_000_000 * 1_000_000_000; // 100M SOL of shares
+
+// Per-ETF limit if set (non-zero), capped by the protocol limit
+fn effective_cap(etf_cap: u64, protocol_cap: u64) -> u64 {
+    if etf_cap == 0 {
+        protocol_cap
+    } else {
+        etf_cap.min(protocol_cap)
     }
+}
 
-    pub fn buy_etf(
-        ctx: Context<BuyETF>,
-        sol_amount: u64,
-        token_percentages: Vec<u8>,
-    ) -> Result<()> {
-        require!(sol_amount > 0, ErrorCode::InvalidAmount);
-        require!(
-            token_percentages.len() == ctx.accounts.etf.token_addresses.len(),
-            ErrorCode::InvalidTokenPercentages
-        );
+// Check a buy of `sol_amount` against the protocol's and the ETF's limits.
+// `committed_supply` is the supply already minted or owed to pending purchases.
+pub fn check_buy_limits(limits: &BuyLimits, sol_amount: u64, committed_supply: u64) -> Result<()> {
+    require!(
+        sol_amount >= limits.min_buy_lamports.max(PROTOCOL_MIN_BUY_LAMPORTS),
+        ErrorCode::BuyBelowMinimum
+    );
+    require!(
+        sol_amount <= effective_cap(limits.max_buy_lamports, PROTOCOL_MAX_BUY_LAMPORTS),
+        ErrorCode::BuyAboveMaximum
+    );
 
-        // Verify percentages sum to 100
-        let total_percentage: u16 = token_percentages.iter().map(|&p| p as u16).sum();
-        require!(total_percentage == 100, ErrorCode::InvalidTokenPercentages);
+    // Supply is counted in SOL terms, as if shares were minted 1:1
+    let (creator_fee, dev_fee) = calculate_fees(sol_amount);
+    let supply_after = committed_supply
+        .checked_add(sol_amount - creator_fee - dev_fee)
+        .ok_or(ErrorCode::MaxSupplyExceeded)?;
+    require!(
+        supply_after <= effective_cap(limits.max_total_supply, PROTOCOL_MAX_TOTAL_SUPPLY),
+        ErrorCode::MaxSupplyExceeded
+    );
+    Ok(())
+}
 
-        // Verify dev wallet is correct
+// Per-ETF limits must fit inside the protocol's and leave some buy size valid
+pub fn validate_buy_limits(limits: &BuyLimits) -> Result<()> {
+    require!(limits.min_buy_lamports <= PROTOCOL_MAX_BUY_LAMPORTS, ErrorCode::InvalidBuyLimits);
+    require!(limits.max_buy_lamports <= PROTOCOL_MAX_BUY_LAMPORTS, ErrorCode::InvalidBuyLimits);
+    require!(limits.max_total_supply <= PROTOCOL_MAX_TOTAL_SUPPLY, ErrorCode::InvalidBuyLimits);
+    if limits.max_buy_lamports != 0 {
         require!(
-            ctx.accounts.dev_wallet.key() == DEV_WALLET,
-            ErrorCode::InvalidDevWallet
+            limits.max_buy_lamports >= limits.min_buy_lamports.max(PROTOCOL_MIN_BUY_LAMPORTS),
+            ErrorCode::InvalidBuyLimits
         );
+    }
+    Ok(())
+}
 
-        // Verify lister account matches ETF lister
-        require!(
-            ctx.accounts.lister_account.key() == ctx.accounts.etf.lister,
-            ErrorCode::InvalidListerAccount
-        );
+// Fee kept in the ETF when `sol_amount` of shares is sold inside the hold period
+pub fn early_exit_fee(sol_amount: u64, fee_bps: u16) -> u64 {
+    (sol_amount as u128 * fee_bps as u128 / 10_000) as u64
+}
 
-        let etf = &mut ctx.accounts.etf;
+// NAV is quoted in lamports per NAV_SCALE shares
+pub const NAV_SCALE: u64 = 1_000_000_000;
 
-        // Calculate fees: 0.5% to creator, 0.5% to dev = 1% total
-        let creator_fee = sol_amount / 200; // 0.5%
-        let dev_fee = sol_amount / 200;     // 0.5%
-        let total_fees = creator_fee + dev_fee;
-        let sol_after_fees = sol_amount - total_fees;
+// Basket tokens are valued through PriceFeed accounts kept by governance,
+// in lamports per PRICE_SCALE base units. Trades and cranks need prices at
+// most MAX_PRICE_AGE_SECONDS old; views accept any age.
+pub const PRICE_SCALE: u64 = 1_000_000_000;
+pub const MAX_PRICE_AGE_SECONDS: i64 = 10 * 60;
 
-        // Transfer SOL from investor to ETF account (for swaps)
-        solana_program::program::invoke(
-            &solana_program::system_instruction::transfer(
-                ctx.accounts.investor.key,
-                &etf.key(),
-                sol_after_fees,
-            ),
-            &[
-                ctx.accounts.investor.to_account_info(),
-                etf.to_account_info(),
-                ctx.accounts.system_program.to_account_info(),
-            ],
-        )?;
+// A keeper settlement must deliver at least this share of each allocation's
+// value at the feed price
+pub const MAX_SETTLEMENT_SLIPPAGE_BPS: u64 = 300;
 
-        // Transfer creator fee directly to lister
-        solana_program::program::invoke(
-            &solana_program::system_instruction::transfer(
-                ctx.accounts.investor.key,
-                ctx.accounts.lister_account.key,
-                creator_fee,
-            ),
-            &[
-                ctx.accounts.investor.to_account_info(),
-                ctx.accounts.lister_account.to_account_info(),
-                ctx.accounts.system_program.to_account_info(),
-            ],
-        )?;
+// Lamports `amount` base units of a token are worth at `price`
+pub fn token_value(amount: u64, price: u64) -> u64 {
+    (amount as u128 * price as u128 / PRICE_SCALE as u128).min(u64::MAX as u128) as u64
+}
 
-        // Transfer dev fee directly to dev wallet
-        solana_program::program::invoke(
-            &solana_program::system_instruction::transfer(
-                ctx.accounts.investor.key,
-                ctx.accounts.dev_wallet.key,
-                dev_fee,
-            ),
-            &[
-                ctx.accounts.investor.to_account_info(),
-                ctx.accounts.dev_wallet.to_account_info(),
-                ctx.accounts.system_program.to_account_info(),
-            ],
-        )?;
+// Shares minted for `value` lamports added to an ETF whose `total_supply`
+// is backed by `net_assets`; 1:1 while there is nothing to price against
+pub fn shares_for_value(value: u64, total_supply: u64, net_assets: u64) -> u64 {
+    if total_supply == 0 || net_assets == 0 {
+        return value;
+    }
+    (value as u128 * total_supply as u128 / net_assets as u128).min(u64::MAX as u128) as u64
+}
 
-        // Emit fee events for transparency
-        emit!(FeeTransferEvent {
-            etf_address: etf.key(),
-            recipient: ctx.accounts.lister_account.key(),
-            amount: creator_fee,
-            fee_type: FeeType::Creator,
-            timestamp: Clock::get()?.unix_timestamp,
-        });
+// Least value a settlement may deliver for an allocation of `lamports`
+pub fn settlement_floor(lamports: u64) -> u64 {
+    (lamports as u128 * (10_000 - MAX_SETTLEMENT_SLIPPAGE_BPS) as u128 / 10_000) as u64
+}
 
-        emit!(FeeTransferEvent {
-            etf_address: etf.key(),
-            recipient: ctx.accounts.dev_wallet.key(),
-            amount: dev_fee,
-            fee_type: FeeType::Dev,
-            timestamp: Clock::get()?.unix_timestamp,
-        });
+// Least SOL liquidate_vault must pay for `token_amount` at the feed `price`
+pub fn liquidation_min_out(token_amount: u64, price: u64) -> u64 {
+    settlement_floor(token_value(token_amount, price))
+}
 
-        // Emit purchase events for each token (for backend to execute swaps)
-        for (token_address, percentage) in etf.token_addresses.iter()
-            .zip(token_percentages.iter())
-        {
-            let sol_for_token = (sol_after_fees as u128 * (*percentage as u128) / 100) as u64;
+// Lamports backing NAV_SCALE shares; an ETF with no supply is at 1:1
+pub fn nav_per_share(net_assets: u64, total_supply: u64) -> u64 {
+    if total_supply == 0 {
+        return NAV_SCALE;
+    }
+    (net_assets as u128 * NAV_SCALE as u128 / total_supply as u128).min(u64::MAX as u128) as u64
+}
 
-            emit!(TokenPurchaseEvent {
-                etf_address: etf.key(),
-                investor: ctx.accounts.investor.key(),
-                token_address: *token_address,
-                sol_amount: sol_for_token,
-                percentage: *percentage,
-                timestamp: Clock::get()?.unix_timestamp,
-            });
+// Buy orders fill at or below their target NAV, sell orders at or above it
+pub fn order_triggered(side: OrderSide, nav: u64, target_nav: u64) -> bool {
+    match side {
+        OrderSide::Buy => nav <= target_nav,
+        OrderSide::Sell => nav >= target_nav,
+    }
+}
+
+// Which threshold of an exit trigger `nav` has hit, if any. Zero disables a
+// threshold.
+pub fn exit_triggered(nav: u64, stop_loss_nav: u64, take_profit_nav: u64) -> Option<ExitKind> {
+    if stop_loss_nav > 0 && nav <= stop_loss_nav {
+        Some(ExitKind::StopLoss)
+    } else if take_profit_nav > 0 && nav >= take_profit_nav {
+        Some(ExitKind::TakeProfit)
+    } else {
+        None
+    }
+}
+
+// Time-weighted average NAV over the `window_seconds` before `now`, and how
+// many seconds of the window the snapshots cover. Each snapshot's NAV holds
+// until the next one. With no time covered (a single snapshot at `now`) the
+// latest NAV is returned; with no snapshot at or before `now`, None.
+pub fn time_weighted_nav(snapshots: &[NavSnapshot], now: i64, window_seconds: i64) -> Option<(u64, i64)> {
+    let mut ordered: Vec<&NavSnapshot> = snapshots.iter().filter(|s| s.timestamp <= now).collect();
+    ordered.sort_by_key(|s| s.timestamp);
+    let latest = ordered.last()?;
+
+    let start = now.saturating_sub(window_seconds);
+    let mut weighted: u128 = 0;
+    let mut covered: i64 = 0;
+    for (i, snapshot) in ordered.iter().enumerate() {
+        let end = ordered.get(i + 1).map_or(now, |next| next.timestamp);
+        let seconds = end - snapshot.timestamp.max(start);
+        if seconds > 0 {
+            weighted += snapshot.nav_per_share as u128 * seconds as u128;
+            covered += seconds;
         }
+    }
+    if covered == 0 {
+        return Some((latest.nav_per_share, 0));
+    }
+    Some(((weighted / covered as u128) as u64, covered))
+}
 
-        // Update ETF state - mint 1:1 with SOL invested (after fees)
-        let tokens_to_mint = sol_after_fees;
-        etf.total_supply = etf.total_supply
-            .checked_add(tokens_to_mint)
-            .ok_or(ErrorCode::InvalidAmount)?;
+// The snapshot in effect at `timestamp`: the last one at or before it, if no
+// older than LEADERBOARD_SNAPSHOT_TOLERANCE_SECONDS
+pub fn snapshot_at(snapshots: &[NavSnapshot], timestamp: i64) -> Option<NavSnapshot> {
+    snapshots
+        .iter()
+        .filter(|s| s.timestamp <= timestamp)
+        .max_by_key(|s| s.timestamp)
+        .filter(|s| timestamp - s.timestamp <= LEADERBOARD_SNAPSHOT_TOLERANCE_SECONDS)
+        .copied()
+}
 
-        Ok(())
+// NAV at a leaderboard boundary: the time-weighted NAV over the
+// LEADERBOARD_TWAP_SECONDS before `timestamp`, with the snapshot in effect at
+// it. None unless the snapshots cover the whole window with no gap longer than
+// LEADERBOARD_SNAPSHOT_TOLERANCE_SECONDS.
+pub fn boundary_nav(snapshots: &[NavSnapshot], timestamp: i64) -> Option<(u64, NavSnapshot)> {
+    let last = snapshot_at(snapshots, timestamp)?;
+    let start = timestamp.checked_sub(LEADERBOARD_TWAP_SECONDS)?;
+    let mut times: Vec<i64> = snapshots.iter().map(|s| s.timestamp).filter(|t| *t <= timestamp).collect();
+    times.sort_unstable();
+    // The window must open on a snapshot at or before its start
+    let in_effect = times.partition_point(|t| *t <= start).checked_sub(1)?;
+    times.push(timestamp);
+    let gapless = times[in_effect..]
+        .windows(2)
+        .all(|pair| pair[1] - pair[0].max(start) <= LEADERBOARD_SNAPSHOT_TOLERANCE_SECONDS);
+    if !gapless {
+        return None;
     }
+    let (nav, _) = time_weighted_nav(snapshots, timestamp, LEADERBOARD_TWAP_SECONDS)?;
+    Some((nav, last))
+}
 
-    pub fn sell_etf(ctx: Context<SellETF>, tokens_to_sell: u64) -> Result<()> {
-        require!(tokens_to_sell > 0, ErrorCode::InvalidAmount);
+// Return between two NAVs in basis points (may be negative)
+pub fn nav_return_bps(start_nav: u64, end_nav: u64) -> Option<i64> {
+    if start_nav == 0 {
+        return None;
+    }
+    let change = end_nav as i128 - start_nav as i128;
+    i64::try_from(change * 10_000 / start_nav as i128).ok()
+}
 
-        // Verify dev wallet is correct
-        require!(
-            ctx.accounts.dev_wallet.key() == DEV_WALLET,
-            ErrorCode::InvalidDevWallet
-        );
+// Rank a return would take on a leaderboard sorted best first. Ties keep the
+// earlier submission ahead.
+pub fn leaderboard_rank(entries: &[LeaderboardEntry], return_bps: i64) -> usize {
+    entries.partition_point(|entry| entry.return_bps >= return_bps)
+}
 
-        // Verify lister account matches ETF lister
-        require!(
-            ctx.accounts.lister_account.key() == ctx.accounts.etf.lister,
-            ErrorCode::InvalidListerAccount
-        );
+// Split of an epoch's reward pool over `ranked` creators, best first. Rank i
+// of n weighs n - i, so the winner of two gets two thirds. Rounding dust
+// stays in the pool.
+pub fn creator_rewards(pool: u64, ranked: usize) -> Vec<u64> {
+    let total_weight = (ranked * (ranked + 1) / 2) as u128;
+    (0..ranked)
+        .map(|rank| (pool as u128 * (ranked - rank) as u128 / total_weight) as u64)
+        .collect()
+}
 
-        let etf = &mut ctx.accounts.etf;
-        require!(
-            etf.total_supply >= tokens_to_sell,
-            ErrorCode::InsufficientFunds
-        );
+// Move dev fees the treasury received since the last sweep into its books:
+// the rewards share to the pool, the rest to the dev balance. Returns both.
+fn sweep_treasury(treasury: &mut Account<Treasury>) -> Result<(u64, u64)> {
+    let info = treasury.to_account_info();
+    let booked = [treasury.dev_balance, treasury.rewards_pool, treasury.rewards_owed]
+        .into_iter()
+        .try_fold(Rent::get()?.minimum_balance(info.data_len()), u64::checked_add)
+        .ok_or(ErrorCode::InvalidAmount)?;
+    let collected = info.lamports().saturating_sub(booked);
+    let to_rewards = (collected as u128 * treasury.rewards_share_bps as u128 / 10_000) as u64;
+    let to_dev = collected - to_rewards;
+    treasury.rewards_pool = treasury
+        .rewards_pool
+        .checked_add(to_rewards)
+        .ok_or(ErrorCode::InvalidAmount)?;
+    treasury.dev_balance = treasury
+        .dev_balance
+        .checked_add(to_dev)
+        .ok_or(ErrorCode::InvalidAmount)?;
+    Ok((to_rewards, to_dev))
+}
 
-        // Calculate SOL to return (1:1)
-        let sol_to_return = tokens_to_sell;
+// SOL routed to one basket token for a given allocation percentage
+pub fn token_allocation(sol_after_fees: u64, percentage: u8) -> u64 {
+    (sol_after_fees as u128 * percentage as u128 / 100) as u64
+}
 
-        // Calculate fees: 0.5% to creator, 0.5% to dev = 1% total
-        let creator_fee = sol_to_return / 200; // 0.5%
-        let dev_fee = sol_to_return / 200;     // 0.5%
-        let total_fees = creator_fee + dev_fee;
-        let sol_after_fees = sol_to_return - total_fees;
+// Leaf of `wallet` in an allowlist merkle tree
+pub fn allowlist_leaf(wallet: &Pubkey) -> [u8; 32] {
+    solana_program::hash::hashv(&[b"allowlist", wallet.as_ref()]).to_bytes()
+}
 
-        // Check ETF has enough lamports
-        let etf_lamports = etf.to_account_info().lamports();
-        let rent = Rent::get()?;
-        let min_rent = rent.minimum_balance(etf.to_account_info().data_len());
+// Parent of two allowlist tree nodes. Pairs are hashed in sorted order, so
+// proofs carry no left/right flags.
+pub fn merkle_parent(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (left, right) = if a <= b { (a, b) } else { (b, a) };
+    solana_program::hash::hashv(&[left, right]).to_bytes()
+}
 
-        require!(
-            etf_lamports >= sol_to_return + min_rent,
-            ErrorCode::InsufficientFunds
-        );
+pub fn verify_merkle_proof(leaf: [u8; 32], proof: &[[u8; 32]], root: [u8; 32]) -> bool {
+    proof.iter().fold(leaf, |node, sibling| merkle_parent(&node, sibling)) == root
+}
 
-        // Transfer SOL back to investor (minus fees)
-        **etf.to_account_info().try_borrow_mut_lamports()? -= sol_to_return;
-        **ctx.accounts.investor.to_account_info().try_borrow_mut_lamports()? += sol_after_fees;
+// Split an ETF's lamports at close time into (rent, dust above rent)
+pub fn split_close_lamports(lamports: u64, rent_minimum: u64) -> (u64, u64) {
+    let rent = lamports.min(rent_minimum);
+    (rent, lamports - rent)
+}
 
-        // Transfer creator fee
-        **ctx.accounts.lister_account.to_account_info().try_borrow_mut_lamports()? += creator_fee;
+// The ETF's vault of `mint`: its associated token account, passed as
+// `info`. None if that account has not been created (nothing was ever
+// delivered to it).
+fn basket_vault<'info>(
+    etf: &Account<'info, ETF>,
+    mint: &Pubkey,
+    info: &'info AccountInfo<'info>,
+) -> Result<Option<Account<'info, TokenAccount>>> {
+    require!(
+        info.key() == get_associated_token_address(&etf.key(), mint),
+        ErrorCode::InvalidVaultAccount
+    );
+    if info.owner == &System::id() && info.data_is_empty() {
+        return Ok(None);
+    }
+    let vault = Account::<TokenAccount>::try_from(info)?;
+    require!(
+        vault.owner == etf.key() && vault.mint == *mint,
+        ErrorCode::InvalidVaultAccount
+    );
+    Ok(Some(vault))
+}
 
-        // Transfer dev fee
-        **ctx.accounts.dev_wallet.to_account_info().try_borrow_mut_lamports()? += dev_fee;
+// Drain every basket vault into `token_owner`'s token account of its mint
+// and close it, returning rent to `rent_destination`. `vault_accounts` holds
+// exactly one (vault, destination) pair per basket token, in token_addresses
+// order; vaults that were never created are skipped.
+fn sweep_vaults<'info>(
+    etf: &Account<'info, ETF>,
+    token_program: &Program<'info, Token>,
+    vault_accounts: &'info [AccountInfo<'info>],
+    token_owner: Pubkey,
+    rent_destination: &AccountInfo<'info>,
+) -> Result<(Vec<TokenRecovered>, u64)> {
+    require!(
+        vault_accounts.len() == 2 * etf.token_addresses.len(),
+        ErrorCode::InvalidVaultAccount
+    );
 
-        // Emit fee events
-        emit!(FeeTransferEvent {
-            etf_address: etf.key(),
-            recipient: ctx.accounts.lister_account.key(),
-            amount: creator_fee,
-            fee_type: FeeType::Creator,
-            timestamp: Clock::get()?.unix_timestamp,
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        b"etf",
+        etf.lister.as_ref(),
+        &[etf.etf_index],
+        &[etf.bump],
+    ]];
+
+    let mut tokens_recovered = Vec::with_capacity(etf.token_addresses.len());
+    let mut vault_rent_recovered: u64 = 0;
+
+    for (mint, pair) in etf.token_addresses.iter().zip(vault_accounts.chunks(2)) {
+        let vault_info = &pair[0];
+        let destination_info = &pair[1];
+        let Some(vault) = basket_vault(etf, mint, vault_info)? else {
+            continue;
+        };
+
+        if vault.amount > 0 {
+            let destination = Account::<TokenAccount>::try_from(destination_info)?;
+            require!(
+                destination.mint == vault.mint && destination.owner == token_owner,
+                ErrorCode::InvalidVaultAccount
+            );
+            token::transfer(
+                CpiContext::new_with_signer(
+                    token_program.to_account_info(),
+                    Transfer {
+                        from: vault_info.clone(),
+                        to: destination_info.clone(),
+                        authority: etf.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                vault.amount,
+            )?;
+        }
+
+        vault_rent_recovered = vault_rent_recovered
+            .checked_add(vault_info.lamports())
+            .ok_or(ErrorCode::InvalidAmount)?;
+
+        token::close_account(CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            CloseAccount {
+                account: vault_info.clone(),
+                destination: rent_destination.clone(),
+                authority: etf.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
+
+        tokens_recovered.push(TokenRecovered {
+            mint: vault.mint,
+            amount: vault.amount,
         });
+    }
+
+    Ok((tokens_recovered, vault_rent_recovered))
+}
+
+// Price of `mint` from its PriceFeed account. `max_age` None accepts a
+// price of any age.
+fn read_price(info: &AccountInfo, mint: &Pubkey, max_age: Option<i64>) -> Result<u64> {
+    require!(info.owner == &crate::ID, ErrorCode::InvalidPriceFeed);
+    let feed = PriceFeed::try_deserialize(&mut &info.try_borrow_data()?[..])
+        .map_err(|_| error!(ErrorCode::InvalidPriceFeed))?;
+    require!(feed.mint == *mint, ErrorCode::InvalidPriceFeed);
+    if let Some(max_age) = max_age {
+        let now = Clock::get()?.unix_timestamp;
+        require!(now.saturating_sub(feed.updated_ts) <= max_age, ErrorCode::StalePrice);
+    }
+    Ok(feed.price)
+}
 
+// Assets backing shares: the SOL the ETF has booked plus its basket holdings
+// at their feed prices. `price_feeds` are in basket order and only read for
+// tokens the ETF holds. Lamports or tokens sent to the ETF outside buys,
+// sells and settlements are never booked, so they cannot move NAV.
+fn net_assets(etf: &ETF, price_feeds: &[AccountInfo], max_age: Option<i64>) -> Result<u64> {
+    let mut total = etf.sol_assets;
+    for (i, (mint, held)) in etf.token_addresses.iter().zip(&etf.holdings).enumerate() {
+        if *held == 0 {
+            continue;
+        }
+        let feed = price_feeds.get(i).ok_or(ErrorCode::MissingPriceFeed)?;
+        total = total
+            .checked_add(token_value(*held, read_price(feed, mint, max_age)?))
+            .ok_or(ErrorCode::InvalidAmount)?;
+    }
+    Ok(total)
+}
+
+// A creator fee split between the lister and, on forks, the parent ETF's
+// lister: (lister_fee, parent_fee)
+pub fn split_creator_fee(creator_fee: u64, parent_fee_bps: u16) -> (u64, u64) {
+    let parent_fee = (creator_fee as u128 * parent_fee_bps as u128 / 10_000) as u64;
+    (creator_fee - parent_fee, parent_fee)
+}
+
+// Book a fork's parent fee, which the caller leaves in the ETF account for
+// claim_parent_fees. Returns (lister_fee, parent_fee).
+fn accrue_parent_fee(etf: &mut ETF, creator_fee: u64) -> Result<(u64, u64)> {
+    let (lister_fee, parent_fee) = split_creator_fee(creator_fee, etf.parent_fee_bps);
+    etf.parent_fees_owed = etf.parent_fees_owed
+        .checked_add(parent_fee)
+        .ok_or(ErrorCode::InvalidAmount)?;
+    Ok((lister_fee, parent_fee))
+}
+
+// Dev fees go to the TREASURY PDA, which must already have been created by
+// configure_treasury: a bare system account at that address would fail the
+// rent-exemption check on a small first fee, and the fee would never be
+// booked. Deploy the program, then configure the treasury, then list ETFs.
+fn check_dev_wallet(dev_wallet: &AccountInfo) -> Result<()> {
+    require!(dev_wallet.key() == TREASURY, ErrorCode::InvalidDevWallet);
+    require!(dev_wallet.owner == &ID, ErrorCode::TreasuryNotConfigured);
+    Ok(())
+}
+
+// Fee events of a trade: the lister's creator fee, the dev fee and, on
+// forks, the parent lister's share of the creator fee
+fn emit_fee_events(etf: &Account<ETF>, lister: Pubkey, creator_fee: u64, dev_fee: u64, timestamp: i64) {
+    let (lister_fee, parent_fee) = split_creator_fee(creator_fee, etf.parent_fee_bps);
+    emit!(FeeTransferEvent {
+        etf_address: etf.key(),
+        recipient: lister,
+        amount: lister_fee,
+        fee_type: FeeType::Creator,
+        timestamp,
+    });
+
+    emit!(FeeTransferEvent {
+        etf_address: etf.key(),
+        recipient: TREASURY,
+        amount: dev_fee,
+        fee_type: FeeType::Dev,
+        timestamp,
+    });
+
+    if parent_fee > 0 {
         emit!(FeeTransferEvent {
             etf_address: etf.key(),
-            recipient: ctx.accounts.dev_wallet.key(),
-            amount: dev_fee,
-            fee_type: FeeType::Dev,
-            timestamp: Clock::get()?.unix_timestamp,
+            recipient: etf.parent_lister,
+            amount: parent_fee,
+            fee_type: FeeType::Parent,
+            timestamp,
         });
+    }
+}
 
-        // Update ETF state
-        etf.total_supply = etf.total_supply
-            .checked_sub(tokens_to_sell)
-            .ok_or(ErrorCode::InvalidAmount)?;
+// Append a new ETF to the registry; returns its registry index
+fn register_etf(registry: &mut Registry, registry_page: &mut RegistryPage, etf: Pubkey) -> Result<u64> {
+    let registry_index = registry.etf_count;
+    registry.etf_count = registry.etf_count
+        .checked_add(1)
+        .ok_or(ErrorCode::InvalidAmount)?;
+    registry.active_count = registry.active_count
+        .checked_add(1)
+        .ok_or(ErrorCode::InvalidAmount)?;
 
-        Ok(())
+    // Append to the current registry page (created on first use)
+    registry_page.page_index = registry_page_index(registry_index);
+    registry_page.entries.push(RegistryEntry { etf, closed: false });
+    Ok(registry_index)
+}
+
+// State of a newly listed ETF with default settings and no parent; the
+// caller sets the bump and metadata
+fn init_etf_state(
+    etf: &mut ETF,
+    lister: Pubkey,
+    etf_index: u8,
+    registry_index: u64,
+    token_addresses: Vec<Pubkey>,
+) -> Result<()> {
+    *etf = ETF {
+        lister,
+        etf_index,
+        holdings: vec![0; token_addresses.len()],
+        token_addresses,
+        registry_index,
+        lister_last_active_ts: Clock::get()?.unix_timestamp,
+        ..Default::default()
+    };
+    Ok(())
+}
+
+// Token list of a fork: the parent's basket without `remove_tokens`, followed
+// by `add_tokens`. Removed tokens must be in the basket and added ones must
+// not be, nor repeat.
+pub fn fork_token_list(parent: &[Pubkey], remove_tokens: &[Pubkey], add_tokens: &[Pubkey]) -> Result<Vec<Pubkey>> {
+    require!(
+        remove_tokens.iter().all(|token| parent.contains(token)),
+        ErrorCode::InvalidForkChanges
+    );
+    let mut tokens: Vec<Pubkey> = parent
+        .iter()
+        .filter(|token| !remove_tokens.contains(token))
+        .copied()
+        .collect();
+    for token in add_tokens {
+        require!(!tokens.contains(token), ErrorCode::InvalidForkChanges);
+        tokens.push(*token);
     }
+    require!(
+        !tokens.is_empty() && tokens.len() <= 10,
+        ErrorCode::InvalidTokenCount
+    );
+    Ok(tokens)
+}
 
-    // Remove claim_fees - fees are now sent automatically
-    // Keeping close_etf for cleanup
+// Checks shared by buy_etf and quote_buy
+fn validate_buy(etf: &ETF, sol_amount: u64, token_percentages: &[u8]) -> Result<()> {
+    require!(sol_amount > 0, ErrorCode::InvalidAmount);
+    require!(etf.status == EtfStatus::Active, ErrorCode::EtfLiquidating);
+    require!(
+        token_percentages.len() == etf.token_addresses.len(),
+        ErrorCode::InvalidTokenPercentages
+    );
 
-    pub fn close_etf(ctx: Context<CloseETF>) -> Result<()> {
-        require!(
-            ctx.accounts.lister.key() == ctx.accounts.etf.lister,
-            ErrorCode::Unauthorized
-        );
+    // Verify percentages sum to 100
+    let total_percentage: u16 = token_percentages.iter().map(|&p| p as u16).sum();
+    require!(total_percentage == 100, ErrorCode::InvalidTokenPercentages);
 
-        require!(
-            ctx.accounts.etf.total_supply == 0,
-            ErrorCode::CannotCloseWithSupply
-        );
+    let committed_supply = etf.total_supply
+        .checked_add(etf.pending_sol)
+        .ok_or(ErrorCode::InvalidAmount)?;
+    check_buy_limits(&etf.limits, sol_amount, committed_supply)
+}
 
-        // Transfer any remaining lamports (rent) to lister
-        let etf_lamports = ctx.accounts.etf.to_account_info().lamports();
-        **ctx.accounts.etf.to_account_info().try_borrow_mut_lamports()? = 0;
-        **ctx.accounts.lister.to_account_info().try_borrow_mut_lamports()? += etf_lamports;
+// Pro-rata share of `distributable` lamports owed for `shares` out of `total_supply`
+pub fn liquidation_payout(shares: u64, total_supply: u64, distributable: u64) -> u64 {
+    if total_supply == 0 {
+        return 0;
+    }
+    (distributable as u128 * shares as u128 / total_supply as u128) as u64
+}
 
-        emit!(ETFClosedEvent {
-            etf_address: ctx.accounts.etf.key(),
-            lister: ctx.accounts.lister.key(),
-            timestamp: Clock::get()?.unix_timestamp,
-        });
+// Fee and per-token purchase events of a buy whose SOL has been collected
+// (the token events tell the backend which swaps to execute)
+fn emit_buy_events(
+    etf: &Account<ETF>,
+    investor: Pubkey,
+    lister: Pubkey,
+    sol_amount: u64,
+    token_percentages: &[u8],
+) -> Result<()> {
+    let (creator_fee, dev_fee) = calculate_fees(sol_amount);
+    let sol_after_fees = sol_amount - creator_fee - dev_fee;
+    let timestamp = Clock::get()?.unix_timestamp;
 
-        Ok(())
+    emit_fee_events(etf, lister, creator_fee, dev_fee, timestamp);
+
+    for (token_address, percentage) in etf.token_addresses.iter().zip(token_percentages.iter()) {
+        emit!(TokenPurchaseEvent {
+            etf_address: etf.key(),
+            investor,
+            token_address: *token_address,
+            sol_amount: token_allocation(sol_after_fees, *percentage),
+            percentage: *percentage,
+            timestamp,
+        });
     }
+    Ok(())
 }
 
-#[derive(Accounts)]
-#[instruction(etf_index: u8)]
-pub struct InitializeETF<'info> {
-    #[account(
-        init,
-        payer = lister,
-        space = 8 + 32 + 1 + (4 + 32 * 10) + 8 + 8 + 1, // Added 1 byte for etf_index
-        seeds = [b"etf", lister.key().as_ref(), &[etf_index]],
-        bump
-    )]
-    pub etf: Account<'info, ETF>,
-    #[account(mut)]
-    pub lister: Signer<'info>,
-    pub system_program: Program<'info, System>,
+// Mint shares at NAV for the SOL left after fees of an instant buy, which
+// the caller has already moved into the ETF. The hold period of the
+// position restarts.
+fn mint_instant_buy(etf: &mut Account<ETF>, position: &mut Account<Position>, sol_amount: u64) -> Result<u64> {
+    let (creator_fee, dev_fee) = calculate_fees(sol_amount);
+    let sol_after_fees = sol_amount - creator_fee - dev_fee;
+    let shares = shares_for_value(sol_after_fees, etf.total_supply, net_assets(etf, &[], None)?);
+
+    etf.sol_assets = etf
+        .sol_assets
+        .checked_add(sol_after_fees)
+        .ok_or(ErrorCode::InvalidAmount)?;
+    etf.total_supply = etf.total_supply.checked_add(shares).ok_or(ErrorCode::InvalidAmount)?;
+    position.shares = position.shares
+        .checked_add(shares)
+        .ok_or(ErrorCode::InvalidAmount)?;
+    position.last_buy_ts = Clock::get()?.unix_timestamp;
+    etf.trade_sequence = etf.trade_sequence
+        .checked_add(1)
+        .ok_or(ErrorCode::InvalidAmount)?;
+
+    // Trade summary - NAV is what backs the remaining supply after this buy
+    emit!(ETFBoughtEvent {
+        etf_address: etf.key(),
+        investor: position.investor,
+        sequence: etf.trade_sequence,
+        sol_amount,
+        shares_minted: shares,
+        creator_fee,
+        dev_fee,
+        total_supply: etf.total_supply,
+        nav_per_share: nav_per_share(net_assets(etf, &[], None)?, etf.total_supply),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(shares)
 }
 
-#[derive(Accounts)]
-pub struct BuyETF<'info> {
-    #[account(mut)]
+// Record a keeper buy of `sol_amount` (fees included) whose SOL after fees
+// the caller has already moved into the ETF. settle_purchase mints its shares.
+fn open_pending_purchase(
+    etf: &mut Account<ETF>,
+    pending: &mut Account<PendingPurchase>,
+    investor: Pubkey,
+    sol_amount: u64,
+    token_percentages: &[u8],
+    bump: u8,
+) -> Result<()> {
+    let (creator_fee, dev_fee) = calculate_fees(sol_amount);
+    let sol_after_fees = sol_amount - creator_fee - dev_fee;
+    pending.etf = etf.key();
+    pending.investor = investor;
+    pending.purchase_id = etf.purchase_count;
+    pending.sol_amount = sol_amount;
+    pending.sol_to_swap = sol_after_fees;
+    pending.allocations = token_percentages.iter()
+        .map(|percentage| token_allocation(sol_after_fees, *percentage))
+        .collect();
+    pending.created_ts = Clock::get()?.unix_timestamp;
+    pending.refundable_ts = pending.created_ts
+        .checked_add(etf.refund_timeout)
+        .ok_or(ErrorCode::InvalidAmount)?;
+    pending.bump = bump;
+
+    etf.purchase_count = etf.purchase_count
+        .checked_add(1)
+        .ok_or(ErrorCode::InvalidAmount)?;
+    etf.pending_sol = etf.pending_sol
+        .checked_add(sol_after_fees)
+        .ok_or(ErrorCode::InvalidAmount)?;
+
+    emit!(PurchasePendingEvent {
+        etf_address: etf.key(),
+        investor,
+        purchase_id: pending.purchase_id,
+        sol_amount: sol_after_fees,
+        timestamp: pending.created_ts,
+    });
+    Ok(())
+}
+
+// Selling inside the hold period costs the early-exit fee, if the lister
+// allows it at all. The last holder out has nobody to pay it to.
+fn hold_period_exit_fee(etf: &ETF, position: &Position, shares: u64) -> Result<u64> {
+    let now = Clock::get()?.unix_timestamp;
+    if now >= position.last_buy_ts.saturating_add(etf.min_hold_seconds) {
+        return Ok(0);
+    }
+    require!(etf.early_exit_fee_bps > 0, ErrorCode::HoldPeriodActive);
+    if etf.total_supply > shares {
+        Ok(early_exit_fee(shares, etf.early_exit_fee_bps))
+    } else {
+        Ok(0)
+    }
+}
+
+// Burn `shares` for their pro-rata slice of the ETF's booked SOL and pay
+// `seller` that slice less fees. The slice of the `exit_fee` shares stays
+// booked, raising NAV for the remaining holders. An ETF holding basket
+// tokens has no SOL to pay out; its holders exit with redeem_to_tokens.
+// The caller has already taken the shares out of the seller's position (or
+// order escrow).
+fn pay_out_sale(
+    etf: &mut Account<ETF>,
+    seller: &AccountInfo,
+    lister: &AccountInfo,
+    dev_wallet: &AccountInfo,
+    shares: u64,
+    exit_fee: u64,
+) -> Result<u64> {
+    require!(etf.status == EtfStatus::Active, ErrorCode::EtfLiquidating);
+    require!(etf.total_supply >= shares, ErrorCode::InsufficientFunds);
+    require!(etf.holdings.iter().all(|held| *held == 0), ErrorCode::RedeemInKind);
+
+    let sol_value = liquidation_payout(shares, etf.total_supply, etf.sol_assets);
+    let withheld = liquidation_payout(exit_fee, etf.total_supply, etf.sol_assets);
+
+    // Calculate fees: 0.5% to creator, 0.5% to dev = 1% total
+    let (creator_fee, dev_fee) = calculate_fees(sol_value);
+    let sol_after_fees = sol_value - creator_fee - dev_fee - withheld;
+
+    // The books never promise more than the ETF holds above rent, SOL held
+    // for unsettled purchases and parent fees owed
+    let etf_lamports = etf.to_account_info().lamports();
+    let min_rent = Rent::get()?.minimum_balance(etf.to_account_info().data_len());
+    require!(
+        etf_lamports >= sol_value + min_rent + etf.pending_sol + etf.parent_fees_owed,
+        ErrorCode::InsufficientFunds
+    );
+
+    // Transfer SOL back to the seller (minus fees); the withheld exit fee
+    // and a fork's parent fee stay put
+    etf.sol_assets -= sol_value - withheld;
+    let (lister_fee, parent_fee) = accrue_parent_fee(etf, creator_fee)?;
+    **etf.to_account_info().try_borrow_mut_lamports()? -= sol_value - withheld - parent_fee;
+    **seller.try_borrow_mut_lamports()? += sol_after_fees;
+
+    // Transfer creator fee
+    **lister.try_borrow_mut_lamports()? += lister_fee;
+
+    // Transfer dev fee
+    **dev_wallet.try_borrow_mut_lamports()? += dev_fee;
+
+    let timestamp = Clock::get()?.unix_timestamp;
+
+    emit_fee_events(etf, lister.key(), creator_fee, dev_fee, timestamp);
+
+    // Each basket token gives up the same fraction of its vault:
+    // shares_burned / supply_before (for backend to execute swaps)
+    for token_address in etf.token_addresses.iter() {
+        emit!(TokenSaleEvent {
+            etf_address: etf.key(),
+            investor: seller.key(),
+            token_address: *token_address,
+            shares_burned: shares,
+            supply_before: etf.total_supply,
+            timestamp,
+        });
+    }
+
+    // Update ETF state
+    etf.total_supply = etf.total_supply
+        .checked_sub(shares)
+        .ok_or(ErrorCode::InvalidAmount)?;
+    etf.trade_sequence = etf.trade_sequence
+        .checked_add(1)
+        .ok_or(ErrorCode::InvalidAmount)?;
+
+    emit!(ETFSoldEvent {
+        etf_address: etf.key(),
+        investor: seller.key(),
+        sequence: etf.trade_sequence,
+        shares_burned: shares,
+        sol_amount: sol_after_fees,
+        creator_fee,
+        dev_fee,
+        early_exit_fee: withheld,
+        total_supply: etf.total_supply,
+        nav_per_share: nav_per_share(net_assets(etf, &[], None)?, etf.total_supply),
+        timestamp,
+    });
+    Ok(sol_after_fees)
+}
+
+// Burn `shares` for their pro-rata slice of everything backing them - the
+// ETF's booked holdings of each basket token and its booked SOL - paid to
+// `seller`'s token accounts and wallet without selling anything. The slice
+// of the `exit_fee` shares stays booked for the remaining holders. Creator
+// and dev fees are charged on the value redeemed at NAV, as on a sell; the
+// caller collects them from whoever pays. `vault_pairs` are (vault ATA,
+// seller token account) per basket token in basket order, and `price_feeds`
+// value NAV at any age. The caller has already taken the shares out of the
+// seller's position (or order escrow). Returns the SOL slice and the
+// (lister_fee, dev_fee, parent_fee) owed.
+fn redeem_in_kind<'info>(
+    etf: &mut Account<'info, ETF>,
+    seller: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    vault_pairs: &'info [AccountInfo<'info>],
+    price_feeds: &[AccountInfo<'info>],
+    shares: u64,
+    exit_fee: u64,
+) -> Result<(u64, (u64, u64, u64))> {
+    require!(
+        vault_pairs.len() == 2 * etf.token_addresses.len(),
+        ErrorCode::InvalidVaultAccount
+    );
+    require!(etf.status == EtfStatus::Active, ErrorCode::EtfLiquidating);
+    require!(etf.total_supply >= shares, ErrorCode::InsufficientFunds);
+
+    let redeemed = shares - exit_fee;
+    let supply_before = etf.total_supply;
+    let assets_before = net_assets(etf, price_feeds, None)?;
+    let timestamp = Clock::get()?.unix_timestamp;
+    let (lister, etf_index, bump) = (etf.lister, etf.etf_index, etf.bump);
+    let signer_seeds: &[&[&[u8]]] = &[&[b"etf", lister.as_ref(), &[etf_index], &[bump]]];
+
+    let mut paid = Vec::with_capacity(etf.token_addresses.len());
+    for ((mint, held), pair) in etf.token_addresses.iter().zip(&etf.holdings).zip(vault_pairs.chunks(2)) {
+        let vault = basket_vault(etf, mint, &pair[0])?;
+        let amount = liquidation_payout(redeemed, supply_before, *held);
+        paid.push(amount);
+        if amount > 0 {
+            require!(vault.is_some(), ErrorCode::InvalidVaultAccount);
+            let destination = Account::<TokenAccount>::try_from(&pair[1])?;
+            require!(
+                destination.mint == *mint && destination.owner == seller.key(),
+                ErrorCode::InvalidVaultAccount
+            );
+            token::transfer(
+                CpiContext::new_with_signer(
+                    token_program.clone(),
+                    Transfer {
+                        from: pair[0].clone(),
+                        to: pair[1].clone(),
+                        authority: etf.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                amount,
+            )?;
+        }
+
+        emit!(TokenRedeemedEvent {
+            etf_address: etf.key(),
+            investor: seller.key(),
+            token_address: *mint,
+            amount,
+            timestamp,
+        });
+    }
+
+    // The SOL the ETF has booked backs shares as much as its tokens do
+    let sol_amount = liquidation_payout(redeemed, supply_before, etf.sol_assets);
+    for (held, amount) in etf.holdings.iter_mut().zip(paid) {
+        *held -= amount;
+    }
+    etf.sol_assets -= sol_amount;
+    **etf.to_account_info().try_borrow_mut_lamports()? -= sol_amount;
+    **seller.try_borrow_mut_lamports()? += sol_amount;
+
+    let (creator_fee, dev_fee) = calculate_fees(liquidation_payout(shares, supply_before, assets_before));
+    let (lister_fee, parent_fee) = accrue_parent_fee(etf, creator_fee)?;
+    emit_fee_events(etf, lister, creator_fee, dev_fee, timestamp);
+
+    etf.total_supply = etf.total_supply
+        .checked_sub(shares)
+        .ok_or(ErrorCode::InvalidAmount)?;
+    etf.trade_sequence = etf.trade_sequence
+        .checked_add(1)
+        .ok_or(ErrorCode::InvalidAmount)?;
+
+    // Same trade summary as a sell; sol_amount is the SOL slice only
+    emit!(ETFSoldEvent {
+        etf_address: etf.key(),
+        investor: seller.key(),
+        sequence: etf.trade_sequence,
+        shares_burned: shares,
+        sol_amount,
+        creator_fee,
+        dev_fee,
+        early_exit_fee: liquidation_payout(exit_fee, supply_before, assets_before),
+        total_supply: etf.total_supply,
+        nav_per_share: nav_per_share(net_assets(etf, price_feeds, None)?, etf.total_supply),
+        timestamp,
+    });
+    Ok((sol_amount, (lister_fee, dev_fee, parent_fee)))
+}
+
+// Take `amount` lamports out of `escrow`, an order or trigger of this
+// program, from its deposit: what it holds above `reserved`
+fn take_from_escrow(escrow: &AccountInfo, reserved: u64, amount: u64) -> Result<()> {
+    require!(
+        escrow.lamports() >= reserved.saturating_add(amount),
+        ErrorCode::InsufficientDeposit
+    );
+    **escrow.try_borrow_mut_lamports()? -= amount;
+    Ok(())
+}
+
+// Pay the (lister_fee, dev_fee, parent_fee) of an in-kind exit out of an
+// escrow's deposit. The parent fee stays in the ETF account, where
+// redeem_in_kind booked it.
+fn pay_fees_from_escrow(
+    escrow: &AccountInfo,
+    reserved: u64,
+    etf: &AccountInfo,
+    lister: &AccountInfo,
+    dev_wallet: &AccountInfo,
+    (lister_fee, dev_fee, parent_fee): (u64, u64, u64),
+) -> Result<()> {
+    take_from_escrow(escrow, reserved, lister_fee + dev_fee + parent_fee)?;
+    **lister.try_borrow_mut_lamports()? += lister_fee;
+    **dev_wallet.try_borrow_mut_lamports()? += dev_fee;
+    **etf.try_borrow_mut_lamports()? += parent_fee;
+    Ok(())
+}
+
+// Gated ETFs need the investor's AllowlistPass (merkle allowlist) or a token
+// account they own of the gate mint (minimum balance) passed to buy_etf
+fn check_gate(etf: &Account<ETF>, investor: &Pubkey, gate_account: Option<&AccountInfo>) -> Result<()> {
+    match etf.gate {
+        EtfGate::Open => Ok(()),
+        EtfGate::MerkleAllowlist { root } => {
+            let account = gate_account.ok_or(ErrorCode::NotAllowlisted)?;
+            require!(account.owner == &crate::ID, ErrorCode::NotAllowlisted);
+            let pass = AllowlistPass::try_deserialize(&mut &account.try_borrow_data()?[..])
+                .map_err(|_| error!(ErrorCode::NotAllowlisted))?;
+            require!(
+                pass.etf == etf.key() && pass.investor == *investor && pass.root == root,
+                ErrorCode::NotAllowlisted
+            );
+            Ok(())
+        }
+        EtfGate::MinimumBalance { mint, amount } => {
+            let account = gate_account.ok_or(ErrorCode::NotAllowlisted)?;
+            require!(account.owner == &token::ID, ErrorCode::NotAllowlisted);
+            let token_account = TokenAccount::try_deserialize(&mut &account.try_borrow_data()?[..])
+                .map_err(|_| error!(ErrorCode::NotAllowlisted))?;
+            require!(
+                token_account.owner == *investor
+                    && token_account.mint == mint
+                    && token_account.amount >= amount,
+                ErrorCode::NotAllowlisted
+            );
+            Ok(())
+        }
+    }
+}
+
+// Top up the share mint's rent before Token-2022 resizes it to hold `metadata`
+// in place of whatever metadata it holds now
+fn fund_share_metadata<'info>(
+    share_mint: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    metadata: &TokenMetadata,
+) -> Result<()> {
+    let current = {
+        let data = share_mint.try_borrow_data()?;
+        let mint = StateWithExtensions::<token_2022::spl_token_2022::state::Mint>::unpack(&data)?;
+        match mint.get_variable_len_extension::<TokenMetadata>() {
+            Ok(current) => current.tlv_size_of()?,
+            Err(_) => 0,
+        }
+    };
+    let len = share_mint.data_len() - current + metadata.tlv_size_of()?;
+    let shortfall = Rent::get()?.minimum_balance(len).saturating_sub(share_mint.lamports());
+    if shortfall > 0 {
+        solana_program::program::invoke(
+            &solana_program::system_instruction::transfer(payer.key, share_mint.key, shortfall),
+            &[payer.clone(), share_mint.clone(), system_program.clone()],
+        )?;
+    }
+    Ok(())
+}
+
+// Tombstone the ETF's registry entry, drain its vaults to the lister and emit
+// the closed event. Shared by close_etf and close_liquidated_etf; the ETF
+// account itself is closed by the caller's `close = lister` constraint.
+fn wind_down_etf<'info>(
+    etf: &Account<'info, ETF>,
+    registry: &mut Account<'info, Registry>,
+    registry_page: &mut Account<'info, RegistryPage>,
+    share_mint: &AccountInfo<'info>,
+    lister: &AccountInfo<'info>,
+    token_program: &Program<'info, Token>,
+    vault_accounts: &'info [AccountInfo<'info>],
+) -> Result<()> {
+    // SOL held for unsettled purchases belongs to their investors
+    require!(etf.pending_sol == 0, ErrorCode::PendingPurchasesOutstanding);
+    require!(etf.parent_fees_owed == 0, ErrorCode::ParentFeesUnclaimed);
+
+    // Wrapped shares count in total_supply, so the share mint is empty by
+    // now. Share mints have no close authority: it stays, with the ETF PDA as
+    // its authority, for an ETF listed again at this address.
+    if share_mint.owner == &token::ID || share_mint.owner == &token_2022::ID {
+        let mint = token_interface::Mint::try_deserialize(&mut &share_mint.try_borrow_data()?[..])?;
+        require!(mint.supply == 0, ErrorCode::SharesStillWrapped);
+    }
+
+    // Tombstone the registry entry so enumeration skips this ETF
+    let slot = (etf.registry_index % REGISTRY_PAGE_SIZE as u64) as usize;
+    let entry = registry_page.entries
+        .get_mut(slot)
+        .ok_or(ErrorCode::InvalidRegistryPage)?;
+    require!(entry.etf == etf.key(), ErrorCode::InvalidRegistryPage);
+    entry.closed = true;
+
+    registry.active_count = registry.active_count.saturating_sub(1);
+
+    let (tokens_recovered, vault_rent_recovered) = sweep_vaults(
+        etf,
+        token_program,
+        vault_accounts,
+        lister.key(),
+        lister,
+    )?;
+
+    // Lamports are moved to the lister by `close = lister` after the handler
+    let etf_info = etf.to_account_info();
+    let rent_minimum = Rent::get()?.minimum_balance(etf_info.data_len());
+    let (rent_recovered, dust_recovered) =
+        split_close_lamports(etf_info.lamports(), rent_minimum);
+
+    emit!(ETFClosedEvent {
+        etf_address: etf.key(),
+        lister: lister.key(),
+        rent_recovered,
+        dust_recovered,
+        vault_rent_recovered,
+        tokens_recovered,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+// Validate human-readable ETF metadata before storing it on-chain
+pub fn validate_metadata(name: &str, symbol: &str, uri: &str) -> Result<()> {
+    require!(
+        !name.is_empty() && name.len() <= MAX_NAME_LEN,
+        ErrorCode::InvalidName
+    );
+    require!(
+        !symbol.is_empty() && symbol.len() <= MAX_SYMBOL_LEN,
+        ErrorCode::InvalidSymbol
+    );
+    require!(uri.len() <= MAX_URI_LEN, ErrorCode::UriTooLong);
+    Ok(())
+}
+
+// Anchor 0.31 emits its IDL instructions, which still call the deprecated
+// AccountInfo::realloc, next to the #[program] module rather than inside it,
+// so the allowance wraps both. The body keeps crate-level indentation
+#[allow(deprecated)]
+mod program_module {
+use super::*;
+
+#[program]
+pub mod mtf_etf {
+    use super::*;
+
+    pub fn initialize_etf(
+        ctx: Context<InitializeETF>,
+        etf_index: u8,
+        token_addresses: Vec<Pubkey>,
+        name: String,
+        symbol: String,
+        uri: String,
+        category: EtfCategory,
+    ) -> Result<()> {
+        require!(
+            etf_index < MAX_ETFS_PER_WALLET,
+            ErrorCode::MaxEtfsReached
+        );
+        require!(
+            !token_addresses.is_empty() && token_addresses.len() <= 10,
+            ErrorCode::InvalidTokenCount
+        );
+        validate_metadata(&name, &symbol, &uri)?;
+
+        ctx.accounts.registry.bump = ctx.bumps.registry;
+        ctx.accounts.registry_page.bump = ctx.bumps.registry_page;
+        let registry_index = register_etf(
+            &mut ctx.accounts.registry,
+            &mut ctx.accounts.registry_page,
+            ctx.accounts.etf.key(),
+        )?;
+
+        let etf = &mut ctx.accounts.etf;
+        init_etf_state(etf, ctx.accounts.lister.key(), etf_index, registry_index, token_addresses)?;
+        etf.bump = ctx.bumps.etf;
+        etf.name = name;
+        etf.symbol = symbol;
+        etf.uri = uri;
+        etf.category = category;
+
+        emit!(ETFCreatedEvent {
+            etf_address: etf.key(),
+            lister: ctx.accounts.lister.key(),
+            token_count: etf.token_addresses.len() as u8,
+            name: etf.name.clone(),
+            symbol: etf.symbol.clone(),
+            uri: etf.uri.clone(),
+            category: etf.category,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Create an ETF for the caller from another ETF's basket, minus
+    // `remove_tokens` plus `add_tokens`. The fork records its parent, and
+    // `parent_fee_bps` of its creator fees accrue to the parent's lister.
+    // Settings (keeper, gate, limits, hold period) start at their defaults.
+    pub fn fork_etf(
+        ctx: Context<ForkETF>,
+        etf_index: u8,
+        changes: ForkChanges,
+        name: String,
+        symbol: String,
+        uri: String,
+        category: EtfCategory,
+    ) -> Result<()> {
+        require!(
+            etf_index < MAX_ETFS_PER_WALLET,
+            ErrorCode::MaxEtfsReached
+        );
+        require!(
+            changes.parent_fee_bps <= MAX_PARENT_FEE_BPS,
+            ErrorCode::InvalidParentFee
+        );
+        let parent = &ctx.accounts.parent;
+        let token_addresses = fork_token_list(&parent.token_addresses, &changes.remove_tokens, &changes.add_tokens)?;
+        validate_metadata(&name, &symbol, &uri)?;
+        let parent_etf = parent.key();
+        let parent_lister = parent.lister;
+
+        ctx.accounts.registry.bump = ctx.bumps.registry;
+        ctx.accounts.registry_page.bump = ctx.bumps.registry_page;
+        let registry_index = register_etf(
+            &mut ctx.accounts.registry,
+            &mut ctx.accounts.registry_page,
+            ctx.accounts.etf.key(),
+        )?;
+
+        let etf = &mut ctx.accounts.etf;
+        init_etf_state(etf, ctx.accounts.lister.key(), etf_index, registry_index, token_addresses)?;
+        etf.bump = ctx.bumps.etf;
+        etf.name = name;
+        etf.symbol = symbol;
+        etf.uri = uri;
+        etf.category = category;
+        etf.parent_etf = Some(parent_etf);
+        etf.parent_lister = parent_lister;
+        etf.parent_fee_bps = changes.parent_fee_bps;
+
+        let timestamp = Clock::get()?.unix_timestamp;
+        emit!(ETFCreatedEvent {
+            etf_address: etf.key(),
+            lister: etf.lister,
+            token_count: etf.token_addresses.len() as u8,
+            name: etf.name.clone(),
+            symbol: etf.symbol.clone(),
+            uri: etf.uri.clone(),
+            category: etf.category,
+            timestamp,
+        });
+        emit!(ETFForkedEvent {
+            etf_address: etf.key(),
+            parent_etf,
+            lister: etf.lister,
+            parent_lister,
+            removed_tokens: changes.remove_tokens,
+            added_tokens: changes.add_tokens,
+            parent_fee_bps: changes.parent_fee_bps,
+            timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Anyone may upgrade an ETF listed before ETF_SPACE grew: the account
+    // is resized (the payer covers the extra rent), registered, and its
+    // lamports above rent are booked as sol_assets. Legacy ETFs could not
+    // have a keeper, so they hold no basket tokens. Legacy holders had no
+    // Position; their supply waits in legacy_shares for
+    // restore_legacy_position.
+    pub fn migrate_etf(ctx: Context<MigrateETF>) -> Result<()> {
+        let info = ctx.accounts.etf.to_account_info();
+        require!(info.data_len() == LEGACY_ETF_SPACE, ErrorCode::AlreadyMigrated);
+        let legacy = {
+            let data = info.try_borrow_data()?;
+            require!(
+                data[..8] == *ETF::DISCRIMINATOR,
+                anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch
+            );
+            LegacyETF::deserialize(&mut &data[8..])?
+        };
+
+        let rent = Rent::get()?;
+        let sol_assets = info.lamports()
+            .checked_sub(rent.minimum_balance(LEGACY_ETF_SPACE))
+            .ok_or(ErrorCode::InsufficientFunds)?;
+        let rent_top_up = rent.minimum_balance(ETF_SPACE) - rent.minimum_balance(LEGACY_ETF_SPACE);
+        solana_program::program::invoke(
+            &solana_program::system_instruction::transfer(
+                ctx.accounts.payer.key,
+                info.key,
+                rent_top_up,
+            ),
+            &[
+                ctx.accounts.payer.to_account_info(),
+                info.clone(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+        info.resize(ETF_SPACE)?;
+
+        ctx.accounts.registry.bump = ctx.bumps.registry;
+        ctx.accounts.registry_page.bump = ctx.bumps.registry_page;
+        let registry_index = register_etf(
+            &mut ctx.accounts.registry,
+            &mut ctx.accounts.registry_page,
+            info.key(),
+        )?;
+
+        let token_count = legacy.token_addresses.len() as u8;
+        let mut etf = ETF::default();
+        init_etf_state(&mut etf, legacy.lister, legacy.etf_index, registry_index, legacy.token_addresses)?;
+        etf.total_supply = legacy.total_supply;
+        etf.accumulated_fees = legacy.accumulated_fees;
+        etf.bump = legacy.bump;
+        etf.sol_assets = sol_assets;
+        etf.legacy_shares = legacy.total_supply;
+        etf.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+
+        emit!(ETFMigratedEvent {
+            etf_address: info.key(),
+            lister: legacy.lister,
+            token_count,
+            registry_index,
+            sol_assets,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Credit a holder of a migrated ETF with the shares they held before
+    // positions existed (governance only, from the pre-migration records).
+    // At most legacy_shares can be restored; they are already in
+    // total_supply, so NAV does not move.
+    pub fn restore_legacy_position(ctx: Context<RestoreLegacyPosition>, shares: u64) -> Result<()> {
+        require!(
+            ctx.accounts.authority.key() == GOVERNANCE_AUTHORITY,
+            ErrorCode::Unauthorized
+        );
+        require!(shares > 0, ErrorCode::InvalidAmount);
+
+        let etf = &mut ctx.accounts.etf;
+        etf.legacy_shares = etf.legacy_shares
+            .checked_sub(shares)
+            .ok_or(ErrorCode::LegacySharesExceeded)?;
+
+        let position = &mut ctx.accounts.position;
+        position.etf = etf.key();
+        position.investor = ctx.accounts.investor.key();
+        position.bump = ctx.bumps.position;
+        position.shares = position.shares
+            .checked_add(shares)
+            .ok_or(ErrorCode::InvalidAmount)?;
+
+        emit!(LegacyPositionRestoredEvent {
+            etf_address: etf.key(),
+            investor: position.investor,
+            shares,
+            legacy_shares: etf.legacy_shares,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Permissionless: pay a fork's accrued parent fees to the parent's lister
+    pub fn claim_parent_fees(ctx: Context<ClaimParentFees>) -> Result<()> {
+        let etf = &mut ctx.accounts.etf;
+        let amount = etf.parent_fees_owed;
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        etf.parent_fees_owed = 0;
+
+        **etf.to_account_info().try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.parent_lister.try_borrow_mut_lamports()? += amount;
+
+        emit!(ParentFeesClaimedEvent {
+            etf_address: etf.key(),
+            parent_lister: etf.parent_lister,
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn buy_etf(
+        ctx: Context<BuyETF>,
+        sol_amount: u64,
+        token_percentages: Vec<u8>,
+    ) -> Result<()> {
+        validate_buy(&ctx.accounts.etf, sol_amount, &token_percentages)?;
+
+        // Verify dev wallet is correct
+        check_dev_wallet(&ctx.accounts.dev_wallet)?;
+
+        // Verify lister account matches ETF lister
+        require!(
+            ctx.accounts.lister_account.key() == ctx.accounts.etf.lister,
+            ErrorCode::InvalidListerAccount
+        );
+
+        // Keeper-settled ETFs record the buy as a pending purchase
+        require!(
+            ctx.accounts.etf.keeper.is_some() == ctx.accounts.pending_purchase.is_some(),
+            ErrorCode::InvalidPendingPurchase
+        );
+
+        check_gate(
+            &ctx.accounts.etf,
+            ctx.accounts.investor.key,
+            ctx.accounts.gate_account.as_ref().map(|account| account.as_ref()),
+        )?;
+
+        let etf = &mut ctx.accounts.etf;
+
+        // Calculate fees: 0.5% to creator, 0.5% to dev = 1% total
+        let (creator_fee, dev_fee) = calculate_fees(sol_amount);
+        let total_fees = creator_fee + dev_fee;
+        let sol_after_fees = sol_amount - total_fees;
+        let (lister_fee, parent_fee) = accrue_parent_fee(etf, creator_fee)?;
+
+        // Transfer SOL from investor to ETF account (for swaps), with a
+        // fork's parent fee
+        solana_program::program::invoke(
+            &solana_program::system_instruction::transfer(
+                ctx.accounts.investor.key,
+                &etf.key(),
+                sol_after_fees + parent_fee,
+            ),
+            &[
+                ctx.accounts.investor.to_account_info(),
+                etf.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        // Transfer creator fee directly to lister
+        solana_program::program::invoke(
+            &solana_program::system_instruction::transfer(
+                ctx.accounts.investor.key,
+                ctx.accounts.lister_account.key,
+                lister_fee,
+            ),
+            &[
+                ctx.accounts.investor.to_account_info(),
+                ctx.accounts.lister_account.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        // Transfer dev fee directly to dev wallet
+        solana_program::program::invoke(
+            &solana_program::system_instruction::transfer(
+                ctx.accounts.investor.key,
+                ctx.accounts.dev_wallet.key,
+                dev_fee,
+            ),
+            &[
+                ctx.accounts.investor.to_account_info(),
+                ctx.accounts.dev_wallet.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        emit_buy_events(
+            etf,
+            ctx.accounts.investor.key(),
+            ctx.accounts.lister_account.key(),
+            sol_amount,
+            &token_percentages,
+        )?;
+
+        let position = &mut ctx.accounts.position;
+        position.etf = etf.key();
+        position.investor = ctx.accounts.investor.key();
+        position.bump = ctx.bumps.position;
+
+        // Two-phase buy: hold the SOL until the keeper settles the swaps,
+        // shares are minted by settle_purchase
+        if let Some(pending) = ctx.accounts.pending_purchase.as_mut() {
+            let bump = ctx.bumps.pending_purchase.ok_or(ErrorCode::InvalidPendingPurchase)?;
+            return open_pending_purchase(etf, pending, ctx.accounts.investor.key(), sol_amount, &token_percentages, bump);
+        }
+
+        mint_instant_buy(etf, position, sol_amount)?;
+
+        Ok(())
+    }
+
+    // Keeper side of a two-phase buy. The keeper delivers the swapped basket
+    // tokens into the vaults and is reimbursed the purchase's SOL; only then
+    // are the investor's shares minted, at NAV for the value delivered. Each
+    // token must be worth at least its allocation, less
+    // MAX_SETTLEMENT_SLIPPAGE_BPS, at a fresh feed price. The pending
+    // account's rent goes back to the investor.
+    // remaining_accounts: (keeper token account, vault ATA, price feed)
+    // triples, one per basket token in token_addresses order
+    pub fn settle_purchase<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SettlePurchase<'info>>,
+        token_amounts: Vec<u64>,
+    ) -> Result<()> {
+        let etf = &mut ctx.accounts.etf;
+        let pending = &ctx.accounts.pending_purchase;
+        require!(etf.status == EtfStatus::Active, ErrorCode::EtfLiquidating);
+        require!(
+            etf.keeper == Some(ctx.accounts.keeper.key()),
+            ErrorCode::Unauthorized
+        );
+        require!(
+            token_amounts.len() == etf.token_addresses.len()
+                && pending.allocations.len() == etf.token_addresses.len(),
+            ErrorCode::InvalidSettlement
+        );
+        require!(
+            ctx.remaining_accounts.len() == 3 * etf.token_addresses.len(),
+            ErrorCode::InvalidVaultAccount
+        );
+
+        // NAV is taken before anything is delivered
+        let price_feeds: Vec<AccountInfo> = ctx
+            .remaining_accounts
+            .chunks(3)
+            .map(|triple| triple[2].clone())
+            .collect();
+        let assets_before = net_assets(etf, &price_feeds, Some(MAX_PRICE_AGE_SECONDS))?;
+
+        let mut delivered_value: u64 = 0;
+        for (i, (mint, triple)) in etf
+            .token_addresses
+            .iter()
+            .zip(ctx.remaining_accounts.chunks(3))
+            .enumerate()
+        {
+            basket_vault(etf, mint, &triple[1])?.ok_or(ErrorCode::InvalidVaultAccount)?;
+            let value = token_value(
+                token_amounts[i],
+                read_price(&triple[2], mint, Some(MAX_PRICE_AGE_SECONDS))?,
+            );
+            // Every token the investor paid for must arrive, worth what was
+            // paid for it
+            require!(
+                value >= settlement_floor(pending.allocations[i])
+                    && (pending.allocations[i] == 0 || token_amounts[i] > 0),
+                ErrorCode::SettlementBelowPrice
+            );
+            delivered_value = delivered_value.checked_add(value).ok_or(ErrorCode::InvalidAmount)?;
+            if token_amounts[i] > 0 {
+                token::transfer(
+                    CpiContext::new(
+                        ctx.accounts.token_program.to_account_info(),
+                        Transfer {
+                            from: triple[0].clone(),
+                            to: triple[1].clone(),
+                            authority: ctx.accounts.keeper.to_account_info(),
+                        },
+                    ),
+                    token_amounts[i],
+                )?;
+            }
+        }
+        for (held, amount) in etf.holdings.iter_mut().zip(&token_amounts) {
+            *held = held.checked_add(*amount).ok_or(ErrorCode::InvalidAmount)?;
+        }
+
+        // Reimburse the keeper out of the SOL held for this purchase
+        let sol_to_swap = pending.sol_to_swap;
+        etf.pending_sol = etf.pending_sol
+            .checked_sub(sol_to_swap)
+            .ok_or(ErrorCode::InsufficientFunds)?;
+        **etf.to_account_info().try_borrow_mut_lamports()? -= sol_to_swap;
+        **ctx.accounts.keeper.to_account_info().try_borrow_mut_lamports()? += sol_to_swap;
+
+        let shares = shares_for_value(delivered_value, etf.total_supply, assets_before);
+        etf.total_supply = etf.total_supply.checked_add(shares).ok_or(ErrorCode::InvalidAmount)?;
+        let position = &mut ctx.accounts.position;
+        position.shares = position.shares.checked_add(shares).ok_or(ErrorCode::InvalidAmount)?;
+        position.last_buy_ts = Clock::get()?.unix_timestamp;
+        etf.trade_sequence = etf.trade_sequence
+            .checked_add(1)
+            .ok_or(ErrorCode::InvalidAmount)?;
+
+        let timestamp = Clock::get()?.unix_timestamp;
+        emit!(PurchaseSettledEvent {
+            etf_address: etf.key(),
+            investor: pending.investor,
+            keeper: ctx.accounts.keeper.key(),
+            purchase_id: pending.purchase_id,
+            sol_amount: sol_to_swap,
+            token_amounts,
+            timestamp,
+        });
+
+        let (creator_fee, dev_fee) = calculate_fees(pending.sol_amount);
+        emit!(ETFBoughtEvent {
+            etf_address: etf.key(),
+            investor: pending.investor,
+            sequence: etf.trade_sequence,
+            sol_amount: pending.sol_amount,
+            shares_minted: shares,
+            creator_fee,
+            dev_fee,
+            total_supply: etf.total_supply,
+            nav_per_share: nav_per_share(net_assets(etf, &price_feeds, None)?, etf.total_supply),
+            timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Investor's way out of a purchase the keeper never settled. Once the
+    // purchase's refund timeout has passed - or straight away if the ETF is
+    // liquidating, since nothing can be settled then - the SOL held for the
+    // swaps goes back to the investor and no shares are minted. Fees are not
+    // refunded.
+    pub fn refund_pending_purchase(ctx: Context<RefundPendingPurchase>) -> Result<()> {
+        let etf = &mut ctx.accounts.etf;
+        let pending = &ctx.accounts.pending_purchase;
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            now >= pending.refundable_ts || etf.status != EtfStatus::Active,
+            ErrorCode::RefundNotYetAvailable
+        );
+
+        let sol_to_refund = pending.sol_to_swap;
+        etf.pending_sol = etf.pending_sol
+            .checked_sub(sol_to_refund)
+            .ok_or(ErrorCode::InsufficientFunds)?;
+        **etf.to_account_info().try_borrow_mut_lamports()? -= sol_to_refund;
+        **ctx.accounts.investor.to_account_info().try_borrow_mut_lamports()? += sol_to_refund;
+
+        emit!(PurchaseRefundedEvent {
+            etf_address: etf.key(),
+            investor: pending.investor,
+            purchase_id: pending.purchase_id,
+            sol_amount: sol_to_refund,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    // Choose who settles buys. With a keeper set, buys become two-phase
+    // (buy_etf + settle_purchase); None restores instant minting. The
+    // mode can only change while the ETF has no shares or pending purchases;
+    // rotating to a different keeper is always allowed. `refund_timeout`
+    // applies to purchases made from now on.
+    pub fn set_keeper(ctx: Context<SetKeeper>, keeper: Option<Pubkey>, refund_timeout: i64) -> Result<()> {
+        require!(
+            (MIN_REFUND_TIMEOUT_SECONDS..=MAX_REFUND_TIMEOUT_SECONDS).contains(&refund_timeout),
+            ErrorCode::InvalidRefundTimeout
+        );
+        let etf = &mut ctx.accounts.etf;
+        if etf.keeper.is_some() != keeper.is_some() {
+            require!(
+                etf.total_supply == 0 && etf.pending_sol == 0,
+                ErrorCode::SettlementModeLocked
+            );
+        }
+
+        etf.keeper = keeper;
+        etf.refund_timeout = refund_timeout;
+        etf.lister_last_active_ts = Clock::get()?.unix_timestamp;
+
+        emit!(KeeperUpdatedEvent {
+            etf_address: etf.key(),
+            keeper,
+            refund_timeout,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Restrict who may buy (lister only). Takes effect for the next buy;
+    // existing positions are unaffected.
+    pub fn set_gate(ctx: Context<SetGate>, gate: EtfGate) -> Result<()> {
+        if let EtfGate::MinimumBalance { amount, .. } = gate {
+            require!(amount > 0, ErrorCode::InvalidAmount);
+        }
+
+        let etf = &mut ctx.accounts.etf;
+        etf.gate = gate;
+        etf.lister_last_active_ts = Clock::get()?.unix_timestamp;
+
+        emit!(GateUpdatedEvent {
+            etf_address: etf.key(),
+            gate,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Bound buy sizes and the ETF's capacity (lister only). Zero leaves a
+    // limit at the protocol default. A supply cap below the current supply
+    // only stops further buys.
+    pub fn set_buy_limits(ctx: Context<SetBuyLimits>, limits: BuyLimits) -> Result<()> {
+        validate_buy_limits(&limits)?;
+
+        let etf = &mut ctx.accounts.etf;
+        etf.limits = limits;
+        etf.lister_last_active_ts = Clock::get()?.unix_timestamp;
+
+        emit!(BuyLimitsUpdatedEvent {
+            etf_address: etf.key(),
+            limits,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Lock shares for `min_hold_seconds` after each buy (lister only). Within
+    // the hold period sells fail, or - with a non-zero early_exit_fee_bps -
+    // pay that fee on top of the usual ones. The fee stays in the ETF for the
+    // remaining holders.
+    pub fn set_hold_period(ctx: Context<SetHoldPeriod>, min_hold_seconds: i64, early_exit_fee_bps: u16) -> Result<()> {
+        require!(
+            (0..=MAX_HOLD_SECONDS).contains(&min_hold_seconds)
+                && early_exit_fee_bps <= MAX_EARLY_EXIT_FEE_BPS,
+            ErrorCode::InvalidHoldPeriod
+        );
+
+        let etf = &mut ctx.accounts.etf;
+        etf.min_hold_seconds = min_hold_seconds;
+        etf.early_exit_fee_bps = early_exit_fee_bps;
+        etf.lister_last_active_ts = Clock::get()?.unix_timestamp;
+
+        emit!(HoldPeriodUpdatedEvent {
+            etf_address: etf.key(),
+            min_hold_seconds,
+            early_exit_fee_bps,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Prove the investor is on the ETF's merkle allowlist. The pass is kept
+    // for later buys and has to be renewed when the lister changes the root.
+    pub fn verify_allowlist(ctx: Context<VerifyAllowlist>, proof: Vec<[u8; 32]>) -> Result<()> {
+        let EtfGate::MerkleAllowlist { root } = ctx.accounts.etf.gate else {
+            return err!(ErrorCode::NotAllowlisted);
+        };
+        require!(
+            proof.len() <= MAX_ALLOWLIST_PROOF_LEN
+                && verify_merkle_proof(allowlist_leaf(ctx.accounts.investor.key), &proof, root),
+            ErrorCode::NotAllowlisted
+        );
+
+        let pass = &mut ctx.accounts.allowlist_pass;
+        pass.etf = ctx.accounts.etf.key();
+        pass.investor = ctx.accounts.investor.key();
+        pass.root = root;
+        pass.bump = ctx.bumps.allowlist_pass;
+
+        Ok(())
+    }
+
+    pub fn update_metadata(
+        ctx: Context<UpdateMetadata>,
+        name: String,
+        symbol: String,
+        uri: String,
+        category: EtfCategory,
+    ) -> Result<()> {
+        validate_metadata(&name, &symbol, &uri)?;
+
+        // A share mint carrying metadata mirrors the ETF's; a classic SPL
+        // mint, or none at all, has nothing to update
+        let share_mint = ctx.accounts.share_mint.to_account_info();
+        if share_mint.owner == &token_2022::ID {
+            let etf = &ctx.accounts.etf;
+            let metadata = TokenMetadata {
+                update_authority: OptionalNonZeroPubkey::try_from(Some(etf.key()))?,
+                mint: share_mint.key(),
+                name: name.clone(),
+                symbol: symbol.clone(),
+                uri: uri.clone(),
+                additional_metadata: Vec::new(),
+            };
+            fund_share_metadata(
+                &share_mint,
+                &ctx.accounts.lister.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+                &metadata,
+            )?;
+
+            let signer_seeds: &[&[&[u8]]] = &[&[
+                b"etf",
+                etf.lister.as_ref(),
+                &[etf.etf_index],
+                &[etf.bump],
+            ]];
+            let fields = [(Field::Name, &name), (Field::Symbol, &symbol), (Field::Uri, &uri)];
+            for (field, value) in fields {
+                token_interface::token_metadata_update_field(
+                    CpiContext::new_with_signer(
+                        ctx.accounts.token_program.to_account_info(),
+                        TokenMetadataUpdateField {
+                            program_id: ctx.accounts.token_program.to_account_info(),
+                            metadata: share_mint.clone(),
+                            update_authority: etf.to_account_info(),
+                        },
+                        signer_seeds,
+                    ),
+                    field,
+                    value.clone(),
+                )?;
+            }
+        }
+
+        let etf = &mut ctx.accounts.etf;
+        etf.name = name;
+        etf.symbol = symbol;
+        etf.uri = uri;
+        etf.category = category;
+        etf.lister_last_active_ts = Clock::get()?.unix_timestamp;
+
+        emit!(MetadataUpdatedEvent {
+            etf_address: etf.key(),
+            name: etf.name.clone(),
+            symbol: etf.symbol.clone(),
+            uri: etf.uri.clone(),
+            category: etf.category,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn sell_etf(ctx: Context<SellETF>, tokens_to_sell: u64) -> Result<()> {
+        require!(tokens_to_sell > 0, ErrorCode::InvalidAmount);
+
+        // Verify dev wallet is correct
+        check_dev_wallet(&ctx.accounts.dev_wallet)?;
+
+        // Verify lister account matches ETF lister
+        require!(
+            ctx.accounts.lister_account.key() == ctx.accounts.etf.lister,
+            ErrorCode::InvalidListerAccount
+        );
+
+        require!(
+            ctx.accounts.position.shares >= tokens_to_sell,
+            ErrorCode::InsufficientShares
+        );
+
+        let etf = &mut ctx.accounts.etf;
+        require!(etf.status == EtfStatus::Active, ErrorCode::EtfLiquidating);
+        require!(
+            etf.total_supply >= tokens_to_sell,
+            ErrorCode::InsufficientFunds
+        );
+
+        let exit_fee = hold_period_exit_fee(etf, &ctx.accounts.position, tokens_to_sell)?;
+
+        let position = &mut ctx.accounts.position;
+        position.shares = position.shares
+            .checked_sub(tokens_to_sell)
+            .ok_or(ErrorCode::InsufficientShares)?;
+
+        pay_out_sale(
+            etf,
+            &ctx.accounts.investor.to_account_info(),
+            &ctx.accounts.lister_account,
+            &ctx.accounts.dev_wallet,
+            tokens_to_sell,
+            exit_fee,
+        )?;
+
+        Ok(())
+    }
+
+    // In-kind exit: burn `shares` and pay the investor their pro-rata slice
+    // of everything backing them - the ETF's booked holdings of each basket
+    // token and its booked SOL - without selling anything. Creator and dev
+    // fees are charged on the value redeemed at NAV, as on a sell, and paid
+    // from the investor's wallet; an early-exit fee is withheld in kind for
+    // the remaining holders.
+    // remaining_accounts: (vault ATA, investor token account) per basket
+    // token, in basket order, then the basket's price feeds in the same
+    // order; they value the reported NAV, at any age. A vault never created
+    // holds nothing to pay.
+    pub fn redeem_to_tokens<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RedeemToTokens<'info>>,
+        shares: u64,
+    ) -> Result<()> {
+        require!(shares > 0, ErrorCode::InvalidAmount);
+        check_dev_wallet(&ctx.accounts.dev_wallet)?;
+        require!(
+            ctx.accounts.lister_account.key() == ctx.accounts.etf.lister,
+            ErrorCode::InvalidListerAccount
+        );
+        require!(
+            ctx.accounts.position.shares >= shares,
+            ErrorCode::InsufficientShares
+        );
+
+        let basket_len = ctx.accounts.etf.token_addresses.len();
+        require!(
+            ctx.remaining_accounts.len() == 3 * basket_len,
+            ErrorCode::InvalidVaultAccount
+        );
+        let (vault_pairs, price_feeds) = ctx.remaining_accounts.split_at(2 * basket_len);
+        let exit_fee = hold_period_exit_fee(&ctx.accounts.etf, &ctx.accounts.position, shares)?;
+
+        let position = &mut ctx.accounts.position;
+        position.shares = position.shares
+            .checked_sub(shares)
+            .ok_or(ErrorCode::InsufficientShares)?;
+        let (_, (lister_fee, dev_fee, parent_fee)) = redeem_in_kind(
+            &mut ctx.accounts.etf,
+            &ctx.accounts.investor.to_account_info(),
+            &ctx.accounts.token_program.to_account_info(),
+            vault_pairs,
+            price_feeds,
+            shares,
+            exit_fee,
+        )?;
+
+        let etf_info = ctx.accounts.etf.to_account_info();
+        for (recipient, fee) in [
+            (&ctx.accounts.lister_account, lister_fee),
+            (&ctx.accounts.dev_wallet, dev_fee),
+            (&etf_info, parent_fee),
+        ] {
+            if fee == 0 {
+                continue;
+            }
+            solana_program::program::invoke(
+                &solana_program::system_instruction::transfer(
+                    ctx.accounts.investor.key,
+                    recipient.key,
+                    fee,
+                ),
+                &[
+                    ctx.accounts.investor.to_account_info(),
+                    recipient.clone(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+            )?;
+        }
+
+        Ok(())
+    }
+
+    // ------------------------------------------------------------------------
+    // Recurring buys. A DcaPlan holds SOL pre-funded by the investor and buys
+    // `amount_per_period` every `interval_seconds`. Anyone may execute a due
+    // plan and is paid DCA_CRANK_TIP_LAMPORTS out of it for doing so.
+    // ------------------------------------------------------------------------
+
+    // Open a plan funded with `deposit`; the first buy is due straight away
+    pub fn create_dca_plan(
+        ctx: Context<CreateDcaPlan>,
+        amount_per_period: u64,
+        interval_seconds: i64,
+        token_percentages: Vec<u8>,
+        deposit: u64,
+    ) -> Result<()> {
+        require!(ctx.accounts.etf.keeper.is_none(), ErrorCode::RequiresInstantBuys);
+        require!(interval_seconds >= MIN_DCA_INTERVAL_SECONDS, ErrorCode::InvalidDcaInterval);
+        validate_buy(&ctx.accounts.etf, amount_per_period, &token_percentages)?;
+
+        if deposit > 0 {
+            solana_program::program::invoke(
+                &solana_program::system_instruction::transfer(
+                    ctx.accounts.investor.key,
+                    &ctx.accounts.dca_plan.key(),
+                    deposit,
+                ),
+                &[
+                    ctx.accounts.investor.to_account_info(),
+                    ctx.accounts.dca_plan.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+            )?;
+        }
+
+        // The position is opened now, paid for by the investor, so that
+        // executions never need a rent payer
+        let position = &mut ctx.accounts.position;
+        position.etf = ctx.accounts.etf.key();
+        position.investor = ctx.accounts.investor.key();
+        position.bump = ctx.bumps.position;
+
+        let now = Clock::get()?.unix_timestamp;
+        let plan = &mut ctx.accounts.dca_plan;
+        plan.etf = ctx.accounts.etf.key();
+        plan.investor = ctx.accounts.investor.key();
+        plan.amount_per_period = amount_per_period;
+        plan.interval_seconds = interval_seconds;
+        plan.next_execution_ts = now;
+        plan.token_percentages = token_percentages;
+        plan.executions = 0;
+        plan.bump = ctx.bumps.dca_plan;
+
+        emit!(DcaPlanCreatedEvent {
+            etf_address: plan.etf,
+            investor: plan.investor,
+            amount_per_period,
+            interval_seconds,
+            deposit,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    pub fn fund_dca_plan(ctx: Context<FundDcaPlan>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+
+        solana_program::program::invoke(
+            &solana_program::system_instruction::transfer(
+                ctx.accounts.investor.key,
+                &ctx.accounts.dca_plan.key(),
+                amount,
+            ),
+            &[
+                ctx.accounts.investor.to_account_info(),
+                ctx.accounts.dca_plan.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        emit!(DcaPlanFundedEvent {
+            etf_address: ctx.accounts.dca_plan.etf,
+            investor: ctx.accounts.investor.key(),
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Permissionless: buy the plan's amount for its investor once it is due.
+    // The buy is an instant buy_etf in every respect except who pays.
+    pub fn execute_dca(ctx: Context<ExecuteDca>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let investor = ctx.accounts.dca_plan.investor;
+        let sol_amount = ctx.accounts.dca_plan.amount_per_period;
+        let token_percentages = ctx.accounts.dca_plan.token_percentages.clone();
+        require!(now >= ctx.accounts.dca_plan.next_execution_ts, ErrorCode::DcaNotDue);
+        require!(ctx.accounts.etf.keeper.is_none(), ErrorCode::RequiresInstantBuys);
+        check_dev_wallet(&ctx.accounts.dev_wallet)?;
+        require!(
+            ctx.accounts.lister_account.key() == ctx.accounts.etf.lister,
+            ErrorCode::InvalidListerAccount
+        );
+        validate_buy(&ctx.accounts.etf, sol_amount, &token_percentages)?;
+        check_gate(
+            &ctx.accounts.etf,
+            &investor,
+            ctx.accounts.gate_account.as_ref().map(|account| account.as_ref()),
+        )?;
+
+        // Pay for the buy and the tip out of the plan's balance above rent
+        let plan_info = ctx.accounts.dca_plan.to_account_info();
+        let rent = Rent::get()?.minimum_balance(plan_info.data_len());
+        let cost = sol_amount
+            .checked_add(DCA_CRANK_TIP_LAMPORTS)
+            .ok_or(ErrorCode::InvalidAmount)?;
+        require!(
+            plan_info.lamports() >= rent.saturating_add(cost),
+            ErrorCode::DcaPlanUnderfunded
+        );
+
+        let (creator_fee, dev_fee) = calculate_fees(sol_amount);
+        let (lister_fee, parent_fee) = accrue_parent_fee(&mut ctx.accounts.etf, creator_fee)?;
+        **plan_info.try_borrow_mut_lamports()? -= cost;
+        **ctx.accounts.etf.to_account_info().try_borrow_mut_lamports()? +=
+            sol_amount - creator_fee - dev_fee + parent_fee;
+        **ctx.accounts.lister_account.try_borrow_mut_lamports()? += lister_fee;
+        **ctx.accounts.dev_wallet.try_borrow_mut_lamports()? += dev_fee;
+        **ctx.accounts.caller.to_account_info().try_borrow_mut_lamports()? += DCA_CRANK_TIP_LAMPORTS;
+
+        emit_buy_events(
+            &ctx.accounts.etf,
+            investor,
+            ctx.accounts.lister_account.key(),
+            sol_amount,
+            &token_percentages,
+        )?;
+        let shares = mint_instant_buy(&mut ctx.accounts.etf, &mut ctx.accounts.position, sol_amount)?;
+
+        // Missed periods are skipped rather than bought back to back
+        let plan = &mut ctx.accounts.dca_plan;
+        plan.executions = plan.executions
+            .checked_add(1)
+            .ok_or(ErrorCode::InvalidAmount)?;
+        plan.next_execution_ts = plan.next_execution_ts.saturating_add(plan.interval_seconds);
+        if plan.next_execution_ts <= now {
+            plan.next_execution_ts = now.saturating_add(plan.interval_seconds);
+        }
+
+        emit!(DcaExecutedEvent {
+            etf_address: plan.etf,
+            investor,
+            caller: ctx.accounts.caller.key(),
+            sol_amount,
+            shares_minted: shares,
+            tip: DCA_CRANK_TIP_LAMPORTS,
+            next_execution_ts: plan.next_execution_ts,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    // Close the plan and refund its whole balance, rent included. Works in
+    // any ETF state, including after the ETF is closed.
+    pub fn cancel_dca_plan(ctx: Context<CancelDcaPlan>) -> Result<()> {
+        emit!(DcaPlanCancelledEvent {
+            etf_address: ctx.accounts.dca_plan.etf,
+            investor: ctx.accounts.investor.key(),
+            refunded: ctx.accounts.dca_plan.to_account_info().lamports(),
+            executions: ctx.accounts.dca_plan.executions,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // ------------------------------------------------------------------------
+    // Limit orders. An order escrows SOL (buy) or shares (sell) until the
+    // ETF's NAV crosses its target; then anyone may fill it. NAV is the
+    // booked holdings at fresh feed prices plus the booked SOL, so orders
+    // follow the market and donations cannot trigger a fill. On an ETF
+    // without a keeper, fills are ordinary instant buys and sells at NAV. On
+    // a keeper ETF, a buy fill opens a pending purchase for the keeper to
+    // settle and a sell fill redeems the shares in kind, both paid for out
+    // of the order's deposit.
+    // ------------------------------------------------------------------------
+
+    // Place an order. Buy orders escrow `amount` lamports (fees included);
+    // sell orders move `amount` shares out of the owner's position, which
+    // must be past its hold period. `deposit` lamports are held on top to pay
+    // for a fill on a keeper ETF: the rent of a buy's pending purchase or the
+    // fees of an in-kind sell. What is left goes back with the order.
+    pub fn place_limit_order(
+        ctx: Context<PlaceLimitOrder>,
+        order_id: u64,
+        side: OrderSide,
+        amount: u64,
+        target_nav: u64,
+        token_percentages: Vec<u8>,
+        deposit: u64,
+    ) -> Result<()> {
+        require!(target_nav > 0, ErrorCode::InvalidAmount);
+        let now = Clock::get()?.unix_timestamp;
+        let etf = &ctx.accounts.etf;
+
+        let position = &mut ctx.accounts.position;
+        position.etf = etf.key();
+        position.investor = ctx.accounts.owner.key();
+        position.bump = ctx.bumps.position;
+
+        match side {
+            OrderSide::Buy => {
+                validate_buy(etf, amount, &token_percentages)?;
+
+                solana_program::program::invoke(
+                    &solana_program::system_instruction::transfer(
+                        ctx.accounts.owner.key,
+                        &ctx.accounts.order.key(),
+                        amount.checked_add(deposit).ok_or(ErrorCode::InvalidAmount)?,
+                    ),
+                    &[
+                        ctx.accounts.owner.to_account_info(),
+                        ctx.accounts.order.to_account_info(),
+                        ctx.accounts.system_program.to_account_info(),
+                    ],
+                )?;
+            }
+            OrderSide::Sell => {
+                require!(amount > 0, ErrorCode::InvalidAmount);
+                require!(etf.status == EtfStatus::Active, ErrorCode::EtfLiquidating);
+                require!(token_percentages.is_empty(), ErrorCode::InvalidTokenPercentages);
+                require!(position.shares >= amount, ErrorCode::InsufficientShares);
+                require!(
+                    now >= position.last_buy_ts.saturating_add(etf.min_hold_seconds),
+                    ErrorCode::HoldPeriodActive
+                );
+                position.shares = position.shares
+                    .checked_sub(amount)
+                    .ok_or(ErrorCode::InsufficientShares)?;
+
+                if deposit > 0 {
+                    solana_program::program::invoke(
+                        &solana_program::system_instruction::transfer(
+                            ctx.accounts.owner.key,
+                            &ctx.accounts.order.key(),
+                            deposit,
+                        ),
+                        &[
+                            ctx.accounts.owner.to_account_info(),
+                            ctx.accounts.order.to_account_info(),
+                            ctx.accounts.system_program.to_account_info(),
+                        ],
+                    )?;
+                }
+            }
+        }
+
+        let order = &mut ctx.accounts.order;
+        order.etf = etf.key();
+        order.owner = ctx.accounts.owner.key();
+        order.order_id = order_id;
+        order.side = side;
+        order.amount = amount;
+        order.target_nav = target_nav;
+        order.token_percentages = token_percentages;
+        order.created_ts = now;
+        order.bump = ctx.bumps.order;
+
+        emit!(LimitOrderPlacedEvent {
+            etf_address: order.etf,
+            owner: order.owner,
+            order_id,
+            side,
+            amount,
+            target_nav,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    // Permissionless: fill an order whose target the NAV has crossed. The
+    // order account is closed to its owner with what is left of its deposit.
+    // A buy fill on a keeper ETF needs the pending purchase account, whose
+    // rent the filler advances and the deposit repays.
+    // remaining_accounts: the basket's price feeds in basket order, which may
+    // be omitted while the ETF holds no tokens; then, for a sell while it
+    // does, a (vault ATA, owner token account) pair per basket token.
+    pub fn fill_limit_order<'info>(ctx: Context<'_, '_, 'info, 'info, FillLimitOrder<'info>>) -> Result<()> {
+        check_dev_wallet(&ctx.accounts.dev_wallet)?;
+        require!(
+            ctx.accounts.lister_account.key() == ctx.accounts.etf.lister,
+            ErrorCode::InvalidListerAccount
+        );
+
+        let basket_len = ctx.accounts.etf.token_addresses.len();
+        let (price_feeds, vault_pairs) = ctx.remaining_accounts.split_at(ctx.remaining_accounts.len().min(basket_len));
+        let order = &ctx.accounts.order;
+        let (owner, order_id, side, amount) = (order.owner, order.order_id, order.side, order.amount);
+        let nav = nav_per_share(
+            net_assets(&ctx.accounts.etf, price_feeds, Some(MAX_PRICE_AGE_SECONDS))?,
+            ctx.accounts.etf.total_supply,
+        );
+        require!(order_triggered(side, nav, order.target_nav), ErrorCode::OrderNotTriggered);
+        let order_info = ctx.accounts.order.to_account_info();
+        let order_rent = Rent::get()?.minimum_balance(order_info.data_len());
+
+        match side {
+            OrderSide::Buy => {
+                let token_percentages = order.token_percentages.clone();
+                validate_buy(&ctx.accounts.etf, amount, &token_percentages)?;
+                require!(
+                    ctx.accounts.etf.keeper.is_some() == ctx.accounts.pending_purchase.is_some(),
+                    ErrorCode::InvalidPendingPurchase
+                );
+                check_gate(
+                    &ctx.accounts.etf,
+                    &owner,
+                    ctx.accounts.gate_account.as_ref().map(|account| account.as_ref()),
+                )?;
+
+                let (creator_fee, dev_fee) = calculate_fees(amount);
+                let (lister_fee, parent_fee) = accrue_parent_fee(&mut ctx.accounts.etf, creator_fee)?;
+                **order_info.try_borrow_mut_lamports()? -= amount;
+                **ctx.accounts.etf.to_account_info().try_borrow_mut_lamports()? +=
+                    amount - creator_fee - dev_fee + parent_fee;
+                **ctx.accounts.lister_account.try_borrow_mut_lamports()? += lister_fee;
+                **ctx.accounts.dev_wallet.try_borrow_mut_lamports()? += dev_fee;
+
+                emit_buy_events(
+                    &ctx.accounts.etf,
+                    owner,
+                    ctx.accounts.lister_account.key(),
+                    amount,
+                    &token_percentages,
+                )?;
+                if let Some(pending) = ctx.accounts.pending_purchase.as_mut() {
+                    let bump = ctx.bumps.pending_purchase.ok_or(ErrorCode::InvalidPendingPurchase)?;
+                    open_pending_purchase(&mut ctx.accounts.etf, pending, owner, amount, &token_percentages, bump)?;
+                    let rent = pending.to_account_info().lamports();
+                    take_from_escrow(&order_info, order_rent, rent)?;
+                    **ctx.accounts.filler.to_account_info().try_borrow_mut_lamports()? += rent;
+                } else {
+                    mint_instant_buy(&mut ctx.accounts.etf, &mut ctx.accounts.position, amount)?;
+                }
+            }
+            OrderSide::Sell if ctx.accounts.etf.holdings.iter().all(|held| *held == 0) => {
+                pay_out_sale(
+                    &mut ctx.accounts.etf,
+                    &ctx.accounts.owner,
+                    &ctx.accounts.lister_account,
+                    &ctx.accounts.dev_wallet,
+                    amount,
+                    0,
+                )?;
+            }
+            OrderSide::Sell => {
+                let (_, fees) = redeem_in_kind(
+                    &mut ctx.accounts.etf,
+                    &ctx.accounts.owner,
+                    &ctx.accounts.token_program.to_account_info(),
+                    vault_pairs,
+                    price_feeds,
+                    amount,
+                    0,
+                )?;
+                pay_fees_from_escrow(
+                    &order_info,
+                    order_rent,
+                    &ctx.accounts.etf.to_account_info(),
+                    &ctx.accounts.lister_account,
+                    &ctx.accounts.dev_wallet,
+                    fees,
+                )?;
+            }
+        }
+
+        emit!(LimitOrderFilledEvent {
+            etf_address: ctx.accounts.etf.key(),
+            owner,
+            order_id,
+            side,
+            amount,
+            nav_per_share: nav,
+            filler: ctx.accounts.filler.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Close the order: buy orders refund their SOL, sell orders return their
+    // shares to the position (re-opened if a liquidation claim closed it).
+    // Works in any ETF state; buy orders even after the ETF is closed.
+    pub fn cancel_limit_order(ctx: Context<CancelLimitOrder>) -> Result<()> {
+        let order = &ctx.accounts.order;
+        if order.side == OrderSide::Sell {
+            let position = ctx.accounts.position.as_mut().ok_or(ErrorCode::OrderPositionRequired)?;
+            position.etf = order.etf;
+            position.investor = order.owner;
+            position.bump = ctx.bumps.position.ok_or(ErrorCode::OrderPositionRequired)?;
+            position.shares = position.shares
+                .checked_add(order.amount)
+                .ok_or(ErrorCode::InvalidAmount)?;
+        }
+
+        emit!(LimitOrderCancelledEvent {
+            etf_address: order.etf,
+            owner: order.owner,
+            order_id: order.order_id,
+            side: order.side,
+            amount: order.amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // ------------------------------------------------------------------------
+    // Exit triggers. An investor attaches stop-loss and take-profit NAV
+    // thresholds to their position; once the NAV computed on-chain crosses
+    // one, anyone may call trigger_exit to sell for them and earn the tip the
+    // trigger holds. NAV is priced like a limit order's, from the ETF's books
+    // and fresh feeds, so donations cannot fire a trigger. On a keeper ETF
+    // the sale is an in-kind redemption whose fees come out of the trigger's
+    // deposit.
+    // ------------------------------------------------------------------------
+
+    // Create or replace the position's trigger. `shares` is how much to sell
+    // when it fires; 0 sells the whole position. `deposit` lamports are held
+    // on top of the tip to pay the fees of an in-kind exit.
+    pub fn set_exit_trigger(
+        ctx: Context<SetExitTrigger>,
+        stop_loss_nav: u64,
+        take_profit_nav: u64,
+        shares: u64,
+        deposit: u64,
+    ) -> Result<()> {
+        require!(
+            stop_loss_nav > 0 || take_profit_nav > 0,
+            ErrorCode::InvalidExitTrigger
+        );
+        require!(
+            stop_loss_nav == 0 || take_profit_nav == 0 || stop_loss_nav < take_profit_nav,
+            ErrorCode::InvalidExitTrigger
+        );
+
+        // Hold rent, the crank tip and the deposit; a replaced trigger is
+        // topped up or refunded to match
+        let trigger_info = ctx.accounts.exit_trigger.to_account_info();
+        let funded = Rent::get()?
+            .minimum_balance(trigger_info.data_len())
+            .saturating_add(EXIT_CRANK_TIP_LAMPORTS)
+            .checked_add(deposit)
+            .ok_or(ErrorCode::InvalidAmount)?;
+        let excess = trigger_info.lamports().saturating_sub(funded);
+        if excess > 0 {
+            **trigger_info.try_borrow_mut_lamports()? -= excess;
+            **ctx.accounts.investor.to_account_info().try_borrow_mut_lamports()? += excess;
+        }
+        let shortfall = funded.saturating_sub(trigger_info.lamports());
+        if shortfall > 0 {
+            solana_program::program::invoke(
+                &solana_program::system_instruction::transfer(
+                    ctx.accounts.investor.key,
+                    &ctx.accounts.exit_trigger.key(),
+                    shortfall,
+                ),
+                &[
+                    ctx.accounts.investor.to_account_info(),
+                    ctx.accounts.exit_trigger.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+            )?;
+        }
+
+        let trigger = &mut ctx.accounts.exit_trigger;
+        trigger.etf = ctx.accounts.etf.key();
+        trigger.investor = ctx.accounts.investor.key();
+        trigger.stop_loss_nav = stop_loss_nav;
+        trigger.take_profit_nav = take_profit_nav;
+        trigger.shares = shares;
+        trigger.bump = ctx.bumps.exit_trigger;
+
+        emit!(ExitTriggerSetEvent {
+            etf_address: trigger.etf,
+            investor: trigger.investor,
+            stop_loss_nav,
+            take_profit_nav,
+            shares,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Permissionless: sell for the investor once a threshold is hit.
+    // remaining_accounts are one price feed per basket token, then the
+    // (vault, investor token account) pairs of an in-kind exit. NAV is read
+    // from the ETF's books at those feeds, so a caller cannot fire a trigger
+    // early. The sell is a sell_etf, or a redeem_to_tokens while the ETF
+    // holds tokens, hold period included. The trigger is closed; its tip
+    // goes to the caller, its rent and what is left of its deposit to the
+    // investor.
+    pub fn trigger_exit<'info>(ctx: Context<'_, '_, 'info, 'info, TriggerExit<'info>>) -> Result<()> {
+        check_dev_wallet(&ctx.accounts.dev_wallet)?;
+        require!(
+            ctx.accounts.lister_account.key() == ctx.accounts.etf.lister,
+            ErrorCode::InvalidListerAccount
+        );
+
+        let basket_len = ctx.accounts.etf.token_addresses.len();
+        let (price_feeds, vault_pairs) = ctx.remaining_accounts.split_at(ctx.remaining_accounts.len().min(basket_len));
+        let trigger = &ctx.accounts.exit_trigger;
+        let nav = nav_per_share(
+            net_assets(&ctx.accounts.etf, price_feeds, Some(MAX_PRICE_AGE_SECONDS))?,
+            ctx.accounts.etf.total_supply,
+        );
+        let kind = exit_triggered(nav, trigger.stop_loss_nav, trigger.take_profit_nav)
+            .ok_or(ErrorCode::ExitNotTriggered)?;
+
+        // Sell what was asked for, or everything left if the position shrank
+        let held = ctx.accounts.position.shares;
+        let shares = if trigger.shares == 0 { held } else { trigger.shares.min(held) };
+        require!(shares > 0, ErrorCode::InsufficientShares);
+
+        let exit_fee = hold_period_exit_fee(&ctx.accounts.etf, &ctx.accounts.position, shares)?;
+        let position = &mut ctx.accounts.position;
+        position.shares = position.shares
+            .checked_sub(shares)
+            .ok_or(ErrorCode::InsufficientShares)?;
+        let sol_amount = if ctx.accounts.etf.holdings.iter().all(|held| *held == 0) {
+            pay_out_sale(
+                &mut ctx.accounts.etf,
+                &ctx.accounts.investor,
+                &ctx.accounts.lister_account,
+                &ctx.accounts.dev_wallet,
+                shares,
+                exit_fee,
+            )?
+        } else {
+            let (sol_amount, fees) = redeem_in_kind(
+                &mut ctx.accounts.etf,
+                &ctx.accounts.investor,
+                &ctx.accounts.token_program.to_account_info(),
+                vault_pairs,
+                price_feeds,
+                shares,
+                exit_fee,
+            )?;
+            let trigger_info = ctx.accounts.exit_trigger.to_account_info();
+            let reserved = Rent::get()?
+                .minimum_balance(trigger_info.data_len())
+                .saturating_add(EXIT_CRANK_TIP_LAMPORTS);
+            pay_fees_from_escrow(
+                &trigger_info,
+                reserved,
+                &ctx.accounts.etf.to_account_info(),
+                &ctx.accounts.lister_account,
+                &ctx.accounts.dev_wallet,
+                fees,
+            )?;
+            sol_amount
+        };
+
+        **ctx.accounts.exit_trigger.to_account_info().try_borrow_mut_lamports()? -= EXIT_CRANK_TIP_LAMPORTS;
+        **ctx.accounts.caller.to_account_info().try_borrow_mut_lamports()? += EXIT_CRANK_TIP_LAMPORTS;
+
+        emit!(ExitTriggeredEvent {
+            etf_address: ctx.accounts.etf.key(),
+            investor: ctx.accounts.investor.key(),
+            caller: ctx.accounts.caller.key(),
+            kind,
+            nav_per_share: nav,
+            shares_sold: shares,
+            sol_amount,
+            tip: EXIT_CRANK_TIP_LAMPORTS,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Remove the trigger and refund its tip and rent. Works in any ETF state.
+    pub fn cancel_exit_trigger(ctx: Context<CancelExitTrigger>) -> Result<()> {
+        emit!(ExitTriggerCancelledEvent {
+            etf_address: ctx.accounts.exit_trigger.etf,
+            investor: ctx.accounts.investor.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // ------------------------------------------------------------------------
+    // NAV history. A ring buffer of NAV snapshots per ETF, so on-chain
+    // consumers (and leaderboards or PnL) have a price history to read,
+    // including a time-weighted average through get_nav_twap.
+    // ------------------------------------------------------------------------
+
+    // Create the ETF's NAV history. Permissionless; the payer only funds rent.
+    pub fn create_nav_history(ctx: Context<CreateNavHistory>) -> Result<()> {
+        let history = &mut ctx.accounts.nav_history;
+        history.etf = ctx.accounts.etf.key();
+        history.head = 0;
+        history.snapshots = Vec::with_capacity(NAV_HISTORY_LEN);
+        history.bump = ctx.bumps.nav_history;
+        Ok(())
+    }
+
+    // Permissionless: record the current NAV, at most once per
+    // NAV_SNAPSHOT_INTERVAL_SECONDS. The oldest snapshot is overwritten once
+    // the buffer is full.
+    // remaining_accounts: the basket's price feeds, in basket order, while
+    // the ETF holds tokens
+    pub fn snapshot_nav(ctx: Context<SnapshotNav>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let etf = &ctx.accounts.etf;
+        let history = &mut ctx.accounts.nav_history;
+        if let Some(last) = history.snapshots.iter().map(|s| s.timestamp).max() {
+            require!(
+                now >= last.saturating_add(NAV_SNAPSHOT_INTERVAL_SECONDS),
+                ErrorCode::NavSnapshotTooSoon
+            );
+        }
+
+        let total_value = net_assets(etf, ctx.remaining_accounts, Some(MAX_PRICE_AGE_SECONDS))?;
+        let snapshot = NavSnapshot {
+            timestamp: now,
+            nav_per_share: nav_per_share(total_value, etf.total_supply),
+            total_value,
+        };
+        let head = history.head as usize;
+        if history.snapshots.len() < NAV_HISTORY_LEN {
+            history.snapshots.push(snapshot);
+        } else {
+            history.snapshots[head] = snapshot;
+        }
+        history.head = ((head + 1) % NAV_HISTORY_LEN) as u16;
+
+        emit!(NavSnapshotEvent {
+            etf_address: etf.key(),
+            nav_per_share: snapshot.nav_per_share,
+            total_value,
+            total_supply: etf.total_supply,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    // ------------------------------------------------------------------------
+    // Leaderboard. Each epoch ranks ETFs by the NAV return their histories
+    // recorded over it. Anyone may submit an ETF once the epoch has ended.
+    // Snapshots record booked NAV, which donations cannot move, and each
+    // boundary NAV is time-weighted over a window the history must cover
+    // without gaps, so the timing of single snapshots barely matters.
+    // ------------------------------------------------------------------------
+
+    // Create the leaderboard of an epoch. Permissionless; the payer only
+    // funds rent.
+    pub fn create_leaderboard_epoch(ctx: Context<CreateLeaderboardEpoch>, epoch_id: u64) -> Result<()> {
+        let start_ts = i64::try_from(epoch_id)
+            .ok()
+            .and_then(|id| id.checked_mul(LEADERBOARD_EPOCH_SECONDS))
+            .and_then(|start| start.checked_add(LEADERBOARD_EPOCH_SECONDS).map(|_| start))
+            .ok_or(ErrorCode::InvalidAmount)?;
+
+        let leaderboard = &mut ctx.accounts.leaderboard;
+        leaderboard.epoch_id = epoch_id;
+        leaderboard.start_ts = start_ts;
+        leaderboard.end_ts = start_ts + LEADERBOARD_EPOCH_SECONDS;
+        leaderboard.entries = Vec::with_capacity(LEADERBOARD_SIZE);
+        leaderboard.bump = ctx.bumps.leaderboard;
+        Ok(())
+    }
+
+    // Permissionless: rank the ETF by its NAV return over the epoch, from the
+    // boundary NAVs of its history (see boundary_nav). Open for
+    // LEADERBOARD_SUBMISSION_SECONDS after the epoch ends. Fails if the return
+    // does not make the top LEADERBOARD_SIZE.
+    pub fn submit_epoch_return(ctx: Context<SubmitEpochReturn>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let leaderboard = &mut ctx.accounts.leaderboard;
+        require!(now >= leaderboard.end_ts, ErrorCode::EpochNotEnded);
+        require!(
+            now < leaderboard.end_ts + LEADERBOARD_SUBMISSION_SECONDS,
+            ErrorCode::SubmissionsClosed
+        );
+        let etf_key = ctx.accounts.etf.key();
+        require!(
+            leaderboard.entries.iter().all(|entry| entry.etf != etf_key),
+            ErrorCode::EtfAlreadyRanked
+        );
+
+        let snapshots = &ctx.accounts.nav_history.snapshots;
+        let (start_nav, start) =
+            boundary_nav(snapshots, leaderboard.start_ts).ok_or(ErrorCode::MissingEpochSnapshot)?;
+        let (end_nav, _) = boundary_nav(snapshots, leaderboard.end_ts).ok_or(ErrorCode::MissingEpochSnapshot)?;
+        require!(
+            start.total_value >= LEADERBOARD_MIN_NET_ASSETS,
+            ErrorCode::BelowLeaderboardMinimum
+        );
+        let return_bps = nav_return_bps(start_nav, end_nav).ok_or(ErrorCode::InvalidAmount)?;
+
+        let rank = leaderboard_rank(&leaderboard.entries, return_bps);
+        require!(rank < LEADERBOARD_SIZE, ErrorCode::ReturnNotRanked);
+        let lister = ctx.accounts.etf.lister;
+        leaderboard.entries.insert(
+            rank,
+            LeaderboardEntry {
+                etf: etf_key,
+                lister,
+                return_bps,
+                start_nav,
+                end_nav,
+            },
+        );
+        leaderboard.entries.truncate(LEADERBOARD_SIZE);
+
+        emit!(EpochReturnSubmittedEvent {
+            epoch_id: leaderboard.epoch_id,
+            etf_address: etf_key,
+            lister,
+            return_bps,
+            rank: rank as u8,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    // ------------------------------------------------------------------------
+    // Treasury and creator rewards. Dev fees accrue in the TREASURY PDA.
+    // A configurable share of them funds a rewards pool that is paid to the
+    // listers on each finalized leaderboard; the rest is withdrawn to
+    // DEV_WALLET.
+    // ------------------------------------------------------------------------
+
+    // Create the treasury or change the rewards share (governance only).
+    // Fees collected so far are split at the old share first.
+    pub fn configure_treasury(ctx: Context<ConfigureTreasury>, rewards_share_bps: u16) -> Result<()> {
+        require!(
+            ctx.accounts.authority.key() == GOVERNANCE_AUTHORITY,
+            ErrorCode::Unauthorized
+        );
+        require!(
+            rewards_share_bps <= MAX_REWARDS_SHARE_BPS,
+            ErrorCode::InvalidRewardsShare
+        );
+
+        let treasury = &mut ctx.accounts.treasury;
+        treasury.bump = ctx.bumps.treasury;
+        sweep_treasury(treasury)?;
+        treasury.rewards_share_bps = rewards_share_bps;
+
+        emit!(TreasuryConfiguredEvent {
+            rewards_share_bps,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Permissionless: book the dev fees collected since the last sweep, and
+    // add `amount` lamports from the funder to the rewards pool (may be 0).
+    pub fn fund_rewards(ctx: Context<FundRewards>, amount: u64) -> Result<()> {
+        let (fees_to_rewards, fees_to_dev) = sweep_treasury(&mut ctx.accounts.treasury)?;
+
+        if amount > 0 {
+            solana_program::program::invoke(
+                &solana_program::system_instruction::transfer(
+                    ctx.accounts.funder.key,
+                    &ctx.accounts.treasury.key(),
+                    amount,
+                ),
+                &[
+                    ctx.accounts.funder.to_account_info(),
+                    ctx.accounts.treasury.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+            )?;
+        }
+        let treasury = &mut ctx.accounts.treasury;
+        treasury.rewards_pool = treasury
+            .rewards_pool
+            .checked_add(amount)
+            .ok_or(ErrorCode::InvalidAmount)?;
+
+        emit!(RewardsFundedEvent {
+            funder: ctx.accounts.funder.key(),
+            amount,
+            fees_to_rewards,
+            fees_to_dev,
+            rewards_pool: treasury.rewards_pool,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Permissionless: pay the dev share of the treasury to DEV_WALLET
+    pub fn withdraw_dev_fees(ctx: Context<WithdrawDevFees>) -> Result<()> {
+        let treasury = &mut ctx.accounts.treasury;
+        sweep_treasury(treasury)?;
+        let amount = treasury.dev_balance;
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        treasury.dev_balance = 0;
+
+        **treasury.to_account_info().try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.dev_wallet.try_borrow_mut_lamports()? += amount;
+
+        emit!(DevFeesWithdrawnEvent {
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Permissionless once submissions have closed: allocate the whole
+    // rewards pool to the epoch's ranked listers (see creator_rewards). An
+    // epoch without entries leaves the pool to the next one.
+    pub fn finalize_epoch(ctx: Context<FinalizeEpoch>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let leaderboard = &ctx.accounts.leaderboard;
+        require!(
+            now >= leaderboard.end_ts + LEADERBOARD_SUBMISSION_SECONDS,
+            ErrorCode::SubmissionsOpen
+        );
+
+        let treasury = &mut ctx.accounts.treasury;
+        sweep_treasury(treasury)?;
+        let amounts = creator_rewards(treasury.rewards_pool, leaderboard.entries.len());
+        let total_rewards: u64 = amounts.iter().sum();
+        treasury.rewards_pool = treasury
+            .rewards_pool
+            .checked_sub(total_rewards)
+            .ok_or(ErrorCode::InvalidAmount)?;
+        treasury.rewards_owed = treasury
+            .rewards_owed
+            .checked_add(total_rewards)
+            .ok_or(ErrorCode::InvalidAmount)?;
+
+        let epoch_rewards = &mut ctx.accounts.epoch_rewards;
+        epoch_rewards.epoch_id = leaderboard.epoch_id;
+        epoch_rewards.rewards = leaderboard
+            .entries
+            .iter()
+            .zip(amounts)
+            .map(|(entry, amount)| CreatorReward {
+                etf: entry.etf,
+                lister: entry.lister,
+                amount,
+                claimed: false,
+            })
+            .collect();
+        epoch_rewards.bump = ctx.bumps.epoch_rewards;
+
+        emit!(EpochFinalizedEvent {
+            epoch_id: leaderboard.epoch_id,
+            total_rewards,
+            ranked: leaderboard.entries.len() as u8,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    // The lister ranked at `rank` (0 = best) claims their reward
+    pub fn claim_creator_reward(ctx: Context<ClaimCreatorReward>, rank: u8) -> Result<()> {
+        let epoch_rewards = &mut ctx.accounts.epoch_rewards;
+        let reward = epoch_rewards
+            .rewards
+            .get_mut(rank as usize)
+            .ok_or(ErrorCode::InvalidAmount)?;
+        require!(reward.lister == ctx.accounts.lister.key(), ErrorCode::Unauthorized);
+        require!(!reward.claimed, ErrorCode::RewardAlreadyClaimed);
+        reward.claimed = true;
+        let amount = reward.amount;
+        let etf_address = reward.etf;
+
+        let treasury = &mut ctx.accounts.treasury;
+        treasury.rewards_owed = treasury
+            .rewards_owed
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InvalidAmount)?;
+        **treasury.to_account_info().try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.lister.to_account_info().try_borrow_mut_lamports()? += amount;
+
+        emit!(CreatorRewardClaimedEvent {
+            epoch_id: epoch_rewards.epoch_id,
+            etf_address,
+            lister: ctx.accounts.lister.key(),
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // ------------------------------------------------------------------------
+    // Wrapped shares. A position's shares can be moved into the ETF's share
+    // mint and back, so other programs can hold, transfer and lend them.
+    // Supply is unchanged either way; only the form the shares are held in.
+    // ------------------------------------------------------------------------
+
+    // Create the ETF's share mint. Permissionless; the payer only funds rent.
+    // The mint is a Token-2022 mint carrying the ETF's name, symbol and uri in
+    // its token-metadata extension, kept in step by update_metadata.
+    pub fn create_share_mint(ctx: Context<CreateShareMint>) -> Result<()> {
+        let etf = &ctx.accounts.etf;
+        let share_mint = ctx.accounts.share_mint.to_account_info();
+        let metadata = TokenMetadata {
+            update_authority: OptionalNonZeroPubkey::try_from(Some(etf.key()))?,
+            mint: share_mint.key(),
+            name: etf.name.clone(),
+            symbol: etf.symbol.clone(),
+            uri: etf.uri.clone(),
+            additional_metadata: Vec::new(),
+        };
+        fund_share_metadata(
+            &share_mint,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &metadata,
+        )?;
+
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"etf",
+            etf.lister.as_ref(),
+            &[etf.etf_index],
+            &[etf.bump],
+        ]];
+        token_interface::token_metadata_initialize(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TokenMetadataInitialize {
+                    program_id: ctx.accounts.token_program.to_account_info(),
+                    metadata: share_mint.clone(),
+                    update_authority: etf.to_account_info(),
+                    mint_authority: etf.to_account_info(),
+                    mint: share_mint,
+                },
+                signer_seeds,
+            ),
+            metadata.name,
+            metadata.symbol,
+            metadata.uri,
+        )?;
+
+        emit!(ShareMintCreatedEvent {
+            etf_address: ctx.accounts.etf.key(),
+            share_mint: ctx.accounts.share_mint.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    // Move `amount` shares out of the owner's position into SPL share tokens
+    pub fn wrap_shares(ctx: Context<WrapShares>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+
+        let etf = &ctx.accounts.etf;
+        require!(etf.status == EtfStatus::Active, ErrorCode::EtfLiquidating);
+
+        let position = &mut ctx.accounts.position;
+        position.shares = position.shares
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientShares)?;
+
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"etf",
+            etf.lister.as_ref(),
+            &[etf.etf_index],
+            &[etf.bump],
+        ]];
+        token_interface::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.share_mint.to_account_info(),
+                    to: ctx.accounts.destination.to_account_info(),
+                    authority: etf.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount,
+        )?;
+
+        emit!(SharesWrappedEvent {
+            etf_address: etf.key(),
+            owner: ctx.accounts.owner.key(),
+            destination: ctx.accounts.destination.key(),
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Burn `amount` share tokens and credit them to `recipient`'s position.
+    // Allowed during liquidation so token holders can still claim.
+    pub fn unwrap_shares(ctx: Context<UnwrapShares>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+
+        token_interface::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.share_mint.to_account_info(),
+                    from: ctx.accounts.source.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let position = &mut ctx.accounts.recipient_position;
+        // Unwrapped shares count as just bought, so passing them through the
+        // share mint cannot skip a hold period. A third party must not be able
+        // to restart someone else's hold, so a recipient that already holds
+        // shares keeps its own timestamp
+        if ctx.accounts.recipient.key() == ctx.accounts.owner.key() || position.shares == 0 {
+            position.last_buy_ts = Clock::get()?.unix_timestamp;
+        }
+        position.etf = ctx.accounts.etf.key();
+        position.investor = ctx.accounts.recipient.key();
+        position.bump = ctx.bumps.recipient_position;
+        position.shares = position.shares
+            .checked_add(amount)
+            .ok_or(ErrorCode::InvalidAmount)?;
+
+        emit!(SharesUnwrappedEvent {
+            etf_address: ctx.accounts.etf.key(),
+            owner: ctx.accounts.owner.key(),
+            recipient: ctx.accounts.recipient.key(),
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // ------------------------------------------------------------------------
+    // Price feeds
+    // ------------------------------------------------------------------------
+
+    // Publish the price of a basket token (governance only). The feed is
+    // created on first use.
+    pub fn set_price(ctx: Context<SetPrice>, price: u64) -> Result<()> {
+        require!(
+            ctx.accounts.authority.key() == GOVERNANCE_AUTHORITY,
+            ErrorCode::Unauthorized
+        );
+        require!(price > 0, ErrorCode::InvalidAmount);
+
+        let now = Clock::get()?.unix_timestamp;
+        let feed = &mut ctx.accounts.price_feed;
+        feed.mint = ctx.accounts.mint.key();
+        feed.price = price;
+        feed.updated_ts = now;
+        feed.bump = ctx.bumps.price_feed;
+
+        emit!(PriceUpdatedEvent {
+            token_address: feed.mint,
+            price,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    // ------------------------------------------------------------------------
+    // Views. Read-only; results are Borsh-encoded into return data, so clients
+    // simulate them and other programs read them back after a CPI.
+    // ------------------------------------------------------------------------
+
+    // What buy_etf would charge and mint for this amount and split. For a
+    // keeper ETF shares_out is an estimate at today's NAV; the count minted
+    // depends on what settlement delivers.
+    // remaining_accounts: the basket's price feeds, in basket order, while
+    // the ETF holds tokens; any age is accepted
+    pub fn quote_buy(
+        ctx: Context<QuoteETF>,
+        sol_amount: u64,
+        token_percentages: Vec<u8>,
+    ) -> Result<BuyQuote> {
+        let etf = &ctx.accounts.etf;
+        validate_buy(etf, sol_amount, &token_percentages)?;
+
+        let (creator_fee, dev_fee) = calculate_fees(sol_amount);
+        let sol_after_fees = sol_amount - creator_fee - dev_fee;
+        let net_assets = net_assets(etf, ctx.remaining_accounts, None)?;
+        Ok(BuyQuote {
+            sol_amount,
+            creator_fee,
+            dev_fee,
+            sol_after_fees,
+            shares_out: shares_for_value(sol_after_fees, etf.total_supply, net_assets),
+            allocations: token_percentages.iter()
+                .map(|percentage| token_allocation(sol_after_fees, *percentage))
+                .collect(),
+            pending: etf.keeper.is_some(),
+            nav_per_share: nav_per_share(net_assets, etf.total_supply),
+        })
+    }
+
+    // What sell_etf would pay out for `shares`, ignoring any hold period.
+    // `liquidity` is the SOL sellers can currently draw on. An ETF holding
+    // basket tokens cannot be sold out of, only redeemed in kind.
+    pub fn quote_sell(ctx: Context<QuoteETF>, shares: u64) -> Result<SellQuote> {
+        let etf = &ctx.accounts.etf;
+        require!(shares > 0, ErrorCode::InvalidAmount);
+        require!(etf.status == EtfStatus::Active, ErrorCode::EtfLiquidating);
+        require!(etf.total_supply >= shares, ErrorCode::InsufficientFunds);
+        require!(etf.holdings.iter().all(|held| *held == 0), ErrorCode::RedeemInKind);
+
+        // Shares redeem pro rata for the booked SOL, before fees
+        let sol_value = liquidation_payout(shares, etf.total_supply, etf.sol_assets);
+        let (creator_fee, dev_fee) = calculate_fees(sol_value);
+        Ok(SellQuote {
+            shares_in: shares,
+            sol_value,
+            creator_fee,
+            dev_fee,
+            sol_out: sol_value - creator_fee - dev_fee,
+            liquidity: etf.sol_assets,
+            nav_per_share: nav_per_share(etf.sol_assets, etf.total_supply),
+        })
+    }
+
+    // remaining_accounts: as for quote_buy
+    pub fn get_nav(ctx: Context<QuoteETF>) -> Result<NavQuote> {
+        let etf = &ctx.accounts.etf;
+        let net_assets = net_assets(etf, ctx.remaining_accounts, None)?;
+        Ok(NavQuote {
+            total_supply: etf.total_supply,
+            net_assets,
+            pending_sol: etf.pending_sol,
+            nav_per_share: nav_per_share(net_assets, etf.total_supply),
+            status: etf.status,
+        })
+    }
+
+    // Time-weighted average NAV over the last `window_seconds`, from the
+    // ETF's NAV history
+    pub fn get_nav_twap(ctx: Context<QuoteNavTwap>, window_seconds: i64) -> Result<TwapQuote> {
+        require!(window_seconds > 0, ErrorCode::InvalidAmount);
+        let now = Clock::get()?.unix_timestamp;
+        let history = &ctx.accounts.nav_history;
+        let (nav_per_share, covered_seconds) = time_weighted_nav(&history.snapshots, now, window_seconds)
+            .ok_or(ErrorCode::NoNavHistory)?;
+        Ok(TwapQuote {
+            nav_per_share,
+            window_seconds,
+            covered_seconds,
+            snapshots: history.snapshots.len() as u16,
+        })
+    }
+
+    // Remove claim_fees - fees are now sent automatically
+    // Keeping close_etf for cleanup
+
+    // Full wind-down: basket vaults are emptied to the lister and closed, the
+    // registry entry is tombstoned and the ETF account (rent + any SOL dust)
+    // is closed to the lister by Anchor's `close` constraint.
+    // remaining_accounts: (vault ATA, lister token account) per basket token, in order
+    pub fn close_etf<'info>(ctx: Context<'_, '_, 'info, 'info, CloseETF<'info>>) -> Result<()> {
+        require!(
+            ctx.accounts.lister.key() == ctx.accounts.etf.lister,
+            ErrorCode::Unauthorized
+        );
+
+        require!(
+            ctx.accounts.etf.total_supply == 0,
+            ErrorCode::CannotCloseWithSupply
+        );
+
+        wind_down_etf(
+            &ctx.accounts.etf,
+            &mut ctx.accounts.registry,
+            &mut ctx.accounts.registry_page,
+            &ctx.accounts.share_mint,
+            &ctx.accounts.lister.to_account_info(),
+            &ctx.accounts.token_program,
+            ctx.remaining_accounts,
+        )?;
+
+        Ok(())
+    }
+
+    // ------------------------------------------------------------------------
+    // Liquidation (forced wind-down)
+    // ------------------------------------------------------------------------
+
+    // Put the ETF into liquidation. The lister may do this at any time;
+    // governance may only do it once the lister has been inactive. Buys and
+    // sells stop at once, but vaults are only converted to SOL after
+    // LIQUIDATION_NOTICE_SECONDS, so holders may take their tokens in kind.
+    pub fn begin_liquidation(ctx: Context<BeginLiquidation>) -> Result<()> {
+        let etf = &mut ctx.accounts.etf;
+        let authority = ctx.accounts.authority.key();
+        let now = Clock::get()?.unix_timestamp;
+
+        require!(etf.status == EtfStatus::Active, ErrorCode::EtfLiquidating);
+
+        if authority != etf.lister {
+            require!(authority == GOVERNANCE_AUTHORITY, ErrorCode::Unauthorized);
+            require!(
+                now.saturating_sub(etf.lister_last_active_ts) >= LISTER_INACTIVITY_SECONDS,
+                ErrorCode::ListerStillActive
+            );
+        }
+
+        etf.status = EtfStatus::Liquidating;
+        etf.liquidation_started_ts = now;
+
+        emit!(LiquidationStartedEvent {
+            etf_address: etf.key(),
+            initiator: authority,
+            total_supply: etf.total_supply,
+            notice_ends_ts: now.saturating_add(LIQUIDATION_NOTICE_SECONDS),
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    // Swap basket tokens out of a vault for SOL once the liquidation notice
+    // has passed. The authority (lister or governance) receives
+    // `token_amount` from the vault and pays `sol_amount` lamports into the
+    // ETF for holders to claim; `sol_amount` must be at least
+    // liquidation_min_out at a fresh feed price.
+    pub fn liquidate_vault(
+        ctx: Context<LiquidateVault>,
+        token_amount: u64,
+        sol_amount: u64,
+    ) -> Result<()> {
+        require!(token_amount > 0, ErrorCode::InvalidAmount);
+
+        let etf = &ctx.accounts.etf;
+        let authority = ctx.accounts.authority.key();
+        let mint = ctx.accounts.vault.mint;
+        let now = Clock::get()?.unix_timestamp;
+        require!(etf.status == EtfStatus::Liquidating, ErrorCode::EtfNotLiquidating);
+        require!(
+            authority == etf.lister || authority == GOVERNANCE_AUTHORITY,
+            ErrorCode::Unauthorized
+        );
+        require!(
+            now >= etf.liquidation_started_ts.saturating_add(LIQUIDATION_NOTICE_SECONDS),
+            ErrorCode::LiquidationNoticePending
+        );
+        let index = etf
+            .token_addresses
+            .iter()
+            .position(|token| *token == mint)
+            .ok_or(ErrorCode::InvalidVaultAccount)?;
+        require!(
+            ctx.accounts.vault.key() == get_associated_token_address(&etf.key(), &mint),
+            ErrorCode::InvalidVaultAccount
+        );
+        let price = read_price(&ctx.accounts.price_feed, &mint, Some(MAX_PRICE_AGE_SECONDS))?;
+        require!(
+            sol_amount >= liquidation_min_out(token_amount, price),
+            ErrorCode::LiquidationBelowPrice
+        );
+
+        // SOL proceeds from the authority into the ETF
+        solana_program::program::invoke(
+            &solana_program::system_instruction::transfer(
+                ctx.accounts.authority.key,
+                &etf.key(),
+                sol_amount,
+            ),
+            &[
+                ctx.accounts.authority.to_account_info(),
+                etf.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        // Vault tokens out to the authority, signed by the ETF PDA
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"etf",
+            etf.lister.as_ref(),
+            &[etf.etf_index],
+            &[etf.bump],
+        ]];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.vault.to_account_info(),
+                    to: ctx.accounts.destination.to_account_info(),
+                    authority: etf.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            token_amount,
+        )?;
+
+        let etf = &mut ctx.accounts.etf;
+        etf.holdings[index] = etf.holdings[index].saturating_sub(token_amount);
+        etf.sol_assets = etf.sol_assets.checked_add(sol_amount).ok_or(ErrorCode::InvalidAmount)?;
+
+        emit!(VaultLiquidatedEvent {
+            etf_address: etf.key(),
+            token_address: mint,
+            token_amount,
+            sol_amount,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    // Holder claims their pro-rata share of the ETF's booked SOL and of
+    // every vault's balance, and burns their whole position. Once an ETF is
+    // liquidating, vault balances belong to the holders whether booked or
+    // not, so the last claimer empties the vaults. No trading fees are
+    // charged on claims.
+    // remaining_accounts: (vault ATA, investor token account) per basket
+    // token, in order, after token_program. A vault never created holds
+    // nothing to pay. Callers from before in-kind claims pass neither; that
+    // is only accepted while the ETF has no booked holdings.
+    pub fn claim_liquidation<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClaimLiquidation<'info>>,
+    ) -> Result<()> {
+        let etf = &ctx.accounts.etf;
+        require!(etf.status == EtfStatus::Liquidating, ErrorCode::EtfNotLiquidating);
+        let sol_only = ctx.remaining_accounts.is_empty() && etf.holdings.iter().all(|held| *held == 0);
+        require!(
+            sol_only || ctx.remaining_accounts.len() == 2 * etf.token_addresses.len(),
+            ErrorCode::InvalidVaultAccount
+        );
+
+        let shares = ctx.accounts.position.shares;
+        require!(shares > 0, ErrorCode::InsufficientShares);
+
+        let timestamp = Clock::get()?.unix_timestamp;
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"etf",
+            etf.lister.as_ref(),
+            &[etf.etf_index],
+            &[etf.bump],
+        ]];
+
+        let mut paid = Vec::with_capacity(etf.token_addresses.len());
+        for (mint, pair) in etf.token_addresses.iter().zip(ctx.remaining_accounts.chunks(2)) {
+            let balance = basket_vault(etf, mint, &pair[0])?.map_or(0, |vault| vault.amount);
+            let amount = liquidation_payout(shares, etf.total_supply, balance);
+            paid.push(amount);
+            if amount == 0 {
+                continue;
+            }
+            let destination = Account::<TokenAccount>::try_from(&pair[1])?;
+            require!(
+                destination.mint == *mint && destination.owner == ctx.accounts.investor.key(),
+                ErrorCode::InvalidVaultAccount
+            );
+            let token_program = ctx.accounts.token_program.as_ref().ok_or(ErrorCode::InvalidVaultAccount)?;
+            token::transfer(
+                CpiContext::new_with_signer(
+                    token_program.to_account_info(),
+                    Transfer {
+                        from: pair[0].clone(),
+                        to: pair[1].clone(),
+                        authority: etf.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                amount,
+            )?;
+
+            emit!(TokenRedeemedEvent {
+                etf_address: etf.key(),
+                investor: ctx.accounts.investor.key(),
+                token_address: *mint,
+                amount,
+                timestamp,
+            });
+        }
+
+        let payout = liquidation_payout(shares, etf.total_supply, etf.sol_assets);
+        let etf = &mut ctx.accounts.etf;
+        for (held, amount) in etf.holdings.iter_mut().zip(paid) {
+            *held = held.saturating_sub(amount);
+        }
+        etf.sol_assets -= payout;
+
+        **etf.to_account_info().try_borrow_mut_lamports()? -= payout;
+        **ctx.accounts.investor.to_account_info().try_borrow_mut_lamports()? += payout;
+
+        etf.total_supply = etf.total_supply
+            .checked_sub(shares)
+            .ok_or(ErrorCode::InvalidAmount)?;
+
+        emit!(LiquidationClaimEvent {
+            etf_address: etf.key(),
+            investor: ctx.accounts.investor.key(),
+            shares_burned: shares,
+            sol_amount: payout,
+            timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Permissionless close once every holder has claimed. The last claim
+    // empties the vaults; rent, dust and tokens sent after it go to the
+    // lister, who does not need to sign.
+    // remaining_accounts: (vault ATA, lister token account) per basket token, in order
+    pub fn close_liquidated_etf<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CloseLiquidatedETF<'info>>,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.etf.status == EtfStatus::Liquidating,
+            ErrorCode::EtfNotLiquidating
+        );
+        require!(
+            ctx.accounts.etf.total_supply == 0,
+            ErrorCode::CannotCloseWithSupply
+        );
+
+        wind_down_etf(
+            &ctx.accounts.etf,
+            &mut ctx.accounts.registry,
+            &mut ctx.accounts.registry_page,
+            &ctx.accounts.share_mint,
+            &ctx.accounts.lister.to_account_info(),
+            &ctx.accounts.token_program,
+            ctx.remaining_accounts,
+        )?;
+
+        Ok(())
+    }
+}
+}
+
+pub use program_module::*;
+
+#[derive(Accounts)]
+#[instruction(etf_index: u8)]
+pub struct InitializeETF<'info> {
+    #[account(
+        init,
+        payer = lister,
+        space = ETF_SPACE,
+        seeds = [b"etf", lister.key().as_ref(), &[etf_index]],
+        bump
+    )]
+    pub etf: Account<'info, ETF>,
+    #[account(
+        init_if_needed,
+        payer = lister,
+        space = 8 + 8 + 8 + 1,
+        seeds = [b"registry"],
+        bump
+    )]
+    pub registry: Account<'info, Registry>,
+    #[account(
+        init_if_needed,
+        payer = lister,
+        space = 8 + 4 + (4 + 33 * REGISTRY_PAGE_SIZE as usize) + 1,
+        seeds = [b"registry_page".as_ref(), &registry_page_index(registry.etf_count).to_le_bytes()],
+        bump
+    )]
+    pub registry_page: Account<'info, RegistryPage>,
+    #[account(mut)]
+    pub lister: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateETF<'info> {
+    /// CHECK: A legacy ETF account, decoded by migrate_etf
+    #[account(
+        mut,
+        owner = crate::ID,
+        constraint = etf.data_len() == LEGACY_ETF_SPACE @ ErrorCode::AlreadyMigrated,
+    )]
+    pub etf: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + 8 + 8 + 1,
+        seeds = [b"registry"],
+        bump
+    )]
+    pub registry: Account<'info, Registry>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + 4 + (4 + 33 * REGISTRY_PAGE_SIZE as usize) + 1,
+        seeds = [b"registry_page".as_ref(), &registry_page_index(registry.etf_count).to_le_bytes()],
+        bump
+    )]
+    pub registry_page: Account<'info, RegistryPage>,
+    // Anyone; pays the rent for the larger account
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RestoreLegacyPosition<'info> {
+    #[account(mut)]
+    pub etf: Account<'info, ETF>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = POSITION_SPACE,
+        seeds = [b"position", etf.key().as_ref(), investor.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+    /// CHECK: Any wallet; only the key seeds its position
+    pub investor: UncheckedAccount<'info>,
+    // Governance; pays the position's rent
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(etf_index: u8)]
+pub struct ForkETF<'info> {
+    #[account(
+        init,
+        payer = lister,
+        space = ETF_SPACE,
+        seeds = [b"etf", lister.key().as_ref(), &[etf_index]],
+        bump
+    )]
+    pub etf: Account<'info, ETF>,
+    pub parent: Account<'info, ETF>,
+    #[account(
+        init_if_needed,
+        payer = lister,
+        space = 8 + 8 + 8 + 1,
+        seeds = [b"registry"],
+        bump
+    )]
+    pub registry: Account<'info, Registry>,
+    #[account(
+        init_if_needed,
+        payer = lister,
+        space = 8 + 4 + (4 + 33 * REGISTRY_PAGE_SIZE as usize) + 1,
+        seeds = [b"registry_page".as_ref(), &registry_page_index(registry.etf_count).to_le_bytes()],
+        bump
+    )]
+    pub registry_page: Account<'info, RegistryPage>,
+    #[account(mut)]
+    pub lister: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimParentFees<'info> {
+    #[account(mut)]
+    pub etf: Account<'info, ETF>,
+    /// CHECK: Validated against etf.parent_lister
+    #[account(mut, address = etf.parent_lister @ ErrorCode::InvalidListerAccount)]
+    pub parent_lister: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct BuyETF<'info> {
+    #[account(mut)]
+    pub etf: Account<'info, ETF>,
+    #[account(
+        init_if_needed,
+        payer = investor,
+        space = POSITION_SPACE,
+        seeds = [b"position", etf.key().as_ref(), investor.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+    // A wallet, or a system-owned PDA signed for by a calling program
+    #[account(mut)]
+    pub investor: Signer<'info>,
+    /// CHECK: This is the lister's account - validated against etf.lister
+    #[account(mut)]
+    pub lister_account: AccountInfo<'info>,
+    /// CHECK: Receives the dev fee - validated against the TREASURY constant
+    #[account(mut)]
+    pub dev_wallet: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+    // Required when the ETF has a keeper, omitted otherwise
+    #[account(
+        init,
+        payer = investor,
+        space = PENDING_PURCHASE_SPACE,
+        seeds = [b"pending", etf.key().as_ref(), &etf.purchase_count.to_le_bytes()],
+        bump
+    )]
+    pub pending_purchase: Option<Account<'info, PendingPurchase>>,
+    /// CHECK: Required when the ETF is gated - validated against etf.gate by check_gate
+    pub gate_account: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct SettlePurchase<'info> {
+    #[account(mut)]
+    pub etf: Account<'info, ETF>,
+    #[account(
+        mut,
+        close = investor,
+        seeds = [b"pending", etf.key().as_ref(), &pending_purchase.purchase_id.to_le_bytes()],
+        bump = pending_purchase.bump,
+        has_one = etf,
+        has_one = investor,
+    )]
+    pub pending_purchase: Account<'info, PendingPurchase>,
+    #[account(
+        mut,
+        seeds = [b"position", etf.key().as_ref(), investor.key().as_ref()],
+        bump = position.bump,
+        has_one = etf,
+        has_one = investor,
+    )]
+    pub position: Account<'info, Position>,
+    /// CHECK: Receives the pending account's rent - validated by has_one on pending_purchase
+    #[account(mut)]
+    pub investor: AccountInfo<'info>,
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct RefundPendingPurchase<'info> {
+    #[account(mut)]
+    pub etf: Account<'info, ETF>,
+    #[account(
+        mut,
+        close = investor,
+        seeds = [b"pending", etf.key().as_ref(), &pending_purchase.purchase_id.to_le_bytes()],
+        bump = pending_purchase.bump,
+        has_one = etf,
+        has_one = investor,
+    )]
+    pub pending_purchase: Account<'info, PendingPurchase>,
+    #[account(mut)]
+    pub investor: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetKeeper<'info> {
+    #[account(mut, has_one = lister)]
+    pub etf: Account<'info, ETF>,
+    pub lister: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetGate<'info> {
+    #[account(mut, has_one = lister)]
+    pub etf: Account<'info, ETF>,
+    pub lister: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetBuyLimits<'info> {
+    #[account(mut, has_one = lister)]
+    pub etf: Account<'info, ETF>,
+    pub lister: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetHoldPeriod<'info> {
+    #[account(mut, has_one = lister)]
+    pub etf: Account<'info, ETF>,
+    pub lister: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyAllowlist<'info> {
+    pub etf: Account<'info, ETF>,
+    #[account(
+        init_if_needed,
+        payer = investor,
+        space = 8 + 32 + 32 + 32 + 1,
+        seeds = [b"allowlist", etf.key().as_ref(), investor.key().as_ref()],
+        bump
+    )]
+    pub allowlist_pass: Account<'info, AllowlistPass>,
+    #[account(mut)]
+    pub investor: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateMetadata<'info> {
+    #[account(mut, has_one = lister)]
+    pub etf: Account<'info, ETF>,
+    /// CHECK: The ETF's share mint, which may not exist - only updated when it is a Token-2022 mint
+    #[account(mut, seeds = [b"shares", etf.key().as_ref()], bump)]
+    pub share_mint: UncheckedAccount<'info>,
+    // Funds the rent of longer share mint metadata
+    #[account(mut)]
+    pub lister: Signer<'info>,
+    pub token_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetPrice<'info> {
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + 32 + 8 + 8 + 1,
+        seeds = [b"price", mint.key().as_ref()],
+        bump
+    )]
+    pub price_feed: Account<'info, PriceFeed>,
+    pub mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct QuoteETF<'info> {
+    pub etf: Account<'info, ETF>,
+}
+
+#[derive(Accounts)]
+pub struct SellETF<'info> {
+    #[account(mut)]
+    pub etf: Account<'info, ETF>,
+    #[account(
+        mut,
+        seeds = [b"position", etf.key().as_ref(), investor.key().as_ref()],
+        bump = position.bump,
+        has_one = etf,
+        has_one = investor,
+    )]
+    pub position: Account<'info, Position>,
+    // A wallet, or a system-owned PDA signed for by a calling program
+    #[account(mut)]
+    pub investor: Signer<'info>,
+    /// CHECK: This is the lister's account - validated against etf.lister
+    #[account(mut)]
+    pub lister_account: AccountInfo<'info>,
+    /// CHECK: Receives the dev fee - validated against the TREASURY constant
+    #[account(mut)]
+    pub dev_wallet: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RedeemToTokens<'info> {
+    #[account(mut)]
+    pub etf: Account<'info, ETF>,
+    #[account(
+        mut,
+        seeds = [b"position", etf.key().as_ref(), investor.key().as_ref()],
+        bump = position.bump,
+        has_one = etf,
+        has_one = investor,
+    )]
+    pub position: Account<'info, Position>,
+    // Pays the fees and receives the SOL slice
+    #[account(mut)]
+    pub investor: Signer<'info>,
+    /// CHECK: This is the lister's account - validated against etf.lister
+    #[account(mut)]
+    pub lister_account: AccountInfo<'info>,
+    /// CHECK: Receives the dev fee - validated against the TREASURY constant
+    #[account(mut)]
+    pub dev_wallet: AccountInfo<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateDcaPlan<'info> {
+    pub etf: Account<'info, ETF>,
+    #[account(
+        init,
+        payer = investor,
+        space = 8 + 32 + 32 + 8 + 8 + 8 + (4 + 10) + 8 + 1,
+        seeds = [b"dca", etf.key().as_ref(), investor.key().as_ref()],
+        bump
+    )]
+    pub dca_plan: Account<'info, DcaPlan>,
+    #[account(
+        init_if_needed,
+        payer = investor,
+        space = POSITION_SPACE,
+        seeds = [b"position", etf.key().as_ref(), investor.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub investor: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FundDcaPlan<'info> {
+    #[account(
+        mut,
+        seeds = [b"dca", dca_plan.etf.as_ref(), investor.key().as_ref()],
+        bump = dca_plan.bump,
+        has_one = investor
+    )]
+    pub dca_plan: Account<'info, DcaPlan>,
+    #[account(mut)]
+    pub investor: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteDca<'info> {
+    #[account(mut)]
+    pub etf: Account<'info, ETF>,
+    #[account(
+        mut,
+        seeds = [b"dca", etf.key().as_ref(), dca_plan.investor.as_ref()],
+        bump = dca_plan.bump,
+        has_one = etf
+    )]
+    pub dca_plan: Account<'info, DcaPlan>,
+    #[account(
+        mut,
+        seeds = [b"position", etf.key().as_ref(), dca_plan.investor.as_ref()],
+        bump = position.bump
+    )]
+    pub position: Account<'info, Position>,
+    /// CHECK: This is the lister's account - validated against etf.lister
+    #[account(mut)]
+    pub lister_account: AccountInfo<'info>,
+    /// CHECK: Receives the dev fee - validated against the TREASURY constant
+    #[account(mut)]
+    pub dev_wallet: AccountInfo<'info>,
+    // Anyone; receives the crank tip
+    #[account(mut)]
+    pub caller: Signer<'info>,
+    /// CHECK: Required when the ETF is gated - validated against etf.gate by check_gate
+    pub gate_account: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct CancelDcaPlan<'info> {
+    /// CHECK: Only a seed of the plan; the ETF may already be closed
+    pub etf: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        close = investor,
+        seeds = [b"dca", etf.key().as_ref(), investor.key().as_ref()],
+        bump = dca_plan.bump,
+        has_one = etf,
+        has_one = investor
+    )]
+    pub dca_plan: Account<'info, DcaPlan>,
+    #[account(mut)]
+    pub investor: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(order_id: u64)]
+pub struct PlaceLimitOrder<'info> {
+    pub etf: Account<'info, ETF>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + 32 + 32 + 8 + 1 + 8 + 8 + (4 + 10) + 8 + 1,
+        seeds = [b"order", etf.key().as_ref(), owner.key().as_ref(), &order_id.to_le_bytes()],
+        bump
+    )]
+    pub order: Account<'info, LimitOrder>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = POSITION_SPACE,
+        seeds = [b"position", etf.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FillLimitOrder<'info> {
+    #[account(mut)]
+    pub etf: Account<'info, ETF>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"order", etf.key().as_ref(), order.owner.as_ref(), &order.order_id.to_le_bytes()],
+        bump = order.bump,
+        has_one = etf,
+        has_one = owner
+    )]
+    pub order: Account<'info, LimitOrder>,
+    #[account(
+        mut,
+        seeds = [b"position", etf.key().as_ref(), order.owner.as_ref()],
+        bump = position.bump
+    )]
+    pub position: Account<'info, Position>,
+    /// CHECK: The order's owner - validated by has_one on the order
+    #[account(mut)]
+    pub owner: AccountInfo<'info>,
+    /// CHECK: This is the lister's account - validated against etf.lister
+    #[account(mut)]
+    pub lister_account: AccountInfo<'info>,
+    /// CHECK: Receives the dev fee - validated against the TREASURY constant
+    #[account(mut)]
+    pub dev_wallet: AccountInfo<'info>,
+    // Anyone; advances the rent of a pending purchase
+    #[account(mut)]
+    pub filler: Signer<'info>,
+    /// CHECK: Required when the ETF is gated - validated against etf.gate by check_gate
+    pub gate_account: Option<UncheckedAccount<'info>>,
+    // Required for buy orders when the ETF has a keeper, omitted otherwise
+    #[account(
+        init,
+        payer = filler,
+        space = PENDING_PURCHASE_SPACE,
+        seeds = [b"pending", etf.key().as_ref(), &etf.purchase_count.to_le_bytes()],
+        bump
+    )]
+    pub pending_purchase: Option<Account<'info, PendingPurchase>>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelLimitOrder<'info> {
+    /// CHECK: Only a seed of the order; the ETF may already be closed
+    pub etf: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"order", etf.key().as_ref(), owner.key().as_ref(), &order.order_id.to_le_bytes()],
+        bump = order.bump,
+        has_one = etf,
+        has_one = owner
+    )]
+    pub order: Account<'info, LimitOrder>,
+    // Required for sell orders, omitted for buy orders
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = POSITION_SPACE,
+        seeds = [b"position", etf.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub position: Option<Account<'info, Position>>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetExitTrigger<'info> {
     pub etf: Account<'info, ETF>,
+    #[account(
+        init_if_needed,
+        payer = investor,
+        space = 8 + 32 + 32 + 8 + 8 + 8 + 1,
+        seeds = [b"exit", etf.key().as_ref(), investor.key().as_ref()],
+        bump
+    )]
+    pub exit_trigger: Account<'info, ExitTrigger>,
+    // Triggers can only be attached to an existing position
+    #[account(
+        seeds = [b"position", etf.key().as_ref(), investor.key().as_ref()],
+        bump = position.bump,
+        has_one = investor
+    )]
+    pub position: Account<'info, Position>,
     #[account(mut)]
     pub investor: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct TriggerExit<'info> {
+    #[account(mut)]
+    pub etf: Account<'info, ETF>,
+    #[account(
+        mut,
+        close = investor,
+        seeds = [b"exit", etf.key().as_ref(), investor.key().as_ref()],
+        bump = exit_trigger.bump,
+        has_one = etf,
+        has_one = investor
+    )]
+    pub exit_trigger: Account<'info, ExitTrigger>,
+    #[account(
+        mut,
+        seeds = [b"position", etf.key().as_ref(), investor.key().as_ref()],
+        bump = position.bump
+    )]
+    pub position: Account<'info, Position>,
+    /// CHECK: The trigger's investor - validated by has_one on the trigger
+    #[account(mut)]
+    pub investor: AccountInfo<'info>,
     /// CHECK: This is the lister's account - validated against etf.lister
     #[account(mut)]
     pub lister_account: AccountInfo<'info>,
-    /// CHECK: This is the dev wallet - validated against DEV_WALLET constant
+    /// CHECK: Receives the dev fee - validated against the TREASURY constant
+    #[account(mut)]
+    pub dev_wallet: AccountInfo<'info>,
+    // Anyone; receives the crank tip
+    #[account(mut)]
+    pub caller: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CancelExitTrigger<'info> {
+    /// CHECK: Only a seed of the trigger; the ETF may already be closed
+    pub etf: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        close = investor,
+        seeds = [b"exit", etf.key().as_ref(), investor.key().as_ref()],
+        bump = exit_trigger.bump,
+        has_one = etf,
+        has_one = investor
+    )]
+    pub exit_trigger: Account<'info, ExitTrigger>,
+    #[account(mut)]
+    pub investor: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CreateNavHistory<'info> {
+    pub etf: Account<'info, ETF>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + 32 + 2 + (4 + NAV_HISTORY_LEN * (8 + 8 + 8)) + 1,
+        seeds = [b"nav_history", etf.key().as_ref()],
+        bump
+    )]
+    pub nav_history: Account<'info, NavHistory>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SnapshotNav<'info> {
+    pub etf: Account<'info, ETF>,
+    #[account(
+        mut,
+        seeds = [b"nav_history", etf.key().as_ref()],
+        bump = nav_history.bump,
+        has_one = etf
+    )]
+    pub nav_history: Account<'info, NavHistory>,
+}
+
+#[derive(Accounts)]
+pub struct QuoteNavTwap<'info> {
+    pub nav_history: Account<'info, NavHistory>,
+}
+
+#[derive(Accounts)]
+#[instruction(epoch_id: u64)]
+pub struct CreateLeaderboardEpoch<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + 8 + 8 + 8 + (4 + LEADERBOARD_SIZE * (32 + 32 + 8 + 8 + 8)) + 1,
+        seeds = [b"leaderboard", epoch_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub leaderboard: Account<'info, LeaderboardEpoch>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SubmitEpochReturn<'info> {
+    #[account(
+        mut,
+        seeds = [b"leaderboard", leaderboard.epoch_id.to_le_bytes().as_ref()],
+        bump = leaderboard.bump
+    )]
+    pub leaderboard: Account<'info, LeaderboardEpoch>,
+    pub etf: Account<'info, ETF>,
+    #[account(
+        seeds = [b"nav_history", etf.key().as_ref()],
+        bump = nav_history.bump,
+        has_one = etf
+    )]
+    pub nav_history: Account<'info, NavHistory>,
+}
+
+#[derive(Accounts)]
+pub struct ConfigureTreasury<'info> {
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + 2 + 8 + 8 + 8 + 1,
+        seeds = [b"treasury"],
+        bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FundRewards<'info> {
+    #[account(mut, seeds = [b"treasury"], bump = treasury.bump)]
+    pub treasury: Account<'info, Treasury>,
     #[account(mut)]
+    pub funder: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawDevFees<'info> {
+    #[account(mut, seeds = [b"treasury"], bump = treasury.bump)]
+    pub treasury: Account<'info, Treasury>,
+    /// CHECK: Only ever DEV_WALLET
+    #[account(mut, address = DEV_WALLET)]
     pub dev_wallet: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeEpoch<'info> {
+    #[account(
+        seeds = [b"leaderboard", leaderboard.epoch_id.to_le_bytes().as_ref()],
+        bump = leaderboard.bump
+    )]
+    pub leaderboard: Account<'info, LeaderboardEpoch>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + 8 + (4 + LEADERBOARD_SIZE * (32 + 32 + 8 + 1)) + 1,
+        seeds = [b"epoch_rewards", leaderboard.epoch_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub epoch_rewards: Account<'info, EpochRewards>,
+    #[account(mut, seeds = [b"treasury"], bump = treasury.bump)]
+    pub treasury: Account<'info, Treasury>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimCreatorReward<'info> {
+    #[account(
+        mut,
+        seeds = [b"epoch_rewards", epoch_rewards.epoch_id.to_le_bytes().as_ref()],
+        bump = epoch_rewards.bump
+    )]
+    pub epoch_rewards: Account<'info, EpochRewards>,
+    #[account(mut, seeds = [b"treasury"], bump = treasury.bump)]
+    pub treasury: Account<'info, Treasury>,
+    #[account(mut)]
+    pub lister: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CreateShareMint<'info> {
+    pub etf: Account<'info, ETF>,
+    // The metadata pointer points the mint at itself; create_share_mint then
+    // writes the metadata, with the ETF as its update authority
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"shares", etf.key().as_ref()],
+        bump,
+        mint::decimals = SHARE_DECIMALS,
+        mint::authority = etf,
+        mint::token_program = token_program,
+        extensions::metadata_pointer::authority = etf,
+        extensions::metadata_pointer::metadata_address = share_mint,
+    )]
+    pub share_mint: InterfaceAccount<'info, token_interface::Mint>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub token_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WrapShares<'info> {
+    pub etf: Account<'info, ETF>,
+    #[account(
+        mut,
+        seeds = [b"position", etf.key().as_ref(), owner.key().as_ref()],
+        bump = position.bump,
+        has_one = etf,
+        constraint = position.investor == owner.key() @ ErrorCode::Unauthorized,
+    )]
+    pub position: Account<'info, Position>,
+    // Token-2022, or SPL Token for a mint created before share mints
+    // carried metadata
+    #[account(mut, seeds = [b"shares", etf.key().as_ref()], bump, mint::token_program = token_program)]
+    pub share_mint: InterfaceAccount<'info, token_interface::Mint>,
+    #[account(mut, token::mint = share_mint)]
+    pub destination: InterfaceAccount<'info, token_interface::TokenAccount>,
+    pub owner: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct UnwrapShares<'info> {
+    pub etf: Account<'info, ETF>,
+    #[account(mut, seeds = [b"shares", etf.key().as_ref()], bump, mint::token_program = token_program)]
+    pub share_mint: InterfaceAccount<'info, token_interface::Mint>,
+    #[account(mut, token::mint = share_mint, token::authority = owner)]
+    pub source: InterfaceAccount<'info, token_interface::TokenAccount>,
+    // Pays for the recipient's position if it does not exist yet
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    /// CHECK: Any account may receive shares; it is only used to derive the position
+    pub recipient: AccountInfo<'info>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = POSITION_SPACE,
+        seeds = [b"position", etf.key().as_ref(), recipient.key().as_ref()],
+        bump
+    )]
+    pub recipient_position: Account<'info, Position>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseETF<'info> {
+    #[account(
+        mut,
+        close = lister,
+        has_one = lister,
+    )]
+    pub etf: Account<'info, ETF>,
+    #[account(mut, seeds = [b"registry"], bump = registry.bump)]
+    pub registry: Account<'info, Registry>,
+    #[account(
+        mut,
+        seeds = [b"registry_page".as_ref(), &registry_page_index(etf.registry_index).to_le_bytes()],
+        bump = registry_page.bump
+    )]
+    pub registry_page: Account<'info, RegistryPage>,
+    /// CHECK: The ETF's share mint, which may not exist - checked by wind_down_etf
+    #[account(seeds = [b"shares", etf.key().as_ref()], bump)]
+    pub share_mint: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub lister: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BeginLiquidation<'info> {
+    #[account(mut)]
+    pub etf: Account<'info, ETF>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct LiquidateVault<'info> {
+    #[account(mut)]
+    pub etf: Account<'info, ETF>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    // The ETF's associated token account of a basket mint
+    #[account(mut, token::authority = etf)]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = vault.mint)]
+    pub destination: Account<'info, TokenAccount>,
+    /// CHECK: PriceFeed of the vault's mint - validated by read_price
+    pub price_feed: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
 
-#[derive(Accounts)]
-pub struct SellETF<'info> {
-    #[account(mut)]
-    pub etf: Account<'info, ETF>,
-    #[account(mut)]
-    pub investor: Signer<'info>,
-    /// CHECK: This is the lister's account - validated against etf.lister
-    #[account(mut)]
-    pub lister_account: AccountInfo<'info>,
-    /// CHECK: This is the dev wallet - validated against DEV_WALLET constant
-    #[account(mut)]
-    pub dev_wallet: AccountInfo<'info>,
-    pub system_program: Program<'info, System>,
+#[derive(Accounts)]
+pub struct ClaimLiquidation<'info> {
+    #[account(mut)]
+    pub etf: Account<'info, ETF>,
+    #[account(
+        mut,
+        close = investor,
+        seeds = [b"position", etf.key().as_ref(), investor.key().as_ref()],
+        bump = position.bump,
+        has_one = etf,
+        has_one = investor,
+    )]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub investor: Signer<'info>,
+    // Required to pay out vault tokens
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct CloseLiquidatedETF<'info> {
+    #[account(
+        mut,
+        close = lister,
+        has_one = lister,
+    )]
+    pub etf: Account<'info, ETF>,
+    #[account(mut, seeds = [b"registry"], bump = registry.bump)]
+    pub registry: Account<'info, Registry>,
+    #[account(
+        mut,
+        seeds = [b"registry_page".as_ref(), &registry_page_index(etf.registry_index).to_le_bytes()],
+        bump = registry_page.bump
+    )]
+    pub registry_page: Account<'info, RegistryPage>,
+    /// CHECK: The ETF's share mint, which may not exist - checked by wind_down_etf
+    #[account(seeds = [b"shares", etf.key().as_ref()], bump)]
+    pub share_mint: UncheckedAccount<'info>,
+    /// CHECK: Rent and swept assets go to the lister - validated by has_one on etf
+    #[account(mut)]
+    pub lister: AccountInfo<'info>,
+    pub caller: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[account]
+pub struct ETF {
+    pub lister: Pubkey,
+    pub etf_index: u8,              // Index 0-4 for up to 5 ETFs per wallet
+    pub token_addresses: Vec<Pubkey>,
+    pub total_supply: u64,
+    pub accumulated_fees: u64,  // Kept for backwards compatibility, now always 0
+    pub bump: u8,
+    pub registry_index: u64,        // Position in the global ETF registry
+    pub name: String,               // Max MAX_NAME_LEN bytes
+    pub symbol: String,             // Ticker, max MAX_SYMBOL_LEN bytes
+    pub uri: String,                // Off-chain metadata JSON (image, socials)
+    pub category: EtfCategory,
+    pub status: EtfStatus,
+    pub lister_last_active_ts: i64, // Refreshed by lister-signed instructions
+    pub liquidation_started_ts: i64,
+    pub trade_sequence: u64,        // Buys + sells so far; carried on trade events
+    pub keeper: Option<Pubkey>,     // Set => buys are settled by this keeper
+    pub pending_sol: u64,           // Held for unsettled purchases
+    pub purchase_count: u64,        // Next PendingPurchase id
+    pub refund_timeout: i64,        // Seconds before an unsettled purchase is refundable
+    pub gate: EtfGate,              // Who may buy
+    pub limits: BuyLimits,          // Buy size and capacity limits
+    pub min_hold_seconds: i64,      // Lock-up after each buy
+    pub early_exit_fee_bps: u16,    // 0 = no selling inside the lock-up
+    pub parent_etf: Option<Pubkey>, // Set on forks
+    pub parent_lister: Pubkey,      // Lister of the parent at fork time
+    pub parent_fee_bps: u16,        // Share of creator fees owed to the parent lister
+    pub parent_fees_owed: u64,      // Held in the ETF until claim_parent_fees
+    pub sol_assets: u64,            // Booked SOL backing shares
+    pub holdings: Vec<u64>,         // Booked basket tokens backing shares, token_addresses order
+    pub legacy_shares: u64,         // Migrated supply not yet restored to a Position
+}
+
+// ETF layout before the registry, metadata and booked NAV, read by migrate_etf
+#[derive(AnchorDeserialize)]
+pub struct LegacyETF {
+    pub lister: Pubkey,
+    pub etf_index: u8,
+    pub token_addresses: Vec<Pubkey>,
+    pub total_supply: u64,
+    pub accumulated_fees: u64,
+    pub bump: u8,
+}
+
+// A freshly listed ETF with default settings, no basket and no parent
+impl Default for ETF {
+    fn default() -> Self {
+        Self {
+            lister: Pubkey::default(),
+            etf_index: 0,
+            token_addresses: Vec::new(),
+            total_supply: 0,
+            accumulated_fees: 0,
+            bump: 0,
+            registry_index: 0,
+            name: String::new(),
+            symbol: String::new(),
+            uri: String::new(),
+            category: EtfCategory::Meme,
+            status: EtfStatus::Active,
+            lister_last_active_ts: 0,
+            liquidation_started_ts: 0,
+            trade_sequence: 0,
+            keeper: None,
+            pending_sol: 0,
+            purchase_count: 0,
+            refund_timeout: DEFAULT_REFUND_TIMEOUT_SECONDS,
+            gate: EtfGate::Open,
+            limits: BuyLimits::default(),
+            min_hold_seconds: 0,
+            early_exit_fee_bps: 0,
+            parent_etf: None,
+            parent_lister: Pubkey::default(),
+            parent_fee_bps: 0,
+            parent_fees_owed: 0,
+            sol_assets: 0,
+            holdings: Vec::new(),
+            legacy_shares: 0,
+        }
+    }
+}
+
+// Governance-kept price of a basket token, used to value ETF holdings
+#[account]
+pub struct PriceFeed {
+    pub mint: Pubkey,
+    pub price: u64, // Lamports per PRICE_SCALE base units
+    pub updated_ts: i64,
+    pub bump: u8,
+}
+
+// Basket changes of fork_etf
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Debug, Default)]
+pub struct ForkChanges {
+    pub remove_tokens: Vec<Pubkey>,
+    pub add_tokens: Vec<Pubkey>,
+    pub parent_fee_bps: u16,        // At most MAX_PARENT_FEE_BPS
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EtfStatus {
+    Active,
+    Liquidating,    // Buys/sells disabled, holders claim pro-rata SOL and tokens
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EtfGate {
+    Open,
+    MerkleAllowlist { root: [u8; 32] },          // Wallets under `root`, see allowlist_leaf
+    MinimumBalance { mint: Pubkey, amount: u64 }, // Holders of at least `amount` of `mint`
+}
+
+// Zero means no ETF-specific limit; the PROTOCOL_* limits always apply
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct BuyLimits {
+    pub min_buy_lamports: u64,
+    pub max_buy_lamports: u64,
+    pub max_total_supply: u64,      // Minted plus pending shares
+}
+
+// Proof, checked by verify_allowlist, that an investor is on an ETF's
+// merkle allowlist. Only valid while the ETF's root is unchanged.
+#[account]
+pub struct AllowlistPass {
+    pub etf: Pubkey,
+    pub investor: Pubkey,
+    pub root: [u8; 32],
+    pub bump: u8,
+}
+
+// Per-investor share balance in an ETF
+#[account]
+pub struct Position {
+    pub etf: Pubkey,
+    pub investor: Pubkey,
+    pub shares: u64,
+    pub bump: u8,
+    pub last_buy_ts: i64,           // Last buy, settlement or unwrap into this position
+}
+
+// Recurring buy of an ETF, funded in advance by the investor. The SOL for
+// future buys is the account's balance above rent.
+#[account]
+pub struct DcaPlan {
+    pub etf: Pubkey,
+    pub investor: Pubkey,
+    pub amount_per_period: u64,     // Lamports per buy, fees included
+    pub interval_seconds: i64,
+    pub next_execution_ts: i64,
+    pub token_percentages: Vec<u8>, // Split of each buy, token_addresses order
+    pub executions: u64,
+    pub bump: u8,
+}
+
+// Order to buy or sell an ETF once its NAV crosses `target_nav`. Buy orders
+// hold their SOL as the account's balance above rent; sell orders hold
+// shares taken out of the owner's position (still counted in total_supply).
+// Any lamports above rent and a buy's SOL are the deposit.
+#[account]
+pub struct LimitOrder {
+    pub etf: Pubkey,
+    pub owner: Pubkey,
+    pub order_id: u64,              // Picked by the owner; part of the PDA seeds
+    pub side: OrderSide,
+    pub amount: u64,                // Lamports to spend (fees included) or shares to sell
+    pub target_nav: u64,            // Lamports per NAV_SCALE shares
+    pub token_percentages: Vec<u8>, // Split of a buy; empty for sells
+    pub created_ts: i64,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OrderSide {
+    Buy,    // Fills at or below target_nav
+    Sell,   // Fills at or above target_nav
+}
+
+// Stop-loss / take-profit thresholds attached to a position. Holds
+// EXIT_CRANK_TIP_LAMPORTS above rent for whoever fires it, and any lamports
+// above that as the deposit.
+#[account]
+pub struct ExitTrigger {
+    pub etf: Pubkey,
+    pub investor: Pubkey,
+    pub stop_loss_nav: u64,         // Sell at or below this NAV; 0 = none
+    pub take_profit_nav: u64,       // Sell at or above this NAV; 0 = none
+    pub shares: u64,                // Shares to sell; 0 = the whole position
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ExitKind {
+    StopLoss,
+    TakeProfit,
+}
+
+// Ring buffer of NAV snapshots. Until it fills, `snapshots` grows; then
+// `head` is the oldest entry, overwritten next.
+#[account]
+pub struct NavHistory {
+    pub etf: Pubkey,
+    pub head: u16,
+    pub snapshots: Vec<NavSnapshot>, // At most NAV_HISTORY_LEN
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct NavSnapshot {
+    pub timestamp: i64,
+    pub nav_per_share: u64,         // Lamports per NAV_SCALE shares
+    pub total_value: u64,           // Net assets in lamports, as in get_nav
+}
+
+// Top ETFs by NAV return over [start_ts, end_ts], best first
+#[account]
+pub struct LeaderboardEpoch {
+    pub epoch_id: u64,
+    pub start_ts: i64,
+    pub end_ts: i64,
+    pub entries: Vec<LeaderboardEntry>, // At most LEADERBOARD_SIZE
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct LeaderboardEntry {
+    pub etf: Pubkey,
+    pub lister: Pubkey,             // The creator being ranked
+    pub return_bps: i64,
+    pub start_nav: u64,             // Time-weighted NAV at the epoch's start
+    pub end_nav: u64,               // Time-weighted NAV at the epoch's end
+}
+
+// Books of the dev fees held by the TREASURY PDA. Lamports above rent and
+// these balances are fees not yet swept.
+#[account]
+pub struct Treasury {
+    pub rewards_share_bps: u16,     // Share of dev fees for the rewards pool
+    pub dev_balance: u64,           // Withdrawable to DEV_WALLET
+    pub rewards_pool: u64,          // Paid out at the next finalize_epoch
+    pub rewards_owed: u64,          // Allocated to finalized epochs, unclaimed
+    pub bump: u8,
+}
+
+// Rewards of a finalized leaderboard epoch, in rank order
+#[account]
+pub struct EpochRewards {
+    pub epoch_id: u64,
+    pub rewards: Vec<CreatorReward>, // At most LEADERBOARD_SIZE
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CreatorReward {
+    pub etf: Pubkey,
+    pub lister: Pubkey,
+    pub amount: u64,
+    pub claimed: bool,
+}
+
+// A keeper-settled buy awaiting its swaps. Fees are already paid; the SOL
+// to swap sits in the ETF until settle_purchase.
+#[account]
+pub struct PendingPurchase {
+    pub etf: Pubkey,
+    pub investor: Pubkey,
+    pub purchase_id: u64,
+    pub sol_amount: u64,            // Paid by the investor, fees included
+    pub sol_to_swap: u64,           // After fees; reimbursed to the keeper
+    pub allocations: Vec<u64>,      // Lamports per basket token, token_addresses order
+    pub created_ts: i64,
+    pub refundable_ts: i64,         // Investor may refund from here on
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EtfCategory {
+    Meme,
+    AI,
+    Gaming,
+    DeFi,
+    Community,
+    Other,
+}
+
+// Global registry header - tracks how many ETFs have been registered
+#[account]
+pub struct Registry {
+    pub etf_count: u64,     // Total ETFs ever registered (next registry_index)
+    pub active_count: u64,  // ETFs that have not been closed
+    pub bump: u8,
+}
+
+// One page of the registry - ETFs are appended in creation order
+#[account]
+pub struct RegistryPage {
+    pub page_index: u32,
+    pub entries: Vec<RegistryEntry>,
+    pub bump: u8,
+}
+
+// Return data of quote_buy
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Debug)]
+pub struct BuyQuote {
+    pub sol_amount: u64,
+    pub creator_fee: u64,
+    pub dev_fee: u64,
+    pub sol_after_fees: u64,
+    pub shares_out: u64,            // Minted at NAV for SOL after fees; an estimate for keeper ETFs
+    pub allocations: Vec<u64>,      // Lamports per basket token, token_addresses order
+    pub pending: bool,              // Keeper-settled: shares are minted on settlement
+    pub nav_per_share: u64,         // Before the buy
+}
+
+// Return data of quote_sell
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Debug)]
+pub struct SellQuote {
+    pub shares_in: u64,
+    pub sol_value: u64,             // Before fees
+    pub creator_fee: u64,
+    pub dev_fee: u64,
+    pub sol_out: u64,               // Paid to the investor
+    pub liquidity: u64,             // SOL available to sellers right now
+    pub nav_per_share: u64,         // Before the sell
+}
+
+// Return data of get_nav
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Debug)]
+pub struct NavQuote {
+    pub total_supply: u64,
+    pub net_assets: u64,            // Lamports above rent, excluding pending_sol
+    pub pending_sol: u64,
+    pub nav_per_share: u64,         // Lamports per NAV_SCALE shares
+    pub status: EtfStatus,
+}
+
+// Return data of get_nav_twap
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Debug)]
+pub struct TwapQuote {
+    pub nav_per_share: u64,         // Time-weighted over the covered part of the window
+    pub window_seconds: i64,
+    pub covered_seconds: i64,       // Less than the window while history is short
+    pub snapshots: u16,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct RegistryEntry {
+    pub etf: Pubkey,
+    pub closed: bool,   // Tombstone set by close_etf
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum FeeType {
+    Creator,
+    Dev,
+    Parent,     // A fork's share of the creator fee, accrued for the parent lister
+}
+
+#[event]
+pub struct ETFCreatedEvent {
+    pub etf_address: Pubkey,
+    pub lister: Pubkey,
+    pub token_count: u8,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub category: EtfCategory,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TokenPurchaseEvent {
+    pub etf_address: Pubkey,
+    pub investor: Pubkey,
+    pub token_address: Pubkey,
+    pub sol_amount: u64,
+    pub percentage: u8,
+    pub timestamp: i64,
+}
+
+// Sell-side counterpart of TokenPurchaseEvent
+#[event]
+pub struct TokenSaleEvent {
+    pub etf_address: Pubkey,
+    pub investor: Pubkey,
+    pub token_address: Pubkey,
+    pub shares_burned: u64,
+    pub supply_before: u64,         // Liquidate shares_burned / supply_before of the vault
+    pub timestamp: i64,
+}
+
+// Basket tokens paid out by redeem_to_tokens
+#[event]
+pub struct TokenRedeemedEvent {
+    pub etf_address: Pubkey,
+    pub investor: Pubkey,
+    pub token_address: Pubkey,
+    pub amount: u64,                // Token base units sent to the investor
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ETFBoughtEvent {
+    pub etf_address: Pubkey,
+    pub investor: Pubkey,
+    pub sequence: u64,              // Per-ETF trade_sequence, gap-free
+    pub sol_amount: u64,            // Paid by the investor, fees included
+    pub shares_minted: u64,
+    pub creator_fee: u64,
+    pub dev_fee: u64,
+    pub total_supply: u64,          // After the trade
+    pub nav_per_share: u64,         // Lamports per NAV_SCALE shares, after the trade
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ETFSoldEvent {
+    pub etf_address: Pubkey,
+    pub investor: Pubkey,
+    pub sequence: u64,              // Per-ETF trade_sequence, gap-free
+    pub shares_burned: u64,
+    pub sol_amount: u64,            // Paid to the investor, fees deducted
+    pub creator_fee: u64,
+    pub dev_fee: u64,
+    pub early_exit_fee: u64,        // Lamports withheld from the seller, booked for the remaining holders
+    pub total_supply: u64,          // After the trade
+    pub nav_per_share: u64,         // Lamports per NAV_SCALE shares, after the trade
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PurchasePendingEvent {
+    pub etf_address: Pubkey,
+    pub investor: Pubkey,
+    pub purchase_id: u64,
+    pub sol_amount: u64,            // To be swapped, fees deducted
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PurchaseSettledEvent {
+    pub etf_address: Pubkey,
+    pub investor: Pubkey,
+    pub keeper: Pubkey,
+    pub purchase_id: u64,
+    pub sol_amount: u64,            // Reimbursed to the keeper
+    pub token_amounts: Vec<u64>,    // Delivered to each vault, token_addresses order
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct KeeperUpdatedEvent {
+    pub etf_address: Pubkey,
+    pub keeper: Option<Pubkey>,
+    pub refund_timeout: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GateUpdatedEvent {
+    pub etf_address: Pubkey,
+    pub gate: EtfGate,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BuyLimitsUpdatedEvent {
+    pub etf_address: Pubkey,
+    pub limits: BuyLimits,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct HoldPeriodUpdatedEvent {
+    pub etf_address: Pubkey,
+    pub min_hold_seconds: i64,
+    pub early_exit_fee_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PurchaseRefundedEvent {
+    pub etf_address: Pubkey,
+    pub investor: Pubkey,
+    pub purchase_id: u64,
+    pub sol_amount: u64,        // Returned to the investor, after fees
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeeTransferEvent {
+    pub etf_address: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub fee_type: FeeType,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MetadataUpdatedEvent {
+    pub etf_address: Pubkey,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub category: EtfCategory,
+    pub timestamp: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TokenRecovered {
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct LiquidationStartedEvent {
+    pub etf_address: Pubkey,
+    pub initiator: Pubkey,
+    pub total_supply: u64,
+    pub notice_ends_ts: i64,        // Vaults may be converted from then on
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VaultLiquidatedEvent {
+    pub etf_address: Pubkey,
+    pub token_address: Pubkey,
+    pub token_amount: u64,
+    pub sol_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LiquidationClaimEvent {
+    pub etf_address: Pubkey,
+    pub investor: Pubkey,
+    pub shares_burned: u64,
+    pub sol_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ETFClosedEvent {
+    pub etf_address: Pubkey,
+    pub lister: Pubkey,
+    pub rent_recovered: u64,        // ETF account rent returned to lister
+    pub dust_recovered: u64,        // SOL left above rent (rounding / donations)
+    pub vault_rent_recovered: u64,  // Rent from closed basket vaults
+    pub tokens_recovered: Vec<TokenRecovered>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DcaPlanCreatedEvent {
+    pub etf_address: Pubkey,
+    pub investor: Pubkey,
+    pub amount_per_period: u64,
+    pub interval_seconds: i64,
+    pub deposit: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DcaPlanFundedEvent {
+    pub etf_address: Pubkey,
+    pub investor: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DcaExecutedEvent {
+    pub etf_address: Pubkey,
+    pub investor: Pubkey,
+    pub caller: Pubkey,
+    pub sol_amount: u64,            // Spent on the buy, fees included
+    pub shares_minted: u64,
+    pub tip: u64,                   // Paid to the caller on top of sol_amount
+    pub next_execution_ts: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DcaPlanCancelledEvent {
+    pub etf_address: Pubkey,
+    pub investor: Pubkey,
+    pub refunded: u64,              // Remaining balance plus rent
+    pub executions: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LimitOrderPlacedEvent {
+    pub etf_address: Pubkey,
+    pub owner: Pubkey,
+    pub order_id: u64,
+    pub side: OrderSide,
+    pub amount: u64,
+    pub target_nav: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LimitOrderFilledEvent {
+    pub etf_address: Pubkey,
+    pub owner: Pubkey,
+    pub order_id: u64,
+    pub side: OrderSide,
+    pub amount: u64,
+    pub nav_per_share: u64,         // NAV that triggered the fill
+    pub filler: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LimitOrderCancelledEvent {
+    pub etf_address: Pubkey,
+    pub owner: Pubkey,
+    pub order_id: u64,
+    pub side: OrderSide,
+    pub amount: u64,                // Lamports refunded or shares returned
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ExitTriggerSetEvent {
+    pub etf_address: Pubkey,
+    pub investor: Pubkey,
+    pub stop_loss_nav: u64,
+    pub take_profit_nav: u64,
+    pub shares: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ExitTriggeredEvent {
+    pub etf_address: Pubkey,
+    pub investor: Pubkey,
+    pub caller: Pubkey,
+    pub kind: ExitKind,
+    pub nav_per_share: u64,         // NAV that fired the trigger
+    pub shares_sold: u64,
+    pub sol_amount: u64,            // Paid to the investor
+    pub tip: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ExitTriggerCancelledEvent {
+    pub etf_address: Pubkey,
+    pub investor: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct NavSnapshotEvent {
+    pub etf_address: Pubkey,
+    pub nav_per_share: u64,
+    pub total_value: u64,
+    pub total_supply: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EpochReturnSubmittedEvent {
+    pub epoch_id: u64,
+    pub etf_address: Pubkey,
+    pub lister: Pubkey,
+    pub return_bps: i64,
+    pub rank: u8,                   // 0 = best, at the time of submission
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PriceUpdatedEvent {
+    pub token_address: Pubkey,
+    pub price: u64, // Lamports per PRICE_SCALE base units
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TreasuryConfiguredEvent {
+    pub rewards_share_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardsFundedEvent {
+    pub funder: Pubkey,
+    pub amount: u64,                // Deposited by the funder
+    pub fees_to_rewards: u64,       // Swept from collected dev fees
+    pub fees_to_dev: u64,
+    pub rewards_pool: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DevFeesWithdrawnEvent {
+    pub amount: u64,
+    pub timestamp: i64,
 }
 
-#[derive(Accounts)]
-pub struct CloseETF<'info> {
-    #[account(
-        mut,
-        close = lister,
-        has_one = lister,
-    )]
-    pub etf: Account<'info, ETF>,
-    #[account(mut)]
-    pub lister: Signer<'info>,
-    pub system_program: Program<'info, System>,
+#[event]
+pub struct EpochFinalizedEvent {
+    pub epoch_id: u64,
+    pub total_rewards: u64,
+    pub ranked: u8,
+    pub timestamp: i64,
 }
 
-#[account]
-pub struct ETF {
+#[event]
+pub struct CreatorRewardClaimedEvent {
+    pub epoch_id: u64,
+    pub etf_address: Pubkey,
     pub lister: Pubkey,
-    pub etf_index: u8,              // Index 0-4 for up to 5 ETFs per wallet
-    pub token_addresses: Vec<Pubkey>,
-    pub total_supply: u64,
-    pub accumulated_fees: u64,  // Kept for backwards compatibility, now always 0
-    pub bump: u8,
+    pub amount: u64,
+    pub timestamp: i64,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
-pub enum FeeType {
-    Creator,
-    Dev,
+#[event]
+pub struct ETFForkedEvent {
+    pub etf_address: Pubkey,
+    pub parent_etf: Pubkey,
+    pub lister: Pubkey,
+    pub parent_lister: Pubkey,
+    pub removed_tokens: Vec<Pubkey>,
+    pub added_tokens: Vec<Pubkey>,
+    pub parent_fee_bps: u16,
+    pub timestamp: i64,
 }
 
 #[event]
-pub struct ETFCreatedEvent {
+pub struct ETFMigratedEvent {
     pub etf_address: Pubkey,
     pub lister: Pubkey,
     pub token_count: u8,
+    pub registry_index: u64,
+    pub sol_assets: u64,            // Lamports above rent, booked as NAV
     pub timestamp: i64,
 }
 
 #[event]
-pub struct TokenPurchaseEvent {
+pub struct LegacyPositionRestoredEvent {
     pub etf_address: Pubkey,
     pub investor: Pubkey,
-    pub token_address: Pubkey,
-    pub sol_amount: u64,
-    pub percentage: u8,
+    pub shares: u64,
+    pub legacy_shares: u64,         // Left to restore
     pub timestamp: i64,
 }
 
 #[event]
-pub struct FeeTransferEvent {
+pub struct ParentFeesClaimedEvent {
     pub etf_address: Pubkey,
-    pub recipient: Pubkey,
+    pub parent_lister: Pubkey,
     pub amount: u64,
-    pub fee_type: FeeType,
     pub timestamp: i64,
 }
 
 #[event]
-pub struct ETFClosedEvent {
+pub struct ShareMintCreatedEvent {
     pub etf_address: Pubkey,
-    pub lister: Pubkey,
+    pub share_mint: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SharesWrappedEvent {
+    pub etf_address: Pubkey,
+    pub owner: Pubkey,              // Position the shares left
+    pub destination: Pubkey,        // Share token account credited
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SharesUnwrappedEvent {
+    pub etf_address: Pubkey,
+    pub owner: Pubkey,              // Authority of the burned share tokens
+    pub recipient: Pubkey,          // Position the shares were credited to
+    pub amount: u64,
     pub timestamp: i64,
 }
 
@@ -391,12 +5083,124 @@ pub enum ErrorCode {
     CannotCloseWithSupply,
     #[msg("Invalid token count - must be between 1 and 10")]
     InvalidTokenCount,
-    #[msg("Invalid dev wallet address")]
+    #[msg("Dev fees must go to the treasury")]
     InvalidDevWallet,
     #[msg("Invalid lister account - must match ETF creator")]
     InvalidListerAccount,
     #[msg("Maximum 5 ETFs per wallet reached")]
     MaxEtfsReached,
+    #[msg("Registry page does not contain this ETF")]
+    InvalidRegistryPage,
+    #[msg("Invalid name - must be between 1 and 32 bytes")]
+    InvalidName,
+    #[msg("Invalid symbol - must be between 1 and 10 bytes")]
+    InvalidSymbol,
+    #[msg("Metadata URI must be at most 200 bytes")]
+    UriTooLong,
+    #[msg("Invalid vault account - must be an ETF-owned basket token account")]
+    InvalidVaultAccount,
+    #[msg("Insufficient shares in position")]
+    InsufficientShares,
+    #[msg("ETF is in liquidation - buys and sells are disabled")]
+    EtfLiquidating,
+    #[msg("ETF is not in liquidation")]
+    EtfNotLiquidating,
+    #[msg("Lister is still active - governance cannot force liquidation yet")]
+    ListerStillActive,
+    #[msg("Pending purchase account must be passed if and only if the ETF has a keeper")]
+    InvalidPendingPurchase,
+    #[msg("Settlement must deliver every basket token the purchase paid for")]
+    InvalidSettlement,
+    #[msg("Settlement mode can only change while the ETF has no shares or pending purchases")]
+    SettlementModeLocked,
+    #[msg("ETF has unsettled purchases")]
+    PendingPurchasesOutstanding,
+    #[msg("Refund timeout out of range")]
+    InvalidRefundTimeout,
+    #[msg("Purchase can still be settled by the keeper")]
+    RefundNotYetAvailable,
+    #[msg("Investor does not meet this ETF's gate")]
+    NotAllowlisted,
+    #[msg("Buy is below the minimum amount")]
+    BuyBelowMinimum,
+    #[msg("Buy is above the maximum amount")]
+    BuyAboveMaximum,
+    #[msg("Buy would take the ETF over its maximum supply")]
+    MaxSupplyExceeded,
+    #[msg("Invalid buy limits")]
+    InvalidBuyLimits,
+    #[msg("Shares are still in their hold period")]
+    HoldPeriodActive,
+    #[msg("Hold period or early-exit fee out of range")]
+    InvalidHoldPeriod,
+    #[msg("DCA interval is too short")]
+    InvalidDcaInterval,
+    #[msg("DCA plan is not due yet")]
+    DcaNotDue,
+    #[msg("DCA plan cannot cover the next buy and crank tip")]
+    DcaPlanUnderfunded,
+    #[msg("DCA plans need an ETF without a keeper")]
+    RequiresInstantBuys,
+    #[msg("NAV has not reached the order's target")]
+    OrderNotTriggered,
+    #[msg("Sell orders return their shares to the owner's position, which must be passed")]
+    OrderPositionRequired,
+    #[msg("Exit trigger needs a threshold, and its stop-loss below its take-profit")]
+    InvalidExitTrigger,
+    #[msg("NAV has not reached a threshold of the exit trigger")]
+    ExitNotTriggered,
+    #[msg("NAV was snapshotted less than an interval ago")]
+    NavSnapshotTooSoon,
+    #[msg("NAV history has no snapshots yet")]
+    NoNavHistory,
+    #[msg("The leaderboard epoch has not ended")]
+    EpochNotEnded,
+    #[msg("NAV history does not cover the window before an epoch boundary")]
+    MissingEpochSnapshot,
+    #[msg("ETF is already on this leaderboard")]
+    EtfAlreadyRanked,
+    #[msg("Return does not make the leaderboard")]
+    ReturnNotRanked,
+    #[msg("ETF net assets were below the leaderboard minimum")]
+    BelowLeaderboardMinimum,
+    #[msg("Rewards share cannot exceed 100%")]
+    InvalidRewardsShare,
+    #[msg("Submissions for this epoch have closed")]
+    SubmissionsClosed,
+    #[msg("Submissions for this epoch are still open")]
+    SubmissionsOpen,
+    #[msg("Reward already claimed")]
+    RewardAlreadyClaimed,
+    #[msg("Fork removes a token not in the parent or adds one already in it")]
+    InvalidForkChanges,
+    #[msg("Parent fee share exceeds the maximum")]
+    InvalidParentFee,
+    #[msg("Parent fees must be claimed first")]
+    ParentFeesUnclaimed,
+    #[msg("Wrapped shares are still outstanding")]
+    SharesStillWrapped,
+    #[msg("The treasury has not been configured")]
+    TreasuryNotConfigured,
+    #[msg("Price feed account does not match the basket token")]
+    InvalidPriceFeed,
+    #[msg("A price feed is needed for every basket token the ETF holds")]
+    MissingPriceFeed,
+    #[msg("Price feed is too old")]
+    StalePrice,
+    #[msg("Settlement delivered less than the allocation is worth at the feed price")]
+    SettlementBelowPrice,
+    #[msg("This ETF holds basket tokens; redeem shares in kind")]
+    RedeemInKind,
+    #[msg("ETF account already has the current layout")]
+    AlreadyMigrated,
+    #[msg("More shares than the ETF has left to restore")]
+    LegacySharesExceeded,
+    #[msg("Vaults cannot be converted until the liquidation notice has passed")]
+    LiquidationNoticePending,
+    #[msg("Liquidation proceeds are below the tokens' value at the feed price")]
+    LiquidationBelowPrice,
+    #[msg("Order or trigger deposit does not cover the cost of an in-kind fill")]
+    InsufficientDeposit,
 }
 
 // ============================================================================
@@ -478,6 +5282,21 @@ mod tests {
         assert_eq!(sol_after_fees, 990_000_000_000); // 990 SOL
     }
 
+    #[test]
+    fn test_calculate_fees_matches_manual_math() {
+        for sol_amount in [0u64, 100, 199, 200, 10_000_000, 1_000_000_000] {
+            let (creator_fee, dev_fee) = calculate_fees(sol_amount);
+            assert_eq!(creator_fee, sol_amount / 200);
+            assert_eq!(dev_fee, sol_amount / 200);
+        }
+    }
+
+    #[test]
+    fn test_token_allocation_matches_manual_math() {
+        assert_eq!(token_allocation(990_000_000, 33), 326_700_000);
+        assert_eq!(token_allocation(u64::MAX / 2, 50), (u64::MAX / 2) / 2);
+    }
+
     // ========================================================================
     // Percentage Validation Tests
     // ========================================================================
@@ -663,6 +5482,154 @@ mod tests {
         assert_eq!(DEV_WALLET.to_string(), dev_wallet_str);
     }
 
+    // ========================================================================
+    // Registry Pagination Tests
+    // ========================================================================
+
+    #[test]
+    fn test_registry_first_page() {
+        assert_eq!(registry_page_index(0), 0);
+        assert_eq!(registry_page_index(REGISTRY_PAGE_SIZE as u64 - 1), 0);
+    }
+
+    #[test]
+    fn test_registry_page_rollover() {
+        assert_eq!(registry_page_index(REGISTRY_PAGE_SIZE as u64), 1);
+        assert_eq!(registry_page_index(REGISTRY_PAGE_SIZE as u64 * 3 + 5), 3);
+    }
+
+    #[test]
+    fn test_registry_slot_within_page() {
+        let registry_index: u64 = REGISTRY_PAGE_SIZE as u64 * 2 + 7;
+        let slot = registry_index % REGISTRY_PAGE_SIZE as u64;
+        assert_eq!(slot, 7);
+    }
+
+    // ========================================================================
+    // Metadata Validation Tests
+    // ========================================================================
+
+    #[test]
+    fn test_metadata_valid() {
+        assert!(validate_metadata("Dog Coins", "DOGS", "https://example.com/dogs.json").is_ok());
+        assert!(validate_metadata("Dog Coins", "DOGS", "").is_ok());
+    }
+
+    #[test]
+    fn test_metadata_name_limits() {
+        assert!(validate_metadata("", "DOGS", "").is_err());
+        assert!(validate_metadata(&"a".repeat(MAX_NAME_LEN), "DOGS", "").is_ok());
+        assert!(validate_metadata(&"a".repeat(MAX_NAME_LEN + 1), "DOGS", "").is_err());
+    }
+
+    #[test]
+    fn test_metadata_symbol_limits() {
+        assert!(validate_metadata("Dog Coins", "", "").is_err());
+        assert!(validate_metadata("Dog Coins", &"D".repeat(MAX_SYMBOL_LEN + 1), "").is_err());
+    }
+
+    #[test]
+    fn test_metadata_uri_too_long() {
+        let uri = "a".repeat(MAX_URI_LEN + 1);
+        assert!(validate_metadata("Dog Coins", "DOGS", &uri).is_err());
+    }
+
+    // ========================================================================
+    // Close Accounting Tests
+    // ========================================================================
+
+    #[test]
+    fn test_close_lamports_rent_only() {
+        let (rent, dust) = split_close_lamports(2_039_280, 2_039_280);
+        assert_eq!(rent, 2_039_280);
+        assert_eq!(dust, 0);
+    }
+
+    #[test]
+    fn test_close_lamports_with_dust() {
+        let (rent, dust) = split_close_lamports(2_039_280 + 1_234, 2_039_280);
+        assert_eq!(rent, 2_039_280);
+        assert_eq!(dust, 1_234);
+    }
+
+    #[test]
+    fn test_close_lamports_below_rent() {
+        // Never report negative dust if the account is somehow under-funded
+        let (rent, dust) = split_close_lamports(1_000, 2_039_280);
+        assert_eq!(rent, 1_000);
+        assert_eq!(dust, 0);
+    }
+
+    // ========================================================================
+    // Liquidation Payout Tests
+    // ========================================================================
+
+    #[test]
+    fn test_liquidation_payout_pro_rata() {
+        // 25% of supply gets 25% of distributable SOL
+        let payout = liquidation_payout(250_000_000, 1_000_000_000, 800_000_000);
+        assert_eq!(payout, 200_000_000);
+    }
+
+    #[test]
+    fn test_liquidation_payout_last_holder_takes_all() {
+        let payout = liquidation_payout(333, 333, 1_000_001);
+        assert_eq!(payout, 1_000_001);
+    }
+
+    #[test]
+    fn test_liquidation_payout_sequential_claims_conserve_value() {
+        let mut total_supply: u64 = 1_000;
+        let mut distributable: u64 = 999_999;
+        let mut paid: u64 = 0;
+        for shares in [333u64, 333, 334] {
+            let payout = liquidation_payout(shares, total_supply, distributable);
+            paid += payout;
+            distributable -= payout;
+            total_supply -= shares;
+        }
+        assert_eq!(total_supply, 0);
+        assert_eq!(distributable, 0);
+        assert_eq!(paid, 999_999);
+    }
+
+    #[test]
+    fn test_liquidation_payout_zero_supply() {
+        assert_eq!(liquidation_payout(0, 0, 1_000), 0);
+    }
+
+    #[test]
+    fn test_lister_inactivity_period() {
+        assert_eq!(LISTER_INACTIVITY_SECONDS, 15_552_000);
+    }
+
+    // ========================================================================
+    // NAV Tests
+    // ========================================================================
+
+    #[test]
+    fn test_nav_one_to_one() {
+        // 1:1 minting keeps NAV at exactly one lamport per share
+        assert_eq!(nav_per_share(990_000_000, 990_000_000), NAV_SCALE);
+    }
+
+    #[test]
+    fn test_nav_empty_etf() {
+        assert_eq!(nav_per_share(0, 0), NAV_SCALE);
+        assert_eq!(nav_per_share(5_000, 0), NAV_SCALE);
+    }
+
+    #[test]
+    fn test_nav_with_surplus() {
+        // Donated dust raises NAV above par
+        assert_eq!(nav_per_share(1_500, 1_000), 1_500_000_000);
+    }
+
+    #[test]
+    fn test_nav_no_overflow() {
+        assert_eq!(nav_per_share(u64::MAX, 1), u64::MAX);
+    }
+
     // ========================================================================
     // Edge Case Tests
     // ========================================================================
@@ -750,4 +5717,297 @@ mod tests {
         let total_fees_paid = initial_sol - sol_returned;
         assert_eq!(total_fees_paid, 19_900_000); // ~0.02 SOL
     }
+
+    // ========================================================================
+    // Allowlist Tests
+    // ========================================================================
+
+    #[test]
+    fn test_merkle_parent_is_order_independent() {
+        let a = [1u8; 32];
+        let b = [2u8; 32];
+        assert_eq!(merkle_parent(&a, &b), merkle_parent(&b, &a));
+        assert_ne!(merkle_parent(&a, &b), merkle_parent(&a, &a));
+    }
+
+    #[test]
+    fn test_verify_merkle_proof() {
+        let wallets = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let leaves = wallets.map(|wallet| allowlist_leaf(&wallet));
+        let root = merkle_parent(&leaves[0], &leaves[1]);
+
+        assert!(verify_merkle_proof(leaves[0], &[leaves[1]], root));
+        assert!(verify_merkle_proof(leaves[1], &[leaves[0]], root));
+        // A single-wallet tree is its own root with an empty proof
+        assert!(verify_merkle_proof(leaves[0], &[], leaves[0]));
+        assert!(!verify_merkle_proof(leaves[0], &[], root));
+        assert!(!verify_merkle_proof(allowlist_leaf(&Pubkey::new_unique()), &[leaves[1]], root));
+    }
+
+    // ========================================================================
+    // Buy Limit Tests
+    // ========================================================================
+
+    #[test]
+    fn test_protocol_minimum_buy_pays_fees() {
+        // Anything smaller rounds both fees down to zero
+        assert_eq!(calculate_fees(PROTOCOL_MIN_BUY_LAMPORTS), (1, 1));
+        assert_eq!(calculate_fees(PROTOCOL_MIN_BUY_LAMPORTS - 1), (0, 0));
+
+        let limits = BuyLimits::default();
+        assert_eq!(
+            check_buy_limits(&limits, PROTOCOL_MIN_BUY_LAMPORTS - 1, 0).unwrap_err(),
+            ErrorCode::BuyBelowMinimum.into()
+        );
+        assert!(check_buy_limits(&limits, PROTOCOL_MIN_BUY_LAMPORTS, 0).is_ok());
+        assert_eq!(
+            check_buy_limits(&limits, PROTOCOL_MAX_BUY_LAMPORTS + 1, 0).unwrap_err(),
+            ErrorCode::BuyAboveMaximum.into()
+        );
+    }
+
+    #[test]
+    fn test_etf_buy_limits() {
+        let limits = BuyLimits {
+            min_buy_lamports: 1_000,
+            max_buy_lamports: 1_000_000,
+            max_total_supply: 2_000_000,
+        };
+        assert_eq!(check_buy_limits(&limits, 999, 0).unwrap_err(), ErrorCode::BuyBelowMinimum.into());
+        assert_eq!(
+            check_buy_limits(&limits, 1_000_001, 0).unwrap_err(),
+            ErrorCode::BuyAboveMaximum.into()
+        );
+
+        // 1_000_000 mints 990_000; the cap counts minted and pending supply
+        assert!(check_buy_limits(&limits, 1_000_000, 1_010_000).is_ok());
+        assert_eq!(
+            check_buy_limits(&limits, 1_000_000, 1_010_001).unwrap_err(),
+            ErrorCode::MaxSupplyExceeded.into()
+        );
+        assert_eq!(
+            check_buy_limits(&limits, 1_000, u64::MAX).unwrap_err(),
+            ErrorCode::MaxSupplyExceeded.into()
+        );
+    }
+
+    #[test]
+    fn test_validate_buy_limits() {
+        assert!(validate_buy_limits(&BuyLimits::default()).is_ok());
+        assert!(validate_buy_limits(&BuyLimits { min_buy_lamports: 500, max_buy_lamports: 500, max_total_supply: 0 }).is_ok());
+        // No buy could satisfy both bounds
+        assert!(validate_buy_limits(&BuyLimits { min_buy_lamports: 501, max_buy_lamports: 500, max_total_supply: 0 }).is_err());
+        assert!(validate_buy_limits(&BuyLimits { min_buy_lamports: 0, max_buy_lamports: 100, max_total_supply: 0 }).is_err());
+        // Above the protocol limits
+        assert!(validate_buy_limits(&BuyLimits { max_buy_lamports: PROTOCOL_MAX_BUY_LAMPORTS + 1, ..Default::default() }).is_err());
+        assert!(validate_buy_limits(&BuyLimits { max_total_supply: PROTOCOL_MAX_TOTAL_SUPPLY + 1, ..Default::default() }).is_err());
+    }
+
+    // ========================================================================
+    // Hold Period Tests
+    // ========================================================================
+
+    #[test]
+    fn test_early_exit_fee() {
+        assert_eq!(early_exit_fee(1_000_000_000, 0), 0);
+        assert_eq!(early_exit_fee(1_000_000_000, 250), 25_000_000);
+        assert_eq!(early_exit_fee(1_000_000_000, MAX_EARLY_EXIT_FEE_BPS), 100_000_000);
+        // No overflow on the largest amounts
+        assert_eq!(early_exit_fee(u64::MAX, 10_000), u64::MAX);
+    }
+
+    // ========================================================================
+    // Limit Order Tests
+    // ========================================================================
+
+    #[test]
+    fn test_order_triggered() {
+        assert!(order_triggered(OrderSide::Buy, NAV_SCALE, NAV_SCALE));
+        assert!(order_triggered(OrderSide::Buy, NAV_SCALE - 1, NAV_SCALE));
+        assert!(!order_triggered(OrderSide::Buy, NAV_SCALE + 1, NAV_SCALE));
+
+        assert!(order_triggered(OrderSide::Sell, NAV_SCALE, NAV_SCALE));
+        assert!(order_triggered(OrderSide::Sell, NAV_SCALE + 1, NAV_SCALE));
+        assert!(!order_triggered(OrderSide::Sell, NAV_SCALE - 1, NAV_SCALE));
+    }
+
+    // ========================================================================
+    // Exit Trigger Tests
+    // ========================================================================
+
+    #[test]
+    fn test_exit_triggered() {
+        let (stop, take) = (NAV_SCALE / 2, NAV_SCALE * 2);
+        assert_eq!(exit_triggered(NAV_SCALE, stop, take), None);
+        assert_eq!(exit_triggered(stop, stop, take), Some(ExitKind::StopLoss));
+        assert_eq!(exit_triggered(take + 1, stop, take), Some(ExitKind::TakeProfit));
+
+        // A zero threshold never fires, not even at a zero NAV
+        assert_eq!(exit_triggered(0, 0, take), None);
+        assert_eq!(exit_triggered(u64::MAX, stop, 0), None);
+    }
+
+    // ========================================================================
+    // NAV History Tests
+    // ========================================================================
+
+    fn snapshot(timestamp: i64, nav_per_share: u64) -> NavSnapshot {
+        NavSnapshot { timestamp, nav_per_share, total_value: 0 }
+    }
+
+    #[test]
+    fn test_time_weighted_nav() {
+        assert_eq!(time_weighted_nav(&[], 100, 50), None);
+        assert_eq!(time_weighted_nav(&[snapshot(200, 5)], 100, 50), None);
+        assert_eq!(time_weighted_nav(&[snapshot(100, 5)], 100, 50), Some((5, 0)));
+
+        // 1_000 for 30s then 2_000 for 10s; ring order does not matter
+        let snapshots = [snapshot(130, 2_000), snapshot(100, 1_000)];
+        assert_eq!(time_weighted_nav(&snapshots, 140, 100), Some((1_250, 40)));
+
+        // Only the window counts: 1_000 for 10s then 2_000 for 10s
+        assert_eq!(time_weighted_nav(&snapshots, 140, 20), Some((1_500, 20)));
+    }
+
+    // ========================================================================
+    // Leaderboard Tests
+    // ========================================================================
+
+    #[test]
+    fn test_snapshot_at() {
+        let snapshots = [snapshot(7_200, 2), snapshot(0, 1), snapshot(20_000, 3)];
+        assert_eq!(snapshot_at(&snapshots, 7_199).map(|s| s.nav_per_share), Some(1));
+        assert_eq!(snapshot_at(&snapshots, 7_200).map(|s| s.nav_per_share), Some(2));
+        // Too stale to stand for the boundary
+        assert_eq!(snapshot_at(&snapshots, 19_999), None);
+        assert_eq!(snapshot_at(&snapshots, -1), None);
+    }
+
+    #[test]
+    fn test_boundary_nav() {
+        let hour = NAV_SNAPSHOT_INTERVAL_SECONDS;
+        let boundary = 100 * hour;
+        let start = boundary - LEADERBOARD_TWAP_SECONDS;
+        // Hourly snapshots, the NAV doubling halfway through the window
+        let hourly: Vec<NavSnapshot> = (0..=6)
+            .map(|i| snapshot(start + i * hour, if i < 3 { 1_000 } else { 2_000 }))
+            .collect();
+        let (nav, last) = boundary_nav(&hourly, boundary).unwrap();
+        assert_eq!(nav, 1_500);
+        assert_eq!(last.timestamp, boundary);
+
+        // The window must open on a snapshot
+        assert_eq!(boundary_nav(&hourly[1..], boundary), None);
+        // A snapshot in effect from before the window covers its start
+        let mut early = hourly.clone();
+        early[0].timestamp = start - 10 * hour;
+        assert!(boundary_nav(&early, boundary).is_some());
+        // No gap inside the window may exceed the tolerance
+        let gappy: Vec<NavSnapshot> = [&hourly[..2], &hourly[4..]].concat();
+        assert_eq!(boundary_nav(&gappy, boundary), None);
+        // A lone snapshot just before the boundary does not stand for it
+        let lone = [snapshot(start - hour, 1_000), snapshot(boundary - 1, 5_000)];
+        assert_eq!(boundary_nav(&lone, boundary), None);
+    }
+
+    #[test]
+    fn test_nav_return_bps() {
+        assert_eq!(nav_return_bps(NAV_SCALE, NAV_SCALE * 3 / 2), Some(5_000));
+        assert_eq!(nav_return_bps(NAV_SCALE, NAV_SCALE / 4), Some(-7_500));
+        assert_eq!(nav_return_bps(NAV_SCALE, NAV_SCALE), Some(0));
+        assert_eq!(nav_return_bps(0, NAV_SCALE), None);
+        assert_eq!(nav_return_bps(1, u64::MAX), None);
+    }
+
+    #[test]
+    fn test_leaderboard_rank() {
+        let entry = |return_bps| LeaderboardEntry {
+            etf: Pubkey::new_unique(),
+            lister: Pubkey::new_unique(),
+            return_bps,
+            start_nav: NAV_SCALE,
+            end_nav: NAV_SCALE,
+        };
+        let entries = [entry(500), entry(100), entry(-200)];
+        assert_eq!(leaderboard_rank(&entries, 600), 0);
+        assert_eq!(leaderboard_rank(&entries, 100), 2);
+        assert_eq!(leaderboard_rank(&entries, -300), 3);
+        assert_eq!(leaderboard_rank(&[], -300), 0);
+    }
+
+    // ========================================================================
+    // Treasury Tests
+    // ========================================================================
+
+    #[test]
+    fn test_treasury_is_its_pda() {
+        assert_eq!(Pubkey::find_program_address(&[b"treasury"], &ID).0, TREASURY);
+    }
+
+    // ========================================================================
+    // Fork Tests
+    // ========================================================================
+
+    #[test]
+    fn test_split_creator_fee() {
+        assert_eq!(split_creator_fee(5_000_000, 0), (5_000_000, 0));
+        assert_eq!(split_creator_fee(5_000_000, 2_000), (4_000_000, 1_000_000));
+        assert_eq!(split_creator_fee(1, MAX_PARENT_FEE_BPS), (1, 0));
+    }
+
+    #[test]
+    fn test_fork_token_list() {
+        let parent: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        let extra = Pubkey::new_unique();
+
+        assert_eq!(fork_token_list(&parent, &[], &[]).unwrap(), parent);
+        assert_eq!(
+            fork_token_list(&parent, &[parent[1]], &[extra]).unwrap(),
+            vec![parent[0], parent[2], extra]
+        );
+        assert!(fork_token_list(&parent, &[extra], &[]).is_err());
+        assert!(fork_token_list(&parent, &[], &[parent[0]]).is_err());
+        assert!(fork_token_list(&parent, &[], &[extra, extra]).is_err());
+        // Re-adding a removed token is a no-op change, but allowed
+        assert!(fork_token_list(&parent, &[parent[0]], &[parent[0]]).is_ok());
+        assert!(fork_token_list(&parent, &parent, &[]).is_err());
+    }
+
+    #[test]
+    fn test_creator_rewards() {
+        assert_eq!(creator_rewards(900, 2), vec![600, 300]);
+        assert_eq!(creator_rewards(1_000, 1), vec![1_000]);
+        assert!(creator_rewards(1_000, 0).is_empty());
+        // Dust stays behind
+        let rewards = creator_rewards(1_000, 3);
+        assert_eq!(rewards, vec![500, 333, 166]);
+        assert!(rewards.iter().sum::<u64>() <= 1_000);
+        let rewards = creator_rewards(u64::MAX, LEADERBOARD_SIZE);
+        assert!(rewards.windows(2).all(|pair| pair[0] > pair[1]));
+    }
+
+    #[test]
+    fn test_token_value() {
+        assert_eq!(token_value(0, 5 * PRICE_SCALE), 0);
+        assert_eq!(token_value(1_000, PRICE_SCALE), 1_000);
+        assert_eq!(token_value(1_000, PRICE_SCALE / 4), 250);
+        assert_eq!(token_value(u64::MAX, u64::MAX), u64::MAX);
+    }
+
+    #[test]
+    fn test_shares_for_value() {
+        // Nothing to price against: 1:1
+        assert_eq!(shares_for_value(1_000, 0, 0), 1_000);
+        assert_eq!(shares_for_value(1_000, 500, 0), 1_000);
+        // NAV of 2 lamports per share halves the shares minted
+        assert_eq!(shares_for_value(1_000, 500, 1_000), 500);
+        assert_eq!(shares_for_value(u64::MAX, u64::MAX, 1), u64::MAX);
+    }
+
+    #[test]
+    fn test_settlement_floor() {
+        assert_eq!(settlement_floor(0), 0);
+        assert_eq!(settlement_floor(10_000), 10_000 - MAX_SETTLEMENT_SLIPPAGE_BPS);
+        assert!(settlement_floor(u64::MAX) < u64::MAX);
+    }
 }
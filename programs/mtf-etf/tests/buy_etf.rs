@@ -0,0 +1,257 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::*;
+use mtf_etf::{
+    calculate_fees, token_allocation, ETFBoughtEvent, ETFSoldEvent, ErrorCode, FeeTransferEvent, FeeType, Position,
+    TokenPurchaseEvent, TREASURY, NAV_SCALE,
+};
+
+#[test]
+fn test_buy_etf_moves_lamports_and_mints_shares() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 2);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    let sol_amount = 2 * LAMPORTS_PER_SOL;
+
+    let etf_before = harness.lamports(&etf.address);
+    let lister_before = harness.lamports(&etf.lister);
+    let dev_before = harness.lamports(&TREASURY);
+    let total_before = harness.total_lamports();
+
+    buy(&mut harness, &etf, investor, sol_amount).unwrap();
+
+    let (creator_fee, dev_fee) = calculate_fees(sol_amount);
+    let sol_after_fees = sol_amount - creator_fee - dev_fee;
+    assert_eq!(creator_fee, 10_000_000);
+    assert_eq!(dev_fee, 10_000_000);
+
+    let position_rent = harness.lamports(&position_address(&etf.address, &investor));
+    assert!(position_rent > 0);
+    assert_eq!(
+        harness.lamports(&investor),
+        10 * LAMPORTS_PER_SOL - sol_amount - position_rent
+    );
+    assert_eq!(harness.lamports(&etf.address), etf_before + sol_after_fees);
+    assert_eq!(harness.lamports(&etf.lister), lister_before + creator_fee);
+    assert_eq!(harness.lamports(&TREASURY), dev_before + dev_fee);
+    assert_eq!(harness.total_lamports(), total_before);
+
+    assert_eq!(etf_state(&harness, &etf.address).total_supply, sol_after_fees);
+    let position: Position = harness
+        .anchor_account(&position_address(&etf.address, &investor))
+        .unwrap();
+    assert_eq!(position.etf, etf.address);
+    assert_eq!(position.investor, investor);
+    assert_eq!(position.shares, sol_after_fees);
+}
+
+#[test]
+fn test_buy_etf_emits_fee_and_purchase_events() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 3);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    let sol_amount = LAMPORTS_PER_SOL;
+
+    let outcome = harness
+        .process_instruction(
+            buy_etf_ix(etf.address, etf.lister, investor, TREASURY, sol_amount, vec![50, 30, 20]),
+            &[investor],
+        )
+        .unwrap();
+
+    let (creator_fee, dev_fee) = calculate_fees(sol_amount);
+    let fees = outcome.events::<FeeTransferEvent>();
+    assert_eq!(fees.len(), 2);
+    assert_eq!(fees[0].recipient, etf.lister);
+    assert_eq!(fees[0].amount, creator_fee);
+    assert!(fees[0].fee_type == FeeType::Creator);
+    assert_eq!(fees[1].recipient, TREASURY);
+    assert_eq!(fees[1].amount, dev_fee);
+    assert!(fees[1].fee_type == FeeType::Dev);
+
+    let sol_after_fees = sol_amount - creator_fee - dev_fee;
+    let purchases = outcome.events::<TokenPurchaseEvent>();
+    assert_eq!(purchases.len(), 3);
+    for ((event, mint), percentage) in purchases.iter().zip(&etf.mints).zip([50u8, 30, 20]) {
+        assert_eq!(event.etf_address, etf.address);
+        assert_eq!(event.investor, investor);
+        assert_eq!(event.token_address, *mint);
+        assert_eq!(event.percentage, percentage);
+        assert_eq!(event.sol_amount, token_allocation(sol_after_fees, percentage));
+        assert_eq!(event.timestamp, harness.clock().unix_timestamp);
+    }
+}
+
+#[test]
+fn test_buy_etf_emits_trade_summary() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 2);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    let sol_amount = 2 * LAMPORTS_PER_SOL;
+
+    let outcome = buy(&mut harness, &etf, investor, sol_amount).unwrap();
+
+    let (creator_fee, dev_fee) = calculate_fees(sol_amount);
+    let events = outcome.events::<ETFBoughtEvent>();
+    assert_eq!(events.len(), 1);
+    let event = &events[0];
+    assert_eq!(event.etf_address, etf.address);
+    assert_eq!(event.investor, investor);
+    assert_eq!(event.sequence, 1);
+    assert_eq!(event.sol_amount, sol_amount);
+    assert_eq!(event.shares_minted, sol_amount - creator_fee - dev_fee);
+    assert_eq!(event.creator_fee, creator_fee);
+    assert_eq!(event.dev_fee, dev_fee);
+    assert_eq!(event.total_supply, etf_state(&harness, &etf.address).total_supply);
+    assert_eq!(event.nav_per_share, NAV_SCALE);
+    assert_eq!(event.timestamp, harness.clock().unix_timestamp);
+}
+
+#[test]
+fn test_trade_sequence_counts_buys_and_sells() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let other = create_etf(&mut harness, 1);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    assert_eq!(etf_state(&harness, &etf.address).trade_sequence, 0);
+
+    let mut sequences = Vec::new();
+    for _ in 0..3 {
+        let outcome = buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+        sequences.push(outcome.events::<ETFBoughtEvent>()[0].sequence);
+    }
+    let outcome = sell(&mut harness, &etf, investor, 1_000).unwrap();
+    sequences.push(outcome.events::<ETFSoldEvent>()[0].sequence);
+    assert_eq!(sequences, vec![1, 2, 3, 4]);
+    assert_eq!(etf_state(&harness, &etf.address).trade_sequence, 4);
+
+    // Sequences are per ETF; a failed trade does not consume one
+    assert_error(sell(&mut harness, &etf, investor, u64::MAX), ErrorCode::InsufficientShares);
+    assert_eq!(etf_state(&harness, &etf.address).trade_sequence, 4);
+    let outcome = buy(&mut harness, &other, investor, LAMPORTS_PER_SOL).unwrap();
+    assert_eq!(outcome.events::<ETFBoughtEvent>()[0].sequence, 1);
+}
+
+#[test]
+fn test_buy_etf_accumulates_position() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    let other = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+    buy(&mut harness, &etf, other, 3 * LAMPORTS_PER_SOL).unwrap();
+
+    assert_eq!(shares(&harness, &etf.address, &investor), 2 * 990_000_000);
+    assert_eq!(shares(&harness, &etf.address, &other), 3 * 990_000_000);
+    assert_eq!(etf_state(&harness, &etf.address).total_supply, 5 * 990_000_000);
+}
+
+#[test]
+fn test_buy_etf_zero_amount() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let investor = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+
+    assert_error(buy(&mut harness, &etf, investor, 0), ErrorCode::InvalidAmount);
+}
+
+#[test]
+fn test_buy_etf_percentage_count_mismatch() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 2);
+    let investor = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+
+    let result = harness.process_instruction(
+        buy_etf_ix(etf.address, etf.lister, investor, TREASURY, 1_000_000, vec![100]),
+        &[investor],
+    );
+    assert_error(result, ErrorCode::InvalidTokenPercentages);
+}
+
+#[test]
+fn test_buy_etf_percentages_must_sum_to_100() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 2);
+    let investor = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+
+    for percentages in [vec![50, 49], vec![60, 50], vec![255, 255]] {
+        let result = harness.process_instruction(
+            buy_etf_ix(etf.address, etf.lister, investor, TREASURY, 1_000_000, percentages),
+            &[investor],
+        );
+        assert_error(result, ErrorCode::InvalidTokenPercentages);
+    }
+}
+
+#[test]
+fn test_buy_etf_wrong_dev_wallet() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let investor = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+    let impostor = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+
+    let result = harness.process_instruction(
+        buy_etf_ix(etf.address, etf.lister, investor, impostor, 1_000_000, vec![100]),
+        &[investor],
+    );
+    assert_error(result, ErrorCode::InvalidDevWallet);
+}
+
+#[test]
+fn test_buy_etf_wrong_lister_account() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let investor = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+    let impostor = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+
+    let result = harness.process_instruction(
+        buy_etf_ix(etf.address, impostor, investor, TREASURY, 1_000_000, vec![100]),
+        &[investor],
+    );
+    assert_error(result, ErrorCode::InvalidListerAccount);
+}
+
+#[test]
+fn test_buy_etf_while_liquidating() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let investor = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+    harness
+        .process_instruction(begin_liquidation_ix(etf.address, etf.lister), &[etf.lister])
+        .unwrap();
+
+    assert_error(buy(&mut harness, &etf, investor, 1_000_000), ErrorCode::EtfLiquidating);
+}
+
+#[test]
+fn test_buy_etf_investor_cannot_afford() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let investor = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+    let lamports_before = harness.lamports(&investor);
+
+    // The System program rejects the transfer; nothing is committed
+    let result = buy(&mut harness, &etf, investor, 2 * LAMPORTS_PER_SOL);
+    assert!(result.is_err());
+    assert_eq!(harness.lamports(&investor), lamports_before);
+    assert!(harness.account(&position_address(&etf.address, &investor)).is_none());
+}
+
+#[test]
+fn test_buy_etf_requires_investor_signature() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let investor = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+
+    let result = harness.process_instruction(
+        buy_etf_ix(etf.address, etf.lister, investor, TREASURY, 1_000_000, vec![100]),
+        &[Pubkey::new_unique()],
+    );
+    assert_eq!(
+        result.unwrap_err().error,
+        program_harness::HarnessError::MissingSignature(investor)
+    );
+}
@@ -0,0 +1,302 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_spl::token_2022::spl_token_2022;
+use anchor_spl::token_2022::spl_token_2022::extension::StateWithExtensions;
+use common::*;
+use mtf_etf::{ETFClosedEvent, ErrorCode, Registry, RegistryPage, TokenRecovered};
+use program_harness::Harness;
+
+// ETF vault plus the lister's token account for every basket mint
+fn fund_vaults(harness: &mut Harness, etf: &TestEtf, amounts: &[u64]) -> Vec<(Pubkey, Pubkey)> {
+    let vaults = lister_vaults(etf);
+    for ((mint, amount), (vault, destination)) in etf.mints.iter().zip(amounts).zip(&vaults) {
+        harness.create_token_account(*vault, mint, &etf.address, *amount);
+        harness.create_token_account(*destination, mint, &etf.lister, 0);
+    }
+    vaults
+}
+
+fn close(harness: &mut Harness, etf: &TestEtf, vaults: &[(Pubkey, Pubkey)]) -> TxResult {
+    harness.process_instruction(
+        close_etf_ix(etf.address, etf.lister, etf.registry_index, vaults),
+        &[etf.lister],
+    )
+}
+
+#[test]
+fn test_close_etf_returns_rent_and_tombstones_registry() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let etf_rent = harness.lamports(&etf.address);
+    let lister_before = harness.lamports(&etf.lister);
+    let total_before = harness.total_lamports();
+
+    let outcome = close(&mut harness, &etf, &lister_vaults(&etf)).unwrap();
+
+    assert!(harness.account(&etf.address).is_none());
+    assert_eq!(harness.lamports(&etf.lister), lister_before + etf_rent);
+    assert_eq!(harness.total_lamports(), total_before);
+
+    let registry: Registry = harness.anchor_account(&registry_address()).unwrap();
+    assert_eq!(registry.etf_count, 1);
+    assert_eq!(registry.active_count, 0);
+    let page: RegistryPage = harness.anchor_account(&registry_page_address(0)).unwrap();
+    assert!(page.entries[0].closed);
+
+    let events = outcome.events::<ETFClosedEvent>();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].etf_address, etf.address);
+    assert_eq!(events[0].lister, etf.lister);
+    assert_eq!(events[0].rent_recovered, etf_rent);
+    assert_eq!(events[0].dust_recovered, 0);
+    assert_eq!(events[0].vault_rent_recovered, 0);
+    assert!(events[0].tokens_recovered.is_empty());
+}
+
+#[test]
+fn test_close_etf_after_round_trip() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 2);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+    let held = shares(&harness, &etf.address, &investor);
+    sell(&mut harness, &etf, investor, held).unwrap();
+
+    close(&mut harness, &etf, &lister_vaults(&etf)).unwrap();
+    assert!(harness.account(&etf.address).is_none());
+}
+
+#[test]
+fn test_close_etf_reports_dust() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let etf_rent = harness.lamports(&etf.address);
+    harness.airdrop(&etf.address, 12_345);
+    let lister_before = harness.lamports(&etf.lister);
+
+    let outcome = close(&mut harness, &etf, &lister_vaults(&etf)).unwrap();
+
+    assert_eq!(harness.lamports(&etf.lister), lister_before + etf_rent + 12_345);
+    let event = &outcome.events::<ETFClosedEvent>()[0];
+    assert_eq!(event.rent_recovered, etf_rent);
+    assert_eq!(event.dust_recovered, 12_345);
+}
+
+#[test]
+fn test_close_etf_sweeps_vaults() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 2);
+    let vaults = fund_vaults(&mut harness, &etf, &[500, 0]);
+    let vault_rent: u64 = vaults.iter().map(|(vault, _)| harness.lamports(vault)).sum();
+    let etf_rent = harness.lamports(&etf.address);
+    let lister_before = harness.lamports(&etf.lister);
+
+    let outcome = close(&mut harness, &etf, &vaults).unwrap();
+
+    for (vault, _) in &vaults {
+        assert!(harness.account(vault).is_none());
+    }
+    assert_eq!(harness.token_balance(&vaults[0].1), Some(500));
+    assert_eq!(harness.token_balance(&vaults[1].1), Some(0));
+    assert_eq!(harness.lamports(&etf.lister), lister_before + etf_rent + vault_rent);
+
+    let event = &outcome.events::<ETFClosedEvent>()[0];
+    assert_eq!(event.vault_rent_recovered, vault_rent);
+    assert_eq!(
+        event.tokens_recovered,
+        vec![
+            TokenRecovered { mint: etf.mints[0], amount: 500 },
+            TokenRecovered { mint: etf.mints[1], amount: 0 },
+        ]
+    );
+}
+
+#[test]
+fn test_close_etf_with_supply() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+
+    assert_error(close(&mut harness, &etf, &lister_vaults(&etf)), ErrorCode::CannotCloseWithSupply);
+    assert!(harness.account(&etf.address).is_some());
+}
+
+#[test]
+fn test_close_etf_rejects_other_signer() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let stranger = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+
+    // `has_one = lister` rejects this before the handler's Unauthorized check
+    let result = harness.process_instruction(
+        close_etf_ix(etf.address, stranger, etf.registry_index, &lister_vaults(&etf)),
+        &[stranger],
+    );
+    assert_error(result, anchor_lang::error::ErrorCode::ConstraintHasOne);
+}
+
+#[test]
+fn test_close_etf_registry_entry_mismatch() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    tamper::<RegistryPage>(&mut harness, &registry_page_address(0), |page| {
+        page.entries[0].etf = Pubkey::new_unique();
+    });
+
+    assert_error(close(&mut harness, &etf, &lister_vaults(&etf)), ErrorCode::InvalidRegistryPage);
+}
+
+#[test]
+fn test_close_etf_registry_entry_missing() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    tamper::<RegistryPage>(&mut harness, &registry_page_address(0), |page| {
+        page.entries.clear();
+    });
+
+    assert_error(close(&mut harness, &etf, &lister_vaults(&etf)), ErrorCode::InvalidRegistryPage);
+}
+
+#[test]
+fn test_close_etf_unpaired_vault_accounts() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let vaults = fund_vaults(&mut harness, &etf, &[1]);
+
+    let mut ix = close_etf_ix(etf.address, etf.lister, etf.registry_index, &vaults);
+    ix.accounts.pop();
+    let result = harness.process_instruction(ix, &[etf.lister]);
+    assert_error(result, ErrorCode::InvalidVaultAccount);
+}
+
+#[test]
+fn test_close_etf_vault_not_owned_by_etf() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let vaults = lister_vaults(&etf);
+    harness.create_token_account(vaults[0].0, &etf.mints[0], &etf.lister, 1);
+    harness.create_token_account(vaults[0].1, &etf.mints[0], &etf.lister, 0);
+
+    assert_error(close(&mut harness, &etf, &vaults), ErrorCode::InvalidVaultAccount);
+}
+
+#[test]
+fn test_close_etf_vault_for_foreign_mint() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let mint = Pubkey::new_unique();
+    harness.create_mint(mint, &etf.lister, 6);
+    let vault = vault_address(&etf.address, &mint);
+    let destination = Pubkey::new_unique();
+    harness.create_token_account(vault, &mint, &etf.address, 1);
+    harness.create_token_account(destination, &mint, &etf.lister, 0);
+
+    assert_error(close(&mut harness, &etf, &[(vault, destination)]), ErrorCode::InvalidVaultAccount);
+}
+
+#[test]
+fn test_close_etf_destination_not_lister() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let mut vaults = fund_vaults(&mut harness, &etf, &[1]);
+    let thief = Pubkey::new_unique();
+    harness.create_token_account(thief, &etf.mints[0], &Pubkey::new_unique(), 0);
+    vaults[0].1 = thief;
+
+    assert_error(close(&mut harness, &etf, &vaults), ErrorCode::InvalidVaultAccount);
+}
+
+#[test]
+fn test_close_etf_requires_every_basket_vault() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 2);
+    let vaults = fund_vaults(&mut harness, &etf, &[0, 700]);
+
+    // Leaving out the funded vault would orphan its tokens
+    assert_error(close(&mut harness, &etf, &vaults[..1]), ErrorCode::InvalidVaultAccount);
+    assert_error(close(&mut harness, &etf, &[]), ErrorCode::InvalidVaultAccount);
+    assert_eq!(harness.token_balance(&vaults[1].0), Some(700));
+}
+
+#[test]
+fn test_close_etf_vaults_in_basket_order() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 2);
+    let vaults = fund_vaults(&mut harness, &etf, &[1, 2]);
+
+    assert_error(close(&mut harness, &etf, &[vaults[1], vaults[0]]), ErrorCode::InvalidVaultAccount);
+}
+
+#[test]
+fn test_close_etf_rejects_non_canonical_vault() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let mut vaults = fund_vaults(&mut harness, &etf, &[900]);
+
+    // An ETF-owned account that is not the ATA cannot stand in for the vault
+    let decoy = Pubkey::new_unique();
+    harness.create_token_account(decoy, &etf.mints[0], &etf.address, 0);
+    let canonical = vaults[0].0;
+    vaults[0].0 = decoy;
+
+    assert_error(close(&mut harness, &etf, &vaults), ErrorCode::InvalidVaultAccount);
+    assert_eq!(harness.token_balance(&canonical), Some(900));
+}
+
+#[test]
+fn test_close_etf_skips_uncreated_vaults() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 2);
+    let mut vaults = lister_vaults(&etf);
+    harness.create_token_account(vaults[1].0, &etf.mints[1], &etf.address, 250);
+    harness.create_token_account(vaults[1].1, &etf.mints[1], &etf.lister, 0);
+    vaults[0].1 = Pubkey::new_unique();
+
+    let outcome = close(&mut harness, &etf, &vaults).unwrap();
+
+    assert!(harness.account(&vaults[1].0).is_none());
+    assert_eq!(harness.token_balance(&vaults[1].1), Some(250));
+    let event = &outcome.events::<ETFClosedEvent>()[0];
+    assert_eq!(event.tokens_recovered, vec![TokenRecovered { mint: etf.mints[1], amount: 250 }]);
+}
+
+#[test]
+fn test_close_etf_leaves_empty_share_mint() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    harness
+        .process_instruction(create_share_mint_ix(etf.address, etf.lister), &[etf.lister])
+        .unwrap();
+
+    close(&mut harness, &etf, &lister_vaults(&etf)).unwrap();
+
+    // Share mints have no close authority; the empty mint stays under the ETF PDA
+    let mint = harness.account(&share_mint_address(&etf.address)).unwrap();
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint.data).unwrap().base;
+    assert_eq!(mint.supply, 0);
+    assert_eq!(mint.mint_authority, COption::Some(etf.address));
+}
+
+#[test]
+fn test_close_etf_with_wrapped_shares() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+    harness
+        .process_instruction(create_share_mint_ix(etf.address, investor), &[investor])
+        .unwrap();
+    let wrapped = Pubkey::new_unique();
+    harness.create_token_account(wrapped, &share_mint_address(&etf.address), &investor, 0);
+    harness
+        .process_instruction(wrap_shares_ix(etf.address, investor, wrapped, 1_000), &[investor])
+        .unwrap();
+
+    // Wrapped shares are in total_supply; forge it to reach the mint check
+    tamper::<mtf_etf::ETF>(&mut harness, &etf.address, |etf| etf.total_supply = 0);
+
+    assert_error(close(&mut harness, &etf, &lister_vaults(&etf)), ErrorCode::SharesStillWrapped);
+}
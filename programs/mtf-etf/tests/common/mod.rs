@@ -0,0 +1,1286 @@
+// Shared setup and instruction builders for the in-process integration tests
+#![allow(dead_code)]
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::{system_program, AccountDeserialize, AccountSerialize, InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::get_associated_token_address;
+use mtf_etf::{
+    registry_page_index, EtfCategory, DEFAULT_REFUND_TIMEOUT_SECONDS, GOVERNANCE_AUTHORITY, ID, PRICE_SCALE, TREASURY,
+};
+use program_harness::{AccountState, Harness, TransactionError, TransactionOutcome};
+
+pub const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+
+// Feed price of every basket token in keeper scenarios: 100_000 lamports
+// per base unit
+pub const TOKEN_PRICE: u64 = 100_000 * PRICE_SCALE;
+
+pub type TxResult = Result<TransactionOutcome, TransactionError>;
+
+// Harness with mtf_etf deployed and the treasury created (no rewards share),
+// so dev fees have a rent-exempt account to land in
+pub fn setup() -> Harness {
+    let mut harness = Harness::new();
+    harness.add_program(ID, mtf_etf::entry);
+    harness.airdrop(&GOVERNANCE_AUTHORITY, LAMPORTS_PER_SOL);
+    harness
+        .process_instruction(configure_treasury_ix(GOVERNANCE_AUTHORITY, 0), &[GOVERNANCE_AUTHORITY])
+        .unwrap();
+    harness
+}
+
+pub fn funded_wallet(harness: &mut Harness, lamports: u64) -> Pubkey {
+    let wallet = Pubkey::new_unique();
+    harness.airdrop(&wallet, lamports);
+    wallet
+}
+
+pub fn governance(harness: &mut Harness) -> Pubkey {
+    if harness.lamports(&GOVERNANCE_AUTHORITY) < LAMPORTS_PER_SOL {
+        harness.airdrop(&GOVERNANCE_AUTHORITY, LAMPORTS_PER_SOL);
+    }
+    GOVERNANCE_AUTHORITY
+}
+
+// ============================================================================
+// PDAs
+// ============================================================================
+
+pub fn etf_address(lister: &Pubkey, etf_index: u8) -> Pubkey {
+    Pubkey::find_program_address(&[b"etf", lister.as_ref(), &[etf_index]], &ID).0
+}
+
+pub fn registry_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"registry"], &ID).0
+}
+
+pub fn registry_page_address(registry_index: u64) -> Pubkey {
+    let page = registry_page_index(registry_index);
+    Pubkey::find_program_address(&[b"registry_page", &page.to_le_bytes()], &ID).0
+}
+
+pub fn position_address(etf: &Pubkey, investor: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"position", etf.as_ref(), investor.as_ref()], &ID).0
+}
+
+pub fn pending_purchase_address(etf: &Pubkey, purchase_id: u64) -> Pubkey {
+    Pubkey::find_program_address(&[b"pending", etf.as_ref(), &purchase_id.to_le_bytes()], &ID).0
+}
+
+pub fn allowlist_pass_address(etf: &Pubkey, investor: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"allowlist", etf.as_ref(), investor.as_ref()], &ID).0
+}
+
+pub fn dca_plan_address(etf: &Pubkey, investor: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"dca", etf.as_ref(), investor.as_ref()], &ID).0
+}
+
+pub fn limit_order_address(etf: &Pubkey, owner: &Pubkey, order_id: u64) -> Pubkey {
+    Pubkey::find_program_address(&[b"order", etf.as_ref(), owner.as_ref(), &order_id.to_le_bytes()], &ID).0
+}
+
+pub fn exit_trigger_address(etf: &Pubkey, investor: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"exit", etf.as_ref(), investor.as_ref()], &ID).0
+}
+
+pub fn nav_history_address(etf: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"nav_history", etf.as_ref()], &ID).0
+}
+
+pub fn leaderboard_address(epoch_id: u64) -> Pubkey {
+    Pubkey::find_program_address(&[b"leaderboard", &epoch_id.to_le_bytes()], &ID).0
+}
+
+pub fn epoch_rewards_address(epoch_id: u64) -> Pubkey {
+    Pubkey::find_program_address(&[b"epoch_rewards", &epoch_id.to_le_bytes()], &ID).0
+}
+
+pub fn share_mint_address(etf: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"shares", etf.as_ref()], &ID).0
+}
+
+pub fn vault_address(etf: &Pubkey, mint: &Pubkey) -> Pubkey {
+    get_associated_token_address(etf, mint)
+}
+
+pub fn price_feed_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"price", mint.as_ref()], &ID).0
+}
+
+// ============================================================================
+// Instruction builders
+// ============================================================================
+
+pub struct Metadata {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub category: EtfCategory,
+}
+
+impl Default for Metadata {
+    fn default() -> Self {
+        Self {
+            name: "Dog Coins".to_string(),
+            symbol: "DOGS".to_string(),
+            uri: "https://example.com/dogs.json".to_string(),
+            category: EtfCategory::Meme,
+        }
+    }
+}
+
+pub fn initialize_etf_ix(
+    lister: Pubkey,
+    etf_index: u8,
+    registry_index: u64,
+    token_addresses: Vec<Pubkey>,
+    metadata: Metadata,
+) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::InitializeETF {
+            etf: etf_address(&lister, etf_index),
+            registry: registry_address(),
+            registry_page: registry_page_address(registry_index),
+            lister,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::InitializeEtf {
+            etf_index,
+            token_addresses,
+            name: metadata.name,
+            symbol: metadata.symbol,
+            uri: metadata.uri,
+            category: metadata.category,
+        }
+        .data(),
+    }
+}
+
+pub fn fork_etf_ix(
+    lister: Pubkey,
+    etf_index: u8,
+    parent: Pubkey,
+    registry_index: u64,
+    changes: mtf_etf::ForkChanges,
+    metadata: Metadata,
+) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::ForkETF {
+            etf: etf_address(&lister, etf_index),
+            parent,
+            registry: registry_address(),
+            registry_page: registry_page_address(registry_index),
+            lister,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::ForkEtf {
+            etf_index,
+            changes,
+            name: metadata.name,
+            symbol: metadata.symbol,
+            uri: metadata.uri,
+            category: metadata.category,
+        }
+        .data(),
+    }
+}
+
+pub fn migrate_etf_ix(etf: Pubkey, payer: Pubkey, registry_index: u64) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::MigrateETF {
+            etf,
+            registry: registry_address(),
+            registry_page: registry_page_address(registry_index),
+            payer,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::MigrateEtf {}.data(),
+    }
+}
+
+pub fn restore_legacy_position_ix(etf: Pubkey, investor: Pubkey, authority: Pubkey, shares: u64) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::RestoreLegacyPosition {
+            etf,
+            position: position_address(&etf, &investor),
+            investor,
+            authority,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::RestoreLegacyPosition { shares }.data(),
+    }
+}
+
+pub fn claim_parent_fees_ix(etf: Pubkey, parent_lister: Pubkey) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::ClaimParentFees { etf, parent_lister }.to_account_metas(None),
+        data: mtf_etf::instruction::ClaimParentFees {}.data(),
+    }
+}
+
+pub fn update_metadata_ix(etf: Pubkey, lister: Pubkey, metadata: Metadata) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::UpdateMetadata {
+            etf,
+            share_mint: share_mint_address(&etf),
+            lister,
+            token_program: anchor_spl::token_2022::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::UpdateMetadata {
+            name: metadata.name,
+            symbol: metadata.symbol,
+            uri: metadata.uri,
+            category: metadata.category,
+        }
+        .data(),
+    }
+}
+
+pub fn buy_etf_ix(
+    etf: Pubkey,
+    lister_account: Pubkey,
+    investor: Pubkey,
+    dev_wallet: Pubkey,
+    sol_amount: u64,
+    token_percentages: Vec<u8>,
+) -> Instruction {
+    buy_etf_pending_ix(etf, lister_account, investor, dev_wallet, None, sol_amount, token_percentages)
+}
+
+// `pending_purchase` is required for keeper-settled ETFs
+pub fn buy_etf_pending_ix(
+    etf: Pubkey,
+    lister_account: Pubkey,
+    investor: Pubkey,
+    dev_wallet: Pubkey,
+    pending_purchase: Option<Pubkey>,
+    sol_amount: u64,
+    token_percentages: Vec<u8>,
+) -> Instruction {
+    buy_etf_gated_ix(etf, lister_account, investor, dev_wallet, pending_purchase, None, sol_amount, token_percentages)
+}
+
+// `gate_account` is required for gated ETFs
+#[allow(clippy::too_many_arguments)]
+pub fn buy_etf_gated_ix(
+    etf: Pubkey,
+    lister_account: Pubkey,
+    investor: Pubkey,
+    dev_wallet: Pubkey,
+    pending_purchase: Option<Pubkey>,
+    gate_account: Option<Pubkey>,
+    sol_amount: u64,
+    token_percentages: Vec<u8>,
+) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::BuyETF {
+            etf,
+            position: position_address(&etf, &investor),
+            investor,
+            lister_account,
+            dev_wallet,
+            system_program: system_program::ID,
+            pending_purchase,
+            gate_account,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::BuyEtf {
+            sol_amount,
+            token_percentages,
+        }
+        .data(),
+    }
+}
+
+// `transfers` are (keeper token account, vault) pairs in basket order
+pub fn settle_purchase_ix(
+    etf: Pubkey,
+    investor: Pubkey,
+    keeper: Pubkey,
+    purchase_id: u64,
+    legs: &[(Pubkey, Pubkey, Pubkey)],
+    token_amounts: Vec<u64>,
+) -> Instruction {
+    let mut accounts = mtf_etf::accounts::SettlePurchase {
+        etf,
+        pending_purchase: pending_purchase_address(&etf, purchase_id),
+        position: position_address(&etf, &investor),
+        investor,
+        keeper,
+        token_program: anchor_spl::token::ID,
+    }
+    .to_account_metas(None);
+    for (source, vault, price_feed) in legs {
+        accounts.push(AccountMeta::new(*source, false));
+        accounts.push(AccountMeta::new(*vault, false));
+        accounts.push(AccountMeta::new_readonly(*price_feed, false));
+    }
+    Instruction {
+        program_id: ID,
+        accounts,
+        data: mtf_etf::instruction::SettlePurchase { token_amounts }.data(),
+    }
+}
+
+pub fn set_keeper_ix(etf: Pubkey, lister: Pubkey, keeper: Option<Pubkey>, refund_timeout: i64) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::SetKeeper { etf, lister }.to_account_metas(None),
+        data: mtf_etf::instruction::SetKeeper { keeper, refund_timeout }.data(),
+    }
+}
+
+pub fn set_gate_ix(etf: Pubkey, lister: Pubkey, gate: mtf_etf::EtfGate) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::SetGate { etf, lister }.to_account_metas(None),
+        data: mtf_etf::instruction::SetGate { gate }.data(),
+    }
+}
+
+pub fn set_buy_limits_ix(etf: Pubkey, lister: Pubkey, limits: mtf_etf::BuyLimits) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::SetBuyLimits { etf, lister }.to_account_metas(None),
+        data: mtf_etf::instruction::SetBuyLimits { limits }.data(),
+    }
+}
+
+pub fn set_hold_period_ix(etf: Pubkey, lister: Pubkey, min_hold_seconds: i64, early_exit_fee_bps: u16) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::SetHoldPeriod { etf, lister }.to_account_metas(None),
+        data: mtf_etf::instruction::SetHoldPeriod { min_hold_seconds, early_exit_fee_bps }.data(),
+    }
+}
+
+pub fn verify_allowlist_ix(etf: Pubkey, investor: Pubkey, proof: Vec<[u8; 32]>) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::VerifyAllowlist {
+            etf,
+            allowlist_pass: allowlist_pass_address(&etf, &investor),
+            investor,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::VerifyAllowlist { proof }.data(),
+    }
+}
+
+pub fn refund_pending_purchase_ix(etf: Pubkey, investor: Pubkey, purchase_id: u64) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::RefundPendingPurchase {
+            etf,
+            pending_purchase: pending_purchase_address(&etf, purchase_id),
+            investor,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::RefundPendingPurchase {}.data(),
+    }
+}
+
+pub fn sell_etf_ix(
+    etf: Pubkey,
+    lister_account: Pubkey,
+    investor: Pubkey,
+    dev_wallet: Pubkey,
+    tokens_to_sell: u64,
+) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::SellETF {
+            etf,
+            position: position_address(&etf, &investor),
+            investor,
+            lister_account,
+            dev_wallet,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::SellEtf { tokens_to_sell }.data(),
+    }
+}
+
+// `vaults` are (vault, investor token account) pairs in basket order
+pub fn redeem_to_tokens_ix(
+    etf: Pubkey,
+    lister: Pubkey,
+    investor: Pubkey,
+    shares: u64,
+    vaults: &[(Pubkey, Pubkey)],
+) -> Instruction {
+    let mut accounts = mtf_etf::accounts::RedeemToTokens {
+        etf,
+        position: position_address(&etf, &investor),
+        investor,
+        lister_account: lister,
+        dev_wallet: TREASURY,
+        token_program: anchor_spl::token::ID,
+        system_program: system_program::ID,
+    }
+    .to_account_metas(None);
+    accounts.extend(vault_metas(vaults));
+
+    Instruction {
+        program_id: ID,
+        accounts,
+        data: mtf_etf::instruction::RedeemToTokens { shares }.data(),
+    }
+}
+
+pub fn close_etf_ix(etf: Pubkey, lister: Pubkey, registry_index: u64, vaults: &[(Pubkey, Pubkey)]) -> Instruction {
+    let mut accounts = mtf_etf::accounts::CloseETF {
+        etf,
+        registry: registry_address(),
+        registry_page: registry_page_address(registry_index),
+        share_mint: share_mint_address(&etf),
+        lister,
+        token_program: anchor_spl::token::ID,
+        system_program: system_program::ID,
+    }
+    .to_account_metas(None);
+    accounts.extend(vault_metas(vaults));
+
+    Instruction {
+        program_id: ID,
+        accounts,
+        data: mtf_etf::instruction::CloseEtf {}.data(),
+    }
+}
+
+pub fn create_dca_plan_ix(
+    etf: Pubkey,
+    investor: Pubkey,
+    amount_per_period: u64,
+    interval_seconds: i64,
+    token_percentages: Vec<u8>,
+    deposit: u64,
+) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::CreateDcaPlan {
+            etf,
+            dca_plan: dca_plan_address(&etf, &investor),
+            position: position_address(&etf, &investor),
+            investor,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::CreateDcaPlan {
+            amount_per_period,
+            interval_seconds,
+            token_percentages,
+            deposit,
+        }
+        .data(),
+    }
+}
+
+pub fn fund_dca_plan_ix(etf: Pubkey, investor: Pubkey, amount: u64) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::FundDcaPlan {
+            dca_plan: dca_plan_address(&etf, &investor),
+            investor,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::FundDcaPlan { amount }.data(),
+    }
+}
+
+pub fn execute_dca_ix(etf: Pubkey, lister: Pubkey, investor: Pubkey, caller: Pubkey, gate_account: Option<Pubkey>) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::ExecuteDca {
+            etf,
+            dca_plan: dca_plan_address(&etf, &investor),
+            position: position_address(&etf, &investor),
+            lister_account: lister,
+            dev_wallet: TREASURY,
+            caller,
+            gate_account,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::ExecuteDca {}.data(),
+    }
+}
+
+pub fn cancel_dca_plan_ix(etf: Pubkey, investor: Pubkey) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::CancelDcaPlan {
+            etf,
+            dca_plan: dca_plan_address(&etf, &investor),
+            investor,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::CancelDcaPlan {}.data(),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn place_limit_order_ix(
+    etf: Pubkey,
+    owner: Pubkey,
+    order_id: u64,
+    side: mtf_etf::OrderSide,
+    amount: u64,
+    target_nav: u64,
+    token_percentages: Vec<u8>,
+    deposit: u64,
+) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::PlaceLimitOrder {
+            etf,
+            order: limit_order_address(&etf, &owner, order_id),
+            position: position_address(&etf, &owner),
+            owner,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::PlaceLimitOrder {
+            order_id,
+            side,
+            amount,
+            target_nav,
+            token_percentages,
+            deposit,
+        }
+        .data(),
+    }
+}
+
+// `purchase_id` fills a buy on a keeper ETF into that pending purchase.
+// Price feeds and in-kind vault pairs are appended by the caller.
+pub fn fill_limit_order_ix(
+    etf: Pubkey,
+    lister: Pubkey,
+    owner: Pubkey,
+    order_id: u64,
+    filler: Pubkey,
+    gate_account: Option<Pubkey>,
+    purchase_id: Option<u64>,
+) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::FillLimitOrder {
+            etf,
+            order: limit_order_address(&etf, &owner, order_id),
+            position: position_address(&etf, &owner),
+            owner,
+            lister_account: lister,
+            dev_wallet: TREASURY,
+            filler,
+            gate_account,
+            pending_purchase: purchase_id.map(|id| pending_purchase_address(&etf, id)),
+            token_program: anchor_spl::token::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::FillLimitOrder {}.data(),
+    }
+}
+
+// `with_position` is required to cancel sell orders
+pub fn cancel_limit_order_ix(etf: Pubkey, owner: Pubkey, order_id: u64, with_position: bool) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::CancelLimitOrder {
+            etf,
+            order: limit_order_address(&etf, &owner, order_id),
+            position: with_position.then(|| position_address(&etf, &owner)),
+            owner,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::CancelLimitOrder {}.data(),
+    }
+}
+
+pub fn set_exit_trigger_ix(
+    etf: Pubkey,
+    investor: Pubkey,
+    stop_loss_nav: u64,
+    take_profit_nav: u64,
+    shares: u64,
+    deposit: u64,
+) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::SetExitTrigger {
+            etf,
+            exit_trigger: exit_trigger_address(&etf, &investor),
+            position: position_address(&etf, &investor),
+            investor,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::SetExitTrigger {
+            stop_loss_nav,
+            take_profit_nav,
+            shares,
+            deposit,
+        }
+        .data(),
+    }
+}
+
+// Price feeds and vault pairs are appended by the caller
+pub fn trigger_exit_ix(etf: Pubkey, lister: Pubkey, investor: Pubkey, caller: Pubkey) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::TriggerExit {
+            etf,
+            exit_trigger: exit_trigger_address(&etf, &investor),
+            position: position_address(&etf, &investor),
+            investor,
+            lister_account: lister,
+            dev_wallet: TREASURY,
+            caller,
+            token_program: anchor_spl::token::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::TriggerExit {}.data(),
+    }
+}
+
+pub fn cancel_exit_trigger_ix(etf: Pubkey, investor: Pubkey) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::CancelExitTrigger {
+            etf,
+            exit_trigger: exit_trigger_address(&etf, &investor),
+            investor,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::CancelExitTrigger {}.data(),
+    }
+}
+
+pub fn create_nav_history_ix(etf: Pubkey, payer: Pubkey) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::CreateNavHistory {
+            etf,
+            nav_history: nav_history_address(&etf),
+            payer,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::CreateNavHistory {}.data(),
+    }
+}
+
+pub fn snapshot_nav_ix(etf: Pubkey) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::SnapshotNav {
+            etf,
+            nav_history: nav_history_address(&etf),
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::SnapshotNav {}.data(),
+    }
+}
+
+pub fn create_leaderboard_epoch_ix(epoch_id: u64, payer: Pubkey) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::CreateLeaderboardEpoch {
+            leaderboard: leaderboard_address(epoch_id),
+            payer,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::CreateLeaderboardEpoch { epoch_id }.data(),
+    }
+}
+
+pub fn submit_epoch_return_ix(epoch_id: u64, etf: Pubkey) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::SubmitEpochReturn {
+            leaderboard: leaderboard_address(epoch_id),
+            etf,
+            nav_history: nav_history_address(&etf),
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::SubmitEpochReturn {}.data(),
+    }
+}
+
+pub fn configure_treasury_ix(authority: Pubkey, rewards_share_bps: u16) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::ConfigureTreasury {
+            treasury: TREASURY,
+            authority,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::ConfigureTreasury { rewards_share_bps }.data(),
+    }
+}
+
+pub fn fund_rewards_ix(funder: Pubkey, amount: u64) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::FundRewards {
+            treasury: TREASURY,
+            funder,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::FundRewards { amount }.data(),
+    }
+}
+
+pub fn withdraw_dev_fees_ix() -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::WithdrawDevFees {
+            treasury: TREASURY,
+            dev_wallet: mtf_etf::DEV_WALLET,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::WithdrawDevFees {}.data(),
+    }
+}
+
+pub fn finalize_epoch_ix(epoch_id: u64, payer: Pubkey) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::FinalizeEpoch {
+            leaderboard: leaderboard_address(epoch_id),
+            epoch_rewards: epoch_rewards_address(epoch_id),
+            treasury: TREASURY,
+            payer,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::FinalizeEpoch {}.data(),
+    }
+}
+
+pub fn claim_creator_reward_ix(epoch_id: u64, lister: Pubkey, rank: u8) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::ClaimCreatorReward {
+            epoch_rewards: epoch_rewards_address(epoch_id),
+            treasury: TREASURY,
+            lister,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::ClaimCreatorReward { rank }.data(),
+    }
+}
+
+pub fn create_share_mint_ix(etf: Pubkey, payer: Pubkey) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::CreateShareMint {
+            etf,
+            share_mint: share_mint_address(&etf),
+            payer,
+            token_program: anchor_spl::token_2022::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::CreateShareMint {}.data(),
+    }
+}
+
+pub fn wrap_shares_ix(etf: Pubkey, owner: Pubkey, destination: Pubkey, amount: u64) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::WrapShares {
+            etf,
+            position: position_address(&etf, &owner),
+            share_mint: share_mint_address(&etf),
+            destination,
+            owner,
+            token_program: anchor_spl::token_2022::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::WrapShares { amount }.data(),
+    }
+}
+
+pub fn unwrap_shares_ix(etf: Pubkey, owner: Pubkey, source: Pubkey, recipient: Pubkey, amount: u64) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::UnwrapShares {
+            etf,
+            share_mint: share_mint_address(&etf),
+            source,
+            owner,
+            recipient,
+            recipient_position: position_address(&etf, &recipient),
+            token_program: anchor_spl::token_2022::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::UnwrapShares { amount }.data(),
+    }
+}
+
+pub fn set_price_ix(authority: Pubkey, mint: Pubkey, price: u64) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::SetPrice {
+            price_feed: price_feed_address(&mint),
+            mint,
+            authority,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::SetPrice { price }.data(),
+    }
+}
+
+// Append the price feeds of `mints` to an instruction that values holdings
+pub fn with_price_feeds(mut ix: Instruction, mints: &[Pubkey]) -> Instruction {
+    ix.accounts.extend(
+        mints
+            .iter()
+            .map(|mint| AccountMeta::new_readonly(price_feed_address(mint), false)),
+    );
+    ix
+}
+
+// Append (vault, token account) pairs to an instruction that pays out in kind
+pub fn with_vault_pairs(mut ix: Instruction, vaults: &[(Pubkey, Pubkey)]) -> Instruction {
+    ix.accounts.extend(vault_metas(vaults));
+    ix
+}
+
+pub fn quote_buy_ix(etf: Pubkey, sol_amount: u64, token_percentages: Vec<u8>) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::QuoteETF { etf }.to_account_metas(None),
+        data: mtf_etf::instruction::QuoteBuy { sol_amount, token_percentages }.data(),
+    }
+}
+
+pub fn quote_sell_ix(etf: Pubkey, shares: u64) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::QuoteETF { etf }.to_account_metas(None),
+        data: mtf_etf::instruction::QuoteSell { shares }.data(),
+    }
+}
+
+pub fn get_nav_ix(etf: Pubkey) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::QuoteETF { etf }.to_account_metas(None),
+        data: mtf_etf::instruction::GetNav {}.data(),
+    }
+}
+
+pub fn get_nav_twap_ix(etf: Pubkey, window_seconds: i64) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::QuoteNavTwap {
+            nav_history: nav_history_address(&etf),
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::GetNavTwap { window_seconds }.data(),
+    }
+}
+
+pub fn begin_liquidation_ix(etf: Pubkey, authority: Pubkey) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::BeginLiquidation { etf, authority }.to_account_metas(None),
+        data: mtf_etf::instruction::BeginLiquidation {}.data(),
+    }
+}
+
+// Converts the ETF's vault of `mint`, priced by that mint's feed
+pub fn liquidate_vault_ix(
+    etf: Pubkey,
+    authority: Pubkey,
+    mint: Pubkey,
+    destination: Pubkey,
+    token_amount: u64,
+    sol_amount: u64,
+) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: mtf_etf::accounts::LiquidateVault {
+            etf,
+            authority,
+            vault: vault_address(&etf, &mint),
+            destination,
+            price_feed: price_feed_address(&mint),
+            token_program: anchor_spl::token::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::LiquidateVault {
+            token_amount,
+            sol_amount,
+        }
+        .data(),
+    }
+}
+
+// `vaults` are (vault, investor token account) pairs in basket order
+pub fn claim_liquidation_ix(etf: Pubkey, investor: Pubkey, vaults: &[(Pubkey, Pubkey)]) -> Instruction {
+    let mut accounts = mtf_etf::accounts::ClaimLiquidation {
+        etf,
+        position: position_address(&etf, &investor),
+        investor,
+        token_program: Some(anchor_spl::token::ID),
+    }
+    .to_account_metas(None);
+    accounts.extend(vault_metas(vaults));
+
+    Instruction {
+        program_id: ID,
+        accounts,
+        data: mtf_etf::instruction::ClaimLiquidation {}.data(),
+    }
+}
+
+pub fn close_liquidated_etf_ix(
+    etf: Pubkey,
+    lister: Pubkey,
+    caller: Pubkey,
+    registry_index: u64,
+    vaults: &[(Pubkey, Pubkey)],
+) -> Instruction {
+    let mut accounts = mtf_etf::accounts::CloseLiquidatedETF {
+        etf,
+        registry: registry_address(),
+        registry_page: registry_page_address(registry_index),
+        share_mint: share_mint_address(&etf),
+        lister,
+        caller,
+        token_program: anchor_spl::token::ID,
+    }
+    .to_account_metas(None);
+    accounts.extend(vault_metas(vaults));
+
+    Instruction {
+        program_id: ID,
+        accounts,
+        data: mtf_etf::instruction::CloseLiquidatedEtf {}.data(),
+    }
+}
+
+fn vault_metas(vaults: &[(Pubkey, Pubkey)]) -> Vec<AccountMeta> {
+    vaults
+        .iter()
+        .flat_map(|(vault, destination)| [AccountMeta::new(*vault, false), AccountMeta::new(*destination, false)])
+        .collect()
+}
+
+// ============================================================================
+// Scenario helpers
+// ============================================================================
+
+// An initialized ETF and the wallets around it
+pub struct TestEtf {
+    pub address: Pubkey,
+    pub lister: Pubkey,
+    pub registry_index: u64,
+    pub mints: Vec<Pubkey>,
+}
+
+// (vault, lister token account) for every basket mint, as close_etf and
+// close_liquidated_etf expect them
+pub fn lister_vaults(etf: &TestEtf) -> Vec<(Pubkey, Pubkey)> {
+    holder_vaults(etf, &etf.lister)
+}
+
+// (vault, `holder`'s associated token account) for every basket mint
+pub fn holder_vaults(etf: &TestEtf, holder: &Pubkey) -> Vec<(Pubkey, Pubkey)> {
+    etf.mints
+        .iter()
+        .map(|mint| (vault_address(&etf.address, mint), get_associated_token_address(holder, mint)))
+        .collect()
+}
+
+pub fn next_registry_index(harness: &Harness) -> u64 {
+    harness
+        .anchor_account::<mtf_etf::Registry>(&registry_address())
+        .map(|registry| registry.etf_count)
+        .unwrap_or_default()
+}
+
+// Create an ETF over `token_count` fresh mints for a new funded lister
+pub fn create_etf(harness: &mut Harness, token_count: usize) -> TestEtf {
+    let lister = funded_wallet(harness, 10 * LAMPORTS_PER_SOL);
+    let mints: Vec<Pubkey> = (0..token_count).map(|_| Pubkey::new_unique()).collect();
+    for mint in &mints {
+        harness.create_mint(*mint, &lister, 6);
+    }
+    let registry_index = next_registry_index(harness);
+
+    harness
+        .process_instruction(
+            initialize_etf_ix(lister, 0, registry_index, mints.clone(), Metadata::default()),
+            &[lister],
+        )
+        .expect("initialize_etf");
+
+    TestEtf {
+        address: etf_address(&lister, 0),
+        lister,
+        registry_index,
+        mints,
+    }
+}
+
+// Percentages summing to 100 for `count` tokens
+pub fn even_percentages(count: usize) -> Vec<u8> {
+    let base = (100 / count) as u8;
+    let mut percentages = vec![base; count];
+    percentages[0] += 100 - base * count as u8;
+    percentages
+}
+
+pub fn buy(harness: &mut Harness, etf: &TestEtf, investor: Pubkey, sol_amount: u64) -> TxResult {
+    harness.process_instruction(
+        buy_etf_ix(
+            etf.address,
+            etf.lister,
+            investor,
+            TREASURY,
+            sol_amount,
+            even_percentages(etf.mints.len()),
+        ),
+        &[investor],
+    )
+}
+
+pub fn sell(harness: &mut Harness, etf: &TestEtf, investor: Pubkey, shares: u64) -> TxResult {
+    harness.process_instruction(
+        sell_etf_ix(etf.address, etf.lister, investor, TREASURY, shares),
+        &[investor],
+    )
+}
+
+pub fn set_price(harness: &mut Harness, mint: &Pubkey, price: u64) {
+    let authority = governance(harness);
+    harness
+        .process_instruction(set_price_ix(authority, *mint, price), &[authority])
+        .expect("set_price");
+}
+
+// ETF settled by a keeper holding plenty of each basket token, whose feeds
+// are all at TOKEN_PRICE
+pub struct KeeperEtf {
+    pub etf: TestEtf,
+    pub keeper: Pubkey,
+    // Keeper-owned token account per basket mint
+    pub sources: Vec<Pubkey>,
+}
+
+impl KeeperEtf {
+    // (keeper token account, vault, price feed) per basket mint
+    pub fn legs(&self) -> Vec<(Pubkey, Pubkey, Pubkey)> {
+        self.etf
+            .mints
+            .iter()
+            .zip(&self.sources)
+            .map(|(mint, source)| {
+                (
+                    *source,
+                    vault_address(&self.etf.address, mint),
+                    price_feed_address(mint),
+                )
+            })
+            .collect()
+    }
+
+    pub fn vaults(&self) -> Vec<Pubkey> {
+        self.etf
+            .mints
+            .iter()
+            .map(|mint| vault_address(&self.etf.address, mint))
+            .collect()
+    }
+}
+
+pub fn keeper_etf(harness: &mut Harness, token_count: usize) -> KeeperEtf {
+    let etf = create_etf(harness, token_count);
+    let keeper = funded_wallet(harness, 10 * LAMPORTS_PER_SOL);
+    harness
+        .process_instruction(
+            set_keeper_ix(etf.address, etf.lister, Some(keeper), DEFAULT_REFUND_TIMEOUT_SECONDS),
+            &[etf.lister],
+        )
+        .unwrap();
+
+    let mut sources = Vec::new();
+    for mint in &etf.mints {
+        let source = Pubkey::new_unique();
+        harness.create_token_account(source, mint, &keeper, 1_000_000_000);
+        harness.create_token_account(vault_address(&etf.address, mint), mint, &etf.address, 0);
+        set_price(harness, mint, TOKEN_PRICE);
+        sources.push(source);
+    }
+    KeeperEtf { etf, keeper, sources }
+}
+
+// Keeper buy split evenly across the basket
+pub fn pending_buy(harness: &mut Harness, etf: &TestEtf, investor: Pubkey, sol_amount: u64) -> TxResult {
+    let purchase_id = etf_state(harness, &etf.address).purchase_count;
+    harness.process_instruction(
+        buy_etf_pending_ix(
+            etf.address,
+            etf.lister,
+            investor,
+            TREASURY,
+            Some(pending_purchase_address(&etf.address, purchase_id)),
+            sol_amount,
+            even_percentages(etf.mints.len()),
+        ),
+        &[investor],
+    )
+}
+
+pub fn settle(
+    harness: &mut Harness,
+    setup: &KeeperEtf,
+    investor: Pubkey,
+    purchase_id: u64,
+    amounts: Vec<u64>,
+) -> TxResult {
+    harness.process_instruction(
+        settle_purchase_ix(
+            setup.etf.address,
+            investor,
+            setup.keeper,
+            purchase_id,
+            &setup.legs(),
+            amounts,
+        ),
+        &[setup.keeper],
+    )
+}
+
+// Settle a purchase with tokens worth exactly its allocations at TOKEN_PRICE
+pub fn settle_at_price(harness: &mut Harness, setup: &KeeperEtf, investor: Pubkey, purchase_id: u64) -> TxResult {
+    let pending: mtf_etf::PendingPurchase = harness
+        .anchor_account(&pending_purchase_address(&setup.etf.address, purchase_id))
+        .expect("pending purchase");
+    let amounts = pending
+        .allocations
+        .iter()
+        .map(|lamports| (*lamports as u128 * PRICE_SCALE as u128 / TOKEN_PRICE as u128) as u64)
+        .collect();
+    settle(harness, setup, investor, purchase_id, amounts)
+}
+
+// Keeper ETF held by another holder and the returned investor, each settled
+// for 1 SOL at TOKEN_PRICE, with empty token accounts of the investor as the
+// (vault, account) pairs of an in-kind exit
+pub fn keeper_holders(harness: &mut Harness) -> (KeeperEtf, Pubkey, Vec<(Pubkey, Pubkey)>) {
+    let setup = keeper_etf(harness, 2);
+    let holder = funded_wallet(harness, 10 * LAMPORTS_PER_SOL);
+    let investor = funded_wallet(harness, 10 * LAMPORTS_PER_SOL);
+    for (purchase_id, wallet) in [holder, investor].into_iter().enumerate() {
+        pending_buy(harness, &setup.etf, wallet, LAMPORTS_PER_SOL).unwrap();
+        settle_at_price(harness, &setup, wallet, purchase_id as u64).unwrap();
+    }
+    let pairs = setup
+        .etf
+        .mints
+        .iter()
+        .zip(setup.vaults())
+        .map(|(mint, vault)| {
+            let account = Pubkey::new_unique();
+            harness.create_token_account(account, mint, &investor, 0);
+            (vault, account)
+        })
+        .collect();
+    (setup, investor, pairs)
+}
+
+pub fn etf_state(harness: &Harness, etf: &Pubkey) -> mtf_etf::ETF {
+    harness.anchor_account(etf).expect("ETF account")
+}
+
+pub fn shares(harness: &Harness, etf: &Pubkey, investor: &Pubkey) -> u64 {
+    harness
+        .anchor_account::<mtf_etf::Position>(&position_address(etf, investor))
+        .map(|position| position.shares)
+        .unwrap_or_default()
+}
+
+// Rewrite an Anchor account in place to reach states instructions cannot produce
+pub fn tamper<T: AccountSerialize + AccountDeserialize>(harness: &mut Harness, address: &Pubkey, edit: impl FnOnce(&mut T)) {
+    let mut account: AccountState = harness.account(address).expect("account to tamper").clone();
+    let mut state = T::try_deserialize(&mut account.data.as_slice()).expect("decode account");
+    edit(&mut state);
+    let mut data = Vec::with_capacity(account.data.len());
+    state.try_serialize(&mut data).expect("encode account");
+    data.resize(account.data.len(), 0);
+    account.data = data;
+    harness.set_account(*address, account);
+}
+
+// Snapshot each ETF hourly over the LEADERBOARD_TWAP_SECONDS up to
+// `boundary`, as a regular crank would; the clock ends at `boundary`
+pub fn crank_nav_histories(harness: &mut Harness, etfs: &[Pubkey], boundary: i64) {
+    let mut timestamp = boundary - mtf_etf::LEADERBOARD_TWAP_SECONDS;
+    while timestamp <= boundary {
+        harness.set_unix_timestamp(timestamp);
+        for etf in etfs {
+            harness.process_instruction(snapshot_nav_ix(*etf), &[]).unwrap();
+        }
+        timestamp += mtf_etf::NAV_SNAPSHOT_INTERVAL_SECONDS;
+    }
+}
+
+// Move an instant ETF's booked SOL and its lamports together, standing in for
+// the gain or loss a real valuation change would book. A bare airdrop is a
+// donation and leaves NAV untouched.
+pub fn shift_net_assets(harness: &mut Harness, etf: &Pubkey, delta: i64) {
+    let mut account = harness.account(etf).expect("ETF account").clone();
+    account.lamports = account.lamports.checked_add_signed(delta).unwrap();
+    harness.set_account(*etf, account);
+    tamper::<mtf_etf::ETF>(harness, etf, |state| {
+        state.sol_assets = state.sol_assets.checked_add_signed(delta).unwrap();
+    });
+}
+
+// ============================================================================
+// Assertions
+// ============================================================================
+
+pub fn assert_error(result: TxResult, expected: impl Into<u32>) {
+    let expected = expected.into();
+    match result {
+        Ok(_) => panic!("expected error {expected}, transaction succeeded"),
+        Err(error) => assert_eq!(
+            error.custom_code(),
+            Some(expected),
+            "unexpected error {:?}\n{}",
+            error.error,
+            error.logs.join("\n")
+        ),
+    }
+}
@@ -0,0 +1,235 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::*;
+use mtf_etf::{
+    calculate_fees, DcaExecutedEvent, DcaPlan, DcaPlanCancelledEvent, DcaPlanCreatedEvent, ETFBoughtEvent, ErrorCode,
+    EtfGate, DCA_CRANK_TIP_LAMPORTS, DEFAULT_REFUND_TIMEOUT_SECONDS, TREASURY, MIN_DCA_INTERVAL_SECONDS,
+};
+use program_harness::Harness;
+
+const DAY: i64 = 24 * 60 * 60;
+const PER_DAY: u64 = LAMPORTS_PER_SOL / 10;
+
+// Two-token ETF and an investor with a funded daily plan of 0.1 SOL
+fn dca_etf(harness: &mut Harness, deposit: u64) -> (TestEtf, Pubkey) {
+    let etf = create_etf(harness, 2);
+    let investor = funded_wallet(harness, 10 * LAMPORTS_PER_SOL);
+    harness
+        .process_instruction(
+            create_dca_plan_ix(etf.address, investor, PER_DAY, DAY, vec![50, 50], deposit),
+            &[investor],
+        )
+        .unwrap();
+    (etf, investor)
+}
+
+fn plan(harness: &Harness, etf: &TestEtf, investor: &Pubkey) -> DcaPlan {
+    harness.anchor_account(&dca_plan_address(&etf.address, investor)).unwrap()
+}
+
+fn execute(harness: &mut Harness, etf: &TestEtf, investor: Pubkey, caller: Pubkey) -> TxResult {
+    harness.process_instruction(execute_dca_ix(etf.address, etf.lister, investor, caller, None), &[caller])
+}
+
+#[test]
+fn test_create_dca_plan() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 2);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+
+    let outcome = harness
+        .process_instruction(
+            create_dca_plan_ix(etf.address, investor, PER_DAY, DAY, vec![50, 50], LAMPORTS_PER_SOL),
+            &[investor],
+        )
+        .unwrap();
+
+    let plan = plan(&harness, &etf, &investor);
+    assert_eq!(plan.etf, etf.address);
+    assert_eq!(plan.investor, investor);
+    assert_eq!(plan.amount_per_period, PER_DAY);
+    assert_eq!(plan.interval_seconds, DAY);
+    assert_eq!(plan.next_execution_ts, harness.clock().unix_timestamp);
+    assert_eq!(plan.executions, 0);
+    let account = harness.account(&dca_plan_address(&etf.address, &investor)).unwrap();
+    assert_eq!(
+        account.lamports,
+        harness.rent().minimum_balance(account.data.len()) + LAMPORTS_PER_SOL
+    );
+
+    // The position is opened up front, empty
+    assert_eq!(shares(&harness, &etf.address, &investor), 0);
+    assert_eq!(outcome.events::<DcaPlanCreatedEvent>()[0].deposit, LAMPORTS_PER_SOL);
+}
+
+#[test]
+fn test_create_dca_plan_validation() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 2);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    let create = |harness: &mut Harness, interval: i64, percentages: Vec<u8>| {
+        harness.process_instruction(
+            create_dca_plan_ix(etf.address, investor, PER_DAY, interval, percentages, LAMPORTS_PER_SOL),
+            &[investor],
+        )
+    };
+
+    assert_error(create(&mut harness, MIN_DCA_INTERVAL_SECONDS - 1, vec![50, 50]), ErrorCode::InvalidDcaInterval);
+    assert_error(create(&mut harness, DAY, vec![50, 40]), ErrorCode::InvalidTokenPercentages);
+    assert_error(create(&mut harness, DAY, vec![100]), ErrorCode::InvalidTokenPercentages);
+
+    // Keeper-settled ETFs cannot mint shares during the execution
+    let keeper = Pubkey::new_unique();
+    harness
+        .process_instruction(
+            set_keeper_ix(etf.address, etf.lister, Some(keeper), DEFAULT_REFUND_TIMEOUT_SECONDS),
+            &[etf.lister],
+        )
+        .unwrap();
+    assert_error(create(&mut harness, DAY, vec![50, 50]), ErrorCode::RequiresInstantBuys);
+}
+
+#[test]
+fn test_execute_dca_buys_and_tips_caller() {
+    let mut harness = setup();
+    let (etf, investor) = dca_etf(&mut harness, LAMPORTS_PER_SOL);
+    let cranker = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+    let plan_address = dca_plan_address(&etf.address, &investor);
+
+    let plan_before = harness.lamports(&plan_address);
+    let cranker_before = harness.lamports(&cranker);
+    let lister_before = harness.lamports(&etf.lister);
+    let dev_before = harness.lamports(&TREASURY);
+    let investor_before = harness.lamports(&investor);
+    let outcome = execute(&mut harness, &etf, investor, cranker).unwrap();
+
+    let (creator_fee, dev_fee) = calculate_fees(PER_DAY);
+    let minted = PER_DAY - creator_fee - dev_fee;
+    assert_eq!(shares(&harness, &etf.address, &investor), minted);
+    assert_eq!(etf_state(&harness, &etf.address).total_supply, minted);
+    assert_eq!(plan_before - harness.lamports(&plan_address), PER_DAY + DCA_CRANK_TIP_LAMPORTS);
+    assert_eq!(harness.lamports(&etf.lister) - lister_before, creator_fee);
+    assert_eq!(harness.lamports(&TREASURY) - dev_before, dev_fee);
+    // The cranker's transaction fee is not charged by the harness
+    assert_eq!(harness.lamports(&cranker) - cranker_before, DCA_CRANK_TIP_LAMPORTS);
+    assert_eq!(harness.lamports(&investor), investor_before);
+
+    let bought = outcome.events::<ETFBoughtEvent>();
+    assert_eq!(bought.len(), 1);
+    assert_eq!(bought[0].investor, investor);
+    let executed = &outcome.events::<DcaExecutedEvent>()[0];
+    assert_eq!(executed.caller, cranker);
+    assert_eq!(executed.shares_minted, minted);
+    assert_eq!(executed.next_execution_ts, harness.clock().unix_timestamp + DAY);
+    assert_eq!(plan(&harness, &etf, &investor).executions, 1);
+}
+
+#[test]
+fn test_donation_does_not_change_dca_shares() {
+    let mut harness = setup();
+    let (etf, investor) = dca_etf(&mut harness, LAMPORTS_PER_SOL);
+    let holder = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    buy(&mut harness, &etf, holder, LAMPORTS_PER_SOL).unwrap();
+    let cranker = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+
+    // Inflating the ETF's lamports right before the crank does not shrink
+    // the investor's buy: NAV is read from the books
+    harness.airdrop(&etf.address, 5 * LAMPORTS_PER_SOL);
+    execute(&mut harness, &etf, investor, cranker).unwrap();
+
+    let (creator_fee, dev_fee) = calculate_fees(PER_DAY);
+    assert_eq!(shares(&harness, &etf.address, &investor), PER_DAY - creator_fee - dev_fee);
+}
+
+#[test]
+fn test_execute_dca_waits_for_interval() {
+    let mut harness = setup();
+    let (etf, investor) = dca_etf(&mut harness, LAMPORTS_PER_SOL);
+    let cranker = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+    execute(&mut harness, &etf, investor, cranker).unwrap();
+
+    harness.advance_time(DAY - 1);
+    assert_error(execute(&mut harness, &etf, investor, cranker), ErrorCode::DcaNotDue);
+    harness.advance_time(1);
+    execute(&mut harness, &etf, investor, cranker).unwrap();
+
+    // A late crank buys once and moves the schedule on, without catching up
+    harness.advance_time(3 * DAY + 5);
+    execute(&mut harness, &etf, investor, cranker).unwrap();
+    let plan = plan(&harness, &etf, &investor);
+    assert_eq!(plan.executions, 3);
+    assert_eq!(plan.next_execution_ts, harness.clock().unix_timestamp + DAY);
+    assert_error(execute(&mut harness, &etf, investor, cranker), ErrorCode::DcaNotDue);
+}
+
+#[test]
+fn test_underfunded_plan_needs_top_up() {
+    let mut harness = setup();
+    let (etf, investor) = dca_etf(&mut harness, PER_DAY);
+    let cranker = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+
+    // The deposit covers the buy but not the tip
+    assert_error(execute(&mut harness, &etf, investor, cranker), ErrorCode::DcaPlanUnderfunded);
+    harness
+        .process_instruction(fund_dca_plan_ix(etf.address, investor, DCA_CRANK_TIP_LAMPORTS), &[investor])
+        .unwrap();
+    execute(&mut harness, &etf, investor, cranker).unwrap();
+
+    // Only rent is left
+    let plan_address = dca_plan_address(&etf.address, &investor);
+    let account = harness.account(&plan_address).unwrap();
+    assert_eq!(account.lamports, harness.rent().minimum_balance(account.data.len()));
+}
+
+#[test]
+fn test_cancel_dca_plan_refunds_balance() {
+    let mut harness = setup();
+    let (etf, investor) = dca_etf(&mut harness, LAMPORTS_PER_SOL);
+    let cranker = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+    execute(&mut harness, &etf, investor, cranker).unwrap();
+
+    let plan_address = dca_plan_address(&etf.address, &investor);
+    let plan_lamports = harness.lamports(&plan_address);
+    let investor_before = harness.lamports(&investor);
+
+    // Nobody else can cancel it
+    assert!(harness
+        .process_instruction(cancel_dca_plan_ix(etf.address, cranker), &[cranker])
+        .is_err());
+
+    let outcome = harness
+        .process_instruction(cancel_dca_plan_ix(etf.address, investor), &[investor])
+        .unwrap();
+    assert!(harness.account(&plan_address).is_none());
+    assert_eq!(harness.lamports(&investor) - investor_before, plan_lamports);
+    let event = &outcome.events::<DcaPlanCancelledEvent>()[0];
+    assert_eq!(event.refunded, plan_lamports);
+    assert_eq!(event.executions, 1);
+
+    // Shares already bought stay with the investor
+    assert!(shares(&harness, &etf.address, &investor) > 0);
+}
+
+#[test]
+fn test_execute_dca_respects_gate() {
+    let mut harness = setup();
+    let (etf, investor) = dca_etf(&mut harness, LAMPORTS_PER_SOL);
+    let cranker = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+    let collection = Pubkey::new_unique();
+    harness.create_mint(collection, &etf.lister, 0);
+    harness
+        .process_instruction(
+            set_gate_ix(etf.address, etf.lister, EtfGate::MinimumBalance { mint: collection, amount: 1 }),
+            &[etf.lister],
+        )
+        .unwrap();
+
+    assert_error(execute(&mut harness, &etf, investor, cranker), ErrorCode::NotAllowlisted);
+    let tokens = Pubkey::new_unique();
+    harness.create_token_account(tokens, &collection, &investor, 1);
+    harness
+        .process_instruction(execute_dca_ix(etf.address, etf.lister, investor, cranker, Some(tokens)), &[cranker])
+        .unwrap();
+    assert!(shares(&harness, &etf.address, &investor) > 0);
+}
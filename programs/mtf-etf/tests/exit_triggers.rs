@@ -0,0 +1,310 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::*;
+use mtf_etf::{
+    calculate_fees, liquidation_payout, token_value, ETFSoldEvent, ErrorCode, ExitKind, ExitTrigger,
+    ExitTriggerSetEvent, ExitTriggeredEvent, TokenRedeemedEvent, EXIT_CRANK_TIP_LAMPORTS, MAX_PRICE_AGE_SECONDS,
+    NAV_SCALE, TREASURY,
+};
+use program_harness::Harness;
+
+// One-token ETF where `investor` holds half the supply next to another holder
+fn shared_etf(harness: &mut Harness) -> (TestEtf, Pubkey) {
+    let etf = create_etf(harness, 1);
+    let other = funded_wallet(harness, 10 * LAMPORTS_PER_SOL);
+    buy(harness, &etf, other, LAMPORTS_PER_SOL).unwrap();
+    let investor = funded_wallet(harness, 10 * LAMPORTS_PER_SOL);
+    buy(harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+    (etf, investor)
+}
+
+fn set_trigger(harness: &mut Harness, etf: &TestEtf, investor: Pubkey, stop: u64, take: u64, shares: u64) -> TxResult {
+    harness.process_instruction(set_exit_trigger_ix(etf.address, investor, stop, take, shares, 0), &[investor])
+}
+
+fn trigger(harness: &mut Harness, etf: &TestEtf, investor: Pubkey, caller: Pubkey) -> TxResult {
+    harness.process_instruction(trigger_exit_ix(etf.address, etf.lister, investor, caller), &[caller])
+}
+
+#[test]
+fn test_set_exit_trigger_prefunds_tip() {
+    let mut harness = setup();
+    let (etf, investor) = shared_etf(&mut harness);
+
+    let outcome = set_trigger(&mut harness, &etf, investor, NAV_SCALE / 2, 2 * NAV_SCALE, 0).unwrap();
+
+    let address = exit_trigger_address(&etf.address, &investor);
+    let state: ExitTrigger = harness.anchor_account(&address).unwrap();
+    assert_eq!(state.investor, investor);
+    assert_eq!(state.stop_loss_nav, NAV_SCALE / 2);
+    assert_eq!(state.take_profit_nav, 2 * NAV_SCALE);
+    assert_eq!(state.shares, 0);
+    let account = harness.account(&address).unwrap();
+    let funded = harness.rent().minimum_balance(account.data.len()) + EXIT_CRANK_TIP_LAMPORTS;
+    assert_eq!(account.lamports, funded);
+    assert_eq!(outcome.events::<ExitTriggerSetEvent>()[0].take_profit_nav, 2 * NAV_SCALE);
+
+    // Replacing the thresholds costs nothing more
+    let investor_before = harness.lamports(&investor);
+    set_trigger(&mut harness, &etf, investor, 0, 3 * NAV_SCALE, 1_000).unwrap();
+    assert_eq!(harness.lamports(&investor), investor_before);
+    assert_eq!(harness.lamports(&address), funded);
+    let state: ExitTrigger = harness.anchor_account(&address).unwrap();
+    assert_eq!((state.stop_loss_nav, state.take_profit_nav, state.shares), (0, 3 * NAV_SCALE, 1_000));
+
+    // A deposit is held on top, topped up or refunded when it changes
+    for deposit in [LAMPORTS_PER_SOL / 10, LAMPORTS_PER_SOL / 100] {
+        harness
+            .process_instruction(
+                set_exit_trigger_ix(etf.address, investor, 0, 3 * NAV_SCALE, 0, deposit),
+                &[investor],
+            )
+            .unwrap();
+        assert_eq!(harness.lamports(&address), funded + deposit);
+    }
+    assert_eq!(investor_before - harness.lamports(&investor), LAMPORTS_PER_SOL / 100);
+}
+
+#[test]
+fn test_set_exit_trigger_validation() {
+    let mut harness = setup();
+    let (etf, investor) = shared_etf(&mut harness);
+
+    assert_error(set_trigger(&mut harness, &etf, investor, 0, 0, 0), ErrorCode::InvalidExitTrigger);
+    assert_error(
+        set_trigger(&mut harness, &etf, investor, NAV_SCALE, NAV_SCALE, 0),
+        ErrorCode::InvalidExitTrigger,
+    );
+
+    // Only positions can carry a trigger
+    let stranger = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+    assert!(set_trigger(&mut harness, &etf, stranger, NAV_SCALE / 2, 0, 0).is_err());
+}
+
+#[test]
+fn test_donation_cannot_fire_trigger() {
+    let mut harness = setup();
+    let (etf, investor) = shared_etf(&mut harness);
+    let caller = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+    let held = shares(&harness, &etf.address, &investor);
+    set_trigger(&mut harness, &etf, investor, 0, NAV_SCALE * 11 / 10, 0).unwrap();
+
+    // Lamports sent straight to the ETF are not booked, so NAV stays put
+    let supply = etf_state(&harness, &etf.address).total_supply;
+    harness.airdrop(&etf.address, supply);
+    assert_error(trigger(&mut harness, &etf, investor, caller), ErrorCode::ExitNotTriggered);
+    assert_eq!(shares(&harness, &etf.address, &investor), held);
+    assert!(harness.account(&exit_trigger_address(&etf.address, &investor)).is_some());
+}
+
+#[test]
+fn test_stop_loss_sells_position_and_tips_caller() {
+    let mut harness = setup();
+    let (etf, investor) = shared_etf(&mut harness);
+    let caller = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+    let held = shares(&harness, &etf.address, &investor);
+    set_trigger(&mut harness, &etf, investor, NAV_SCALE * 9 / 10, 0, 0).unwrap();
+
+    // Callers cannot fire it before the on-chain NAV gets there
+    assert_error(trigger(&mut harness, &etf, investor, caller), ErrorCode::ExitNotTriggered);
+
+    let supply = etf_state(&harness, &etf.address).total_supply;
+    shift_net_assets(&mut harness, &etf.address, -((supply / 10) as i64));
+    let value = liquidation_payout(held, supply, etf_state(&harness, &etf.address).sol_assets);
+
+    let address = exit_trigger_address(&etf.address, &investor);
+    let trigger_lamports = harness.lamports(&address);
+    let investor_before = harness.lamports(&investor);
+    let caller_before = harness.lamports(&caller);
+    let outcome = trigger(&mut harness, &etf, investor, caller).unwrap();
+
+    let (creator_fee, dev_fee) = calculate_fees(value);
+    let paid = value - creator_fee - dev_fee;
+    assert_eq!(shares(&harness, &etf.address, &investor), 0);
+    assert_eq!(etf_state(&harness, &etf.address).total_supply, supply - held);
+    assert_eq!(harness.lamports(&caller) - caller_before, EXIT_CRANK_TIP_LAMPORTS);
+    // The investor gets the sale and the trigger's rent back
+    assert_eq!(
+        harness.lamports(&investor) - investor_before,
+        paid + trigger_lamports - EXIT_CRANK_TIP_LAMPORTS
+    );
+    assert!(harness.account(&address).is_none());
+
+    let fired = outcome.events::<ExitTriggeredEvent>();
+    assert_eq!(fired[0].kind, ExitKind::StopLoss);
+    assert_eq!(fired[0].shares_sold, held);
+    assert_eq!(fired[0].sol_amount, paid);
+    assert_eq!(fired[0].caller, caller);
+    assert_eq!(outcome.events::<ETFSoldEvent>()[0].investor, investor);
+}
+
+#[test]
+fn test_take_profit_sells_configured_shares() {
+    let mut harness = setup();
+    let (etf, investor) = shared_etf(&mut harness);
+    let caller = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+    let held = shares(&harness, &etf.address, &investor);
+    set_trigger(&mut harness, &etf, investor, 0, NAV_SCALE * 3 / 2, held / 4).unwrap();
+
+    assert_error(trigger(&mut harness, &etf, investor, caller), ErrorCode::ExitNotTriggered);
+    let supply = etf_state(&harness, &etf.address).total_supply;
+    shift_net_assets(&mut harness, &etf.address, (supply / 2) as i64);
+
+    let outcome = trigger(&mut harness, &etf, investor, caller).unwrap();
+    assert_eq!(shares(&harness, &etf.address, &investor), held - held / 4);
+    assert_eq!(outcome.events::<ExitTriggeredEvent>()[0].kind, ExitKind::TakeProfit);
+
+    // Triggers fire once
+    assert!(trigger(&mut harness, &etf, investor, caller).is_err());
+}
+
+#[test]
+fn test_trigger_sells_what_is_left() {
+    let mut harness = setup();
+    let (etf, investor) = shared_etf(&mut harness);
+    let caller = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+    let held = shares(&harness, &etf.address, &investor);
+    set_trigger(&mut harness, &etf, investor, 0, NAV_SCALE, held).unwrap();
+    sell(&mut harness, &etf, investor, held / 2).unwrap();
+
+    let outcome = trigger(&mut harness, &etf, investor, caller).unwrap();
+    assert_eq!(outcome.events::<ExitTriggeredEvent>()[0].shares_sold, held - held / 2);
+    assert_eq!(shares(&harness, &etf.address, &investor), 0);
+}
+
+#[test]
+fn test_trigger_exit_respects_hold_period() {
+    let mut harness = setup();
+    let (etf, investor) = shared_etf(&mut harness);
+    let caller = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+    harness
+        .process_instruction(set_hold_period_ix(etf.address, etf.lister, 3_600, 0), &[etf.lister])
+        .unwrap();
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+    set_trigger(&mut harness, &etf, investor, 0, NAV_SCALE, 0).unwrap();
+
+    assert_error(trigger(&mut harness, &etf, investor, caller), ErrorCode::HoldPeriodActive);
+    harness.advance_time(3_600);
+    trigger(&mut harness, &etf, investor, caller).unwrap();
+}
+
+#[test]
+fn test_cancel_exit_trigger_refunds() {
+    let mut harness = setup();
+    let (etf, investor) = shared_etf(&mut harness);
+    set_trigger(&mut harness, &etf, investor, NAV_SCALE / 2, 0, 0).unwrap();
+
+    let address = exit_trigger_address(&etf.address, &investor);
+    let trigger_lamports = harness.lamports(&address);
+    let investor_before = harness.lamports(&investor);
+    harness
+        .process_instruction(cancel_exit_trigger_ix(etf.address, investor), &[investor])
+        .unwrap();
+
+    assert!(harness.account(&address).is_none());
+    assert_eq!(harness.lamports(&investor) - investor_before, trigger_lamports);
+}
+
+// Fire on a keeper ETF, valued by its price feeds; `pairs` pay the exit in kind
+fn trigger_keeper(
+    harness: &mut Harness,
+    etf: &TestEtf,
+    investor: Pubkey,
+    caller: Pubkey,
+    pairs: &[(Pubkey, Pubkey)],
+) -> TxResult {
+    let ix = trigger_exit_ix(etf.address, etf.lister, investor, caller);
+    harness.process_instruction(with_vault_pairs(with_price_feeds(ix, &etf.mints), pairs), &[caller])
+}
+
+#[test]
+fn test_keeper_stop_loss_fires_on_price_drop() {
+    let mut harness = setup();
+    let (setup, investor, pairs) = keeper_holders(&mut harness);
+    let etf = &setup.etf;
+    let caller = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+    let held = shares(&harness, &etf.address, &investor);
+    let deposit = LAMPORTS_PER_SOL / 100;
+    harness
+        .process_instruction(
+            set_exit_trigger_ix(etf.address, investor, NAV_SCALE * 7 / 10, 0, 0, deposit),
+            &[investor],
+        )
+        .unwrap();
+    assert_error(trigger_keeper(&mut harness, etf, investor, caller, &pairs), ErrorCode::ExitNotTriggered);
+
+    // The first token loses three quarters at its feed: NAV is 0.625
+    set_price(&mut harness, &etf.mints[0], TOKEN_PRICE / 4);
+    let state = etf_state(&harness, &etf.address);
+    let assets = token_value(state.holdings[0], TOKEN_PRICE / 4)
+        + token_value(state.holdings[1], TOKEN_PRICE)
+        + state.sol_assets;
+    let address = exit_trigger_address(&etf.address, &investor);
+    let trigger_lamports = harness.lamports(&address);
+    let investor_before = harness.lamports(&investor);
+    let caller_before = harness.lamports(&caller);
+    let lister_before = harness.lamports(&etf.lister);
+    let dev_before = harness.lamports(&TREASURY);
+    let outcome = trigger_keeper(&mut harness, etf, investor, caller, &pairs).unwrap();
+
+    // The investor redeems in kind; fees on the value at NAV come out of the
+    // deposit, and the caller still earns the tip
+    for (i, (_, account)) in pairs.iter().enumerate() {
+        assert_eq!(
+            harness.token_balance(account),
+            Some(liquidation_payout(held, state.total_supply, state.holdings[i]))
+        );
+    }
+    let (creator_fee, dev_fee) = calculate_fees(liquidation_payout(held, state.total_supply, assets));
+    assert_eq!(harness.lamports(&etf.lister) - lister_before, creator_fee);
+    assert_eq!(harness.lamports(&TREASURY) - dev_before, dev_fee);
+    assert_eq!(harness.lamports(&caller) - caller_before, EXIT_CRANK_TIP_LAMPORTS);
+    let fired = outcome.events::<ExitTriggeredEvent>();
+    assert_eq!(fired[0].kind, ExitKind::StopLoss);
+    assert_eq!(fired[0].nav_per_share, NAV_SCALE * 5 / 8);
+    assert_eq!(
+        harness.lamports(&investor) - investor_before,
+        fired[0].sol_amount + trigger_lamports - EXIT_CRANK_TIP_LAMPORTS - creator_fee - dev_fee
+    );
+    assert_eq!(shares(&harness, &etf.address, &investor), 0);
+    assert_eq!(etf_state(&harness, &etf.address).total_supply, state.total_supply - held);
+    assert_eq!(outcome.events::<TokenRedeemedEvent>().len(), 2);
+    assert!(harness.account(&address).is_none());
+}
+
+#[test]
+fn test_keeper_trigger_validation() {
+    let mut harness = setup();
+    let (setup, investor, pairs) = keeper_holders(&mut harness);
+    let etf = &setup.etf;
+    let caller = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+    harness
+        .process_instruction(set_exit_trigger_ix(etf.address, investor, 0, NAV_SCALE, 0, 0), &[investor])
+        .unwrap();
+
+    // NAV needs every feed of a held token, and fresh ones
+    assert_error(trigger(&mut harness, etf, investor, caller), ErrorCode::MissingPriceFeed);
+    harness.advance_time(MAX_PRICE_AGE_SECONDS + 1);
+    assert_error(trigger_keeper(&mut harness, etf, investor, caller, &pairs), ErrorCode::StalePrice);
+    for mint in &etf.mints {
+        set_price(&mut harness, mint, TOKEN_PRICE);
+    }
+
+    // An in-kind exit needs every vault pair and a deposit covering its fees
+    assert_error(
+        trigger_keeper(&mut harness, etf, investor, caller, &pairs[..1]),
+        ErrorCode::InvalidVaultAccount,
+    );
+    assert_error(
+        trigger_keeper(&mut harness, etf, investor, caller, &pairs),
+        ErrorCode::InsufficientDeposit,
+    );
+    harness
+        .process_instruction(
+            set_exit_trigger_ix(etf.address, investor, 0, NAV_SCALE, 0, LAMPORTS_PER_SOL / 100),
+            &[investor],
+        )
+        .unwrap();
+    trigger_keeper(&mut harness, etf, investor, caller, &pairs).unwrap();
+}
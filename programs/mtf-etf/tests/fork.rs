@@ -0,0 +1,242 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::*;
+use mtf_etf::{
+    calculate_fees, split_creator_fee, ETFCreatedEvent, ETFForkedEvent, ErrorCode, FeeTransferEvent, FeeType,
+    ForkChanges, ParentFeesClaimedEvent, MAX_PARENT_FEE_BPS,
+};
+use program_harness::Harness;
+
+fn fork(harness: &mut Harness, parent: &TestEtf, changes: ForkChanges) -> (TestEtf, TxResult) {
+    let lister = funded_wallet(harness, 10 * LAMPORTS_PER_SOL);
+    for mint in &changes.add_tokens {
+        harness.create_mint(*mint, &lister, 6);
+    }
+    let registry_index = next_registry_index(harness);
+    let mints = parent
+        .mints
+        .iter()
+        .filter(|mint| !changes.remove_tokens.contains(mint))
+        .chain(&changes.add_tokens)
+        .copied()
+        .collect();
+    let result = harness.process_instruction(
+        fork_etf_ix(lister, 0, parent.address, registry_index, changes, Metadata::default()),
+        &[lister],
+    );
+    let forked = TestEtf {
+        address: etf_address(&lister, 0),
+        lister,
+        registry_index,
+        mints,
+    };
+    (forked, result)
+}
+
+fn with_fee(parent_fee_bps: u16) -> ForkChanges {
+    ForkChanges {
+        parent_fee_bps,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_fork_copies_basket_and_records_parent() {
+    let mut harness = setup();
+    let parent = create_etf(&mut harness, 3);
+    let added = Pubkey::new_unique();
+
+    let (forked, result) = fork(
+        &mut harness,
+        &parent,
+        ForkChanges {
+            remove_tokens: vec![parent.mints[1]],
+            add_tokens: vec![added],
+            parent_fee_bps: 1_000,
+        },
+    );
+    let outcome = result.unwrap();
+
+    let state = etf_state(&harness, &forked.address);
+    assert_eq!(state.lister, forked.lister);
+    assert_eq!(state.token_addresses, vec![parent.mints[0], parent.mints[2], added]);
+    assert_eq!(state.parent_etf, Some(parent.address));
+    assert_eq!(state.parent_lister, parent.lister);
+    assert_eq!(state.parent_fee_bps, 1_000);
+    assert_eq!(state.parent_fees_owed, 0);
+    assert_eq!(state.registry_index, 1);
+    assert_eq!(etf_state(&harness, &parent.address).parent_etf, None);
+
+    let created = outcome.events::<ETFCreatedEvent>();
+    assert_eq!(created.len(), 1);
+    let metadata = Metadata::default();
+    assert_eq!(created[0].name, metadata.name);
+    assert_eq!(created[0].symbol, metadata.symbol);
+    assert_eq!(created[0].uri, metadata.uri);
+    assert_eq!(created[0].category, metadata.category);
+    let events = outcome.events::<ETFForkedEvent>();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].etf_address, forked.address);
+    assert_eq!(events[0].parent_etf, parent.address);
+    assert_eq!(events[0].lister, forked.lister);
+    assert_eq!(events[0].parent_lister, parent.lister);
+    assert_eq!(events[0].removed_tokens, vec![parent.mints[1]]);
+    assert_eq!(events[0].added_tokens, vec![added]);
+    assert_eq!(events[0].parent_fee_bps, 1_000);
+}
+
+#[test]
+fn test_fork_rejects_invalid_changes() {
+    let mut harness = setup();
+    let parent = create_etf(&mut harness, 2);
+
+    let (_, result) = fork(
+        &mut harness,
+        &parent,
+        ForkChanges {
+            remove_tokens: vec![Pubkey::new_unique()],
+            ..Default::default()
+        },
+    );
+    assert_error(result, ErrorCode::InvalidForkChanges);
+
+    let (_, result) = fork(
+        &mut harness,
+        &parent,
+        ForkChanges {
+            add_tokens: vec![parent.mints[0]],
+            ..Default::default()
+        },
+    );
+    assert_error(result, ErrorCode::InvalidForkChanges);
+
+    let (_, result) = fork(
+        &mut harness,
+        &parent,
+        ForkChanges {
+            remove_tokens: parent.mints.clone(),
+            ..Default::default()
+        },
+    );
+    assert_error(result, ErrorCode::InvalidTokenCount);
+
+    let (_, result) = fork(&mut harness, &parent, with_fee(MAX_PARENT_FEE_BPS + 1));
+    assert_error(result, ErrorCode::InvalidParentFee);
+}
+
+#[test]
+fn test_fork_routes_creator_fee_share_to_parent() {
+    let mut harness = setup();
+    let parent = create_etf(&mut harness, 1);
+    let (forked, result) = fork(&mut harness, &parent, with_fee(2_000));
+    result.unwrap();
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+
+    let lister_before = harness.lamports(&forked.lister);
+    let etf_before = harness.lamports(&forked.address);
+    let outcome = buy(&mut harness, &forked, investor, LAMPORTS_PER_SOL).unwrap();
+
+    let (creator_fee, dev_fee) = calculate_fees(LAMPORTS_PER_SOL);
+    let (lister_fee, parent_fee) = split_creator_fee(creator_fee, 2_000);
+    assert!(parent_fee > 0);
+    assert_eq!(harness.lamports(&forked.lister) - lister_before, lister_fee);
+    assert_eq!(
+        harness.lamports(&forked.address) - etf_before,
+        LAMPORTS_PER_SOL - creator_fee - dev_fee + parent_fee
+    );
+    assert_eq!(etf_state(&harness, &forked.address).parent_fees_owed, parent_fee);
+
+    let fees = outcome.events::<FeeTransferEvent>();
+    assert_eq!(fees.len(), 3);
+    assert_eq!(fees[0].amount, lister_fee);
+    assert!(fees[2].fee_type == FeeType::Parent);
+    assert_eq!(fees[2].recipient, parent.lister);
+    assert_eq!(fees[2].amount, parent_fee);
+
+    // Parent fees are not share backing: a full exit leaves them in place
+    let held = shares(&harness, &forked.address, &investor);
+    sell(&mut harness, &forked, investor, held).unwrap();
+    let (sell_creator_fee, _) = calculate_fees(LAMPORTS_PER_SOL - creator_fee - dev_fee);
+    let owed = parent_fee + split_creator_fee(sell_creator_fee, 2_000).1;
+    assert_eq!(etf_state(&harness, &forked.address).parent_fees_owed, owed);
+}
+
+#[test]
+fn test_fork_without_fee_share_pays_lister_in_full() {
+    let mut harness = setup();
+    let parent = create_etf(&mut harness, 1);
+    let (forked, result) = fork(&mut harness, &parent, ForkChanges::default());
+    result.unwrap();
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+
+    let lister_before = harness.lamports(&forked.lister);
+    let outcome = buy(&mut harness, &forked, investor, LAMPORTS_PER_SOL).unwrap();
+
+    let (creator_fee, _) = calculate_fees(LAMPORTS_PER_SOL);
+    assert_eq!(harness.lamports(&forked.lister) - lister_before, creator_fee);
+    assert_eq!(etf_state(&harness, &forked.address).parent_fees_owed, 0);
+    assert_eq!(outcome.events::<FeeTransferEvent>().len(), 2);
+}
+
+#[test]
+fn test_claim_parent_fees() {
+    let mut harness = setup();
+    let parent = create_etf(&mut harness, 1);
+    let (forked, result) = fork(&mut harness, &parent, with_fee(MAX_PARENT_FEE_BPS));
+    result.unwrap();
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    buy(&mut harness, &forked, investor, LAMPORTS_PER_SOL).unwrap();
+    let owed = etf_state(&harness, &forked.address).parent_fees_owed;
+
+    // Only the recorded parent lister can receive them
+    let stranger = Pubkey::new_unique();
+    assert_error(
+        harness.process_instruction(claim_parent_fees_ix(forked.address, stranger), &[]),
+        ErrorCode::InvalidListerAccount,
+    );
+
+    let parent_lister_before = harness.lamports(&parent.lister);
+    let outcome = harness
+        .process_instruction(claim_parent_fees_ix(forked.address, parent.lister), &[])
+        .unwrap();
+    assert_eq!(harness.lamports(&parent.lister) - parent_lister_before, owed);
+    assert_eq!(etf_state(&harness, &forked.address).parent_fees_owed, 0);
+
+    let events = outcome.events::<ParentFeesClaimedEvent>();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].parent_lister, parent.lister);
+    assert_eq!(events[0].amount, owed);
+
+    // Nothing left to claim
+    assert_error(
+        harness.process_instruction(claim_parent_fees_ix(forked.address, parent.lister), &[]),
+        ErrorCode::InvalidAmount,
+    );
+}
+
+#[test]
+fn test_close_fork_requires_parent_fees_claimed() {
+    let mut harness = setup();
+    let parent = create_etf(&mut harness, 1);
+    let (forked, result) = fork(&mut harness, &parent, with_fee(1_000));
+    result.unwrap();
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    buy(&mut harness, &forked, investor, LAMPORTS_PER_SOL).unwrap();
+    let held = shares(&harness, &forked.address, &investor);
+    sell(&mut harness, &forked, investor, held).unwrap();
+
+    let close = |harness: &mut Harness| {
+        harness.process_instruction(
+            close_etf_ix(forked.address, forked.lister, forked.registry_index, &lister_vaults(&forked)),
+            &[forked.lister],
+        )
+    };
+    assert_error(close(&mut harness), ErrorCode::ParentFeesUnclaimed);
+
+    harness
+        .process_instruction(claim_parent_fees_ix(forked.address, parent.lister), &[])
+        .unwrap();
+    close(&mut harness).unwrap();
+    assert!(harness.account(&forked.address).is_none());
+}
@@ -0,0 +1,218 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::*;
+use mtf_etf::{allowlist_leaf, merkle_parent, ErrorCode, EtfGate, GateUpdatedEvent, TREASURY};
+use program_harness::Harness;
+
+// Four-wallet allowlist: (root, proof for each wallet)
+fn allowlist(wallets: &[Pubkey; 4]) -> ([u8; 32], Vec<Vec<[u8; 32]>>) {
+    let leaves: Vec<[u8; 32]> = wallets.iter().map(allowlist_leaf).collect();
+    let left = merkle_parent(&leaves[0], &leaves[1]);
+    let right = merkle_parent(&leaves[2], &leaves[3]);
+    let proofs = vec![
+        vec![leaves[1], right],
+        vec![leaves[0], right],
+        vec![leaves[3], left],
+        vec![leaves[2], left],
+    ];
+    (merkle_parent(&left, &right), proofs)
+}
+
+fn gate(harness: &mut Harness, etf: &TestEtf, gate: EtfGate) {
+    harness
+        .process_instruction(set_gate_ix(etf.address, etf.lister, gate), &[etf.lister])
+        .unwrap();
+}
+
+fn gated_buy(harness: &mut Harness, etf: &TestEtf, investor: Pubkey, gate_account: Option<Pubkey>) -> TxResult {
+    harness.process_instruction(
+        buy_etf_gated_ix(
+            etf.address,
+            etf.lister,
+            investor,
+            TREASURY,
+            None,
+            gate_account,
+            LAMPORTS_PER_SOL,
+            even_percentages(etf.mints.len()),
+        ),
+        &[investor],
+    )
+}
+
+#[test]
+fn test_set_gate() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 2);
+    assert_eq!(etf_state(&harness, &etf.address).gate, EtfGate::Open);
+
+    let gate = EtfGate::MinimumBalance { mint: Pubkey::new_unique(), amount: 10 };
+    let outcome = harness
+        .process_instruction(set_gate_ix(etf.address, etf.lister, gate), &[etf.lister])
+        .unwrap();
+    assert_eq!(etf_state(&harness, &etf.address).gate, gate);
+    let events = outcome.events::<GateUpdatedEvent>();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].gate, gate);
+}
+
+#[test]
+fn test_set_gate_lister_only() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let stranger = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+
+    assert!(harness
+        .process_instruction(
+            set_gate_ix(etf.address, stranger, EtfGate::MerkleAllowlist { root: [1; 32] }),
+            &[stranger],
+        )
+        .is_err());
+    assert_error(
+        harness.process_instruction(
+            set_gate_ix(etf.address, etf.lister, EtfGate::MinimumBalance { mint: Pubkey::new_unique(), amount: 0 }),
+            &[etf.lister],
+        ),
+        ErrorCode::InvalidAmount,
+    );
+}
+
+#[test]
+fn test_open_etf_ignores_gate_account() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 2);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+
+    gated_buy(&mut harness, &etf, investor, Some(Pubkey::new_unique())).unwrap();
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+    assert_eq!(shares(&harness, &etf.address, &investor), 2 * 990_000_000);
+}
+
+#[test]
+fn test_allowlisted_investor_buys_with_pass() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 2);
+    let wallets = [(); 4].map(|_| funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL));
+    let (root, proofs) = allowlist(&wallets);
+    gate(&mut harness, &etf, EtfGate::MerkleAllowlist { root });
+
+    assert_error(gated_buy(&mut harness, &etf, wallets[2], None), ErrorCode::NotAllowlisted);
+
+    harness
+        .process_instruction(verify_allowlist_ix(etf.address, wallets[2], proofs[2].clone()), &[wallets[2]])
+        .unwrap();
+    let pass: mtf_etf::AllowlistPass = harness
+        .anchor_account(&allowlist_pass_address(&etf.address, &wallets[2]))
+        .unwrap();
+    assert_eq!(pass.investor, wallets[2]);
+    assert_eq!(pass.root, root);
+
+    let pass = allowlist_pass_address(&etf.address, &wallets[2]);
+    gated_buy(&mut harness, &etf, wallets[2], Some(pass)).unwrap();
+    gated_buy(&mut harness, &etf, wallets[2], Some(pass)).unwrap();
+    assert_eq!(shares(&harness, &etf.address, &wallets[2]), 2 * 990_000_000);
+}
+
+#[test]
+fn test_allowlist_rejects_outsiders() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let wallets = [(); 4].map(|_| funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL));
+    let (root, proofs) = allowlist(&wallets);
+    gate(&mut harness, &etf, EtfGate::MerkleAllowlist { root });
+    let outsider = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+
+    // A member's proof does not verify for anyone else
+    assert_error(
+        harness.process_instruction(verify_allowlist_ix(etf.address, outsider, proofs[0].clone()), &[outsider]),
+        ErrorCode::NotAllowlisted,
+    );
+    assert_error(
+        harness.process_instruction(verify_allowlist_ix(etf.address, wallets[1], proofs[0].clone()), &[wallets[1]]),
+        ErrorCode::NotAllowlisted,
+    );
+
+    // Nor does a member's pass
+    harness
+        .process_instruction(verify_allowlist_ix(etf.address, wallets[0], proofs[0].clone()), &[wallets[0]])
+        .unwrap();
+    let member_pass = allowlist_pass_address(&etf.address, &wallets[0]);
+    assert_error(gated_buy(&mut harness, &etf, outsider, Some(member_pass)), ErrorCode::NotAllowlisted);
+    assert_error(gated_buy(&mut harness, &etf, outsider, Some(outsider)), ErrorCode::NotAllowlisted);
+}
+
+#[test]
+fn test_new_root_requires_new_pass() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let wallets = [(); 4].map(|_| funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL));
+    let (root, proofs) = allowlist(&wallets);
+    gate(&mut harness, &etf, EtfGate::MerkleAllowlist { root });
+    harness
+        .process_instruction(verify_allowlist_ix(etf.address, wallets[0], proofs[0].clone()), &[wallets[0]])
+        .unwrap();
+    let pass = allowlist_pass_address(&etf.address, &wallets[0]);
+
+    // The lister swaps one member out; the remaining members re-verify
+    let newcomer = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+    let (new_root, new_proofs) = allowlist(&[wallets[0], wallets[1], wallets[2], newcomer]);
+    gate(&mut harness, &etf, EtfGate::MerkleAllowlist { root: new_root });
+    assert_error(gated_buy(&mut harness, &etf, wallets[0], Some(pass)), ErrorCode::NotAllowlisted);
+
+    harness
+        .process_instruction(verify_allowlist_ix(etf.address, wallets[0], new_proofs[0].clone()), &[wallets[0]])
+        .unwrap();
+    gated_buy(&mut harness, &etf, wallets[0], Some(pass)).unwrap();
+}
+
+#[test]
+fn test_verify_allowlist_requires_merkle_gate() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let investor = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+
+    assert_error(
+        harness.process_instruction(verify_allowlist_ix(etf.address, investor, vec![]), &[investor]),
+        ErrorCode::NotAllowlisted,
+    );
+}
+
+#[test]
+fn test_minimum_balance_gate() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 2);
+    let collection = Pubkey::new_unique();
+    harness.create_mint(collection, &etf.lister, 0);
+    gate(&mut harness, &etf, EtfGate::MinimumBalance { mint: collection, amount: 2 });
+
+    let holder = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    let holder_tokens = Pubkey::new_unique();
+    harness.create_token_account(holder_tokens, &collection, &holder, 2);
+    gated_buy(&mut harness, &etf, holder, Some(holder_tokens)).unwrap();
+    assert_eq!(shares(&harness, &etf.address, &holder), 990_000_000);
+
+    // Too small a balance, somebody else's tokens, the wrong mint, no account
+    let small = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    let small_tokens = Pubkey::new_unique();
+    harness.create_token_account(small_tokens, &collection, &small, 1);
+    assert_error(gated_buy(&mut harness, &etf, small, Some(small_tokens)), ErrorCode::NotAllowlisted);
+    assert_error(gated_buy(&mut harness, &etf, small, Some(holder_tokens)), ErrorCode::NotAllowlisted);
+    let other_tokens = Pubkey::new_unique();
+    harness.create_token_account(other_tokens, &etf.mints[0], &small, 100);
+    assert_error(gated_buy(&mut harness, &etf, small, Some(other_tokens)), ErrorCode::NotAllowlisted);
+    assert_error(gated_buy(&mut harness, &etf, small, None), ErrorCode::NotAllowlisted);
+}
+
+#[test]
+fn test_gate_does_not_block_sells() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+
+    gate(&mut harness, &etf, EtfGate::MerkleAllowlist { root: [7; 32] });
+    assert_error(buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL), ErrorCode::NotAllowlisted);
+    sell(&mut harness, &etf, investor, 990_000_000).unwrap();
+    assert_eq!(shares(&harness, &etf.address, &investor), 0);
+}
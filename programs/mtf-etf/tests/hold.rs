@@ -0,0 +1,212 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::*;
+use mtf_etf::{
+    calculate_fees, ETFSoldEvent, ErrorCode, HoldPeriodUpdatedEvent, Position, MAX_EARLY_EXIT_FEE_BPS, MAX_HOLD_SECONDS,
+};
+use program_harness::Harness;
+
+const DAY: i64 = 24 * 60 * 60;
+
+fn hold(harness: &mut Harness, etf: &TestEtf, min_hold_seconds: i64, early_exit_fee_bps: u16) {
+    harness
+        .process_instruction(
+            set_hold_period_ix(etf.address, etf.lister, min_hold_seconds, early_exit_fee_bps),
+            &[etf.lister],
+        )
+        .unwrap();
+}
+
+fn last_buy_ts(harness: &Harness, etf: &TestEtf, investor: &Pubkey) -> i64 {
+    let position: Position = harness.anchor_account(&position_address(&etf.address, investor)).unwrap();
+    position.last_buy_ts
+}
+
+#[test]
+fn test_set_hold_period() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let state = etf_state(&harness, &etf.address);
+    assert_eq!((state.min_hold_seconds, state.early_exit_fee_bps), (0, 0));
+
+    let outcome = harness
+        .process_instruction(set_hold_period_ix(etf.address, etf.lister, 7 * DAY, 200), &[etf.lister])
+        .unwrap();
+    let state = etf_state(&harness, &etf.address);
+    assert_eq!((state.min_hold_seconds, state.early_exit_fee_bps), (7 * DAY, 200));
+    let events = outcome.events::<HoldPeriodUpdatedEvent>();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].min_hold_seconds, 7 * DAY);
+    assert_eq!(events[0].early_exit_fee_bps, 200);
+}
+
+#[test]
+fn test_set_hold_period_validation() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let stranger = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+
+    assert!(harness
+        .process_instruction(set_hold_period_ix(etf.address, stranger, DAY, 0), &[stranger])
+        .is_err());
+    for (seconds, bps) in [(-1, 0), (MAX_HOLD_SECONDS + 1, 0), (DAY, MAX_EARLY_EXIT_FEE_BPS + 1)] {
+        assert_error(
+            harness.process_instruction(set_hold_period_ix(etf.address, etf.lister, seconds, bps), &[etf.lister]),
+            ErrorCode::InvalidHoldPeriod,
+        );
+    }
+}
+
+#[test]
+fn test_buy_records_last_buy_ts() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+    let first = harness.clock().unix_timestamp;
+    assert_eq!(last_buy_ts(&harness, &etf, &investor), first);
+
+    harness.advance_time(DAY);
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+    assert_eq!(last_buy_ts(&harness, &etf, &investor), first + DAY);
+}
+
+#[test]
+fn test_sell_blocked_during_hold_period() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    hold(&mut harness, &etf, DAY, 0);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+
+    assert_error(sell(&mut harness, &etf, investor, 1_000), ErrorCode::HoldPeriodActive);
+    harness.advance_time(DAY - 1);
+    assert_error(sell(&mut harness, &etf, investor, 1_000), ErrorCode::HoldPeriodActive);
+    harness.advance_time(1);
+    sell(&mut harness, &etf, investor, 1_000).unwrap();
+
+    // Buying again restarts the clock for the whole position
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+    assert_error(sell(&mut harness, &etf, investor, 2_000), ErrorCode::HoldPeriodActive);
+}
+
+#[test]
+fn test_early_exit_fee_stays_with_holders() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    hold(&mut harness, &etf, DAY, 500);
+    let patient = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    let flipper = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    buy(&mut harness, &etf, patient, LAMPORTS_PER_SOL).unwrap();
+    buy(&mut harness, &etf, flipper, LAMPORTS_PER_SOL).unwrap();
+
+    let etf_before = harness.lamports(&etf.address);
+    let flipper_before = harness.lamports(&flipper);
+    let outcome = sell(&mut harness, &etf, flipper, 990_000_000).unwrap();
+
+    // 5% early-exit fee on top of the usual 1%
+    let (creator_fee, dev_fee) = calculate_fees(990_000_000);
+    let exit_fee = 49_500_000;
+    assert_eq!(harness.lamports(&flipper) - flipper_before, 990_000_000 - creator_fee - dev_fee - exit_fee);
+    assert_eq!(etf_before - harness.lamports(&etf.address), 990_000_000 - exit_fee);
+
+    let event = &outcome.events::<ETFSoldEvent>()[0];
+    assert_eq!(event.early_exit_fee, exit_fee);
+    assert_eq!(event.sol_amount, 990_000_000 - creator_fee - dev_fee - exit_fee);
+    // The remaining holder's shares are now backed by more SOL
+    assert_eq!(etf_state(&harness, &etf.address).sol_assets, 990_000_000 + exit_fee);
+    assert_eq!(event.nav_per_share, (990_000_000 + exit_fee) * mtf_etf::NAV_SCALE / 990_000_000);
+
+    // After the hold period the remaining holder sells for the usual fees
+    // and collects the whole exit fee
+    harness.advance_time(DAY);
+    let patient_before = harness.lamports(&patient);
+    let outcome = sell(&mut harness, &etf, patient, 990_000_000).unwrap();
+    let (creator_fee, dev_fee) = calculate_fees(990_000_000 + exit_fee);
+    assert_eq!(harness.lamports(&patient) - patient_before, 990_000_000 + exit_fee - creator_fee - dev_fee);
+    assert_eq!(outcome.events::<ETFSoldEvent>()[0].early_exit_fee, 0);
+    assert_eq!(etf_state(&harness, &etf.address).sol_assets, 0);
+}
+
+#[test]
+fn test_last_holder_exits_without_early_exit_fee() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    hold(&mut harness, &etf, DAY, 500);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+
+    let outcome = sell(&mut harness, &etf, investor, 990_000_000).unwrap();
+    assert_eq!(outcome.events::<ETFSoldEvent>()[0].early_exit_fee, 0);
+    assert_eq!(etf_state(&harness, &etf.address).total_supply, 0);
+}
+
+#[test]
+fn test_unwrap_restarts_hold_period_for_new_holders() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    hold(&mut harness, &etf, DAY, 0);
+    let holder = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    let other = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+    buy(&mut harness, &etf, holder, LAMPORTS_PER_SOL).unwrap();
+    harness
+        .process_instruction(create_share_mint_ix(etf.address, holder), &[holder])
+        .unwrap();
+    let tokens = Pubkey::new_unique();
+    harness.create_token_account(tokens, &share_mint_address(&etf.address), &holder, 0);
+
+    // Shares wrapped straight after the buy cannot be unwrapped into a fresh
+    // position and sold
+    harness
+        .process_instruction(wrap_shares_ix(etf.address, holder, tokens, 500_000_000), &[holder])
+        .unwrap();
+    harness.advance_time(DAY);
+    harness
+        .process_instruction(unwrap_shares_ix(etf.address, holder, tokens, other, 250_000_000), &[holder])
+        .unwrap();
+    assert_eq!(last_buy_ts(&harness, &etf, &other), harness.clock().unix_timestamp);
+    assert_error(sell(&mut harness, &etf, other, 1_000), ErrorCode::HoldPeriodActive);
+    sell(&mut harness, &etf, holder, 1_000).unwrap();
+
+    // Unwrapping into the owner's own position restarts their clock too
+    harness
+        .process_instruction(unwrap_shares_ix(etf.address, holder, tokens, holder, 250_000_000), &[holder])
+        .unwrap();
+    assert_eq!(last_buy_ts(&harness, &etf, &holder), harness.clock().unix_timestamp);
+    assert_error(sell(&mut harness, &etf, holder, 1_000), ErrorCode::HoldPeriodActive);
+}
+
+#[test]
+fn test_unwrap_cannot_extend_another_holders_hold() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    hold(&mut harness, &etf, DAY, 0);
+    let victim = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    let attacker = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    buy(&mut harness, &etf, victim, LAMPORTS_PER_SOL).unwrap();
+    let bought_at = last_buy_ts(&harness, &etf, &victim);
+    buy(&mut harness, &etf, attacker, LAMPORTS_PER_SOL).unwrap();
+    harness
+        .process_instruction(create_share_mint_ix(etf.address, attacker), &[attacker])
+        .unwrap();
+    let tokens = Pubkey::new_unique();
+    harness.create_token_account(tokens, &share_mint_address(&etf.address), &attacker, 0);
+    harness
+        .process_instruction(wrap_shares_ix(etf.address, attacker, tokens, 10), &[attacker])
+        .unwrap();
+
+    // Dust unwrapped into the victim's position just before their hold ends
+    // leaves their clock alone
+    harness.advance_time(DAY - 1);
+    harness
+        .process_instruction(unwrap_shares_ix(etf.address, attacker, tokens, victim, 1), &[attacker])
+        .unwrap();
+    assert_eq!(last_buy_ts(&harness, &etf, &victim), bought_at);
+    harness.advance_time(1);
+    harness
+        .process_instruction(unwrap_shares_ix(etf.address, attacker, tokens, victim, 1), &[attacker])
+        .unwrap();
+    sell(&mut harness, &etf, victim, 990_000_002).unwrap();
+}
@@ -0,0 +1,284 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::*;
+use mtf_etf::{
+    ETFCreatedEvent, EtfCategory, EtfStatus, ErrorCode, MetadataUpdatedEvent, Registry, RegistryPage,
+    MAX_NAME_LEN, MAX_SYMBOL_LEN, MAX_URI_LEN, REGISTRY_PAGE_SIZE,
+};
+
+fn initialize(harness: &mut program_harness::Harness, lister: Pubkey, etf_index: u8, tokens: Vec<Pubkey>, metadata: Metadata) -> TxResult {
+    let registry_index = next_registry_index(harness);
+    harness.process_instruction(
+        initialize_etf_ix(lister, etf_index, registry_index, tokens, metadata),
+        &[lister],
+    )
+}
+
+fn tokens(count: usize) -> Vec<Pubkey> {
+    (0..count).map(|_| Pubkey::new_unique()).collect()
+}
+
+#[test]
+fn test_initialize_etf_stores_state_and_registers() {
+    let mut harness = setup();
+    let lister = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    let mints = tokens(3);
+
+    let outcome = initialize(&mut harness, lister, 2, mints.clone(), Metadata::default()).unwrap();
+
+    let etf_key = etf_address(&lister, 2);
+    let etf = etf_state(&harness, &etf_key);
+    assert_eq!(etf.lister, lister);
+    assert_eq!(etf.etf_index, 2);
+    assert_eq!(etf.token_addresses, mints);
+    assert_eq!(etf.total_supply, 0);
+    assert_eq!(etf.registry_index, 0);
+    assert_eq!(etf.name, "Dog Coins");
+    assert_eq!(etf.symbol, "DOGS");
+    assert_eq!(etf.category, EtfCategory::Meme);
+    assert_eq!(etf.status, EtfStatus::Active);
+    assert_eq!(etf.lister_last_active_ts, harness.clock().unix_timestamp);
+
+    let registry: Registry = harness.anchor_account(&registry_address()).unwrap();
+    assert_eq!(registry.etf_count, 1);
+    assert_eq!(registry.active_count, 1);
+    let page: RegistryPage = harness.anchor_account(&registry_page_address(0)).unwrap();
+    assert_eq!(page.page_index, 0);
+    assert_eq!(page.entries.len(), 1);
+    assert_eq!(page.entries[0].etf, etf_key);
+    assert!(!page.entries[0].closed);
+
+    let events = outcome.events::<ETFCreatedEvent>();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].etf_address, etf_key);
+    assert_eq!(events[0].lister, lister);
+    assert_eq!(events[0].token_count, 3);
+    assert_eq!(events[0].timestamp, harness.clock().unix_timestamp);
+}
+
+#[test]
+fn test_created_event_carries_metadata() {
+    let mut harness = setup();
+    let lister = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    let metadata = Metadata {
+        name: "Robot Coins".to_string(),
+        symbol: "BOTS".to_string(),
+        uri: "https://example.com/bots.json".to_string(),
+        category: EtfCategory::AI,
+    };
+
+    let outcome = initialize(&mut harness, lister, 0, tokens(2), metadata).unwrap();
+
+    // Indexers list the ETF from this event alone, without a MetadataUpdatedEvent
+    let event = &outcome.events::<ETFCreatedEvent>()[0];
+    assert_eq!(event.name, "Robot Coins");
+    assert_eq!(event.symbol, "BOTS");
+    assert_eq!(event.uri, "https://example.com/bots.json");
+    assert_eq!(event.category, EtfCategory::AI);
+    assert!(outcome.events::<MetadataUpdatedEvent>().is_empty());
+}
+
+#[test]
+fn test_initialize_etf_lister_pays_rent() {
+    let mut harness = setup();
+    let lister = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    let total_before = harness.total_lamports();
+
+    initialize(&mut harness, lister, 0, tokens(1), Metadata::default()).unwrap();
+
+    let rent_paid = harness.lamports(&etf_address(&lister, 0))
+        + harness.lamports(&registry_address())
+        + harness.lamports(&registry_page_address(0));
+    assert_eq!(harness.lamports(&lister), 10 * LAMPORTS_PER_SOL - rent_paid);
+    assert_eq!(harness.total_lamports(), total_before);
+}
+
+#[test]
+fn test_initialize_etf_appends_to_registry() {
+    let mut harness = setup();
+    let first = create_etf(&mut harness, 1);
+    let second = create_etf(&mut harness, 2);
+
+    assert_eq!(first.registry_index, 0);
+    assert_eq!(second.registry_index, 1);
+    let page: RegistryPage = harness.anchor_account(&registry_page_address(1)).unwrap();
+    let listed: Vec<Pubkey> = page.entries.iter().map(|entry| entry.etf).collect();
+    assert_eq!(listed, vec![first.address, second.address]);
+}
+
+#[test]
+fn test_initialize_etf_opens_new_registry_page() {
+    let mut harness = setup();
+    let first = create_etf(&mut harness, 1);
+    // Jump the registry to the last slot of page 0
+    tamper::<Registry>(&mut harness, &registry_address(), |registry| {
+        registry.etf_count = REGISTRY_PAGE_SIZE as u64;
+    });
+
+    let second = create_etf(&mut harness, 1);
+
+    assert_eq!(second.registry_index, REGISTRY_PAGE_SIZE as u64);
+    let page: RegistryPage = harness.anchor_account(&registry_page_address(second.registry_index)).unwrap();
+    assert_eq!(page.page_index, 1);
+    assert_eq!(page.entries[0].etf, second.address);
+    let first_page: RegistryPage = harness.anchor_account(&registry_page_address(0)).unwrap();
+    assert_eq!(first_page.entries[0].etf, first.address);
+}
+
+#[test]
+fn test_initialize_etf_twice_fails() {
+    let mut harness = setup();
+    let lister = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    initialize(&mut harness, lister, 0, tokens(1), Metadata::default()).unwrap();
+
+    // The ETF PDA already exists, so the System program refuses to create it
+    let result = initialize(&mut harness, lister, 0, tokens(1), Metadata::default());
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_initialize_etf_requires_lister_signature() {
+    let mut harness = setup();
+    let lister = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+
+    let ix = initialize_etf_ix(lister, 0, 0, tokens(1), Metadata::default());
+    let result = harness.process_instruction(ix, &[]);
+    assert_eq!(
+        result.unwrap_err().error,
+        program_harness::HarnessError::MissingSignature(lister)
+    );
+}
+
+#[test]
+fn test_initialize_etf_max_etfs_reached() {
+    let mut harness = setup();
+    let lister = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    let result = initialize(&mut harness, lister, 5, tokens(1), Metadata::default());
+    assert_error(result, ErrorCode::MaxEtfsReached);
+}
+
+#[test]
+fn test_initialize_etf_invalid_token_count() {
+    let mut harness = setup();
+    let lister = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+
+    let result = initialize(&mut harness, lister, 0, Vec::new(), Metadata::default());
+    assert_error(result, ErrorCode::InvalidTokenCount);
+
+    let result = initialize(&mut harness, lister, 0, tokens(11), Metadata::default());
+    assert_error(result, ErrorCode::InvalidTokenCount);
+
+    initialize(&mut harness, lister, 0, tokens(10), Metadata::default()).unwrap();
+}
+
+#[test]
+fn test_initialize_etf_invalid_name() {
+    let mut harness = setup();
+    let lister = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+
+    for name in [String::new(), "n".repeat(MAX_NAME_LEN + 1)] {
+        let metadata = Metadata { name, ..Metadata::default() };
+        let result = initialize(&mut harness, lister, 0, tokens(1), metadata);
+        assert_error(result, ErrorCode::InvalidName);
+    }
+}
+
+#[test]
+fn test_initialize_etf_invalid_symbol() {
+    let mut harness = setup();
+    let lister = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+
+    for symbol in [String::new(), "S".repeat(MAX_SYMBOL_LEN + 1)] {
+        let metadata = Metadata { symbol, ..Metadata::default() };
+        let result = initialize(&mut harness, lister, 0, tokens(1), metadata);
+        assert_error(result, ErrorCode::InvalidSymbol);
+    }
+}
+
+#[test]
+fn test_initialize_etf_uri_too_long() {
+    let mut harness = setup();
+    let lister = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+
+    let metadata = Metadata { uri: "u".repeat(MAX_URI_LEN + 1), ..Metadata::default() };
+    let result = initialize(&mut harness, lister, 0, tokens(1), metadata);
+    assert_error(result, ErrorCode::UriTooLong);
+
+    // Limits are inclusive and fit in the allocated account
+    let metadata = Metadata {
+        name: "n".repeat(MAX_NAME_LEN),
+        symbol: "S".repeat(MAX_SYMBOL_LEN),
+        uri: "u".repeat(MAX_URI_LEN),
+        category: EtfCategory::Other,
+    };
+    initialize(&mut harness, lister, 0, tokens(10), metadata).unwrap();
+}
+
+#[test]
+fn test_failed_initialize_leaves_no_accounts() {
+    let mut harness = setup();
+    let lister = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+
+    let metadata = Metadata { name: String::new(), ..Metadata::default() };
+    assert!(initialize(&mut harness, lister, 0, tokens(1), metadata).is_err());
+
+    assert!(harness.account(&etf_address(&lister, 0)).is_none());
+    assert!(harness.account(&registry_address()).is_none());
+    assert_eq!(harness.lamports(&lister), 10 * LAMPORTS_PER_SOL);
+}
+
+// ============================================================================
+// update_metadata
+// ============================================================================
+
+#[test]
+fn test_update_metadata() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 2);
+    harness.advance_time(3_600);
+
+    let metadata = Metadata {
+        name: "AI Agents".to_string(),
+        symbol: "AGNT".to_string(),
+        uri: "https://example.com/agents.json".to_string(),
+        category: EtfCategory::AI,
+    };
+    let outcome = harness
+        .process_instruction(update_metadata_ix(etf.address, etf.lister, metadata), &[etf.lister])
+        .unwrap();
+
+    let state = etf_state(&harness, &etf.address);
+    assert_eq!(state.name, "AI Agents");
+    assert_eq!(state.symbol, "AGNT");
+    assert_eq!(state.category, EtfCategory::AI);
+    assert_eq!(state.lister_last_active_ts, harness.clock().unix_timestamp);
+
+    let events = outcome.events::<MetadataUpdatedEvent>();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].etf_address, etf.address);
+    assert_eq!(events[0].symbol, "AGNT");
+}
+
+#[test]
+fn test_update_metadata_rejects_other_signer() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let stranger = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+
+    let result = harness.process_instruction(
+        update_metadata_ix(etf.address, stranger, Metadata::default()),
+        &[stranger],
+    );
+    assert_error(result, anchor_lang::error::ErrorCode::ConstraintHasOne);
+}
+
+#[test]
+fn test_update_metadata_validates() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+
+    let metadata = Metadata { symbol: String::new(), ..Metadata::default() };
+    let result = harness.process_instruction(update_metadata_ix(etf.address, etf.lister, metadata), &[etf.lister]);
+    assert_error(result, ErrorCode::InvalidSymbol);
+}
@@ -0,0 +1,240 @@
+// Property-based checks that random buy/sell/close sequences neither create
+// nor lose lamports. Every step is mirrored in a simple model and the chain
+// state is compared against it after each instruction.
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::*;
+use mtf_etf::{calculate_fees, TREASURY, POSITION_SPACE, PROTOCOL_MIN_BUY_LAMPORTS};
+use program_harness::Harness;
+use proptest::prelude::*;
+
+const INVESTORS: usize = 3;
+const INVESTOR_FUNDS: u64 = 100 * LAMPORTS_PER_SOL;
+
+#[derive(Debug, Clone)]
+enum Op {
+    Buy { investor: usize, sol_amount: u64 },
+    // Sell `bps` basis points of the current position; above 10_000 oversells
+    Sell { investor: usize, bps: u64 },
+    Close,
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        4 => (0..INVESTORS, 0..=2 * LAMPORTS_PER_SOL)
+            .prop_map(|(investor, sol_amount)| Op::Buy { investor, sol_amount }),
+        4 => (0..INVESTORS, 0..=11_000u64).prop_map(|(investor, bps)| Op::Sell { investor, bps }),
+        1 => Just(Op::Close),
+    ]
+}
+
+// Expected balances, updated from the fee formula rather than from chain state
+struct Model {
+    supply: u64,
+    positions: [u64; INVESTORS],
+    has_position: [bool; INVESTORS],
+    wallets: [u64; INVESTORS],
+    etf: u64,
+    lister: u64,
+    dev: u64,
+    creator_fees: u64,
+    dev_fees: u64,
+    close_refund: u64,
+    closed: bool,
+}
+
+struct World {
+    harness: Harness,
+    etf: TestEtf,
+    investors: [Pubkey; INVESTORS],
+    lister_start: u64,
+    dev_start: u64,
+    total_lamports: u128,
+}
+
+impl World {
+    fn new() -> (Self, Model) {
+        let mut harness = setup();
+        let etf = create_etf(&mut harness, 3);
+        let investors = [(); INVESTORS].map(|_| funded_wallet(&mut harness, INVESTOR_FUNDS));
+        let model = Model {
+            supply: 0,
+            positions: [0; INVESTORS],
+            has_position: [false; INVESTORS],
+            wallets: [INVESTOR_FUNDS; INVESTORS],
+            etf: harness.lamports(&etf.address),
+            lister: harness.lamports(&etf.lister),
+            dev: harness.lamports(&TREASURY),
+            creator_fees: 0,
+            dev_fees: 0,
+            close_refund: 0,
+            closed: false,
+        };
+        let world = World {
+            lister_start: model.lister,
+            dev_start: model.dev,
+            total_lamports: harness.total_lamports(),
+            harness,
+            etf,
+            investors,
+        };
+        (world, model)
+    }
+
+    fn apply(&mut self, model: &mut Model, op: &Op) {
+        match *op {
+            Op::Buy { investor, sol_amount } => {
+                let result = buy(&mut self.harness, &self.etf, self.investors[investor], sol_amount);
+                let expect_ok = !model.closed && sol_amount >= PROTOCOL_MIN_BUY_LAMPORTS;
+                assert_eq!(result.is_ok(), expect_ok, "{op:?}: {result:?}");
+                if !expect_ok {
+                    return;
+                }
+
+                let (creator_fee, dev_fee) = calculate_fees(sol_amount);
+                let minted = sol_amount - creator_fee - dev_fee;
+                if !model.has_position[investor] {
+                    model.has_position[investor] = true;
+                    model.wallets[investor] -= self.harness.rent().minimum_balance(POSITION_SPACE);
+                }
+                model.wallets[investor] -= sol_amount;
+                model.positions[investor] += minted;
+                model.supply += minted;
+                model.etf += minted;
+                model.lister += creator_fee;
+                model.dev += dev_fee;
+                model.creator_fees += creator_fee;
+                model.dev_fees += dev_fee;
+            }
+            Op::Sell { investor, bps } => {
+                let held = model.positions[investor];
+                let amount = (held as u128 * bps as u128 / 10_000) as u64;
+                let result = sell(&mut self.harness, &self.etf, self.investors[investor], amount);
+                let expect_ok = !model.closed && amount > 0 && amount <= held;
+                assert_eq!(result.is_ok(), expect_ok, "{op:?} amount {amount}: {result:?}");
+                if !expect_ok {
+                    return;
+                }
+
+                let (creator_fee, dev_fee) = calculate_fees(amount);
+                model.wallets[investor] += amount - creator_fee - dev_fee;
+                model.positions[investor] -= amount;
+                model.supply -= amount;
+                model.etf -= amount;
+                model.lister += creator_fee;
+                model.dev += dev_fee;
+                model.creator_fees += creator_fee;
+                model.dev_fees += dev_fee;
+            }
+            Op::Close => {
+                let result = self.harness.process_instruction(
+                    close_etf_ix(self.etf.address, self.etf.lister, self.etf.registry_index, &lister_vaults(&self.etf)),
+                    &[self.etf.lister],
+                );
+                let expect_ok = !model.closed && model.supply == 0;
+                assert_eq!(result.is_ok(), expect_ok, "{op:?}: {result:?}");
+                if expect_ok {
+                    model.lister += model.etf;
+                    model.close_refund = model.etf;
+                    model.etf = 0;
+                    model.closed = true;
+                }
+            }
+        }
+    }
+
+    fn check(&self, model: &Model) {
+        let harness = &self.harness;
+        let rent = harness.rent();
+
+        // No lamports created or destroyed across the whole bank
+        assert_eq!(harness.total_lamports(), self.total_lamports);
+
+        // Every balance matches the fee formula exactly
+        for (investor, expected) in self.investors.iter().zip(model.wallets) {
+            assert_eq!(harness.lamports(investor), expected);
+        }
+        assert_eq!(harness.lamports(&self.etf.address), model.etf);
+        assert_eq!(harness.lamports(&self.etf.lister), model.lister);
+        assert_eq!(harness.lamports(&TREASURY), model.dev);
+
+        // Fee recipients received exactly the fees charged
+        assert_eq!(
+            harness.lamports(&self.etf.lister) - self.lister_start,
+            model.creator_fees + model.close_refund
+        );
+        assert_eq!(harness.lamports(&TREASURY) - self.dev_start, model.dev_fees);
+
+        if model.closed {
+            assert!(harness.account(&self.etf.address).is_none());
+            return;
+        }
+
+        // ETF holds rent plus every outstanding 1:1 redemption claim
+        let etf = etf_state(harness, &self.etf.address);
+        let etf_account = harness.account(&self.etf.address).unwrap();
+        assert!(etf_account.lamports >= rent.minimum_balance(etf_account.data.len()) + etf.total_supply);
+
+        // Positions add up to supply
+        let positions: u64 = self
+            .investors
+            .iter()
+            .map(|investor| shares(harness, &self.etf.address, investor))
+            .sum();
+        assert_eq!(positions, etf.total_supply);
+        assert_eq!(etf.total_supply, model.supply);
+
+        // Nothing drops below rent exemption
+        let mut touched = vec![self.etf.address, self.etf.lister, TREASURY, registry_address()];
+        touched.push(registry_page_address(self.etf.registry_index));
+        touched.extend(self.investors);
+        touched.extend(self.investors.iter().map(|investor| position_address(&self.etf.address, investor)));
+        for address in touched {
+            if let Some(account) = harness.account(&address) {
+                assert!(
+                    rent.is_exempt(account.lamports, account.data.len()),
+                    "{address} is below rent exemption"
+                );
+            }
+        }
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn test_random_trading_conserves_value(ops in prop::collection::vec(op(), 1..40)) {
+        let (mut world, mut model) = World::new();
+        world.check(&model);
+        for op in &ops {
+            world.apply(&mut model, op);
+            world.check(&model);
+        }
+    }
+
+    #[test]
+    fn test_full_exit_always_allows_close(ops in prop::collection::vec(op(), 1..25)) {
+        let (mut world, mut model) = World::new();
+        for op in &ops {
+            world.apply(&mut model, op);
+        }
+
+        // Everyone sells out; the ETF must then be closable with only rent left
+        for investor in 0..INVESTORS {
+            if !model.closed && model.positions[investor] > 0 {
+                world.apply(&mut model, &Op::Sell { investor, bps: 10_000 });
+            }
+        }
+        world.check(&model);
+        if !model.closed {
+            let etf_account = world.harness.account(&world.etf.address).unwrap();
+            let rent = world.harness.rent().minimum_balance(etf_account.data.len());
+            prop_assert_eq!(model.etf, rent);
+            world.apply(&mut model, &Op::Close);
+        }
+        prop_assert!(model.closed);
+        world.check(&model);
+    }
+}
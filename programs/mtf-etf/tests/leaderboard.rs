@@ -0,0 +1,222 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::*;
+use mtf_etf::{
+    EpochReturnSubmittedEvent, ErrorCode, LeaderboardEpoch, LEADERBOARD_EPOCH_SECONDS, LEADERBOARD_SIZE,
+    LEADERBOARD_TWAP_SECONDS, NAV_SCALE, NAV_SNAPSHOT_INTERVAL_SECONDS,
+};
+use program_harness::Harness;
+
+// The next epoch, with the clock moved to its start and its leaderboard created
+fn next_epoch(harness: &mut Harness) -> u64 {
+    let epoch_id = (harness.clock().unix_timestamp / LEADERBOARD_EPOCH_SECONDS + 1) as u64;
+    harness.set_unix_timestamp(epoch_id as i64 * LEADERBOARD_EPOCH_SECONDS);
+    let payer = funded_wallet(harness, LAMPORTS_PER_SOL);
+    harness
+        .process_instruction(create_leaderboard_epoch_ix(epoch_id, payer), &[payer])
+        .unwrap();
+    epoch_id
+}
+
+// ETF with `invested` SOL of net assets and a NAV history
+fn tracked_etf(harness: &mut Harness, invested: u64) -> TestEtf {
+    let etf = create_etf(harness, 1);
+    let investor = funded_wallet(harness, invested + LAMPORTS_PER_SOL);
+    buy(harness, &etf, investor, invested).unwrap();
+    let payer = funded_wallet(harness, LAMPORTS_PER_SOL);
+    harness
+        .process_instruction(create_nav_history_ix(etf.address, payer), &[payer])
+        .unwrap();
+    etf
+}
+
+fn snapshot(harness: &mut Harness, etf: &TestEtf) {
+    harness.process_instruction(snapshot_nav_ix(etf.address), &[]).unwrap();
+}
+
+fn submit(harness: &mut Harness, epoch_id: u64, etf: &TestEtf) -> TxResult {
+    harness.process_instruction(submit_epoch_return_ix(epoch_id, etf.address), &[])
+}
+
+fn leaderboard(harness: &Harness, epoch_id: u64) -> LeaderboardEpoch {
+    harness.anchor_account(&leaderboard_address(epoch_id)).unwrap()
+}
+
+// Crank every ETF's history into the start of the epoch, raise each NAV by
+// its gain (in basis points of net assets) mid-epoch and crank into the end
+fn run_epoch(harness: &mut Harness, epoch_id: u64, etfs: &[(&TestEtf, u64)]) {
+    let start = epoch_id as i64 * LEADERBOARD_EPOCH_SECONDS;
+    let addresses: Vec<Pubkey> = etfs.iter().map(|(etf, _)| etf.address).collect();
+    crank_nav_histories(harness, &addresses, start);
+    harness.set_unix_timestamp(start + LEADERBOARD_EPOCH_SECONDS / 2);
+    for (etf, gain_bps) in etfs {
+        let supply = etf_state(harness, &etf.address).total_supply;
+        shift_net_assets(harness, &etf.address, (supply * gain_bps / 10_000) as i64);
+    }
+    crank_nav_histories(harness, &addresses, start + LEADERBOARD_EPOCH_SECONDS);
+}
+
+#[test]
+fn test_submissions_rank_by_epoch_return() {
+    let mut harness = setup();
+    let flat = tracked_etf(&mut harness, 2 * LAMPORTS_PER_SOL);
+    let best = tracked_etf(&mut harness, 2 * LAMPORTS_PER_SOL);
+    let good = tracked_etf(&mut harness, 2 * LAMPORTS_PER_SOL);
+    let epoch_id = next_epoch(&mut harness);
+    run_epoch(&mut harness, epoch_id, &[(&flat, 0), (&best, 5_000), (&good, 1_000)]);
+
+    for etf in [&flat, &best] {
+        submit(&mut harness, epoch_id, etf).unwrap();
+    }
+    let outcome = submit(&mut harness, epoch_id, &good).unwrap();
+    let event = &outcome.events::<EpochReturnSubmittedEvent>()[0];
+    assert_eq!(event.epoch_id, epoch_id);
+    assert_eq!(event.etf_address, good.address);
+    assert_eq!(event.lister, good.lister);
+    assert_eq!(event.return_bps, 1_000);
+    assert_eq!(event.rank, 1);
+
+    let board = leaderboard(&harness, epoch_id);
+    assert_eq!(board.start_ts, epoch_id as i64 * LEADERBOARD_EPOCH_SECONDS);
+    assert_eq!(board.end_ts, board.start_ts + LEADERBOARD_EPOCH_SECONDS);
+    let ranked: Vec<(Pubkey, i64)> = board.entries.iter().map(|entry| (entry.etf, entry.return_bps)).collect();
+    assert_eq!(ranked, vec![(best.address, 5_000), (good.address, 1_000), (flat.address, 0)]);
+    assert_eq!(board.entries[0].lister, best.lister);
+
+    // Returns come from the snapshots, so they are submitted once
+    assert_error(submit(&mut harness, epoch_id, &best), ErrorCode::EtfAlreadyRanked);
+}
+
+#[test]
+fn test_submit_requires_ended_epoch_and_boundary_snapshots() {
+    let mut harness = setup();
+    let etf = tracked_etf(&mut harness, 2 * LAMPORTS_PER_SOL);
+    let epoch_id = next_epoch(&mut harness);
+    crank_nav_histories(&mut harness, &[etf.address], epoch_id as i64 * LEADERBOARD_EPOCH_SECONDS);
+
+    harness.advance_time(LEADERBOARD_EPOCH_SECONDS - 1);
+    assert_error(submit(&mut harness, epoch_id, &etf), ErrorCode::EpochNotEnded);
+
+    // The last snapshot is too old to stand for the end of the epoch
+    harness.advance_time(1);
+    assert_error(submit(&mut harness, epoch_id, &etf), ErrorCode::MissingEpochSnapshot);
+
+    // Late end snapshots do not count either
+    harness.advance_time(1);
+    snapshot(&mut harness, &etf);
+    assert_error(submit(&mut harness, epoch_id, &etf), ErrorCode::MissingEpochSnapshot);
+}
+
+#[test]
+fn test_boundary_navs_are_time_weighted() {
+    let mut harness = setup();
+    let etf = tracked_etf(&mut harness, 2 * LAMPORTS_PER_SOL);
+    let epoch_id = next_epoch(&mut harness);
+    let end = (epoch_id as i64 + 1) * LEADERBOARD_EPOCH_SECONDS;
+    crank_nav_histories(&mut harness, &[etf.address], epoch_id as i64 * LEADERBOARD_EPOCH_SECONDS);
+
+    // NAV rises 20% halfway through the end window, so it counts for half
+    let supply = etf_state(&harness, &etf.address).total_supply;
+    let mut timestamp = end - LEADERBOARD_TWAP_SECONDS;
+    while timestamp <= end {
+        harness.set_unix_timestamp(timestamp);
+        if timestamp == end - LEADERBOARD_TWAP_SECONDS / 2 {
+            shift_net_assets(&mut harness, &etf.address, (supply / 5) as i64);
+        }
+        snapshot(&mut harness, &etf);
+        timestamp += NAV_SNAPSHOT_INTERVAL_SECONDS;
+    }
+
+    // A jump after the end is not part of the epoch
+    harness.advance_time(NAV_SNAPSHOT_INTERVAL_SECONDS);
+    shift_net_assets(&mut harness, &etf.address, supply as i64);
+    snapshot(&mut harness, &etf);
+
+    submit(&mut harness, epoch_id, &etf).unwrap();
+    let entry = &leaderboard(&harness, epoch_id).entries[0];
+    assert_eq!((entry.start_nav, entry.end_nav), (NAV_SCALE, NAV_SCALE * 11 / 10));
+    assert_eq!(entry.return_bps, 1_000);
+}
+
+#[test]
+fn test_cherry_picked_snapshots_are_not_ranked() {
+    let mut harness = setup();
+    let etf = tracked_etf(&mut harness, 2 * LAMPORTS_PER_SOL);
+    let epoch_id = next_epoch(&mut harness);
+    let end = (epoch_id as i64 + 1) * LEADERBOARD_EPOCH_SECONDS;
+    crank_nav_histories(&mut harness, &[etf.address], epoch_id as i64 * LEADERBOARD_EPOCH_SECONDS);
+
+    // A single snapshot taken at a chosen moment before the end leaves the
+    // rest of the window uncovered
+    harness.set_unix_timestamp(end - NAV_SNAPSHOT_INTERVAL_SECONDS);
+    let supply = etf_state(&harness, &etf.address).total_supply;
+    shift_net_assets(&mut harness, &etf.address, supply as i64);
+    snapshot(&mut harness, &etf);
+    harness.set_unix_timestamp(end);
+    assert_error(submit(&mut harness, epoch_id, &etf), ErrorCode::MissingEpochSnapshot);
+}
+
+#[test]
+fn test_donation_does_not_change_rank() {
+    let mut harness = setup();
+    let earner = tracked_etf(&mut harness, 2 * LAMPORTS_PER_SOL);
+    let donated = tracked_etf(&mut harness, 2 * LAMPORTS_PER_SOL);
+    let epoch_id = next_epoch(&mut harness);
+
+    // Lamports sent straight to an ETF are not booked, so its snapshots and
+    // return stay flat
+    harness.airdrop(&donated.address, 10 * LAMPORTS_PER_SOL);
+    run_epoch(&mut harness, epoch_id, &[(&earner, 500), (&donated, 0)]);
+    submit(&mut harness, epoch_id, &donated).unwrap();
+    submit(&mut harness, epoch_id, &earner).unwrap();
+
+    let board = leaderboard(&harness, epoch_id);
+    let ranked: Vec<(Pubkey, i64)> = board.entries.iter().map(|entry| (entry.etf, entry.return_bps)).collect();
+    assert_eq!(ranked, vec![(earner.address, 500), (donated.address, 0)]);
+}
+
+#[test]
+fn test_small_etfs_are_not_ranked() {
+    let mut harness = setup();
+    let etf = tracked_etf(&mut harness, LAMPORTS_PER_SOL / 2);
+    let epoch_id = next_epoch(&mut harness);
+    run_epoch(&mut harness, epoch_id, &[(&etf, 50_000)]);
+
+    assert_error(submit(&mut harness, epoch_id, &etf), ErrorCode::BelowLeaderboardMinimum);
+}
+
+#[test]
+fn test_full_leaderboard_keeps_top_returns() {
+    let mut harness = setup();
+    let etfs: Vec<TestEtf> = (0..=LEADERBOARD_SIZE)
+        .map(|_| tracked_etf(&mut harness, 2 * LAMPORTS_PER_SOL))
+        .collect();
+    let epoch_id = next_epoch(&mut harness);
+    // Gains of 100, 200, ... bps; the first ETF has the lowest
+    let gains: Vec<(&TestEtf, u64)> = etfs.iter().zip((1..).map(|i| i * 100)).collect();
+    run_epoch(&mut harness, epoch_id, &gains);
+
+    for etf in &etfs[1..] {
+        submit(&mut harness, epoch_id, etf).unwrap();
+    }
+    assert_error(submit(&mut harness, epoch_id, &etfs[0]), ErrorCode::ReturnNotRanked);
+
+    let board = leaderboard(&harness, epoch_id);
+    assert_eq!(board.entries.len(), LEADERBOARD_SIZE);
+    assert_eq!(board.entries[0].etf, etfs[LEADERBOARD_SIZE].address);
+    assert_eq!(board.entries[LEADERBOARD_SIZE - 1].return_bps, 200);
+}
+
+#[test]
+fn test_nav_history_must_belong_to_etf() {
+    let mut harness = setup();
+    let etf = tracked_etf(&mut harness, 2 * LAMPORTS_PER_SOL);
+    let other = tracked_etf(&mut harness, 2 * LAMPORTS_PER_SOL);
+    let epoch_id = next_epoch(&mut harness);
+    run_epoch(&mut harness, epoch_id, &[(&etf, 1_000), (&other, 0)]);
+
+    let mut ix = submit_epoch_return_ix(epoch_id, other.address);
+    ix.accounts[2].pubkey = nav_history_address(&etf.address);
+    assert!(harness.process_instruction(ix, &[]).is_err());
+}
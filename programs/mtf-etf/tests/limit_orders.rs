@@ -0,0 +1,434 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::*;
+use mtf_etf::{
+    calculate_fees, liquidation_payout, shares_for_value, token_value, ETFSoldEvent, ErrorCode, LimitOrder,
+    LimitOrderCancelledEvent, LimitOrderFilledEvent, LimitOrderPlacedEvent, OrderSide, PendingPurchase,
+    TokenRedeemedEvent, MAX_PRICE_AGE_SECONDS, NAV_SCALE, PENDING_PURCHASE_SPACE, PRICE_SCALE, TREASURY,
+};
+use program_harness::Harness;
+
+const ORDER_ID: u64 = 7;
+
+fn place(harness: &mut Harness, etf: &TestEtf, owner: Pubkey, side: OrderSide, amount: u64, target_nav: u64) -> TxResult {
+    let percentages = match side {
+        OrderSide::Buy => even_percentages(etf.mints.len()),
+        OrderSide::Sell => vec![],
+    };
+    harness.process_instruction(
+        place_limit_order_ix(etf.address, owner, ORDER_ID, side, amount, target_nav, percentages, 0),
+        &[owner],
+    )
+}
+
+fn fill(harness: &mut Harness, etf: &TestEtf, owner: Pubkey, filler: Pubkey) -> TxResult {
+    harness.process_instruction(
+        fill_limit_order_ix(etf.address, etf.lister, owner, ORDER_ID, filler, None, None),
+        &[filler],
+    )
+}
+
+fn place_with_deposit(
+    harness: &mut Harness,
+    etf: &TestEtf,
+    owner: Pubkey,
+    side: OrderSide,
+    amount: u64,
+    target_nav: u64,
+    deposit: u64,
+) -> TxResult {
+    let percentages = match side {
+        OrderSide::Buy => even_percentages(etf.mints.len()),
+        OrderSide::Sell => vec![],
+    };
+    harness.process_instruction(
+        place_limit_order_ix(etf.address, owner, ORDER_ID, side, amount, target_nav, percentages, deposit),
+        &[owner],
+    )
+}
+
+// Fill on a keeper ETF, valued by its price feeds; `pairs` pay a sell in kind
+fn fill_keeper(
+    harness: &mut Harness,
+    etf: &TestEtf,
+    owner: Pubkey,
+    filler: Pubkey,
+    purchase_id: Option<u64>,
+    pairs: &[(Pubkey, Pubkey)],
+) -> TxResult {
+    let ix = fill_limit_order_ix(etf.address, etf.lister, owner, ORDER_ID, filler, None, purchase_id);
+    harness.process_instruction(with_vault_pairs(with_price_feeds(ix, &etf.mints), pairs), &[filler])
+}
+
+#[test]
+fn test_place_buy_order_escrows_sol() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 2);
+    let owner = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+
+    let outcome = place(&mut harness, &etf, owner, OrderSide::Buy, LAMPORTS_PER_SOL, NAV_SCALE / 2).unwrap();
+
+    let address = limit_order_address(&etf.address, &owner, ORDER_ID);
+    let order: LimitOrder = harness.anchor_account(&address).unwrap();
+    assert_eq!(order.owner, owner);
+    assert_eq!(order.side, OrderSide::Buy);
+    assert_eq!(order.amount, LAMPORTS_PER_SOL);
+    assert_eq!(order.target_nav, NAV_SCALE / 2);
+    assert_eq!(order.token_percentages, vec![50, 50]);
+    let account = harness.account(&address).unwrap();
+    assert_eq!(
+        account.lamports,
+        harness.rent().minimum_balance(account.data.len()) + LAMPORTS_PER_SOL
+    );
+
+    // Nothing is bought until the order fills
+    assert_eq!(etf_state(&harness, &etf.address).total_supply, 0);
+    let placed = outcome.events::<LimitOrderPlacedEvent>();
+    assert_eq!(placed[0].order_id, ORDER_ID);
+    assert_eq!(placed[0].target_nav, NAV_SCALE / 2);
+}
+
+#[test]
+fn test_buy_order_fills_once_nav_falls_to_target() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 2);
+    let holder = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    buy(&mut harness, &etf, holder, LAMPORTS_PER_SOL).unwrap();
+    let owner = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    let filler = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+
+    let target = NAV_SCALE * 9 / 10;
+    place(&mut harness, &etf, owner, OrderSide::Buy, LAMPORTS_PER_SOL, target).unwrap();
+    assert_error(fill(&mut harness, &etf, owner, filler), ErrorCode::OrderNotTriggered);
+
+    // The basket loses 10%
+    let supply = etf_state(&harness, &etf.address).total_supply;
+    shift_net_assets(&mut harness, &etf.address, -((supply / 10) as i64));
+
+    let address = limit_order_address(&etf.address, &owner, ORDER_ID);
+    let order_lamports = harness.lamports(&address);
+    let owner_before = harness.lamports(&owner);
+    let outcome = fill(&mut harness, &etf, owner, filler).unwrap();
+
+    // Shares are minted at the fallen NAV
+    let (creator_fee, dev_fee) = calculate_fees(LAMPORTS_PER_SOL);
+    assert_eq!(
+        shares(&harness, &etf.address, &owner),
+        (LAMPORTS_PER_SOL - creator_fee - dev_fee) * 10 / 9
+    );
+    // The order is closed and its rent returned to the owner
+    assert!(harness.account(&address).is_none());
+    assert_eq!(harness.lamports(&owner) - owner_before, order_lamports - LAMPORTS_PER_SOL);
+
+    let filled = outcome.events::<LimitOrderFilledEvent>();
+    assert_eq!(filled[0].filler, filler);
+    assert_eq!(filled[0].nav_per_share, target);
+}
+
+#[test]
+fn test_sell_order_fills_once_nav_rises_to_target() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let owner = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    buy(&mut harness, &etf, owner, 2 * LAMPORTS_PER_SOL).unwrap();
+    let filler = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+
+    let held = shares(&harness, &etf.address, &owner);
+    let selling = held / 2;
+    place(&mut harness, &etf, owner, OrderSide::Sell, selling, NAV_SCALE * 11 / 10).unwrap();
+
+    // The shares are escrowed, still part of the supply
+    assert_eq!(shares(&harness, &etf.address, &owner), held - selling);
+    assert_eq!(etf_state(&harness, &etf.address).total_supply, held);
+    assert_error(fill(&mut harness, &etf, owner, filler), ErrorCode::OrderNotTriggered);
+
+    shift_net_assets(&mut harness, &etf.address, (held / 10) as i64);
+    let value = liquidation_payout(selling, held, etf_state(&harness, &etf.address).sol_assets);
+
+    let address = limit_order_address(&etf.address, &owner, ORDER_ID);
+    let order_lamports = harness.lamports(&address);
+    let owner_before = harness.lamports(&owner);
+    let outcome = fill(&mut harness, &etf, owner, filler).unwrap();
+
+    let (creator_fee, dev_fee) = calculate_fees(value);
+    let paid = value - creator_fee - dev_fee;
+    assert_eq!(harness.lamports(&owner) - owner_before, paid + order_lamports);
+    assert_eq!(etf_state(&harness, &etf.address).total_supply, held - selling);
+    assert_eq!(shares(&harness, &etf.address, &owner), held - selling);
+
+    let sold = outcome.events::<ETFSoldEvent>();
+    assert_eq!(sold[0].investor, owner);
+    assert_eq!(sold[0].sol_amount, paid);
+    assert_eq!(outcome.events::<LimitOrderFilledEvent>()[0].side, OrderSide::Sell);
+}
+
+#[test]
+fn test_donation_cannot_trigger_fill() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let owner = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    buy(&mut harness, &etf, owner, 2 * LAMPORTS_PER_SOL).unwrap();
+    let filler = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    let held = shares(&harness, &etf.address, &owner);
+    place(&mut harness, &etf, owner, OrderSide::Sell, held / 2, NAV_SCALE * 11 / 10).unwrap();
+
+    // Lamports sent straight to the ETF are not booked, so NAV stays put
+    harness.airdrop(&etf.address, held);
+    assert_error(fill(&mut harness, &etf, owner, filler), ErrorCode::OrderNotTriggered);
+    assert_eq!(shares(&harness, &etf.address, &owner), held - held / 2);
+    assert!(harness.account(&limit_order_address(&etf.address, &owner, ORDER_ID)).is_some());
+}
+
+#[test]
+fn test_cancel_buy_order_refunds_escrow() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 2);
+    let owner = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    place(&mut harness, &etf, owner, OrderSide::Buy, LAMPORTS_PER_SOL, NAV_SCALE / 2).unwrap();
+
+    let address = limit_order_address(&etf.address, &owner, ORDER_ID);
+    let order_lamports = harness.lamports(&address);
+    let owner_before = harness.lamports(&owner);
+    let outcome = harness
+        .process_instruction(cancel_limit_order_ix(etf.address, owner, ORDER_ID, false), &[owner])
+        .unwrap();
+
+    assert!(harness.account(&address).is_none());
+    assert_eq!(harness.lamports(&owner) - owner_before, order_lamports);
+    assert_eq!(outcome.events::<LimitOrderCancelledEvent>()[0].amount, LAMPORTS_PER_SOL);
+
+    // Only the owner can cancel
+    place(&mut harness, &etf, owner, OrderSide::Buy, LAMPORTS_PER_SOL, NAV_SCALE / 2).unwrap();
+    let stranger = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+    let mut ix = cancel_limit_order_ix(etf.address, stranger, ORDER_ID, false);
+    ix.accounts[1].pubkey = address;
+    assert!(harness.process_instruction(ix, &[stranger]).is_err());
+}
+
+#[test]
+fn test_cancel_sell_order_returns_shares() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let owner = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    buy(&mut harness, &etf, owner, LAMPORTS_PER_SOL).unwrap();
+    let held = shares(&harness, &etf.address, &owner);
+    place(&mut harness, &etf, owner, OrderSide::Sell, held, 2 * NAV_SCALE).unwrap();
+    assert_eq!(shares(&harness, &etf.address, &owner), 0);
+
+    // The shares go back to the position, so it has to be passed
+    assert_error(
+        harness.process_instruction(cancel_limit_order_ix(etf.address, owner, ORDER_ID, false), &[owner]),
+        ErrorCode::OrderPositionRequired,
+    );
+    harness
+        .process_instruction(cancel_limit_order_ix(etf.address, owner, ORDER_ID, true), &[owner])
+        .unwrap();
+
+    assert_eq!(shares(&harness, &etf.address, &owner), held);
+    assert!(harness.account(&limit_order_address(&etf.address, &owner, ORDER_ID)).is_none());
+    sell(&mut harness, &etf, owner, held).unwrap();
+}
+
+#[test]
+fn test_place_limit_order_validation() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 2);
+    let owner = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    buy(&mut harness, &etf, owner, LAMPORTS_PER_SOL).unwrap();
+    let held = shares(&harness, &etf.address, &owner);
+
+    assert_error(place(&mut harness, &etf, owner, OrderSide::Buy, LAMPORTS_PER_SOL, 0), ErrorCode::InvalidAmount);
+    assert_error(place(&mut harness, &etf, owner, OrderSide::Sell, held + 1, NAV_SCALE), ErrorCode::InsufficientShares);
+    assert_error(
+        harness.process_instruction(
+            place_limit_order_ix(etf.address, owner, ORDER_ID, OrderSide::Buy, LAMPORTS_PER_SOL, NAV_SCALE, vec![100], 0),
+            &[owner],
+        ),
+        ErrorCode::InvalidTokenPercentages,
+    );
+    assert_error(
+        harness.process_instruction(
+            place_limit_order_ix(etf.address, owner, ORDER_ID, OrderSide::Sell, held, NAV_SCALE, vec![50, 50], 0),
+            &[owner],
+        ),
+        ErrorCode::InvalidTokenPercentages,
+    );
+
+    // Escrowed shares must be out of their hold period
+    harness
+        .process_instruction(set_hold_period_ix(etf.address, etf.lister, 3_600, 100), &[etf.lister])
+        .unwrap();
+    assert_error(place(&mut harness, &etf, owner, OrderSide::Sell, held, NAV_SCALE), ErrorCode::HoldPeriodActive);
+    harness.advance_time(3_600);
+    place(&mut harness, &etf, owner, OrderSide::Sell, held, NAV_SCALE).unwrap();
+}
+
+#[test]
+fn test_keeper_sell_order_fills_in_kind_once_price_rises() {
+    let mut harness = setup();
+    let (setup, owner, pairs) = keeper_holders(&mut harness);
+    let etf = &setup.etf;
+    let filler = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+    let held = shares(&harness, &etf.address, &owner);
+    let selling = held / 2;
+    let deposit = LAMPORTS_PER_SOL / 100;
+    place_with_deposit(&mut harness, etf, owner, OrderSide::Sell, selling, NAV_SCALE * 6 / 5, deposit).unwrap();
+    assert_error(
+        fill_keeper(&mut harness, etf, owner, filler, None, &pairs),
+        ErrorCode::OrderNotTriggered,
+    );
+
+    // The first token doubles at its feed: NAV is 1.5, past the target
+    set_price(&mut harness, &etf.mints[0], 2 * TOKEN_PRICE);
+    let state = etf_state(&harness, &etf.address);
+    let address = limit_order_address(&etf.address, &owner, ORDER_ID);
+    let order_lamports = harness.lamports(&address);
+    let owner_before = harness.lamports(&owner);
+    let lister_before = harness.lamports(&etf.lister);
+    let dev_before = harness.lamports(&TREASURY);
+    let outcome = fill_keeper(&mut harness, etf, owner, filler, None, &pairs).unwrap();
+
+    // The owner takes their slice of each holding; fees on its value at NAV
+    // come out of the deposit, the rest of which returns with the order
+    for (i, (_, account)) in pairs.iter().enumerate() {
+        assert_eq!(
+            harness.token_balance(account),
+            Some(liquidation_payout(selling, state.total_supply, state.holdings[i]))
+        );
+    }
+    let (creator_fee, dev_fee) = calculate_fees(selling + selling / 2);
+    assert_eq!(harness.lamports(&etf.lister) - lister_before, creator_fee);
+    assert_eq!(harness.lamports(&TREASURY) - dev_before, dev_fee);
+    assert_eq!(
+        harness.lamports(&owner) - owner_before,
+        order_lamports - creator_fee - dev_fee
+    );
+    assert!(harness.account(&address).is_none());
+    assert_eq!(etf_state(&harness, &etf.address).total_supply, state.total_supply - selling);
+    assert_eq!(outcome.events::<TokenRedeemedEvent>().len(), 2);
+    assert_eq!(outcome.events::<ETFSoldEvent>()[0].creator_fee, creator_fee);
+    assert_eq!(outcome.events::<LimitOrderFilledEvent>()[0].nav_per_share, NAV_SCALE * 3 / 2);
+}
+
+#[test]
+fn test_keeper_buy_order_opens_purchase_once_price_falls() {
+    let mut harness = setup();
+    let (setup, _, _) = keeper_holders(&mut harness);
+    let etf = &setup.etf;
+    let owner = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    let filler = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+    let rent = harness.rent().minimum_balance(PENDING_PURCHASE_SPACE);
+    place_with_deposit(&mut harness, etf, owner, OrderSide::Buy, LAMPORTS_PER_SOL, NAV_SCALE * 4 / 5, rent).unwrap();
+    let purchase_id = etf_state(&harness, &etf.address).purchase_count;
+    assert_error(
+        fill_keeper(&mut harness, etf, owner, filler, Some(purchase_id), &[]),
+        ErrorCode::OrderNotTriggered,
+    );
+
+    // Both tokens halve at their feeds
+    for mint in &etf.mints {
+        set_price(&mut harness, mint, TOKEN_PRICE / 2);
+    }
+    let state = etf_state(&harness, &etf.address);
+    let filler_before = harness.lamports(&filler);
+    let outcome = fill_keeper(&mut harness, etf, owner, filler, Some(purchase_id), &[]).unwrap();
+
+    // The fill hands the buy to the keeper; the deposit repays the filler
+    // for the pending purchase's rent
+    let pending: PendingPurchase = harness
+        .anchor_account(&pending_purchase_address(&etf.address, purchase_id))
+        .unwrap();
+    let (creator_fee, dev_fee) = calculate_fees(LAMPORTS_PER_SOL);
+    assert_eq!(pending.investor, owner);
+    assert_eq!(pending.sol_to_swap, LAMPORTS_PER_SOL - creator_fee - dev_fee);
+    assert_eq!(harness.lamports(&filler), filler_before);
+    assert_eq!(shares(&harness, &etf.address, &owner), 0);
+    assert_eq!(outcome.events::<LimitOrderFilledEvent>()[0].nav_per_share, NAV_SCALE / 2);
+
+    // Settled at the fallen price, the buy mints at the fallen NAV
+    let amounts: Vec<u64> = pending
+        .allocations
+        .iter()
+        .map(|lamports| (*lamports as u128 * PRICE_SCALE as u128 / (TOKEN_PRICE / 2) as u128) as u64)
+        .collect();
+    let delivered: u64 = amounts.iter().map(|amount| token_value(*amount, TOKEN_PRICE / 2)).sum();
+    settle(&mut harness, &setup, owner, purchase_id, amounts).unwrap();
+    assert_eq!(
+        shares(&harness, &etf.address, &owner),
+        shares_for_value(delivered, state.total_supply, state.total_supply / 2)
+    );
+}
+
+#[test]
+fn test_keeper_fill_validation() {
+    let mut harness = setup();
+    let (setup, owner, pairs) = keeper_holders(&mut harness);
+    let etf = &setup.etf;
+    let filler = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+    let held = shares(&harness, &etf.address, &owner);
+    place_with_deposit(&mut harness, etf, owner, OrderSide::Sell, held, NAV_SCALE, 1).unwrap();
+
+    // NAV needs every feed of a held token, and fresh ones
+    assert_error(
+        harness.process_instruction(
+            fill_limit_order_ix(etf.address, etf.lister, owner, ORDER_ID, filler, None, None),
+            &[filler],
+        ),
+        ErrorCode::MissingPriceFeed,
+    );
+    harness.advance_time(MAX_PRICE_AGE_SECONDS + 1);
+    assert_error(fill_keeper(&mut harness, etf, owner, filler, None, &pairs), ErrorCode::StalePrice);
+    for mint in &etf.mints {
+        set_price(&mut harness, mint, TOKEN_PRICE);
+    }
+
+    // A sell in kind needs every vault pair and a deposit covering its fees
+    assert_error(
+        fill_keeper(&mut harness, etf, owner, filler, None, &pairs[..1]),
+        ErrorCode::InvalidVaultAccount,
+    );
+    assert_error(
+        fill_keeper(&mut harness, etf, owner, filler, None, &pairs),
+        ErrorCode::InsufficientDeposit,
+    );
+
+    // A buy on a keeper ETF must open a pending purchase
+    let buyer = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    place_with_deposit(&mut harness, etf, buyer, OrderSide::Buy, LAMPORTS_PER_SOL, NAV_SCALE, 0).unwrap();
+    assert_error(
+        fill_keeper(&mut harness, etf, buyer, filler, None, &[]),
+        ErrorCode::InvalidPendingPurchase,
+    );
+    let purchase_id = etf_state(&harness, &etf.address).purchase_count;
+    assert_error(
+        fill_keeper(&mut harness, etf, buyer, filler, Some(purchase_id), &[]),
+        ErrorCode::InsufficientDeposit,
+    );
+}
+
+#[test]
+fn test_fill_limit_order_validates_accounts() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 2);
+    let owner = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    let filler = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+    place(&mut harness, &etf, owner, OrderSide::Buy, LAMPORTS_PER_SOL, NAV_SCALE).unwrap();
+
+    let wrong_lister = Pubkey::new_unique();
+    assert_error(
+        harness.process_instruction(
+            fill_limit_order_ix(etf.address, wrong_lister, owner, ORDER_ID, filler, None, None),
+            &[filler],
+        ),
+        ErrorCode::InvalidListerAccount,
+    );
+
+    // Fills pay the owner of the order, never the filler
+    let mut ix = fill_limit_order_ix(etf.address, etf.lister, owner, ORDER_ID, filler, None, None);
+    ix.accounts[3].pubkey = filler;
+    assert!(harness.process_instruction(ix, &[filler]).is_err());
+
+    fill(&mut harness, &etf, owner, filler).unwrap();
+    assert!(fill(&mut harness, &etf, owner, filler).is_err());
+}
@@ -0,0 +1,166 @@
+mod common;
+
+use common::*;
+use mtf_etf::{
+    BuyLimits, BuyLimitsUpdatedEvent, ErrorCode, DEFAULT_REFUND_TIMEOUT_SECONDS, TREASURY, PROTOCOL_MAX_BUY_LAMPORTS,
+    PROTOCOL_MIN_BUY_LAMPORTS,
+};
+use program_harness::Harness;
+
+fn limit(harness: &mut Harness, etf: &TestEtf, limits: BuyLimits) {
+    harness
+        .process_instruction(set_buy_limits_ix(etf.address, etf.lister, limits), &[etf.lister])
+        .unwrap();
+}
+
+#[test]
+fn test_set_buy_limits() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    assert_eq!(etf_state(&harness, &etf.address).limits, BuyLimits::default());
+
+    let limits = BuyLimits {
+        min_buy_lamports: LAMPORTS_PER_SOL / 10,
+        max_buy_lamports: 5 * LAMPORTS_PER_SOL,
+        max_total_supply: 100 * LAMPORTS_PER_SOL,
+    };
+    let outcome = harness
+        .process_instruction(set_buy_limits_ix(etf.address, etf.lister, limits), &[etf.lister])
+        .unwrap();
+    assert_eq!(etf_state(&harness, &etf.address).limits, limits);
+    let events = outcome.events::<BuyLimitsUpdatedEvent>();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].limits, limits);
+}
+
+#[test]
+fn test_set_buy_limits_validation() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let stranger = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+
+    assert!(harness
+        .process_instruction(set_buy_limits_ix(etf.address, stranger, BuyLimits::default()), &[stranger])
+        .is_err());
+    for limits in [
+        BuyLimits { min_buy_lamports: 2, max_buy_lamports: 1, max_total_supply: 0 },
+        BuyLimits { max_buy_lamports: PROTOCOL_MAX_BUY_LAMPORTS + 1, ..Default::default() },
+    ] {
+        assert_error(
+            harness.process_instruction(set_buy_limits_ix(etf.address, etf.lister, limits), &[etf.lister]),
+            ErrorCode::InvalidBuyLimits,
+        );
+    }
+}
+
+#[test]
+fn test_dust_buys_rejected() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let investor = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+
+    // Below the protocol minimum the fees would round to zero
+    assert_error(buy(&mut harness, &etf, investor, PROTOCOL_MIN_BUY_LAMPORTS - 1), ErrorCode::BuyBelowMinimum);
+    let lister_before = harness.lamports(&etf.lister);
+    buy(&mut harness, &etf, investor, PROTOCOL_MIN_BUY_LAMPORTS).unwrap();
+    assert_eq!(harness.lamports(&etf.lister) - lister_before, 1);
+}
+
+#[test]
+fn test_etf_buy_size_limits() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    limit(
+        &mut harness,
+        &etf,
+        BuyLimits {
+            min_buy_lamports: LAMPORTS_PER_SOL / 10,
+            max_buy_lamports: LAMPORTS_PER_SOL,
+            max_total_supply: 0,
+        },
+    );
+
+    assert_error(buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL / 10 - 1), ErrorCode::BuyBelowMinimum);
+    assert_error(buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL + 1), ErrorCode::BuyAboveMaximum);
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL / 10).unwrap();
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+
+    // Quotes apply the same limits
+    assert!(harness
+        .process_instruction(quote_buy_ix(etf.address, LAMPORTS_PER_SOL + 1, vec![100]), &[])
+        .is_err());
+}
+
+#[test]
+fn test_max_total_supply() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    limit(
+        &mut harness,
+        &etf,
+        BuyLimits { max_total_supply: 2 * 990_000_000, ..Default::default() },
+    );
+
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+    assert_error(buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL + 1_000), ErrorCode::MaxSupplyExceeded);
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+    assert_error(buy(&mut harness, &etf, investor, PROTOCOL_MIN_BUY_LAMPORTS), ErrorCode::MaxSupplyExceeded);
+
+    // Selling frees capacity; lowering the cap below supply only blocks buys
+    sell(&mut harness, &etf, investor, 990_000_000).unwrap();
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL / 2).unwrap();
+    limit(&mut harness, &etf, BuyLimits { max_total_supply: 1, ..Default::default() });
+    assert_error(buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL), ErrorCode::MaxSupplyExceeded);
+    sell(&mut harness, &etf, investor, 990_000_000).unwrap();
+}
+
+#[test]
+fn test_max_total_supply_counts_pending_purchases() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let keeper = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+    harness
+        .process_instruction(
+            set_keeper_ix(etf.address, etf.lister, Some(keeper), DEFAULT_REFUND_TIMEOUT_SECONDS),
+            &[etf.lister],
+        )
+        .unwrap();
+    limit(&mut harness, &etf, BuyLimits { max_total_supply: 990_000_000, ..Default::default() });
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+
+    let pending_buy = |harness: &mut Harness, purchase_id: u64| {
+        harness.process_instruction(
+            buy_etf_pending_ix(
+                etf.address,
+                etf.lister,
+                investor,
+                TREASURY,
+                Some(pending_purchase_address(&etf.address, purchase_id)),
+                LAMPORTS_PER_SOL,
+                vec![100],
+            ),
+            &[investor],
+        )
+    };
+    pending_buy(&mut harness, 0).unwrap();
+    assert_eq!(etf_state(&harness, &etf.address).total_supply, 0);
+    assert_error(pending_buy(&mut harness, 1), ErrorCode::MaxSupplyExceeded);
+}
+
+#[test]
+fn test_limits_do_not_block_sells() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+
+    limit(
+        &mut harness,
+        &etf,
+        BuyLimits { min_buy_lamports: 5 * LAMPORTS_PER_SOL, ..Default::default() },
+    );
+    sell(&mut harness, &etf, investor, 1_000).unwrap();
+    assert_eq!(shares(&harness, &etf.address, &investor), 990_000_000 - 1_000);
+}
@@ -0,0 +1,456 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::get_associated_token_address;
+use mtf_etf::{
+    liquidation_min_out, liquidation_payout, ETFClosedEvent, EtfStatus, ErrorCode, LiquidationClaimEvent,
+    LiquidationStartedEvent, TokenRedeemedEvent, VaultLiquidatedEvent, LISTER_INACTIVITY_SECONDS,
+    LIQUIDATION_NOTICE_SECONDS,
+};
+use program_harness::Harness;
+
+fn begin(harness: &mut Harness, etf: &TestEtf, authority: Pubkey) -> TxResult {
+    harness.process_instruction(begin_liquidation_ix(etf.address, authority), &[authority])
+}
+
+fn claim(harness: &mut Harness, etf: &TestEtf, investor: Pubkey) -> TxResult {
+    harness.process_instruction(
+        claim_liquidation_ix(etf.address, investor, &holder_vaults(etf, &investor)),
+        &[investor],
+    )
+}
+
+// Empty associated token accounts of `owner` for every basket mint
+fn token_accounts(harness: &mut Harness, etf: &TestEtf, owner: &Pubkey) -> Vec<Pubkey> {
+    etf.mints
+        .iter()
+        .map(|mint| {
+            let account = get_associated_token_address(owner, mint);
+            harness.create_token_account(account, mint, owner, 0);
+            account
+        })
+        .collect()
+}
+
+// Keeper ETF with `holders` each settled for 1 SOL at TOKEN_PRICE, in
+// liquidation since now
+fn liquidating_etf(harness: &mut Harness, token_count: usize, holders: usize) -> (KeeperEtf, Vec<Pubkey>) {
+    let setup = keeper_etf(harness, token_count);
+    let mut wallets = Vec::new();
+    for purchase_id in 0..holders {
+        let wallet = funded_wallet(harness, 10 * LAMPORTS_PER_SOL);
+        pending_buy(harness, &setup.etf, wallet, LAMPORTS_PER_SOL).unwrap();
+        settle_at_price(harness, &setup, wallet, purchase_id as u64).unwrap();
+        token_accounts(harness, &setup.etf, &wallet);
+        wallets.push(wallet);
+    }
+    begin(harness, &setup.etf, setup.etf.lister).unwrap();
+    (setup, wallets)
+}
+
+// Past the notice, with fresh feeds
+fn end_notice(harness: &mut Harness, etf: &TestEtf) {
+    harness.advance_time(LIQUIDATION_NOTICE_SECONDS);
+    for mint in &etf.mints {
+        set_price(harness, mint, TOKEN_PRICE);
+    }
+}
+
+fn liquidate(harness: &mut Harness, etf: &TestEtf, authority: Pubkey, token: usize, amount: u64, sol: u64) -> TxResult {
+    let destination = get_associated_token_address(&authority, &etf.mints[token]);
+    harness.process_instruction(
+        liquidate_vault_ix(etf.address, authority, etf.mints[token], destination, amount, sol),
+        &[authority],
+    )
+}
+
+#[test]
+fn test_lister_begins_liquidation() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+
+    let outcome = begin(&mut harness, &etf, etf.lister).unwrap();
+
+    let state = etf_state(&harness, &etf.address);
+    assert_eq!(state.status, EtfStatus::Liquidating);
+    assert_eq!(state.liquidation_started_ts, harness.clock().unix_timestamp);
+
+    let events = outcome.events::<LiquidationStartedEvent>();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].initiator, etf.lister);
+    assert_eq!(events[0].total_supply, state.total_supply);
+    assert_eq!(events[0].notice_ends_ts, state.liquidation_started_ts + LIQUIDATION_NOTICE_SECONDS);
+}
+
+#[test]
+fn test_begin_liquidation_twice() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    begin(&mut harness, &etf, etf.lister).unwrap();
+
+    assert_error(begin(&mut harness, &etf, etf.lister), ErrorCode::EtfLiquidating);
+}
+
+#[test]
+fn test_governance_liquidation_requires_inactive_lister() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let governance = governance(&mut harness);
+
+    harness.advance_time(LISTER_INACTIVITY_SECONDS - 1);
+    assert_error(begin(&mut harness, &etf, governance), ErrorCode::ListerStillActive);
+
+    harness.advance_time(1);
+    begin(&mut harness, &etf, governance).unwrap();
+    assert_eq!(etf_state(&harness, &etf.address).status, EtfStatus::Liquidating);
+}
+
+#[test]
+fn test_metadata_update_resets_inactivity() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let governance = governance(&mut harness);
+
+    harness.advance_time(LISTER_INACTIVITY_SECONDS - 10);
+    harness
+        .process_instruction(update_metadata_ix(etf.address, etf.lister, Metadata::default()), &[etf.lister])
+        .unwrap();
+    harness.advance_time(10);
+
+    assert_error(begin(&mut harness, &etf, governance), ErrorCode::ListerStillActive);
+}
+
+#[test]
+fn test_stranger_cannot_begin_liquidation() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let stranger = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+    harness.advance_time(LISTER_INACTIVITY_SECONDS);
+
+    assert_error(begin(&mut harness, &etf, stranger), ErrorCode::Unauthorized);
+}
+
+#[test]
+fn test_liquidate_vault_swaps_tokens_for_sol() {
+    let mut harness = setup();
+    let (setup, _) = liquidating_etf(&mut harness, 1, 1);
+    let etf = &setup.etf;
+    let destination = token_accounts(&mut harness, etf, &etf.lister)[0];
+    let vault = vault_address(&etf.address, &etf.mints[0]);
+    end_notice(&mut harness, etf);
+    let etf_before = harness.lamports(&etf.address);
+    let min_out = liquidation_min_out(4_000, TOKEN_PRICE);
+
+    let outcome = liquidate(&mut harness, etf, etf.lister, 0, 4_000, min_out).unwrap();
+
+    assert_eq!(harness.token_balance(&vault), Some(5_900));
+    assert_eq!(harness.token_balance(&destination), Some(4_000));
+    assert_eq!(harness.lamports(&etf.address), etf_before + min_out);
+    let state = etf_state(&harness, &etf.address);
+    assert_eq!(state.holdings, vec![5_900]);
+    assert_eq!(state.sol_assets, min_out);
+
+    let events = outcome.events::<VaultLiquidatedEvent>();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].token_address, etf.mints[0]);
+    assert_eq!(events[0].token_amount, 4_000);
+    assert_eq!(events[0].sol_amount, min_out);
+}
+
+#[test]
+fn test_liquidate_vault_waits_for_notice() {
+    let mut harness = setup();
+    let (setup, _) = liquidating_etf(&mut harness, 1, 1);
+    let etf = &setup.etf;
+    token_accounts(&mut harness, etf, &etf.lister);
+    let min_out = liquidation_min_out(1_000, TOKEN_PRICE);
+
+    harness.advance_time(LIQUIDATION_NOTICE_SECONDS - 1);
+    set_price(&mut harness, &etf.mints[0], TOKEN_PRICE);
+    assert_error(
+        liquidate(&mut harness, etf, etf.lister, 0, 1_000, min_out),
+        ErrorCode::LiquidationNoticePending,
+    );
+
+    harness.advance_time(1);
+    liquidate(&mut harness, etf, etf.lister, 0, 1_000, min_out).unwrap();
+}
+
+#[test]
+fn test_liquidate_vault_is_bounded_by_feed_price() {
+    let mut harness = setup();
+    let (setup, _) = liquidating_etf(&mut harness, 1, 1);
+    let etf = &setup.etf;
+    token_accounts(&mut harness, etf, &etf.lister);
+    let min_out = liquidation_min_out(1_000, TOKEN_PRICE);
+
+    // The feed set at listing is stale by the end of the notice
+    harness.advance_time(LIQUIDATION_NOTICE_SECONDS);
+    assert_error(liquidate(&mut harness, etf, etf.lister, 0, 1_000, min_out), ErrorCode::StalePrice);
+
+    set_price(&mut harness, &etf.mints[0], TOKEN_PRICE);
+    assert_error(
+        liquidate(&mut harness, etf, etf.lister, 0, 1_000, min_out - 1),
+        ErrorCode::LiquidationBelowPrice,
+    );
+    liquidate(&mut harness, etf, etf.lister, 0, 1_000, min_out).unwrap();
+}
+
+#[test]
+fn test_liquidate_vault_requires_liquidation() {
+    let mut harness = setup();
+    let setup = keeper_etf(&mut harness, 1);
+    let etf = &setup.etf;
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    pending_buy(&mut harness, etf, investor, LAMPORTS_PER_SOL).unwrap();
+    settle_at_price(&mut harness, &setup, investor, 0).unwrap();
+    token_accounts(&mut harness, etf, &etf.lister);
+
+    assert_error(liquidate(&mut harness, etf, etf.lister, 0, 1, 1), ErrorCode::EtfNotLiquidating);
+
+    begin(&mut harness, etf, etf.lister).unwrap();
+    end_notice(&mut harness, etf);
+    assert_error(liquidate(&mut harness, etf, etf.lister, 0, 0, 1), ErrorCode::InvalidAmount);
+
+    let stranger = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+    token_accounts(&mut harness, etf, &stranger);
+    assert_error(liquidate(&mut harness, etf, stranger, 0, 1, 1), ErrorCode::Unauthorized);
+}
+
+#[test]
+fn test_liquidate_vault_rejects_foreign_mint() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let mint = Pubkey::new_unique();
+    harness.create_mint(mint, &etf.lister, 6);
+    let destination = get_associated_token_address(&etf.lister, &mint);
+    harness.create_token_account(vault_address(&etf.address, &mint), &mint, &etf.address, 1_000);
+    harness.create_token_account(destination, &mint, &etf.lister, 0);
+    set_price(&mut harness, &mint, TOKEN_PRICE);
+    begin(&mut harness, &etf, etf.lister).unwrap();
+    harness.advance_time(LIQUIDATION_NOTICE_SECONDS);
+
+    let result = harness.process_instruction(
+        liquidate_vault_ix(etf.address, etf.lister, mint, destination, 1, 1),
+        &[etf.lister],
+    );
+    assert_error(result, ErrorCode::InvalidVaultAccount);
+}
+
+#[test]
+fn test_claims_are_pro_rata_and_close_positions() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let alice = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    let bob = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    buy(&mut harness, &etf, alice, LAMPORTS_PER_SOL).unwrap();
+    buy(&mut harness, &etf, bob, 3 * LAMPORTS_PER_SOL).unwrap();
+    begin(&mut harness, &etf, etf.lister).unwrap();
+
+    let state = etf_state(&harness, &etf.address);
+    let rent = harness.lamports(&etf.address) - state.total_supply;
+    let alice_shares = shares(&harness, &etf.address, &alice);
+    let alice_position = position_address(&etf.address, &alice);
+    let position_rent = harness.lamports(&alice_position);
+    let alice_before = harness.lamports(&alice);
+
+    let outcome = claim(&mut harness, &etf, alice).unwrap();
+
+    let payout = liquidation_payout(alice_shares, state.total_supply, state.total_supply);
+    assert_eq!(payout, alice_shares);
+    assert_eq!(harness.lamports(&alice), alice_before + payout + position_rent);
+    assert!(harness.account(&alice_position).is_none());
+    let event = &outcome.events::<LiquidationClaimEvent>()[0];
+    assert_eq!(event.shares_burned, alice_shares);
+    assert_eq!(event.sol_amount, payout);
+
+    claim(&mut harness, &etf, bob).unwrap();
+    assert_eq!(etf_state(&harness, &etf.address).total_supply, 0);
+    assert_eq!(harness.lamports(&etf.address), rent);
+}
+
+#[test]
+fn test_claim_requires_liquidation_and_position() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+
+    assert_error(claim(&mut harness, &etf, investor), ErrorCode::EtfNotLiquidating);
+
+    // A sold-out position still exists with zero shares
+    let held = shares(&harness, &etf.address, &investor);
+    sell(&mut harness, &etf, investor, held).unwrap();
+    begin(&mut harness, &etf, etf.lister).unwrap();
+    assert_error(claim(&mut harness, &etf, investor), ErrorCode::InsufficientShares);
+}
+
+#[test]
+fn test_close_liquidated_etf_is_permissionless() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    let keeper = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+    begin(&mut harness, &etf, etf.lister).unwrap();
+
+    let ix = close_liquidated_etf_ix(etf.address, etf.lister, keeper, etf.registry_index, &lister_vaults(&etf));
+    assert_error(harness.process_instruction(ix.clone(), &[keeper]), ErrorCode::CannotCloseWithSupply);
+
+    claim(&mut harness, &etf, investor).unwrap();
+    let etf_lamports = harness.lamports(&etf.address);
+    let lister_before = harness.lamports(&etf.lister);
+    let outcome = harness.process_instruction(ix, &[keeper]).unwrap();
+
+    assert!(harness.account(&etf.address).is_none());
+    assert_eq!(harness.lamports(&etf.lister), lister_before + etf_lamports);
+    assert_eq!(outcome.events::<ETFClosedEvent>().len(), 1);
+}
+
+#[test]
+fn test_close_liquidated_etf_requires_liquidation() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let keeper = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+
+    let ix = close_liquidated_etf_ix(etf.address, etf.lister, keeper, etf.registry_index, &lister_vaults(&etf));
+    assert_error(harness.process_instruction(ix, &[keeper]), ErrorCode::EtfNotLiquidating);
+}
+
+#[test]
+fn test_claim_during_notice_pays_tokens_in_kind() {
+    let mut harness = setup();
+    let (setup, holders) = liquidating_etf(&mut harness, 2, 2);
+    let etf = &setup.etf;
+    let alice = holders[0];
+    let accounts = holder_vaults(etf, &alice);
+
+    let outcome = claim(&mut harness, etf, alice).unwrap();
+
+    // Half the supply takes half of each vault
+    for (vault, account) in &accounts {
+        assert_eq!(harness.token_balance(account), Some(4_950));
+        assert_eq!(harness.token_balance(vault), Some(4_950));
+    }
+    let state = etf_state(&harness, &etf.address);
+    assert_eq!(state.holdings, vec![4_950, 4_950]);
+    assert_eq!(state.total_supply, shares(&harness, &etf.address, &holders[1]));
+
+    let redeemed = outcome.events::<TokenRedeemedEvent>();
+    assert_eq!(redeemed.len(), 2);
+    assert_eq!(redeemed[0].investor, alice);
+    assert_eq!(redeemed[0].amount, 4_950);
+    assert_eq!(outcome.events::<LiquidationClaimEvent>()[0].sol_amount, 0);
+}
+
+#[test]
+fn test_claim_rejects_foreign_token_account() {
+    let mut harness = setup();
+    let (setup, holders) = liquidating_etf(&mut harness, 1, 1);
+    let etf = &setup.etf;
+    let stranger = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+    let foreign = token_accounts(&mut harness, etf, &stranger)[0];
+
+    let vault = vault_address(&etf.address, &etf.mints[0]);
+    let result = harness.process_instruction(
+        claim_liquidation_ix(etf.address, holders[0], &[(vault, foreign)]),
+        &[holders[0]],
+    );
+    assert_error(result, ErrorCode::InvalidVaultAccount);
+
+    let result = harness.process_instruction(claim_liquidation_ix(etf.address, holders[0], &[]), &[holders[0]]);
+    assert_error(result, ErrorCode::InvalidVaultAccount);
+}
+
+// begin -> in-kind claim during the notice -> conversion -> last claim -> close
+#[test]
+fn test_liquidation_full_flow() {
+    let mut harness = setup();
+    let (setup, holders) = liquidating_etf(&mut harness, 2, 2);
+    let etf = &setup.etf;
+    let (alice, bob) = (holders[0], holders[1]);
+    let vaults = setup.vaults();
+    let lister_accounts = token_accounts(&mut harness, etf, &etf.lister);
+
+    claim(&mut harness, etf, alice).unwrap();
+
+    // Tokens sent to a vault during liquidation go to the remaining holders
+    harness.create_token_account(vaults[1], &etf.mints[1], &etf.address, 4_950 + 1_000);
+
+    end_notice(&mut harness, etf);
+    let proceeds = liquidation_min_out(4_950, TOKEN_PRICE);
+    liquidate(&mut harness, etf, etf.lister, 0, 4_950, proceeds).unwrap();
+    assert_eq!(harness.token_balance(&lister_accounts[0]), Some(4_950));
+    assert_eq!(etf_state(&harness, &etf.address).sol_assets, proceeds);
+
+    let bob_accounts = holder_vaults(etf, &bob);
+    let position_rent = harness.lamports(&position_address(&etf.address, &bob));
+    let bob_before = harness.lamports(&bob);
+    let outcome = claim(&mut harness, etf, bob).unwrap();
+
+    assert_eq!(harness.lamports(&bob), bob_before + proceeds + position_rent);
+    assert_eq!(harness.token_balance(&bob_accounts[0].1), Some(0));
+    assert_eq!(harness.token_balance(&bob_accounts[1].1), Some(5_950));
+    assert_eq!(outcome.events::<LiquidationClaimEvent>()[0].sol_amount, proceeds);
+    for vault in &vaults {
+        assert_eq!(harness.token_balance(vault), Some(0));
+    }
+    let state = etf_state(&harness, &etf.address);
+    assert_eq!(state.total_supply, 0);
+    assert_eq!(state.sol_assets, 0);
+
+    // Nothing is left for the lister but rent
+    let keeper = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+    let rent = harness.lamports(&etf.address);
+    let vault_rent: u64 = vaults.iter().map(|vault| harness.lamports(vault)).sum();
+    assert_eq!(rent, harness.rent().minimum_balance(mtf_etf::ETF_SPACE));
+    let lister_before = harness.lamports(&etf.lister);
+    let ix = close_liquidated_etf_ix(etf.address, etf.lister, keeper, etf.registry_index, &lister_vaults(etf));
+    let outcome = harness.process_instruction(ix, &[keeper]).unwrap();
+
+    assert!(harness.account(&etf.address).is_none());
+    assert_eq!(harness.lamports(&etf.lister), lister_before + rent + vault_rent);
+    assert_eq!(harness.token_balance(&lister_accounts[1]), Some(0));
+    let closed = &outcome.events::<ETFClosedEvent>()[0];
+    assert_eq!(closed.dust_recovered, 0);
+    assert!(closed.tokens_recovered.iter().all(|token| token.amount == 0));
+}
+
+// A claim with no token program and no vaults, as for an instant ETF
+fn claim_sol_only(harness: &mut Harness, etf: &Pubkey, investor: Pubkey) -> TxResult {
+    let ix = Instruction {
+        program_id: mtf_etf::ID,
+        accounts: mtf_etf::accounts::ClaimLiquidation {
+            etf: *etf,
+            position: position_address(etf, &investor),
+            investor,
+            token_program: None,
+        }
+        .to_account_metas(None),
+        data: mtf_etf::instruction::ClaimLiquidation {}.data(),
+    };
+    harness.process_instruction(ix, &[investor])
+}
+
+#[test]
+fn test_claim_without_vaults_needs_no_booked_holdings() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+    begin(&mut harness, &etf, etf.lister).unwrap();
+
+    claim_sol_only(&mut harness, &etf.address, investor).unwrap();
+    assert_eq!(etf_state(&harness, &etf.address).total_supply, 0);
+
+    let (setup, holders) = liquidating_etf(&mut harness, 1, 1);
+    assert_error(
+        claim_sol_only(&mut harness, &setup.etf.address, holders[0]),
+        ErrorCode::InvalidVaultAccount,
+    );
+}
@@ -0,0 +1,228 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AnchorSerialize, Discriminator};
+use common::*;
+use mtf_etf::{
+    liquidation_payout, ETFMigratedEvent, EtfCategory, EtfStatus, ErrorCode, LegacyPositionRestoredEvent,
+    RegistryPage, ETF, ETF_SPACE, LEGACY_ETF_SPACE, ID,
+};
+use program_harness::{AccountState, Harness};
+
+// Booked by a legacy ETF for 1 SOL bought at 1:1 after fees
+const LEGACY_SUPPLY: u64 = 990_000_000;
+
+// Write an ETF account in the layout listed before the registry existed,
+// holding LEGACY_SUPPLY lamports above rent
+fn legacy_etf(harness: &mut Harness, mints: &[Pubkey]) -> TestEtf {
+    let lister = funded_wallet(harness, 10 * LAMPORTS_PER_SOL);
+    let address = etf_address(&lister, 0);
+    let bump = Pubkey::find_program_address(&[b"etf", lister.as_ref(), &[0]], &ID).1;
+
+    let mut data = ETF::DISCRIMINATOR.to_vec();
+    lister.serialize(&mut data).unwrap();
+    0u8.serialize(&mut data).unwrap();
+    mints.to_vec().serialize(&mut data).unwrap();
+    LEGACY_SUPPLY.serialize(&mut data).unwrap();
+    0u64.serialize(&mut data).unwrap();
+    bump.serialize(&mut data).unwrap();
+    data.resize(LEGACY_ETF_SPACE, 0);
+
+    let lamports = harness.rent().minimum_balance(LEGACY_ETF_SPACE) + LEGACY_SUPPLY;
+    harness.set_account(address, AccountState { lamports, data, owner: ID, executable: false });
+    TestEtf {
+        address,
+        lister,
+        registry_index: next_registry_index(harness),
+        mints: mints.to_vec(),
+    }
+}
+
+fn migrate(harness: &mut Harness, etf: &TestEtf, payer: Pubkey) -> TxResult {
+    harness.process_instruction(migrate_etf_ix(etf.address, payer, etf.registry_index), &[payer])
+}
+
+fn restore(harness: &mut Harness, etf: &TestEtf, investor: Pubkey, authority: Pubkey, shares: u64) -> TxResult {
+    harness.process_instruction(
+        restore_legacy_position_ix(etf.address, investor, authority, shares),
+        &[authority],
+    )
+}
+
+// A migrated legacy ETF over one mint
+fn migrated_etf(harness: &mut Harness) -> TestEtf {
+    let etf = legacy_etf(harness, &[Pubkey::new_unique()]);
+    let payer = funded_wallet(harness, LAMPORTS_PER_SOL);
+    migrate(harness, &etf, payer).unwrap();
+    etf
+}
+
+#[test]
+fn test_migrate_etf_backfills_state_and_registers() {
+    let mut harness = setup();
+    let listed = create_etf(&mut harness, 1);
+    let mints = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+    let etf = legacy_etf(&mut harness, &mints);
+    let payer = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+
+    let outcome = migrate(&mut harness, &etf, payer).unwrap();
+
+    assert_eq!(harness.account(&etf.address).unwrap().data.len(), ETF_SPACE);
+    let state = etf_state(&harness, &etf.address);
+    assert_eq!(state.lister, etf.lister);
+    assert_eq!(state.etf_index, 0);
+    assert_eq!(state.token_addresses, mints);
+    assert_eq!(state.total_supply, LEGACY_SUPPLY);
+    assert_eq!(state.registry_index, 1);
+    assert_eq!(state.category, EtfCategory::Meme);
+    assert_eq!(state.status, EtfStatus::Active);
+    assert_eq!(state.keeper, None);
+    assert_eq!(state.sol_assets, LEGACY_SUPPLY);
+    assert_eq!(state.holdings, vec![0, 0]);
+    assert_eq!(state.legacy_shares, LEGACY_SUPPLY);
+    assert_eq!(state.lister_last_active_ts, harness.clock().unix_timestamp);
+
+    let page: RegistryPage = harness.anchor_account(&registry_page_address(1)).unwrap();
+    assert_eq!(page.entries[0].etf, listed.address);
+    assert_eq!(page.entries[1].etf, etf.address);
+
+    let events = outcome.events::<ETFMigratedEvent>();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].etf_address, etf.address);
+    assert_eq!(events[0].lister, etf.lister);
+    assert_eq!(events[0].token_count, 2);
+    assert_eq!(events[0].registry_index, 1);
+    assert_eq!(events[0].sol_assets, LEGACY_SUPPLY);
+}
+
+#[test]
+fn test_migrate_etf_payer_covers_rent() {
+    let mut harness = setup();
+    let etf = legacy_etf(&mut harness, &[Pubkey::new_unique()]);
+    let payer = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+    let total_before = harness.total_lamports();
+
+    migrate(&mut harness, &etf, payer).unwrap();
+
+    let rent = harness.rent().clone();
+    assert_eq!(harness.lamports(&etf.address), rent.minimum_balance(ETF_SPACE) + LEGACY_SUPPLY);
+    let rent_paid = rent.minimum_balance(ETF_SPACE) - rent.minimum_balance(LEGACY_ETF_SPACE)
+        + harness.lamports(&registry_address())
+        + harness.lamports(&registry_page_address(0));
+    assert_eq!(harness.lamports(&payer), LAMPORTS_PER_SOL - rent_paid);
+    assert_eq!(harness.total_lamports(), total_before);
+}
+
+#[test]
+fn test_migrated_etf_trades_at_booked_nav() {
+    let mut harness = setup();
+    let etf = legacy_etf(&mut harness, &[Pubkey::new_unique()]);
+    let payer = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+    migrate(&mut harness, &etf, payer).unwrap();
+
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+
+    assert_eq!(shares(&harness, &etf.address, &investor), 990_000_000);
+    let state = etf_state(&harness, &etf.address);
+    assert_eq!(state.total_supply, 2 * LEGACY_SUPPLY);
+    assert_eq!(state.sol_assets, 2 * LEGACY_SUPPLY);
+}
+
+#[test]
+fn test_migrate_etf_runs_once() {
+    let mut harness = setup();
+    let etf = legacy_etf(&mut harness, &[Pubkey::new_unique()]);
+    let payer = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+    migrate(&mut harness, &etf, payer).unwrap();
+
+    let again = TestEtf { registry_index: next_registry_index(&harness), ..etf };
+    assert_error(migrate(&mut harness, &again, payer), ErrorCode::AlreadyMigrated);
+
+    let current = create_etf(&mut harness, 1);
+    let current = TestEtf { registry_index: next_registry_index(&harness), ..current };
+    assert_error(migrate(&mut harness, &current, payer), ErrorCode::AlreadyMigrated);
+}
+
+#[test]
+fn test_restore_legacy_position_credits_shares() {
+    let mut harness = setup();
+    let etf = migrated_etf(&mut harness);
+    let governance = governance(&mut harness);
+    let holder = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+
+    let outcome = restore(&mut harness, &etf, holder, governance, 600_000_000).unwrap();
+
+    assert_eq!(shares(&harness, &etf.address, &holder), 600_000_000);
+    let state = etf_state(&harness, &etf.address);
+    assert_eq!(state.legacy_shares, LEGACY_SUPPLY - 600_000_000);
+    assert_eq!(state.total_supply, LEGACY_SUPPLY);
+    let events = outcome.events::<LegacyPositionRestoredEvent>();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].investor, holder);
+    assert_eq!(events[0].shares, 600_000_000);
+    assert_eq!(events[0].legacy_shares, LEGACY_SUPPLY - 600_000_000);
+
+    // Restored shares trade like any others
+    let before = harness.lamports(&holder);
+    sell(&mut harness, &etf, holder, 600_000_000).unwrap();
+    assert!(harness.lamports(&holder) > before);
+    assert_eq!(shares(&harness, &etf.address, &holder), 0);
+}
+
+#[test]
+fn test_restore_legacy_position_is_bounded_and_governed() {
+    let mut harness = setup();
+    let etf = migrated_etf(&mut harness);
+    let governance = governance(&mut harness);
+    let holder = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+
+    assert_error(restore(&mut harness, &etf, holder, holder, 1), ErrorCode::Unauthorized);
+    assert_error(
+        restore(&mut harness, &etf, holder, governance, LEGACY_SUPPLY + 1),
+        ErrorCode::LegacySharesExceeded,
+    );
+
+    restore(&mut harness, &etf, holder, governance, LEGACY_SUPPLY - 1).unwrap();
+    restore(&mut harness, &etf, holder, governance, 1).unwrap();
+    assert_eq!(shares(&harness, &etf.address, &holder), LEGACY_SUPPLY);
+    assert_error(
+        restore(&mut harness, &etf, holder, governance, 1),
+        ErrorCode::LegacySharesExceeded,
+    );
+}
+
+#[test]
+fn test_restored_legacy_holders_claim_liquidation() {
+    let mut harness = setup();
+    let etf = migrated_etf(&mut harness);
+    let governance = governance(&mut harness);
+    let alice = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+    let bob = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+    restore(&mut harness, &etf, alice, governance, 330_000_000).unwrap();
+    harness
+        .process_instruction(begin_liquidation_ix(etf.address, etf.lister), &[etf.lister])
+        .unwrap();
+
+    let claim = |harness: &mut Harness, investor: Pubkey| {
+        harness.process_instruction(
+            claim_liquidation_ix(etf.address, investor, &holder_vaults(&etf, &investor)),
+            &[investor],
+        )
+    };
+    let alice_before = harness.lamports(&alice);
+    let position_rent = harness.lamports(&position_address(&etf.address, &alice));
+    claim(&mut harness, alice).unwrap();
+    let payout = liquidation_payout(330_000_000, LEGACY_SUPPLY, LEGACY_SUPPLY);
+    assert_eq!(harness.lamports(&alice), alice_before + payout + position_rent);
+
+    // The ETF cannot close while legacy shares are unrestored
+    let keeper = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+    let close = close_liquidated_etf_ix(etf.address, etf.lister, keeper, etf.registry_index, &lister_vaults(&etf));
+    assert_error(harness.process_instruction(close.clone(), &[keeper]), ErrorCode::CannotCloseWithSupply);
+
+    restore(&mut harness, &etf, bob, governance, LEGACY_SUPPLY - 330_000_000).unwrap();
+    claim(&mut harness, bob).unwrap();
+    harness.process_instruction(close, &[keeper]).unwrap();
+    assert!(harness.account(&etf.address).is_none());
+}
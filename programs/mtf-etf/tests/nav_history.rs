@@ -0,0 +1,126 @@
+mod common;
+
+use anchor_lang::AnchorDeserialize;
+use common::*;
+use mtf_etf::{
+    ErrorCode, NavHistory, NavSnapshotEvent, TwapQuote, NAV_HISTORY_LEN, NAV_SCALE, NAV_SNAPSHOT_INTERVAL_SECONDS,
+};
+use program_harness::Harness;
+
+fn snapshot(harness: &mut Harness, etf: &TestEtf) -> TxResult {
+    harness.process_instruction(snapshot_nav_ix(etf.address), &[])
+}
+
+fn history(harness: &Harness, etf: &TestEtf) -> NavHistory {
+    harness.anchor_account(&nav_history_address(&etf.address)).unwrap()
+}
+
+fn twap(harness: &mut Harness, etf: &TestEtf, window_seconds: i64) -> Result<TwapQuote, u32> {
+    let outcome = harness
+        .process_instruction(get_nav_twap_ix(etf.address, window_seconds), &[])
+        .map_err(|error| error.custom_code().unwrap_or(u32::MAX))?;
+    let (_, data) = outcome.return_data.expect("return data");
+    Ok(TwapQuote::try_from_slice(&data).unwrap())
+}
+
+// One-token ETF with a bought position and an empty NAV history
+fn tracked_etf(harness: &mut Harness) -> TestEtf {
+    let etf = create_etf(harness, 1);
+    let investor = funded_wallet(harness, 10 * LAMPORTS_PER_SOL);
+    buy(harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+    let payer = funded_wallet(harness, LAMPORTS_PER_SOL);
+    harness
+        .process_instruction(create_nav_history_ix(etf.address, payer), &[payer])
+        .unwrap();
+    etf
+}
+
+#[test]
+fn test_snapshot_records_nav_once_per_interval() {
+    let mut harness = setup();
+    let etf = tracked_etf(&mut harness);
+    assert_eq!(twap(&mut harness, &etf, 3_600).unwrap_err(), u32::from(ErrorCode::NoNavHistory));
+
+    let outcome = snapshot(&mut harness, &etf).unwrap();
+    let supply = etf_state(&harness, &etf.address).total_supply;
+    let event = &outcome.events::<NavSnapshotEvent>()[0];
+    assert_eq!(event.nav_per_share, NAV_SCALE);
+    assert_eq!(event.total_value, supply);
+    assert_eq!(event.total_supply, supply);
+
+    let state = history(&harness, &etf);
+    assert_eq!(state.etf, etf.address);
+    assert_eq!(state.snapshots.len(), 1);
+    assert_eq!(state.snapshots[0].timestamp, harness.clock().unix_timestamp);
+
+    assert_error(snapshot(&mut harness, &etf), ErrorCode::NavSnapshotTooSoon);
+    harness.advance_time(NAV_SNAPSHOT_INTERVAL_SECONDS);
+    snapshot(&mut harness, &etf).unwrap();
+    assert_eq!(history(&harness, &etf).snapshots.len(), 2);
+}
+
+#[test]
+fn test_snapshot_ring_overwrites_oldest() {
+    let mut harness = setup();
+    let etf = tracked_etf(&mut harness);
+    let first = harness.clock().unix_timestamp;
+
+    for _ in 0..=NAV_HISTORY_LEN {
+        snapshot(&mut harness, &etf).unwrap();
+        harness.advance_time(NAV_SNAPSHOT_INTERVAL_SECONDS);
+    }
+
+    let state = history(&harness, &etf);
+    assert_eq!(state.snapshots.len(), NAV_HISTORY_LEN);
+    assert_eq!(state.head, 1);
+    let overwritten = first + NAV_HISTORY_LEN as i64 * NAV_SNAPSHOT_INTERVAL_SECONDS;
+    assert_eq!(state.snapshots[0].timestamp, overwritten);
+    assert_eq!(state.snapshots.iter().map(|s| s.timestamp).min(), Some(first + NAV_SNAPSHOT_INTERVAL_SECONDS));
+}
+
+#[test]
+fn test_twap_weights_nav_by_time() {
+    let mut harness = setup();
+    let etf = tracked_etf(&mut harness);
+    snapshot(&mut harness, &etf).unwrap();
+
+    // NAV doubles after three intervals and holds for one more
+    harness.advance_time(3 * NAV_SNAPSHOT_INTERVAL_SECONDS);
+    let supply = etf_state(&harness, &etf.address).total_supply;
+    shift_net_assets(&mut harness, &etf.address, supply as i64);
+    snapshot(&mut harness, &etf).unwrap();
+    harness.advance_time(NAV_SNAPSHOT_INTERVAL_SECONDS);
+
+    let quote = twap(&mut harness, &etf, 4 * NAV_SNAPSHOT_INTERVAL_SECONDS).unwrap();
+    assert_eq!(quote.nav_per_share, NAV_SCALE * 5 / 4);
+    assert_eq!(quote.covered_seconds, 4 * NAV_SNAPSHOT_INTERVAL_SECONDS);
+    assert_eq!(quote.snapshots, 2);
+
+    // A longer window only averages what the history covers
+    let quote = twap(&mut harness, &etf, 24 * NAV_SNAPSHOT_INTERVAL_SECONDS).unwrap();
+    assert_eq!(quote.nav_per_share, NAV_SCALE * 5 / 4);
+    assert_eq!(quote.covered_seconds, 4 * NAV_SNAPSHOT_INTERVAL_SECONDS);
+
+    let quote = twap(&mut harness, &etf, NAV_SNAPSHOT_INTERVAL_SECONDS).unwrap();
+    assert_eq!(quote.nav_per_share, 2 * NAV_SCALE);
+
+    assert_eq!(twap(&mut harness, &etf, 0).unwrap_err(), u32::from(ErrorCode::InvalidAmount));
+}
+
+#[test]
+fn test_nav_history_is_bound_to_its_etf() {
+    let mut harness = setup();
+    let etf = tracked_etf(&mut harness);
+    let payer = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+
+    // One history per ETF
+    assert!(harness
+        .process_instruction(create_nav_history_ix(etf.address, payer), &[payer])
+        .is_err());
+
+    // Another ETF cannot write into it
+    let other = create_etf(&mut harness, 1);
+    let mut ix = snapshot_nav_ix(other.address);
+    ix.accounts[1].pubkey = nav_history_address(&etf.address);
+    assert!(harness.process_instruction(ix, &[]).is_err());
+}
@@ -0,0 +1,258 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::*;
+use mtf_etf::{calculate_fees, ETFSoldEvent, ErrorCode, TokenRedeemedEvent, TokenSaleEvent, NAV_SCALE, TREASURY};
+use program_harness::Harness;
+
+// Two-token keeper ETF held equally by `other` and `investor`, each settled
+// at TOKEN_PRICE, with empty token accounts of the investor. Returns
+// (vault, account) pairs.
+fn redeemable_etf(harness: &mut Harness) -> (TestEtf, Pubkey, Vec<(Pubkey, Pubkey)>) {
+    let setup = keeper_etf(harness, 2);
+    let other = funded_wallet(harness, 10 * LAMPORTS_PER_SOL);
+    let investor = funded_wallet(harness, 10 * LAMPORTS_PER_SOL);
+    for (purchase_id, wallet) in [other, investor].into_iter().enumerate() {
+        pending_buy(harness, &setup.etf, wallet, LAMPORTS_PER_SOL).unwrap();
+        settle_at_price(harness, &setup, wallet, purchase_id as u64).unwrap();
+    }
+
+    let mut pairs = Vec::new();
+    for (mint, vault) in setup.etf.mints.iter().zip(setup.vaults()) {
+        let account = Pubkey::new_unique();
+        harness.create_token_account(account, mint, &investor, 0);
+        pairs.push((vault, account));
+    }
+    (setup.etf, investor, pairs)
+}
+
+fn redeem(harness: &mut Harness, etf: &TestEtf, investor: Pubkey, shares: u64, vaults: &[(Pubkey, Pubkey)]) -> TxResult {
+    harness.process_instruction(
+        with_price_feeds(
+            redeem_to_tokens_ix(etf.address, etf.lister, investor, shares, vaults),
+            &etf.mints,
+        ),
+        &[investor],
+    )
+}
+
+#[test]
+fn test_redeem_pays_pro_rata_tokens() {
+    let mut harness = setup();
+    let (etf, investor, pairs) = redeemable_etf(&mut harness);
+    let held = shares(&harness, &etf.address, &investor);
+    let supply = etf_state(&harness, &etf.address).total_supply;
+    assert_eq!(held * 2, supply);
+    // Tokens sent to a vault outside settlement are not the holders'
+    harness.create_token_account(pairs[1].0, &etf.mints[1], &etf.address, 9_900 + 5_000);
+
+    let etf_before = harness.lamports(&etf.address);
+    let investor_before = harness.lamports(&investor);
+    let lister_before = harness.lamports(&etf.lister);
+    let dev_before = harness.lamports(&TREASURY);
+    let outcome = redeem(&mut harness, &etf, investor, held, &pairs).unwrap();
+
+    // Half the supply takes half of each booked holding; there is no SOL
+    assert_eq!(harness.token_balance(&pairs[0].1), Some(4_950));
+    assert_eq!(harness.token_balance(&pairs[1].1), Some(4_950));
+    assert_eq!(harness.token_balance(&pairs[0].0), Some(4_950));
+    assert_eq!(harness.lamports(&etf.address), etf_before);
+    assert_eq!(etf_state(&harness, &etf.address).holdings, vec![4_950, 4_950]);
+
+    let (creator_fee, dev_fee) = calculate_fees(held);
+    assert_eq!(investor_before - harness.lamports(&investor), creator_fee + dev_fee);
+    assert_eq!(harness.lamports(&etf.lister) - lister_before, creator_fee);
+    assert_eq!(harness.lamports(&TREASURY) - dev_before, dev_fee);
+    assert_eq!(shares(&harness, &etf.address, &investor), 0);
+    assert_eq!(etf_state(&harness, &etf.address).total_supply, supply - held);
+
+    let redeemed = outcome.events::<TokenRedeemedEvent>();
+    assert_eq!(redeemed.len(), 2);
+    assert_eq!(redeemed[1].token_address, etf.mints[1]);
+    assert_eq!(redeemed[1].amount, 4_950);
+    // A trade summary like a sell, but nothing for the backend to swap
+    let sold = outcome.events::<ETFSoldEvent>();
+    assert_eq!(sold[0].shares_burned, held);
+    assert_eq!(sold[0].sol_amount, 0);
+    assert_eq!(sold[0].nav_per_share, NAV_SCALE);
+    assert!(outcome.events::<TokenSaleEvent>().is_empty());
+}
+
+#[test]
+fn test_redeem_pays_sol_of_instant_etf() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 2);
+    let other = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    buy(&mut harness, &etf, other, LAMPORTS_PER_SOL).unwrap();
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+    let held = shares(&harness, &etf.address, &investor);
+    // No vault was ever created, so no token accounts are needed
+    let pairs: Vec<_> = etf
+        .mints
+        .iter()
+        .map(|mint| (vault_address(&etf.address, mint), Pubkey::new_unique()))
+        .collect();
+
+    let etf_before = harness.lamports(&etf.address);
+    let investor_before = harness.lamports(&investor);
+    let outcome = redeem(&mut harness, &etf, investor, held, &pairs).unwrap();
+
+    // Half the supply takes half the booked SOL, fees paid from the wallet
+    let (creator_fee, dev_fee) = calculate_fees(held);
+    assert_eq!(etf_before - harness.lamports(&etf.address), held);
+    assert_eq!(
+        harness.lamports(&investor) - investor_before,
+        held - creator_fee - dev_fee
+    );
+    assert_eq!(etf_state(&harness, &etf.address).sol_assets, held);
+    assert_eq!(outcome.events::<ETFSoldEvent>()[0].sol_amount, held);
+    assert_eq!(outcome.events::<TokenRedeemedEvent>().len(), 2);
+}
+
+#[test]
+fn test_redeem_fees_follow_nav() {
+    let mut harness = setup();
+    let (etf, investor, pairs) = redeemable_etf(&mut harness);
+    let held = shares(&harness, &etf.address, &investor);
+    // The first token doubles, so each share is worth 1.5 lamports
+    set_price(&mut harness, &etf.mints[0], 2 * TOKEN_PRICE);
+
+    let investor_before = harness.lamports(&investor);
+    let lister_before = harness.lamports(&etf.lister);
+    let outcome = redeem(&mut harness, &etf, investor, held, &pairs).unwrap();
+
+    let value = held + held / 2;
+    let (creator_fee, dev_fee) = calculate_fees(value);
+    assert_eq!(creator_fee, calculate_fees(held).0 * 3 / 2);
+    assert_eq!(investor_before - harness.lamports(&investor), creator_fee + dev_fee);
+    assert_eq!(harness.lamports(&etf.lister) - lister_before, creator_fee);
+    let sold = outcome.events::<ETFSoldEvent>();
+    assert_eq!((sold[0].creator_fee, sold[0].dev_fee), (creator_fee, dev_fee));
+    assert_eq!(sold[0].nav_per_share, NAV_SCALE * 3 / 2);
+}
+
+#[test]
+fn test_redeem_rejects_unordered_or_foreign_vaults() {
+    let mut harness = setup();
+    let (etf, investor, pairs) = redeemable_etf(&mut harness);
+    let held = shares(&harness, &etf.address, &investor);
+
+    let reversed = [pairs[1], pairs[0]];
+    assert_error(redeem(&mut harness, &etf, investor, held, &reversed), ErrorCode::InvalidVaultAccount);
+    let twice = [pairs[0], pairs[0]];
+    assert_error(redeem(&mut harness, &etf, investor, held, &twice), ErrorCode::InvalidVaultAccount);
+
+    // Destinations must be the investor's own accounts
+    let stranger_account = Pubkey::new_unique();
+    harness.create_token_account(stranger_account, &etf.mints[0], &Pubkey::new_unique(), 0);
+    assert_error(
+        redeem(&mut harness, &etf, investor, held, &[(pairs[0].0, stranger_account), pairs[1]]),
+        ErrorCode::InvalidVaultAccount,
+    );
+
+    // Vaults must be the ETF's associated token accounts
+    let fake_vault = Pubkey::new_unique();
+    harness.create_token_account(fake_vault, &etf.mints[0], &etf.address, 1_000);
+    assert_error(
+        redeem(&mut harness, &etf, investor, held, &[(fake_vault, pairs[0].1), pairs[1]]),
+        ErrorCode::InvalidVaultAccount,
+    );
+}
+
+#[test]
+fn test_redeem_requires_every_basket_vault() {
+    let mut harness = setup();
+    let (etf, investor, pairs) = redeemable_etf(&mut harness);
+    let held = shares(&harness, &etf.address, &investor);
+
+    assert_error(redeem(&mut harness, &etf, investor, held, &pairs[1..]), ErrorCode::InvalidVaultAccount);
+    assert_error(redeem(&mut harness, &etf, investor, held, &[]), ErrorCode::InvalidVaultAccount);
+    assert_eq!(shares(&harness, &etf.address, &investor), held);
+}
+
+#[test]
+fn test_redeem_withholds_early_exit_fee_in_kind() {
+    let mut harness = setup();
+    let (etf, investor, pairs) = redeemable_etf(&mut harness);
+    let keeper = etf_state(&harness, &etf.address).keeper.unwrap();
+    let setup = KeeperEtf {
+        sources: etf.mints.iter().map(|_| Pubkey::new_unique()).collect(),
+        etf,
+        keeper,
+    };
+    harness
+        .process_instruction(
+            set_hold_period_ix(setup.etf.address, setup.etf.lister, 3_600, 1_000),
+            &[setup.etf.lister],
+        )
+        .unwrap();
+    for (mint, source) in setup.etf.mints.iter().zip(&setup.sources) {
+        harness.create_token_account(*source, mint, &keeper, 1_000_000);
+    }
+    pending_buy(&mut harness, &setup.etf, investor, LAMPORTS_PER_SOL).unwrap();
+    settle_at_price(&mut harness, &setup, investor, 2).unwrap();
+    let held = shares(&harness, &setup.etf.address, &investor);
+    let supply = etf_state(&harness, &setup.etf.address).total_supply;
+
+    let outcome = redeem(&mut harness, &setup.etf, investor, held, &pairs).unwrap();
+
+    // 10% of the investor's two thirds of each holding stays behind
+    let kept = held - held / 10;
+    assert_eq!(
+        harness.token_balance(&pairs[1].1),
+        Some((14_850 * kept as u128 / supply as u128) as u64)
+    );
+    // Reported in lamports at NAV, which is still one lamport per share
+    assert_eq!(outcome.events::<ETFSoldEvent>()[0].early_exit_fee, held / 10);
+
+    // Without an early-exit fee the hold period blocks redemptions too
+    harness
+        .process_instruction(
+            set_hold_period_ix(setup.etf.address, setup.etf.lister, 3_600, 0),
+            &[setup.etf.lister],
+        )
+        .unwrap();
+    pending_buy(&mut harness, &setup.etf, investor, LAMPORTS_PER_SOL).unwrap();
+    settle_at_price(&mut harness, &setup, investor, 3).unwrap();
+    assert_error(
+        redeem(&mut harness, &setup.etf, investor, 1_000, &pairs),
+        ErrorCode::HoldPeriodActive,
+    );
+}
+
+#[test]
+fn test_redeem_validation() {
+    let mut harness = setup();
+    let (etf, investor, pairs) = redeemable_etf(&mut harness);
+    let held = shares(&harness, &etf.address, &investor);
+
+    assert_error(redeem(&mut harness, &etf, investor, 0, &pairs), ErrorCode::InvalidAmount);
+    assert_error(redeem(&mut harness, &etf, investor, held + 1, &pairs), ErrorCode::InsufficientShares);
+    assert_error(
+        harness.process_instruction(
+            redeem_to_tokens_ix(etf.address, Pubkey::new_unique(), investor, held, &pairs),
+            &[investor],
+        ),
+        ErrorCode::InvalidListerAccount,
+    );
+    // Holdings are valued for the reported NAV, so their feeds are needed
+    assert_error(
+        harness.process_instruction(
+            redeem_to_tokens_ix(etf.address, etf.lister, investor, held, &pairs),
+            &[investor],
+        ),
+        ErrorCode::InvalidVaultAccount,
+    );
+    let reversed = [etf.mints[1], etf.mints[0]];
+    assert_error(
+        harness.process_instruction(
+            with_price_feeds(
+                redeem_to_tokens_ix(etf.address, etf.lister, investor, held, &pairs),
+                &reversed,
+            ),
+            &[investor],
+        ),
+        ErrorCode::InvalidPriceFeed,
+    );
+}
@@ -0,0 +1,235 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::*;
+use mtf_etf::{
+    calculate_fees, CreatorRewardClaimedEvent, EpochFinalizedEvent, EpochRewards, ErrorCode, RewardsFundedEvent,
+    Treasury, DEV_WALLET, GOVERNANCE_AUTHORITY, LEADERBOARD_EPOCH_SECONDS, LEADERBOARD_SUBMISSION_SECONDS, TREASURY,
+};
+use program_harness::Harness;
+
+fn treasury(harness: &Harness) -> Treasury {
+    harness.anchor_account(&TREASURY).unwrap()
+}
+
+fn configure(harness: &mut Harness, rewards_share_bps: u16) -> TxResult {
+    harness.process_instruction(
+        configure_treasury_ix(GOVERNANCE_AUTHORITY, rewards_share_bps),
+        &[GOVERNANCE_AUTHORITY],
+    )
+}
+
+fn fund(harness: &mut Harness, amount: u64) -> TxResult {
+    let funder = funded_wallet(harness, amount + LAMPORTS_PER_SOL);
+    harness.process_instruction(fund_rewards_ix(funder, amount), &[funder])
+}
+
+// Epoch in which `gains` (bps of net assets, one ETF each) were recorded,
+// with every ETF submitted and the clock at the end of the epoch
+fn ranked_epoch(harness: &mut Harness, gains: &[u64]) -> (u64, Vec<TestEtf>) {
+    let etfs: Vec<TestEtf> = gains
+        .iter()
+        .map(|_| {
+            let etf = create_etf(harness, 1);
+            let investor = funded_wallet(harness, 3 * LAMPORTS_PER_SOL);
+            buy(harness, &etf, investor, 2 * LAMPORTS_PER_SOL).unwrap();
+            let payer = funded_wallet(harness, LAMPORTS_PER_SOL);
+            harness
+                .process_instruction(create_nav_history_ix(etf.address, payer), &[payer])
+                .unwrap();
+            etf
+        })
+        .collect();
+
+    let epoch_id = (harness.clock().unix_timestamp / LEADERBOARD_EPOCH_SECONDS + 1) as u64;
+    harness.set_unix_timestamp(epoch_id as i64 * LEADERBOARD_EPOCH_SECONDS);
+    let payer = funded_wallet(harness, LAMPORTS_PER_SOL);
+    harness
+        .process_instruction(create_leaderboard_epoch_ix(epoch_id, payer), &[payer])
+        .unwrap();
+    let addresses: Vec<Pubkey> = etfs.iter().map(|etf| etf.address).collect();
+    crank_nav_histories(harness, &addresses, epoch_id as i64 * LEADERBOARD_EPOCH_SECONDS);
+    for (etf, gain_bps) in etfs.iter().zip(gains) {
+        let supply = etf_state(harness, &etf.address).total_supply;
+        shift_net_assets(harness, &etf.address, (supply * gain_bps / 10_000) as i64);
+    }
+    crank_nav_histories(harness, &addresses, (epoch_id as i64 + 1) * LEADERBOARD_EPOCH_SECONDS);
+    for etf in &etfs {
+        harness
+            .process_instruction(submit_epoch_return_ix(epoch_id, etf.address), &[])
+            .unwrap();
+    }
+    (epoch_id, etfs)
+}
+
+fn finalize(harness: &mut Harness, epoch_id: u64) -> TxResult {
+    let payer = funded_wallet(harness, LAMPORTS_PER_SOL);
+    harness.process_instruction(finalize_epoch_ix(epoch_id, payer), &[payer])
+}
+
+#[test]
+fn test_dev_fees_split_between_rewards_and_dev() {
+    let mut harness = setup();
+    configure(&mut harness, 2_000).unwrap();
+    let etf = create_etf(&mut harness, 1);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+
+    let treasury_before = harness.lamports(&TREASURY);
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+    let (_, dev_fee) = calculate_fees(LAMPORTS_PER_SOL);
+    assert_eq!(harness.lamports(&TREASURY) - treasury_before, dev_fee);
+
+    // Sweeping books 20% of the fee for rewards, next to the deposit
+    let outcome = fund(&mut harness, LAMPORTS_PER_SOL).unwrap();
+    let funded = &outcome.events::<RewardsFundedEvent>()[0];
+    assert_eq!(funded.fees_to_rewards, dev_fee / 5);
+    assert_eq!(funded.fees_to_dev, dev_fee - dev_fee / 5);
+    assert_eq!(funded.rewards_pool, LAMPORTS_PER_SOL + dev_fee / 5);
+    let books = treasury(&harness);
+    assert_eq!(books.rewards_pool, LAMPORTS_PER_SOL + dev_fee / 5);
+    assert_eq!(books.dev_balance, dev_fee - dev_fee / 5);
+
+    // Withdrawals only pay the dev share, and only to DEV_WALLET
+    let dev_before = harness.lamports(&DEV_WALLET);
+    harness.process_instruction(withdraw_dev_fees_ix(), &[]).unwrap();
+    assert_eq!(harness.lamports(&DEV_WALLET) - dev_before, dev_fee - dev_fee / 5);
+    assert_eq!(treasury(&harness).dev_balance, 0);
+    assert_error(harness.process_instruction(withdraw_dev_fees_ix(), &[]), ErrorCode::InvalidAmount);
+
+    let mut ix = withdraw_dev_fees_ix();
+    ix.accounts[1].pubkey = Pubkey::new_unique();
+    assert!(harness.process_instruction(ix, &[]).is_err());
+}
+
+#[test]
+fn test_fees_collected_before_a_share_change_keep_the_old_share() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+
+    configure(&mut harness, 10_000).unwrap();
+    let (_, dev_fee) = calculate_fees(LAMPORTS_PER_SOL);
+    let books = treasury(&harness);
+    assert_eq!(books.rewards_share_bps, 10_000);
+    assert_eq!(books.dev_balance, dev_fee);
+    assert_eq!(books.rewards_pool, 0);
+}
+
+#[test]
+fn test_configure_treasury_is_governance_only() {
+    let mut harness = setup();
+    assert_error(configure(&mut harness, 10_001), ErrorCode::InvalidRewardsShare);
+
+    let stranger = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+    assert_error(
+        harness.process_instruction(configure_treasury_ix(stranger, 5_000), &[stranger]),
+        ErrorCode::Unauthorized,
+    );
+}
+
+#[test]
+fn test_dev_fees_must_go_to_treasury() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    assert_error(
+        harness.process_instruction(
+            buy_etf_ix(etf.address, etf.lister, investor, DEV_WALLET, LAMPORTS_PER_SOL, vec![100]),
+            &[investor],
+        ),
+        ErrorCode::InvalidDevWallet,
+    );
+}
+
+#[test]
+fn test_fees_wait_for_treasury_configuration() {
+    let mut harness = Harness::new();
+    harness.add_program(mtf_etf::ID, mtf_etf::entry);
+    let etf = create_etf(&mut harness, 1);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+
+    // Even a funded system account at the PDA is not a treasury
+    harness.airdrop(&TREASURY, LAMPORTS_PER_SOL);
+    assert_error(buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL), ErrorCode::TreasuryNotConfigured);
+
+    harness.airdrop(&GOVERNANCE_AUTHORITY, LAMPORTS_PER_SOL);
+    configure(&mut harness, 0).unwrap();
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+}
+
+#[test]
+fn test_finalized_epoch_pays_ranked_creators() {
+    let mut harness = setup();
+    let (epoch_id, etfs) = ranked_epoch(&mut harness, &[1_000, 3_000]);
+    fund(&mut harness, 900_000_000).unwrap();
+
+    // Submissions get their window first
+    assert_error(finalize(&mut harness, epoch_id), ErrorCode::SubmissionsOpen);
+    harness.advance_time(LEADERBOARD_SUBMISSION_SECONDS);
+    assert_error(
+        harness.process_instruction(submit_epoch_return_ix(epoch_id, etfs[0].address), &[]),
+        ErrorCode::SubmissionsClosed,
+    );
+
+    let outcome = finalize(&mut harness, epoch_id).unwrap();
+    let finalized = &outcome.events::<EpochFinalizedEvent>()[0];
+    assert_eq!(finalized.total_rewards, 900_000_000);
+    assert_eq!(finalized.ranked, 2);
+    let rewards: EpochRewards = harness.anchor_account(&epoch_rewards_address(epoch_id)).unwrap();
+    assert_eq!(rewards.rewards[0].lister, etfs[1].lister);
+    assert_eq!(rewards.rewards[0].amount, 600_000_000);
+    assert_eq!(rewards.rewards[1].amount, 300_000_000);
+    let books = treasury(&harness);
+    assert_eq!((books.rewards_pool, books.rewards_owed), (0, 900_000_000));
+    assert!(finalize(&mut harness, epoch_id).is_err());
+
+    // Only the ranked lister claims, once
+    assert_error(
+        harness.process_instruction(claim_creator_reward_ix(epoch_id, etfs[0].lister, 0), &[etfs[0].lister]),
+        ErrorCode::Unauthorized,
+    );
+    let winner = etfs[1].lister;
+    let before = harness.lamports(&winner);
+    let outcome = harness
+        .process_instruction(claim_creator_reward_ix(epoch_id, winner, 0), &[winner])
+        .unwrap();
+    assert_eq!(harness.lamports(&winner) - before, 600_000_000);
+    let claimed = &outcome.events::<CreatorRewardClaimedEvent>()[0];
+    assert_eq!(claimed.etf_address, etfs[1].address);
+    assert_eq!(claimed.amount, 600_000_000);
+    assert_eq!(treasury(&harness).rewards_owed, 300_000_000);
+    assert_error(
+        harness.process_instruction(claim_creator_reward_ix(epoch_id, winner, 0), &[winner]),
+        ErrorCode::RewardAlreadyClaimed,
+    );
+}
+
+#[test]
+fn test_empty_epoch_leaves_pool_for_the_next() {
+    let mut harness = setup();
+    let (epoch_id, _) = ranked_epoch(&mut harness, &[]);
+    fund(&mut harness, 900_000_000).unwrap();
+    harness.advance_time(LEADERBOARD_SUBMISSION_SECONDS);
+
+    let outcome = finalize(&mut harness, epoch_id).unwrap();
+    assert_eq!(outcome.events::<EpochFinalizedEvent>()[0].total_rewards, 0);
+    assert_eq!(treasury(&harness).rewards_pool, 900_000_000);
+}
+
+#[test]
+fn test_reward_claim_cannot_exceed_booked_rewards() {
+    let mut harness = setup();
+    let (epoch_id, etfs) = ranked_epoch(&mut harness, &[1_000]);
+    fund(&mut harness, 900_000_000).unwrap();
+    harness.advance_time(LEADERBOARD_SUBMISSION_SECONDS);
+    finalize(&mut harness, epoch_id).unwrap();
+
+    tamper::<Treasury>(&mut harness, &TREASURY, |treasury| treasury.rewards_owed = 0);
+
+    let lister = etfs[0].lister;
+    assert_error(
+        harness.process_instruction(claim_creator_reward_ix(epoch_id, lister, 0), &[lister]),
+        ErrorCode::InvalidAmount,
+    );
+}
@@ -0,0 +1,246 @@
+mod common;
+
+use common::*;
+use mtf_etf::{
+    calculate_fees, ETFSoldEvent, ErrorCode, FeeTransferEvent, FeeType, TokenSaleEvent, TREASURY, ETF, NAV_SCALE,
+};
+
+#[test]
+fn test_sell_etf_returns_sol_minus_fees() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 2);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    buy(&mut harness, &etf, investor, 2 * LAMPORTS_PER_SOL).unwrap();
+
+    let held = shares(&harness, &etf.address, &investor);
+    let to_sell = held / 2;
+    let investor_before = harness.lamports(&investor);
+    let etf_before = harness.lamports(&etf.address);
+    let lister_before = harness.lamports(&etf.lister);
+    let dev_before = harness.lamports(&TREASURY);
+    let total_before = harness.total_lamports();
+
+    let outcome = sell(&mut harness, &etf, investor, to_sell).unwrap();
+
+    let (creator_fee, dev_fee) = calculate_fees(to_sell);
+    assert_eq!(harness.lamports(&investor), investor_before + to_sell - creator_fee - dev_fee);
+    assert_eq!(harness.lamports(&etf.address), etf_before - to_sell);
+    assert_eq!(harness.lamports(&etf.lister), lister_before + creator_fee);
+    assert_eq!(harness.lamports(&TREASURY), dev_before + dev_fee);
+    assert_eq!(harness.total_lamports(), total_before);
+
+    assert_eq!(shares(&harness, &etf.address, &investor), held - to_sell);
+    assert_eq!(etf_state(&harness, &etf.address).total_supply, held - to_sell);
+
+    let fees = outcome.events::<FeeTransferEvent>();
+    assert_eq!(fees.len(), 2);
+    assert_eq!(fees[0].recipient, etf.lister);
+    assert_eq!(fees[0].amount, creator_fee);
+    assert!(fees[0].fee_type == FeeType::Creator);
+    assert_eq!(fees[1].recipient, TREASURY);
+    assert_eq!(fees[1].amount, dev_fee);
+    assert!(fees[1].fee_type == FeeType::Dev);
+}
+
+#[test]
+fn test_sell_etf_emits_token_sale_and_trade_summary() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 3);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    buy(&mut harness, &etf, investor, 2 * LAMPORTS_PER_SOL).unwrap();
+    let supply_before = etf_state(&harness, &etf.address).total_supply;
+    let to_sell = supply_before / 4;
+
+    let outcome = sell(&mut harness, &etf, investor, to_sell).unwrap();
+
+    let sales = outcome.events::<TokenSaleEvent>();
+    assert_eq!(sales.len(), 3);
+    for (event, mint) in sales.iter().zip(&etf.mints) {
+        assert_eq!(event.etf_address, etf.address);
+        assert_eq!(event.investor, investor);
+        assert_eq!(event.token_address, *mint);
+        assert_eq!(event.shares_burned, to_sell);
+        assert_eq!(event.supply_before, supply_before);
+        assert_eq!(event.timestamp, harness.clock().unix_timestamp);
+    }
+
+    let (creator_fee, dev_fee) = calculate_fees(to_sell);
+    let events = outcome.events::<ETFSoldEvent>();
+    assert_eq!(events.len(), 1);
+    let event = &events[0];
+    assert_eq!(event.etf_address, etf.address);
+    assert_eq!(event.investor, investor);
+    assert_eq!(event.sequence, 2);
+    assert_eq!(event.shares_burned, to_sell);
+    assert_eq!(event.sol_amount, to_sell - creator_fee - dev_fee);
+    assert_eq!(event.creator_fee, creator_fee);
+    assert_eq!(event.dev_fee, dev_fee);
+    assert_eq!(event.total_supply, supply_before - to_sell);
+    assert_eq!(event.nav_per_share, NAV_SCALE);
+}
+
+#[test]
+fn test_sell_etf_ignores_donated_lamports() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+    let held = shares(&harness, &etf.address, &investor);
+
+    // Lamports sent straight to the ETF are never booked, so they move
+    // neither NAV nor what a seller is paid
+    harness.airdrop(&etf.address, held / 2);
+    let investor_before = harness.lamports(&investor);
+    let outcome = sell(&mut harness, &etf, investor, held / 2).unwrap();
+
+    let (creator_fee, dev_fee) = calculate_fees(held / 2);
+    assert_eq!(
+        harness.lamports(&investor),
+        investor_before + held / 2 - creator_fee - dev_fee
+    );
+    let event = &outcome.events::<ETFSoldEvent>()[0];
+    assert_eq!(event.total_supply, held - held / 2);
+    assert_eq!(event.nav_per_share, NAV_SCALE);
+    assert_eq!(etf_state(&harness, &etf.address).sol_assets, held - held / 2);
+}
+
+#[test]
+fn test_sell_entire_position_leaves_etf_rent_exempt() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    let etf_rent = harness.lamports(&etf.address);
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+
+    let held = shares(&harness, &etf.address, &investor);
+    sell(&mut harness, &etf, investor, held).unwrap();
+
+    assert_eq!(harness.lamports(&etf.address), etf_rent);
+    assert_eq!(etf_state(&harness, &etf.address).total_supply, 0);
+    assert_eq!(shares(&harness, &etf.address, &investor), 0);
+}
+
+#[test]
+fn test_sell_etf_zero_amount() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+
+    assert_error(sell(&mut harness, &etf, investor, 0), ErrorCode::InvalidAmount);
+}
+
+#[test]
+fn test_sell_etf_more_than_position() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    let other = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+    buy(&mut harness, &etf, other, LAMPORTS_PER_SOL).unwrap();
+
+    // Supply covers it, but the investor's own position does not
+    let held = shares(&harness, &etf.address, &investor);
+    assert_error(sell(&mut harness, &etf, investor, held + 1), ErrorCode::InsufficientShares);
+}
+
+#[test]
+fn test_sell_etf_without_position() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    let stranger = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+
+    let result = sell(&mut harness, &etf, stranger, 1);
+    assert_error(result, anchor_lang::error::ErrorCode::AccountNotInitialized);
+}
+
+#[test]
+fn test_sell_etf_wrong_dev_wallet() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    let impostor = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+
+    let result = harness.process_instruction(
+        sell_etf_ix(etf.address, etf.lister, investor, impostor, 1_000),
+        &[investor],
+    );
+    assert_error(result, ErrorCode::InvalidDevWallet);
+}
+
+#[test]
+fn test_sell_etf_wrong_lister_account() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    let impostor = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+
+    let result = harness.process_instruction(
+        sell_etf_ix(etf.address, impostor, investor, TREASURY, 1_000),
+        &[investor],
+    );
+    assert_error(result, ErrorCode::InvalidListerAccount);
+}
+
+#[test]
+fn test_sell_etf_while_liquidating() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+    harness
+        .process_instruction(begin_liquidation_ix(etf.address, etf.lister), &[etf.lister])
+        .unwrap();
+
+    assert_error(sell(&mut harness, &etf, investor, 1_000), ErrorCode::EtfLiquidating);
+}
+
+#[test]
+fn test_sell_etf_supply_below_position() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+
+    // Only reachable if supply and positions drift apart
+    tamper::<ETF>(&mut harness, &etf.address, |state| state.total_supply = 10);
+    assert_error(sell(&mut harness, &etf, investor, 11), ErrorCode::InsufficientFunds);
+}
+
+#[test]
+fn test_sell_etf_lamports_below_rent() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+
+    // SOL already moved out of the ETF (e.g. spent on swaps)
+    let mut account = harness.account(&etf.address).unwrap().clone();
+    account.lamports -= 1;
+    harness.set_account(etf.address, account);
+
+    let held = shares(&harness, &etf.address, &investor);
+    assert_error(sell(&mut harness, &etf, investor, held), ErrorCode::InsufficientFunds);
+    sell(&mut harness, &etf, investor, held - 1).unwrap();
+}
+
+#[test]
+fn test_sell_etf_requires_investor_signature() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+
+    let result = harness.process_instruction(
+        sell_etf_ix(etf.address, etf.lister, investor, TREASURY, 1_000),
+        &[etf.lister],
+    );
+    assert_eq!(
+        result.unwrap_err().error,
+        program_harness::HarnessError::MissingSignature(investor)
+    );
+}
@@ -0,0 +1,484 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::*;
+use mtf_etf::{
+    settlement_floor, ETFBoughtEvent, ErrorCode, KeeperUpdatedEvent, PendingPurchase, PurchasePendingEvent,
+    PurchaseRefundedEvent, PurchaseSettledEvent, DEFAULT_REFUND_TIMEOUT_SECONDS, MAX_PRICE_AGE_SECONDS,
+    MAX_REFUND_TIMEOUT_SECONDS, MIN_REFUND_TIMEOUT_SECONDS, NAV_SCALE,
+};
+use program_harness::Harness;
+
+const TIMEOUT: i64 = DEFAULT_REFUND_TIMEOUT_SECONDS;
+
+#[test]
+fn test_set_keeper_emits_event() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let keeper = Pubkey::new_unique();
+
+    let outcome = harness
+        .process_instruction(set_keeper_ix(etf.address, etf.lister, Some(keeper), TIMEOUT), &[etf.lister])
+        .unwrap();
+
+    assert_eq!(etf_state(&harness, &etf.address).keeper, Some(keeper));
+    let events = outcome.events::<KeeperUpdatedEvent>();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].keeper, Some(keeper));
+}
+
+#[test]
+fn test_set_keeper_requires_lister() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let intruder = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+
+    let result = harness.process_instruction(set_keeper_ix(etf.address, intruder, Some(intruder), TIMEOUT), &[intruder]);
+    assert!(result.is_err());
+    assert_eq!(etf_state(&harness, &etf.address).keeper, None);
+}
+
+#[test]
+fn test_settlement_mode_locked_with_shares_outstanding() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+
+    let result =
+        harness.process_instruction(set_keeper_ix(etf.address, etf.lister, Some(Pubkey::new_unique()), TIMEOUT), &[etf.lister]);
+    assert_error(result, ErrorCode::SettlementModeLocked);
+}
+
+#[test]
+fn test_keeper_can_be_rotated_with_shares_outstanding() {
+    let mut harness = setup();
+    let setup = keeper_etf(&mut harness, 2);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    pending_buy(&mut harness, &setup.etf, investor, LAMPORTS_PER_SOL).unwrap();
+
+    // Swapping keepers keeps the mode; dropping the keeper does not
+    let next = Pubkey::new_unique();
+    harness
+        .process_instruction(set_keeper_ix(setup.etf.address, setup.etf.lister, Some(next), TIMEOUT), &[setup.etf.lister])
+        .unwrap();
+    let result =
+        harness.process_instruction(set_keeper_ix(setup.etf.address, setup.etf.lister, None, TIMEOUT), &[setup.etf.lister]);
+    assert_error(result, ErrorCode::SettlementModeLocked);
+}
+
+#[test]
+fn test_pending_buy_holds_sol_without_minting() {
+    let mut harness = setup();
+    let setup = keeper_etf(&mut harness, 2);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+
+    let outcome = pending_buy(&mut harness, &setup.etf, investor, LAMPORTS_PER_SOL).unwrap();
+
+    let etf = etf_state(&harness, &setup.etf.address);
+    assert_eq!(etf.total_supply, 0);
+    assert_eq!(etf.pending_sol, 990_000_000);
+    assert_eq!(etf.purchase_count, 1);
+    assert_eq!(etf.trade_sequence, 0);
+    assert_eq!(shares(&harness, &setup.etf.address, &investor), 0);
+
+    let pending: PendingPurchase =
+        harness.anchor_account(&pending_purchase_address(&setup.etf.address, 0)).unwrap();
+    assert_eq!(pending.investor, investor);
+    assert_eq!(pending.purchase_id, 0);
+    assert_eq!(pending.sol_amount, LAMPORTS_PER_SOL);
+    assert_eq!(pending.sol_to_swap, 990_000_000);
+    assert_eq!(pending.allocations, vec![495_000_000, 495_000_000]);
+
+    let events = outcome.events::<PurchasePendingEvent>();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].sol_amount, 990_000_000);
+    assert!(outcome.events::<ETFBoughtEvent>().is_empty());
+}
+
+#[test]
+fn test_keeper_etf_requires_pending_account() {
+    let mut harness = setup();
+    let setup = keeper_etf(&mut harness, 2);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+
+    assert_error(buy(&mut harness, &setup.etf, investor, LAMPORTS_PER_SOL), ErrorCode::InvalidPendingPurchase);
+}
+
+#[test]
+fn test_instant_etf_rejects_pending_account() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 2);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+
+    assert_error(pending_buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL), ErrorCode::InvalidPendingPurchase);
+    assert!(harness.account(&pending_purchase_address(&etf.address, 0)).is_none());
+}
+
+#[test]
+fn test_settle_purchase_mints_and_reimburses_keeper() {
+    let mut harness = setup();
+    let setup = keeper_etf(&mut harness, 2);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    pending_buy(&mut harness, &setup.etf, investor, LAMPORTS_PER_SOL).unwrap();
+    let pending_address = pending_purchase_address(&setup.etf.address, 0);
+    let pending_rent = harness.lamports(&pending_address);
+    let keeper_before = harness.lamports(&setup.keeper);
+    let investor_before = harness.lamports(&investor);
+    let total_before = harness.total_lamports();
+
+    // 4_950 tokens at 100_000 lamports each are worth each 0.495 SOL allocation
+    let outcome = settle(&mut harness, &setup, investor, 0, vec![4_950, 4_950]).unwrap();
+
+    let vaults = setup.vaults();
+    assert_eq!(harness.token_balance(&vaults[0]), Some(4_950));
+    assert_eq!(harness.token_balance(&vaults[1]), Some(4_950));
+    assert_eq!(harness.lamports(&setup.keeper), keeper_before + 990_000_000);
+    assert_eq!(harness.lamports(&investor), investor_before + pending_rent);
+    assert!(harness.account(&pending_address).is_none());
+    assert_eq!(harness.total_lamports(), total_before);
+
+    let etf = etf_state(&harness, &setup.etf.address);
+    assert_eq!(etf.total_supply, 990_000_000);
+    assert_eq!(etf.pending_sol, 0);
+    assert_eq!(etf.holdings, vec![4_950, 4_950]);
+    assert_eq!(etf.sol_assets, 0);
+    assert_eq!(etf.trade_sequence, 1);
+    assert_eq!(shares(&harness, &setup.etf.address, &investor), 990_000_000);
+
+    let settled = outcome.events::<PurchaseSettledEvent>();
+    assert_eq!(settled.len(), 1);
+    assert_eq!(settled[0].token_amounts, vec![4_950, 4_950]);
+    let bought = outcome.events::<ETFBoughtEvent>();
+    assert_eq!(bought.len(), 1);
+    assert_eq!(bought[0].sol_amount, LAMPORTS_PER_SOL);
+    assert_eq!(bought[0].shares_minted, 990_000_000);
+    assert_eq!(bought[0].nav_per_share, NAV_SCALE);
+    assert_eq!(bought[0].sequence, 1);
+}
+
+#[test]
+fn test_settle_purchase_requires_keeper() {
+    let mut harness = setup();
+    let mut setup = keeper_etf(&mut harness, 2);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    pending_buy(&mut harness, &setup.etf, investor, LAMPORTS_PER_SOL).unwrap();
+
+    setup.keeper = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+    assert_error(
+        settle(&mut harness, &setup, investor, 0, vec![4_950, 4_950]),
+        ErrorCode::Unauthorized,
+    );
+}
+
+#[test]
+fn test_settle_purchase_requires_every_token() {
+    let mut harness = setup();
+    let setup = keeper_etf(&mut harness, 2);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    pending_buy(&mut harness, &setup.etf, investor, LAMPORTS_PER_SOL).unwrap();
+
+    assert_error(
+        settle(&mut harness, &setup, investor, 0, vec![9_900, 0]),
+        ErrorCode::SettlementBelowPrice,
+    );
+    assert_error(
+        settle(&mut harness, &setup, investor, 0, vec![4_950]),
+        ErrorCode::InvalidSettlement,
+    );
+}
+
+#[test]
+fn test_settle_purchase_enforces_price_floor() {
+    let mut harness = setup();
+    let setup = keeper_etf(&mut harness, 2);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    pending_buy(&mut harness, &setup.etf, investor, LAMPORTS_PER_SOL).unwrap();
+
+    // A token base unit apiece cannot buy the investor's SOL
+    assert_error(
+        settle(&mut harness, &setup, investor, 0, vec![1, 1]),
+        ErrorCode::SettlementBelowPrice,
+    );
+    // MAX_SETTLEMENT_SLIPPAGE_BPS under each allocation's value is the floor
+    let floor = settlement_floor(495_000_000).div_ceil(100_000);
+    assert_error(
+        settle(&mut harness, &setup, investor, 0, vec![floor - 1, 4_950]),
+        ErrorCode::SettlementBelowPrice,
+    );
+    settle(&mut harness, &setup, investor, 0, vec![floor, 4_950]).unwrap();
+
+    // Shares are minted on the value delivered, not the SOL paid
+    let value = (floor + 4_950) * 100_000;
+    assert_eq!(shares(&harness, &setup.etf.address, &investor), value);
+}
+
+#[test]
+fn test_settle_purchase_requires_fresh_prices() {
+    let mut harness = setup();
+    let setup = keeper_etf(&mut harness, 2);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    pending_buy(&mut harness, &setup.etf, investor, LAMPORTS_PER_SOL).unwrap();
+
+    harness.advance_time(MAX_PRICE_AGE_SECONDS + 1);
+    assert_error(
+        settle_at_price(&mut harness, &setup, investor, 0),
+        ErrorCode::StalePrice,
+    );
+
+    for mint in &setup.etf.mints {
+        set_price(&mut harness, mint, TOKEN_PRICE);
+    }
+    settle_at_price(&mut harness, &setup, investor, 0).unwrap();
+}
+
+#[test]
+fn test_settle_purchase_rejects_mismatched_price_feed() {
+    let mut harness = setup();
+    let setup = keeper_etf(&mut harness, 2);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    pending_buy(&mut harness, &setup.etf, investor, LAMPORTS_PER_SOL).unwrap();
+
+    let mut legs = setup.legs();
+    legs[1].2 = legs[0].2;
+    let ix = settle_purchase_ix(setup.etf.address, investor, setup.keeper, 0, &legs, vec![4_950, 4_950]);
+    assert_error(
+        harness.process_instruction(ix, &[setup.keeper]),
+        ErrorCode::InvalidPriceFeed,
+    );
+
+    // Feeds only come from set_price
+    legs[1].2 = setup.sources[1];
+    let ix = settle_purchase_ix(setup.etf.address, investor, setup.keeper, 0, &legs, vec![4_950, 4_950]);
+    assert_error(
+        harness.process_instruction(ix, &[setup.keeper]),
+        ErrorCode::InvalidPriceFeed,
+    );
+}
+
+#[test]
+fn test_settle_purchase_rejects_foreign_vault() {
+    let mut harness = setup();
+    let setup = keeper_etf(&mut harness, 2);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    pending_buy(&mut harness, &setup.etf, investor, LAMPORTS_PER_SOL).unwrap();
+
+    // A token account of the right mint that the keeper, not the ETF, owns
+    let foreign = Pubkey::new_unique();
+    harness.create_token_account(foreign, &setup.etf.mints[1], &setup.keeper, 0);
+    // and one the ETF owns that is not its vault of that mint
+    let stray = Pubkey::new_unique();
+    harness.create_token_account(stray, &setup.etf.mints[1], &setup.etf.address, 0);
+    for vault in [foreign, stray, setup.vaults()[0]] {
+        let mut legs = setup.legs();
+        legs[1].1 = vault;
+        let ix = settle_purchase_ix(setup.etf.address, investor, setup.keeper, 0, &legs, vec![4_950, 4_950]);
+        assert_error(
+            harness.process_instruction(ix, &[setup.keeper]),
+            ErrorCode::InvalidVaultAccount,
+        );
+    }
+}
+
+#[test]
+fn test_later_settlements_mint_at_nav() {
+    let mut harness = setup();
+    let setup = keeper_etf(&mut harness, 2);
+    let early = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    let late = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    pending_buy(&mut harness, &setup.etf, early, LAMPORTS_PER_SOL).unwrap();
+    settle(&mut harness, &setup, early, 0, vec![4_950, 4_950]).unwrap();
+
+    // The basket doubles in price: the same SOL buys half the tokens and
+    // half the shares
+    for mint in &setup.etf.mints {
+        set_price(&mut harness, mint, 2 * TOKEN_PRICE);
+    }
+    pending_buy(&mut harness, &setup.etf, late, LAMPORTS_PER_SOL).unwrap();
+    settle(&mut harness, &setup, late, 1, vec![2_475, 2_475]).unwrap();
+
+    assert_eq!(shares(&harness, &setup.etf.address, &early), 990_000_000);
+    assert_eq!(shares(&harness, &setup.etf.address, &late), 495_000_000);
+    assert_eq!(etf_state(&harness, &setup.etf.address).holdings, vec![7_425, 7_425]);
+}
+
+#[test]
+fn test_settled_etf_redeems_in_kind() {
+    let mut harness = setup();
+    let setup = keeper_etf(&mut harness, 2);
+    let early = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    let late = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    pending_buy(&mut harness, &setup.etf, early, LAMPORTS_PER_SOL).unwrap();
+    settle(&mut harness, &setup, early, 0, vec![4_950, 4_950]).unwrap();
+    pending_buy(&mut harness, &setup.etf, late, LAMPORTS_PER_SOL).unwrap();
+
+    // The settled SOL went to the keeper; the ETF holds tokens, not SOL
+    assert_error(
+        sell(&mut harness, &setup.etf, early, 990_000_000),
+        ErrorCode::RedeemInKind,
+    );
+
+    let mut pairs = Vec::new();
+    for (mint, vault) in setup.etf.mints.iter().zip(setup.vaults()) {
+        let account = Pubkey::new_unique();
+        harness.create_token_account(account, mint, &early, 0);
+        pairs.push((vault, account));
+    }
+    let ix = with_price_feeds(
+        redeem_to_tokens_ix(setup.etf.address, setup.etf.lister, early, 990_000_000, &pairs),
+        &setup.etf.mints,
+    );
+    harness.process_instruction(ix, &[early]).unwrap();
+
+    assert_eq!(harness.token_balance(&pairs[0].1), Some(4_950));
+    assert_eq!(harness.token_balance(&pairs[1].1), Some(4_950));
+    let etf = etf_state(&harness, &setup.etf.address);
+    assert_eq!(etf.holdings, vec![0, 0]);
+    assert_eq!(etf.total_supply, 0);
+    // The pending purchase is untouched and can still settle
+    assert_eq!(etf.pending_sol, 990_000_000);
+    settle_at_price(&mut harness, &setup, late, 1).unwrap();
+    assert_eq!(shares(&harness, &setup.etf.address, &late), 990_000_000);
+}
+
+#[test]
+fn test_close_blocked_by_pending_purchase() {
+    let mut harness = setup();
+    let setup = keeper_etf(&mut harness, 2);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    pending_buy(&mut harness, &setup.etf, investor, LAMPORTS_PER_SOL).unwrap();
+
+    let result = harness.process_instruction(
+        close_etf_ix(setup.etf.address, setup.etf.lister, setup.etf.registry_index, &lister_vaults(&setup.etf)),
+        &[setup.etf.lister],
+    );
+    assert_error(result, ErrorCode::PendingPurchasesOutstanding);
+}
+
+fn refund(harness: &mut Harness, etf: &TestEtf, investor: Pubkey, purchase_id: u64) -> TxResult {
+    harness.process_instruction(refund_pending_purchase_ix(etf.address, investor, purchase_id), &[investor])
+}
+
+#[test]
+fn test_set_keeper_rejects_refund_timeout_out_of_range() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+
+    for timeout in [MIN_REFUND_TIMEOUT_SECONDS - 1, MAX_REFUND_TIMEOUT_SECONDS + 1, -1] {
+        let ix = set_keeper_ix(etf.address, etf.lister, Some(Pubkey::new_unique()), timeout);
+        assert_error(harness.process_instruction(ix, &[etf.lister]), ErrorCode::InvalidRefundTimeout);
+    }
+}
+
+#[test]
+fn test_refund_timeout_fixed_at_purchase() {
+    let mut harness = setup();
+    let setup = keeper_etf(&mut harness, 2);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    pending_buy(&mut harness, &setup.etf, investor, LAMPORTS_PER_SOL).unwrap();
+    let created_ts = harness.clock().unix_timestamp;
+
+    // Lengthening the timeout only affects later purchases
+    let ix = set_keeper_ix(setup.etf.address, setup.etf.lister, Some(setup.keeper), MAX_REFUND_TIMEOUT_SECONDS);
+    harness.process_instruction(ix, &[setup.etf.lister]).unwrap();
+    pending_buy(&mut harness, &setup.etf, investor, LAMPORTS_PER_SOL).unwrap();
+
+    let first: PendingPurchase = harness.anchor_account(&pending_purchase_address(&setup.etf.address, 0)).unwrap();
+    let second: PendingPurchase = harness.anchor_account(&pending_purchase_address(&setup.etf.address, 1)).unwrap();
+    assert_eq!(first.refundable_ts, created_ts + DEFAULT_REFUND_TIMEOUT_SECONDS);
+    assert_eq!(second.refundable_ts, created_ts + MAX_REFUND_TIMEOUT_SECONDS);
+}
+
+#[test]
+fn test_refund_before_timeout_rejected() {
+    let mut harness = setup();
+    let setup = keeper_etf(&mut harness, 2);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    pending_buy(&mut harness, &setup.etf, investor, LAMPORTS_PER_SOL).unwrap();
+
+    harness.advance_time(DEFAULT_REFUND_TIMEOUT_SECONDS - 1);
+    assert_error(refund(&mut harness, &setup.etf, investor, 0), ErrorCode::RefundNotYetAvailable);
+}
+
+#[test]
+fn test_refund_returns_unswapped_sol() {
+    let mut harness = setup();
+    let setup = keeper_etf(&mut harness, 2);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    pending_buy(&mut harness, &setup.etf, investor, LAMPORTS_PER_SOL).unwrap();
+    let pending_address = pending_purchase_address(&setup.etf.address, 0);
+    let pending_rent = harness.lamports(&pending_address);
+    let investor_before = harness.lamports(&investor);
+    let etf_before = harness.lamports(&setup.etf.address);
+    let total_before = harness.total_lamports();
+
+    harness.advance_time(DEFAULT_REFUND_TIMEOUT_SECONDS);
+    let outcome = refund(&mut harness, &setup.etf, investor, 0).unwrap();
+
+    // Fees stay paid; the SOL held for the swaps and the account rent come back
+    assert_eq!(harness.lamports(&investor), investor_before + 990_000_000 + pending_rent);
+    assert_eq!(harness.lamports(&setup.etf.address), etf_before - 990_000_000);
+    assert!(harness.account(&pending_address).is_none());
+    assert_eq!(harness.total_lamports(), total_before);
+
+    let etf = etf_state(&harness, &setup.etf.address);
+    assert_eq!(etf.pending_sol, 0);
+    assert_eq!(etf.total_supply, 0);
+    assert_eq!(shares(&harness, &setup.etf.address, &investor), 0);
+
+    let events = outcome.events::<PurchaseRefundedEvent>();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].investor, investor);
+    assert_eq!(events[0].purchase_id, 0);
+    assert_eq!(events[0].sol_amount, 990_000_000);
+
+    // Nothing left for the keeper to settle
+    assert!(settle(&mut harness, &setup, investor, 0, vec![4_950, 4_950]).is_err());
+}
+
+#[test]
+fn test_refund_only_by_investor() {
+    let mut harness = setup();
+    let setup = keeper_etf(&mut harness, 2);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    let other = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+    pending_buy(&mut harness, &setup.etf, investor, LAMPORTS_PER_SOL).unwrap();
+    harness.advance_time(DEFAULT_REFUND_TIMEOUT_SECONDS);
+
+    assert!(refund(&mut harness, &setup.etf, other, 0).is_err());
+    assert_eq!(etf_state(&harness, &setup.etf.address).pending_sol, 990_000_000);
+}
+
+#[test]
+fn test_keeper_may_settle_after_timeout_until_refunded() {
+    let mut harness = setup();
+    let setup = keeper_etf(&mut harness, 2);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    pending_buy(&mut harness, &setup.etf, investor, LAMPORTS_PER_SOL).unwrap();
+    harness.advance_time(DEFAULT_REFUND_TIMEOUT_SECONDS);
+    for mint in &setup.etf.mints {
+        set_price(&mut harness, mint, TOKEN_PRICE);
+    }
+
+    settle_at_price(&mut harness, &setup, investor, 0).unwrap();
+    assert!(refund(&mut harness, &setup.etf, investor, 0).is_err());
+    assert_eq!(shares(&harness, &setup.etf.address, &investor), 990_000_000);
+}
+
+#[test]
+fn test_refund_immediately_once_liquidating() {
+    let mut harness = setup();
+    let setup = keeper_etf(&mut harness, 2);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    pending_buy(&mut harness, &setup.etf, investor, LAMPORTS_PER_SOL).unwrap();
+    harness
+        .process_instruction(begin_liquidation_ix(setup.etf.address, setup.etf.lister), &[setup.etf.lister])
+        .unwrap();
+
+    assert_error(
+        settle(&mut harness, &setup, investor, 0, vec![4_950, 4_950]),
+        ErrorCode::EtfLiquidating,
+    );
+    refund(&mut harness, &setup.etf, investor, 0).unwrap();
+    assert_eq!(etf_state(&harness, &setup.etf.address).pending_sol, 0);
+}
@@ -0,0 +1,305 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_spl::token::spl_token;
+use anchor_spl::token_2022::spl_token_2022;
+use anchor_spl::token_2022::spl_token_2022::extension::metadata_pointer::MetadataPointer;
+use anchor_spl::token_2022::spl_token_2022::extension::{BaseStateWithExtensions, StateWithExtensions};
+use anchor_spl::token_interface::spl_token_metadata_interface::state::TokenMetadata;
+use common::*;
+use mtf_etf::{ErrorCode, SharesUnwrappedEvent, SharesWrappedEvent, SHARE_DECIMALS};
+use program_harness::Harness;
+
+// ETF with a share mint and one investor holding `sol_amount` worth of shares
+fn wrapped_etf(harness: &mut Harness, sol_amount: u64) -> (TestEtf, Pubkey) {
+    let etf = create_etf(harness, 2);
+    let investor = funded_wallet(harness, 10 * LAMPORTS_PER_SOL);
+    buy(harness, &etf, investor, sol_amount).unwrap();
+    harness
+        .process_instruction(create_share_mint_ix(etf.address, investor), &[investor])
+        .unwrap();
+    (etf, investor)
+}
+
+fn share_account(harness: &mut Harness, etf: &TestEtf, owner: &Pubkey) -> Pubkey {
+    let account = Pubkey::new_unique();
+    harness.create_token_account(account, &share_mint_address(&etf.address), owner, 0);
+    account
+}
+
+fn share_supply(harness: &Harness, etf: &TestEtf) -> u64 {
+    let mint = harness.account(&share_mint_address(&etf.address)).unwrap();
+    StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint.data).unwrap().base.supply
+}
+
+fn share_metadata(harness: &Harness, etf: &TestEtf) -> TokenMetadata {
+    let mint = harness.account(&share_mint_address(&etf.address)).unwrap();
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint.data).unwrap();
+    mint.get_variable_len_extension::<TokenMetadata>().unwrap()
+}
+
+// Point an instruction built for Token-2022 at the SPL Token program
+fn with_spl_token(mut ix: Instruction) -> Instruction {
+    for meta in &mut ix.accounts {
+        if meta.pubkey == spl_token_2022::ID {
+            meta.pubkey = spl_token::ID;
+        }
+    }
+    ix
+}
+
+#[test]
+fn test_create_share_mint() {
+    let mut harness = setup();
+    let (etf, investor) = wrapped_etf(&mut harness, LAMPORTS_PER_SOL);
+
+    let account = harness.account(&share_mint_address(&etf.address)).unwrap();
+    assert_eq!(account.owner, spl_token_2022::ID);
+    assert!(account.lamports >= harness.rent().minimum_balance(account.data.len()));
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&account.data).unwrap();
+    assert_eq!(mint.base.mint_authority, Some(etf.address).into());
+    assert_eq!(mint.base.decimals, SHARE_DECIMALS);
+    assert_eq!(mint.base.supply, 0);
+
+    // The mint carries the ETF's metadata itself, under the ETF's authority
+    let pointer = mint.get_extension::<MetadataPointer>().unwrap();
+    assert_eq!(Option::<Pubkey>::from(pointer.authority), Some(etf.address));
+    assert_eq!(Option::<Pubkey>::from(pointer.metadata_address), Some(share_mint_address(&etf.address)));
+    let metadata = share_metadata(&harness, &etf);
+    let expected = Metadata::default();
+    assert_eq!(Option::<Pubkey>::from(metadata.update_authority), Some(etf.address));
+    assert_eq!(metadata.mint, share_mint_address(&etf.address));
+    assert_eq!(
+        (metadata.name, metadata.symbol, metadata.uri),
+        (expected.name, expected.symbol, expected.uri)
+    );
+
+    // Only one mint per ETF
+    harness.advance_time(1);
+    assert!(harness
+        .process_instruction(create_share_mint_ix(etf.address, investor), &[investor])
+        .is_err());
+}
+
+#[test]
+fn test_wrap_moves_shares_into_tokens() {
+    let mut harness = setup();
+    let (etf, investor) = wrapped_etf(&mut harness, LAMPORTS_PER_SOL);
+    let destination = share_account(&mut harness, &etf, &investor);
+    let supply_before = etf_state(&harness, &etf.address).total_supply;
+
+    let outcome = harness
+        .process_instruction(wrap_shares_ix(etf.address, investor, destination, 400_000_000), &[investor])
+        .unwrap();
+
+    assert_eq!(shares(&harness, &etf.address, &investor), 590_000_000);
+    assert_eq!(harness.token_balance(&destination), Some(400_000_000));
+    assert_eq!(share_supply(&harness, &etf), 400_000_000);
+    assert_eq!(etf_state(&harness, &etf.address).total_supply, supply_before);
+
+    let events = outcome.events::<SharesWrappedEvent>();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].owner, investor);
+    assert_eq!(events[0].destination, destination);
+    assert_eq!(events[0].amount, 400_000_000);
+}
+
+#[test]
+fn test_wrap_more_than_position() {
+    let mut harness = setup();
+    let (etf, investor) = wrapped_etf(&mut harness, LAMPORTS_PER_SOL);
+    let destination = share_account(&mut harness, &etf, &investor);
+
+    assert_error(
+        harness.process_instruction(wrap_shares_ix(etf.address, investor, destination, 990_000_001), &[investor]),
+        ErrorCode::InsufficientShares,
+    );
+    assert_error(
+        harness.process_instruction(wrap_shares_ix(etf.address, investor, destination, 0), &[investor]),
+        ErrorCode::InvalidAmount,
+    );
+}
+
+#[test]
+fn test_wrap_rejects_foreign_token_account() {
+    let mut harness = setup();
+    let (etf, investor) = wrapped_etf(&mut harness, LAMPORTS_PER_SOL);
+    let destination = Pubkey::new_unique();
+    harness.create_token_account(destination, &etf.mints[0], &investor, 0);
+
+    assert!(harness
+        .process_instruction(wrap_shares_ix(etf.address, investor, destination, 1_000), &[investor])
+        .is_err());
+    assert_eq!(shares(&harness, &etf.address, &investor), 990_000_000);
+}
+
+#[test]
+fn test_unwrap_credits_recipient_position() {
+    let mut harness = setup();
+    let (etf, investor) = wrapped_etf(&mut harness, LAMPORTS_PER_SOL);
+    let holder = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+    let investor_tokens = share_account(&mut harness, &etf, &investor);
+    harness
+        .process_instruction(wrap_shares_ix(etf.address, investor, investor_tokens, 500_000_000), &[investor])
+        .unwrap();
+
+    // Tokens move freely; the holder unwraps into their own position and sells
+    let holder_tokens = share_account(&mut harness, &etf, &holder);
+    harness
+        .process_instruction(
+            spl_token_2022::instruction::transfer_checked(
+                &spl_token_2022::ID,
+                &investor_tokens,
+                &share_mint_address(&etf.address),
+                &holder_tokens,
+                &investor,
+                &[],
+                200_000_000,
+                SHARE_DECIMALS,
+            )
+            .unwrap(),
+            &[investor],
+        )
+        .unwrap();
+
+    let outcome = harness
+        .process_instruction(
+            unwrap_shares_ix(etf.address, holder, holder_tokens, holder, 200_000_000),
+            &[holder],
+        )
+        .unwrap();
+    assert_eq!(harness.token_balance(&holder_tokens), Some(0));
+    assert_eq!(shares(&harness, &etf.address, &holder), 200_000_000);
+    assert_eq!(share_supply(&harness, &etf), 300_000_000);
+
+    let events = outcome.events::<SharesUnwrappedEvent>();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].owner, holder);
+    assert_eq!(events[0].recipient, holder);
+
+    sell(&mut harness, &etf, holder, 200_000_000).unwrap();
+    assert_eq!(shares(&harness, &etf.address, &holder), 0);
+}
+
+#[test]
+fn test_unwrap_to_another_recipient() {
+    let mut harness = setup();
+    let (etf, investor) = wrapped_etf(&mut harness, LAMPORTS_PER_SOL);
+    let tokens = share_account(&mut harness, &etf, &investor);
+    harness
+        .process_instruction(wrap_shares_ix(etf.address, investor, tokens, 500_000_000), &[investor])
+        .unwrap();
+
+    // The recipient does not sign; the owner pays for its position
+    let recipient = Pubkey::new_unique();
+    harness
+        .process_instruction(unwrap_shares_ix(etf.address, investor, tokens, recipient, 100_000_000), &[investor])
+        .unwrap();
+    assert_eq!(shares(&harness, &etf.address, &recipient), 100_000_000);
+    assert_eq!(harness.token_balance(&tokens), Some(400_000_000));
+
+    let position: mtf_etf::Position = harness
+        .anchor_account(&position_address(&etf.address, &recipient))
+        .unwrap();
+    assert_eq!(position.etf, etf.address);
+    assert_eq!(position.investor, recipient);
+}
+
+#[test]
+fn test_unwrap_requires_token_authority() {
+    let mut harness = setup();
+    let (etf, investor) = wrapped_etf(&mut harness, LAMPORTS_PER_SOL);
+    let tokens = share_account(&mut harness, &etf, &investor);
+    harness
+        .process_instruction(wrap_shares_ix(etf.address, investor, tokens, 500_000_000), &[investor])
+        .unwrap();
+
+    let thief = funded_wallet(&mut harness, LAMPORTS_PER_SOL);
+    assert!(harness
+        .process_instruction(unwrap_shares_ix(etf.address, thief, tokens, thief, 500_000_000), &[thief])
+        .is_err());
+    assert_eq!(harness.token_balance(&tokens), Some(500_000_000));
+}
+
+#[test]
+fn test_liquidation_blocks_wrap_but_not_unwrap() {
+    let mut harness = setup();
+    let (etf, investor) = wrapped_etf(&mut harness, LAMPORTS_PER_SOL);
+    let tokens = share_account(&mut harness, &etf, &investor);
+    harness
+        .process_instruction(wrap_shares_ix(etf.address, investor, tokens, 500_000_000), &[investor])
+        .unwrap();
+    harness
+        .process_instruction(begin_liquidation_ix(etf.address, etf.lister), &[etf.lister])
+        .unwrap();
+
+    assert_error(
+        harness.process_instruction(wrap_shares_ix(etf.address, investor, tokens, 1), &[investor]),
+        ErrorCode::EtfLiquidating,
+    );
+
+    // Wrapped shares come back into the position and are claimed with it
+    harness
+        .process_instruction(unwrap_shares_ix(etf.address, investor, tokens, investor, 500_000_000), &[investor])
+        .unwrap();
+    assert_eq!(shares(&harness, &etf.address, &investor), 990_000_000);
+    harness
+        .process_instruction(claim_liquidation_ix(etf.address, investor, &holder_vaults(&etf, &investor)), &[investor])
+        .unwrap();
+    assert_eq!(etf_state(&harness, &etf.address).total_supply, 0);
+}
+
+#[test]
+fn test_update_metadata_renames_share_mint() {
+    let mut harness = setup();
+    let (etf, _) = wrapped_etf(&mut harness, LAMPORTS_PER_SOL);
+    let (name, symbol, uri) = ("Dog and Cat Coins", "PETS", "https://example.com/a-much-longer-path/pets.json");
+    let metadata = Metadata {
+        name: name.to_string(),
+        symbol: symbol.to_string(),
+        uri: uri.to_string(),
+        ..Metadata::default()
+    };
+
+    // The lister funds the rent of the longer metadata
+    let lister_before = harness.lamports(&etf.lister);
+    harness
+        .process_instruction(update_metadata_ix(etf.address, etf.lister, metadata), &[etf.lister])
+        .unwrap();
+
+    let updated = share_metadata(&harness, &etf);
+    assert_eq!((updated.name.as_str(), updated.symbol.as_str(), updated.uri.as_str()), (name, symbol, uri));
+    let mint = harness.account(&share_mint_address(&etf.address)).unwrap();
+    assert!(mint.lamports >= harness.rent().minimum_balance(mint.data.len()));
+    assert!(harness.lamports(&etf.lister) < lister_before);
+}
+
+#[test]
+fn test_classic_share_mint_still_wraps() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+
+    // A share mint from before share mints carried metadata
+    harness.create_mint(share_mint_address(&etf.address), &etf.address, SHARE_DECIMALS);
+    let tokens = share_account(&mut harness, &etf, &investor);
+    harness
+        .process_instruction(with_spl_token(wrap_shares_ix(etf.address, investor, tokens, 1_000)), &[investor])
+        .unwrap();
+    assert_eq!(harness.token_balance(&tokens), Some(1_000));
+    harness
+        .process_instruction(
+            with_spl_token(unwrap_shares_ix(etf.address, investor, tokens, investor, 1_000)),
+            &[investor],
+        )
+        .unwrap();
+    assert_eq!(harness.token_balance(&tokens), Some(0));
+
+    // Renaming the ETF leaves a mint without metadata alone
+    harness
+        .process_instruction(update_metadata_ix(etf.address, etf.lister, Metadata::default()), &[etf.lister])
+        .unwrap();
+    assert_eq!(harness.account(&share_mint_address(&etf.address)).unwrap().owner, spl_token::ID);
+}
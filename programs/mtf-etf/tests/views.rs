@@ -0,0 +1,186 @@
+mod common;
+
+use anchor_lang::prelude::{AccountInfo, Pubkey};
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::{get_return_data, invoke, set_return_data};
+use anchor_lang::{AnchorDeserialize, InstructionData};
+use common::*;
+use mtf_etf::{BuyQuote, ErrorCode, EtfStatus, NavQuote, SellQuote, DEFAULT_REFUND_TIMEOUT_SECONDS, ID, NAV_SCALE};
+use program_harness::Harness;
+
+// Run a view and decode its return data; views need no signers
+fn view<T: AnchorDeserialize>(harness: &mut Harness, ix: Instruction) -> Result<T, u32> {
+    let program_id = ix.program_id;
+    let outcome = harness
+        .process_instruction(ix, &[])
+        .map_err(|error| error.custom_code().unwrap_or(u32::MAX))?;
+    let (returned_by, data) = outcome.return_data.expect("return data");
+    assert_eq!(returned_by, program_id);
+    Ok(T::try_from_slice(&data).unwrap())
+}
+
+#[test]
+fn test_quote_buy_matches_buy() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 3);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+
+    let quote: BuyQuote = view(&mut harness, quote_buy_ix(etf.address, LAMPORTS_PER_SOL, vec![50, 30, 20])).unwrap();
+    assert_eq!(quote.creator_fee, 5_000_000);
+    assert_eq!(quote.dev_fee, 5_000_000);
+    assert_eq!(quote.sol_after_fees, 990_000_000);
+    assert_eq!(quote.allocations, vec![495_000_000, 297_000_000, 198_000_000]);
+    assert!(!quote.pending);
+    assert_eq!(quote.nav_per_share, NAV_SCALE);
+
+    let lister_before = harness.lamports(&etf.lister);
+    harness
+        .process_instruction(
+            buy_etf_ix(etf.address, etf.lister, investor, mtf_etf::TREASURY, LAMPORTS_PER_SOL, vec![50, 30, 20]),
+            &[investor],
+        )
+        .unwrap();
+    assert_eq!(shares(&harness, &etf.address, &investor), quote.shares_out);
+    assert_eq!(harness.lamports(&etf.lister) - lister_before, quote.creator_fee);
+}
+
+#[test]
+fn test_quote_buy_flags_keeper_settlement() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    harness
+        .process_instruction(
+            set_keeper_ix(etf.address, etf.lister, Some(Pubkey::new_unique()), DEFAULT_REFUND_TIMEOUT_SECONDS),
+            &[etf.lister],
+        )
+        .unwrap();
+
+    let quote: BuyQuote = view(&mut harness, quote_buy_ix(etf.address, LAMPORTS_PER_SOL, vec![100])).unwrap();
+    assert!(quote.pending);
+}
+
+#[test]
+fn test_quote_buy_validates_like_buy() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 2);
+
+    let result = view::<BuyQuote>(&mut harness, quote_buy_ix(etf.address, LAMPORTS_PER_SOL, vec![50, 40]));
+    assert_eq!(result.unwrap_err(), u32::from(ErrorCode::InvalidTokenPercentages));
+    let result = view::<BuyQuote>(&mut harness, quote_buy_ix(etf.address, 0, vec![50, 50]));
+    assert_eq!(result.unwrap_err(), u32::from(ErrorCode::InvalidAmount));
+}
+
+#[test]
+fn test_quote_sell_matches_sell() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+
+    let quote: SellQuote = view(&mut harness, quote_sell_ix(etf.address, 500_000_000)).unwrap();
+    assert_eq!(quote.sol_value, 500_000_000);
+    assert_eq!(quote.creator_fee, 2_500_000);
+    assert_eq!(quote.sol_out, 495_000_000);
+    assert_eq!(quote.liquidity, 990_000_000);
+
+    let before = harness.lamports(&investor);
+    sell(&mut harness, &etf, investor, 500_000_000).unwrap();
+    assert_eq!(harness.lamports(&investor) - before, quote.sol_out);
+
+    let result = view::<SellQuote>(&mut harness, quote_sell_ix(etf.address, 490_000_001));
+    assert_eq!(result.unwrap_err(), u32::from(ErrorCode::InsufficientFunds));
+}
+
+#[test]
+fn test_get_nav_ignores_donations() {
+    let mut harness = setup();
+    let etf = create_etf(&mut harness, 1);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+
+    let nav: NavQuote = view(&mut harness, get_nav_ix(etf.address)).unwrap();
+    assert_eq!(nav.total_supply, 990_000_000);
+    assert_eq!(nav.net_assets, 990_000_000);
+    assert_eq!(nav.nav_per_share, NAV_SCALE);
+    assert_eq!(nav.status, EtfStatus::Active);
+
+    harness.airdrop(&etf.address, 99_000_000);
+    harness
+        .process_instruction(begin_liquidation_ix(etf.address, etf.lister), &[etf.lister])
+        .unwrap();
+    let nav: NavQuote = view(&mut harness, get_nav_ix(etf.address)).unwrap();
+    assert_eq!(nav.net_assets, 990_000_000);
+    assert_eq!(nav.nav_per_share, NAV_SCALE);
+    assert_eq!(nav.status, EtfStatus::Liquidating);
+}
+
+#[test]
+fn test_get_nav_values_holdings_at_feed_prices() {
+    let mut harness = setup();
+    let setup = keeper_etf(&mut harness, 2);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    pending_buy(&mut harness, &setup.etf, investor, LAMPORTS_PER_SOL).unwrap();
+    settle_at_price(&mut harness, &setup, investor, 0).unwrap();
+    let basket = setup.etf.mints.clone();
+
+    let nav: NavQuote = view(&mut harness, with_price_feeds(get_nav_ix(setup.etf.address), &basket)).unwrap();
+    assert_eq!(nav.net_assets, 990_000_000);
+    assert_eq!(nav.nav_per_share, NAV_SCALE);
+
+    // Holdings are booked, so tokens dropped into a vault change nothing
+    let held = harness.token_balance(&setup.vaults()[0]).unwrap();
+    harness.create_token_account(setup.vaults()[0], &basket[0], &setup.etf.address, held + 1_000_000);
+    let nav: NavQuote = view(&mut harness, with_price_feeds(get_nav_ix(setup.etf.address), &basket)).unwrap();
+    assert_eq!(nav.net_assets, 990_000_000);
+
+    set_price(&mut harness, &basket[0], 2 * TOKEN_PRICE);
+    let nav: NavQuote = view(&mut harness, with_price_feeds(get_nav_ix(setup.etf.address), &basket)).unwrap();
+    assert_eq!(nav.net_assets, 1_485_000_000);
+    assert_eq!(nav.nav_per_share, 1_500_000_000);
+
+    // Without its feeds a holding cannot be valued
+    assert_eq!(
+        view::<NavQuote>(&mut harness, get_nav_ix(setup.etf.address)),
+        Err(ErrorCode::MissingPriceFeed.into())
+    );
+}
+
+// Stand-in for a program that reads mtf_etf's NAV by CPI and passes it on
+const CALLER_ID: Pubkey = anchor_lang::solana_program::pubkey!("NavCa11er1111111111111111111111111111111111");
+
+fn nav_caller(_program_id: &Pubkey, accounts: &[AccountInfo], _data: &[u8]) -> ProgramResult {
+    invoke(
+        &Instruction {
+            program_id: ID,
+            accounts: vec![AccountMeta::new_readonly(*accounts[0].key, false)],
+            data: mtf_etf::instruction::GetNav {}.data(),
+        },
+        accounts,
+    )?;
+    let (program_id, data) = get_return_data().expect("get_nav return data");
+    assert_eq!(program_id, ID);
+    let nav = NavQuote::try_from_slice(&data).unwrap();
+    set_return_data(&nav.nav_per_share.to_le_bytes());
+    Ok(())
+}
+
+#[test]
+fn test_get_nav_by_cpi() {
+    let mut harness = setup();
+    harness.add_program(CALLER_ID, nav_caller);
+    let etf = create_etf(&mut harness, 1);
+    let investor = funded_wallet(&mut harness, 10 * LAMPORTS_PER_SOL);
+    buy(&mut harness, &etf, investor, LAMPORTS_PER_SOL).unwrap();
+    shift_net_assets(&mut harness, &etf.address, 495_000_000);
+
+    let ix = Instruction {
+        program_id: CALLER_ID,
+        accounts: vec![AccountMeta::new_readonly(etf.address, false), AccountMeta::new_readonly(ID, false)],
+        data: Vec::new(),
+    };
+    let outcome = harness.process_instruction(ix, &[]).unwrap();
+    let (returned_by, data) = outcome.return_data.unwrap();
+    assert_eq!(returned_by, CALLER_ID);
+    assert_eq!(u64::from_le_bytes(data.try_into().unwrap()), 1_500_000_000);
+}
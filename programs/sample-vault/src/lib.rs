@@ -0,0 +1,259 @@
+// Sample integration of the mtf_etf CPI interface.
+//
+// The vault pools deposits for one ETF in a single position held by its
+// `depositor` PDA. That PDA is system-owned and carries no data, so it can be
+// the investor of buy_etf / sell_etf when this program signs for it with
+// invoke_signed. Depositors get the pooled shares as share tokens of the
+// ETF's Token-2022 share mint via wrap_shares, and redeem by handing them back
+// through unwrap_shares.
+//
+// It is deliberately minimal - no vault state, no strategy - and is meant as
+// a starting point for lending and yield programs, not for deployment.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program;
+use anchor_spl::token_2022::Token2022;
+use mtf_etf::cpi::accounts::{BuyETF, SellETF, UnwrapShares, WrapShares};
+use mtf_etf::program::MtfEtf;
+use mtf_etf::{calculate_fees, EtfGate, ETF, POSITION_SPACE};
+
+declare_id!("sHijBcWh5howXUMBjCEUP67jpNP233wi8eNtJ3E1wQx");
+
+// Anchor 0.31 emits its IDL instructions, which still call the deprecated
+// AccountInfo::realloc, next to the #[program] module rather than inside it,
+// so the allowance wraps both. The body keeps crate-level indentation
+#[allow(deprecated)]
+mod program_module {
+use super::*;
+
+#[program]
+pub mod sample_vault {
+    use super::*;
+
+    // Buy `sol_amount` of the ETF on the user's behalf and mint them the
+    // resulting shares as share tokens
+    pub fn deposit(ctx: Context<Deposit>, sol_amount: u64, token_percentages: Vec<u8>) -> Result<()> {
+        // Keeper-settled buys mint shares later, so there would be nothing to wrap
+        require!(ctx.accounts.etf.keeper.is_none(), VaultError::KeeperSettled);
+        // The depositor holds no allowlist pass or gate tokens
+        require!(ctx.accounts.etf.gate == EtfGate::Open, VaultError::GatedEtf);
+
+        // Fund the depositor with the buy, plus its position's rent the first time
+        let mut funding = sol_amount;
+        if ctx.accounts.depositor_position.data_is_empty() {
+            funding += Rent::get()?.minimum_balance(POSITION_SPACE);
+        }
+        solana_program::program::invoke(
+            &solana_program::system_instruction::transfer(
+                ctx.accounts.user.key,
+                ctx.accounts.depositor.key,
+                funding,
+            ),
+            &[
+                ctx.accounts.user.to_account_info(),
+                ctx.accounts.depositor.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        let etf_key = ctx.accounts.etf.key();
+        let signer_seeds: &[&[&[u8]]] = &[&[b"depositor", etf_key.as_ref(), &[ctx.bumps.depositor]]];
+
+        mtf_etf::cpi::buy_etf(
+            CpiContext::new_with_signer(
+                ctx.accounts.mtf_etf_program.to_account_info(),
+                BuyETF {
+                    etf: ctx.accounts.etf.to_account_info(),
+                    position: ctx.accounts.depositor_position.to_account_info(),
+                    investor: ctx.accounts.depositor.to_account_info(),
+                    lister_account: ctx.accounts.lister_account.to_account_info(),
+                    dev_wallet: ctx.accounts.dev_wallet.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    pending_purchase: None,
+                    gate_account: None,
+                },
+                signer_seeds,
+            ),
+            sol_amount,
+            token_percentages,
+        )?;
+
+        // Instant buys mint 1:1 with the SOL left after fees
+        let (creator_fee, dev_fee) = calculate_fees(sol_amount);
+        let shares = sol_amount - creator_fee - dev_fee;
+
+        mtf_etf::cpi::wrap_shares(
+            CpiContext::new_with_signer(
+                ctx.accounts.mtf_etf_program.to_account_info(),
+                WrapShares {
+                    etf: ctx.accounts.etf.to_account_info(),
+                    position: ctx.accounts.depositor_position.to_account_info(),
+                    share_mint: ctx.accounts.share_mint.to_account_info(),
+                    destination: ctx.accounts.user_shares.to_account_info(),
+                    owner: ctx.accounts.depositor.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            shares,
+        )?;
+
+        emit!(VaultDepositEvent {
+            etf_address: etf_key,
+            user: ctx.accounts.user.key(),
+            sol_amount,
+            shares,
+        });
+
+        Ok(())
+    }
+
+    // Return `shares` share tokens to the pooled position, sell them and
+    // forward the proceeds to the user
+    pub fn withdraw(ctx: Context<Withdraw>, shares: u64) -> Result<()> {
+        mtf_etf::cpi::unwrap_shares(
+            CpiContext::new(
+                ctx.accounts.mtf_etf_program.to_account_info(),
+                UnwrapShares {
+                    etf: ctx.accounts.etf.to_account_info(),
+                    share_mint: ctx.accounts.share_mint.to_account_info(),
+                    source: ctx.accounts.user_shares.to_account_info(),
+                    owner: ctx.accounts.user.to_account_info(),
+                    recipient: ctx.accounts.depositor.to_account_info(),
+                    recipient_position: ctx.accounts.depositor_position.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                },
+            ),
+            shares,
+        )?;
+
+        let etf_key = ctx.accounts.etf.key();
+        let signer_seeds: &[&[&[u8]]] = &[&[b"depositor", etf_key.as_ref(), &[ctx.bumps.depositor]]];
+
+        let lamports_before = ctx.accounts.depositor.lamports();
+        mtf_etf::cpi::sell_etf(
+            CpiContext::new_with_signer(
+                ctx.accounts.mtf_etf_program.to_account_info(),
+                SellETF {
+                    etf: ctx.accounts.etf.to_account_info(),
+                    position: ctx.accounts.depositor_position.to_account_info(),
+                    investor: ctx.accounts.depositor.to_account_info(),
+                    lister_account: ctx.accounts.lister_account.to_account_info(),
+                    dev_wallet: ctx.accounts.dev_wallet.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            shares,
+        )?;
+        let sol_out = ctx.accounts.depositor.lamports() - lamports_before;
+
+        solana_program::program::invoke_signed(
+            &solana_program::system_instruction::transfer(
+                ctx.accounts.depositor.key,
+                ctx.accounts.user.key,
+                sol_out,
+            ),
+            &[
+                ctx.accounts.depositor.to_account_info(),
+                ctx.accounts.user.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+            signer_seeds,
+        )?;
+
+        emit!(VaultWithdrawEvent {
+            etf_address: etf_key,
+            user: ctx.accounts.user.key(),
+            shares,
+            sol_out,
+        });
+
+        Ok(())
+    }
+}
+}
+
+pub use program_module::*;
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(mut)]
+    pub etf: Account<'info, ETF>,
+    // Holds the vault's pooled position; never has data
+    #[account(mut, seeds = [b"depositor", etf.key().as_ref()], bump)]
+    pub depositor: SystemAccount<'info>,
+    /// CHECK: Depositor's position - created and validated by mtf_etf
+    #[account(mut)]
+    pub depositor_position: UncheckedAccount<'info>,
+    /// CHECK: ETF share mint - validated by mtf_etf
+    #[account(mut)]
+    pub share_mint: UncheckedAccount<'info>,
+    /// CHECK: Share token account credited with the deposit - validated by mtf_etf
+    #[account(mut)]
+    pub user_shares: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    /// CHECK: ETF lister - validated by mtf_etf
+    #[account(mut)]
+    pub lister_account: UncheckedAccount<'info>,
+    /// CHECK: Dev wallet - validated by mtf_etf
+    #[account(mut)]
+    pub dev_wallet: UncheckedAccount<'info>,
+    pub mtf_etf_program: Program<'info, MtfEtf>,
+    pub token_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(mut)]
+    pub etf: Account<'info, ETF>,
+    #[account(mut, seeds = [b"depositor", etf.key().as_ref()], bump)]
+    pub depositor: SystemAccount<'info>,
+    /// CHECK: Depositor's position - validated by mtf_etf
+    #[account(mut)]
+    pub depositor_position: UncheckedAccount<'info>,
+    /// CHECK: ETF share mint - validated by mtf_etf
+    #[account(mut)]
+    pub share_mint: UncheckedAccount<'info>,
+    /// CHECK: User's share token account - mtf_etf checks the user is its authority
+    #[account(mut)]
+    pub user_shares: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    /// CHECK: ETF lister - validated by mtf_etf
+    #[account(mut)]
+    pub lister_account: UncheckedAccount<'info>,
+    /// CHECK: Dev wallet - validated by mtf_etf
+    #[account(mut)]
+    pub dev_wallet: UncheckedAccount<'info>,
+    pub mtf_etf_program: Program<'info, MtfEtf>,
+    pub token_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event]
+pub struct VaultDepositEvent {
+    pub etf_address: Pubkey,
+    pub user: Pubkey,
+    pub sol_amount: u64,
+    pub shares: u64,
+}
+
+#[event]
+pub struct VaultWithdrawEvent {
+    pub etf_address: Pubkey,
+    pub user: Pubkey,
+    pub shares: u64,
+    pub sol_out: u64,
+}
+
+#[error_code]
+pub enum VaultError {
+    #[msg("ETF settles buys through a keeper; the vault needs instant buys")]
+    KeeperSettled,
+    #[msg("ETF is gated; the vault only deposits into open ETFs")]
+    GatedEtf,
+}
@@ -0,0 +1,229 @@
+// Deposit into and withdraw from an ETF through the sample vault, with
+// mtf_etf running under it in the program harness
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use mtf_etf::{EtfCategory, DEFAULT_REFUND_TIMEOUT_SECONDS, GOVERNANCE_AUTHORITY, TREASURY};
+use mtf_etf_client::instructions::{self, EtfMetadata};
+use mtf_etf_client::pda;
+use program_harness::Harness;
+use sample_vault::{VaultDepositEvent, VaultError};
+
+const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+
+struct Vault {
+    harness: Harness,
+    etf: Pubkey,
+    lister: Pubkey,
+}
+
+fn depositor(etf: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"depositor", etf.as_ref()], &sample_vault::ID).0
+}
+
+// A two-token ETF with its share mint created
+fn vault() -> Vault {
+    let mut harness = Harness::new();
+    harness.add_program(mtf_etf::ID, mtf_etf::entry);
+    harness.add_program(sample_vault::ID, sample_vault::entry);
+    harness.airdrop(&GOVERNANCE_AUTHORITY, LAMPORTS_PER_SOL);
+    harness
+        .process_instruction(
+            instructions::configure_treasury(GOVERNANCE_AUTHORITY, 0),
+            &[GOVERNANCE_AUTHORITY],
+        )
+        .unwrap();
+
+    let lister = Pubkey::new_unique();
+    harness.airdrop(&lister, 10 * LAMPORTS_PER_SOL);
+    let mints = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+    for mint in &mints {
+        harness.create_mint(*mint, &lister, 6);
+    }
+
+    let metadata = EtfMetadata {
+        name: "Dog Coins".to_string(),
+        symbol: "DOGS".to_string(),
+        uri: String::new(),
+        category: EtfCategory::Meme,
+    };
+    let etf = pda::etf_address(&lister, 0).0;
+    harness
+        .process_transaction(
+            &[
+                instructions::initialize_etf(lister, 0, 0, mints, metadata),
+                instructions::create_share_mint(etf, lister),
+            ],
+            &[lister],
+        )
+        .unwrap();
+
+    Vault { harness, etf, lister }
+}
+
+impl Vault {
+    fn user(&mut self) -> (Pubkey, Pubkey) {
+        let user = Pubkey::new_unique();
+        self.harness.airdrop(&user, 10 * LAMPORTS_PER_SOL);
+        let shares = Pubkey::new_unique();
+        self.harness
+            .create_token_account(shares, &pda::share_mint_address(&self.etf).0, &user, 0);
+        (user, shares)
+    }
+
+    fn deposit_ix(&self, user: Pubkey, user_shares: Pubkey, sol_amount: u64) -> Instruction {
+        let depositor = depositor(&self.etf);
+        Instruction {
+            program_id: sample_vault::ID,
+            accounts: sample_vault::accounts::Deposit {
+                etf: self.etf,
+                depositor,
+                depositor_position: pda::position_address(&self.etf, &depositor).0,
+                share_mint: pda::share_mint_address(&self.etf).0,
+                user_shares,
+                user,
+                lister_account: self.lister,
+                dev_wallet: TREASURY,
+                mtf_etf_program: mtf_etf::ID,
+                token_program: anchor_spl::token_2022::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: sample_vault::instruction::Deposit {
+                sol_amount,
+                token_percentages: vec![50, 50],
+            }
+            .data(),
+        }
+    }
+
+    fn withdraw_ix(&self, user: Pubkey, user_shares: Pubkey, shares: u64) -> Instruction {
+        let depositor = depositor(&self.etf);
+        Instruction {
+            program_id: sample_vault::ID,
+            accounts: sample_vault::accounts::Withdraw {
+                etf: self.etf,
+                depositor,
+                depositor_position: pda::position_address(&self.etf, &depositor).0,
+                share_mint: pda::share_mint_address(&self.etf).0,
+                user_shares,
+                user,
+                lister_account: self.lister,
+                dev_wallet: TREASURY,
+                mtf_etf_program: mtf_etf::ID,
+                token_program: anchor_spl::token_2022::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: sample_vault::instruction::Withdraw { shares }.data(),
+        }
+    }
+
+    fn pooled_shares(&self) -> u64 {
+        self.harness
+            .anchor_account::<mtf_etf::Position>(&pda::position_address(&self.etf, &depositor(&self.etf)).0)
+            .map(|position| position.shares)
+            .unwrap_or_default()
+    }
+}
+
+#[test]
+fn test_deposit_buys_and_wraps_for_user() {
+    let mut vault = vault();
+    let (user, user_shares) = vault.user();
+
+    let outcome = vault
+        .harness
+        .process_instruction(vault.deposit_ix(user, user_shares, LAMPORTS_PER_SOL), &[user])
+        .unwrap();
+
+    // The ETF sees the depositor PDA as its investor; the user holds SPL shares
+    assert_eq!(vault.harness.token_balance(&user_shares), Some(990_000_000));
+    assert_eq!(vault.pooled_shares(), 0);
+    let position: mtf_etf::Position = vault
+        .harness
+        .anchor_account(&pda::position_address(&vault.etf, &depositor(&vault.etf)).0)
+        .unwrap();
+    assert_eq!(position.investor, depositor(&vault.etf));
+    let etf: mtf_etf::ETF = vault.harness.anchor_account(&vault.etf).unwrap();
+    assert_eq!(etf.total_supply, 990_000_000);
+
+    // Every lamport the depositor was sent went into the buy and the position
+    assert_eq!(vault.harness.lamports(&depositor(&vault.etf)), 0);
+
+    let events = outcome.events::<VaultDepositEvent>();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].user, user);
+    assert_eq!(events[0].shares, 990_000_000);
+}
+
+#[test]
+fn test_withdraw_sells_and_forwards_sol() {
+    let mut vault = vault();
+    let (alice, alice_shares) = vault.user();
+    let (bob, bob_shares) = vault.user();
+    vault
+        .harness
+        .process_instruction(vault.deposit_ix(alice, alice_shares, LAMPORTS_PER_SOL), &[alice])
+        .unwrap();
+    vault
+        .harness
+        .process_instruction(vault.deposit_ix(bob, bob_shares, 2 * LAMPORTS_PER_SOL), &[bob])
+        .unwrap();
+    assert_eq!(vault.harness.token_balance(&bob_shares), Some(1_980_000_000));
+
+    let alice_before = vault.harness.lamports(&alice);
+    vault
+        .harness
+        .process_instruction(vault.withdraw_ix(alice, alice_shares, 990_000_000), &[alice])
+        .unwrap();
+
+    // Sell at par less 1% fees
+    assert_eq!(vault.harness.lamports(&alice) - alice_before, 980_100_000);
+    assert_eq!(vault.harness.token_balance(&alice_shares), Some(0));
+    assert_eq!(vault.pooled_shares(), 0);
+    assert_eq!(vault.harness.lamports(&depositor(&vault.etf)), 0);
+    let etf: mtf_etf::ETF = vault.harness.anchor_account(&vault.etf).unwrap();
+    assert_eq!(etf.total_supply, 1_980_000_000);
+}
+
+#[test]
+fn test_withdraw_needs_share_tokens() {
+    let mut vault = vault();
+    let (alice, alice_shares) = vault.user();
+    let (mallory, mallory_shares) = vault.user();
+    vault
+        .harness
+        .process_instruction(vault.deposit_ix(alice, alice_shares, LAMPORTS_PER_SOL), &[alice])
+        .unwrap();
+
+    // Neither an empty account nor someone else's tokens redeem the pool
+    assert!(vault
+        .harness
+        .process_instruction(vault.withdraw_ix(mallory, mallory_shares, 1_000), &[mallory])
+        .is_err());
+    assert!(vault
+        .harness
+        .process_instruction(vault.withdraw_ix(mallory, alice_shares, 1_000), &[mallory])
+        .is_err());
+    assert_eq!(vault.harness.token_balance(&alice_shares), Some(990_000_000));
+}
+
+#[test]
+fn test_deposit_rejects_keeper_settled_etf() {
+    let mut vault = vault();
+    vault
+        .harness
+        .process_instruction(
+            instructions::set_keeper(vault.etf, vault.lister, Some(Pubkey::new_unique()), DEFAULT_REFUND_TIMEOUT_SECONDS),
+            &[vault.lister],
+        )
+        .unwrap();
+    let (user, user_shares) = vault.user();
+
+    let error = vault
+        .harness
+        .process_instruction(vault.deposit_ix(user, user_shares, LAMPORTS_PER_SOL), &[user])
+        .unwrap_err();
+    assert_eq!(error.custom_code(), Some(VaultError::KeeperSettled.into()));
+}